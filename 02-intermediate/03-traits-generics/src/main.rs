@@ -23,6 +23,11 @@ fn print_pair<T: Display, U: Display>(first: T, second: U) {
 }
 
 /// Generic struct with a single type parameter
+///
+/// Deriving serde's traits on a generic type makes impls that are
+/// themselves generic: `Container<T>` is `Serialize` whenever `T` is, so
+/// the bound is placed on the impl, not on `Container`
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
 struct Container<T> {
     value: T,
 }
@@ -38,6 +43,7 @@ impl<T> Container<T> {
 }
 
 /// Generic struct with multiple type parameters
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
 struct Pair<T, U> {
     first: T,
     second: U,
@@ -231,6 +237,8 @@ impl Combiner<i32, ()> for String {
 trait Drawable {
     fn draw(&self);
     fn area(&self) -> f64;
+    /// Hands `self` to the visitor method for its concrete type
+    fn accept(&self, visitor: &mut dyn ShapeVisitor);
 }
 
 struct Circle {
@@ -245,6 +253,10 @@ impl Drawable for Circle {
     fn area(&self) -> f64 {
         std::f64::consts::PI * self.radius * self.radius
     }
+
+    fn accept(&self, visitor: &mut dyn ShapeVisitor) {
+        visitor.visit_circle(self);
+    }
 }
 
 struct Rectangle {
@@ -260,6 +272,10 @@ impl Drawable for Rectangle {
     fn area(&self) -> f64 {
         self.width * self.height
     }
+
+    fn accept(&self, visitor: &mut dyn ShapeVisitor) {
+        visitor.visit_rectangle(self);
+    }
 }
 
 /// Function that accepts trait objects
@@ -313,6 +329,10 @@ impl Drawable for Triangle {
     fn area(&self) -> f64 {
         0.5 * self.base * self.height
     }
+
+    fn accept(&self, visitor: &mut dyn ShapeVisitor) {
+        visitor.visit_triangle(self);
+    }
 }
 
 fn demonstrate_dispatch() {
@@ -531,21 +551,191 @@ fn load_data<T: Deserializable>(_data: T) {
 
 // NEWTYPE PATTERN
 /// Newtype pattern for type safety
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
 struct Meters(f64);
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
 struct Kilometers(f64);
 
 impl Meters {
-    fn to_kilometers(&self) -> Kilometers {
+    fn to_kilometers(self) -> Kilometers {
         Kilometers(self.0 / 1000.0)
     }
 }
 
 impl Kilometers {
-    fn to_meters(&self) -> Meters {
+    fn to_meters(self) -> Meters {
         Meters(self.0 * 1000.0)
     }
 }
 
+// Operator overloading: the std::ops traits give newtypes their own
+// arithmetic, restricted to the combinations that make sense. Meters plus
+// meters is meters, meters times a number is meters, but there's no impl
+// for meters times meters, so `Meters(1.0) * Meters(2.0)` doesn't compile.
+impl std::ops::Add for Meters {
+    type Output = Meters;
+
+    fn add(self, other: Meters) -> Meters {
+        Meters(self.0 + other.0)
+    }
+}
+
+/// Mixed units convert to the left-hand side's
+impl std::ops::Add<Kilometers> for Meters {
+    type Output = Meters;
+
+    fn add(self, other: Kilometers) -> Meters {
+        self + other.to_meters()
+    }
+}
+
+impl std::ops::Sub for Meters {
+    type Output = Meters;
+
+    fn sub(self, other: Meters) -> Meters {
+        Meters(self.0 - other.0)
+    }
+}
+
+impl std::ops::Mul<f64> for Meters {
+    type Output = Meters;
+
+    fn mul(self, factor: f64) -> Meters {
+        Meters(self.0 * factor)
+    }
+}
+
+/// So scaling reads either way round: `2.0 * Meters(5.0)`
+impl std::ops::Mul<Meters> for f64 {
+    type Output = Meters;
+
+    fn mul(self, meters: Meters) -> Meters {
+        meters * self
+    }
+}
+
+impl std::ops::Add for Kilometers {
+    type Output = Kilometers;
+
+    fn add(self, other: Kilometers) -> Kilometers {
+        Kilometers(self.0 + other.0)
+    }
+}
+
+impl std::ops::Sub for Kilometers {
+    type Output = Kilometers;
+
+    fn sub(self, other: Kilometers) -> Kilometers {
+        Kilometers(self.0 - other.0)
+    }
+}
+
+impl std::ops::Mul<f64> for Kilometers {
+    type Output = Kilometers;
+
+    fn mul(self, factor: f64) -> Kilometers {
+        Kilometers(self.0 * factor)
+    }
+}
+
+/// `PartialEq` and `PartialOrd` take the type to compare with as a
+/// parameter, so meters can be compared with kilometers directly
+impl PartialEq<Kilometers> for Meters {
+    fn eq(&self, other: &Kilometers) -> bool {
+        self.0 == other.to_meters().0
+    }
+}
+
+impl PartialOrd<Kilometers> for Meters {
+    fn partial_cmp(&self, other: &Kilometers) -> Option<std::cmp::Ordering> {
+        self.0.partial_cmp(&other.to_meters().0)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+struct Seconds(f64);
+
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+struct MetersPerSecond(f64);
+
+/// Dividing a distance by a time gives a speed: an operator whose output
+/// is a different type from either input
+impl std::ops::Div<Seconds> for Meters {
+    type Output = MetersPerSecond;
+
+    fn div(self, time: Seconds) -> MetersPerSecond {
+        MetersPerSecond(self.0 / time.0)
+    }
+}
+
+impl std::ops::Div<Seconds> for Kilometers {
+    type Output = MetersPerSecond;
+
+    fn div(self, time: Seconds) -> MetersPerSecond {
+        self.to_meters() / time
+    }
+}
+
+/// The same idea with one generic type instead of a struct per unit: the
+/// dimension is a phantom type parameter, so mixing lengths and times is a
+/// type error while every dimension shares the arithmetic below
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+struct Quantity<D> {
+    /// In SI units: meters, seconds, meters per second
+    value: f64,
+    _dimension: std::marker::PhantomData<D>,
+}
+
+/// Dimension markers for `Quantity`; never constructed
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+struct Length;
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+struct Time;
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+struct Speed;
+
+impl<D> Quantity<D> {
+    fn new(value: f64) -> Self {
+        Quantity {
+            value,
+            _dimension: std::marker::PhantomData,
+        }
+    }
+}
+
+/// Only quantities of the same dimension `D` can be added
+impl<D> std::ops::Add for Quantity<D> {
+    type Output = Quantity<D>;
+
+    fn add(self, other: Quantity<D>) -> Quantity<D> {
+        Quantity::new(self.value + other.value)
+    }
+}
+
+impl<D> std::ops::Sub for Quantity<D> {
+    type Output = Quantity<D>;
+
+    fn sub(self, other: Quantity<D>) -> Quantity<D> {
+        Quantity::new(self.value - other.value)
+    }
+}
+
+impl<D> std::ops::Mul<f64> for Quantity<D> {
+    type Output = Quantity<D>;
+
+    fn mul(self, factor: f64) -> Quantity<D> {
+        Quantity::new(self.value * factor)
+    }
+}
+
+impl std::ops::Div<Quantity<Time>> for Quantity<Length> {
+    type Output = Quantity<Speed>;
+
+    fn div(self, time: Quantity<Time>) -> Quantity<Speed> {
+        Quantity::new(self.value / time.value)
+    }
+}
+
 /// Newtype with trait implementation
 struct Username(String);
 
@@ -555,6 +745,117 @@ impl Display for Username {
     }
 }
 
+// VISITOR PATTERN
+/// An operation over shapes, with a method for each concrete shape
+///
+/// `Drawable::area` puts each operation in the shapes: adding a shape is
+/// one new impl, but adding an operation means touching every shape. An
+/// enum with a `match` is the opposite, and so is a visitor: new
+/// operations are a new visitor, while a new shape means a new method
+/// here. Unlike an enum, the set of shapes stays open to trait objects.
+///
+/// `shape.accept(visitor)` picks the method by the shape's type, then
+/// the call dispatches on the visitor's: double dispatch.
+trait ShapeVisitor {
+    fn visit_circle(&mut self, circle: &Circle);
+    fn visit_rectangle(&mut self, rectangle: &Rectangle);
+    fn visit_triangle(&mut self, triangle: &Triangle);
+}
+
+/// Adds up the area of every shape it visits
+#[derive(Default)]
+struct AreaSummer {
+    total: f64,
+}
+
+impl ShapeVisitor for AreaSummer {
+    fn visit_circle(&mut self, circle: &Circle) {
+        self.total += std::f64::consts::PI * circle.radius * circle.radius;
+    }
+
+    fn visit_rectangle(&mut self, rectangle: &Rectangle) {
+        self.total += rectangle.width * rectangle.height;
+    }
+
+    fn visit_triangle(&mut self, triangle: &Triangle) {
+        self.total += 0.5 * triangle.base * triangle.height;
+    }
+}
+
+/// Draws the shapes it visits side by side, bottoms aligned, as an SVG
+/// document
+#[derive(Default)]
+struct SvgExporter {
+    elements: Vec<String>,
+    /// Where the next shape's left edge goes
+    x: f64,
+    /// Height of the tallest shape so far
+    height: f64,
+}
+
+impl SvgExporter {
+    const GAP: f64 = 10.0;
+
+    /// Reserves a `width` by `height` slot after the previous shape, and
+    /// returns its left edge
+    fn place(&mut self, width: f64, height: f64) -> f64 {
+        let left = self.x;
+        self.x += width + Self::GAP;
+        self.height = self.height.max(height);
+        left
+    }
+
+    fn finish(self) -> String {
+        let width = (self.x - Self::GAP).max(0.0);
+        // Shapes are placed with y pointing up from their bottom edge;
+        // flipping the group puts them the right way up in SVG's y-down
+        // coordinates
+        format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\">\n\
+             <g transform=\"translate(0 {}) scale(1 -1)\">\n{}</g>\n</svg>\n",
+            width,
+            self.height,
+            self.height,
+            self.elements
+                .iter()
+                .map(|element| format!("  {}\n", element))
+                .collect::<String>()
+        )
+    }
+}
+
+impl ShapeVisitor for SvgExporter {
+    fn visit_circle(&mut self, circle: &Circle) {
+        let diameter = 2.0 * circle.radius;
+        let left = self.place(diameter, diameter);
+        self.elements.push(format!(
+            "<circle cx=\"{}\" cy=\"{}\" r=\"{}\"/>",
+            left + circle.radius,
+            circle.radius,
+            circle.radius
+        ));
+    }
+
+    fn visit_rectangle(&mut self, rectangle: &Rectangle) {
+        let left = self.place(rectangle.width, rectangle.height);
+        self.elements.push(format!(
+            "<rect x=\"{}\" y=\"0\" width=\"{}\" height=\"{}\"/>",
+            left, rectangle.width, rectangle.height
+        ));
+    }
+
+    fn visit_triangle(&mut self, triangle: &Triangle) {
+        let left = self.place(triangle.base, triangle.height);
+        self.elements.push(format!(
+            "<polygon points=\"{},0 {},0 {},{}\"/>",
+            left,
+            left + triangle.base,
+            left + triangle.base / 2.0,
+            triangle.height
+        ));
+    }
+}
+
 // TYPE STATE PATTERN
 /// Type state pattern for compile-time state verification
 struct Locked;
@@ -648,453 +949,3223 @@ struct Person {
     age: u32,
 }
 
-/// Phantom data for zero-cost abstractions
-struct Slice<'a, T> {
-    data: &'a [T],
-    _phantom: std::marker::PhantomData<T>,
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Method {
+    Get,
+    Post,
+    Put,
+    Delete,
 }
 
-impl<'a, T> Slice<'a, T> {
-    fn new(data: &'a [T]) -> Self {
-        Slice {
-            data,
-            _phantom: std::marker::PhantomData,
-        }
-    }
-
-    fn len(&self) -> usize {
-        self.data.len()
+impl Display for Method {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Method::Get => "GET",
+            Method::Post => "POST",
+            Method::Put => "PUT",
+            Method::Delete => "DELETE",
+        };
+        write!(f, "{}", name)
     }
 }
 
-// ============================================================================
-// COMBINING CONCEPTS - REAL WORLD EXAMPLES
-// ============================================================================
+struct NoUrl;
 
-/// Generic cache with trait bounds
-trait Cache<K, V> {
-    fn get(&self, key: &K) -> Option<&V>;
-    fn set(&mut self, key: K, value: V);
-    fn remove(&mut self, key: &K) -> Option<V>;
+struct WithUrl {
+    url: String,
 }
 
-struct SimpleCache<K, V>
-where
-    K: Eq + std::hash::Hash,
-{
-    data: std::collections::HashMap<K, V>,
+struct Ready {
+    url: String,
+    body: Option<String>,
 }
 
-impl<K, V> SimpleCache<K, V>
-where
-    K: Eq + std::hash::Hash,
-{
-    fn new() -> Self {
-        SimpleCache {
-            data: std::collections::HashMap::new(),
+/// HTTP request builder whose type tracks what's been set
+///
+/// The door and person builders show the mechanics; this is the shape it
+/// usually takes in real code. Each state carries the data set so far, so
+/// `send` never has to unwrap an `Option`, and a request without a URL or
+/// a body decision is a compile error rather than a runtime one:
+///
+/// ```text
+/// RequestBuilder::new(Method::Get).send();              // no `send` on NoUrl
+/// RequestBuilder::new(Method::Get).url("/a").send();    // no `send` on WithUrl
+/// ```
+struct RequestBuilder<State> {
+    method: Method,
+    headers: Vec<(String, String)>,
+    state: State,
+}
+
+impl RequestBuilder<NoUrl> {
+    fn new(method: Method) -> Self {
+        RequestBuilder {
+            method,
+            headers: Vec::new(),
+            state: NoUrl,
+        }
+    }
+
+    fn url(self, url: &str) -> RequestBuilder<WithUrl> {
+        RequestBuilder {
+            method: self.method,
+            headers: self.headers,
+            state: WithUrl {
+                url: url.to_string(),
+            },
         }
     }
 }
 
-impl<K, V> Cache<K, V> for SimpleCache<K, V>
-where
-    K: Eq + std::hash::Hash,
-{
-    fn get(&self, key: &K) -> Option<&V> {
-        self.data.get(key)
+/// Headers can be added in any state
+impl<State> RequestBuilder<State> {
+    fn header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
     }
+}
 
-    fn set(&mut self, key: K, value: V) {
-        self.data.insert(key, value);
+impl RequestBuilder<WithUrl> {
+    /// Also sets `Content-Length`
+    fn body(self, body: &str) -> RequestBuilder<Ready> {
+        self.header("Content-Length", &body.len().to_string())
+            .finish(Some(body.to_string()))
     }
 
-    fn remove(&mut self, key: &K) -> Option<V> {
-        self.data.remove(key)
+    /// Says outright that there's no body, as for most GETs
+    fn no_body(self) -> RequestBuilder<Ready> {
+        self.finish(None)
+    }
+
+    fn finish(self, body: Option<String>) -> RequestBuilder<Ready> {
+        RequestBuilder {
+            method: self.method,
+            headers: self.headers,
+            state: Ready {
+                url: self.state.url,
+                body,
+            },
+        }
     }
 }
 
-/// Repository pattern with generics and trait objects
-trait Repository<T> {
-    fn find_by_id(&self, id: u32) -> Option<T>;
-    fn save(&mut self, item: T) -> u32;
-    fn delete(&mut self, id: u32) -> bool;
+impl RequestBuilder<Ready> {
+    fn send(self) -> Request {
+        Request {
+            method: self.method,
+            url: self.state.url,
+            headers: self.headers,
+            body: self.state.body,
+        }
+    }
 }
 
-struct InMemoryRepository<T> {
-    items: std::collections::HashMap<u32, T>,
-    next_id: u32,
+#[derive(Debug, PartialEq)]
+struct Request {
+    method: Method,
+    url: String,
+    headers: Vec<(String, String)>,
+    body: Option<String>,
 }
 
-impl<T: Clone> InMemoryRepository<T> {
-    fn new() -> Self {
-        InMemoryRepository {
-            items: std::collections::HashMap::new(),
-            next_id: 1,
-        }
+impl Request {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(header, _)| header.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
     }
 }
 
-impl<T: Clone> Repository<T> for InMemoryRepository<T> {
-    fn find_by_id(&self, id: u32) -> Option<T> {
-        self.items.get(&id).cloned()
-    }
+/// Phantom data for zero-cost abstractions
+struct Slice<'a, T> {
+    data: &'a [T],
+    _phantom: std::marker::PhantomData<T>,
+}
 
-    fn save(&mut self, item: T) -> u32 {
-        let id = self.next_id;
-        self.next_id += 1;
-        self.items.insert(id, item);
-        id
+impl<'a, T> Slice<'a, T> {
+    fn new(data: &'a [T]) -> Self {
+        Slice {
+            data,
+            _phantom: std::marker::PhantomData,
+        }
     }
 
-    fn delete(&mut self, id: u32) -> bool {
-        self.items.remove(&id).is_some()
+    fn len(&self) -> usize {
+        self.data.len()
     }
 }
 
-/// Generic data processor with multiple trait bounds
-trait Processor {
-    type Input;
-    type Output;
-    type Error;
+// GENERIC ASSOCIATED TYPES
+/// Iterator whose items may borrow from the iterator itself
+///
+/// `Iterator2::Item` is a single type, fixed when the trait is
+/// implemented, so it can't name the lifetime of the `&mut self` passed to
+/// each `next` call. Making `Item` generic over a lifetime (a generic
+/// associated type, or GAT) lets every call hand out a borrow that lasts
+/// only until the following call. The price is that items can't be kept
+/// past that, so there's no `collect`; lending iterators are driven with
+/// `while let`.
+trait LendingIterator {
+    type Item<'a>
+    where
+        Self: 'a;
 
-    fn process(&self, input: Self::Input) -> Result<Self::Output, Self::Error>;
+    fn next(&mut self) -> Option<Self::Item<'_>>;
 }
 
-struct StringProcessor;
-
-impl Processor for StringProcessor {
-    type Input = String;
-    type Output = Vec<String>;
-    type Error = String;
+/// Overlapping mutable windows over a buffer: `[a, b]`, then `[b, c]`, ...
+///
+/// The standard library has `windows` but no `windows_mut`, because as an
+/// `Iterator` two windows alive at once would both be able to change the
+/// element they share. Lending each window only until the next call makes
+/// that impossible.
+struct WindowsMut<'s, T> {
+    buffer: &'s mut [T],
+    start: usize,
+    size: usize,
+}
 
-    fn process(&self, input: Self::Input) -> Result<Self::Output, Self::Error> {
-        if input.is_empty() {
-            Err("Empty input".to_string())
-        } else {
-            Ok(input.split_whitespace().map(String::from).collect())
+impl<'s, T> WindowsMut<'s, T> {
+    fn new(buffer: &'s mut [T], size: usize) -> Self {
+        assert!(size > 0, "Window size must be at least 1");
+        WindowsMut {
+            buffer,
+            start: 0,
+            size,
         }
     }
 }
 
-/// Trait for operations with default implementations
-trait Mathematic {
-    fn add(&self, other: &Self) -> Self;
-    fn subtract(&self, other: &Self) -> Self;
-
-    // Default implementation
-    fn multiply(&self, times: usize) -> Self
+impl<'s, T> LendingIterator for WindowsMut<'s, T> {
+    type Item<'a>
+        = &'a mut [T]
     where
-        Self: Sized + Clone,
-    {
-        let mut result = self.clone();
-        for _ in 1..times {
-            result = result.add(self);
-        }
-        result
+        Self: 'a;
+
+    fn next(&mut self) -> Option<Self::Item<'_>> {
+        let window = self.buffer.get_mut(self.start..self.start + self.size)?;
+        self.start += 1;
+        Some(window)
     }
 }
 
-#[derive(Clone)]
-struct Number(i32);
+/// Every `Iterator2` is also a lending iterator, one whose items don't
+/// borrow anything. A wrapper rather than a blanket impl, so `next` isn't
+/// ambiguous on types like `Counter`.
+struct Lend<I>(I);
 
-impl Mathematic for Number {
-    fn add(&self, other: &Self) -> Self {
-        Number(self.0 + other.0)
-    }
+impl<I: Iterator2> LendingIterator for Lend<I> {
+    type Item<'a>
+        = I::Item
+    where
+        Self: 'a;
 
-    fn subtract(&self, other: &Self) -> Self {
-        Number(self.0 - other.0)
+    fn next(&mut self) -> Option<Self::Item<'_>> {
+        self.0.next()
     }
+}
 
-    // Can override default implementation
-    fn multiply(&self, times: usize) -> Self {
-        Number(self.0 * times as i32)
+/// Works with any lending iterator, borrowed items or not
+fn count_items<I: LendingIterator>(mut iter: I) -> usize {
+    let mut count = 0;
+    while iter.next().is_some() {
+        count += 1;
     }
+    count
 }
 
 // ============================================================================
-// MAIN FUNCTION
+// 13. CONST GENERICS
 // ============================================================================
 
-fn main() {
-    println!("=== Traits and Generics Examples ===\n");
+/// Matrix whose dimensions are part of its type
+///
+/// `R` and `C` are const generic parameters: values rather than types,
+/// known at compile time. The data is a plain nested array, with no heap
+/// allocation, and multiplying matrices of the wrong shapes is a type
+/// error instead of a runtime check.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Matrix<const R: usize, const C: usize> {
+    rows: [[f64; C]; R],
+}
 
-    // 1. Generic Functions and Structs
-    println!("--- Generic Functions and Structs ---");
-    print_value(42);
-    print_value("Hello");
-    print_pair(10, "items");
+impl<const R: usize, const C: usize> Matrix<R, C> {
+    fn new(rows: [[f64; C]; R]) -> Self {
+        Matrix { rows }
+    }
 
-    let container = Container::new(100);
-    println!("Container value: {}", container.get());
+    fn zeros() -> Self {
+        Matrix {
+            rows: [[0.0; C]; R],
+        }
+    }
 
-    let pair = Pair::new("first", 2);
-    let (a, b) = pair.into_tuple();
-    println!("Tuple: ({}, {})", a, b);
+    fn get(&self, row: usize, col: usize) -> f64 {
+        self.rows[row][col]
+    }
 
-    // 2. Trait Bounds
+    /// `(rows, columns)`, read straight from the type
+    fn dimensions(&self) -> (usize, usize) {
+        (R, C)
+    }
+
+    /// An R×C matrix becomes C×R
+    fn transpose(&self) -> Matrix<C, R> {
+        let mut result = Matrix::<C, R>::zeros();
+        for (r, row) in self.rows.iter().enumerate() {
+            for (c, &value) in row.iter().enumerate() {
+                result.rows[c][r] = value;
+            }
+        }
+        result
+    }
+
+    /// R×C times C×K gives R×K. `other` must have as many rows as `self`
+    /// has columns, because both are the same parameter `C`.
+    fn multiply<const K: usize>(&self, other: &Matrix<C, K>) -> Matrix<R, K> {
+        let mut result = Matrix::<R, K>::zeros();
+        for r in 0..R {
+            for k in 0..K {
+                result.rows[r][k] = (0..C).map(|c| self.rows[r][c] * other.rows[c][k]).sum();
+            }
+        }
+        result
+    }
+}
+
+/// Only square matrices have an identity
+impl<const N: usize> Matrix<N, N> {
+    fn identity() -> Self {
+        let mut result = Self::zeros();
+        for i in 0..N {
+            result.rows[i][i] = 1.0;
+        }
+        result
+    }
+}
+
+impl<const R: usize, const C: usize> Display for Matrix<R, C> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for row in &self.rows {
+            let cells: Vec<String> = row.iter().map(|value| value.to_string()).collect();
+            writeln!(f, "[{}]", cells.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// Queue of at most `N` items that overwrites the oldest when full
+///
+/// The capacity is a const parameter, so the storage is an array inline in
+/// the struct and `RingBuffer<f64, 8>` and `RingBuffer<f64, 16>` are
+/// different types.
+struct RingBuffer<T, const N: usize> {
+    slots: [Option<T>; N],
+    /// Index of the oldest item
+    start: usize,
+    len: usize,
+}
+
+impl<T, const N: usize> RingBuffer<T, N> {
+    fn new() -> Self {
+        // Evaluated when the type is used, so `RingBuffer<T, 0>::new()`
+        // fails to compile
+        const { assert!(N > 0, "A ring buffer needs room for at least one item") };
+        RingBuffer {
+            slots: std::array::from_fn(|_| None),
+            start: 0,
+            len: 0,
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    fn is_full(&self) -> bool {
+        self.len == N
+    }
+
+    fn capacity(&self) -> usize {
+        N
+    }
+
+    /// Adds `item` as the newest, and returns the oldest if it had to make
+    /// room
+    fn push(&mut self, item: T) -> Option<T> {
+        let end = (self.start + self.len) % N;
+        let overwritten = self.slots[end].replace(item);
+        if self.is_full() {
+            self.start = (self.start + 1) % N;
+        } else {
+            self.len += 1;
+        }
+        overwritten
+    }
+
+    /// Removes the oldest item
+    fn pop(&mut self) -> Option<T> {
+        let item = self.slots[self.start].take()?;
+        self.start = (self.start + 1) % N;
+        self.len -= 1;
+        Some(item)
+    }
+
+    /// Oldest first
+    fn iter(&self) -> impl Iterator<Item = &T> {
+        (0..self.len).filter_map(move |i| self.slots[(self.start + i) % N].as_ref())
+    }
+}
+
+// ============================================================================
+// COMBINING CONCEPTS - REAL WORLD EXAMPLES
+// ============================================================================
+
+/// Generic cache with trait bounds
+trait Cache<K, V> {
+    fn get(&self, key: &K) -> Option<&V>;
+    fn set(&mut self, key: K, value: V);
+    fn remove(&mut self, key: &K) -> Option<V>;
+}
+
+/// Decides which entry a full cache gives up, and whether an entry has
+/// expired
+///
+/// This is the strategy pattern with generics: `SimpleCache` stores
+/// entries and counts hits, and leaves every decision about which key goes
+/// next to its policy type. Being a type parameter rather than a
+/// `Box<dyn EvictionPolicy<K>>`, the policy's calls are resolved at
+/// compile time.
+trait EvictionPolicy<K> {
+    /// `key` was added or its value replaced
+    fn inserted(&mut self, key: &K);
+
+    /// `key` was found by a lookup
+    fn accessed(&mut self, key: &K);
+
+    /// `key` left the cache for some other reason than being the victim
+    fn removed(&mut self, key: &K);
+
+    /// The key to evict to make room, which the policy then forgets
+    fn victim(&mut self) -> Option<K>;
+
+    /// Keys from the first to the last that would be evicted
+    fn eviction_order(&self) -> Vec<&K>;
+
+    /// Whether `key` should be treated as if it weren't there
+    fn is_expired(&self, key: &K) -> bool {
+        false
+    }
+}
+
+/// Evicts the least recently used key
+struct LruPolicy<K> {
+    /// Keys by when they were last used, oldest first
+    order: std::collections::BTreeMap<u64, K>,
+    last_used: std::collections::HashMap<K, u64>,
+    /// Counts every use, so later uses always sort after earlier ones
+    tick: u64,
+}
+
+impl<K: Eq + std::hash::Hash + Clone> LruPolicy<K> {
+    fn new() -> Self {
+        LruPolicy {
+            order: std::collections::BTreeMap::new(),
+            last_used: std::collections::HashMap::new(),
+            tick: 0,
+        }
+    }
+
+    fn touch(&mut self, key: &K) {
+        self.tick += 1;
+        if let Some(previous) = self.last_used.insert(key.clone(), self.tick) {
+            self.order.remove(&previous);
+        }
+        self.order.insert(self.tick, key.clone());
+    }
+}
+
+impl<K: Eq + std::hash::Hash + Clone> EvictionPolicy<K> for LruPolicy<K> {
+    fn inserted(&mut self, key: &K) {
+        self.touch(key);
+    }
+
+    fn accessed(&mut self, key: &K) {
+        self.touch(key);
+    }
+
+    fn removed(&mut self, key: &K) {
+        if let Some(tick) = self.last_used.remove(key) {
+            self.order.remove(&tick);
+        }
+    }
+
+    fn victim(&mut self) -> Option<K> {
+        let (_, key) = self.order.pop_first()?;
+        self.last_used.remove(&key);
+        Some(key)
+    }
+
+    fn eviction_order(&self) -> Vec<&K> {
+        self.order.values().collect()
+    }
+}
+
+/// Evicts the least frequently used key; ties go to the one used longest
+/// ago
+struct LfuPolicy<K> {
+    /// How often each key has been used, and when it was last used
+    uses: std::collections::HashMap<K, (u64, u64)>,
+    tick: u64,
+}
+
+impl<K: Eq + std::hash::Hash + Clone> LfuPolicy<K> {
+    fn new() -> Self {
+        LfuPolicy {
+            uses: std::collections::HashMap::new(),
+            tick: 0,
+        }
+    }
+
+    fn count_use(&mut self, key: &K) {
+        self.tick += 1;
+        let uses = self.uses.entry(key.clone()).or_insert((0, 0));
+        *uses = (uses.0 + 1, self.tick);
+    }
+
+    /// How often `key` has been used since it was added
+    fn use_count(&self, key: &K) -> u64 {
+        self.uses.get(key).map_or(0, |&(count, _)| count)
+    }
+}
+
+impl<K: Eq + std::hash::Hash + Clone> EvictionPolicy<K> for LfuPolicy<K> {
+    fn inserted(&mut self, key: &K) {
+        self.count_use(key);
+    }
+
+    fn accessed(&mut self, key: &K) {
+        self.count_use(key);
+    }
+
+    fn removed(&mut self, key: &K) {
+        self.uses.remove(key);
+    }
+
+    fn victim(&mut self) -> Option<K> {
+        let key = self
+            .uses
+            .iter()
+            .min_by_key(|(_, &uses)| uses)
+            .map(|(key, _)| key.clone())?;
+        self.uses.remove(&key);
+        Some(key)
+    }
+
+    fn eviction_order(&self) -> Vec<&K> {
+        let mut keys: Vec<_> = self.uses.iter().collect();
+        keys.sort_by_key(|(_, &uses)| uses);
+        keys.into_iter().map(|(key, _)| key).collect()
+    }
+}
+
+/// Where `TtlPolicy` gets the time from, so tests can supply their own
+trait Clock {
+    fn now(&self) -> std::time::Instant;
+}
+
+struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> std::time::Instant {
+        std::time::Instant::now()
+    }
+}
+
+/// Expires each key a fixed time after it was last written. When the cache
+/// is full and nothing has expired yet, the key closest to expiring goes.
+///
+/// Lookups don't extend an entry's life; only `set` does.
+struct TtlPolicy<K, C: Clock = SystemClock> {
+    ttl: std::time::Duration,
+    clock: C,
+    /// When each key expires, and a write counter to break ties between
+    /// keys written at the same instant
+    expires: std::collections::HashMap<K, (std::time::Instant, u64)>,
+    writes: u64,
+}
+
+impl<K: Eq + std::hash::Hash + Clone> TtlPolicy<K> {
+    fn new(ttl: std::time::Duration) -> Self {
+        Self::with_clock(ttl, SystemClock)
+    }
+}
+
+impl<K: Eq + std::hash::Hash + Clone, C: Clock> TtlPolicy<K, C> {
+    fn with_clock(ttl: std::time::Duration, clock: C) -> Self {
+        TtlPolicy {
+            ttl,
+            clock,
+            expires: std::collections::HashMap::new(),
+            writes: 0,
+        }
+    }
+}
+
+impl<K: Eq + std::hash::Hash + Clone, C: Clock> EvictionPolicy<K> for TtlPolicy<K, C> {
+    fn inserted(&mut self, key: &K) {
+        self.writes += 1;
+        let deadline = self.clock.now() + self.ttl;
+        self.expires.insert(key.clone(), (deadline, self.writes));
+    }
+
+    fn accessed(&mut self, key: &K) {}
+
+    fn removed(&mut self, key: &K) {
+        self.expires.remove(key);
+    }
+
+    fn victim(&mut self) -> Option<K> {
+        let key = self
+            .expires
+            .iter()
+            .min_by_key(|(_, &deadline)| deadline)
+            .map(|(key, _)| key.clone())?;
+        self.expires.remove(&key);
+        Some(key)
+    }
+
+    fn eviction_order(&self) -> Vec<&K> {
+        let mut keys: Vec<_> = self.expires.iter().collect();
+        keys.sort_by_key(|(_, &deadline)| deadline);
+        keys.into_iter().map(|(key, _)| key).collect()
+    }
+
+    fn is_expired(&self, key: &K) -> bool {
+        self.expires
+            .get(key)
+            .is_some_and(|&(deadline, _)| self.clock.now() >= deadline)
+    }
+}
+
+/// Cache with a capacity limit, which asks its eviction policy `P` what to
+/// give up when it's full. Without a policy it evicts the least recently
+/// used entry.
+///
+/// `Cache::get` takes `&self`, but a lookup still has to tell the policy
+/// and count a hit or miss. That bookkeeping lives in a `Cell` and a
+/// `RefCell` (interior mutability), which is also what makes the cache
+/// `!Sync`.
+struct SimpleCache<K, V, P = LruPolicy<K>>
+where
+    K: Eq + std::hash::Hash + Clone,
+    P: EvictionPolicy<K>,
+{
+    data: std::collections::HashMap<K, V>,
+    policy: std::cell::RefCell<P>,
+    capacity: usize,
+    stats: std::cell::Cell<CacheStats>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct CacheStats {
+    hits: u64,
+    misses: u64,
+    evictions: u64,
+    /// Expired entries that have been dropped
+    expirations: u64,
+}
+
+impl CacheStats {
+    /// Fraction of lookups that found their key, or 0 before any lookups
+    fn hit_rate(&self) -> f64 {
+        let lookups = self.hits + self.misses;
+        if lookups == 0 {
+            0.0
+        } else {
+            self.hits as f64 / lookups as f64
+        }
+    }
+}
+
+impl<K, V> SimpleCache<K, V>
+where
+    K: Eq + std::hash::Hash + Clone,
+{
+    /// A cache that never evicts
+    fn new() -> Self {
+        Self::with_capacity(usize::MAX)
+    }
+
+    /// An LRU cache that holds at most `capacity` entries
+    fn with_capacity(capacity: usize) -> Self {
+        Self::with_policy(capacity, LruPolicy::new())
+    }
+}
+
+impl<K, V, P> SimpleCache<K, V, P>
+where
+    K: Eq + std::hash::Hash + Clone,
+    P: EvictionPolicy<K>,
+{
+    /// A cache that holds at most `capacity` entries and evicts them as
+    /// `policy` says. The policy should be new, with no keys of its own.
+    fn with_policy(capacity: usize, policy: P) -> Self {
+        assert!(capacity > 0, "Cache capacity must be at least 1");
+        SimpleCache {
+            data: std::collections::HashMap::new(),
+            policy: std::cell::RefCell::new(policy),
+            capacity,
+            stats: std::cell::Cell::new(CacheStats::default()),
+        }
+    }
+
+    /// Counts expired entries too, until `purge_expired` or a full cache
+    /// drops them
+    fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    fn stats(&self) -> CacheStats {
+        self.stats.get()
+    }
+
+    fn policy(&self) -> std::cell::Ref<'_, P> {
+        self.policy.borrow()
+    }
+
+    /// Live entries, from the one the policy would keep longest to the one
+    /// it would evict first. Iterating doesn't count as using them.
+    fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        let policy = self.policy.borrow();
+        // The references come from `data`, not from the `policy` guard
+        let entries: Vec<_> = policy
+            .eviction_order()
+            .into_iter()
+            .rev()
+            .filter(|key| !policy.is_expired(key))
+            .filter_map(|key| self.data.get_key_value(key))
+            .collect();
+        entries.into_iter()
+    }
+
+    /// Drops every expired entry, and returns how many there were
+    fn purge_expired(&mut self) -> usize {
+        let policy = self.policy.get_mut();
+        let expired: Vec<K> = self
+            .data
+            .keys()
+            .filter(|key| policy.is_expired(key))
+            .cloned()
+            .collect();
+        for key in &expired {
+            self.data.remove(key);
+            policy.removed(key);
+        }
+        let count = expired.len();
+        self.record(|stats| stats.expirations += count as u64);
+        count
+    }
+
+    fn record(&self, update: impl FnOnce(&mut CacheStats)) {
+        let mut stats = self.stats.get();
+        update(&mut stats);
+        self.stats.set(stats);
+    }
+
+    fn evict(&mut self) {
+        if let Some(key) = self.policy.get_mut().victim() {
+            self.data.remove(&key);
+            self.record(|stats| stats.evictions += 1);
+        }
+    }
+}
+
+impl<K, V, P> Cache<K, V> for SimpleCache<K, V, P>
+where
+    K: Eq + std::hash::Hash + Clone,
+    P: EvictionPolicy<K>,
+{
+    /// An expired entry is a miss, though it stays in the cache until it's
+    /// purged
+    fn get(&self, key: &K) -> Option<&V> {
+        let mut policy = self.policy.borrow_mut();
+        match self.data.get(key) {
+            Some(value) if !policy.is_expired(key) => {
+                policy.accessed(key);
+                self.record(|stats| stats.hits += 1);
+                Some(value)
+            }
+            _ => {
+                self.record(|stats| stats.misses += 1);
+                None
+            }
+        }
+    }
+
+    /// Adding a new key to a full cache drops any expired entries first,
+    /// and if that doesn't make room, evicts the policy's victim.
+    fn set(&mut self, key: K, value: V) {
+        if let Some(existing) = self.data.get_mut(&key) {
+            *existing = value;
+        } else {
+            if self.data.len() >= self.capacity {
+                self.purge_expired();
+            }
+            if self.data.len() >= self.capacity {
+                self.evict();
+            }
+            self.data.insert(key.clone(), value);
+        }
+        self.policy.get_mut().inserted(&key);
+    }
+
+    fn remove(&mut self, key: &K) -> Option<V> {
+        let value = self.data.remove(key)?;
+        self.policy.get_mut().removed(key);
+        Some(value)
+    }
+}
+
+/// Repository pattern with generics and trait objects
+trait Repository<T> {
+    fn find_by_id(&self, id: u32) -> Option<T>;
+    fn save(&mut self, item: T) -> u32;
+    fn delete(&mut self, id: u32) -> bool;
+
+    /// The page of items `query` asks for. Takes `&dyn Query` rather than
+    /// a generic parameter so `dyn Repository` still works.
+    fn find(&self, query: &dyn Query<T>) -> Page<T>;
+}
+
+/// Which items a `Repository::find` returns, in what order, and which
+/// page of them
+///
+/// Any `Fn(&T) -> bool` closure is a query that filters and returns every
+/// match, in the order the items were saved. `sorted_by` and `paged` wrap
+/// a query in one that also orders or pages the results:
+///
+/// ```text
+/// (|n: &u32| n % 2 == 0).sorted_by(|a, b| b.cmp(a)).paged(2, 10)
+/// ```
+trait Query<T> {
+    fn matches(&self, item: &T) -> bool;
+
+    /// How two matches are ordered; equal ones keep the order they were
+    /// saved in
+    fn compare(&self, a: &T, b: &T) -> std::cmp::Ordering {
+        std::cmp::Ordering::Equal
+    }
+
+    /// The page wanted, counting from 1, and how many items a page holds
+    fn page(&self) -> (usize, usize) {
+        (1, usize::MAX)
+    }
+
+    fn sorted_by<S>(self, order: S) -> Sorted<Self, S>
+    where
+        Self: Sized,
+        S: Fn(&T, &T) -> std::cmp::Ordering,
+    {
+        Sorted { query: self, order }
+    }
+
+    fn paged(self, number: usize, per_page: usize) -> Paged<Self>
+    where
+        Self: Sized,
+    {
+        assert!(number > 0, "Pages are numbered from 1");
+        assert!(per_page > 0, "A page must hold at least one item");
+        Paged {
+            query: self,
+            number,
+            per_page,
+        }
+    }
+}
+
+impl<T, F> Query<T> for F
+where
+    F: Fn(&T) -> bool,
+{
+    fn matches(&self, item: &T) -> bool {
+        self(item)
+    }
+}
+
+struct Sorted<Q, S> {
+    query: Q,
+    order: S,
+}
+
+impl<T, Q, S> Query<T> for Sorted<Q, S>
+where
+    Q: Query<T>,
+    S: Fn(&T, &T) -> std::cmp::Ordering,
+{
+    fn matches(&self, item: &T) -> bool {
+        self.query.matches(item)
+    }
+
+    fn compare(&self, a: &T, b: &T) -> std::cmp::Ordering {
+        (self.order)(a, b)
+    }
+
+    fn page(&self) -> (usize, usize) {
+        self.query.page()
+    }
+}
+
+struct Paged<Q> {
+    query: Q,
+    number: usize,
+    per_page: usize,
+}
+
+impl<T, Q: Query<T>> Query<T> for Paged<Q> {
+    fn matches(&self, item: &T) -> bool {
+        self.query.matches(item)
+    }
+
+    fn compare(&self, a: &T, b: &T) -> std::cmp::Ordering {
+        self.query.compare(a, b)
+    }
+
+    fn page(&self) -> (usize, usize) {
+        (self.number, self.per_page)
+    }
+}
+
+/// One page of a query's results
+#[derive(Debug, Clone, PartialEq)]
+struct Page<T> {
+    items: Vec<T>,
+    /// Counting from 1
+    number: usize,
+    per_page: usize,
+    /// Matches across every page
+    total: usize,
+}
+
+impl<T> Page<T> {
+    fn total_pages(&self) -> usize {
+        self.total.div_ceil(self.per_page)
+    }
+
+    fn has_next(&self) -> bool {
+        self.number < self.total_pages()
+    }
+}
+
+/// Runs `query` over a repository's items, whatever they're stored in
+fn run_query<'a, T: Clone + 'a>(
+    items: impl Iterator<Item = (&'a u32, &'a T)>,
+    query: &dyn Query<T>,
+) -> Page<T> {
+    let mut matches: Vec<_> = items.filter(|(_, item)| query.matches(item)).collect();
+    // Ids go up as items are saved, and the sort below is stable, so this
+    // is what puts equal items in the order they were saved
+    matches.sort_by_key(|(id, _)| **id);
+    matches.sort_by(|(_, a), (_, b)| query.compare(a, b));
+
+    let (number, per_page) = query.page();
+    let total = matches.len();
+    let items = matches
+        .into_iter()
+        .skip((number - 1).saturating_mul(per_page))
+        .take(per_page)
+        .map(|(_, item)| item.clone())
+        .collect();
+    Page {
+        items,
+        number,
+        per_page,
+        total,
+    }
+}
+
+struct InMemoryRepository<T> {
+    items: std::collections::HashMap<u32, T>,
+    next_id: u32,
+}
+
+impl<T: Clone> InMemoryRepository<T> {
+    fn new() -> Self {
+        InMemoryRepository {
+            items: std::collections::HashMap::new(),
+            next_id: 1,
+        }
+    }
+
+    fn snapshot(&self) -> RepositorySnapshot<T> {
+        RepositorySnapshot {
+            next_id: self.next_id,
+            items: self
+                .items
+                .iter()
+                .map(|(&id, item)| (id, item.clone()))
+                .collect(),
+        }
+    }
+
+    fn from_snapshot(snapshot: RepositorySnapshot<T>) -> Self {
+        InMemoryRepository {
+            items: snapshot.items.into_iter().collect(),
+            next_id: snapshot.next_id,
+        }
+    }
+}
+
+/// Everything needed to rebuild a repository. `next_id` is kept so
+/// deleting the newest item doesn't let its id be handed out again.
+///
+/// By default the derive bounds the impls it generates with `T: Serialize`
+/// and `T: Deserialize<'de>`; `#[serde(bound)]` replaces those. Asking for
+/// `DeserializeOwned` instead means a snapshot can never borrow from the
+/// text it was read from, so it can outlive the buffer, and a
+/// `RepositorySnapshot<&str>` is rejected at compile time rather than
+/// failing on a string with escapes in it.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(bound(
+    serialize = "T: serde::Serialize",
+    deserialize = "T: serde::de::DeserializeOwned"
+))]
+struct RepositorySnapshot<T> {
+    next_id: u32,
+    items: std::collections::BTreeMap<u32, T>,
+}
+
+impl<T> RepositorySnapshot<T> {
+    fn empty() -> Self {
+        RepositorySnapshot {
+            next_id: 1,
+            items: std::collections::BTreeMap::new(),
+        }
+    }
+}
+
+impl<T: Clone> Repository<T> for InMemoryRepository<T> {
+    fn find_by_id(&self, id: u32) -> Option<T> {
+        self.items.get(&id).cloned()
+    }
+
+    fn save(&mut self, item: T) -> u32 {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.items.insert(id, item);
+        id
+    }
+
+    fn delete(&mut self, id: u32) -> bool {
+        self.items.remove(&id).is_some()
+    }
+
+    fn find(&self, query: &dyn Query<T>) -> Page<T> {
+        run_query(self.items.iter(), query)
+    }
+}
+
+/// Repository that keeps its items in a JSON file, so they survive a
+/// restart
+///
+/// Code written against `Repository<T>` can't tell it apart from
+/// `InMemoryRepository`; only the constructor changes. Every save and
+/// delete rewrites the whole file, which is fine for an example and for
+/// small data sets.
+struct FileRepository<T> {
+    path: std::path::PathBuf,
+    items: std::collections::BTreeMap<u32, T>,
+    next_id: u32,
+}
+
+impl<T> FileRepository<T>
+where
+    T: serde::Serialize + serde::de::DeserializeOwned,
+{
+    /// Loads the snapshot in `path`, or starts empty if there's no file
+    /// yet
+    fn open(path: impl Into<std::path::PathBuf>) -> std::io::Result<Self> {
+        let path = path.into();
+        let snapshot = match std::fs::read_to_string(&path) {
+            Ok(json) => serde_json::from_str(&json)?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => RepositorySnapshot::empty(),
+            Err(e) => return Err(e),
+        };
+        Ok(FileRepository {
+            path,
+            items: snapshot.items,
+            next_id: snapshot.next_id,
+        })
+    }
+
+    fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    /// Like `Repository::save`, but reports a failed write instead of
+    /// panicking. The item is only kept if the write succeeds.
+    fn try_save(&mut self, item: T) -> std::io::Result<u32> {
+        let id = self.next_id;
+        self.items.insert(id, item);
+        self.next_id += 1;
+        if let Err(e) = self.write() {
+            self.items.remove(&id);
+            self.next_id -= 1;
+            return Err(e);
+        }
+        Ok(id)
+    }
+
+    /// Like `Repository::delete`, but reports a failed write instead of
+    /// panicking. The item is only dropped if the write succeeds.
+    fn try_delete(&mut self, id: u32) -> std::io::Result<bool> {
+        let Some(item) = self.items.remove(&id) else {
+            return Ok(false);
+        };
+        if let Err(e) = self.write() {
+            self.items.insert(id, item);
+            return Err(e);
+        }
+        Ok(true)
+    }
+
+    /// Writes to a temporary file next to the real one and renames it into
+    /// place, so a crash mid-write leaves the old file intact rather than
+    /// half a new one
+    fn write(&self) -> std::io::Result<()> {
+        // A snapshot of borrowed items, so writing doesn't clone them
+        let snapshot = RepositorySnapshot {
+            next_id: self.next_id,
+            items: self.items.iter().map(|(&id, item)| (id, item)).collect(),
+        };
+        let json = serde_json::to_string_pretty(&snapshot)?;
+
+        let mut temp_path = self.path.clone().into_os_string();
+        temp_path.push(".tmp");
+        let mut temp = std::fs::File::create(&temp_path)?;
+        std::io::Write::write_all(&mut temp, json.as_bytes())?;
+        temp.sync_all()?;
+        std::fs::rename(&temp_path, &self.path)
+    }
+}
+
+/// `Repository` has no way to report errors, so a failed write panics; use
+/// `try_save` and `try_delete` to handle them
+impl<T> Repository<T> for FileRepository<T>
+where
+    T: Clone + serde::Serialize + serde::de::DeserializeOwned,
+{
+    fn find_by_id(&self, id: u32) -> Option<T> {
+        self.items.get(&id).cloned()
+    }
+
+    fn save(&mut self, item: T) -> u32 {
+        self.try_save(item)
+            .unwrap_or_else(|e| panic!("Could not write {}: {}", self.path.display(), e))
+    }
+
+    fn delete(&mut self, id: u32) -> bool {
+        self.try_delete(id)
+            .unwrap_or_else(|e| panic!("Could not write {}: {}", self.path.display(), e))
+    }
+
+    fn find(&self, query: &dyn Query<T>) -> Page<T> {
+        run_query(self.items.iter(), query)
+    }
+}
+
+/// Generic data processor with multiple trait bounds
+///
+/// The provided methods are combinators: each wraps `self` in a new
+/// processor whose associated types are worked out from the ones it wraps,
+/// so pipelines are built by chaining calls rather than writing the
+/// plumbing by hand.
+trait Processor {
+    type Input;
+    type Output;
+    type Error;
+
+    fn process(&self, input: Self::Input) -> Result<Self::Output, Self::Error>;
+
+    /// Feeds this processor's output into `next`
+    fn then<P>(self, next: P) -> Then<Self, P>
+    where
+        Self: Sized,
+        P: Processor<Input = Self::Output, Error = Self::Error>,
+    {
+        Then { first: self, next }
+    }
+
+    /// Transforms successful output with a function that can't fail
+    fn map_output<F, O>(self, f: F) -> MapOutput<Self, F>
+    where
+        Self: Sized,
+        F: Fn(Self::Output) -> O,
+    {
+        MapOutput { processor: self, f }
+    }
+
+    /// Tries up to `attempts` times in all, returning the last error if
+    /// every attempt fails
+    fn retry(self, attempts: usize) -> Retry<Self>
+    where
+        Self: Sized,
+        Self::Input: Clone,
+    {
+        assert!(attempts > 0, "Retry needs at least one attempt");
+        Retry {
+            processor: self,
+            attempts,
+        }
+    }
+
+    /// Processes a batch of inputs across rayon's thread pool, keeping
+    /// their order. Fails if any input does.
+    fn parallel(self) -> Parallel<Self>
+    where
+        Self: Sized + Sync,
+        Self::Input: Send,
+        Self::Output: Send,
+        Self::Error: Send,
+    {
+        Parallel { processor: self }
+    }
+}
+
+struct Then<A, B> {
+    first: A,
+    next: B,
+}
+
+impl<A, B> Processor for Then<A, B>
+where
+    A: Processor,
+    B: Processor<Input = A::Output, Error = A::Error>,
+{
+    type Input = A::Input;
+    type Output = B::Output;
+    type Error = A::Error;
+
+    fn process(&self, input: Self::Input) -> Result<Self::Output, Self::Error> {
+        self.next.process(self.first.process(input)?)
+    }
+}
+
+struct MapOutput<P, F> {
+    processor: P,
+    f: F,
+}
+
+impl<P, F, O> Processor for MapOutput<P, F>
+where
+    P: Processor,
+    F: Fn(P::Output) -> O,
+{
+    type Input = P::Input;
+    type Output = O;
+    type Error = P::Error;
+
+    fn process(&self, input: Self::Input) -> Result<Self::Output, Self::Error> {
+        self.processor.process(input).map(&self.f)
+    }
+}
+
+struct Retry<P> {
+    processor: P,
+    attempts: usize,
+}
+
+impl<P> Processor for Retry<P>
+where
+    P: Processor,
+    P::Input: Clone,
+{
+    type Input = P::Input;
+    type Output = P::Output;
+    type Error = P::Error;
+
+    fn process(&self, input: Self::Input) -> Result<Self::Output, Self::Error> {
+        let mut result = self.processor.process(input.clone());
+        for _ in 1..self.attempts {
+            if result.is_ok() {
+                break;
+            }
+            result = self.processor.process(input.clone());
+        }
+        result
+    }
+}
+
+struct Parallel<P> {
+    processor: P,
+}
+
+impl<P> Processor for Parallel<P>
+where
+    P: Processor + Sync,
+    P::Input: Send,
+    P::Output: Send,
+    P::Error: Send,
+{
+    type Input = Vec<P::Input>;
+    type Output = Vec<P::Output>;
+    type Error = P::Error;
+
+    fn process(&self, inputs: Self::Input) -> Result<Self::Output, Self::Error> {
+        use rayon::prelude::*;
+        inputs
+            .into_par_iter()
+            .map(|input| self.processor.process(input))
+            .collect()
+    }
+}
+
+struct StringProcessor;
+
+impl Processor for StringProcessor {
+    type Input = String;
+    type Output = Vec<String>;
+    type Error = String;
+
+    fn process(&self, input: Self::Input) -> Result<Self::Output, Self::Error> {
+        if input.is_empty() {
+            Err("Empty input".to_string())
+        } else {
+            Ok(input.split_whitespace().map(String::from).collect())
+        }
+    }
+}
+
+/// Counts how often each word appears, most frequent first, then
+/// alphabetically
+struct WordCounter;
+
+impl Processor for WordCounter {
+    type Input = Vec<String>;
+    type Output = Vec<(String, usize)>;
+    type Error = String;
+
+    fn process(&self, words: Self::Input) -> Result<Self::Output, Self::Error> {
+        if words.is_empty() {
+            return Err("No words".to_string());
+        }
+        let mut counts = std::collections::HashMap::new();
+        for word in words {
+            *counts.entry(word).or_insert(0) += 1;
+        }
+        let mut counts: Vec<_> = counts.into_iter().collect();
+        counts.sort_by(|(a, a_count), (b, b_count)| b_count.cmp(a_count).then(a.cmp(b)));
+        Ok(counts)
+    }
+}
+
+/// Lowercase words with punctuation trimmed off, keeping any that are left
+fn normalize_words(words: Vec<String>) -> Vec<String> {
+    words
+        .into_iter()
+        .map(|word| {
+            word.trim_matches(|c: char| !c.is_alphanumeric())
+                .to_lowercase()
+        })
+        .filter(|word| !word.is_empty())
+        .collect()
+}
+
+/// Splits, normalizes and counts the words in a text
+fn word_frequency_pipeline(
+) -> impl Processor<Input = String, Output = Vec<(String, usize)>, Error = String> {
+    StringProcessor
+        .map_output(normalize_words)
+        .then(WordCounter)
+}
+
+/// Fails its first `failures` calls, like a service that's briefly down
+struct FlakyProcessor {
+    failures: std::sync::atomic::AtomicUsize,
+}
+
+impl FlakyProcessor {
+    fn new(failures: usize) -> Self {
+        FlakyProcessor {
+            failures: std::sync::atomic::AtomicUsize::new(failures),
+        }
+    }
+}
+
+impl Processor for FlakyProcessor {
+    type Input = String;
+    type Output = String;
+    type Error = String;
+
+    fn process(&self, input: Self::Input) -> Result<Self::Output, Self::Error> {
+        let failed = self.failures.fetch_update(
+            std::sync::atomic::Ordering::SeqCst,
+            std::sync::atomic::Ordering::SeqCst,
+            |left| left.checked_sub(1),
+        );
+        match failed {
+            Ok(_) => Err("Service unavailable".to_string()),
+            Err(_) => Ok(input),
+        }
+    }
+}
+
+/// Trait for operations with default implementations
+trait Mathematic {
+    fn add(&self, other: &Self) -> Self;
+    fn subtract(&self, other: &Self) -> Self;
+
+    // Default implementation
+    fn multiply(&self, times: usize) -> Self
+    where
+        Self: Sized + Clone,
+    {
+        let mut result = self.clone();
+        for _ in 1..times {
+            result = result.add(self);
+        }
+        result
+    }
+}
+
+#[derive(Clone)]
+struct Number(i32);
+
+impl Mathematic for Number {
+    fn add(&self, other: &Self) -> Self {
+        Number(self.0 + other.0)
+    }
+
+    fn subtract(&self, other: &Self) -> Self {
+        Number(self.0 - other.0)
+    }
+
+    // Can override default implementation
+    fn multiply(&self, times: usize) -> Self {
+        Number(self.0 * times as i32)
+    }
+}
+
+/// Observer pattern over typed events
+///
+/// Handlers for every event type live in one map, keyed by the event's
+/// `TypeId`. Each is stored as a `Box<dyn FnMut(&dyn Any)>` that downcasts
+/// the event back to the type it subscribed to, so the map can hold them
+/// all while `subscribe` and `publish` stay fully typed.
+struct EventBus {
+    handlers: std::collections::HashMap<std::any::TypeId, Vec<Subscription>>,
+    next_id: u64,
+}
+
+/// A handler with its event type erased
+type AnyHandler = Box<dyn FnMut(&dyn std::any::Any)>;
+
+struct Subscription {
+    id: u64,
+    handler: AnyHandler,
+}
+
+/// Returned by `subscribe`; pass it to `unsubscribe` to remove the handler
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct SubscriptionToken {
+    event: std::any::TypeId,
+    id: u64,
+}
+
+impl EventBus {
+    fn new() -> Self {
+        EventBus {
+            handlers: std::collections::HashMap::new(),
+            next_id: 0,
+        }
+    }
+
+    /// Calls `handler` with every `E` published from now on
+    fn subscribe<E: 'static>(
+        &mut self,
+        mut handler: impl FnMut(&E) + 'static,
+    ) -> SubscriptionToken {
+        let event = std::any::TypeId::of::<E>();
+        let id = self.next_id;
+        self.next_id += 1;
+        let handler = Box::new(move |event: &dyn std::any::Any| {
+            // Only ever stored under `E`'s TypeId, so this always succeeds
+            if let Some(event) = event.downcast_ref::<E>() {
+                handler(event);
+            }
+        });
+        self.handlers
+            .entry(event)
+            .or_default()
+            .push(Subscription { id, handler });
+        SubscriptionToken { event, id }
+    }
+
+    /// Returns false if the handler was already removed
+    fn unsubscribe(&mut self, token: SubscriptionToken) -> bool {
+        let Some(subscriptions) = self.handlers.get_mut(&token.event) else {
+            return false;
+        };
+        let before = subscriptions.len();
+        subscriptions.retain(|subscription| subscription.id != token.id);
+        before != subscriptions.len()
+    }
+
+    /// Calls every handler subscribed to `E`, oldest first, and returns how
+    /// many there were
+    fn publish<E: 'static>(&mut self, event: &E) -> usize {
+        match self.handlers.get_mut(&std::any::TypeId::of::<E>()) {
+            Some(subscriptions) => {
+                for subscription in subscriptions.iter_mut() {
+                    (subscription.handler)(event);
+                }
+                subscriptions.len()
+            }
+            None => 0,
+        }
+    }
+
+    fn handler_count<E: 'static>(&self) -> usize {
+        self.handlers
+            .get(&std::any::TypeId::of::<E>())
+            .map_or(0, Vec::len)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct UserCreated {
+    id: u32,
+    username: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct UserDeleted {
+    id: u32,
+}
+
+/// What every plugin is run with: the text to work on and any settings
+struct Context {
+    input: String,
+    settings: std::collections::HashMap<String, String>,
+}
+
+impl Context {
+    fn new(input: &str) -> Self {
+        Context {
+            input: input.to_string(),
+            settings: std::collections::HashMap::new(),
+        }
+    }
+
+    fn with_setting(mut self, key: &str, value: &str) -> Self {
+        self.settings.insert(key.to_string(), value.to_string());
+        self
+    }
+
+    fn setting(&self, key: &str) -> Option<&str> {
+        self.settings.get(key).map(String::as_str)
+    }
+}
+
+/// An extension the registry can run without knowing its concrete type
+///
+/// `Any` as a supertrait lets a `&dyn Plugin` be upcast to `&dyn Any` and
+/// downcast back to the concrete plugin, for callers that need more than
+/// this trait offers.
+trait Plugin: std::any::Any {
+    fn name(&self) -> &str;
+    fn version(&self) -> &str;
+    fn execute(&self, context: &Context) -> Result<String, String>;
+
+    /// Lower runs first; plugins with the same priority run in the order
+    /// they were registered
+    fn priority(&self) -> i32 {
+        0
+    }
+}
+
+struct UppercasePlugin;
+
+impl Plugin for UppercasePlugin {
+    fn name(&self) -> &str {
+        "uppercase"
+    }
+
+    fn version(&self) -> &str {
+        "1.0.0"
+    }
+
+    fn execute(&self, context: &Context) -> Result<String, String> {
+        Ok(context.input.to_uppercase())
+    }
+}
+
+/// Counts words, and remembers how often it's been run
+struct WordCountPlugin {
+    runs: std::cell::Cell<u32>,
+}
+
+impl WordCountPlugin {
+    fn new() -> Self {
+        WordCountPlugin {
+            runs: std::cell::Cell::new(0),
+        }
+    }
+
+    fn runs(&self) -> u32 {
+        self.runs.get()
+    }
+}
+
+impl Plugin for WordCountPlugin {
+    fn name(&self) -> &str {
+        "word-count"
+    }
+
+    fn version(&self) -> &str {
+        "2.1.0"
+    }
+
+    fn execute(&self, context: &Context) -> Result<String, String> {
+        self.runs.set(self.runs.get() + 1);
+        Ok(format!(
+            "{} words",
+            context.input.split_whitespace().count()
+        ))
+    }
+
+    fn priority(&self) -> i32 {
+        -10
+    }
+}
+
+/// Needs a `greeting` setting, and fails without one
+struct GreetingPlugin;
+
+impl Plugin for GreetingPlugin {
+    fn name(&self) -> &str {
+        "greeting"
+    }
+
+    fn version(&self) -> &str {
+        "0.3.0"
+    }
+
+    fn execute(&self, context: &Context) -> Result<String, String> {
+        let greeting = context
+            .setting("greeting")
+            .ok_or("Missing setting: greeting")?;
+        Ok(format!("{}, {}", greeting, context.input))
+    }
+}
+
+/// Plugins by name, run in priority order
+struct PluginRegistry {
+    plugins: Vec<Box<dyn Plugin>>,
+}
+
+impl PluginRegistry {
+    fn new() -> Self {
+        PluginRegistry {
+            plugins: Vec::new(),
+        }
+    }
+
+    fn register(&mut self, plugin: Box<dyn Plugin>) -> Result<(), String> {
+        if self.get(plugin.name()).is_some() {
+            return Err(format!("Plugin already registered: {}", plugin.name()));
+        }
+        // A stable sort keeps equal priorities in registration order
+        self.plugins.push(plugin);
+        self.plugins.sort_by_key(|plugin| plugin.priority());
+        Ok(())
+    }
+
+    fn get(&self, name: &str) -> Option<&dyn Plugin> {
+        self.plugins
+            .iter()
+            .find(|plugin| plugin.name() == name)
+            .map(|plugin| plugin.as_ref())
+    }
+
+    /// The plugin called `name`, if it's a `P`
+    fn get_as<P: Plugin>(&self, name: &str) -> Option<&P> {
+        let plugin: &dyn std::any::Any = self.get(name)?;
+        plugin.downcast_ref::<P>()
+    }
+
+    /// Names with versions, in the order the plugins run
+    fn list(&self) -> Vec<String> {
+        self.plugins
+            .iter()
+            .map(|plugin| format!("{} {}", plugin.name(), plugin.version()))
+            .collect()
+    }
+
+    /// Runs every plugin, even after one fails, and returns each one's
+    /// result by name
+    fn execute_all(&self, context: &Context) -> Vec<(&str, Result<String, String>)> {
+        self.plugins
+            .iter()
+            .map(|plugin| (plugin.name(), plugin.execute(context)))
+            .collect()
+    }
+}
+
+// ============================================================================
+// ASYNC TRAITS
+// ============================================================================
+
+/// Repository whose storage might be a database or a remote service, so
+/// every call is async
+///
+/// Written with `#[async_trait]`, which turns each `async fn` into a method
+/// returning `Pin<Box<dyn Future + Send>>`. That costs an allocation per
+/// call, but keeps the trait object safe: `Box<dyn AsyncRepository<T>>`
+/// works, unlike with the native version below.
+#[async_trait::async_trait]
+trait AsyncRepository<T>: Send + Sync {
+    async fn find_by_id(&self, id: u32) -> Option<T>;
+    async fn save(&self, item: T) -> u32;
+    async fn delete(&self, id: u32) -> bool;
+}
+
+/// Wraps the synchronous `InMemoryRepository` in a tokio lock, so it can be
+/// shared between tasks and takes `&self` everywhere
+struct TokioRepository<T> {
+    inner: tokio::sync::RwLock<InMemoryRepository<T>>,
+}
+
+impl<T: Clone> TokioRepository<T> {
+    fn new() -> Self {
+        TokioRepository {
+            inner: tokio::sync::RwLock::new(InMemoryRepository::new()),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl<T: Clone + Send + Sync> AsyncRepository<T> for TokioRepository<T> {
+    async fn find_by_id(&self, id: u32) -> Option<T> {
+        self.inner.read().await.find_by_id(id)
+    }
+
+    async fn save(&self, item: T) -> u32 {
+        self.inner.write().await.save(item)
+    }
+
+    async fn delete(&self, id: u32) -> bool {
+        self.inner.write().await.delete(id)
+    }
+}
+
+/// Cache with native async methods: each returns `impl Future`, which the
+/// compiler turns into a concrete future type per implementation
+///
+/// No boxing, but a trait with `impl Trait` in return position can't be
+/// made into `dyn AsyncCache`, so it's for static dispatch only. Spelling
+/// the methods out as `-> impl Future + Send` rather than `async fn` is
+/// what lets callers spawn them on a multi-threaded runtime.
+trait AsyncCache<K, V> {
+    fn get(&self, key: &K) -> impl std::future::Future<Output = Option<V>> + Send;
+    fn set(&self, key: K, value: V) -> impl std::future::Future<Output = ()> + Send;
+    fn remove(&self, key: &K) -> impl std::future::Future<Output = Option<V>> + Send;
+}
+
+/// `SimpleCache` behind a tokio mutex. Values come back cloned, since a
+/// reference can't outlive the lock.
+struct TokioCache<K, V>
+where
+    K: Eq + std::hash::Hash + Clone,
+{
+    inner: tokio::sync::Mutex<SimpleCache<K, V>>,
+}
+
+impl<K, V> TokioCache<K, V>
+where
+    K: Eq + std::hash::Hash + Clone,
+{
+    fn with_capacity(capacity: usize) -> Self {
+        TokioCache {
+            inner: tokio::sync::Mutex::new(SimpleCache::with_capacity(capacity)),
+        }
+    }
+
+    async fn stats(&self) -> CacheStats {
+        self.inner.lock().await.stats()
+    }
+}
+
+// `async fn` in the impl satisfies the trait's `impl Future + Send`, as
+// long as the future it makes really is `Send`
+impl<K, V> AsyncCache<K, V> for TokioCache<K, V>
+where
+    K: Eq + std::hash::Hash + Clone + Send + Sync,
+    V: Clone + Send + Sync,
+{
+    async fn get(&self, key: &K) -> Option<V> {
+        self.inner.lock().await.get(key).cloned()
+    }
+
+    async fn set(&self, key: K, value: V) {
+        self.inner.lock().await.set(key, value);
+    }
+
+    async fn remove(&self, key: &K) -> Option<V> {
+        self.inner.lock().await.remove(key)
+    }
+}
+
+/// Looks up `id` through the cache, falling back to the repository. Works
+/// with any cache and any repository, boxed or not.
+async fn cached_find<T, C>(cache: &C, repo: &dyn AsyncRepository<T>, id: u32) -> Option<T>
+where
+    T: Clone,
+    C: AsyncCache<u32, T>,
+{
+    if let Some(item) = cache.get(&id).await {
+        return Some(item);
+    }
+    let item = repo.find_by_id(id).await?;
+    cache.set(id, item.clone()).await;
+    Some(item)
+}
+
+// ============================================================================
+// MAIN FUNCTION
+// ============================================================================
+
+fn main() {
+    println!("=== Traits and Generics Examples ===\n");
+
+    // 1. Generic Functions and Structs
+    println!("--- Generic Functions and Structs ---");
+    print_value(42);
+    print_value("Hello");
+    print_pair(10, "items");
+
+    let container = Container::new(100);
+    println!("Container value: {}", container.get());
+
+    let pair = Pair::new("first", 2);
+    println!(
+        "As JSON: {}",
+        serde_json::to_string(&pair).expect("a pair of a string and a number serializes")
+    );
+    let (a, b) = pair.into_tuple();
+    println!("Tuple: ({}, {})", a, b);
+
+    // 2. Trait Bounds
     println!("\n--- Trait Bounds ---");
     compare_and_display(10, 20);
     compare_and_display("apple", "banana");
 
-    let comp = Comparator::new(5);
-    println!("5 > 3? {}", comp.is_greater_than(&3));
+    let comp = Comparator::new(5);
+    println!("5 > 3? {}", comp.is_greater_than(&3));
+
+    // 3. Where Clauses
+    println!("\n--- Where Clauses ---");
+    complex_function("test", 42, "string");
+
+    let result = longest_with_announcement("long", "short", "Comparing strings");
+    println!("Longest: {}", result);
+
+    // 4. Associated Types
+    println!("\n--- Associated Types ---");
+    let mut counter = Counter { count: 0, max: 5 };
+    while let Some(value) = counter.next() {
+        println!("Count: {}", value);
+    }
+
+    // 5. Generic Traits
+    println!("\n--- Generic Traits ---");
+    let wrapper = IntWrapper(42);
+    let as_string: String = wrapper.convert();
+    let as_float: f64 = wrapper.convert();
+    println!("As string: {}, As float: {}", as_string, as_float);
+
+    // 6. Trait Objects
+    println!("\n--- Trait Objects ---");
+    let shapes: Vec<Box<dyn Drawable>> = vec![
+        Box::new(Circle { radius: 3.0 }),
+        Box::new(Rectangle {
+            width: 4.0,
+            height: 5.0,
+        }),
+        Box::new(Triangle {
+            base: 3.0,
+            height: 4.0,
+        }),
+    ];
+    draw_shapes(&shapes);
+
+    let shape = create_shape("circle");
+    shape.draw();
+
+    // 7. Static vs Dynamic Dispatch
+    println!("\n--- Static vs Dynamic Dispatch ---");
+    demonstrate_dispatch();
+
+    // 8. Impl Trait
+    println!("\n--- Impl Trait ---");
+    let adder = make_adder(10);
+    println!("10 + 5 = {}", adder(5));
+
+    print_iter(1..4);
+
+    let iter = create_iterator(1, 4);
+    for item in iter {
+        println!("Iterator item: {}", item);
+    }
+
+    // 9. Trait Inheritance
+    println!("\n--- Trait Inheritance ---");
+    let dog = Dog {
+        name: "Buddy".to_string(),
+        owner: "Alice".to_string(),
+    };
+    pet_info(&dog);
+    println!("Has fur? {}", dog.has_fur());
+
+    // 10. Blanket Implementations
+    println!("\n--- Blanket Implementations ---");
+    let number = 42;
+    number.print();
+    "Hello".print();
+
+    // 11. Marker Traits
+    println!("\n--- Marker Traits ---");
+    let safe_data = SafeData { value: 100 };
+    let handle = process_in_thread(safe_data);
+    let _ = handle.join();
+
+    let value = 42;
+    let (v1, v2) = demonstrate_copy(value);
+    println!("Copied values: {} and {}", v1, v2);
+
+    print_maybe_sized("This works with str!");
+
+    // 12. Advanced Patterns
+    println!("\n--- Advanced Patterns ---");
+
+    // Newtype pattern
+    let distance = Meters(5000.0);
+    let km = distance.to_kilometers();
+    println!("Distance: {} meters = {} km", distance.0, km.0);
+
+    // Operator overloading
+    let total = distance + Kilometers(1.5) - Meters(500.0);
+    println!("Total: {:?}, doubled: {:?}", total, total * 2.0);
+    println!("Longer than 5 km? {}", total > Kilometers(5.0));
+    let speed = total / Seconds(1200.0);
+    println!("Over 20 minutes: {:?}", speed);
+    let lap = Quantity::<Length>::new(400.0) * 4.0;
+    let pace = lap / Quantity::<Time>::new(320.0);
+    println!("Four laps in 320 s: {} m/s", pace.value);
+
+    let username = Username("rustacean".to_string());
+    println!("Username: {}", username);
+
+    // Visitor pattern
+    let shapes: Vec<Box<dyn Drawable>> = vec![
+        Box::new(Circle { radius: 5.0 }),
+        Box::new(Rectangle {
+            width: 20.0,
+            height: 10.0,
+        }),
+        Box::new(Triangle {
+            base: 10.0,
+            height: 8.0,
+        }),
+    ];
+    let mut areas = AreaSummer::default();
+    let mut svg = SvgExporter::default();
+    for shape in &shapes {
+        shape.accept(&mut areas);
+        shape.accept(&mut svg);
+    }
+    println!("Total area: {:.2}", areas.total);
+    print!("{}", svg.finish());
+
+    // Type state pattern
+    let door = Door::<Locked>::new();
+    let door = door.unlock();
+    door.open();
+    let door = door.lock();
+
+    // Builder with type state
+    let person = PersonBuilder::new()
+        .name("Alice".to_string())
+        .age(30)
+        .build();
+    println!("Person: {} is {} years old", person.name, person.age);
+
+    // A more realistic type state builder
+    let request = RequestBuilder::new(Method::Post)
+        .header("Content-Type", "application/json")
+        .url("https://api.example.com/users")
+        .body(r#"{"name":"Alice"}"#)
+        .send();
+    println!(
+        "{} {} with {} header(s) and body {:?}",
+        request.method,
+        request.url,
+        request.headers.len(),
+        request.body
+    );
+
+    // Generic associated types
+    let mut totals = [1, 2, 3, 4, 5];
+    let mut windows = WindowsMut::new(&mut totals, 2);
+    while let Some([previous, current]) = windows.next() {
+        *current += *previous;
+    }
+    println!("Running totals: {:?}", totals);
+    let counted = count_items(Lend(Counter { count: 0, max: 4 }));
+    println!("Counter lent {} items", counted);
+
+    // 13. Const Generics
+    println!("\n--- Const Generics ---");
+    let a = Matrix::new([[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]]);
+    let b = Matrix::new([[7.0, 8.0], [9.0, 10.0], [11.0, 12.0]]);
+    let product = a.multiply(&b);
+    println!(
+        "{:?} x {:?} = {:?}:",
+        a.dimensions(),
+        b.dimensions(),
+        product.dimensions()
+    );
+    print!("{}", product);
+    // a.multiply(&a) would not compile: a 2x3 can't multiply a 2x3
+    println!("Transposed:\n{}", a.transpose());
+
+    let mut recent: RingBuffer<u32, 3> = RingBuffer::new();
+    for reading in [10, 20, 30, 40, 50] {
+        if let Some(dropped) = recent.push(reading) {
+            println!("Dropped {} to make room", dropped);
+        }
+    }
+    println!(
+        "Last {} readings: {:?}",
+        recent.capacity(),
+        recent.iter().collect::<Vec<_>>()
+    );
+
+    // Real world examples
+    println!("\n--- Real World Examples ---");
+
+    // Cache
+    let mut cache = SimpleCache::with_capacity(2);
+    cache.set("key1", "value1");
+    cache.set("key2", "value2");
+    if let Some(value) = cache.get(&"key1") {
+        println!("Cache hit: {}", value);
+    }
+    cache.set("key3", "value3"); // evicts key2, the least recently used
+    let keys: Vec<_> = cache.iter().map(|(key, _)| *key).collect();
+    println!("Cached keys, most recent first: {:?}", keys);
+    let stats = cache.stats();
+    println!(
+        "Hits: {}, misses: {}, evictions: {}",
+        stats.hits, stats.misses, stats.evictions
+    );
+
+    // The same cache with other eviction policies
+    let mut lfu = SimpleCache::with_policy(2, LfuPolicy::new());
+    lfu.set("popular", 1);
+    lfu.set("rare", 2);
+    lfu.get(&"popular");
+    lfu.set("new", 3); // evicts rare, the least frequently used
+    let keys: Vec<_> = lfu.iter().map(|(key, _)| *key).collect();
+    println!("LFU keys, most used first: {:?}", keys);
+
+    let ttl = std::time::Duration::from_millis(10);
+    let mut expiring = SimpleCache::with_policy(10, TtlPolicy::new(ttl));
+    expiring.set("session", "abc123");
+    println!("Before expiry: {:?}", expiring.get(&"session"));
+    std::thread::sleep(ttl * 2);
+    println!("After expiry: {:?}", expiring.get(&"session"));
+    println!("Purged {} expired entries", expiring.purge_expired());
+
+    // Repository
+    let mut repo = InMemoryRepository::new();
+    let id = repo.save("Item 1");
+    if let Some(item) = repo.find_by_id(id) {
+        println!("Found item: {}", item);
+    }
+
+    // Querying a repository
+    let mut fruit = InMemoryRepository::new();
+    for name in ["pear", "apple", "fig", "banana", "cherry", "plum", "kiwi"] {
+        fruit.save(name.to_string());
+    }
+    let short_names = (|name: &String| name.len() <= 4)
+        .sorted_by(|a: &String, b: &String| a.cmp(b))
+        .paged(1, 3);
+    let page = fruit.find(&short_names);
+    println!(
+        "Short fruit, page {} of {}: {:?}",
+        page.number,
+        page.total_pages(),
+        page.items
+    );
+
+    // Swapping the storage behind the same trait
+    let path = std::env::temp_dir().join("traits-generics-repository.json");
+    let _ = std::fs::remove_file(&path);
+    let mut backends: Vec<(&str, Box<dyn Repository<String>>)> = vec![
+        ("memory", Box::new(InMemoryRepository::new())),
+        (
+            "file",
+            Box::new(FileRepository::open(&path).expect("temp dir should be readable")),
+        ),
+    ];
+    for (name, repo) in &mut backends {
+        let id = repo.save(format!("Saved in {}", name));
+        println!("{} backend gave id {}", name, id);
+    }
+    drop(backends);
+    let reopened: FileRepository<String> =
+        FileRepository::open(&path).expect("file was just written");
+    println!("After reopening the file: {:?}", reopened.find_by_id(1));
+    let _ = std::fs::remove_file(&path);
+
+    // Processor
+    let processor = StringProcessor;
+    match processor.process("hello world rust".to_string()) {
+        Ok(words) => println!("Processed words: {:?}", words),
+        Err(e) => println!("Error: {}", e),
+    }
+
+    // Processor pipelines
+    let pipeline = word_frequency_pipeline();
+    match pipeline.process("The cat saw the dog. The dog ran!".to_string()) {
+        Ok(counts) => println!("Word counts: {:?}", &counts[..3]),
+        Err(e) => println!("Error: {}", e),
+    }
+    let documents = vec![
+        "to be or not to be".to_string(),
+        "the rain in Spain".to_string(),
+    ];
+    let top_words = word_frequency_pipeline()
+        .map_output(|counts| counts[0].0.clone())
+        .parallel();
+    println!("Top word per document: {:?}", top_words.process(documents));
+    let reliable = FlakyProcessor::new(2).retry(3);
+    println!(
+        "Flaky with retries: {:?}",
+        reliable.process("ok".to_string())
+    );
+
+    // Mathematic trait
+    let num1 = Number(5);
+    let num2 = Number(3);
+    let sum = num1.add(&num2);
+    let product = num1.multiply(4);
+    println!("Sum: {}, Product: {}", sum.0, product.0);
+
+    // Event bus
+    let mut bus = EventBus::new();
+    let welcome = bus.subscribe(|event: &UserCreated| {
+        println!("Welcome, {}!", event.username);
+    });
+    let user_count = std::rc::Rc::new(std::cell::Cell::new(0));
+    {
+        let user_count = std::rc::Rc::clone(&user_count);
+        bus.subscribe(move |_: &UserCreated| user_count.set(user_count.get() + 1));
+    }
+    {
+        let user_count = std::rc::Rc::clone(&user_count);
+        bus.subscribe(move |_: &UserDeleted| user_count.set(user_count.get() - 1));
+    }
+    bus.publish(&UserCreated {
+        id: 1,
+        username: "alice".to_string(),
+    });
+    bus.unsubscribe(welcome);
+    bus.publish(&UserCreated {
+        id: 2,
+        username: "bob".to_string(),
+    });
+    bus.publish(&UserDeleted { id: 1 });
+    println!("Users after events: {}", user_count.get());
+
+    // Plugin registry
+    let mut registry = PluginRegistry::new();
+    let plugins: Vec<Box<dyn Plugin>> = vec![
+        Box::new(UppercasePlugin),
+        Box::new(GreetingPlugin),
+        Box::new(WordCountPlugin::new()),
+    ];
+    for plugin in plugins {
+        if let Err(e) = registry.register(plugin) {
+            println!("Error: {}", e);
+        }
+    }
+    println!("Plugins: {:?}", registry.list());
+    let context = Context::new("traits and generics").with_setting("greeting", "Hello");
+    for (name, result) in registry.execute_all(&context) {
+        println!("  {}: {:?}", name, result);
+    }
+    if let Some(counter) = registry.get_as::<WordCountPlugin>("word-count") {
+        println!("word-count has run {} time(s)", counter.runs());
+    }
+
+    // Async traits
+    println!("\n--- Async Traits ---");
+    let runtime = tokio::runtime::Runtime::new().expect("tokio runtime should start");
+    runtime.block_on(async {
+        // A trait object shared between tasks
+        let repo: std::sync::Arc<dyn AsyncRepository<String>> =
+            std::sync::Arc::new(TokioRepository::new());
+        let tasks: Vec<_> = (1..=3)
+            .map(|n| {
+                let repo = std::sync::Arc::clone(&repo);
+                tokio::spawn(async move { repo.save(format!("Task {}", n)).await })
+            })
+            .collect();
+        let mut ids = Vec::new();
+        for task in tasks {
+            ids.push(task.await.expect("task should not panic"));
+        }
+        ids.sort();
+        println!("Tasks saved ids: {:?}", ids);
+
+        let cache = TokioCache::with_capacity(10);
+        let first = cached_find(&cache, repo.as_ref(), 1).await;
+        let again = cached_find(&cache, repo.as_ref(), 1).await;
+        println!("Found {:?}, then {:?} from the cache", first, again);
+        let stats = cache.stats().await;
+        println!("Cache hits: {}, misses: {}", stats.hits, stats.misses);
+    });
+
+    println!("\n=== All examples completed successfully! ===");
+}
 
-    // 3. Where Clauses
-    println!("\n--- Where Clauses ---");
-    complex_function("test", 42, "string");
+// ============================================================================
+// TESTS
+// ============================================================================
 
-    let result = longest_with_announcement("long", "short", "Comparing strings");
-    println!("Longest: {}", result);
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    // 4. Associated Types
-    println!("\n--- Associated Types ---");
-    let mut counter = Counter { count: 0, max: 5 };
-    while let Some(value) = counter.next() {
-        println!("Count: {}", value);
+    #[test]
+    fn test_generic_container() {
+        let container = Container::new(42);
+        assert_eq!(*container.get(), 42);
+
+        let container_str = Container::new("test");
+        assert_eq!(*container_str.get(), "test");
     }
 
-    // 5. Generic Traits
-    println!("\n--- Generic Traits ---");
-    let wrapper = IntWrapper(42);
-    let as_string: String = wrapper.convert();
-    let as_float: f64 = wrapper.convert();
-    println!("As string: {}, As float: {}", as_string, as_float);
+    /// Serializes to JSON and back
+    fn round_trip<T>(value: &T) -> T
+    where
+        T: serde::Serialize + serde::de::DeserializeOwned,
+    {
+        let json = serde_json::to_string(value).unwrap();
+        serde_json::from_str(&json).unwrap()
+    }
 
-    // 6. Trait Objects
-    println!("\n--- Trait Objects ---");
-    let shapes: Vec<Box<dyn Drawable>> = vec![
-        Box::new(Circle { radius: 3.0 }),
-        Box::new(Rectangle {
+    #[test]
+    fn test_container_serde() {
+        let container = Container::new(42);
+        assert_eq!(
+            serde_json::to_string(&container).unwrap(),
+            r#"{"value":42}"#
+        );
+        assert_eq!(round_trip(&container), container);
+
+        let words = Container::new(vec!["a".to_string(), "b".to_string()]);
+        assert_eq!(round_trip(&words), words);
+    }
+
+    #[test]
+    fn test_pair_serde() {
+        let pair = Pair::new("x".to_string(), 1.5);
+        assert_eq!(
+            serde_json::to_string(&pair).unwrap(),
+            r#"{"first":"x","second":1.5}"#
+        );
+        assert_eq!(round_trip(&pair), pair);
+
+        // Bounds compose: this is Serialize because each part is
+        let nested = Pair::new(Container::new(7u8), Some(Pair::new(true, 'c')));
+        assert_eq!(round_trip(&nested), nested);
+    }
+
+    #[test]
+    fn test_pair_serde_rejects_missing_field() {
+        let result: Result<Pair<String, f64>, _> = serde_json::from_str(r#"{"first":"x"}"#);
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("missing field `second`"));
+    }
+
+    #[test]
+    fn test_repository_snapshot_round_trip() {
+        let mut repo = InMemoryRepository::new();
+        let first = repo.save(Pair::new("a".to_string(), 1));
+        let last = repo.save(Pair::new("b".to_string(), 2));
+        assert!(repo.delete(last));
+
+        let snapshot = round_trip(&repo.snapshot());
+        assert_eq!(snapshot, repo.snapshot());
+
+        let mut restored = InMemoryRepository::from_snapshot(snapshot);
+        assert_eq!(
+            restored.find_by_id(first),
+            Some(Pair::new("a".to_string(), 1))
+        );
+        assert_eq!(restored.find_by_id(last), None);
+        assert_eq!(restored.save(Pair::new("c".to_string(), 3)), last + 1);
+    }
+
+    #[test]
+    fn test_file_repository_writes_snapshots() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("items.json");
+        let mut file = FileRepository::open(&path).unwrap();
+        let ids = save_three(&mut file);
+
+        let json = std::fs::read_to_string(&path).unwrap();
+        let snapshot: RepositorySnapshot<String> = serde_json::from_str(&json).unwrap();
+        let memory = InMemoryRepository::from_snapshot(snapshot);
+        assert_eq!(memory.find_by_id(ids[2]), Some("three".to_string()));
+        assert_eq!(memory.snapshot().next_id, 4);
+    }
+
+    #[test]
+    fn test_comparator() {
+        let comp = Comparator::new(10);
+        assert!(comp.is_greater_than(&5));
+        assert!(!comp.is_greater_than(&15));
+    }
+
+    #[test]
+    fn test_counter_iterator() {
+        let mut counter = Counter { count: 0, max: 3 };
+        assert_eq!(counter.next(), Some(1));
+        assert_eq!(counter.next(), Some(2));
+        assert_eq!(counter.next(), Some(3));
+        assert_eq!(counter.next(), None);
+    }
+
+    #[test]
+    fn test_converter() {
+        let wrapper = IntWrapper(42);
+        let as_string: String = wrapper.convert();
+        let as_float: f64 = wrapper.convert();
+
+        assert_eq!(as_string, "42");
+        assert_eq!(as_float, 42.0);
+    }
+
+    #[test]
+    fn test_drawable_circle() {
+        let circle = Circle { radius: 2.0 };
+        let area = circle.area();
+        assert!((area - (std::f64::consts::PI * 4.0)).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_drawable_rectangle() {
+        let rect = Rectangle {
             width: 4.0,
             height: 5.0,
-        }),
-        Box::new(Triangle {
-            base: 3.0,
-            height: 4.0,
-        }),
-    ];
-    draw_shapes(&shapes);
+        };
+        assert_eq!(rect.area(), 20.0);
+    }
 
-    let shape = create_shape("circle");
-    shape.draw();
+    #[test]
+    fn test_impl_trait() {
+        let adder = make_adder(5);
+        assert_eq!(adder(3), 8);
+        assert_eq!(adder(10), 15);
+    }
 
-    // 7. Static vs Dynamic Dispatch
-    println!("\n--- Static vs Dynamic Dispatch ---");
-    demonstrate_dispatch();
+    #[test]
+    fn test_dog_traits() {
+        let dog = Dog {
+            name: "Buddy".to_string(),
+            owner: "Alice".to_string(),
+        };
 
-    // 8. Impl Trait
-    println!("\n--- Impl Trait ---");
-    let adder = make_adder(10);
-    println!("10 + 5 = {}", adder(5));
+        assert_eq!(dog.name(), "Buddy");
+        assert_eq!(dog.owner(), "Alice");
+        assert!(dog.has_fur());
+    }
 
-    print_iter(1..4);
+    #[test]
+    fn test_meters_kilometers() {
+        let meters = Meters(5000.0);
+        let km = meters.to_kilometers();
+        assert_eq!(km.0, 5.0);
 
-    let iter = create_iterator(1, 4);
-    for item in iter {
-        println!("Iterator item: {}", item);
+        let km2 = Kilometers(3.0);
+        let m2 = km2.to_meters();
+        assert_eq!(m2.0, 3000.0);
+    }
+
+    #[test]
+    fn test_meters_arithmetic() {
+        assert_eq!(Meters(3.0) + Meters(4.0), Meters(7.0));
+        assert_eq!(Meters(10.0) - Meters(4.0), Meters(6.0));
+        assert_eq!(Meters(2.5) * 4.0, Meters(10.0));
+        assert_eq!(4.0 * Meters(2.5), Meters(10.0));
+        assert_eq!(Kilometers(1.0) + Kilometers(0.5), Kilometers(1.5));
+        assert_eq!(Kilometers(2.0) - Kilometers(0.5), Kilometers(1.5));
+        assert_eq!(Kilometers(2.0) * 3.0, Kilometers(6.0));
+    }
+
+    #[test]
+    fn test_mixed_units() {
+        assert_eq!(Meters(500.0) + Kilometers(1.5), Meters(2000.0));
+        assert!(Meters(1500.0) > Kilometers(1.0));
+        assert!(Meters(999.0) < Kilometers(1.0));
+        assert!(Meters(1000.0) == Kilometers(1.0));
+        assert!(Meters(2.0) > Meters(1.0));
+        assert!(Kilometers(1.0) < Kilometers(2.0));
+    }
+
+    #[test]
+    fn test_speed() {
+        assert_eq!(Meters(100.0) / Seconds(10.0), MetersPerSecond(10.0));
+        assert_eq!(Kilometers(3.6) / Seconds(3600.0), MetersPerSecond(1.0));
+        assert!(Seconds(1.0) < Seconds(2.0));
+    }
+
+    #[test]
+    fn test_phantom_quantities() {
+        let length = Quantity::<Length>::new(100.0) + Quantity::new(50.0);
+        let length = (length - Quantity::new(30.0)) * 0.5;
+        assert_eq!(length, Quantity::new(60.0));
+
+        let speed: Quantity<Speed> = length / Quantity::<Time>::new(12.0);
+        assert_eq!(speed.value, 5.0);
+        assert!(Quantity::<Time>::new(1.0) < Quantity::new(2.0));
+    }
+
+    #[test]
+    fn test_matrix_multiply() {
+        let a = Matrix::new([[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]]);
+        let b = Matrix::new([[7.0, 8.0], [9.0, 10.0], [11.0, 12.0]]);
+        let product: Matrix<2, 2> = a.multiply(&b);
+        assert_eq!(product, Matrix::new([[58.0, 64.0], [139.0, 154.0]]));
+
+        let column = Matrix::new([[1.0], [1.0], [1.0]]);
+        assert_eq!(a.multiply(&column), Matrix::new([[6.0], [15.0]]));
+    }
+
+    #[test]
+    fn test_matrix_identity_and_transpose() {
+        let a = Matrix::new([[1.0, 2.0], [3.0, 4.0]]);
+        assert_eq!(a.multiply(&Matrix::identity()), a);
+        assert_eq!(Matrix::<2, 2>::identity().multiply(&a), a);
+
+        let wide = Matrix::new([[1.0, 2.0, 3.0]]);
+        let tall = wide.transpose();
+        assert_eq!(tall.dimensions(), (3, 1));
+        assert_eq!(tall.get(2, 0), 3.0);
+        assert_eq!(tall.transpose(), wide);
+    }
+
+    #[test]
+    fn test_matrix_display() {
+        let m = Matrix::new([[1.0, 2.5], [0.0, -1.0]]);
+        assert_eq!(m.to_string(), "[1, 2.5]\n[0, -1]\n");
+    }
+
+    #[test]
+    fn test_ring_buffer_fifo() {
+        let mut buffer: RingBuffer<&str, 3> = RingBuffer::new();
+        assert!(buffer.is_empty());
+        assert_eq!(buffer.pop(), None);
+
+        buffer.push("a");
+        buffer.push("b");
+        assert_eq!(buffer.pop(), Some("a"));
+        buffer.push("c");
+        buffer.push("d");
+        assert!(buffer.is_full());
+        assert_eq!(
+            buffer.iter().copied().collect::<Vec<_>>(),
+            vec!["b", "c", "d"]
+        );
+        assert_eq!(buffer.pop(), Some("b"));
+        assert_eq!(buffer.len(), 2);
+    }
+
+    #[test]
+    fn test_ring_buffer_overwrites_oldest() {
+        let mut buffer: RingBuffer<u32, 2> = RingBuffer::new();
+        assert_eq!(buffer.push(1), None);
+        assert_eq!(buffer.push(2), None);
+        assert_eq!(buffer.push(3), Some(1));
+        assert_eq!(buffer.push(4), Some(2));
+
+        assert_eq!(buffer.len(), 2);
+        assert_eq!(buffer.capacity(), 2);
+        assert_eq!(buffer.pop(), Some(3));
+        assert_eq!(buffer.pop(), Some(4));
+        assert_eq!(buffer.pop(), None);
+    }
+
+    #[test]
+    fn test_ring_buffer_wraps_repeatedly() {
+        // Checked against a VecDeque doing the same by hand
+        let mut buffer: RingBuffer<usize, 4> = RingBuffer::new();
+        let mut model = std::collections::VecDeque::new();
+        for n in 0..100 {
+            let dropped = if model.len() == 4 {
+                model.pop_front()
+            } else {
+                None
+            };
+            model.push_back(n);
+            assert_eq!(buffer.push(n), dropped);
+            if n % 3 == 0 {
+                assert_eq!(buffer.pop(), model.pop_front());
+            }
+            assert!(buffer.iter().eq(model.iter()));
+        }
+    }
+
+    #[test]
+    fn test_cache() {
+        let mut cache = SimpleCache::new();
+        cache.set("key1", 100);
+        cache.set("key2", 200);
+
+        assert_eq!(cache.get(&"key1"), Some(&100));
+        assert_eq!(cache.get(&"key2"), Some(&200));
+        assert_eq!(cache.get(&"key3"), None);
+
+        assert_eq!(cache.remove(&"key1"), Some(100));
+        assert_eq!(cache.get(&"key1"), None);
+    }
+
+    #[test]
+    fn test_cache_evicts_least_recently_used() {
+        let mut cache = SimpleCache::with_capacity(2);
+        cache.set("a", 1);
+        cache.set("b", 2);
+        // Reading "a" makes "b" the least recently used
+        assert_eq!(cache.get(&"a"), Some(&1));
+        cache.set("c", 3);
+
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.get(&"b"), None);
+        assert_eq!(cache.get(&"a"), Some(&1));
+        assert_eq!(cache.get(&"c"), Some(&3));
+        assert_eq!(cache.stats().evictions, 1);
+    }
+
+    #[test]
+    fn test_cache_replacing_value_does_not_evict() {
+        let mut cache = SimpleCache::with_capacity(2);
+        cache.set("a", 1);
+        cache.set("b", 2);
+        cache.set("a", 10);
+        cache.set("c", 3);
+
+        assert_eq!(cache.get(&"a"), Some(&10));
+        assert_eq!(cache.get(&"b"), None);
+        assert_eq!(cache.stats().evictions, 1);
+    }
+
+    #[test]
+    fn test_cache_iter_most_recent_first() {
+        let mut cache = SimpleCache::with_capacity(3);
+        cache.set(1, "one");
+        cache.set(2, "two");
+        cache.set(3, "three");
+        cache.get(&1);
+
+        let keys: Vec<_> = cache.iter().map(|(key, _)| *key).collect();
+        assert_eq!(keys, vec![1, 3, 2]);
+        // Iterating isn't a lookup
+        assert_eq!(cache.stats().hits, 1);
+    }
+
+    #[test]
+    fn test_cache_remove_frees_space() {
+        let mut cache = SimpleCache::with_capacity(2);
+        cache.set("a", 1);
+        cache.set("b", 2);
+        assert_eq!(cache.remove(&"a"), Some(1));
+        assert_eq!(cache.remove(&"a"), None);
+        cache.set("c", 3);
+
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.stats().evictions, 0);
+        assert_eq!(cache.get(&"b"), Some(&2));
+    }
+
+    #[test]
+    fn test_cache_stats() {
+        let mut cache = SimpleCache::new();
+        assert_eq!(cache.stats().hit_rate(), 0.0);
+        cache.set("a", 1);
+        cache.get(&"a");
+        cache.get(&"a");
+        cache.get(&"a");
+        cache.get(&"missing");
+
+        let stats = cache.stats();
+        assert_eq!((stats.hits, stats.misses), (3, 1));
+        assert_eq!(stats.hit_rate(), 0.75);
+    }
+
+    #[test]
+    #[should_panic(expected = "Cache capacity must be at least 1")]
+    fn test_cache_zero_capacity() {
+        let _ = SimpleCache::<&str, i32>::with_capacity(0);
+    }
+
+    /// A clock that only moves when told to; clones share the same time
+    #[derive(Clone)]
+    struct FakeClock {
+        now: std::rc::Rc<std::cell::Cell<std::time::Instant>>,
+    }
+
+    impl FakeClock {
+        fn new() -> Self {
+            FakeClock {
+                now: std::rc::Rc::new(std::cell::Cell::new(std::time::Instant::now())),
+            }
+        }
+
+        fn advance(&self, seconds: u64) {
+            self.now
+                .set(self.now.get() + std::time::Duration::from_secs(seconds));
+        }
+    }
+
+    impl Clock for FakeClock {
+        fn now(&self) -> std::time::Instant {
+            self.now.get()
+        }
+    }
+
+    fn ttl_cache(
+        capacity: usize,
+        seconds: u64,
+    ) -> (
+        SimpleCache<&'static str, i32, TtlPolicy<&'static str, FakeClock>>,
+        FakeClock,
+    ) {
+        let clock = FakeClock::new();
+        let policy = TtlPolicy::with_clock(std::time::Duration::from_secs(seconds), clock.clone());
+        (SimpleCache::with_policy(capacity, policy), clock)
+    }
+
+    #[test]
+    fn test_lfu_evicts_least_frequently_used() {
+        let mut cache = SimpleCache::with_policy(2, LfuPolicy::new());
+        cache.set("a", 1);
+        cache.set("b", 2);
+        cache.get(&"a");
+        cache.get(&"a");
+        cache.get(&"b");
+        cache.set("c", 3);
+
+        assert_eq!(cache.get(&"b"), None);
+        assert_eq!(cache.get(&"a"), Some(&1));
+        assert_eq!(cache.policy().use_count(&"a"), 4);
+        assert_eq!(cache.stats().evictions, 1);
+    }
+
+    #[test]
+    fn test_lfu_ties_go_to_least_recently_used() {
+        let mut cache = SimpleCache::with_policy(2, LfuPolicy::new());
+        cache.set("a", 1);
+        cache.set("b", 2);
+        cache.get(&"b");
+        cache.get(&"a");
+
+        let keys: Vec<_> = cache.iter().map(|(key, _)| *key).collect();
+        assert_eq!(keys, vec!["a", "b"]);
+        cache.set("c", 3);
+        assert_eq!(cache.get(&"b"), None);
+    }
+
+    #[test]
+    fn test_ttl_entries_expire() {
+        let (mut cache, clock) = ttl_cache(10, 60);
+        cache.set("a", 1);
+        clock.advance(30);
+        cache.set("b", 2);
+        clock.advance(29);
+        assert_eq!(cache.get(&"a"), Some(&1));
+
+        clock.advance(1);
+        assert_eq!(cache.get(&"a"), None);
+        assert_eq!(cache.get(&"b"), Some(&2));
+        let keys: Vec<_> = cache.iter().map(|(key, _)| *key).collect();
+        assert_eq!(keys, vec!["b"]);
+
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.purge_expired(), 1);
+        assert_eq!(cache.len(), 1);
+        assert_eq!(cache.stats().expirations, 1);
+    }
+
+    #[test]
+    fn test_ttl_only_writes_extend_life() {
+        let (mut cache, clock) = ttl_cache(10, 60);
+        cache.set("read", 1);
+        cache.set("written", 2);
+        clock.advance(45);
+        cache.get(&"read");
+        cache.set("written", 20);
+        clock.advance(15);
+
+        assert_eq!(cache.get(&"read"), None);
+        assert_eq!(cache.get(&"written"), Some(&20));
+    }
+
+    #[test]
+    fn test_ttl_full_cache_drops_expired_before_evicting() {
+        let (mut cache, clock) = ttl_cache(2, 60);
+        cache.set("old", 1);
+        clock.advance(30);
+        cache.set("newer", 2);
+        clock.advance(30);
+        cache.set("newest", 3);
+
+        assert_eq!(cache.get(&"newer"), Some(&2));
+        let stats = cache.stats();
+        assert_eq!(stats.expirations, 1);
+        assert_eq!(stats.evictions, 0);
+    }
+
+    #[test]
+    fn test_ttl_evicts_soonest_to_expire() {
+        let (mut cache, clock) = ttl_cache(2, 60);
+        cache.set("a", 1);
+        cache.set("b", 2);
+        clock.advance(10);
+        cache.set("a", 10);
+        cache.set("c", 3);
+
+        assert_eq!(cache.get(&"b"), None);
+        assert_eq!(cache.get(&"a"), Some(&10));
+        assert_eq!(cache.stats().evictions, 1);
+    }
+
+    #[test]
+    fn test_repository() {
+        let mut repo = InMemoryRepository::new();
+
+        let id1 = repo.save("Item 1".to_string());
+        let id2 = repo.save("Item 2".to_string());
+
+        assert_eq!(repo.find_by_id(id1), Some("Item 1".to_string()));
+        assert_eq!(repo.find_by_id(id2), Some("Item 2".to_string()));
+
+        assert!(repo.delete(id1));
+        assert_eq!(repo.find_by_id(id1), None);
     }
 
-    // 9. Trait Inheritance
-    println!("\n--- Trait Inheritance ---");
-    let dog = Dog {
-        name: "Buddy".to_string(),
-        owner: "Alice".to_string(),
-    };
-    pet_info(&dog);
-    println!("Has fur? {}", dog.has_fur());
+    fn numbers() -> InMemoryRepository<u32> {
+        let mut repo = InMemoryRepository::new();
+        for n in [5, 3, 8, 1, 9, 2, 7] {
+            repo.save(n);
+        }
+        repo
+    }
 
-    // 10. Blanket Implementations
-    println!("\n--- Blanket Implementations ---");
-    let number = 42;
-    number.print();
-    "Hello".print();
+    #[test]
+    fn test_query_filter_keeps_save_order() {
+        let page = numbers().find(&|n: &u32| n % 2 == 1);
+        assert_eq!(page.items, vec![5, 3, 1, 9, 7]);
+        assert_eq!((page.number, page.total, page.total_pages()), (1, 5, 1));
+        assert!(!page.has_next());
+    }
 
-    // 11. Marker Traits
-    println!("\n--- Marker Traits ---");
-    let safe_data = SafeData { value: 100 };
-    let handle = process_in_thread(safe_data);
-    let _ = handle.join();
+    #[test]
+    fn test_query_sorted() {
+        let descending = (|_: &u32| true).sorted_by(|a: &u32, b: &u32| b.cmp(a));
+        assert_eq!(numbers().find(&descending).items, vec![9, 8, 7, 5, 3, 2, 1]);
+
+        // Equal keys stay in save order
+        let mut words = InMemoryRepository::new();
+        for word in ["bb", "a", "cc", "d", "aa"] {
+            words.save(word);
+        }
+        let by_length = (|_: &&str| true).sorted_by(|a: &&str, b: &&str| a.len().cmp(&b.len()));
+        assert_eq!(
+            words.find(&by_length).items,
+            vec!["a", "d", "bb", "cc", "aa"]
+        );
+    }
 
-    let value = 42;
-    let (v1, v2) = demonstrate_copy(value);
-    println!("Copied values: {} and {}", v1, v2);
+    #[test]
+    fn test_query_pages() {
+        let repo = numbers();
+        let ascending = |page| {
+            (|_: &u32| true)
+                .sorted_by(|a: &u32, b: &u32| a.cmp(b))
+                .paged(page, 3)
+        };
 
-    print_maybe_sized("This works with str!");
+        let first = repo.find(&ascending(1));
+        assert_eq!(first.items, vec![1, 2, 3]);
+        assert_eq!((first.total, first.total_pages()), (7, 3));
+        assert!(first.has_next());
 
-    // 12. Advanced Patterns
-    println!("\n--- Advanced Patterns ---");
+        let last = repo.find(&ascending(3));
+        assert_eq!(last.items, vec![9]);
+        assert!(!last.has_next());
 
-    // Newtype pattern
-    let distance = Meters(5000.0);
-    let km = distance.to_kilometers();
-    println!("Distance: {} meters = {} km", distance.0, km.0);
+        let beyond = repo.find(&ascending(4));
+        assert!(beyond.items.is_empty());
+        assert_eq!(beyond.total, 7);
+    }
 
-    let username = Username("rustacean".to_string());
-    println!("Username: {}", username);
+    #[test]
+    fn test_query_paged_before_sorted() {
+        let query = (|n: &u32| *n > 2)
+            .paged(2, 2)
+            .sorted_by(|a: &u32, b: &u32| a.cmp(b));
+        assert_eq!(numbers().find(&query).items, vec![7, 8]);
+    }
 
-    // Type state pattern
-    let door = Door::<Locked>::new();
-    let door = door.unlock();
-    door.open();
-    let door = door.lock();
+    #[test]
+    #[should_panic(expected = "Pages are numbered from 1")]
+    fn test_query_page_zero() {
+        let _ = (|_: &u32| true).paged(0, 10);
+    }
 
-    // Builder with type state
-    let person = PersonBuilder::new()
-        .name("Alice".to_string())
-        .age(30)
-        .build();
-    println!("Person: {} is {} years old", person.name, person.age);
+    #[test]
+    fn test_query_no_matches() {
+        let page = numbers().find(&(|n: &u32| *n > 100).paged(1, 5));
+        assert!(page.items.is_empty());
+        assert_eq!((page.total, page.total_pages()), (0, 0));
+        assert!(!page.has_next());
+    }
 
-    // Real world examples
-    println!("\n--- Real World Examples ---");
+    #[test]
+    fn test_query_file_repository() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("items.json");
+        let mut repo = FileRepository::open(&path).unwrap();
+        save_three(&mut repo);
+
+        let repo: FileRepository<String> = FileRepository::open(&path).unwrap();
+        let query = (|item: &String| item.contains('o'))
+            .sorted_by(|a: &String, b: &String| a.len().cmp(&b.len()).reverse());
+        let page = repo.find(&query);
+        assert_eq!(page.items, vec!["one".to_string(), "two".to_string()]);
+
+        // The same query through the trait object
+        let repos: Vec<Box<dyn Repository<String>>> = vec![Box::new(repo)];
+        assert_eq!(repos[0].find(&query).total, 2);
+    }
 
-    // Cache
-    let mut cache = SimpleCache::new();
-    cache.set("key1", "value1");
-    if let Some(value) = cache.get(&"key1") {
-        println!("Cache hit: {}", value);
+    /// Fills in any repository, whatever it stores things in
+    fn save_three(repo: &mut impl Repository<String>) -> Vec<u32> {
+        ["one", "two", "three"]
+            .into_iter()
+            .map(|item| repo.save(item.to_string()))
+            .collect()
     }
 
-    // Repository
-    let mut repo = InMemoryRepository::new();
-    let id = repo.save("Item 1");
-    if let Some(item) = repo.find_by_id(id) {
-        println!("Found item: {}", item);
+    #[test]
+    fn test_file_repository_survives_reopening() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("items.json");
+
+        let mut repo = FileRepository::open(&path).unwrap();
+        let ids = save_three(&mut repo);
+        assert!(repo.delete(ids[1]));
+
+        let repo: FileRepository<String> = FileRepository::open(&path).unwrap();
+        assert_eq!(repo.len(), 2);
+        assert_eq!(repo.find_by_id(ids[0]), Some("one".to_string()));
+        assert_eq!(repo.find_by_id(ids[1]), None);
+        assert_eq!(repo.find_by_id(ids[2]), Some("three".to_string()));
     }
 
-    // Processor
-    let processor = StringProcessor;
-    match processor.process("hello world rust".to_string()) {
-        Ok(words) => println!("Processed words: {:?}", words),
-        Err(e) => println!("Error: {}", e),
+    #[test]
+    fn test_file_repository_behaves_like_in_memory() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut file = FileRepository::open(dir.path().join("items.json")).unwrap();
+        let mut memory = InMemoryRepository::new();
+
+        assert_eq!(save_three(&mut file), save_three(&mut memory));
+        assert_eq!(file.find_by_id(2), memory.find_by_id(2));
+        assert_eq!(file.delete(2), memory.delete(2));
+        assert_eq!(file.delete(2), memory.delete(2));
     }
 
-    // Mathematic trait
-    let num1 = Number(5);
-    let num2 = Number(3);
-    let sum = num1.add(&num2);
-    let product = num1.multiply(4);
-    println!("Sum: {}, Product: {}", sum.0, product.0);
+    #[test]
+    fn test_file_repository_does_not_reuse_ids() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("items.json");
 
-    println!("\n=== All examples completed successfully! ===");
-}
+        let mut repo = FileRepository::open(&path).unwrap();
+        let first = repo.save("first".to_string());
+        assert!(repo.delete(first));
 
-// ============================================================================
-// TESTS
-// ============================================================================
+        let mut repo = FileRepository::open(&path).unwrap();
+        assert_ne!(repo.save("second".to_string()), first);
+    }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    #[test]
+    fn test_file_repository_writes_atomically() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("items.json");
+        let mut repo = FileRepository::open(&path).unwrap();
+        save_three(&mut repo);
+
+        let names: Vec<_> = std::fs::read_dir(dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect();
+        assert_eq!(names, vec!["items.json"]);
+    }
 
     #[test]
-    fn test_generic_container() {
-        let container = Container::new(42);
-        assert_eq!(*container.get(), 42);
+    fn test_file_repository_failed_write_keeps_old_state() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut repo = FileRepository::open(dir.path().join("missing/items.json")).unwrap();
 
-        let container_str = Container::new("test");
-        assert_eq!(*container_str.get(), "test");
+        assert!(repo.try_save("lost".to_string()).is_err());
+        assert_eq!(repo.len(), 0);
+        assert_eq!(repo.find_by_id(1), None);
     }
 
     #[test]
-    fn test_comparator() {
-        let comp = Comparator::new(10);
-        assert!(comp.is_greater_than(&5));
-        assert!(!comp.is_greater_than(&15));
+    fn test_file_repository_rejects_corrupt_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("items.json");
+        std::fs::write(&path, "{ not json").unwrap();
+
+        let err = FileRepository::<String>::open(&path).err().unwrap();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    }
+
+    /// A handler that records every event it's given
+    fn recorder<E: Clone + 'static>() -> (
+        std::rc::Rc<std::cell::RefCell<Vec<E>>>,
+        impl FnMut(&E) + 'static,
+    ) {
+        let events = std::rc::Rc::new(std::cell::RefCell::new(Vec::new()));
+        let handler = {
+            let events = std::rc::Rc::clone(&events);
+            move |event: &E| events.borrow_mut().push(event.clone())
+        };
+        (events, handler)
     }
 
     #[test]
-    fn test_counter_iterator() {
-        let mut counter = Counter { count: 0, max: 3 };
-        assert_eq!(counter.next(), Some(1));
-        assert_eq!(counter.next(), Some(2));
-        assert_eq!(counter.next(), Some(3));
-        assert_eq!(counter.next(), None);
+    fn test_event_bus_dispatches_by_type() {
+        let mut bus = EventBus::new();
+        let (created, on_created) = recorder::<UserCreated>();
+        let (deleted, on_deleted) = recorder::<UserDeleted>();
+        bus.subscribe(on_created);
+        bus.subscribe(on_deleted);
+
+        let alice = UserCreated {
+            id: 1,
+            username: "alice".to_string(),
+        };
+        assert_eq!(bus.publish(&alice), 1);
+        assert_eq!(bus.publish(&UserDeleted { id: 1 }), 1);
+
+        assert_eq!(*created.borrow(), vec![alice]);
+        assert_eq!(*deleted.borrow(), vec![UserDeleted { id: 1 }]);
     }
 
     #[test]
-    fn test_converter() {
-        let wrapper = IntWrapper(42);
-        let as_string: String = wrapper.convert();
-        let as_float: f64 = wrapper.convert();
+    fn test_event_bus_calls_handlers_in_subscription_order() {
+        let mut bus = EventBus::new();
+        let calls = std::rc::Rc::new(std::cell::RefCell::new(Vec::new()));
+        for name in ["first", "second", "third"] {
+            let calls = std::rc::Rc::clone(&calls);
+            bus.subscribe(move |_: &UserDeleted| calls.borrow_mut().push(name));
+        }
 
-        assert_eq!(as_string, "42");
-        assert_eq!(as_float, 42.0);
+        assert_eq!(bus.publish(&UserDeleted { id: 1 }), 3);
+        assert_eq!(*calls.borrow(), vec!["first", "second", "third"]);
     }
 
     #[test]
-    fn test_drawable_circle() {
-        let circle = Circle { radius: 2.0 };
-        let area = circle.area();
-        assert!((area - (std::f64::consts::PI * 4.0)).abs() < 0.001);
+    fn test_event_bus_unsubscribe() {
+        let mut bus = EventBus::new();
+        let (kept, keep) = recorder::<UserDeleted>();
+        let (dropped, drop_me) = recorder::<UserDeleted>();
+        bus.subscribe(keep);
+        let token = bus.subscribe(drop_me);
+
+        assert!(bus.unsubscribe(token));
+        assert!(!bus.unsubscribe(token));
+        assert_eq!(bus.handler_count::<UserDeleted>(), 1);
+
+        bus.publish(&UserDeleted { id: 7 });
+        assert_eq!(kept.borrow().len(), 1);
+        assert!(dropped.borrow().is_empty());
     }
 
     #[test]
-    fn test_drawable_rectangle() {
-        let rect = Rectangle {
-            width: 4.0,
-            height: 5.0,
+    fn test_event_bus_without_handlers() {
+        let mut bus = EventBus::new();
+        let (_, on_created) = recorder::<UserCreated>();
+        let token = bus.subscribe(on_created);
+
+        assert_eq!(bus.publish(&UserDeleted { id: 1 }), 0);
+        assert_eq!(bus.publish(&"not an event"), 0);
+        assert_eq!(bus.handler_count::<UserDeleted>(), 0);
+        // Tokens are only good for the type they were issued for
+        let other = SubscriptionToken {
+            event: std::any::TypeId::of::<UserDeleted>(),
+            id: token.id,
         };
-        assert_eq!(rect.area(), 20.0);
+        assert!(!bus.unsubscribe(other));
+    }
+
+    fn registry() -> PluginRegistry {
+        let mut registry = PluginRegistry::new();
+        registry.register(Box::new(UppercasePlugin)).unwrap();
+        registry.register(Box::new(GreetingPlugin)).unwrap();
+        registry.register(Box::new(WordCountPlugin::new())).unwrap();
+        registry
     }
 
     #[test]
-    fn test_impl_trait() {
-        let adder = make_adder(5);
-        assert_eq!(adder(3), 8);
-        assert_eq!(adder(10), 15);
+    fn test_plugin_registry_runs_in_priority_order() {
+        let registry = registry();
+        assert_eq!(
+            registry.list(),
+            vec!["word-count 2.1.0", "uppercase 1.0.0", "greeting 0.3.0"]
+        );
+
+        let context = Context::new("hi there").with_setting("greeting", "Hello");
+        let results = registry.execute_all(&context);
+        assert_eq!(
+            results,
+            vec![
+                ("word-count", Ok("2 words".to_string())),
+                ("uppercase", Ok("HI THERE".to_string())),
+                ("greeting", Ok("Hello, hi there".to_string())),
+            ]
+        );
     }
 
     #[test]
-    fn test_dog_traits() {
-        let dog = Dog {
-            name: "Buddy".to_string(),
-            owner: "Alice".to_string(),
-        };
+    fn test_plugin_failure_does_not_stop_others() {
+        let registry = registry();
+        let results = registry.execute_all(&Context::new("hi"));
+
+        assert_eq!(results.len(), 3);
+        assert_eq!(
+            results[2],
+            ("greeting", Err("Missing setting: greeting".to_string()))
+        );
+        assert!(results[..2].iter().all(|(_, result)| result.is_ok()));
+    }
 
-        assert_eq!(dog.name(), "Buddy");
-        assert_eq!(dog.owner(), "Alice");
-        assert!(dog.has_fur());
+    #[test]
+    fn test_plugin_registry_rejects_duplicate_names() {
+        let mut registry = registry();
+        assert_eq!(
+            registry.register(Box::new(UppercasePlugin)),
+            Err("Plugin already registered: uppercase".to_string())
+        );
+        assert_eq!(registry.list().len(), 3);
     }
 
     #[test]
-    fn test_meters_kilometers() {
-        let meters = Meters(5000.0);
-        let km = meters.to_kilometers();
-        assert_eq!(km.0, 5.0);
+    fn test_plugin_lookup_and_downcast() {
+        let registry = registry();
+        assert_eq!(registry.get("uppercase").unwrap().version(), "1.0.0");
+        assert!(registry.get("missing").is_none());
+
+        registry.execute_all(&Context::new("one"));
+        registry.execute_all(&Context::new("two"));
+        let counter = registry.get_as::<WordCountPlugin>("word-count").unwrap();
+        assert_eq!(counter.runs(), 2);
+
+        assert!(registry.get_as::<UppercasePlugin>("word-count").is_none());
+        assert!(registry.get_as::<WordCountPlugin>("missing").is_none());
+    }
 
-        let km2 = Kilometers(3.0);
-        let m2 = km2.to_meters();
-        assert_eq!(m2.0, 3000.0);
+    #[tokio::test]
+    async fn test_async_repository() {
+        let repo = TokioRepository::new();
+        let id = repo.save("Item".to_string()).await;
+
+        assert_eq!(repo.find_by_id(id).await, Some("Item".to_string()));
+        assert!(repo.delete(id).await);
+        assert!(!repo.delete(id).await);
+        assert_eq!(repo.find_by_id(id).await, None);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_async_repository_trait_object_across_tasks() {
+        let repo: std::sync::Arc<dyn AsyncRepository<u32>> =
+            std::sync::Arc::new(TokioRepository::new());
+        let tasks: Vec<_> = (0..50)
+            .map(|n| {
+                let repo = std::sync::Arc::clone(&repo);
+                tokio::spawn(async move { repo.save(n).await })
+            })
+            .collect();
+
+        let mut ids = Vec::new();
+        for task in tasks {
+            ids.push(task.await.unwrap());
+        }
+        ids.sort();
+        ids.dedup();
+        assert_eq!(ids.len(), 50);
+    }
+
+    #[tokio::test]
+    async fn test_async_repository_boxed_backends() {
+        let backends: Vec<Box<dyn AsyncRepository<&str>>> = vec![
+            Box::new(TokioRepository::new()),
+            Box::new(TokioRepository::new()),
+        ];
+        for repo in &backends {
+            let id = repo.save("same").await;
+            assert_eq!(repo.find_by_id(id).await, Some("same"));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_async_cache() {
+        let cache = TokioCache::with_capacity(2);
+        cache.set("a", 1).await;
+        cache.set("b", 2).await;
+        assert_eq!(cache.get(&"a").await, Some(1));
+        cache.set("c", 3).await;
+
+        assert_eq!(cache.get(&"b").await, None);
+        assert_eq!(cache.remove(&"a").await, Some(1));
+        assert_eq!(cache.stats().await.evictions, 1);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_async_cache_futures_are_send() {
+        let cache = std::sync::Arc::new(TokioCache::with_capacity(10));
+        let writer = {
+            let cache = std::sync::Arc::clone(&cache);
+            tokio::spawn(async move { cache.set(1, "one".to_string()).await })
+        };
+        writer.await.unwrap();
+        assert_eq!(cache.get(&1).await, Some("one".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_cached_find_falls_back_to_repository() {
+        let repo = TokioRepository::new();
+        let id = repo.save("Item".to_string()).await;
+        let cache = TokioCache::with_capacity(10);
+
+        assert_eq!(
+            cached_find(&cache, &repo, id).await,
+            Some("Item".to_string())
+        );
+        assert!(repo.delete(id).await);
+        // Still served by the cache after the repository lost it
+        assert_eq!(
+            cached_find(&cache, &repo, id).await,
+            Some("Item".to_string())
+        );
+        assert_eq!(cached_find(&cache, &repo, 99).await, None);
+
+        let stats = cache.stats().await;
+        assert_eq!((stats.hits, stats.misses), (1, 2));
     }
 
     #[test]
-    fn test_cache() {
-        let mut cache = SimpleCache::new();
-        cache.set("key1", 100);
-        cache.set("key2", 200);
+    fn test_processor_then_and_map_output() {
+        let count_words = StringProcessor.map_output(|words| words.len());
+        assert_eq!(count_words.process("a b c".to_string()), Ok(3));
+        assert_eq!(
+            count_words.process(String::new()),
+            Err("Empty input".to_string())
+        );
 
-        assert_eq!(cache.get(&"key1"), Some(&100));
-        assert_eq!(cache.get(&"key2"), Some(&200));
-        assert_eq!(cache.get(&"key3"), None);
+        let counts = StringProcessor.then(WordCounter);
+        assert_eq!(
+            counts.process("b a b".to_string()),
+            Ok(vec![("b".to_string(), 2), ("a".to_string(), 1)])
+        );
+        // An error from the second stage comes through too
+        assert_eq!(
+            counts.process("   ".to_string()),
+            Err("No words".to_string())
+        );
+    }
 
-        assert_eq!(cache.remove(&"key1"), Some(100));
-        assert_eq!(cache.get(&"key1"), None);
+    #[test]
+    fn test_word_frequency_pipeline() {
+        let counts = word_frequency_pipeline()
+            .process("The cat saw the dog. The dog ran!".to_string())
+            .unwrap();
+        assert_eq!(
+            counts,
+            vec![
+                ("the".to_string(), 3),
+                ("dog".to_string(), 2),
+                ("cat".to_string(), 1),
+                ("ran".to_string(), 1),
+                ("saw".to_string(), 1),
+            ]
+        );
+        assert_eq!(
+            word_frequency_pipeline().process("-- ...".to_string()),
+            Err("No words".to_string())
+        );
     }
 
     #[test]
-    fn test_repository() {
-        let mut repo = InMemoryRepository::new();
+    fn test_processor_retry() {
+        let retried = FlakyProcessor::new(2).retry(3);
+        assert_eq!(retried.process("ok".to_string()), Ok("ok".to_string()));
+
+        let retried = FlakyProcessor::new(3).retry(3);
+        assert_eq!(
+            retried.process("ok".to_string()),
+            Err("Service unavailable".to_string())
+        );
+        // The failures are used up, so the next call succeeds first time
+        assert_eq!(retried.process("ok".to_string()), Ok("ok".to_string()));
+    }
 
-        let id1 = repo.save("Item 1".to_string());
-        let id2 = repo.save("Item 2".to_string());
+    #[test]
+    #[should_panic(expected = "Retry needs at least one attempt")]
+    fn test_processor_retry_zero_attempts() {
+        let _ = StringProcessor.retry(0);
+    }
 
-        assert_eq!(repo.find_by_id(id1), Some("Item 1".to_string()));
-        assert_eq!(repo.find_by_id(id2), Some("Item 2".to_string()));
+    #[test]
+    fn test_processor_parallel() {
+        let lengths = StringProcessor.map_output(|words| words.len()).parallel();
+        let inputs: Vec<String> = (1..=100).map(|n| "word ".repeat(n)).collect();
+        assert_eq!(lengths.process(inputs), Ok((1..=100).collect()));
 
-        assert!(repo.delete(id1));
-        assert_eq!(repo.find_by_id(id1), None);
+        let inputs = vec!["fine".to_string(), String::new(), "also fine".to_string()];
+        assert_eq!(lengths.process(inputs), Err("Empty input".to_string()));
     }
 
     #[test]
@@ -1139,6 +4210,148 @@ mod tests {
         assert_eq!(person.age, 25);
     }
 
+    #[test]
+    fn test_request_builder() {
+        let request = RequestBuilder::new(Method::Put)
+            .url("https://example.com/items/1")
+            .header("Accept", "application/json")
+            .body("{}")
+            .send();
+
+        assert_eq!(request.method, Method::Put);
+        assert_eq!(request.url, "https://example.com/items/1");
+        assert_eq!(request.body.as_deref(), Some("{}"));
+        assert_eq!(request.header("accept"), Some("application/json"));
+        assert_eq!(request.header("Content-Length"), Some("2"));
+    }
+
+    #[test]
+    fn test_request_builder_headers_in_any_state() {
+        let request = RequestBuilder::new(Method::Get)
+            .header("A", "1")
+            .url("/")
+            .header("B", "2")
+            .no_body()
+            .header("C", "3")
+            .send();
+
+        let names: Vec<_> = request
+            .headers
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect();
+        assert_eq!(names, vec!["A", "B", "C"]);
+        assert_eq!(request.body, None);
+        assert_eq!(request.header("Content-Length"), None);
+    }
+
+    #[test]
+    fn test_method_display() {
+        assert_eq!(Method::Get.to_string(), "GET");
+        assert_eq!(Method::Delete.to_string(), "DELETE");
+    }
+
+    #[test]
+    fn test_windows_mut() {
+        let mut data = [1, 2, 3, 4];
+        let mut windows = WindowsMut::new(&mut data, 2);
+        assert_eq!(windows.next(), Some(&mut [1, 2][..]));
+
+        let second = windows.next().unwrap();
+        second[1] = 30;
+        assert_eq!(windows.next(), Some(&mut [30, 4][..]));
+        assert_eq!(windows.next(), None);
+        assert_eq!(data, [1, 2, 30, 4]);
+    }
+
+    #[test]
+    fn test_windows_mut_changes_carry_forward() {
+        let mut totals = [1, 2, 3, 4, 5];
+        let mut windows = WindowsMut::new(&mut totals, 2);
+        while let Some([previous, current]) = windows.next() {
+            *current += *previous;
+        }
+        assert_eq!(totals, [1, 3, 6, 10, 15]);
+    }
+
+    #[test]
+    fn test_windows_mut_sizes() {
+        let mut data = [1, 2, 3];
+        assert_eq!(count_items(WindowsMut::new(&mut data, 1)), 3);
+        assert_eq!(count_items(WindowsMut::new(&mut data, 3)), 1);
+        assert_eq!(count_items(WindowsMut::new(&mut data, 4)), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Window size must be at least 1")]
+    fn test_windows_mut_zero_size() {
+        let _ = WindowsMut::new(&mut [1, 2, 3], 0);
+    }
+
+    #[test]
+    fn test_lend_matches_iterator2() {
+        let mut lent = Lend(Counter { count: 0, max: 3 });
+        assert_eq!(lent.next(), Some(1));
+        assert_eq!(lent.next(), Some(2));
+        assert_eq!(lent.next(), Some(3));
+        assert_eq!(lent.next(), None);
+        assert_eq!(count_items(Lend(Counter { count: 0, max: 7 })), 7);
+    }
+
+    fn visitor_shapes() -> Vec<Box<dyn Drawable>> {
+        vec![
+            Box::new(Circle { radius: 1.0 }),
+            Box::new(Rectangle {
+                width: 4.0,
+                height: 2.0,
+            }),
+            Box::new(Triangle {
+                base: 3.0,
+                height: 4.0,
+            }),
+        ]
+    }
+
+    #[test]
+    fn test_area_summer_matches_area() {
+        let shapes = visitor_shapes();
+        let mut summer = AreaSummer::default();
+        for shape in &shapes {
+            shape.accept(&mut summer);
+        }
+
+        let expected: f64 = shapes.iter().map(|shape| shape.area()).sum();
+        assert!((summer.total - expected).abs() < 1e-10);
+        assert!((summer.total - (std::f64::consts::PI + 8.0 + 6.0)).abs() < 1e-10);
+    }
+
+    #[test]
+    fn test_svg_exporter() {
+        let mut svg = SvgExporter::default();
+        for shape in &visitor_shapes() {
+            shape.accept(&mut svg);
+        }
+
+        assert_eq!(
+            svg.finish(),
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"29\" height=\"4\">\n\
+             <g transform=\"translate(0 4) scale(1 -1)\">\n\
+             \x20\x20<circle cx=\"1\" cy=\"1\" r=\"1\"/>\n\
+             \x20\x20<rect x=\"12\" y=\"0\" width=\"4\" height=\"2\"/>\n\
+             \x20\x20<polygon points=\"26,0 29,0 27.5,4\"/>\n\
+             </g>\n</svg>\n"
+        );
+    }
+
+    #[test]
+    fn test_svg_exporter_empty() {
+        assert_eq!(
+            SvgExporter::default().finish(),
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"0\" height=\"0\">\n\
+             <g transform=\"translate(0 0) scale(1 -1)\">\n</g>\n</svg>\n"
+        );
+    }
+
     #[test]
     fn test_slice_phantom() {
         let data = vec![1, 2, 3, 4, 5];
@@ -5,7 +5,7 @@
 //! This file demonstrates how to use Criterion for benchmarking Rust code.
 //! Criterion provides statistical analysis and detailed reports.
 
-use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
 use testing::*;
 
 // ============================================================================
@@ -13,7 +13,7 @@ use testing::*;
 // ============================================================================
 
 fn benchmark_calculator_add(c: &mut Criterion) {
-    let calc = Calculator::new();
+    let mut calc = Calculator::new();
 
     c.bench_function("calculator_add", |b| {
         b.iter(|| black_box(calc.add(black_box(100), black_box(200))))
@@ -21,7 +21,7 @@ fn benchmark_calculator_add(c: &mut Criterion) {
 }
 
 fn benchmark_calculator_multiply(c: &mut Criterion) {
-    let calc = Calculator::new();
+    let mut calc = Calculator::new();
 
     c.bench_function("calculator_multiply", |b| {
         b.iter(|| black_box(calc.multiply(black_box(123), black_box(456))))
@@ -29,7 +29,7 @@ fn benchmark_calculator_multiply(c: &mut Criterion) {
 }
 
 fn benchmark_calculator_divide(c: &mut Criterion) {
-    let calc = Calculator::new();
+    let mut calc = Calculator::new();
 
     c.bench_function("calculator_divide", |b| {
         b.iter(|| black_box(calc.divide(black_box(1000), black_box(7))))
@@ -37,13 +37,31 @@ fn benchmark_calculator_divide(c: &mut Criterion) {
 }
 
 fn benchmark_calculator_factorial(c: &mut Criterion) {
-    let calc = Calculator::new();
-
     let mut group = c.benchmark_group("factorial");
 
     for n in [5, 10, 15, 20].iter() {
         group.bench_with_input(BenchmarkId::from_parameter(n), n, |b, &n| {
-            b.iter(|| black_box(calc.factorial(black_box(n))));
+            b.iter(|| black_box(Calculator::factorial(black_box(n))));
+        });
+    }
+
+    group.finish();
+}
+
+/// Both factorials side by side for each `n`. The throughput is set to `n`
+/// multiplications, so the report shows each as multiplications per second.
+/// In release builds the optimizer usually turns the recursion into a loop
+/// and the two come out close; measuring is how to find out.
+fn benchmark_factorial_iterative_vs_recursive(c: &mut Criterion) {
+    let mut group = c.benchmark_group("factorial_iterative_vs_recursive");
+
+    for n in [5u32, 10, 15, 20] {
+        group.throughput(Throughput::Elements(n as u64));
+        group.bench_with_input(BenchmarkId::new("recursive", n), &n, |b, &n| {
+            b.iter(|| black_box(Calculator::factorial(black_box(n))))
+        });
+        group.bench_with_input(BenchmarkId::new("iterative", n), &n, |b, &n| {
+            b.iter(|| black_box(Calculator::factorial_iterative(black_box(n))))
         });
     }
 
@@ -57,7 +75,7 @@ fn benchmark_calculator_factorial(c: &mut Criterion) {
 fn benchmark_user_service_create(c: &mut Criterion) {
     c.bench_function("user_service_create", |b| {
         b.iter_batched(
-            || UserService::new(),
+            UserService::new,
             |mut service| {
                 black_box(service.create_user(
                     black_box("testuser".to_string()),
@@ -107,7 +125,7 @@ fn benchmark_user_service_find_by_username(c: &mut Criterion) {
 fn benchmark_bank_account_deposit(c: &mut Criterion) {
     c.bench_function("bank_account_deposit", |b| {
         b.iter_batched(
-            || BankAccount::new(),
+            BankAccount::new,
             |mut account| black_box(account.deposit(black_box(100.0))),
             criterion::BatchSize::SmallInput,
         )
@@ -165,7 +183,7 @@ fn benchmark_reverse_string(c: &mut Criterion) {
     let mut group = c.benchmark_group("reverse_string");
 
     let long_string = "a".repeat(1000);
-    let test_strings = vec![
+    let test_strings = [
         ("short", "hello"),
         ("medium", "The quick brown fox jumps over the lazy dog"),
         ("long", long_string.as_str()),
@@ -183,7 +201,7 @@ fn benchmark_reverse_string(c: &mut Criterion) {
 fn benchmark_is_palindrome(c: &mut Criterion) {
     let mut group = c.benchmark_group("is_palindrome");
 
-    let test_cases = vec![
+    let test_cases = [
         ("short_true", "racecar"),
         ("short_false", "hello"),
         ("medium_true", "A man a plan a canal Panama"),
@@ -199,6 +217,23 @@ fn benchmark_is_palindrome(c: &mut Criterion) {
     group.finish();
 }
 
+/// Palindromes of growing length, measured in bytes per second. A steady
+/// rate means the check is linear in the input.
+fn benchmark_is_palindrome_throughput(c: &mut Criterion) {
+    let mut group = c.benchmark_group("is_palindrome_throughput");
+
+    for len in [16, 256, 4096] {
+        let half = "ab".repeat(len / 4);
+        let s = format!("{}{}", half, half.chars().rev().collect::<String>());
+        group.throughput(Throughput::Bytes(s.len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(len), &s, |b, s| {
+            b.iter(|| black_box(is_palindrome(black_box(s))))
+        });
+    }
+
+    group.finish();
+}
+
 // ============================================================================
 // BENCHMARK 5: TEMPERATURE CONVERSIONS
 // ============================================================================
@@ -247,6 +282,30 @@ fn benchmark_find_max_min(c: &mut Criterion) {
     group.finish();
 }
 
+/// `find_max` in elements per second, on ascending input (a new maximum
+/// at every step) and descending input (the first element wins)
+fn benchmark_find_max_throughput(c: &mut Criterion) {
+    let mut group = c.benchmark_group("find_max_throughput");
+
+    for size in [100, 10_000, 1_000_000] {
+        let ascending: Vec<i32> = (0..size).collect();
+        let descending: Vec<i32> = (0..size).rev().collect();
+        group.throughput(Throughput::Elements(size as u64));
+        group.bench_with_input(
+            BenchmarkId::new("ascending", size),
+            &ascending,
+            |b, nums| b.iter(|| black_box(find_max(black_box(nums)))),
+        );
+        group.bench_with_input(
+            BenchmarkId::new("descending", size),
+            &descending,
+            |b, nums| b.iter(|| black_box(find_max(black_box(nums)))),
+        );
+    }
+
+    group.finish();
+}
+
 // ============================================================================
 // BENCHMARK 7: DATA PROCESSOR
 // ============================================================================
@@ -280,7 +339,7 @@ fn benchmark_complex_workflow(c: &mut Criterion) {
     c.bench_function("complex_workflow", |b| {
         b.iter_batched(
             || (UserService::new(), BankAccount::new(), Calculator::new()),
-            |(mut service, mut account, calc)| {
+            |(mut service, mut account, mut calc)| {
                 // Create user
                 let user_id = service
                     .create_user("john".to_string(), "john@example.com".to_string(), 30)
@@ -359,7 +418,7 @@ fn benchmark_user_validation(c: &mut Criterion) {
 // ============================================================================
 
 fn benchmark_batch_calculator_operations(c: &mut Criterion) {
-    let calc = Calculator::new();
+    let mut calc = Calculator::new();
 
     c.bench_function("batch_calculator_operations", |b| {
         b.iter(|| {
@@ -410,7 +469,8 @@ criterion_group!(
     benchmark_calculator_add,
     benchmark_calculator_multiply,
     benchmark_calculator_divide,
-    benchmark_calculator_factorial
+    benchmark_calculator_factorial,
+    benchmark_factorial_iterative_vs_recursive
 );
 
 criterion_group!(
@@ -430,12 +490,17 @@ criterion_group!(
 criterion_group!(
     string_benches,
     benchmark_reverse_string,
-    benchmark_is_palindrome
+    benchmark_is_palindrome,
+    benchmark_is_palindrome_throughput
 );
 
 criterion_group!(temperature_benches, benchmark_temperature_conversions);
 
-criterion_group!(vector_benches, benchmark_find_max_min);
+criterion_group!(
+    vector_benches,
+    benchmark_find_max_min,
+    benchmark_find_max_throughput
+);
 
 criterion_group!(processor_benches, benchmark_file_processor);
 
@@ -0,0 +1,26 @@
+//! Feeds arbitrary text to `Calculator::evaluate`
+//!
+//! The parser must never panic, whatever it's given: every bad input has
+//! an `ExprError`, nesting past the depth limit included, and overflow is
+//! reported rather than wrapping. As an extra check, a successful result
+//! must evaluate back to itself when written out as an expression.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use testing::Calculator;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(expression) = std::str::from_utf8(data) else {
+        return;
+    };
+    let mut calc = Calculator::new();
+    let Ok(value) = calc.evaluate(expression) else {
+        return;
+    };
+    // i32::MIN can't be written as a literal: 2147483648 is too large
+    // before the minus applies to it
+    if value != i32::MIN {
+        assert_eq!(calc.evaluate(&value.to_string()), Ok(value));
+    }
+});
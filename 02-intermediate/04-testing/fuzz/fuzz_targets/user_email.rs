@@ -0,0 +1,38 @@
+//! Feeds arbitrary details to `User::new` and `UserService::create_user`
+//!
+//! The input is read as three lines: username, email and age, so the seed
+//! files stay readable. Validation must never panic; it must accept
+//! exactly the users that meet the documented rules; and an accepted user
+//! must survive a JSON round trip, whatever characters it holds.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use testing::{User, UserService};
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(text) = std::str::from_utf8(data) else {
+        return;
+    };
+    let mut lines = text.splitn(3, '\n');
+    let username = lines.next().unwrap_or_default().to_string();
+    let email = lines.next().unwrap_or_default().to_string();
+    let age = lines
+        .next()
+        .and_then(|age| age.trim().parse().ok())
+        .unwrap_or(0);
+
+    let valid = !username.is_empty() && email.contains('@') && age >= 13;
+    let result = User::new(1, username.clone(), email.clone(), age);
+    assert_eq!(result.is_ok(), valid, "{:?}", result);
+    let Ok(user) = result else {
+        return;
+    };
+
+    let json = serde_json::to_string(&user).unwrap();
+    assert_eq!(serde_json::from_str::<User>(&json).unwrap(), user);
+
+    let mut service = UserService::new();
+    let id = service.create_user(username.clone(), email, age).unwrap();
+    assert_eq!(service.find_by_username(&username).map(|u| u.id), Some(id));
+});
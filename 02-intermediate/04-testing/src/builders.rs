@@ -0,0 +1,299 @@
+//! Test data builders and object mothers
+//!
+//! Most tests need a user or an account but only care about one or two of
+//! its details. Spelling out every argument to `User::new` hides which
+//! ones matter, and changing a constructor means editing every test that
+//! calls it. A builder fills in sensible defaults, so a test names only
+//! what it depends on:
+//!
+//! ```
+//! use testing::builders::{AccountBuilder, UserBuilder};
+//!
+//! let kid = UserBuilder::new().age(15).build();
+//! assert!(!kid.is_adult());
+//!
+//! let mut account = AccountBuilder::new().balance(50.0).overdraft_limit(25.0).build();
+//! account.withdraw(75.0).unwrap();
+//! assert_eq!(account.balance(), -25.0);
+//! ```
+//!
+//! The object mothers at the bottom of this module are the users and
+//! accounts tests ask for most often, given names. They return builders,
+//! so a test can still override a detail: `teenager().username("kim")`.
+
+use crate::{BankAccount, Clock, User, UserService};
+
+/// Builds a [`User`]: by default id 1, `alice`, `alice@test.com`, aged 30
+///
+/// The email follows the username unless it's set explicitly.
+#[derive(Debug, Clone)]
+pub struct UserBuilder {
+    id: u64,
+    username: String,
+    email: Option<String>,
+    age: u8,
+}
+
+impl UserBuilder {
+    pub fn new() -> Self {
+        UserBuilder {
+            id: 1,
+            username: "alice".to_string(),
+            email: None,
+            age: 30,
+        }
+    }
+
+    pub fn id(mut self, id: u64) -> Self {
+        self.id = id;
+        self
+    }
+
+    pub fn username(mut self, username: &str) -> Self {
+        self.username = username.to_string();
+        self
+    }
+
+    pub fn email(mut self, email: &str) -> Self {
+        self.email = Some(email.to_string());
+        self
+    }
+
+    pub fn age(mut self, age: u8) -> Self {
+        self.age = age;
+        self
+    }
+
+    /// Panics if the details don't make a valid user; use `try_build` to
+    /// test the validation itself
+    pub fn build(self) -> User {
+        self.try_build()
+            .unwrap_or_else(|e| panic!("UserBuilder made an invalid user: {}", e))
+    }
+
+    pub fn try_build(self) -> Result<User, String> {
+        let email = self.email_or_default();
+        User::new(self.id, self.username, email, self.age)
+    }
+
+    /// Adds the user to `service` and returns the id it was given; the
+    /// builder's own id is ignored
+    ///
+    /// Panics if the service refuses the user.
+    pub fn create_in(self, service: &mut UserService) -> u64 {
+        let email = self.email_or_default();
+        service
+            .create_user(self.username, email, self.age)
+            .unwrap_or_else(|e| panic!("UserService refused the user: {}", e))
+    }
+
+    fn email_or_default(&self) -> String {
+        self.email
+            .clone()
+            .unwrap_or_else(|| format!("{}@test.com", self.username))
+    }
+}
+
+impl Default for UserBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Builds a [`BankAccount`]: by default empty, with no overdraft, on the
+/// system clock
+pub struct AccountBuilder {
+    balance: f64,
+    overdraft_limit: f64,
+    recurring_interest: Option<(f64, u64)>,
+    clock: Option<Box<dyn Clock>>,
+}
+
+impl AccountBuilder {
+    pub fn new() -> Self {
+        AccountBuilder {
+            balance: 0.0,
+            overdraft_limit: 0.0,
+            recurring_interest: None,
+            clock: None,
+        }
+    }
+
+    /// Opens the account with one deposit of `balance`, or one withdrawal
+    /// if it's negative, which needs an overdraft limit to cover it
+    pub fn balance(mut self, balance: f64) -> Self {
+        self.balance = balance;
+        self
+    }
+
+    pub fn overdraft_limit(mut self, limit: f64) -> Self {
+        self.overdraft_limit = limit;
+        self
+    }
+
+    pub fn recurring_interest(mut self, rate: f64, period: u64) -> Self {
+        self.recurring_interest = Some((rate, period));
+        self
+    }
+
+    /// Timestamps the account's transactions with `clock` instead of the
+    /// system clock
+    pub fn clock(mut self, clock: Box<dyn Clock>) -> Self {
+        self.clock = Some(clock);
+        self
+    }
+
+    /// Panics if the settings are invalid, as `BankAccount`'s own builder
+    /// methods do, or the opening balance is past the overdraft limit
+    pub fn build(self) -> BankAccount {
+        let mut account = match self.clock {
+            Some(clock) => BankAccount::with_clock(clock),
+            None => BankAccount::new(),
+        }
+        .with_overdraft_limit(self.overdraft_limit);
+        if let Some((rate, period)) = self.recurring_interest {
+            account = account.with_recurring_interest(rate, period);
+        }
+
+        let opened = if self.balance > 0.0 {
+            account.deposit(self.balance)
+        } else if self.balance < 0.0 {
+            account.withdraw(-self.balance)
+        } else {
+            Ok(())
+        };
+        opened.unwrap_or_else(|e| {
+            panic!("AccountBuilder couldn't open with {}: {}", self.balance, e)
+        });
+        account
+    }
+}
+
+impl Default for AccountBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// ============================================================================
+// OBJECT MOTHERS
+// ============================================================================
+
+/// Aged 30
+pub fn adult() -> UserBuilder {
+    UserBuilder::new()
+}
+
+/// Aged 15: old enough to sign up, but not an adult
+pub fn teenager() -> UserBuilder {
+    UserBuilder::new().username("teen").age(15)
+}
+
+/// Aged 12: a year too young to sign up
+pub fn child() -> UserBuilder {
+    UserBuilder::new().username("kid").age(12)
+}
+
+/// Holds $100, with no overdraft
+pub fn funded_account() -> AccountBuilder {
+    AccountBuilder::new().balance(100.0)
+}
+
+/// $100 overdrawn against a $100 limit, so nothing more can be withdrawn
+pub fn overdrawn_account() -> AccountBuilder {
+    AccountBuilder::new().overdraft_limit(100.0).balance(-100.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MockClock;
+
+    #[test]
+    fn user_defaults() {
+        let user = UserBuilder::new().build();
+        assert_eq!(user.id, 1);
+        assert_eq!(user.username, "alice");
+        assert_eq!(user.email, "alice@test.com");
+        assert_eq!(user.age, 30);
+    }
+
+    #[test]
+    fn email_follows_username_unless_set() {
+        assert_eq!(
+            UserBuilder::new().username("bob").build().email,
+            "bob@test.com"
+        );
+        let user = UserBuilder::new()
+            .username("bob")
+            .email("robert@example.com")
+            .build();
+        assert_eq!(user.email, "robert@example.com");
+    }
+
+    #[test]
+    fn try_build_reports_invalid_users() {
+        assert_eq!(
+            child().try_build().unwrap_err(),
+            "User must be at least 13 years old"
+        );
+        assert_eq!(
+            UserBuilder::new().email("nowhere").try_build().unwrap_err(),
+            "Invalid email format"
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "UserBuilder made an invalid user: Username cannot be empty")]
+    fn build_panics_on_invalid_users() {
+        let _ = UserBuilder::new().username("").build();
+    }
+
+    #[test]
+    fn create_in_uses_service_ids() {
+        let mut service = UserService::new();
+        let first = UserBuilder::new().id(99).create_in(&mut service);
+        let second = teenager().create_in(&mut service);
+        assert_ne!(first, 99);
+        assert_ne!(first, second);
+        assert_eq!(service.find_by_username("teen").unwrap().id, second);
+    }
+
+    #[test]
+    fn account_defaults() {
+        let account = AccountBuilder::new().build();
+        assert_eq!(account.balance(), 0.0);
+        assert_eq!(account.overdraft_limit(), 0.0);
+        assert_eq!(account.transaction_count(), 0);
+    }
+
+    #[test]
+    fn opening_balance_is_one_transaction() {
+        let account = funded_account().build();
+        assert_eq!(account.balance(), 100.0);
+        assert_eq!(account.transaction_count(), 1);
+
+        let account = overdrawn_account().build();
+        assert_eq!(account.balance(), -100.0);
+        assert_eq!(account.available(), 0.0);
+    }
+
+    #[test]
+    fn account_uses_given_clock() {
+        let clock = MockClock::new(1_000);
+        let mut account = AccountBuilder::new()
+            .clock(Box::new(clock.clone()))
+            .recurring_interest(0.1, 100)
+            .balance(50.0)
+            .build();
+        assert_eq!(account.next_interest_due(), Some(1_100));
+        clock.advance(100);
+        assert_eq!(account.accrue_interest(), 5.0);
+    }
+
+    #[test]
+    #[should_panic(expected = "AccountBuilder couldn't open with -10: Insufficient funds")]
+    fn negative_balance_needs_overdraft() {
+        let _ = AccountBuilder::new().balance(-10.0).build();
+    }
+}
@@ -8,7 +8,9 @@
 //! - Integration tests
 //! - Documentation tests
 //! - Property-based testing
-//! - Mocking
+//! - Mocking, including HTTP servers
+//! - Async testing
+//! - Concurrency testing
 //! - Benchmarking
 //!
 //! ## Example: Calculator
@@ -16,12 +18,23 @@
 //! ```
 //! use testing::Calculator;
 //!
-//! let calc = Calculator::new();
+//! let mut calc = Calculator::new();
 //! assert_eq!(calc.add(2, 3), 5);
 //! assert_eq!(calc.subtract(10, 4), 6);
 //! ```
 
-use std::collections::HashMap;
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::io::{BufRead, Read, Write};
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+pub mod builders;
+pub mod strategies;
 
 // ============================================================================
 // SECTION 1: BASIC STRUCTURES FOR TESTING
@@ -36,7 +49,7 @@ use std::collections::HashMap;
 /// ```
 /// use testing::Calculator;
 ///
-/// let calc = Calculator::new();
+/// let mut calc = Calculator::new();
 /// assert_eq!(calc.multiply(4, 5), 20);
 /// ```
 ///
@@ -45,47 +58,132 @@ use std::collections::HashMap;
 /// ```
 /// use testing::Calculator;
 ///
-/// let calc = Calculator::new();
+/// let mut calc = Calculator::new();
 /// assert_eq!(calc.divide(10, 2), Ok(5));
 /// assert_eq!(calc.divide(10, 0), Err("Division by zero"));
 /// ```
-#[derive(Debug, Clone)]
+///
+/// Every successful calculation is recorded in the history:
+///
+/// ```
+/// use testing::{Calculator, MockClock, Operation};
+///
+/// let mut calc = Calculator::with_clock(Box::new(MockClock::new(1_000)));
+/// calc.add(2, 3);
+/// calc.multiply(5, 4);
+///
+/// assert_eq!(calc.history().len(), 2);
+/// assert_eq!(calc.history()[0].operation, Operation::Add(2, 3));
+/// assert_eq!(calc.history()[0].timestamp, 1_000);
+///
+/// let undone = calc.undo_last().unwrap();
+/// assert_eq!(undone.result, 20);
+/// assert_eq!(calc.last_result(), Some(5));
+/// ```
 pub struct Calculator {
-    history: Vec<String>,
+    history: VecDeque<HistoryEntry>,
+    clock: Box<dyn Clock>,
+}
+
+/// A calculation the [`Calculator`] performed, with its operands
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Operation {
+    Add(i32, i32),
+    Subtract(i32, i32),
+    Multiply(i32, i32),
+    Divide(i32, i32),
+    /// An expression passed to [`Calculator::evaluate`]
+    Evaluate(String),
+}
+
+/// One line of a [`Calculator`]'s history
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub operation: Operation,
+    pub result: i32,
+    /// When it was calculated, in seconds since the Unix epoch
+    pub timestamp: u64,
+}
+
+impl std::fmt::Display for HistoryEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (a, op, b) = match &self.operation {
+            Operation::Add(a, b) => (a, "+", b),
+            Operation::Subtract(a, b) => (a, "-", b),
+            Operation::Multiply(a, b) => (a, "*", b),
+            Operation::Divide(a, b) => (a, "/", b),
+            Operation::Evaluate(expression) => {
+                return write!(f, "{} = {}", expression, self.result)
+            }
+        };
+        write!(f, "{} {} {} = {}", a, op, b, self.result)
+    }
+}
+
+impl std::fmt::Debug for Calculator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Calculator")
+            .field("history", &self.history)
+            .finish_non_exhaustive()
+    }
 }
 
 impl Calculator {
+    /// The most entries the history keeps; older ones are dropped first
+    pub const HISTORY_LIMIT: usize = 1_000;
+
     /// Creates a new Calculator instance
     pub fn new() -> Self {
+        Self::with_clock(Box::new(SystemClock))
+    }
+
+    /// Creates a calculator that timestamps its history with `clock`
+    pub fn with_clock(clock: Box<dyn Clock>) -> Self {
         Calculator {
-            history: Vec::new(),
+            history: VecDeque::new(),
+            clock,
         }
     }
 
     /// Adds two numbers
-    pub fn add(&self, a: i32, b: i32) -> i32 {
-        a + b
+    pub fn add(&mut self, a: i32, b: i32) -> i32 {
+        self.record(Operation::Add(a, b), a + b)
     }
 
     /// Subtracts b from a
-    pub fn subtract(&self, a: i32, b: i32) -> i32 {
-        a - b
+    pub fn subtract(&mut self, a: i32, b: i32) -> i32 {
+        self.record(Operation::Subtract(a, b), a - b)
     }
 
     /// Multiplies two numbers
-    pub fn multiply(&self, a: i32, b: i32) -> i32 {
-        a * b
+    pub fn multiply(&mut self, a: i32, b: i32) -> i32 {
+        self.record(Operation::Multiply(a, b), a * b)
     }
 
-    /// Divides a by b, returns error if b is zero
-    pub fn divide(&self, a: i32, b: i32) -> Result<i32, &'static str> {
+    /// Divides a by b, returns error if b is zero. Failed divisions
+    /// aren't recorded.
+    pub fn divide(&mut self, a: i32, b: i32) -> Result<i32, &'static str> {
         if b == 0 {
             Err("Division by zero")
         } else {
-            Ok(a / b)
+            Ok(self.record(Operation::Divide(a, b), a / b))
         }
     }
 
+    /// Appends to the history and passes `result` through
+    fn record(&mut self, operation: Operation, result: i32) -> i32 {
+        if self.history.len() == Self::HISTORY_LIMIT {
+            self.history.pop_front();
+        }
+        self.history.push_back(HistoryEntry {
+            operation,
+            result,
+            timestamp: self.clock.now(),
+        });
+        result
+    }
+
     /// Private helper function to format operation
     fn format_operation(&self, op: &str, a: i32, b: i32, result: i32) -> String {
         format!("{} {} {} = {}", a, op, b, result)
@@ -99,10 +197,92 @@ impl Calculator {
         }
     }
 
-    /// Gets calculation history
-    pub fn history(&self) -> &[String] {
+    /// Computes factorial with a loop; same results as [`Calculator::factorial`]
+    /// without a call per step, which `benches/` compares
+    ///
+    /// ```
+    /// use testing::Calculator;
+    ///
+    /// assert_eq!(Calculator::factorial_iterative(5), 120);
+    /// assert_eq!(Calculator::factorial_iterative(20), Calculator::factorial(20));
+    /// ```
+    pub fn factorial_iterative(n: u32) -> u64 {
+        (2..=n as u64).product()
+    }
+
+    /// Evaluates an integer expression with `+`, `-`, `*`, `/`,
+    /// parentheses and unary minus, using the usual precedence.
+    /// Division truncates toward zero, like [`Calculator::divide`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use testing::{Calculator, ExprError};
+    ///
+    /// let mut calc = Calculator::new();
+    /// assert_eq!(calc.evaluate("2 + 3 * (4 - 1)"), Ok(11));
+    /// assert_eq!(calc.evaluate("-(2 + 3) * 2"), Ok(-10));
+    /// assert_eq!(calc.evaluate("1 / (2 - 2)"), Err(ExprError::DivisionByZero));
+    /// assert_eq!(calc.evaluate("2 +"), Err(ExprError::UnexpectedEnd));
+    /// ```
+    ///
+    /// A successful evaluation is recorded as one history entry, not one
+    /// per operator.
+    pub fn evaluate(&mut self, expression: &str) -> Result<i32, ExprError> {
+        let mut parser = Parser {
+            tokens: tokenize(expression)?,
+            next: 0,
+            depth: 0,
+        };
+        let value = parser.expression(0)?;
+        // Anything left over, like the `3` in `2 3` or a stray `)`
+        if let Some((position, _)) = parser.peek() {
+            return Err(ExprError::UnexpectedToken { position });
+        }
+        Ok(self.record(Operation::Evaluate(expression.trim().to_string()), value))
+    }
+
+    /// Gets calculation history, oldest first
+    pub fn history(&self) -> &VecDeque<HistoryEntry> {
         &self.history
     }
+
+    /// Entries timestamped in `from..to`
+    pub fn history_between(&self, from: u64, to: u64) -> impl Iterator<Item = &HistoryEntry> {
+        self.history
+            .iter()
+            .filter(move |entry| (from..to).contains(&entry.timestamp))
+    }
+
+    /// The result of the newest entry
+    pub fn last_result(&self) -> Option<i32> {
+        self.history.back().map(|entry| entry.result)
+    }
+
+    /// Removes and returns the newest entry
+    pub fn undo_last(&mut self) -> Option<HistoryEntry> {
+        self.history.pop_back()
+    }
+
+    pub fn clear_history(&mut self) {
+        self.history.clear();
+    }
+
+    /// Writes the history as a JSON array, oldest entry first
+    pub fn save_history<W: Write>(&self, writer: W) -> Result<(), serde_json::Error> {
+        serde_json::to_writer_pretty(writer, &self.history)
+    }
+
+    /// Replaces the history with entries written by
+    /// [`Calculator::save_history`]. Only the newest `HISTORY_LIMIT` are
+    /// kept. On error the history is left unchanged.
+    pub fn load_history<R: Read>(&mut self, reader: R) -> Result<(), serde_json::Error> {
+        let mut history: VecDeque<HistoryEntry> = serde_json::from_reader(reader)?;
+        let excess = history.len().saturating_sub(Self::HISTORY_LIMIT);
+        history.drain(..excess);
+        self.history = history;
+        Ok(())
+    }
 }
 
 impl Default for Calculator {
@@ -111,11 +291,222 @@ impl Default for Calculator {
     }
 }
 
+/// Why [`Calculator::evaluate`] rejected an expression. Positions are byte
+/// offsets into the input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExprError {
+    /// A character that isn't a digit, operator, parenthesis or whitespace
+    InvalidCharacter {
+        character: char,
+        position: usize,
+    },
+    /// A number literal that doesn't fit in an `i32`
+    NumberTooLarge {
+        position: usize,
+    },
+    /// A token where it can't go, like the second `*` in `2 * * 3` or the
+    /// `3` in `2 3`
+    UnexpectedToken {
+        position: usize,
+    },
+    /// The input stopped where an operand was expected, as in `2 +` or ``
+    UnexpectedEnd,
+    /// An opening parenthesis that is never closed
+    UnclosedParen {
+        position: usize,
+    },
+    /// Parentheses or unary minuses nested more than 256 deep; `position`
+    /// is the first one past the limit
+    TooDeeplyNested {
+        position: usize,
+    },
+    DivisionByZero,
+    /// An intermediate result outside the range of `i32`
+    Overflow,
+}
+
+impl std::fmt::Display for ExprError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExprError::InvalidCharacter {
+                character,
+                position,
+            } => write!(f, "invalid character '{}' at {}", character, position),
+            ExprError::NumberTooLarge { position } => {
+                write!(f, "number at {} is too large", position)
+            }
+            ExprError::UnexpectedToken { position } => {
+                write!(f, "unexpected token at {}", position)
+            }
+            ExprError::UnexpectedEnd => write!(f, "unexpected end of expression"),
+            ExprError::UnclosedParen { position } => {
+                write!(f, "parenthesis at {} is never closed", position)
+            }
+            ExprError::TooDeeplyNested { position } => {
+                write!(f, "expression at {} is nested too deeply", position)
+            }
+            ExprError::DivisionByZero => write!(f, "division by zero"),
+            ExprError::Overflow => write!(f, "arithmetic overflow"),
+        }
+    }
+}
+
+impl std::error::Error for ExprError {}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Token {
+    Number(i32),
+    Plus,
+    Minus,
+    Star,
+    Slash,
+    LParen,
+    RParen,
+}
+
+/// Splits an expression into tokens, each with its byte offset
+fn tokenize(input: &str) -> Result<Vec<(usize, Token)>, ExprError> {
+    let mut tokens = Vec::new();
+    let mut chars = input.char_indices().peekable();
+    while let Some((position, character)) = chars.next() {
+        let token = match character {
+            c if c.is_whitespace() => continue,
+            '+' => Token::Plus,
+            '-' => Token::Minus,
+            '*' => Token::Star,
+            '/' => Token::Slash,
+            '(' => Token::LParen,
+            ')' => Token::RParen,
+            '0'..='9' => {
+                let mut end = position + 1;
+                while let Some(&(next, '0'..='9')) = chars.peek() {
+                    end = next + 1;
+                    chars.next();
+                }
+                let value = input[position..end]
+                    .parse()
+                    .map_err(|_| ExprError::NumberTooLarge { position })?;
+                Token::Number(value)
+            }
+            character => {
+                return Err(ExprError::InvalidCharacter {
+                    character,
+                    position,
+                })
+            }
+        };
+        tokens.push((position, token));
+    }
+    Ok(tokens)
+}
+
+/// A precedence-climbing parser that evaluates as it goes
+///
+/// Each binary operator has a precedence, and `expression(min)` only
+/// consumes operators binding at least as tightly as `min`. Parsing the
+/// right operand with `precedence + 1` makes operators of equal precedence
+/// group to the left, so `8 - 3 - 2` is `(8 - 3) - 2`.
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    next: usize,
+    /// Open parentheses and unary minuses around the current token
+    depth: usize,
+}
+
+impl Parser {
+    /// Each level of nesting is a few calls deep, so without a limit a
+    /// long enough run of `(` or `-` overflows the stack instead of
+    /// returning an error
+    const MAX_DEPTH: usize = 256;
+
+    /// Goes one level deeper for the parenthesis or minus at `position`
+    fn nest(&mut self, position: usize) -> Result<(), ExprError> {
+        if self.depth == Self::MAX_DEPTH {
+            return Err(ExprError::TooDeeplyNested { position });
+        }
+        self.depth += 1;
+        Ok(())
+    }
+
+    fn peek(&self) -> Option<(usize, Token)> {
+        self.tokens.get(self.next).copied()
+    }
+
+    fn advance(&mut self) -> Option<(usize, Token)> {
+        let token = self.peek();
+        self.next += 1;
+        token
+    }
+
+    fn precedence(token: Token) -> Option<u8> {
+        match token {
+            Token::Plus | Token::Minus => Some(1),
+            Token::Star | Token::Slash => Some(2),
+            _ => None,
+        }
+    }
+
+    fn expression(&mut self, min_precedence: u8) -> Result<i32, ExprError> {
+        let mut lhs = self.unary()?;
+        while let Some((_, op)) = self.peek() {
+            let Some(precedence) = Self::precedence(op).filter(|&p| p >= min_precedence) else {
+                break;
+            };
+            self.advance();
+            let rhs = self.expression(precedence + 1)?;
+            lhs = Self::apply(op, lhs, rhs)?;
+        }
+        Ok(lhs)
+    }
+
+    /// A leading minus binds tighter than any binary operator, so `-2 * 3`
+    /// is `(-2) * 3` and `--2` is 2
+    fn unary(&mut self) -> Result<i32, ExprError> {
+        if let Some((position, Token::Minus)) = self.peek() {
+            self.advance();
+            self.nest(position)?;
+            let value = self.unary()?;
+            self.depth -= 1;
+            return value.checked_neg().ok_or(ExprError::Overflow);
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Result<i32, ExprError> {
+        match self.advance() {
+            Some((_, Token::Number(value))) => Ok(value),
+            Some((open, Token::LParen)) => {
+                self.nest(open)?;
+                let value = self.expression(0)?;
+                self.depth -= 1;
+                match self.advance() {
+                    Some((_, Token::RParen)) => Ok(value),
+                    Some((position, _)) => Err(ExprError::UnexpectedToken { position }),
+                    None => Err(ExprError::UnclosedParen { position: open }),
+                }
+            }
+            Some((position, _)) => Err(ExprError::UnexpectedToken { position }),
+            None => Err(ExprError::UnexpectedEnd),
+        }
+    }
+
+    fn apply(op: Token, a: i32, b: i32) -> Result<i32, ExprError> {
+        match op {
+            Token::Plus => a.checked_add(b).ok_or(ExprError::Overflow),
+            Token::Minus => a.checked_sub(b).ok_or(ExprError::Overflow),
+            Token::Star => a.checked_mul(b).ok_or(ExprError::Overflow),
+            _ if b == 0 => Err(ExprError::DivisionByZero),
+            // i32::MIN / -1 is the one division that overflows
+            _ => a.checked_div(b).ok_or(ExprError::Overflow),
+        }
+    }
+}
+
 // ============================================================================
 // SECTION 2: USER SERVICE (FOR TESTING VALIDATION AND ERRORS)
 // ============================================================================
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct User {
     pub id: u64,
     pub username: String,
@@ -147,41 +538,229 @@ impl User {
     }
 }
 
+/// Where a [`UserService`] keeps its users
+///
+/// The service validates users and assigns their ids; a store only holds
+/// what it's given. Changes can fail, since a store may write to disk.
+#[cfg_attr(test, mockall::automock)]
+pub trait UserStore {
+    /// Every stored user, keyed by id
+    fn users(&self) -> &BTreeMap<u64, User>;
+    /// Adds `user`, replacing any user with the same id
+    fn save(&mut self, user: User) -> Result<(), String>;
+    /// Returns whether there was a user to delete
+    fn delete(&mut self, id: u64) -> Result<bool, String>;
+}
+
+/// A [`UserStore`] that lives only as long as the service
+#[derive(Debug, Default)]
+pub struct InMemoryUserStore {
+    users: BTreeMap<u64, User>,
+}
+
+impl InMemoryUserStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl UserStore for InMemoryUserStore {
+    fn users(&self) -> &BTreeMap<u64, User> {
+        &self.users
+    }
+
+    fn save(&mut self, user: User) -> Result<(), String> {
+        self.users.insert(user.id, user);
+        Ok(())
+    }
+
+    fn delete(&mut self, id: u64) -> Result<bool, String> {
+        Ok(self.users.remove(&id).is_some())
+    }
+}
+
+/// A [`UserStore`] kept in a JSON file
+///
+/// The users are read once when the store is opened and held in memory.
+/// Every change rewrites the whole file, which is simple and plenty fast
+/// for the handful of users in this example.
+#[derive(Debug)]
+pub struct FileUserStore {
+    path: PathBuf,
+    users: BTreeMap<u64, User>,
+}
+
+impl FileUserStore {
+    /// Opens the store at `path`. A missing file is an empty store; the
+    /// file is created by the first change.
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self, String> {
+        let path = path.into();
+        let users: Vec<User> = match std::fs::read_to_string(&path) {
+            Ok(json) => serde_json::from_str(&json)
+                .map_err(|e| format!("Invalid user file {}: {}", path.display(), e))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(format!("Cannot read {}: {}", path.display(), e)),
+        };
+        Ok(FileUserStore {
+            path,
+            users: users.into_iter().map(|user| (user.id, user)).collect(),
+        })
+    }
+
+    /// Writes every user to the file, in id order
+    fn flush(&self) -> Result<(), String> {
+        let users: Vec<&User> = self.users.values().collect();
+        let json = serde_json::to_string_pretty(&users).map_err(|e| e.to_string())?;
+        std::fs::write(&self.path, json)
+            .map_err(|e| format!("Cannot write {}: {}", self.path.display(), e))
+    }
+}
+
+impl UserStore for FileUserStore {
+    fn users(&self) -> &BTreeMap<u64, User> {
+        &self.users
+    }
+
+    fn save(&mut self, user: User) -> Result<(), String> {
+        let id = user.id;
+        let previous = self.users.insert(id, user);
+        self.flush().inspect_err(|_| {
+            // Undo the change the file missed, so memory and file agree
+            match previous {
+                Some(previous) => self.users.insert(id, previous),
+                None => self.users.remove(&id),
+            };
+        })
+    }
+
+    fn delete(&mut self, id: u64) -> Result<bool, String> {
+        let Some(user) = self.users.remove(&id) else {
+            return Ok(false);
+        };
+        self.flush().inspect_err(|_| {
+            self.users.insert(id, user);
+        })?;
+        Ok(true)
+    }
+}
+
+/// Which users [`UserService::search`] returns. Every condition that is
+/// set must hold; the default matches everyone.
+///
+/// ```
+/// use testing::UserFilter;
+///
+/// let adult_a_names = UserFilter {
+///     username_prefix: Some("a".to_string()),
+///     adult_only: true,
+///     ..UserFilter::default()
+/// };
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct UserFilter {
+    /// Ages within this range, inclusive at both ends
+    pub age_range: Option<RangeInclusive<u8>>,
+    pub username_prefix: Option<String>,
+    pub adult_only: bool,
+}
+
+impl UserFilter {
+    pub fn matches(&self, user: &User) -> bool {
+        self.age_range
+            .as_ref()
+            .is_none_or(|range| range.contains(&user.age))
+            && self
+                .username_prefix
+                .as_ref()
+                .is_none_or(|prefix| user.username.starts_with(prefix.as_str()))
+            && (!self.adult_only || user.is_adult())
+    }
+}
+
 pub struct UserService {
-    users: HashMap<u64, User>,
-    next_id: u64,
+    store: Box<dyn UserStore>,
+    ids: Box<dyn IdGenerator>,
 }
 
 impl UserService {
     pub fn new() -> Self {
-        UserService {
-            users: HashMap::new(),
-            next_id: 1,
-        }
+        Self::with_id_generator(Box::new(SequentialIds::default()))
+    }
+
+    /// Creates a service that takes user ids from `ids`
+    pub fn with_id_generator(ids: Box<dyn IdGenerator>) -> Self {
+        Self::with_store(Box::new(InMemoryUserStore::new()), ids)
+    }
+
+    /// Creates a service that keeps its users in `store`
+    pub fn with_store(store: Box<dyn UserStore>, ids: Box<dyn IdGenerator>) -> Self {
+        UserService { store, ids }
     }
 
+    /// Ids already in the store are skipped, so a service reopened on a
+    /// [`FileUserStore`] carries on after the users it finds there
     pub fn create_user(&mut self, username: String, email: String, age: u8) -> Result<u64, String> {
-        let user = User::new(self.next_id, username, email, age)?;
+        // Validate before taking an id, so failed attempts don't use one up
+        let mut user = User::new(0, username, email, age)?;
+        user.id = self.ids.next_id();
+        while self.store.users().contains_key(&user.id) {
+            user.id = self.ids.next_id();
+        }
         let id = user.id;
-        self.users.insert(id, user);
-        self.next_id += 1;
+        self.store.save(user)?;
         Ok(id)
     }
 
     pub fn get_user(&self, id: u64) -> Option<&User> {
-        self.users.get(&id)
+        self.store.users().get(&id)
     }
 
-    pub fn delete_user(&mut self, id: u64) -> bool {
-        self.users.remove(&id).is_some()
+    /// Returns whether the user existed
+    pub fn delete_user(&mut self, id: u64) -> Result<bool, String> {
+        self.store.delete(id)
     }
 
     pub fn count(&self) -> usize {
-        self.users.len()
+        self.store.users().len()
     }
 
     pub fn find_by_username(&self, username: &str) -> Option<&User> {
-        self.users.values().find(|u| u.username == username)
+        self.store.users().values().find(|u| u.username == username)
+    }
+
+    /// Replaces a user's details, keeping their id. The new details are
+    /// validated like a new user's; if they fail, the user is unchanged.
+    pub fn update_user(
+        &mut self,
+        id: u64,
+        username: String,
+        email: String,
+        age: u8,
+    ) -> Result<(), String> {
+        if !self.store.users().contains_key(&id) {
+            return Err("User not found".to_string());
+        }
+        self.store.save(User::new(id, username, email, age)?)
+    }
+
+    /// One page of users in id order. Pages count from 0, and a page past
+    /// the end is empty.
+    pub fn list_users(&self, page: usize, per_page: usize) -> Vec<&User> {
+        self.store
+            .users()
+            .values()
+            .skip(page.saturating_mul(per_page))
+            .take(per_page)
+            .collect()
+    }
+
+    /// Every user matching `filter`, in id order
+    pub fn search(&self, filter: &UserFilter) -> Vec<&User> {
+        self.store
+            .users()
+            .values()
+            .filter(|user| filter.matches(user))
+            .collect()
     }
 }
 
@@ -202,11 +781,162 @@ pub trait DataProcessor {
 
 pub struct FileProcessor {
     prefix: String,
+    /// The largest input file accepted, in bytes
+    max_size: u64,
+}
+
+/// Why [`FileProcessor::process_file`] failed. Nothing is written when it
+/// does.
+#[derive(Debug)]
+pub enum ProcessError {
+    NotFound(PathBuf),
+    PermissionDenied(PathBuf),
+    TooLarge {
+        size: u64,
+        limit: u64,
+    },
+    /// Line numbers count from 1
+    InvalidUtf8 {
+        line: usize,
+    },
+    Io(std::io::Error),
+}
+
+impl std::fmt::Display for ProcessError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProcessError::NotFound(path) => write!(f, "{} not found", path.display()),
+            ProcessError::PermissionDenied(path) => {
+                write!(f, "permission denied for {}", path.display())
+            }
+            ProcessError::TooLarge { size, limit } => {
+                write!(f, "input is {} bytes, over the limit of {}", size, limit)
+            }
+            ProcessError::InvalidUtf8 { line } => write!(f, "line {} is not valid UTF-8", line),
+            ProcessError::Io(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for ProcessError {}
+
+impl ProcessError {
+    fn from_io(e: std::io::Error, path: &Path) -> Self {
+        match e.kind() {
+            std::io::ErrorKind::NotFound => ProcessError::NotFound(path.to_path_buf()),
+            std::io::ErrorKind::PermissionDenied => {
+                ProcessError::PermissionDenied(path.to_path_buf())
+            }
+            _ => ProcessError::Io(e),
+        }
+    }
+}
+
+/// What [`FileProcessor::process_lines`] did with its input
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ProcessSummary {
+    pub lines_processed: usize,
+    /// Lines that failed [`DataProcessor::validate`], left out of the
+    /// output
+    pub lines_skipped: usize,
 }
 
 impl FileProcessor {
+    /// Inputs up to this size are accepted unless `with_max_size` says
+    /// otherwise
+    pub const DEFAULT_MAX_SIZE: u64 = 1024 * 1024;
+
     pub fn new(prefix: String) -> Self {
-        FileProcessor { prefix }
+        FileProcessor {
+            prefix,
+            max_size: Self::DEFAULT_MAX_SIZE,
+        }
+    }
+
+    pub fn with_max_size(mut self, bytes: u64) -> Self {
+        self.max_size = bytes;
+        self
+    }
+
+    /// Processes each line of `reader` into `writer`, one output line per
+    /// valid input line. Lines may end in `\n` or `\r\n`; output lines end
+    /// in `\n`.
+    ///
+    /// ```
+    /// use testing::FileProcessor;
+    ///
+    /// let processor = FileProcessor::new("LOG".to_string());
+    /// let mut output = Vec::new();
+    /// let summary = processor
+    ///     .process_lines("first\n\nsecond\n".as_bytes(), &mut output)
+    ///     .unwrap();
+    ///
+    /// assert_eq!(String::from_utf8(output).unwrap(), "LOG: FIRST\nLOG: SECOND\n");
+    /// assert_eq!(summary.lines_skipped, 1);
+    /// ```
+    pub fn process_lines<R: BufRead, W: Write>(
+        &self,
+        mut reader: R,
+        mut writer: W,
+    ) -> Result<ProcessSummary, ProcessError> {
+        let mut summary = ProcessSummary::default();
+        let mut bytes = Vec::new();
+        for line_number in 1.. {
+            bytes.clear();
+            if reader
+                .read_until(b'\n', &mut bytes)
+                .map_err(ProcessError::Io)?
+                == 0
+            {
+                break;
+            }
+            let line = std::str::from_utf8(&bytes)
+                .map_err(|_| ProcessError::InvalidUtf8 { line: line_number })?;
+            let line = line.strip_suffix('\n').unwrap_or(line);
+            let line = line.strip_suffix('\r').unwrap_or(line);
+            if self.validate(line) {
+                writeln!(writer, "{}", self.process(line)).map_err(ProcessError::Io)?;
+                summary.lines_processed += 1;
+            } else {
+                summary.lines_skipped += 1;
+            }
+        }
+        Ok(summary)
+    }
+
+    /// Processes the file at `input` into a new file at `output`
+    ///
+    /// The whole output is built before anything is written, so on any
+    /// error `output` is left as it was. Inputs over the size limit are
+    /// refused before they're read.
+    pub fn process_file(
+        &self,
+        input: &Path,
+        output: &Path,
+    ) -> Result<ProcessSummary, ProcessError> {
+        let file = std::fs::File::open(input).map_err(|e| ProcessError::from_io(e, input))?;
+        let size = file
+            .metadata()
+            .map_err(|e| ProcessError::from_io(e, input))?
+            .len();
+        if size > self.max_size {
+            return Err(ProcessError::TooLarge {
+                size,
+                limit: self.max_size,
+            });
+        }
+        // The file might grow after the check, so don't read past the limit
+        let mut limited = std::io::BufReader::new(file).take(self.max_size + 1);
+        let mut processed = Vec::new();
+        let summary = self.process_lines(&mut limited, &mut processed)?;
+        if limited.limit() == 0 {
+            return Err(ProcessError::TooLarge {
+                size: self.max_size + 1,
+                limit: self.max_size,
+            });
+        }
+        std::fs::write(output, processed).map_err(|e| ProcessError::from_io(e, output))?;
+        Ok(summary)
     }
 }
 
@@ -329,41 +1059,345 @@ pub fn find_min<T: Ord + Copy>(slice: &[T]) -> Option<T> {
 // SECTION 7: BANKING EXAMPLE (FOR TESTING WITH STATE)
 // ============================================================================
 
-#[derive(Debug, Clone)]
+/// What moved money in or out of a [`BankAccount`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TransactionKind {
+    Deposit,
+    Withdrawal,
+    TransferIn,
+    TransferOut,
+    /// Earned on a positive balance, or charged on an overdrawn one
+    Interest,
+}
+
+/// One entry in a [`BankAccount`]'s ledger
+#[derive(Debug, Clone, PartialEq)]
+pub struct Transaction {
+    /// Unique within the account, counting up from 1
+    pub id: u64,
+    pub kind: TransactionKind,
+    /// The change to the balance: positive for money in, negative for
+    /// money out
+    pub amount: f64,
+    /// Seconds since the Unix epoch
+    pub timestamp: u64,
+    pub memo: String,
+}
+
+/// A [`Transaction`] with the balance just after it
+#[derive(Debug, Clone, PartialEq)]
+pub struct StatementLine {
+    pub transaction: Transaction,
+    pub balance: f64,
+}
+
+/// The transactions in a time range with a running balance, as printed on
+/// a bank statement
+#[derive(Debug, Clone, PartialEq)]
+pub struct Statement {
+    /// The balance before the first line
+    pub opening_balance: f64,
+    pub lines: Vec<StatementLine>,
+}
+
+impl Statement {
+    /// The balance after the last line
+    pub fn closing_balance(&self) -> f64 {
+        self.lines
+            .last()
+            .map_or(self.opening_balance, |line| line.balance)
+    }
+
+    /// Sum of the money that came in
+    pub fn total_in(&self) -> f64 {
+        self.amounts().filter(|&amount| amount > 0.0).sum()
+    }
+
+    /// Sum of the money that went out, as a positive number
+    pub fn total_out(&self) -> f64 {
+        -self.amounts().filter(|&amount| amount < 0.0).sum::<f64>()
+    }
+
+    fn amounts(&self) -> impl Iterator<Item = f64> + '_ {
+        self.lines.iter().map(|line| line.transaction.amount)
+    }
+}
+
+impl std::fmt::Display for Statement {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "{:>12} {:<12} {:>10} {:>10}  Memo",
+            "Time", "Kind", "Amount", "Balance"
+        )?;
+        writeln!(
+            f,
+            "{:>12} {:<12} {:>10} {:>10.2}",
+            "", "Opening", "", self.opening_balance
+        )?;
+        for line in &self.lines {
+            let kind = format!("{:?}", line.transaction.kind);
+            write!(
+                f,
+                "{:>12} {:<12} {:>10.2} {:>10.2}",
+                line.transaction.timestamp, kind, line.transaction.amount, line.balance
+            )?;
+            if !line.transaction.memo.is_empty() {
+                write!(f, "  {}", line.transaction.memo)?;
+            }
+            writeln!(f)?;
+        }
+        write!(
+            f,
+            "{:>12} {:<12} {:>10} {:>10.2}",
+            "",
+            "Closing",
+            "",
+            self.closing_balance()
+        )
+    }
+}
+
 pub struct BankAccount {
     balance: f64,
-    transactions: Vec<f64>,
+    /// How far below zero the balance may go
+    overdraft_limit: f64,
+    transactions: Vec<Transaction>,
+    recurring_interest: Option<RecurringInterest>,
+    clock: Box<dyn Clock>,
+}
+
+/// Interest paid automatically every `period` seconds
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct RecurringInterest {
+    rate: f64,
+    period: u64,
+    /// When the next payment is owed
+    next_due: u64,
+}
+
+impl std::fmt::Debug for BankAccount {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BankAccount")
+            .field("balance", &self.balance)
+            .field("overdraft_limit", &self.overdraft_limit)
+            .field("transactions", &self.transactions)
+            .field("recurring_interest", &self.recurring_interest)
+            .finish_non_exhaustive()
+    }
 }
 
 impl BankAccount {
     pub fn new() -> Self {
+        Self::with_clock(Box::new(SystemClock))
+    }
+
+    /// Creates an account that timestamps its transactions with `clock`
+    pub fn with_clock(clock: Box<dyn Clock>) -> Self {
         BankAccount {
             balance: 0.0,
+            overdraft_limit: 0.0,
             transactions: Vec::new(),
+            recurring_interest: None,
+            clock,
+        }
+    }
+
+    /// Allows withdrawals to take the balance down to `-limit`
+    ///
+    /// # Panics
+    ///
+    /// If `limit` is negative or not a number.
+    pub fn with_overdraft_limit(mut self, limit: f64) -> Self {
+        assert!(limit >= 0.0, "Overdraft limit must be non-negative");
+        self.overdraft_limit = limit;
+        self
+    }
+
+    pub fn overdraft_limit(&self) -> f64 {
+        self.overdraft_limit
+    }
+
+    /// Pays interest at `rate` every `period` seconds, the first time one
+    /// period from now
+    ///
+    /// Nothing runs in the background: interest that has come due is added
+    /// by [`BankAccount::accrue_interest`], which every deposit,
+    /// withdrawal, transfer and interest payment calls first. Each payment
+    /// is timestamped at the end of its period and reckoned on the balance
+    /// at that moment, so paying late doesn't change the amounts.
+    ///
+    /// ```
+    /// use testing::{BankAccount, MockClock};
+    ///
+    /// const DAY: u64 = 24 * 60 * 60;
+    /// let clock = MockClock::new(0);
+    /// let mut account =
+    ///     BankAccount::with_clock(Box::new(clock.clone())).with_recurring_interest(0.01, 30 * DAY);
+    /// account.deposit(1000.0).unwrap();
+    ///
+    /// clock.advance(60 * DAY);
+    /// assert_eq!(account.accrue_interest(), 20.1); // 10.00, then 10.10
+    /// assert_eq!(account.balance(), 1020.1);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// If `rate` is negative or not finite, or `period` is zero.
+    pub fn with_recurring_interest(mut self, rate: f64, period: u64) -> Self {
+        assert!(
+            rate >= 0.0 && rate.is_finite(),
+            "Interest rate must be a non-negative number"
+        );
+        assert!(period > 0, "Interest period must be at least one second");
+        self.recurring_interest = Some(RecurringInterest {
+            rate,
+            period,
+            next_due: self.clock.now() + period,
+        });
+        self
+    }
+
+    /// When the next recurring interest payment is owed, if there is one
+    pub fn next_interest_due(&self) -> Option<u64> {
+        self.recurring_interest.map(|schedule| schedule.next_due)
+    }
+
+    /// Adds every recurring interest payment that has come due and returns
+    /// their total. Returns 0.0 when none is due.
+    pub fn accrue_interest(&mut self) -> f64 {
+        let Some(mut schedule) = self.recurring_interest else {
+            return 0.0;
+        };
+        let now = self.clock.now();
+        let mut total = 0.0;
+        while schedule.next_due <= now {
+            total += self.add_interest(schedule.rate, schedule.next_due);
+            schedule.next_due += schedule.period;
         }
+        self.recurring_interest = Some(schedule);
+        round_to_cents(total)
+    }
+
+    /// What can still be withdrawn, counting the overdraft
+    pub fn available(&self) -> f64 {
+        (self.balance + self.overdraft_limit).max(0.0)
     }
 
     pub fn deposit(&mut self, amount: f64) -> Result<(), String> {
-        if amount <= 0.0 {
+        self.deposit_with_memo(amount, "")
+    }
+
+    pub fn deposit_with_memo(&mut self, amount: f64, memo: &str) -> Result<(), String> {
+        if amount.is_nan() || amount <= 0.0 {
             return Err("Deposit amount must be positive".to_string());
         }
-        self.balance += amount;
-        self.transactions.push(amount);
+        self.accrue_interest();
+        self.record(TransactionKind::Deposit, amount, memo);
         Ok(())
     }
 
     pub fn withdraw(&mut self, amount: f64) -> Result<(), String> {
-        if amount <= 0.0 {
+        self.withdraw_with_memo(amount, "")
+    }
+
+    pub fn withdraw_with_memo(&mut self, amount: f64, memo: &str) -> Result<(), String> {
+        if amount.is_nan() || amount <= 0.0 {
             return Err("Withdrawal amount must be positive".to_string());
         }
-        if amount > self.balance {
+        self.accrue_interest();
+        self.check_funds(amount)?;
+        self.record(TransactionKind::Withdrawal, -amount, memo);
+        Ok(())
+    }
+
+    fn check_funds(&self, amount: f64) -> Result<(), String> {
+        if amount > self.available() {
             return Err("Insufficient funds".to_string());
         }
-        self.balance -= amount;
-        self.transactions.push(-amount);
         Ok(())
     }
 
+    /// Moves `amount` into `other`. Either both sides change or, on error,
+    /// neither does. The borrow checker rules out transferring to the same
+    /// account, since it can't be borrowed mutably twice.
+    ///
+    /// ```
+    /// use testing::BankAccount;
+    ///
+    /// let mut checking = BankAccount::new().with_overdraft_limit(50.0);
+    /// let mut savings = BankAccount::new();
+    /// checking.deposit(100.0).unwrap();
+    ///
+    /// checking.transfer(&mut savings, 120.0).unwrap();
+    /// assert_eq!(checking.balance(), -20.0);
+    /// assert_eq!(savings.balance(), 120.0);
+    ///
+    /// assert!(checking.transfer(&mut savings, 40.0).is_err());
+    /// ```
+    pub fn transfer(&mut self, other: &mut BankAccount, amount: f64) -> Result<(), String> {
+        self.transfer_with_memo(other, amount, "")
+    }
+
+    /// A transfer whose memo appears on both accounts' transactions
+    pub fn transfer_with_memo(
+        &mut self,
+        other: &mut BankAccount,
+        amount: f64,
+        memo: &str,
+    ) -> Result<(), String> {
+        if amount.is_nan() || amount <= 0.0 {
+            return Err("Transfer amount must be positive".to_string());
+        }
+        self.accrue_interest();
+        other.accrue_interest();
+        // Checking funds is the only step that can fail, so it goes first
+        self.check_funds(amount)?;
+        self.record(TransactionKind::TransferOut, -amount, memo);
+        other.record(TransactionKind::TransferIn, amount, memo);
+        Ok(())
+    }
+
+    /// Adds one period's interest at `rate` (0.05 is 5%) and returns it.
+    ///
+    /// Interest is rounded to the nearest cent, halves away from zero, and
+    /// a zero amount isn't recorded as a transaction. An overdrawn balance
+    /// is charged at the same rate, which can take it past the overdraft
+    /// limit; withdrawals then fail until it's paid back.
+    pub fn apply_interest(&mut self, rate: f64) -> Result<f64, String> {
+        if !(rate >= 0.0 && rate.is_finite()) {
+            return Err("Interest rate must be a non-negative number".to_string());
+        }
+        self.accrue_interest();
+        Ok(self.add_interest(rate, self.clock.now()))
+    }
+
+    fn add_interest(&mut self, rate: f64, timestamp: u64) -> f64 {
+        let interest = round_to_cents(self.balance * rate);
+        if interest != 0.0 {
+            let memo = format!("Interest at {:.2}%", rate * 100.0);
+            self.record_at(TransactionKind::Interest, interest, &memo, timestamp);
+        }
+        interest
+    }
+
+    fn record(&mut self, kind: TransactionKind, amount: f64, memo: &str) {
+        self.record_at(kind, amount, memo, self.clock.now());
+    }
+
+    /// The only place the balance changes, so it always equals the sum of
+    /// the ledger's amounts, added in order
+    fn record_at(&mut self, kind: TransactionKind, amount: f64, memo: &str, timestamp: u64) {
+        self.balance += amount;
+        self.transactions.push(Transaction {
+            id: self.transactions.len() as u64 + 1,
+            kind,
+            amount,
+            timestamp,
+            memo: memo.to_string(),
+        });
+    }
+
     pub fn balance(&self) -> f64 {
         self.balance
     }
@@ -371,660 +1405,2942 @@ impl BankAccount {
     pub fn transaction_count(&self) -> usize {
         self.transactions.len()
     }
-}
 
-impl Default for BankAccount {
-    fn default() -> Self {
-        Self::new()
+    /// The whole ledger, oldest first
+    pub fn transactions(&self) -> &[Transaction] {
+        &self.transactions
     }
-}
-
-// ============================================================================
-// MAIN FUNCTION
-// ============================================================================
 
-fn main() {
-    println!("=== Rust Testing Examples ===\n");
+    /// Transactions timestamped in `from..to`
+    pub fn transactions_between(&self, from: u64, to: u64) -> impl Iterator<Item = &Transaction> {
+        self.transactions
+            .iter()
+            .filter(move |t| (from..to).contains(&t.timestamp))
+    }
 
-    // Calculator demo
-    let calc = Calculator::new();
-    println!("Calculator:");
-    println!("  2 + 3 = {}", calc.add(2, 3));
-    println!("  10 - 4 = {}", calc.subtract(10, 4));
-    println!("  4 * 5 = {}", calc.multiply(4, 5));
-    println!("  10 / 2 = {:?}", calc.divide(10, 2));
-    println!("  5! = {}", Calculator::factorial(5));
+    pub fn transactions_of_kind(
+        &self,
+        kind: TransactionKind,
+    ) -> impl Iterator<Item = &Transaction> {
+        self.transactions.iter().filter(move |t| t.kind == kind)
+    }
 
-    // User service demo
-    println!("\nUser Service:");
-    let mut user_service = UserService::new();
-    match user_service.create_user("alice".to_string(), "alice@example.com".to_string(), 25) {
-        Ok(id) => {
-            println!("  Created user with ID: {}", id);
-            if let Some(user) = user_service.get_user(id) {
-                println!("  User: {:?}", user);
-                println!("  Is adult: {}", user.is_adult());
+    /// The transactions timestamped in `from..to`, each with the balance
+    /// after it. The opening balance is everything before `from`.
+    ///
+    /// ```
+    /// use testing::{BankAccount, MockClock};
+    ///
+    /// let clock = MockClock::new(100);
+    /// let mut account = BankAccount::with_clock(Box::new(clock.clone()));
+    /// account.deposit(500.0).unwrap();
+    /// clock.advance(100);
+    /// account.withdraw_with_memo(120.0, "rent").unwrap();
+    /// account.deposit(20.0).unwrap();
+    ///
+    /// let statement = account.statement(200, 300);
+    /// assert_eq!(statement.opening_balance, 500.0);
+    /// assert_eq!(statement.lines[0].balance, 380.0);
+    /// assert_eq!(statement.closing_balance(), account.balance());
+    /// assert_eq!(statement.total_out(), 120.0);
+    /// ```
+    pub fn statement(&self, from: u64, to: u64) -> Statement {
+        let mut balance = 0.0;
+        let mut opening_balance = 0.0;
+        let mut lines = Vec::new();
+        for transaction in &self.transactions {
+            balance += transaction.amount;
+            if transaction.timestamp < from {
+                opening_balance = balance;
+            } else if transaction.timestamp < to {
+                lines.push(StatementLine {
+                    transaction: transaction.clone(),
+                    balance,
+                });
             }
         }
-        Err(e) => println!("  Error: {}", e),
+        Statement {
+            opening_balance,
+            lines,
+        }
     }
+}
 
-    // File processor demo
-    println!("\nFile Processor:");
-    let processor = FileProcessor::new("PROCESSED".to_string());
-    let result = processor.process("hello world");
-    println!("  Result: {}", result);
-    println!("  Valid: {}", processor.validate("hello"));
-
-    // Temperature conversion demo
-    println!("\nTemperature Conversion:");
-    println!("  0°C = {}°F", celsius_to_fahrenheit(0.0));
-    println!("  100°C = {}°F", celsius_to_fahrenheit(100.0));
-    println!("  32°F = {}°C", fahrenheit_to_celsius(32.0));
-
-    // String utilities demo
-    println!("\nString Utilities:");
-    println!("  Reverse 'hello': {}", reverse_string("hello"));
-    println!("  Is 'racecar' palindrome: {}", is_palindrome("racecar"));
-    println!("  Is 'hello' palindrome: {}", is_palindrome("hello"));
-
-    // Vector utilities demo
-    println!("\nVector Utilities:");
-    let numbers = vec![5, 2, 8, 1, 9];
-    println!("  Max of {:?}: {:?}", numbers, find_max(&numbers));
-    println!("  Min of {:?}: {:?}", numbers, find_min(&numbers));
-
-    // Bank account demo
-    println!("\nBank Account:");
-    let mut account = BankAccount::new();
-    account.deposit(100.0).unwrap();
-    println!("  After deposit of $100: ${}", account.balance());
-    account.withdraw(30.0).unwrap();
-    println!("  After withdrawal of $30: ${}", account.balance());
-    println!("  Total transactions: {}", account.transaction_count());
+impl Default for BankAccount {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
-    println!("\n=== Run 'cargo test' to execute all tests ===");
+/// Rounds to two decimal places, halves away from zero
+///
+/// The small nudge keeps amounts like 1.005, stored as 1.00499999…, from
+/// rounding down.
+fn round_to_cents(amount: f64) -> f64 {
+    let cents = amount * 100.0;
+    (cents + cents.signum() * 1e-9).round() / 100.0
 }
 
 // ============================================================================
-// TESTS MODULE
+// SECTION 8: INJECTED DEPENDENCIES (FOR TESTING NONDETERMINISM)
 // ============================================================================
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+/// Source of the current time, in seconds since the Unix epoch
+pub trait Clock {
+    fn now(&self) -> u64;
+}
 
-    // ========================================================================
-    // TEST 1-5: BASIC UNIT TESTS WITH ASSERTIONS
-    // ========================================================================
+pub struct SystemClock;
 
-    #[test]
-    fn test_calculator_add() {
-        let calc = Calculator::new();
-        assert_eq!(calc.add(2, 3), 5);
-        assert_eq!(calc.add(-1, 1), 0);
-        assert_eq!(calc.add(0, 0), 0);
+impl Clock for SystemClock {
+    fn now(&self) -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0)
     }
+}
 
-    #[test]
-    fn test_calculator_subtract() {
-        let calc = Calculator::new();
-        assert_eq!(calc.subtract(10, 4), 6);
-        assert_eq!(calc.subtract(5, 5), 0);
-        assert_eq!(calc.subtract(0, 10), -10);
+/// A clock that only moves when told to. Clones share the same time, so a
+/// test can keep one handle and give another to the code under test.
+///
+/// ```
+/// use testing::{Clock, MockClock};
+///
+/// let clock = MockClock::new(1_000);
+/// let handle = clock.clone();
+/// handle.advance(60);
+/// assert_eq!(clock.now(), 1_060);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct MockClock {
+    now: Rc<Cell<u64>>,
+}
+
+impl MockClock {
+    pub fn new(start: u64) -> Self {
+        MockClock {
+            now: Rc::new(Cell::new(start)),
+        }
     }
 
-    #[test]
-    fn test_calculator_multiply() {
-        let calc = Calculator::new();
-        assert_eq!(calc.multiply(4, 5), 20);
-        assert_eq!(calc.multiply(0, 100), 0);
-        assert_eq!(calc.multiply(-2, 3), -6);
+    pub fn set(&self, now: u64) {
+        self.now.set(now);
     }
 
-    #[test]
-    fn test_calculator_divide_success() {
-        let calc = Calculator::new();
-        assert_eq!(calc.divide(10, 2), Ok(5));
-        assert_eq!(calc.divide(9, 3), Ok(3));
-        assert_eq!(calc.divide(-10, 2), Ok(-5));
+    pub fn advance(&self, seconds: u64) {
+        self.now.set(self.now.get() + seconds);
     }
+}
 
-    #[test]
-    fn test_calculator_divide_by_zero() {
-        let calc = Calculator::new();
-        assert_eq!(calc.divide(10, 0), Err("Division by zero"));
-        assert!(calc.divide(0, 0).is_err());
+impl Clock for MockClock {
+    fn now(&self) -> u64 {
+        self.now.get()
     }
+}
 
-    // ========================================================================
-    // TEST 6-8: TESTING WITH ASSERT!, ASSERT_NE!
-    // ========================================================================
+/// Source of new entity ids
+pub trait IdGenerator {
+    fn next_id(&mut self) -> u64;
+}
 
-    #[test]
-    fn test_assertions_various() {
-        let calc = Calculator::new();
-        let result = calc.add(2, 2);
+/// Counts up from a starting id (1 by default)
+pub struct SequentialIds {
+    next: u64,
+}
 
-        assert!(result > 0, "Result should be positive");
-        assert!(result == 4, "Result should equal 4");
-        assert_ne!(result, 5, "Result should not equal 5");
+impl SequentialIds {
+    pub fn starting_at(first: u64) -> Self {
+        SequentialIds { next: first }
     }
+}
 
-    #[test]
-    fn test_user_validation() {
-        let user = User::new(1, "alice".to_string(), "alice@test.com".to_string(), 25);
-        assert!(user.is_ok(), "User creation should succeed");
-
-        let user = user.unwrap();
-        assert_eq!(user.username, "alice");
-        assert!(user.email.contains('@'));
-        assert!(user.is_adult());
+impl Default for SequentialIds {
+    fn default() -> Self {
+        Self::starting_at(1)
     }
+}
 
-    #[test]
-    fn test_user_validation_failures() {
-        // Empty username
-        let result = User::new(1, "".to_string(), "test@test.com".to_string(), 20);
-        assert!(result.is_err());
-        assert_eq!(result.unwrap_err(), "Username cannot be empty");
-
-        // Invalid email
-        let result = User::new(1, "bob".to_string(), "invalid-email".to_string(), 20);
-        assert!(result.is_err());
-        assert_eq!(result.unwrap_err(), "Invalid email format");
-
-        // Under age
-        let result = User::new(1, "kid".to_string(), "kid@test.com".to_string(), 10);
-        assert!(result.is_err());
-        assert_eq!(result.unwrap_err(), "User must be at least 13 years old");
+impl IdGenerator for SequentialIds {
+    fn next_id(&mut self) -> u64 {
+        let id = self.next;
+        self.next += 1;
+        id
     }
+}
 
-    // ========================================================================
-    // TEST 9-11: TESTING PANICS WITH #[should_panic]
-    // ========================================================================
+/// Hands out a fixed list of ids in order. Running out panics: a test that
+/// needs more ids than it declared is a bug in the test.
+pub struct FixedIds {
+    ids: VecDeque<u64>,
+}
 
-    #[test]
-    #[should_panic]
-    fn test_panic_basic() {
-        panic!("This test should panic");
+impl FixedIds {
+    pub fn new(ids: impl IntoIterator<Item = u64>) -> Self {
+        FixedIds {
+            ids: ids.into_iter().collect(),
+        }
     }
+}
 
-    #[test]
-    #[should_panic(expected = "index out of bounds")]
-    fn test_panic_with_message() {
-        let v = vec![1, 2, 3];
-        let _ = v[99]; // This will panic with "index out of bounds"
+impl IdGenerator for FixedIds {
+    fn next_id(&mut self) -> u64 {
+        self.ids.pop_front().expect("FixedIds ran out of ids")
     }
+}
 
-    #[test]
-    #[should_panic(expected = "assertion failed")]
-    fn test_panic_on_assertion() {
-        assert!(false, "assertion failed: this should panic");
-    }
+/// Source of random numbers
+pub trait RandomSource {
+    fn next_u64(&mut self) -> u64;
+}
 
-    // ========================================================================
-    // TEST 12-14: IGNORED TESTS
-    // ========================================================================
+/// SplitMix64: small, fast, and fully determined by its seed, so the same
+/// seed gives the same sequence in every test run. Not for cryptography.
+pub struct SeededRng {
+    state: u64,
+}
 
-    #[test]
-    #[ignore]
-    fn expensive_test() {
-        // This test is ignored by default, run with: cargo test -- --ignored
-        let _calc = Calculator::new();
-        let result = Calculator::factorial(20);
-        assert!(result > 0);
+impl SeededRng {
+    pub fn new(seed: u64) -> Self {
+        SeededRng { state: seed }
     }
 
-    #[test]
-    #[ignore = "requires network connection"]
-    fn test_network_feature() {
-        // This test would require network access
-        assert!(true);
+    /// Seeds from the current time, for non-test use
+    pub fn from_time() -> Self {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos() as u64)
+            .unwrap_or(0);
+        Self::new(nanos)
     }
+}
 
-    #[test]
-    #[ignore = "slow computation"]
-    fn test_heavy_computation() {
-        let mut sum = 0;
-        for i in 0..1000 {
-            sum += i;
-        }
-        assert_eq!(sum, 499500);
+impl RandomSource for SeededRng {
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
     }
+}
 
-    // ========================================================================
-    // TEST 15-17: TESTING PRIVATE FUNCTIONS
-    // ========================================================================
+/// Registers users and issues email verification tokens that expire.
+///
+/// Time, ids and tokens all come from injected dependencies, which is what
+/// makes expiry and token values testable.
+pub struct SignupService {
+    users: UserService,
+    clock: Box<dyn Clock>,
+    rng: Box<dyn RandomSource>,
+    token_ttl: u64,
+    pending: HashMap<String, PendingVerification>,
+    verified: HashSet<u64>,
+}
 
-    #[test]
-    fn test_private_function_format_operation() {
-        let calc = Calculator::new();
-        // Can access private functions within the same module
-        let result = calc.format_operation("+", 2, 3, 5);
-        assert_eq!(result, "2 + 3 = 5");
+#[derive(Debug, Clone, PartialEq)]
+pub struct PendingVerification {
+    pub user_id: u64,
+    pub expires_at: u64,
+}
+
+impl SignupService {
+    /// Tokens are valid for a day unless configured otherwise
+    pub const DEFAULT_TOKEN_TTL: u64 = 24 * 60 * 60;
+
+    pub fn new(users: UserService, clock: Box<dyn Clock>, rng: Box<dyn RandomSource>) -> Self {
+        SignupService {
+            users,
+            clock,
+            rng,
+            token_ttl: Self::DEFAULT_TOKEN_TTL,
+            pending: HashMap::new(),
+            verified: HashSet::new(),
+        }
     }
 
-    #[test]
-    fn test_calculator_factorial() {
-        let _calc = Calculator::new();
-        assert_eq!(Calculator::factorial(0), 1);
-        assert_eq!(Calculator::factorial(1), 1);
-        assert_eq!(Calculator::factorial(5), 120);
-        assert_eq!(Calculator::factorial(10), 3628800);
+    /// Sets how long verification tokens stay valid, in seconds
+    pub fn with_token_ttl(mut self, seconds: u64) -> Self {
+        self.token_ttl = seconds;
+        self
     }
 
-    #[test]
-    fn test_default_implementations() {
-        let calc = Calculator::default();
-        assert_eq!(calc.history().len(), 0);
+    /// Creates the user and returns its id with a verification token
+    pub fn register(
+        &mut self,
+        username: String,
+        email: String,
+        age: u8,
+    ) -> Result<(u64, String), String> {
+        let user_id = self.users.create_user(username, email, age)?;
+        let token = format!("{:016x}", self.rng.next_u64());
+        self.pending.insert(
+            token.clone(),
+            PendingVerification {
+                user_id,
+                expires_at: self.clock.now() + self.token_ttl,
+            },
+        );
+        Ok((user_id, token))
+    }
 
-        let service = UserService::default();
-        assert_eq!(service.count(), 0);
+    /// Marks the token's user as verified. Tokens can only be used once.
+    pub fn verify(&mut self, token: &str) -> Result<u64, String> {
+        let pending = self
+            .pending
+            .remove(token)
+            .ok_or_else(|| "Unknown verification token".to_string())?;
+        if self.clock.now() >= pending.expires_at {
+            return Err("Verification token expired".to_string());
+        }
+        self.verified.insert(pending.user_id);
+        Ok(pending.user_id)
+    }
 
-        let account = BankAccount::default();
-        assert_eq!(account.balance(), 0.0);
+    pub fn is_verified(&self, user_id: u64) -> bool {
+        self.verified.contains(&user_id)
     }
 
-    // ========================================================================
-    // TEST 18-22: TESTING WITH SETUP AND TEARDOWN (FIXTURES)
-    // ========================================================================
+    pub fn pending(&self, token: &str) -> Option<&PendingVerification> {
+        self.pending.get(token)
+    }
 
-    struct TestFixture {
-        user_service: UserService,
-        test_users: Vec<u64>,
+    pub fn users(&self) -> &UserService {
+        &self.users
     }
 
-    impl TestFixture {
-        fn setup() -> Self {
-            let mut user_service = UserService::new();
-            let mut test_users = Vec::new();
+    pub fn users_mut(&mut self) -> &mut UserService {
+        &mut self.users
+    }
+}
 
-            // Create some test users
-            let id1 = user_service
-                .create_user("alice".to_string(), "alice@test.com".to_string(), 25)
-                .unwrap();
-            let id2 = user_service
-                .create_user("bob".to_string(), "bob@test.com".to_string(), 30)
-                .unwrap();
-            let id3 = user_service
-                .create_user("charlie".to_string(), "charlie@test.com".to_string(), 15)
-                .unwrap();
+// ============================================================================
+// SECTION 9: ASYNC NOTIFICATIONS (FOR TESTING ASYNC CODE)
+// ============================================================================
 
-            test_users.push(id1);
-            test_users.push(id2);
-            test_users.push(id3);
+/// Why a notification couldn't be delivered, after every attempt failed
+#[derive(Debug, Clone, PartialEq)]
+pub enum NotifyError {
+    /// The last attempt took longer than the service's timeout
+    TimedOut { attempts: u32 },
+    /// The last attempt was refused by the transport
+    Failed { attempts: u32, reason: String },
+}
 
-            TestFixture {
-                user_service,
-                test_users,
+impl std::fmt::Display for NotifyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NotifyError::TimedOut { attempts } => {
+                write!(f, "timed out after {} attempts", attempts)
+            }
+            NotifyError::Failed { attempts, reason } => {
+                write!(f, "failed after {} attempts: {}", attempts, reason)
             }
         }
     }
+}
 
-    #[test]
-    fn test_user_service_with_fixture() {
-        let fixture = TestFixture::setup();
-        assert_eq!(fixture.user_service.count(), 3);
-        assert_eq!(fixture.test_users.len(), 3);
-    }
+impl std::error::Error for NotifyError {}
 
-    #[test]
-    fn test_user_service_get_user() {
-        let fixture = TestFixture::setup();
-        let user = fixture.user_service.get_user(fixture.test_users[0]);
-        assert!(user.is_some());
-        assert_eq!(user.unwrap().username, "alice");
+/// Delivers one message, such as an email or a push notification
+#[allow(async_fn_in_trait)]
+pub trait Transport {
+    async fn send(&self, to: &str, message: &str) -> Result<(), String>;
+}
+
+/// A [`Transport`] that takes a fixed time per message and can be told to
+/// refuse the first few, standing in for a slow, flaky network
+#[derive(Debug, Default)]
+pub struct SimulatedTransport {
+    delay: Duration,
+    failures_left: Cell<u32>,
+    attempts: Cell<u32>,
+    sent: RefCell<Vec<(String, String)>>,
+}
+
+impl SimulatedTransport {
+    pub fn new(delay: Duration) -> Self {
+        SimulatedTransport {
+            delay,
+            ..Self::default()
+        }
     }
 
-    #[test]
-    fn test_user_service_delete_user() {
-        let mut fixture = TestFixture::setup();
-        let initial_count = fixture.user_service.count();
+    /// Refuses the next `failures` messages, after the usual delay
+    pub fn failing_first(self, failures: u32) -> Self {
+        self.failures_left.set(failures);
+        self
+    }
 
-        let deleted = fixture.user_service.delete_user(fixture.test_users[0]);
-        assert!(deleted);
-        assert_eq!(fixture.user_service.count(), initial_count - 1);
+    /// How many sends were started, including ones that timed out
+    pub fn attempts(&self) -> u32 {
+        self.attempts.get()
+    }
 
-        let user = fixture.user_service.get_user(fixture.test_users[0]);
-        assert!(user.is_none());
+    /// `(to, message)` for every message delivered, oldest first
+    pub fn sent(&self) -> Vec<(String, String)> {
+        self.sent.borrow().clone()
     }
+}
 
-    #[test]
-    fn test_user_service_find_by_username() {
-        let fixture = TestFixture::setup();
-        let user = fixture.user_service.find_by_username("bob");
-        assert!(user.is_some());
-        assert_eq!(user.unwrap().email, "bob@test.com");
+impl Transport for SimulatedTransport {
+    async fn send(&self, to: &str, message: &str) -> Result<(), String> {
+        self.attempts.set(self.attempts.get() + 1);
+        tokio::time::sleep(self.delay).await;
+        if self.failures_left.get() > 0 {
+            self.failures_left.set(self.failures_left.get() - 1);
+            return Err("Service unavailable".to_string());
+        }
+        self.sent
+            .borrow_mut()
+            .push((to.to_string(), message.to_string()));
+        Ok(())
+    }
+}
 
-        let user = fixture.user_service.find_by_username("nonexistent");
-        assert!(user.is_none());
+/// Sends notifications through a [`Transport`], giving each attempt a
+/// time limit and retrying failures with exponential backoff
+///
+/// The waits are real `tokio::time` sleeps. Tests pause tokio's clock, so
+/// they run instantly and can assert on exactly how much virtual time
+/// passed.
+pub struct NotificationService<T: Transport> {
+    transport: T,
+    timeout: Duration,
+    max_attempts: u32,
+    backoff: Duration,
+}
+
+impl<T: Transport> NotificationService<T> {
+    /// A service that tries 3 times, allows 1s per attempt, and waits
+    /// 100ms before the first retry
+    pub fn new(transport: T) -> Self {
+        NotificationService {
+            transport,
+            timeout: Duration::from_secs(1),
+            max_attempts: 3,
+            backoff: Duration::from_millis(100),
+        }
     }
 
-    #[test]
-    fn test_user_is_adult() {
-        let fixture = TestFixture::setup();
-        let alice = fixture
-            .user_service
-            .get_user(fixture.test_users[0])
-            .unwrap();
-        let charlie = fixture
-            .user_service
-            .get_user(fixture.test_users[2])
-            .unwrap();
+    /// Gives up on an attempt that takes longer than `timeout`
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
 
-        assert!(alice.is_adult());
-        assert!(!charlie.is_adult());
+    /// Makes up to `max_attempts` attempts, waiting `backoff` before the
+    /// first retry and twice as long before each one after that
+    pub fn with_retries(mut self, max_attempts: u32, backoff: Duration) -> Self {
+        assert!(max_attempts > 0, "At least one attempt is needed");
+        self.max_attempts = max_attempts;
+        self.backoff = backoff;
+        self
     }
 
-    // ========================================================================
-    // TEST 23-25: TESTING FILE PROCESSOR (TRAIT TESTING)
-    // ========================================================================
+    pub fn transport(&self) -> &T {
+        &self.transport
+    }
 
-    #[test]
-    fn test_file_processor_process() {
-        let processor = FileProcessor::new("PREFIX".to_string());
-        let result = processor.process("hello");
-        assert_eq!(result, "PREFIX: HELLO");
+    /// Sends `message` to `to`, returning how many attempts it took
+    pub async fn notify(&self, to: &str, message: &str) -> Result<u32, NotifyError> {
+        let mut backoff = self.backoff;
+        let mut attempt = 1;
+        loop {
+            let error =
+                match tokio::time::timeout(self.timeout, self.transport.send(to, message)).await {
+                    Ok(Ok(())) => return Ok(attempt),
+                    Ok(Err(reason)) => NotifyError::Failed {
+                        attempts: attempt,
+                        reason,
+                    },
+                    Err(_) => NotifyError::TimedOut { attempts: attempt },
+                };
+            if attempt == self.max_attempts {
+                return Err(error);
+            }
+            tokio::time::sleep(backoff).await;
+            backoff *= 2;
+            attempt += 1;
+        }
     }
+}
 
-    #[test]
-    fn test_file_processor_validate() {
-        let processor = FileProcessor::new("TEST".to_string());
-        assert!(processor.validate("valid data"));
-        assert!(!processor.validate(""));
+// ============================================================================
+// SECTION 10: SHARED ACCOUNT (FOR TESTING CONCURRENT CODE)
+// ============================================================================
 
-        let long_string = "a".repeat(1001);
-        assert!(!processor.validate(&long_string));
+#[cfg(feature = "loom")]
+use loom::sync::atomic::{AtomicU64, Ordering};
+#[cfg(not(feature = "loom"))]
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// An account balance that many threads can change at once, shared
+/// behind an `Arc`
+///
+/// The balance is a count of cents in a single atomic, and every change is
+/// one compare-and-swap. A withdrawal checks the funds and takes them in
+/// the same step, so two threads can never both spend the last of the
+/// money. Built with the `loom` feature, the atomic is loom's, so the loom
+/// tests can try every way the threads could interleave.
+///
+/// ```
+/// use std::sync::Arc;
+/// use testing::SharedAccount;
+///
+/// let account = Arc::new(SharedAccount::with_balance(10.0));
+/// let handles: Vec<_> = (0..4)
+///     .map(|_| {
+///         let account = Arc::clone(&account);
+///         std::thread::spawn(move || account.deposit(2.5))
+///     })
+///     .collect();
+/// for handle in handles {
+///     handle.join().unwrap().unwrap();
+/// }
+/// assert_eq!(account.balance(), 20.0);
+/// ```
+#[derive(Debug)]
+pub struct SharedAccount {
+    cents: AtomicU64,
+}
+
+impl SharedAccount {
+    pub fn new() -> Self {
+        SharedAccount {
+            cents: AtomicU64::new(0),
+        }
     }
 
-    #[test]
-    fn test_data_processor_trait() {
-        let processor: Box<dyn DataProcessor> = Box::new(FileProcessor::new("BOX".to_string()));
-        let result = processor.process("test");
-        assert!(result.contains("BOX"));
-        assert!(result.contains("TEST"));
+    /// Panics if `balance` is negative, not a number, or too large
+    pub fn with_balance(balance: f64) -> Self {
+        let cents = if balance == 0.0 {
+            Some(0)
+        } else {
+            to_cents(balance)
+        };
+        SharedAccount {
+            cents: AtomicU64::new(cents.expect("Opening balance must be a non-negative amount")),
+        }
     }
 
-    // ========================================================================
-    // TEST 26-28: TEMPERATURE CONVERSION TESTS
-    // ========================================================================
+    pub fn balance(&self) -> f64 {
+        self.cents.load(Ordering::Acquire) as f64 / 100.0
+    }
 
-    #[test]
-    fn test_celsius_to_fahrenheit() {
-        assert_eq!(celsius_to_fahrenheit(0.0), 32.0);
-        assert_eq!(celsius_to_fahrenheit(100.0), 212.0);
-        assert_eq!(celsius_to_fahrenheit(-40.0), -40.0);
+    /// Amounts are rounded to the nearest cent
+    pub fn deposit(&self, amount: f64) -> Result<(), String> {
+        let cents = to_cents(amount).ok_or("Deposit amount must be positive")?;
+        self.cents
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |balance| {
+                balance.checked_add(cents)
+            })
+            .map(|_| ())
+            .map_err(|_| "Balance would overflow".to_string())
     }
 
-    #[test]
-    fn test_fahrenheit_to_celsius() {
-        assert_eq!(fahrenheit_to_celsius(32.0), 0.0);
-        assert_eq!(fahrenheit_to_celsius(212.0), 100.0);
-        assert_eq!(fahrenheit_to_celsius(-40.0), -40.0);
+    /// Amounts are rounded to the nearest cent
+    pub fn withdraw(&self, amount: f64) -> Result<(), String> {
+        let cents = to_cents(amount).ok_or("Withdrawal amount must be positive")?;
+        self.cents
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |balance| {
+                balance.checked_sub(cents)
+            })
+            .map(|_| ())
+            .map_err(|_| "Insufficient funds".to_string())
     }
+}
 
-    #[test]
-    fn test_temperature_conversion_roundtrip() {
-        let celsius = 25.0;
-        let fahrenheit = celsius_to_fahrenheit(celsius);
-        let back_to_celsius = fahrenheit_to_celsius(fahrenheit);
-        assert!((celsius - back_to_celsius).abs() < 0.0001);
+impl Default for SharedAccount {
+    fn default() -> Self {
+        Self::new()
     }
+}
 
-    // ========================================================================
-    // TEST 29-31: STRING UTILITY TESTS
-    // ========================================================================
+/// `amount` in whole cents, or `None` unless it's at least one cent and
+/// fits in a `u64`
+fn to_cents(amount: f64) -> Option<u64> {
+    let cents = (amount * 100.0).round();
+    // NaN fails both comparisons
+    (cents >= 1.0 && cents < u64::MAX as f64).then_some(cents as u64)
+}
 
-    #[test]
-    fn test_reverse_string() {
-        assert_eq!(reverse_string("hello"), "olleh");
-        assert_eq!(reverse_string(""), "");
-        assert_eq!(reverse_string("a"), "a");
-        assert_eq!(reverse_string("Rust"), "tsuR");
+// ============================================================================
+// SECTION 11: EXCHANGE RATES (FOR TESTING HTTP CLIENTS)
+// ============================================================================
+
+/// Why an exchange rate couldn't be fetched
+#[derive(Debug, Clone, PartialEq)]
+pub enum RateError {
+    /// The server answered, but not with a success status
+    Status(u16),
+    /// No answer within the client's timeout
+    TimedOut,
+    /// The server couldn't be reached at all
+    Unavailable(String),
+    /// The answer wasn't the JSON the client expected
+    Malformed(String),
+    /// The answer had no rate for the currency asked for
+    UnknownCurrency(String),
+}
+
+impl std::fmt::Display for RateError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RateError::Status(status) => write!(f, "rate server returned status {}", status),
+            RateError::TimedOut => write!(f, "rate server timed out"),
+            RateError::Unavailable(reason) => write!(f, "rate server unavailable: {}", reason),
+            RateError::Malformed(reason) => write!(f, "malformed rate response: {}", reason),
+            RateError::UnknownCurrency(code) => write!(f, "no rate for {}", code),
+        }
     }
+}
 
-    #[test]
-    fn test_is_palindrome() {
-        assert!(is_palindrome("racecar"));
-        assert!(is_palindrome("A man a plan a canal Panama"));
-        assert!(is_palindrome(""));
-        assert!(is_palindrome("a"));
-        assert!(!is_palindrome("hello"));
+impl std::error::Error for RateError {}
+
+/// Looks up exchange rates, over the network or not. Currency codes are
+/// ISO 4217, like `USD`.
+#[allow(async_fn_in_trait)]
+pub trait ExchangeRateClient {
+    /// How many units of `to` one unit of `from` buys
+    async fn rate(&self, from: &str, to: &str) -> Result<f64, RateError>;
+}
+
+/// An [`ExchangeRateClient`] for an HTTP server that answers
+/// `GET {base_url}/latest?base=USD&symbols=EUR` with
+/// `{"base": "USD", "rates": {"EUR": 0.92}}`
+///
+/// `base_url` can be `http://` or `https://`; TLS is handled by rustls.
+#[derive(Debug, Clone)]
+pub struct HttpRateClient {
+    client: reqwest::Client,
+    base_url: String,
+}
+
+#[derive(Deserialize)]
+struct RatesResponse {
+    rates: HashMap<String, f64>,
+}
+
+impl HttpRateClient {
+    /// Requests that take longer than this fail with `RateError::TimedOut`
+    pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
+
+    pub fn new(base_url: impl Into<String>) -> Self {
+        HttpRateClient {
+            client: Self::client(Self::DEFAULT_TIMEOUT),
+            base_url: base_url.into(),
+        }
     }
 
-    #[test]
-    fn test_palindrome_case_insensitive() {
-        assert!(is_palindrome("RaceCar"));
-        assert!(is_palindrome("No lemon, no melon"));
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.client = Self::client(timeout);
+        self
     }
 
-    // ========================================================================
-    // TEST 32-34: VECTOR UTILITY TESTS (GENERICS)
-    // ========================================================================
+    fn client(timeout: Duration) -> reqwest::Client {
+        reqwest::Client::builder()
+            .timeout(timeout)
+            .build()
+            .expect("a client with only a timeout set always builds")
+    }
+}
 
-    #[test]
-    fn test_find_max() {
-        assert_eq!(find_max(&[1, 5, 3, 2]), Some(5));
-        assert_eq!(find_max(&[-1, -5, -3]), Some(-1));
-        assert_eq!(find_max(&[42]), Some(42));
-        assert_eq!(find_max::<i32>(&[]), None);
+impl ExchangeRateClient for HttpRateClient {
+    async fn rate(&self, from: &str, to: &str) -> Result<f64, RateError> {
+        let url = format!("{}/latest", self.base_url.trim_end_matches('/'));
+        let response = self
+            .client
+            .get(url)
+            .query(&[("base", from), ("symbols", to)])
+            .send()
+            .await
+            .map_err(rate_error)?;
+        if !response.status().is_success() {
+            return Err(RateError::Status(response.status().as_u16()));
+        }
+        let body: RatesResponse = response.json().await.map_err(rate_error)?;
+        match body.rates.get(to) {
+            Some(&rate) if rate > 0.0 && rate.is_finite() => Ok(rate),
+            Some(rate) => Err(RateError::Malformed(format!("rate {} for {}", rate, to))),
+            None => Err(RateError::UnknownCurrency(to.to_string())),
+        }
     }
+}
 
-    #[test]
-    fn test_find_min() {
-        assert_eq!(find_min(&[1, 5, 3, 2]), Some(1));
-        assert_eq!(find_min(&[-1, -5, -3]), Some(-5));
-        assert_eq!(find_min(&[42]), Some(42));
-        assert_eq!(find_min::<i32>(&[]), None);
+fn rate_error(e: reqwest::Error) -> RateError {
+    // A timeout while reading the body is a decode error too, so check
+    // for timeouts first
+    if e.is_timeout() {
+        RateError::TimedOut
+    } else if e.is_decode() {
+        RateError::Malformed(e.to_string())
+    } else {
+        RateError::Unavailable(e.to_string())
     }
+}
 
-    #[test]
-    fn test_find_with_chars() {
-        assert_eq!(find_max(&['a', 'z', 'm']), Some('z'));
-        assert_eq!(find_min(&['a', 'z', 'm']), Some('a'));
+/// An [`ExchangeRateClient`] with rates set up front, which counts the
+/// lookups it's asked for
+#[derive(Debug, Default)]
+pub struct FixedRates {
+    rates: HashMap<(String, String), f64>,
+    lookups: Cell<u32>,
+}
+
+impl FixedRates {
+    pub fn new() -> Self {
+        Self::default()
     }
 
-    // ========================================================================
-    // TEST 35-37: BANK ACCOUNT TESTS (STATEFUL TESTING)
-    // ========================================================================
+    pub fn with_rate(mut self, from: &str, to: &str, rate: f64) -> Self {
+        self.rates.insert((from.to_string(), to.to_string()), rate);
+        self
+    }
 
-    #[test]
-    fn test_bank_account_deposit() {
-        let mut account = BankAccount::new();
-        assert_eq!(account.balance(), 0.0);
+    pub fn lookups(&self) -> u32 {
+        self.lookups.get()
+    }
+}
 
-        account.deposit(100.0).unwrap();
-        assert_eq!(account.balance(), 100.0);
+impl ExchangeRateClient for FixedRates {
+    async fn rate(&self, from: &str, to: &str) -> Result<f64, RateError> {
+        self.lookups.set(self.lookups.get() + 1);
+        self.rates
+            .get(&(from.to_string(), to.to_string()))
+            .copied()
+            .ok_or_else(|| RateError::UnknownCurrency(to.to_string()))
+    }
+}
 
-        account.deposit(50.0).unwrap();
-        assert_eq!(account.balance(), 150.0);
-        assert_eq!(account.transaction_count(), 2);
+/// Converts amounts between currencies at rates from an
+/// [`ExchangeRateClient`]
+///
+/// Each pair of currencies is looked up once, and its rate reused from
+/// then on. Results are rounded to the nearest cent.
+///
+/// ```
+/// use testing::{CurrencyConverter, FixedRates};
+///
+/// let converter = CurrencyConverter::new(FixedRates::new().with_rate("USD", "EUR", 0.9));
+/// let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+///
+/// assert_eq!(runtime.block_on(converter.convert(10.0, "usd", "EUR")), Ok(9.0));
+/// assert_eq!(runtime.block_on(converter.convert(20.0, "USD", "EUR")), Ok(18.0));
+/// assert_eq!(converter.client().lookups(), 1);
+/// ```
+#[derive(Debug)]
+pub struct CurrencyConverter<C> {
+    client: C,
+    rates: RefCell<HashMap<(String, String), f64>>,
+}
+
+impl<C: ExchangeRateClient> CurrencyConverter<C> {
+    pub fn new(client: C) -> Self {
+        CurrencyConverter {
+            client,
+            rates: RefCell::new(HashMap::new()),
+        }
     }
 
-    #[test]
-    fn test_bank_account_withdraw() {
-        let mut account = BankAccount::new();
-        account.deposit(100.0).unwrap();
+    pub fn client(&self) -> &C {
+        &self.client
+    }
 
-        account.withdraw(30.0).unwrap();
-        assert_eq!(account.balance(), 70.0);
-        assert_eq!(account.transaction_count(), 2);
+    /// Currency codes may be in any case
+    pub async fn convert(&self, amount: f64, from: &str, to: &str) -> Result<f64, RateError> {
+        let rate = self.rate(&from.to_uppercase(), &to.to_uppercase()).await?;
+        Ok(round_to_cents(amount * rate))
     }
 
-    #[test]
-    fn test_bank_account_insufficient_funds() {
-        let mut account = BankAccount::new();
-        account.deposit(50.0).unwrap();
+    async fn rate(&self, from: &str, to: &str) -> Result<f64, RateError> {
+        if from == to {
+            return Ok(1.0);
+        }
+        let pair = (from.to_string(), to.to_string());
+        if let Some(&rate) = self.rates.borrow().get(&pair) {
+            return Ok(rate);
+        }
+        // Errors aren't cached, so a failed lookup is tried again next time
+        let rate = self.client.rate(from, to).await?;
+        self.rates.borrow_mut().insert(pair, rate);
+        Ok(rate)
+    }
+}
 
-        let result = account.withdraw(100.0);
-        assert!(result.is_err());
-        assert_eq!(result.unwrap_err(), "Insufficient funds");
-        assert_eq!(account.balance(), 50.0);
+// ============================================================================
+// MAIN FUNCTION
+// ============================================================================
+
+fn main() {
+    println!("=== Rust Testing Examples ===\n");
+
+    // Calculator demo
+    let mut calc = Calculator::new();
+    println!("Calculator:");
+    println!("  2 + 3 = {}", calc.add(2, 3));
+    println!("  10 - 4 = {}", calc.subtract(10, 4));
+    println!("  4 * 5 = {}", calc.multiply(4, 5));
+    println!("  10 / 2 = {:?}", calc.divide(10, 2));
+    println!("  5! = {}", Calculator::factorial(5));
+    for expression in ["2 + 3 * (4 - 1)", "-(8 - 3 - 2) * 4", "7 / (3 - 3)"] {
+        match calc.evaluate(expression) {
+            Ok(value) => println!("  {} = {}", expression, value),
+            Err(e) => println!("  {}: {}", expression, e),
+        }
+    }
+    if let Some(undone) = calc.undo_last() {
+        println!("  Undid: {}", undone);
+    }
+    println!("  History ({} entries):", calc.history().len());
+    for entry in calc.history() {
+        println!("    {}", entry);
     }
 
-    // ========================================================================
-    // TEST 38-40: PARAMETERIZED TESTS (TABLE-DRIVEN TESTS)
-    // ========================================================================
+    // User service demo
+    println!("\nUser Service:");
+    let mut user_service = UserService::new();
+    match user_service.create_user("alice".to_string(), "alice@example.com".to_string(), 25) {
+        Ok(id) => {
+            println!("  Created user with ID: {}", id);
+            if let Some(user) = user_service.get_user(id) {
+                println!("  User: {:?}", user);
+                println!("  Is adult: {}", user.is_adult());
+            }
+        }
+        Err(e) => println!("  Error: {}", e),
+    }
+
+    // File processor demo
+    println!("\nFile Processor:");
+    let processor = FileProcessor::new("PROCESSED".to_string());
+    let result = processor.process("hello world");
+    println!("  Result: {}", result);
+    println!("  Valid: {}", processor.validate("hello"));
+
+    // Temperature conversion demo
+    println!("\nTemperature Conversion:");
+    println!("  0°C = {}°F", celsius_to_fahrenheit(0.0));
+    println!("  100°C = {}°F", celsius_to_fahrenheit(100.0));
+    println!("  32°F = {}°C", fahrenheit_to_celsius(32.0));
+
+    // String utilities demo
+    println!("\nString Utilities:");
+    println!("  Reverse 'hello': {}", reverse_string("hello"));
+    println!("  Is 'racecar' palindrome: {}", is_palindrome("racecar"));
+    println!("  Is 'hello' palindrome: {}", is_palindrome("hello"));
+
+    // Vector utilities demo
+    println!("\nVector Utilities:");
+    let numbers = vec![5, 2, 8, 1, 9];
+    println!("  Max of {:?}: {:?}", numbers, find_max(&numbers));
+    println!("  Min of {:?}: {:?}", numbers, find_min(&numbers));
+
+    // Bank account demo
+    println!("\nBank Account:");
+    let mut account = BankAccount::new();
+    account.deposit(100.0).unwrap();
+    println!("  After deposit of $100: ${}", account.balance());
+    account.withdraw(30.0).unwrap();
+    println!("  After withdrawal of $30: ${}", account.balance());
+    println!("  Total transactions: {}", account.transaction_count());
+    let mut savings = BankAccount::new();
+    account
+        .transfer(&mut savings, 50.0)
+        .expect("the account holds $70");
+    println!(
+        "  After transferring $50 to savings: ${} (savings ${})",
+        account.balance(),
+        savings.balance()
+    );
+    if let Ok(interest) = savings.apply_interest(0.015) {
+        println!("  Savings interest at 1.5%: ${}", interest);
+    }
+    println!("  Statement:");
+    for line in account.statement(0, u64::MAX).to_string().lines() {
+        println!("    {}", line);
+    }
+
+    // Signup service demo
+    println!("\nSignup Service:");
+    let mut signups = SignupService::new(
+        UserService::new(),
+        Box::new(SystemClock),
+        Box::new(SeededRng::from_time()),
+    );
+    if let Ok((id, token)) =
+        signups.register("dana".to_string(), "dana@example.com".to_string(), 31)
+    {
+        println!("  Registered user {} with token {}", id, token);
+        println!("  Verified: {:?}", signups.verify(&token));
+    }
+
+    // Notification service demo
+    println!("\nNotification Service:");
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_time()
+        .build()
+        .expect("Failed to start the async runtime");
+    let notifications = NotificationService::new(
+        SimulatedTransport::new(Duration::from_millis(20)).failing_first(1),
+    );
+    match runtime.block_on(notifications.notify("dana@example.com", "Welcome!")) {
+        Ok(attempts) => println!("  Delivered after {} attempts", attempts),
+        Err(e) => println!("  Error: {}", e),
+    }
+
+    // Shared account demo
+    println!("\nShared Account:");
+    let shared = SharedAccount::with_balance(100.0);
+    std::thread::scope(|scope| {
+        for _ in 0..4 {
+            scope.spawn(|| {
+                for _ in 0..10 {
+                    let _ = shared.withdraw(3.0);
+                }
+            });
+        }
+    });
+    println!(
+        "  Balance after 40 withdrawals of $3 from 4 threads: ${:.2}",
+        shared.balance()
+    );
+
+    // Currency converter demo
+    println!("\nCurrency Converter:");
+    let converter = CurrencyConverter::new(FixedRates::new().with_rate("USD", "EUR", 0.92));
+    match runtime.block_on(converter.convert(250.0, "USD", "EUR")) {
+        Ok(euros) => println!("  $250.00 is EUR {:.2}", euros),
+        Err(e) => println!("  Error: {}", e),
+    }
+
+    println!("\n=== Run 'cargo test' to execute all tests ===");
+}
+
+// ============================================================================
+// TESTS MODULE
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builders::{self, AccountBuilder, UserBuilder};
+    use rstest::{fixture, rstest};
+
+    // ========================================================================
+    // TEST 1-5: BASIC UNIT TESTS WITH ASSERTIONS
+    // ========================================================================
+
+    #[test]
+    fn test_calculator_add() {
+        let mut calc = Calculator::new();
+        assert_eq!(calc.add(2, 3), 5);
+        assert_eq!(calc.add(-1, 1), 0);
+        assert_eq!(calc.add(0, 0), 0);
+    }
+
+    #[test]
+    fn test_calculator_subtract() {
+        let mut calc = Calculator::new();
+        assert_eq!(calc.subtract(10, 4), 6);
+        assert_eq!(calc.subtract(5, 5), 0);
+        assert_eq!(calc.subtract(0, 10), -10);
+    }
+
+    #[test]
+    fn test_calculator_multiply() {
+        let mut calc = Calculator::new();
+        assert_eq!(calc.multiply(4, 5), 20);
+        assert_eq!(calc.multiply(0, 100), 0);
+        assert_eq!(calc.multiply(-2, 3), -6);
+    }
+
+    #[test]
+    fn test_calculator_divide_success() {
+        let mut calc = Calculator::new();
+        assert_eq!(calc.divide(10, 2), Ok(5));
+        assert_eq!(calc.divide(9, 3), Ok(3));
+        assert_eq!(calc.divide(-10, 2), Ok(-5));
+    }
+
+    #[test]
+    fn test_calculator_divide_by_zero() {
+        let mut calc = Calculator::new();
+        assert_eq!(calc.divide(10, 0), Err("Division by zero"));
+        assert!(calc.divide(0, 0).is_err());
+    }
+
+    // ========================================================================
+    // TEST 6-8: TESTING WITH ASSERT!, ASSERT_NE!
+    // ========================================================================
+
+    #[test]
+    fn test_assertions_various() {
+        let mut calc = Calculator::new();
+        let result = calc.add(2, 2);
+
+        assert!(result > 0, "Result should be positive");
+        assert!(result == 4, "Result should equal 4");
+        assert_ne!(result, 5, "Result should not equal 5");
+    }
+
+    #[test]
+    fn test_user_validation() {
+        let user = User::new(1, "alice".to_string(), "alice@test.com".to_string(), 25);
+        assert!(user.is_ok(), "User creation should succeed");
+
+        let user = user.unwrap();
+        assert_eq!(user.username, "alice");
+        assert!(user.email.contains('@'));
+        assert!(user.is_adult());
+    }
+
+    #[test]
+    fn test_user_validation_failures() {
+        // Empty username
+        let result = User::new(1, "".to_string(), "test@test.com".to_string(), 20);
+        assert!(result.is_err());
+        assert_eq!(result.unwrap_err(), "Username cannot be empty");
+
+        // Invalid email
+        let result = User::new(1, "bob".to_string(), "invalid-email".to_string(), 20);
+        assert!(result.is_err());
+        assert_eq!(result.unwrap_err(), "Invalid email format");
+
+        // Under age
+        let result = User::new(1, "kid".to_string(), "kid@test.com".to_string(), 10);
+        assert!(result.is_err());
+        assert_eq!(result.unwrap_err(), "User must be at least 13 years old");
+    }
+
+    // ========================================================================
+    // TEST 9-11: TESTING PANICS WITH #[should_panic]
+    // ========================================================================
+
+    #[test]
+    #[should_panic]
+    fn test_panic_basic() {
+        panic!("This test should panic");
+    }
+
+    #[test]
+    #[should_panic(expected = "index out of bounds")]
+    #[allow(clippy::useless_vec)] // an array index would be rejected at compile time
+    fn test_panic_with_message() {
+        let v = vec![1, 2, 3];
+        let _ = v[99]; // This will panic with "index out of bounds"
+    }
+
+    #[test]
+    #[should_panic(expected = "assertion failed")]
+    #[allow(clippy::assertions_on_constants)]
+    fn test_panic_on_assertion() {
+        assert!(false, "assertion failed: this should panic");
+    }
+
+    // ========================================================================
+    // TEST 12-14: IGNORED TESTS
+    // ========================================================================
+
+    #[test]
+    #[ignore]
+    fn expensive_test() {
+        // This test is ignored by default, run with: cargo test -- --ignored
+        let _calc = Calculator::new();
+        let result = Calculator::factorial(20);
+        assert!(result > 0);
+    }
+
+    #[test]
+    #[ignore = "requires network connection"]
+    #[allow(clippy::assertions_on_constants)]
+    fn test_network_feature() {
+        // This test would require network access
+        assert!(true);
+    }
+
+    #[test]
+    #[ignore = "slow computation"]
+    fn test_heavy_computation() {
+        let mut sum = 0;
+        for i in 0..1000 {
+            sum += i;
+        }
+        assert_eq!(sum, 499500);
+    }
+
+    // ========================================================================
+    // TEST 15-17: TESTING PRIVATE FUNCTIONS
+    // ========================================================================
+
+    #[test]
+    fn test_private_function_format_operation() {
+        let calc = Calculator::new();
+        // Can access private functions within the same module
+        let result = calc.format_operation("+", 2, 3, 5);
+        assert_eq!(result, "2 + 3 = 5");
+    }
+
+    #[test]
+    fn test_factorial_iterative_matches_recursive() {
+        // 20! is the largest that fits in a u64
+        for n in 0..=20 {
+            assert_eq!(
+                Calculator::factorial_iterative(n),
+                Calculator::factorial(n),
+                "{}!",
+                n
+            );
+        }
+    }
+
+    #[test]
+    fn test_calculator_factorial() {
+        let _calc = Calculator::new();
+        assert_eq!(Calculator::factorial(0), 1);
+        assert_eq!(Calculator::factorial(1), 1);
+        assert_eq!(Calculator::factorial(5), 120);
+        assert_eq!(Calculator::factorial(10), 3628800);
+    }
+
+    #[test]
+    fn test_default_implementations() {
+        let calc = Calculator::default();
+        assert_eq!(calc.history().len(), 0);
+
+        let service = UserService::default();
+        assert_eq!(service.count(), 0);
+
+        let account = BankAccount::default();
+        assert_eq!(account.balance(), 0.0);
+    }
+
+    // ========================================================================
+    // TEST 18-22: TESTING WITH SETUP AND TEARDOWN (FIXTURES)
+    // ========================================================================
+
+    /// One fixture for everything nondeterministic: a mock clock, fixed
+    /// ids, a seeded RNG and the in-memory user store, already wired into
+    /// a `SignupService`. Tests override only what they care about.
+    struct TestEnv {
+        clock: MockClock,
+        signups: SignupService,
+        user_ids: HashMap<&'static str, u64>,
+    }
+
+    struct TestEnvBuilder {
+        start: u64,
+        ids: Option<Vec<u64>>,
+        seed: u64,
+        users: Vec<(&'static str, u8)>,
+    }
+
+    impl TestEnvBuilder {
+        fn at(mut self, start: u64) -> Self {
+            self.start = start;
+            self
+        }
+
+        fn ids(mut self, ids: impl IntoIterator<Item = u64>) -> Self {
+            self.ids = Some(ids.into_iter().collect());
+            self
+        }
+
+        fn seed(mut self, seed: u64) -> Self {
+            self.seed = seed;
+            self
+        }
+
+        /// Pre-creates a user with email `<username>@test.com`
+        fn user(mut self, username: &'static str, age: u8) -> Self {
+            self.users.push((username, age));
+            self
+        }
+
+        fn build(self) -> TestEnv {
+            let ids: Box<dyn IdGenerator> = match self.ids {
+                Some(ids) => Box::new(FixedIds::new(ids)),
+                None => Box::new(SequentialIds::default()),
+            };
+            let mut users = UserService::with_id_generator(ids);
+            let mut user_ids = HashMap::new();
+            for (username, age) in self.users {
+                let id = UserBuilder::new()
+                    .username(username)
+                    .age(age)
+                    .create_in(&mut users);
+                user_ids.insert(username, id);
+            }
+
+            let clock = MockClock::new(self.start);
+            let signups = SignupService::new(
+                users,
+                Box::new(clock.clone()),
+                Box::new(SeededRng::new(self.seed)),
+            );
+            TestEnv {
+                clock,
+                signups,
+                user_ids,
+            }
+        }
+    }
+
+    /// The usual environment: alice (25), bob (30) and charlie (15).
+    /// `#[rstest]` tests get a fresh one by naming an `env` argument.
+    #[fixture]
+    fn env() -> TestEnv {
+        TestEnv::builder()
+            .user("alice", 25)
+            .user("bob", 30)
+            .user("charlie", 15)
+            .build()
+    }
+
+    /// A `UserService` holding alice (25), adam (17), bob (30), anna (13)
+    /// and carol (64), with ids 1 to 5 in that order
+    #[fixture]
+    fn user_service() -> UserService {
+        let mut service = UserService::new();
+        for (username, age) in [
+            ("alice", 25),
+            ("adam", 17),
+            ("bob", 30),
+            ("anna", 13),
+            ("carol", 64),
+        ] {
+            UserBuilder::new()
+                .username(username)
+                .age(age)
+                .create_in(&mut service);
+        }
+        service
+    }
+
+    /// An account with one deposit of `balance`, 100.0 unless a test asks
+    /// for another with `#[with(...)]`
+    #[fixture]
+    fn funded_account(#[default(100.0)] balance: f64) -> BankAccount {
+        AccountBuilder::new().balance(balance).build()
+    }
+
+    impl TestEnv {
+        fn builder() -> TestEnvBuilder {
+            TestEnvBuilder {
+                start: 1_700_000_000,
+                ids: None,
+                seed: 42,
+                users: Vec::new(),
+            }
+        }
+
+        fn users(&self) -> &UserService {
+            self.signups.users()
+        }
+
+        fn id(&self, username: &str) -> u64 {
+            self.user_ids[username]
+        }
+    }
+
+    #[rstest]
+    fn test_user_service_with_fixture(env: TestEnv) {
+        assert_eq!(env.users().count(), 3);
+        assert_eq!(env.user_ids.len(), 3);
+    }
+
+    #[rstest]
+    fn test_user_service_get_user(env: TestEnv) {
+        let user = env.users().get_user(env.id("alice"));
+        assert!(user.is_some());
+        assert_eq!(user.unwrap().username, "alice");
+    }
+
+    #[rstest]
+    fn test_user_service_delete_user(mut env: TestEnv) {
+        let alice = env.id("alice");
+        let initial_count = env.users().count();
+
+        let deleted = env.signups.users_mut().delete_user(alice).unwrap();
+        assert!(deleted);
+        assert_eq!(env.users().count(), initial_count - 1);
+        assert!(env.users().get_user(alice).is_none());
+    }
+
+    #[rstest]
+    fn test_user_service_find_by_username(env: TestEnv) {
+        let user = env.users().find_by_username("bob");
+        assert!(user.is_some());
+        assert_eq!(user.unwrap().email, "bob@test.com");
+
+        let user = env.users().find_by_username("nonexistent");
+        assert!(user.is_none());
+    }
+
+    #[rstest]
+    fn test_user_is_adult(env: TestEnv) {
+        let alice = env.users().get_user(env.id("alice")).unwrap();
+        let charlie = env.users().get_user(env.id("charlie")).unwrap();
+
+        assert!(alice.is_adult());
+        assert!(!charlie.is_adult());
+    }
+
+    #[test]
+    fn test_fixed_ids() {
+        let env = TestEnv::builder()
+            .ids([700, 701])
+            .user("alice", 25)
+            .user("bob", 30)
+            .build();
+        assert_eq!(env.id("alice"), 700);
+        assert_eq!(env.users().get_user(701).unwrap().username, "bob");
+    }
+
+    #[test]
+    fn test_signup_verification() {
+        let mut env = TestEnv::builder().build();
+        let (id, token) = env
+            .signups
+            .register("dana".to_string(), "dana@test.com".to_string(), 31)
+            .unwrap();
+
+        assert!(!env.signups.is_verified(id));
+        assert_eq!(env.signups.verify(&token), Ok(id));
+        assert!(env.signups.is_verified(id));
+        assert_eq!(
+            env.signups.verify(&token),
+            Err("Unknown verification token".to_string())
+        );
+    }
+
+    #[test]
+    fn test_signup_token_expiry() {
+        let mut env = TestEnv::builder().at(1_000).build();
+        let (_, token) = env
+            .signups
+            .register("dana".to_string(), "dana@test.com".to_string(), 31)
+            .unwrap();
+        let expires_at = env.signups.pending(&token).unwrap().expires_at;
+        assert_eq!(expires_at, 1_000 + SignupService::DEFAULT_TOKEN_TTL);
+
+        // One second before the deadline the token still works...
+        let (_, other) = env
+            .signups
+            .register("erin".to_string(), "erin@test.com".to_string(), 40)
+            .unwrap();
+        env.clock.set(expires_at - 1);
+        assert!(env.signups.verify(&other).is_ok());
+
+        // ...and at the deadline it doesn't
+        env.clock.advance(1);
+        assert_eq!(
+            env.signups.verify(&token),
+            Err("Verification token expired".to_string())
+        );
+    }
+
+    #[test]
+    fn test_signup_tokens_are_deterministic() {
+        let register = |seed| {
+            let mut env = TestEnv::builder().seed(seed).build();
+            env.signups
+                .register("dana".to_string(), "dana@test.com".to_string(), 31)
+                .unwrap()
+                .1
+        };
+        assert_eq!(register(7), register(7));
+        assert_ne!(register(7), register(8));
+    }
+
+    // ========================================================================
+    // TEST 23-25: TESTING FILE PROCESSOR (TRAIT TESTING)
+    // ========================================================================
+
+    #[test]
+    fn test_file_processor_process() {
+        let processor = FileProcessor::new("PREFIX".to_string());
+        let result = processor.process("hello");
+        assert_eq!(result, "PREFIX: HELLO");
+    }
+
+    #[test]
+    fn test_file_processor_validate() {
+        let processor = FileProcessor::new("TEST".to_string());
+        assert!(processor.validate("valid data"));
+        assert!(!processor.validate(""));
+
+        let long_string = "a".repeat(1001);
+        assert!(!processor.validate(&long_string));
+    }
+
+    #[test]
+    fn test_data_processor_trait() {
+        let processor: Box<dyn DataProcessor> = Box::new(FileProcessor::new("BOX".to_string()));
+        let result = processor.process("test");
+        assert!(result.contains("BOX"));
+        assert!(result.contains("TEST"));
+    }
+
+    // ========================================================================
+    // TEST 26-28: TEMPERATURE CONVERSION TESTS
+    // ========================================================================
+
+    #[test]
+    fn test_celsius_to_fahrenheit() {
+        assert_eq!(celsius_to_fahrenheit(0.0), 32.0);
+        assert_eq!(celsius_to_fahrenheit(100.0), 212.0);
+        assert_eq!(celsius_to_fahrenheit(-40.0), -40.0);
+    }
+
+    #[test]
+    fn test_fahrenheit_to_celsius() {
+        assert_eq!(fahrenheit_to_celsius(32.0), 0.0);
+        assert_eq!(fahrenheit_to_celsius(212.0), 100.0);
+        assert_eq!(fahrenheit_to_celsius(-40.0), -40.0);
+    }
+
+    #[test]
+    fn test_temperature_conversion_roundtrip() {
+        let celsius = 25.0;
+        let fahrenheit = celsius_to_fahrenheit(celsius);
+        let back_to_celsius = fahrenheit_to_celsius(fahrenheit);
+        assert!((celsius - back_to_celsius).abs() < 0.0001);
+    }
+
+    // ========================================================================
+    // TEST 29-31: STRING UTILITY TESTS
+    // ========================================================================
+
+    #[test]
+    fn test_reverse_string() {
+        assert_eq!(reverse_string("hello"), "olleh");
+        assert_eq!(reverse_string(""), "");
+        assert_eq!(reverse_string("a"), "a");
+        assert_eq!(reverse_string("Rust"), "tsuR");
+    }
+
+    #[test]
+    fn test_is_palindrome() {
+        assert!(is_palindrome("racecar"));
+        assert!(is_palindrome("A man a plan a canal Panama"));
+        assert!(is_palindrome(""));
+        assert!(is_palindrome("a"));
+        assert!(!is_palindrome("hello"));
+    }
+
+    #[test]
+    fn test_palindrome_case_insensitive() {
+        assert!(is_palindrome("RaceCar"));
+        assert!(is_palindrome("No lemon, no melon"));
+    }
+
+    // ========================================================================
+    // TEST 32-34: VECTOR UTILITY TESTS (GENERICS)
+    // ========================================================================
+
+    #[test]
+    fn test_find_max() {
+        assert_eq!(find_max(&[1, 5, 3, 2]), Some(5));
+        assert_eq!(find_max(&[-1, -5, -3]), Some(-1));
+        assert_eq!(find_max(&[42]), Some(42));
+        assert_eq!(find_max::<i32>(&[]), None);
+    }
+
+    #[test]
+    fn test_find_min() {
+        assert_eq!(find_min(&[1, 5, 3, 2]), Some(1));
+        assert_eq!(find_min(&[-1, -5, -3]), Some(-5));
+        assert_eq!(find_min(&[42]), Some(42));
+        assert_eq!(find_min::<i32>(&[]), None);
+    }
+
+    #[test]
+    fn test_find_with_chars() {
+        assert_eq!(find_max(&['a', 'z', 'm']), Some('z'));
+        assert_eq!(find_min(&['a', 'z', 'm']), Some('a'));
+    }
+
+    // ========================================================================
+    // TEST 35-37: BANK ACCOUNT TESTS (STATEFUL TESTING)
+    // ========================================================================
+
+    #[test]
+    fn test_bank_account_deposit() {
+        let mut account = BankAccount::new();
+        assert_eq!(account.balance(), 0.0);
+
+        account.deposit(100.0).unwrap();
+        assert_eq!(account.balance(), 100.0);
+
+        account.deposit(50.0).unwrap();
+        assert_eq!(account.balance(), 150.0);
+        assert_eq!(account.transaction_count(), 2);
+    }
+
+    #[test]
+    fn test_bank_account_withdraw() {
+        let mut account = BankAccount::new();
+        account.deposit(100.0).unwrap();
+
+        account.withdraw(30.0).unwrap();
+        assert_eq!(account.balance(), 70.0);
+        assert_eq!(account.transaction_count(), 2);
+    }
+
+    #[test]
+    fn test_bank_account_insufficient_funds() {
+        let mut account = BankAccount::new();
+        account.deposit(50.0).unwrap();
+
+        let result = account.withdraw(100.0);
+        assert!(result.is_err());
+        assert_eq!(result.unwrap_err(), "Insufficient funds");
+        assert_eq!(account.balance(), 50.0);
+    }
+
+    // ========================================================================
+    // TEST 38-40: PARAMETERIZED TESTS (TABLE-DRIVEN TESTS)
+    // ========================================================================
+
+    // Each #[case] becomes its own test, named after the function and the
+    // case number, so a failure points straight at the inputs
+
+    #[rstest]
+    #[case(2, 3, 5)]
+    #[case(0, 0, 0)]
+    #[case(-1, 1, 0)]
+    #[case(100, 200, 300)]
+    #[case(-5, -5, -10)]
+    fn test_calculator_add_parameterized(#[case] a: i32, #[case] b: i32, #[case] expected: i32) {
+        let mut calc = Calculator::new();
+        assert_eq!(calc.add(a, b), expected);
+    }
+
+    #[rstest]
+    #[case(2, 3, 6)]
+    #[case(0, 100, 0)]
+    #[case(-2, 3, -6)]
+    #[case(4, 5, 20)]
+    #[case(-1, -1, 1)]
+    fn test_calculator_multiply_parameterized(
+        #[case] a: i32,
+        #[case] b: i32,
+        #[case] expected: i32,
+    ) {
+        let mut calc = Calculator::new();
+        assert_eq!(calc.multiply(a, b), expected);
+    }
+
+    // Cases can be named, which reads better than a number in the output
+    #[rstest]
+    #[case::word("racecar", true)]
+    #[case::not_a_palindrome("hello", false)]
+    #[case::ignores_case_and_spaces("A man a plan a canal Panama", true)]
+    #[case::empty("", true)]
+    #[case::single_char("a", true)]
+    #[case::two_chars("ab", false)]
+    fn test_palindrome_parameterized(#[case] input: &str, #[case] expected: bool) {
+        assert_eq!(is_palindrome(input), expected);
+    }
+
+    // ========================================================================
+    // TEST 41-43: CUSTOM ASSERTIONS AND HELPER FUNCTIONS
+    // ========================================================================
+
+    fn assert_almost_equal(a: f64, b: f64, epsilon: f64) {
+        assert!(
+            (a - b).abs() < epsilon,
+            "Values are not almost equal: {} vs {} (epsilon: {})",
+            a,
+            b,
+            epsilon
+        );
+    }
+
+    #[test]
+    fn test_custom_assertion_temperature() {
+        let celsius = 37.0; // Body temperature
+        let fahrenheit = celsius_to_fahrenheit(celsius);
+        assert_almost_equal(fahrenheit, 98.6, 0.1);
+    }
+
+    #[test]
+    fn test_custom_assertion_division() {
+        let mut calc = Calculator::new();
+        let result = calc.divide(10, 3).unwrap() as f64;
+        assert_almost_equal(result, 3.0, 1.0);
+    }
+
+    #[test]
+    #[allow(clippy::nonminimal_bool)]
+    fn test_result_unwrapping() {
+        let mut calc = Calculator::new();
+        let result = calc.divide(10, 2);
+
+        // Test Result methods
+        assert!(result.is_ok());
+        assert!(!result.is_err());
+        assert_eq!(result.unwrap(), 5);
+    }
+
+    // ========================================================================
+    // NESTED TEST MODULE (ORGANIZATION)
+    // ========================================================================
+
+    mod calculator_edge_cases {
+        use super::*;
+
+        #[test]
+        fn test_large_numbers() {
+            let mut calc = Calculator::new();
+            assert_eq!(calc.add(i32::MAX - 1, 1), i32::MAX);
+            assert_eq!(calc.subtract(i32::MIN + 1, 1), i32::MIN);
+        }
+
+        #[test]
+        fn test_zero_operations() {
+            let mut calc = Calculator::new();
+            assert_eq!(calc.multiply(0, 1000000), 0);
+            assert_eq!(calc.multiply(1000000, 0), 0);
+        }
+
+        #[test]
+        fn test_negative_operations() {
+            let mut calc = Calculator::new();
+            assert_eq!(calc.multiply(-1, -1), 1);
+            assert_eq!(calc.multiply(-5, -5), 25);
+        }
+    }
+
+    mod expression_tests {
+        use super::*;
+
+        fn eval(expression: &str) -> Result<i32, ExprError> {
+            Calculator::new().evaluate(expression)
+        }
+
+        #[test]
+        fn test_precedence() {
+            assert_eq!(eval("2 + 3 * 4"), Ok(14));
+            assert_eq!(eval("2 * 3 + 4"), Ok(10));
+            assert_eq!(eval("10 - 4 / 2"), Ok(8));
+            assert_eq!(eval("2 + 3 * (4 - 1)"), Ok(11));
+        }
+
+        #[test]
+        fn test_left_associativity() {
+            assert_eq!(eval("8 - 3 - 2"), Ok(3));
+            assert_eq!(eval("64 / 4 / 2"), Ok(8));
+            assert_eq!(eval("2 * 6 / 4"), Ok(3));
+        }
+
+        #[test]
+        fn test_parentheses() {
+            assert_eq!(eval("(2 + 3) * 4"), Ok(20));
+            assert_eq!(eval("8 - (3 - 2)"), Ok(7));
+            assert_eq!(eval("2 * (3 + (4 - 1))"), Ok(12));
+            assert_eq!(eval("((7))"), Ok(7));
+        }
+
+        #[test]
+        fn test_unary_minus() {
+            assert_eq!(eval("-5"), Ok(-5));
+            assert_eq!(eval("--5"), Ok(5));
+            assert_eq!(eval("-2 * 3"), Ok(-6));
+            assert_eq!(eval("2 * -3"), Ok(-6));
+            assert_eq!(eval("3 - -2"), Ok(5));
+            assert_eq!(eval("-(2 + 3)"), Ok(-5));
+        }
+
+        #[test]
+        fn test_whitespace_and_division() {
+            assert_eq!(eval("  1+2  "), Ok(3));
+            assert_eq!(eval("12 / 5"), Ok(2));
+            // Truncates toward zero, like `divide`
+            assert_eq!(eval("-7 / 2"), Ok(-3));
+        }
+
+        #[rstest]
+        #[case("", ExprError::UnexpectedEnd)]
+        #[case("2 +", ExprError::UnexpectedEnd)]
+        #[case("-", ExprError::UnexpectedEnd)]
+        #[case("2 3", ExprError::UnexpectedToken { position: 2 })]
+        #[case("2 * * 3", ExprError::UnexpectedToken { position: 4 })]
+        #[case("()", ExprError::UnexpectedToken { position: 1 })]
+        #[case("(1 2)", ExprError::UnexpectedToken { position: 3 })]
+        #[case("1 + 2)", ExprError::UnexpectedToken { position: 5 })]
+        #[case("(1 + 2", ExprError::UnclosedParen { position: 0 })]
+        #[case("2 * ((3)", ExprError::UnclosedParen { position: 4 })]
+        #[case("2 $ 3", ExprError::InvalidCharacter { character: '$', position: 2 })]
+        #[case("1 + 99999999999", ExprError::NumberTooLarge { position: 4 })]
+        #[case("1 / 0", ExprError::DivisionByZero)]
+        #[case("1 / (2 - 2)", ExprError::DivisionByZero)]
+        #[case("2147483647 + 1", ExprError::Overflow)]
+        #[case("-(-2147483647 - 1)", ExprError::Overflow)]
+        #[case("(-2147483647 - 1) / -1", ExprError::Overflow)]
+        fn test_error_cases(#[case] expression: &str, #[case] expected: ExprError) {
+            assert_eq!(eval(expression), Err(expected));
+        }
+
+        #[test]
+        fn test_nesting_limit() {
+            let parens = |depth: usize| format!("{}1{}", "(".repeat(depth), ")".repeat(depth));
+            let minuses = |depth: usize| format!("{}1", "-".repeat(depth));
+            let limit = Parser::MAX_DEPTH;
+
+            assert_eq!(eval(&parens(limit)), Ok(1));
+            assert_eq!(eval(&minuses(limit)), Ok(1));
+            assert_eq!(
+                eval(&parens(limit + 1)),
+                Err(ExprError::TooDeeplyNested { position: limit })
+            );
+            assert_eq!(
+                eval(&minuses(limit + 1)),
+                Err(ExprError::TooDeeplyNested { position: limit })
+            );
+            // Both kinds count towards the one limit
+            let mixed = format!(
+                "{}1{}",
+                "-(".repeat(limit / 2 + 1),
+                ")".repeat(limit / 2 + 1)
+            );
+            assert_eq!(
+                eval(&mixed),
+                Err(ExprError::TooDeeplyNested { position: limit })
+            );
+            // Deep enough to overflow the stack without the limit
+            assert!(matches!(
+                eval(&parens(100_000)),
+                Err(ExprError::TooDeeplyNested { .. })
+            ));
+            assert!(matches!(
+                eval(&minuses(100_000)),
+                Err(ExprError::TooDeeplyNested { .. })
+            ));
+            // Only nesting counts, not how many groups there are
+            assert_eq!(eval(&vec!["(1)"; 1_000].join(" + ")), Ok(1_000));
+        }
+
+        #[test]
+        fn test_error_messages() {
+            assert_eq!(
+                eval("2 $ 3").unwrap_err().to_string(),
+                "invalid character '$' at 2"
+            );
+            assert_eq!(
+                eval("(1 + 2").unwrap_err().to_string(),
+                "parenthesis at 0 is never closed"
+            );
+        }
+    }
+
+    mod history_tests {
+        use super::*;
+
+        /// A calculator on a fake clock, and the handle that moves it
+        fn calculator_at(now: u64) -> (Calculator, MockClock) {
+            let clock = MockClock::new(now);
+            (Calculator::with_clock(Box::new(clock.clone())), clock)
+        }
+
+        #[test]
+        fn test_operations_are_recorded() {
+            let (mut calc, clock) = calculator_at(100);
+            calc.add(2, 3);
+            clock.advance(5);
+            calc.subtract(10, 4);
+            calc.multiply(-2, 3);
+            calc.divide(9, 3).unwrap();
+
+            let operations: Vec<_> = calc.history().iter().map(|e| &e.operation).collect();
+            assert_eq!(
+                operations,
+                vec![
+                    &Operation::Add(2, 3),
+                    &Operation::Subtract(10, 4),
+                    &Operation::Multiply(-2, 3),
+                    &Operation::Divide(9, 3),
+                ]
+            );
+            let results: Vec<_> = calc.history().iter().map(|e| e.result).collect();
+            assert_eq!(results, vec![5, 6, -6, 3]);
+            assert_eq!(calc.history()[0].timestamp, 100);
+            assert_eq!(calc.history()[1].timestamp, 105);
+        }
+
+        #[test]
+        fn test_failures_are_not_recorded() {
+            let (mut calc, _) = calculator_at(0);
+            assert!(calc.divide(1, 0).is_err());
+            assert!(calc.evaluate("2 +").is_err());
+            assert!(calc.history().is_empty());
+        }
+
+        #[test]
+        fn test_evaluate_records_one_entry() {
+            let (mut calc, _) = calculator_at(0);
+            calc.evaluate(" 2 + 3 * 4 ").unwrap();
+            assert_eq!(calc.history().len(), 1);
+            assert_eq!(
+                calc.history()[0].operation,
+                Operation::Evaluate("2 + 3 * 4".to_string())
+            );
+            assert_eq!(calc.last_result(), Some(14));
+        }
+
+        #[test]
+        fn test_undo_last() {
+            let (mut calc, _) = calculator_at(0);
+            calc.add(1, 1);
+            calc.multiply(3, 3);
+
+            assert_eq!(calc.undo_last().map(|e| e.result), Some(9));
+            assert_eq!(calc.last_result(), Some(2));
+            assert_eq!(
+                calc.undo_last().map(|e| e.operation),
+                Some(Operation::Add(1, 1))
+            );
+            assert_eq!(calc.undo_last(), None);
+            assert_eq!(calc.last_result(), None);
+        }
+
+        #[test]
+        fn test_history_between() {
+            let (mut calc, clock) = calculator_at(10);
+            for i in 0..5 {
+                calc.add(i, i);
+                clock.advance(10);
+            }
+            // Timestamps 10, 20, 30, 40, 50; the end is exclusive
+            let results: Vec<_> = calc.history_between(20, 40).map(|e| e.result).collect();
+            assert_eq!(results, vec![2, 4]);
+            assert_eq!(calc.history_between(60, 100).count(), 0);
+        }
+
+        #[test]
+        fn test_history_limit_drops_oldest() {
+            let (mut calc, _) = calculator_at(0);
+            for i in 0..Calculator::HISTORY_LIMIT as i32 + 5 {
+                calc.add(i, 0);
+            }
+            assert_eq!(calc.history().len(), Calculator::HISTORY_LIMIT);
+            assert_eq!(calc.history()[0].operation, Operation::Add(5, 0));
+        }
+
+        #[test]
+        fn test_entry_display() {
+            let (mut calc, _) = calculator_at(0);
+            calc.subtract(7, 9);
+            calc.evaluate("(1 + 2) * 3").unwrap();
+            let lines: Vec<_> = calc.history().iter().map(|e| e.to_string()).collect();
+            assert_eq!(lines, vec!["7 - 9 = -2", "(1 + 2) * 3 = 9"]);
+        }
+
+        #[test]
+        fn test_save_load_round_trip() {
+            let (mut calc, clock) = calculator_at(1_000);
+            calc.add(2, 3);
+            clock.advance(60);
+            calc.divide(-7, 2).unwrap();
+            calc.evaluate("-(4 - 1)").unwrap();
+
+            let mut json = Vec::new();
+            calc.save_history(&mut json).unwrap();
+
+            let (mut restored, _) = calculator_at(0);
+            restored.load_history(json.as_slice()).unwrap();
+            assert_eq!(restored.history(), calc.history());
+        }
+
+        #[test]
+        fn test_json_format() {
+            let (mut calc, _) = calculator_at(1_000);
+            calc.add(2, 3);
+            calc.evaluate("1 + 1").unwrap();
+
+            let mut json = Vec::new();
+            calc.save_history(&mut json).unwrap();
+            let value: serde_json::Value = serde_json::from_slice(&json).unwrap();
+            assert_eq!(
+                value,
+                serde_json::json!([
+                    { "operation": { "add": [2, 3] }, "result": 5, "timestamp": 1000 },
+                    { "operation": { "evaluate": "1 + 1" }, "result": 2, "timestamp": 1000 },
+                ])
+            );
+        }
+
+        #[test]
+        fn test_load_invalid_json_keeps_history() {
+            let (mut calc, _) = calculator_at(0);
+            calc.add(1, 2);
+
+            let unknown_operation =
+                r#"[{"operation": {"modulo": [5, 2]}, "result": 1, "timestamp": 0}]"#;
+            assert!(calc.load_history(unknown_operation.as_bytes()).is_err());
+            assert!(calc.load_history("not json".as_bytes()).is_err());
+            assert_eq!(calc.history().len(), 1);
+            assert_eq!(calc.last_result(), Some(3));
+        }
+
+        #[test]
+        fn test_load_keeps_newest_entries() {
+            let entries: Vec<_> = (0..Calculator::HISTORY_LIMIT as i32 + 3)
+                .map(|i| HistoryEntry {
+                    operation: Operation::Add(i, 0),
+                    result: i,
+                    timestamp: 0,
+                })
+                .collect();
+            let json = serde_json::to_vec(&entries).unwrap();
+
+            let (mut calc, _) = calculator_at(0);
+            calc.load_history(json.as_slice()).unwrap();
+            assert_eq!(calc.history().len(), Calculator::HISTORY_LIMIT);
+            assert_eq!(calc.history()[0].result, 3);
+        }
+    }
+
+    mod bank_account_edge_cases {
+        use super::*;
+
+        #[test]
+        fn test_negative_deposit() {
+            let mut account = BankAccount::new();
+            let result = account.deposit(-50.0);
+            assert!(result.is_err());
+            assert_eq!(result.unwrap_err(), "Deposit amount must be positive");
+        }
+
+        #[test]
+        fn test_negative_withdrawal() {
+            let mut account = BankAccount::new();
+            account.deposit(100.0).unwrap();
+            let result = account.withdraw(-50.0);
+            assert!(result.is_err());
+        }
+
+        #[test]
+        fn test_zero_operations() {
+            let mut account = BankAccount::new();
+            assert!(account.deposit(0.0).is_err());
+            assert!(account.withdraw(0.0).is_err());
+        }
+
+        #[test]
+        fn test_nan_amounts() {
+            // NaN fails every comparison, so `amount <= 0.0` alone lets it in
+            let mut account = BankAccount::new();
+            assert!(account.deposit(f64::NAN).is_err());
+            assert!(account.withdraw(f64::NAN).is_err());
+            assert_eq!(account.balance(), 0.0);
+        }
+    }
+
+    mod bank_account_transfers {
+        use super::*;
+
+        // #[from] lets one fixture fill several arguments, each with its
+        // own #[with] balance
+
+        #[rstest]
+        fn test_transfer_moves_funds(
+            #[from(funded_account)] mut from: BankAccount,
+            #[from(funded_account)]
+            #[with(10.0)]
+            mut to: BankAccount,
+        ) {
+            from.transfer(&mut to, 40.0).unwrap();
+            assert_eq!(from.balance(), 60.0);
+            assert_eq!(to.balance(), 50.0);
+            assert_eq!(from.transaction_count(), 2);
+            assert_eq!(to.transaction_count(), 2);
+        }
+
+        #[rstest]
+        fn test_transfer_entire_balance(
+            #[from(funded_account)]
+            #[with(75.0)]
+            mut from: BankAccount,
+        ) {
+            let mut to = BankAccount::new();
+            from.transfer(&mut to, 75.0).unwrap();
+            assert_eq!(from.balance(), 0.0);
+            assert_eq!(to.balance(), 75.0);
+        }
+
+        #[rstest]
+        fn test_transfer_insufficient_funds_changes_nothing(
+            #[from(funded_account)]
+            #[with(30.0)]
+            mut from: BankAccount,
+            #[from(funded_account)]
+            #[with(5.0)]
+            mut to: BankAccount,
+        ) {
+            assert_eq!(
+                from.transfer(&mut to, 30.01),
+                Err("Insufficient funds".to_string())
+            );
+            assert_eq!(from.balance(), 30.0);
+            assert_eq!(to.balance(), 5.0);
+            assert_eq!(from.transaction_count(), 1);
+            assert_eq!(to.transaction_count(), 1);
+        }
+
+        #[rstest]
+        fn test_transfer_chain_runs_dry(
+            #[from(funded_account)]
+            #[with(50.0)]
+            mut a: BankAccount,
+        ) {
+            // a -> b -> c, then the emptied accounts can't pay again
+            let mut b = BankAccount::new();
+            let mut c = BankAccount::new();
+            a.transfer(&mut b, 50.0).unwrap();
+            b.transfer(&mut c, 50.0).unwrap();
+            assert!(a.transfer(&mut c, 1.0).is_err());
+            assert!(b.transfer(&mut a, 1.0).is_err());
+            assert_eq!((a.balance(), b.balance(), c.balance()), (0.0, 0.0, 50.0));
+        }
+
+        #[rstest]
+        fn test_transfer_invalid_amounts(
+            #[values(0.0, -10.0, f64::NAN)] amount: f64,
+            #[from(funded_account)] mut from: BankAccount,
+        ) {
+            let mut to = BankAccount::new();
+            assert_eq!(
+                from.transfer(&mut to, amount),
+                Err("Transfer amount must be positive".to_string())
+            );
+            assert_eq!(from.balance(), 100.0);
+        }
+
+        #[rstest]
+        fn test_transfer_into_overdraft(
+            #[from(funded_account)]
+            #[with(20.0)]
+            from: BankAccount,
+        ) {
+            let mut from = from.with_overdraft_limit(100.0);
+            let mut to = BankAccount::new();
+            from.transfer(&mut to, 120.0).unwrap();
+            assert_eq!(from.balance(), -100.0);
+            assert_eq!(from.available(), 0.0);
+            assert!(from.transfer(&mut to, 0.01).is_err());
+        }
+
+        #[test]
+        fn test_overdraft_limit_boundaries() {
+            let mut account = BankAccount::new().with_overdraft_limit(50.0);
+            assert_eq!(account.available(), 50.0);
+            assert!(account.withdraw(50.01).is_err());
+            account.withdraw(50.0).unwrap();
+            assert_eq!(account.balance(), -50.0);
+            assert!(account.withdraw(0.01).is_err());
+
+            account.deposit(30.0).unwrap();
+            assert_eq!(account.available(), 30.0);
+        }
+
+        #[test]
+        fn test_default_has_no_overdraft() {
+            let mut account = BankAccount::new();
+            assert_eq!(account.overdraft_limit(), 0.0);
+            assert!(account.withdraw(0.01).is_err());
+        }
+
+        #[test]
+        #[should_panic(expected = "Overdraft limit must be non-negative")]
+        fn test_negative_overdraft_limit_panics() {
+            let _ = BankAccount::new().with_overdraft_limit(-1.0);
+        }
+    }
+
+    mod bank_account_interest {
+        use super::*;
+
+        #[test]
+        fn test_interest_accrues() {
+            let mut account = BankAccount::new();
+            account.deposit(1000.0).unwrap();
+            assert_eq!(account.apply_interest(0.05), Ok(50.0));
+            assert_eq!(account.balance(), 1050.0);
+            // Compounds on the new balance
+            assert_eq!(account.apply_interest(0.05), Ok(52.5));
+            assert_eq!(account.balance(), 1102.5);
+            assert_eq!(account.transaction_count(), 3);
+        }
+
+        #[rstest]
+        #[case::rounds_half_up(100.0, 0.00125, 0.13)] // 0.125
+        #[case::rounds_down(100.0, 0.00124, 0.12)] // 0.124
+        #[case::binary_half_cent(100.5, 0.01, 1.01)] // 1.005 despite its binary representation
+        #[case::up_to_whole_amount(333.33, 0.03, 10.0)] // 9.9999
+        #[case::half_a_cent(0.5, 0.01, 0.01)] // 0.005
+        #[case::to_nothing(0.4, 0.01, 0.0)] // 0.004
+        fn test_interest_rounds_to_cents(
+            #[case] balance: f64,
+            #[case] rate: f64,
+            #[case] expected: f64,
+            #[with(balance)] mut funded_account: BankAccount,
+        ) {
+            assert_eq!(funded_account.apply_interest(rate), Ok(expected));
+        }
+
+        #[test]
+        fn test_zero_interest_is_not_recorded() {
+            let mut account = BankAccount::new();
+            account.deposit(0.4).unwrap();
+            assert_eq!(account.apply_interest(0.01), Ok(0.0));
+            assert_eq!(account.apply_interest(0.0), Ok(0.0));
+            assert_eq!(account.transaction_count(), 1);
+
+            let mut empty = BankAccount::new();
+            assert_eq!(empty.apply_interest(0.1), Ok(0.0));
+            assert_eq!(empty.transaction_count(), 0);
+        }
+
+        #[test]
+        fn test_overdrawn_balance_is_charged() {
+            let mut account = builders::overdrawn_account().build();
+            assert_eq!(account.apply_interest(0.1), Ok(-10.0));
+            // Past the limit now, so nothing more can be withdrawn
+            assert_eq!(account.balance(), -110.0);
+            assert_eq!(account.available(), 0.0);
+            assert!(account.withdraw(1.0).is_err());
+        }
+
+        // #[values] runs the test once per value
+        #[rstest]
+        fn test_invalid_rates(
+            #[values(-0.01, f64::NAN, f64::INFINITY)] rate: f64,
+            mut funded_account: BankAccount,
+        ) {
+            assert!(funded_account.apply_interest(rate).is_err());
+            assert_eq!(funded_account.balance(), 100.0);
+        }
+    }
+
+    mod bank_account_ledger {
+        use super::*;
+
+        /// An account on a fake clock, and the handle that moves it
+        fn account_at(now: u64) -> (BankAccount, MockClock) {
+            let clock = MockClock::new(now);
+            (BankAccount::with_clock(Box::new(clock.clone())), clock)
+        }
+
+        /// Deposits at t=100, a withdrawal and interest at t=200, and a
+        /// transfer out at t=300
+        fn busy_account() -> BankAccount {
+            let (mut account, clock) = account_at(100);
+            account.deposit_with_memo(1000.0, "salary").unwrap();
+            account.deposit(0.1).unwrap();
+            clock.set(200);
+            account.withdraw_with_memo(250.0, "rent").unwrap();
+            account.apply_interest(0.01).unwrap();
+            clock.set(300);
+            let mut other = BankAccount::new();
+            account
+                .transfer_with_memo(&mut other, 100.2, "savings")
+                .unwrap();
+            account
+        }
+
+        #[test]
+        fn test_transactions_are_recorded() {
+            let account = busy_account();
+            let summary: Vec<_> = account
+                .transactions()
+                .iter()
+                .map(|t| (t.id, t.kind, t.amount, t.timestamp, t.memo.as_str()))
+                .collect();
+            assert_eq!(
+                summary,
+                vec![
+                    (1, TransactionKind::Deposit, 1000.0, 100, "salary"),
+                    (2, TransactionKind::Deposit, 0.1, 100, ""),
+                    (3, TransactionKind::Withdrawal, -250.0, 200, "rent"),
+                    (4, TransactionKind::Interest, 7.5, 200, "Interest at 1.00%"),
+                    (5, TransactionKind::TransferOut, -100.2, 300, "savings"),
+                ]
+            );
+        }
+
+        #[test]
+        fn test_transfer_records_both_sides() {
+            let (mut from, _) = account_at(50);
+            let (mut to, _) = account_at(60);
+            from.deposit(10.0).unwrap();
+            from.transfer_with_memo(&mut to, 4.0, "lunch").unwrap();
+
+            let incoming = &to.transactions()[0];
+            assert_eq!(incoming.kind, TransactionKind::TransferIn);
+            assert_eq!(incoming.amount, 4.0);
+            assert_eq!(incoming.memo, "lunch");
+            // Each side timestamps with its own clock
+            assert_eq!(incoming.timestamp, 60);
+            assert_eq!(from.transactions()[1].timestamp, 50);
+        }
+
+        #[test]
+        fn test_failed_operations_leave_no_transaction() {
+            let (mut account, _) = account_at(0);
+            let mut other = BankAccount::new();
+            assert!(account.deposit(-1.0).is_err());
+            assert!(account.withdraw(1.0).is_err());
+            assert!(account.transfer(&mut other, 1.0).is_err());
+            assert!(account.apply_interest(-0.1).is_err());
+            assert!(account.transactions().is_empty());
+            assert!(other.transactions().is_empty());
+        }
+
+        #[test]
+        fn test_filter_by_date_range() {
+            let account = busy_account();
+            let ids = |from, to| -> Vec<u64> {
+                account
+                    .transactions_between(from, to)
+                    .map(|t| t.id)
+                    .collect()
+            };
+            assert_eq!(ids(0, 1000), vec![1, 2, 3, 4, 5]);
+            assert_eq!(ids(100, 200), vec![1, 2]);
+            assert_eq!(ids(200, 300), vec![3, 4]);
+            assert_eq!(ids(101, 200), Vec::<u64>::new());
+            assert_eq!(ids(300, 300), Vec::<u64>::new());
+        }
+
+        #[test]
+        fn test_filter_by_kind() {
+            let account = busy_account();
+            let deposits: Vec<_> = account
+                .transactions_of_kind(TransactionKind::Deposit)
+                .map(|t| t.amount)
+                .collect();
+            assert_eq!(deposits, vec![1000.0, 0.1]);
+            assert_eq!(
+                account
+                    .transactions_of_kind(TransactionKind::TransferIn)
+                    .count(),
+                0
+            );
+        }
+
+        #[test]
+        fn test_statement_running_balance() {
+            let account = busy_account();
+            let statement = account.statement(200, 301);
+            assert_eq!(statement.opening_balance, 1000.1);
+            let balances: Vec<_> = statement.lines.iter().map(|l| l.balance).collect();
+            assert_eq!(balances, vec![750.1, 757.6, 657.4]);
+        }
+
+        #[test]
+        fn test_statement_totals_match_balance() {
+            let account = busy_account();
+            let statement = account.statement(0, u64::MAX);
+            assert_eq!(statement.opening_balance, 0.0);
+            assert_eq!(statement.lines.len(), account.transaction_count());
+            // Same additions in the same order, so exactly equal
+            assert_eq!(statement.closing_balance(), account.balance());
+            assert_almost_equal(
+                statement.opening_balance + statement.total_in() - statement.total_out(),
+                account.balance(),
+                1e-9,
+            );
+            assert_almost_equal(statement.total_in(), 1007.6, 1e-9);
+            assert_almost_equal(statement.total_out(), 350.2, 1e-9);
+        }
+
+        #[test]
+        fn test_statements_chain() {
+            // Each period's closing balance is the next one's opening
+            let account = busy_account();
+            let periods = [(0, 150), (150, 250), (250, 350)];
+            let statements: Vec<_> = periods
+                .iter()
+                .map(|&(from, to)| account.statement(from, to))
+                .collect();
+            for pair in statements.windows(2) {
+                assert_eq!(pair[0].closing_balance(), pair[1].opening_balance);
+            }
+            assert_eq!(statements[2].closing_balance(), account.balance());
+        }
+
+        #[test]
+        fn test_empty_statement() {
+            let account = busy_account();
+            let statement = account.statement(400, 500);
+            assert!(statement.lines.is_empty());
+            assert_eq!(statement.opening_balance, account.balance());
+            assert_eq!(statement.closing_balance(), account.balance());
+            assert_eq!(statement.total_in(), 0.0);
+            assert_eq!(statement.total_out(), 0.0);
+        }
+
+        #[test]
+        fn test_statement_display() {
+            let (mut account, _) = account_at(100);
+            account.deposit_with_memo(50.0, "gift").unwrap();
+            account.withdraw(20.25).unwrap();
+            let expected = [
+                "        Time Kind             Amount    Balance  Memo",
+                "             Opening                       0.00",
+                "         100 Deposit           50.00      50.00  gift",
+                "         100 Withdrawal       -20.25      29.75",
+                "             Closing                      29.75",
+            ]
+            .join("\n");
+            assert_eq!(account.statement(0, 1000).to_string(), expected);
+        }
+    }
+
+    mod bank_account_recurring_interest {
+        use super::*;
+
+        const DAY: u64 = 24 * 60 * 60;
+        const MONTH: u64 = 30 * DAY;
+        const START: u64 = 1_700_000_000;
+
+        /// 1,000.00 on deposit at START, earning 1% every 30 days
+        fn monthly_account() -> (BankAccount, MockClock) {
+            let clock = MockClock::new(START);
+            let account = AccountBuilder::new()
+                .clock(Box::new(clock.clone()))
+                .recurring_interest(0.01, MONTH)
+                .balance(1000.0)
+                .build();
+            (account, clock)
+        }
+
+        fn interest(account: &BankAccount) -> Vec<(u64, f64)> {
+            account
+                .transactions_of_kind(TransactionKind::Interest)
+                .map(|t| (t.timestamp, t.amount))
+                .collect()
+        }
+
+        #[test]
+        fn test_nothing_due_before_first_period() {
+            let (mut account, clock) = monthly_account();
+            assert_eq!(account.next_interest_due(), Some(START + MONTH));
+
+            clock.advance(MONTH - 1);
+            assert_eq!(account.accrue_interest(), 0.0);
+            assert_eq!(account.balance(), 1000.0);
+        }
+
+        #[test]
+        fn test_due_exactly_at_period_end() {
+            let (mut account, clock) = monthly_account();
+            clock.advance(MONTH);
+
+            assert_eq!(account.accrue_interest(), 10.0);
+            assert_eq!(interest(&account), vec![(START + MONTH, 10.0)]);
+            assert_eq!(account.next_interest_due(), Some(START + 2 * MONTH));
+            // Paid once only
+            assert_eq!(account.accrue_interest(), 0.0);
+        }
+
+        #[test]
+        fn test_catches_up_on_missed_periods() {
+            let (mut account, clock) = monthly_account();
+            clock.advance(3 * MONTH + 5 * DAY);
+
+            // Compounded, and each dated at the end of its own period
+            assert_eq!(account.accrue_interest(), 30.3);
+            assert_eq!(
+                interest(&account),
+                vec![
+                    (START + MONTH, 10.0),
+                    (START + 2 * MONTH, 10.1),
+                    (START + 3 * MONTH, 10.2),
+                ]
+            );
+            assert_eq!(account.balance(), 1030.3);
+        }
+
+        #[test]
+        fn test_paying_late_changes_nothing() {
+            let (mut on_time, on_time_clock) = monthly_account();
+            for _ in 0..12 {
+                on_time_clock.advance(MONTH);
+                on_time.accrue_interest();
+            }
+
+            let (mut late, late_clock) = monthly_account();
+            late_clock.advance(12 * MONTH);
+            late.accrue_interest();
+
+            assert_eq!(interest(&late), interest(&on_time));
+            assert_eq!(late.balance(), on_time.balance());
+        }
+
+        #[test]
+        fn test_deposit_accrues_first() {
+            let (mut account, clock) = monthly_account();
+            clock.advance(MONTH + DAY);
+
+            // The interest due a day ago is on the balance before this
+            // deposit, and comes before it in the ledger
+            account.deposit(500.0).unwrap();
+            let kinds: Vec<TransactionKind> =
+                account.transactions().iter().map(|t| t.kind).collect();
+            assert_eq!(
+                kinds,
+                vec![
+                    TransactionKind::Deposit,
+                    TransactionKind::Interest,
+                    TransactionKind::Deposit
+                ]
+            );
+            assert_eq!(account.balance(), 1510.0);
+        }
+
+        #[test]
+        fn test_withdrawal_can_spend_accrued_interest() {
+            let (mut account, clock) = monthly_account();
+            clock.advance(MONTH);
+            account.withdraw(1010.0).unwrap();
+            assert_eq!(account.balance(), 0.0);
+        }
+
+        #[test]
+        fn test_transfer_accrues_both_accounts() {
+            let (mut from, clock) = monthly_account();
+            let mut to = BankAccount::with_clock(Box::new(clock.clone()))
+                .with_recurring_interest(0.02, MONTH);
+            to.deposit(100.0).unwrap();
+            clock.advance(MONTH);
+
+            from.transfer(&mut to, 10.0).unwrap();
+            assert_eq!(from.balance(), 1000.0);
+            assert_eq!(to.balance(), 112.0);
+        }
+
+        #[test]
+        fn test_statement_covers_each_month() {
+            let (mut account, clock) = monthly_account();
+            clock.advance(2 * MONTH);
+            account.accrue_interest();
+
+            let second_month = account.statement(START + MONTH + 1, START + 2 * MONTH + 1);
+            assert_eq!(second_month.opening_balance, 1010.0);
+            assert_eq!(second_month.total_in(), 10.1);
+            assert_eq!(second_month.closing_balance(), account.balance());
+        }
+
+        #[test]
+        fn test_no_schedule_accrues_nothing() {
+            let clock = MockClock::new(START);
+            let mut account = BankAccount::with_clock(Box::new(clock.clone()));
+            account.deposit(1000.0).unwrap();
+            clock.advance(10 * MONTH);
+
+            assert_eq!(account.next_interest_due(), None);
+            assert_eq!(account.accrue_interest(), 0.0);
+        }
+
+        #[test]
+        #[should_panic(expected = "Interest period must be at least one second")]
+        fn test_zero_period_panics() {
+            let _ = BankAccount::new().with_recurring_interest(0.01, 0);
+        }
+
+        #[test]
+        #[should_panic(expected = "Interest rate must be a non-negative number")]
+        fn test_nan_rate_panics() {
+            let _ = BankAccount::new().with_recurring_interest(f64::NAN, MONTH);
+        }
+    }
+
+    mod user_store_tests {
+        use super::*;
+
+        fn user(id: u64, username: &str) -> User {
+            UserBuilder::new().id(id).username(username).build()
+        }
+
+        #[test]
+        fn test_in_memory_store_save_replaces() {
+            let mut store = InMemoryUserStore::new();
+            store.save(user(1, "alice")).unwrap();
+            store.save(user(1, "alicia")).unwrap();
+
+            assert_eq!(store.users().len(), 1);
+            assert_eq!(store.users()[&1].username, "alicia");
+        }
+
+        #[test]
+        fn test_in_memory_store_delete() {
+            let mut store = InMemoryUserStore::new();
+            store.save(user(1, "alice")).unwrap();
+
+            assert_eq!(store.delete(1), Ok(true));
+            assert_eq!(store.delete(1), Ok(false));
+            assert!(store.users().is_empty());
+        }
+
+        #[test]
+        fn test_file_store_missing_file_is_empty() {
+            let dir = tempfile::tempdir().unwrap();
+            let path = dir.path().join("users.json");
+
+            let store = FileUserStore::open(&path).unwrap();
+            assert!(store.users().is_empty());
+            // Nothing is written until something changes
+            assert!(!path.exists());
+        }
+
+        #[test]
+        fn test_file_store_persists_changes() {
+            let dir = tempfile::tempdir().unwrap();
+            let path = dir.path().join("users.json");
+
+            let mut store = FileUserStore::open(&path).unwrap();
+            store.save(user(1, "alice")).unwrap();
+            store.save(user(2, "bob")).unwrap();
+            store.delete(1).unwrap();
+
+            let reopened = FileUserStore::open(&path).unwrap();
+            assert_eq!(reopened.users(), store.users());
+            assert_eq!(reopened.users()[&2], user(2, "bob"));
+        }
+
+        #[test]
+        fn test_file_store_rejects_invalid_json() {
+            let dir = tempfile::tempdir().unwrap();
+            let path = dir.path().join("users.json");
+            std::fs::write(&path, "not json").unwrap();
+
+            let err = FileUserStore::open(&path).unwrap_err();
+            assert!(err.starts_with("Invalid user file"), "{}", err);
+        }
+
+        #[test]
+        fn test_file_store_failed_write_changes_nothing() {
+            let dir = tempfile::tempdir().unwrap();
+            let path = dir.path().join("users.json");
+            let mut store = FileUserStore::open(&path).unwrap();
+            store.save(user(1, "alice")).unwrap();
+
+            // With the directory gone, every write fails
+            dir.close().unwrap();
+
+            assert!(store.save(user(1, "alicia")).is_err());
+            assert!(store.save(user(2, "bob")).is_err());
+            assert!(store.delete(1).is_err());
+            assert_eq!(store.users().len(), 1);
+            assert_eq!(store.users()[&1].username, "alice");
+        }
+
+        #[test]
+        fn test_service_skips_ids_already_stored() {
+            let mut store = InMemoryUserStore::new();
+            store.save(user(1, "alice")).unwrap();
+            store.save(user(2, "bob")).unwrap();
+            let mut service =
+                UserService::with_store(Box::new(store), Box::new(SequentialIds::default()));
+
+            let id = service
+                .create_user("carol".to_string(), "carol@test.com".to_string(), 41)
+                .unwrap();
+            assert_eq!(id, 3);
+            assert_eq!(service.get_user(1).unwrap().username, "alice");
+            assert_eq!(service.count(), 3);
+        }
+    }
+
+    mod user_service_queries {
+        use super::*;
+
+        fn usernames<'a>(users: &[&'a User]) -> Vec<&'a str> {
+            users.iter().map(|user| user.username.as_str()).collect()
+        }
+
+        const EVERYONE: &[&str] = &["alice", "adam", "bob", "anna", "carol"];
+
+        #[rstest]
+        fn test_update_user(mut user_service: UserService) {
+            user_service
+                .update_user(3, "robert".to_string(), "robert@test.com".to_string(), 31)
+                .unwrap();
+
+            let user = user_service.get_user(3).unwrap();
+            assert_eq!(user.username, "robert");
+            assert_eq!(user.email, "robert@test.com");
+            assert_eq!(user.age, 31);
+            assert_eq!(user_service.count(), EVERYONE.len());
+        }
+
+        #[rstest]
+        #[case::unknown_user(99, "bob", "bob@test.com", 30, "User not found")]
+        #[case::empty_username(3, "", "bob@test.com", 30, "Username cannot be empty")]
+        #[case::invalid_email(3, "bob", "bob.test.com", 30, "Invalid email format")]
+        #[case::too_young(3, "bob", "bob@test.com", 12, "User must be at least 13 years old")]
+        fn test_update_user_failures(
+            mut user_service: UserService,
+            #[case] id: u64,
+            #[case] username: &str,
+            #[case] email: &str,
+            #[case] age: u8,
+            #[case] expected: &str,
+        ) {
+            let result = user_service.update_user(id, username.to_string(), email.to_string(), age);
+            assert_eq!(result, Err(expected.to_string()));
+            // A failed update leaves the user as they were
+            assert_eq!(user_service.get_user(3).unwrap().age, 30);
+        }
+
+        #[rstest]
+        #[case::first_page(0, 2, &["alice", "adam"])]
+        #[case::second_page(1, 2, &["bob", "anna"])]
+        #[case::short_last_page(2, 2, &["carol"])]
+        #[case::just_past_the_end(3, 2, &[])]
+        #[case::exactly_one_page(0, 5, EVERYONE)]
+        #[case::page_bigger_than_everyone(0, 10, EVERYONE)]
+        #[case::after_exactly_one_page(1, 5, &[])]
+        #[case::zero_per_page(0, 0, &[])]
+        // Far past the end, without overflowing
+        #[case::huge_page(usize::MAX, 2, &[])]
+        fn test_list_users_pages(
+            user_service: UserService,
+            #[case] page: usize,
+            #[case] per_page: usize,
+            #[case] expected: &[&str],
+        ) {
+            assert_eq!(
+                usernames(&user_service.list_users(page, per_page)),
+                expected
+            );
+        }
+
+        #[test]
+        fn test_list_users_empty_service() {
+            let service = UserService::new();
+            assert!(service.list_users(0, 10).is_empty());
+        }
+
+        #[rstest]
+        fn test_list_users_skips_deleted(mut user_service: UserService) {
+            user_service.delete_user(2).unwrap();
+            assert_eq!(
+                usernames(&user_service.list_users(0, 2)),
+                vec!["alice", "bob"]
+            );
+        }
+
+        fn ages(range: RangeInclusive<u8>) -> UserFilter {
+            UserFilter {
+                age_range: Some(range),
+                ..UserFilter::default()
+            }
+        }
+
+        fn prefix(prefix: &str) -> UserFilter {
+            UserFilter {
+                username_prefix: Some(prefix.to_string()),
+                ..UserFilter::default()
+            }
+        }
+
+        fn adults() -> UserFilter {
+            UserFilter {
+                adult_only: true,
+                ..UserFilter::default()
+            }
+        }
+
+        #[rstest]
+        #[case::everyone(UserFilter::default(), EVERYONE)]
+        #[case::age_range(ages(17..=30), &["alice", "adam", "bob"])]
+        #[case::single_age(ages(64..=64), &["carol"])]
+        #[case::username_prefix(prefix("a"), &["alice", "adam", "anna"])]
+        #[case::adult_only(adults(), &["alice", "bob", "carol"])]
+        #[case::combined(UserFilter { adult_only: true, ..prefix("a") }, &["alice"])]
+        // Prefixes are case-sensitive
+        #[case::prefix_case(prefix("A"), &[])]
+        #[case::no_one_that_age(ages(40..=50), &[])]
+        // A backwards range is empty
+        #[allow(clippy::reversed_empty_ranges)]
+        #[case::backwards_range(ages(30..=17), &[])]
+        fn test_search(
+            user_service: UserService,
+            #[case] filter: UserFilter,
+            #[case] expected: &[&str],
+        ) {
+            assert_eq!(usernames(&user_service.search(&filter)), expected);
+        }
+
+        #[test]
+        fn test_search_empty_service() {
+            let service = UserService::new();
+            assert!(service.search(&UserFilter::default()).is_empty());
+        }
+    }
+
+    // The loom build swaps in atomics that only work inside `loom::model`
+    #[cfg(not(feature = "loom"))]
+    mod shared_account_tests {
+        use super::*;
+        use std::sync::{Arc, Barrier};
+
+        #[test]
+        fn test_deposit_and_withdraw() {
+            let account = SharedAccount::new();
+            account.deposit(50.0).unwrap();
+            account.withdraw(20.25).unwrap();
+            assert_eq!(account.balance(), 29.75);
+        }
 
-    #[test]
-    fn test_calculator_add_parameterized() {
-        let calc = Calculator::new();
-        let test_cases = vec![
-            (2, 3, 5),
-            (0, 0, 0),
-            (-1, 1, 0),
-            (100, 200, 300),
-            (-5, -5, -10),
-        ];
-
-        for (a, b, expected) in test_cases {
+        #[test]
+        fn test_amounts_rounded_to_cents() {
+            let account = SharedAccount::new();
+            account.deposit(0.104).unwrap();
+            account.deposit(0.106).unwrap();
+            assert_eq!(account.balance(), 0.21);
+        }
+
+        #[rstest]
+        #[case::zero(0.0)]
+        #[case::negative(-5.0)]
+        #[case::under_a_cent(0.004)]
+        #[case::nan(f64::NAN)]
+        #[case::infinite(f64::INFINITY)]
+        fn test_invalid_amounts(#[case] amount: f64) {
+            let account = SharedAccount::with_balance(10.0);
             assert_eq!(
-                calc.add(a, b),
-                expected,
-                "Failed: {} + {} should equal {}",
-                a,
-                b,
-                expected
+                account.deposit(amount),
+                Err("Deposit amount must be positive".to_string())
+            );
+            assert_eq!(
+                account.withdraw(amount),
+                Err("Withdrawal amount must be positive".to_string())
             );
+            assert_eq!(account.balance(), 10.0);
         }
-    }
-
-    #[test]
-    fn test_calculator_multiply_parameterized() {
-        let calc = Calculator::new();
-        let test_cases = vec![(2, 3, 6), (0, 100, 0), (-2, 3, -6), (4, 5, 20), (-1, -1, 1)];
 
-        for (a, b, expected) in test_cases {
+        #[test]
+        fn test_insufficient_funds() {
+            let account = SharedAccount::with_balance(10.0);
             assert_eq!(
-                calc.multiply(a, b),
-                expected,
-                "Failed: {} * {} should equal {}",
-                a,
-                b,
-                expected
+                account.withdraw(10.01),
+                Err("Insufficient funds".to_string())
             );
+            account.withdraw(10.0).unwrap();
+            assert_eq!(account.balance(), 0.0);
         }
-    }
 
-    #[test]
-    fn test_palindrome_parameterized() {
-        let test_cases = vec![
-            ("racecar", true),
-            ("hello", false),
-            ("A man a plan a canal Panama", true),
-            ("", true),
-            ("a", true),
-            ("ab", false),
-        ];
-
-        for (input, expected) in test_cases {
+        #[test]
+        fn test_overflow_refused() {
+            let account = SharedAccount::new();
+            account.deposit(1e17).unwrap();
             assert_eq!(
-                is_palindrome(input),
-                expected,
-                "Failed: is_palindrome({:?}) should be {}",
-                input,
-                expected
+                account.deposit(1.8e17),
+                Err("Balance would overflow".to_string())
             );
+            assert_eq!(account.balance(), 1e17);
         }
-    }
-
-    // ========================================================================
-    // TEST 41-43: CUSTOM ASSERTIONS AND HELPER FUNCTIONS
-    // ========================================================================
-
-    fn assert_almost_equal(a: f64, b: f64, epsilon: f64) {
-        assert!(
-            (a - b).abs() < epsilon,
-            "Values are not almost equal: {} vs {} (epsilon: {})",
-            a,
-            b,
-            epsilon
-        );
-    }
-
-    #[test]
-    fn test_custom_assertion_temperature() {
-        let celsius = 37.0; // Body temperature
-        let fahrenheit = celsius_to_fahrenheit(celsius);
-        assert_almost_equal(fahrenheit, 98.6, 0.1);
-    }
 
-    #[test]
-    fn test_custom_assertion_division() {
-        let calc = Calculator::new();
-        let result = calc.divide(10, 3).unwrap() as f64;
-        assert_almost_equal(result, 3.0, 1.0);
-    }
+        #[test]
+        #[should_panic(expected = "Opening balance must be a non-negative amount")]
+        fn test_negative_opening_balance() {
+            let _ = SharedAccount::with_balance(-1.0);
+        }
 
-    #[test]
-    fn test_result_unwrapping() {
-        let calc = Calculator::new();
-        let result = calc.divide(10, 2);
+        /// Many more threads than cores all try to withdraw at once, with a
+        /// barrier so they really do start together. Exactly as many can
+        /// succeed as the balance allows.
+        #[test]
+        fn test_stress_withdrawals_never_overdraw() {
+            const THREADS: usize = 64;
+            let account = Arc::new(SharedAccount::with_balance(40.0));
+            let barrier = Arc::new(Barrier::new(THREADS));
+
+            let handles: Vec<_> = (0..THREADS)
+                .map(|_| {
+                    let account = Arc::clone(&account);
+                    let barrier = Arc::clone(&barrier);
+                    std::thread::spawn(move || {
+                        barrier.wait();
+                        account.withdraw(1.0).is_ok()
+                    })
+                })
+                .collect();
+            let succeeded = handles
+                .into_iter()
+                .map(|handle| handle.join().unwrap())
+                .filter(|&ok| ok)
+                .count();
+
+            assert_eq!(succeeded, 40);
+            assert_eq!(account.balance(), 0.0);
+        }
 
-        // Test Result methods
-        assert!(result.is_ok());
-        assert!(!result.is_err());
-        assert_eq!(result.unwrap(), 5);
+        /// Threads deposit and withdraw in a tight loop. However they
+        /// interleave, the balance must equal the opening balance plus
+        /// every deposit minus every withdrawal that succeeded.
+        #[test]
+        fn test_stress_mixed_operations_balance() {
+            const THREADS: usize = 16;
+            const ROUNDS: usize = 2_000;
+            let account = Arc::new(SharedAccount::with_balance(10.0));
+            let barrier = Arc::new(Barrier::new(THREADS));
+
+            let handles: Vec<_> = (0..THREADS)
+                .map(|thread| {
+                    let account = Arc::clone(&account);
+                    let barrier = Arc::clone(&barrier);
+                    std::thread::spawn(move || {
+                        barrier.wait();
+                        let mut net_cents = 0i64;
+                        for round in 0..ROUNDS {
+                            if (thread + round) % 2 == 0 {
+                                account.deposit(0.25).unwrap();
+                                net_cents += 25;
+                            } else if account.withdraw(0.5).is_ok() {
+                                net_cents -= 50;
+                            }
+                        }
+                        net_cents
+                    })
+                })
+                .collect();
+            let net_cents: i64 = handles
+                .into_iter()
+                .map(|handle| handle.join().unwrap())
+                .sum();
+
+            assert_eq!(account.balance(), (1000 + net_cents) as f64 / 100.0);
+        }
     }
 
-    // ========================================================================
-    // NESTED TEST MODULE (ORGANIZATION)
-    // ========================================================================
-
-    mod calculator_edge_cases {
+    mod file_processor_lines {
         use super::*;
 
-        #[test]
-        fn test_large_numbers() {
-            let calc = Calculator::new();
-            assert_eq!(calc.add(i32::MAX - 1, 1), i32::MAX);
-            assert_eq!(calc.subtract(i32::MIN + 1, 1), i32::MIN);
+        fn run(input: &[u8]) -> Result<(String, ProcessSummary), ProcessError> {
+            let mut output = Vec::new();
+            let summary = FileProcessor::new("P".to_string()).process_lines(input, &mut output)?;
+            Ok((String::from_utf8(output).unwrap(), summary))
         }
 
-        #[test]
-        fn test_zero_operations() {
-            let calc = Calculator::new();
-            assert_eq!(calc.multiply(0, 1000000), 0);
-            assert_eq!(calc.multiply(1000000, 0), 0);
+        #[rstest]
+        #[case::unix(b"a\nb\n")]
+        #[case::windows(b"a\r\nb\r\n")]
+        #[case::no_trailing_newline(b"a\nb")]
+        fn test_line_endings(#[case] input: &[u8]) {
+            let (output, summary) = run(input).unwrap();
+            assert_eq!(output, "P: A\nP: B\n");
+            assert_eq!(summary.lines_processed, 2);
         }
 
         #[test]
-        fn test_negative_operations() {
-            let calc = Calculator::new();
-            assert_eq!(calc.multiply(-1, -1), 1);
-            assert_eq!(calc.multiply(-5, -5), 25);
+        fn test_invalid_lines_skipped() {
+            let long = "x".repeat(1001);
+            let input = format!("ok\n\n{}\nfine\n", long);
+            let (output, summary) = run(input.as_bytes()).unwrap();
+            assert_eq!(output, "P: OK\nP: FINE\n");
+            assert_eq!(
+                summary,
+                ProcessSummary {
+                    lines_processed: 2,
+                    lines_skipped: 2,
+                }
+            );
         }
-    }
-
-    mod bank_account_edge_cases {
-        use super::*;
 
         #[test]
-        fn test_negative_deposit() {
-            let mut account = BankAccount::new();
-            let result = account.deposit(-50.0);
-            assert!(result.is_err());
-            assert_eq!(result.unwrap_err(), "Deposit amount must be positive");
+        fn test_empty_input() {
+            let (output, summary) = run(b"").unwrap();
+            assert!(output.is_empty());
+            assert_eq!(summary, ProcessSummary::default());
         }
 
         #[test]
-        fn test_negative_withdrawal() {
-            let mut account = BankAccount::new();
-            account.deposit(100.0).unwrap();
-            let result = account.withdraw(-50.0);
-            assert!(result.is_err());
+        fn test_invalid_utf8_reports_line() {
+            let err = run(b"one\ntwo\nth\xffree\n").unwrap_err();
+            assert!(matches!(err, ProcessError::InvalidUtf8 { line: 3 }));
+            assert_eq!(err.to_string(), "line 3 is not valid UTF-8");
         }
 
         #[test]
-        fn test_zero_operations() {
-            let mut account = BankAccount::new();
-            assert!(account.deposit(0.0).is_err());
-            assert!(account.withdraw(0.0).is_err());
+        fn test_multibyte_characters() {
+            let (output, _) = run("héllo wörld\n".as_bytes()).unwrap();
+            assert_eq!(output, "P: HÉLLO WÖRLD\n");
         }
     }
 }
 
+// ============================================================================
+// MODEL CHECKING WITH LOOM
+// ============================================================================
+
+/// Each test's closure runs once for every way loom can interleave its
+/// threads, so an assertion that holds here holds however the threads are
+/// scheduled. Run with `cargo test --release --features loom loom_tests`.
+#[cfg(all(test, feature = "loom"))]
+mod loom_tests {
+    use super::*;
+    use loom::sync::Arc;
+    use loom::thread;
+
+    #[test]
+    fn loom_only_one_withdrawal_gets_the_last_funds() {
+        loom::model(|| {
+            let account = Arc::new(SharedAccount::with_balance(100.0));
+            let other = {
+                let account = Arc::clone(&account);
+                thread::spawn(move || account.withdraw(60.0).is_ok())
+            };
+            let here = account.withdraw(60.0).is_ok();
+            let there = other.join().unwrap();
+
+            assert!(here != there, "exactly one withdrawal succeeds");
+            assert_eq!(account.balance(), 40.0);
+        });
+    }
+
+    #[test]
+    fn loom_concurrent_deposits_all_count() {
+        loom::model(|| {
+            let account = Arc::new(SharedAccount::new());
+            let handles: Vec<_> = (0..2)
+                .map(|_| {
+                    let account = Arc::clone(&account);
+                    thread::spawn(move || account.deposit(5.0).unwrap())
+                })
+                .collect();
+            account.deposit(5.0).unwrap();
+            for handle in handles {
+                handle.join().unwrap();
+            }
+
+            assert_eq!(account.balance(), 15.0);
+        });
+    }
+
+    #[test]
+    fn loom_withdrawal_sees_deposit_or_refuses() {
+        loom::model(|| {
+            let account = Arc::new(SharedAccount::with_balance(5.0));
+            let depositor = {
+                let account = Arc::clone(&account);
+                thread::spawn(move || account.deposit(10.0).unwrap())
+            };
+            // Only possible if the deposit landed first
+            let withdrew = account.withdraw(12.0).is_ok();
+            depositor.join().unwrap();
+
+            let expected = if withdrew { 3.0 } else { 15.0 };
+            assert_eq!(account.balance(), expected);
+        });
+    }
+}
+
 // ============================================================================
 // PROPERTY-BASED TESTING WITH PROPTEST
 // ============================================================================
@@ -1032,26 +4348,75 @@ mod tests {
 #[cfg(test)]
 mod property_tests {
     use super::*;
+    use crate::strategies::{self, BankOp};
     use proptest::prelude::*;
 
     proptest! {
         #[test]
         fn test_add_commutative(a in -1000i32..1000i32, b in -1000i32..1000i32) {
-            let calc = Calculator::new();
+            let mut calc = Calculator::new();
             prop_assert_eq!(calc.add(a, b), calc.add(b, a));
         }
 
         #[test]
         fn test_add_associative(a in -100i32..100i32, b in -100i32..100i32, c in -100i32..100i32) {
-            let calc = Calculator::new();
-            let left = calc.add(calc.add(a, b), c);
-            let right = calc.add(a, calc.add(b, c));
+            let mut calc = Calculator::new();
+            let ab = calc.add(a, b);
+            let left = calc.add(ab, c);
+            let bc = calc.add(b, c);
+            let right = calc.add(a, bc);
             prop_assert_eq!(left, right);
         }
 
+        #[test]
+        fn test_history_json_round_trip(
+            operands in prop::collection::vec((any::<i16>(), any::<i16>(), 0..4u8), 0..20)
+        ) {
+            let mut calc = Calculator::with_clock(Box::new(MockClock::new(0)));
+            for (a, b, op) in operands {
+                let (a, b) = (i32::from(a), i32::from(b));
+                match op {
+                    0 => { calc.add(a, b); }
+                    1 => { calc.subtract(a, b); }
+                    2 => { calc.multiply(a, b); }
+                    _ => { let _ = calc.divide(a, b); }
+                }
+            }
+
+            let mut json = Vec::new();
+            calc.save_history(&mut json).unwrap();
+            let mut restored = Calculator::new();
+            restored.load_history(json.as_slice()).unwrap();
+            prop_assert_eq!(restored.history(), calc.history());
+        }
+
+        #[test]
+        fn test_statement_matches_balance(
+            operations in prop::collection::vec((0..3u8, 1u32..100_000), 0..40)
+        ) {
+            let clock = MockClock::new(0);
+            let mut account = BankAccount::with_clock(Box::new(clock.clone()))
+                .with_overdraft_limit(500.0);
+            for (op, cents) in operations {
+                let amount = f64::from(cents) / 100.0;
+                // Rejected withdrawals simply leave no transaction
+                let _ = match op {
+                    0 => account.deposit(amount),
+                    1 => account.withdraw(amount),
+                    _ => account.apply_interest(0.01).map(|_| ()),
+                };
+                clock.advance(1);
+            }
+
+            let statement = account.statement(0, u64::MAX);
+            prop_assert_eq!(statement.closing_balance(), account.balance());
+            let sum: f64 = account.transactions().iter().map(|t| t.amount).sum();
+            prop_assert!((sum - account.balance()).abs() < 1e-6);
+        }
+
         #[test]
         fn test_multiply_commutative(a in -1000i32..1000i32, b in -1000i32..1000i32) {
-            let calc = Calculator::new();
+            let mut calc = Calculator::new();
             prop_assert_eq!(calc.multiply(a, b), calc.multiply(b, a));
         }
 
@@ -1077,11 +4442,137 @@ mod property_tests {
 
         #[test]
         fn test_divide_then_multiply(a in 1i32..1000i32, b in 1i32..100i32) {
-            let calc = Calculator::new();
+            let mut calc = Calculator::new();
             let divided = calc.divide(a * b, b).unwrap();
             prop_assert_eq!(divided, a);
         }
     }
+
+    // ========================================================================
+    // STATEFUL PROPERTIES: RUNS OF BANK OPERATIONS, CHECKED AT EVERY STEP
+    // ========================================================================
+
+    const MONTH: u64 = 30 * 24 * 60 * 60;
+
+    /// Two accounts on one clock, so transfers have somewhere to go
+    fn accounts(overdraft_limit: f64) -> (BankAccount, BankAccount, MockClock) {
+        let clock = MockClock::new(0);
+        let account =
+            BankAccount::with_clock(Box::new(clock.clone())).with_overdraft_limit(overdraft_limit);
+        let other = BankAccount::with_clock(Box::new(clock.clone()));
+        (account, other, clock)
+    }
+
+    /// What must hold of any account, whatever has happened to it
+    fn check_invariants(account: &BankAccount) -> Result<(), TestCaseError> {
+        let ledger: f64 = account.transactions().iter().map(|t| t.amount).sum();
+        prop_assert_eq!(account.balance(), ledger);
+        prop_assert_eq!(
+            account.statement(0, u64::MAX).closing_balance(),
+            account.balance()
+        );
+        prop_assert!(account
+            .transactions()
+            .windows(2)
+            .all(|pair| pair[0].timestamp <= pair[1].timestamp));
+        Ok(())
+    }
+
+    proptest! {
+        #[test]
+        fn test_ops_never_overdraw_without_limit(ops in strategies::bank_ops(60)) {
+            let (mut account, mut other, clock) = accounts(0.0);
+            for op in &ops {
+                let _ = op.apply(&mut account, &mut other, &clock);
+                check_invariants(&account)?;
+                check_invariants(&other)?;
+                prop_assert!(account.balance() >= 0.0, "{:?} left {}", op, account.balance());
+            }
+        }
+
+        // Withdrawals and transfers stop at the limit. Interest charged on
+        // an overdrawn balance may go past it, but then nothing more can
+        // be taken out.
+        #[test]
+        fn test_ops_respect_overdraft_limit(
+            limit in strategies::amount(),
+            ops in strategies::bank_ops(60),
+        ) {
+            let (mut account, mut other, clock) = accounts(limit);
+            for op in &ops {
+                let available = account.available();
+                let result = op.apply(&mut account, &mut other, &clock);
+                check_invariants(&account)?;
+                if let BankOp::Withdraw(amount) | BankOp::Transfer(amount) = *op {
+                    prop_assert_eq!(result.is_ok(), amount <= available, "{:?}", op);
+                    if result.is_ok() {
+                        prop_assert!(account.balance() >= -limit - 1e-9);
+                    }
+                }
+            }
+        }
+
+        #[test]
+        fn test_rejected_ops_change_nothing(
+            limit in prop_oneof![Just(0.0), strategies::amount()],
+            ops in strategies::bank_ops(60),
+        ) {
+            let (mut account, mut other, clock) = accounts(limit);
+            for op in &ops {
+                let before = (account.transactions().to_vec(), other.transactions().to_vec());
+                if op.apply(&mut account, &mut other, &clock).is_err() {
+                    prop_assert_eq!(account.transactions(), before.0.as_slice());
+                    prop_assert_eq!(other.transactions(), before.1.as_slice());
+                }
+            }
+        }
+
+        #[test]
+        fn test_transfers_conserve_money(ops in strategies::bank_ops(60)) {
+            let (mut account, mut other, clock) = accounts(0.0);
+            for op in &ops {
+                let total = account.balance() + other.balance();
+                let result = op.apply(&mut account, &mut other, &clock);
+                if let (BankOp::Transfer(_), Ok(())) = (op, result) {
+                    let after = account.balance() + other.balance();
+                    prop_assert!((after - total).abs() < 1e-6, "{} became {}", total, after);
+                }
+            }
+        }
+
+        // Waits make monthly interest come due between other operations,
+        // and with the interest added in, the ledger still adds up
+        #[test]
+        fn test_ops_with_recurring_interest(ops in strategies::bank_ops(60)) {
+            let (account, mut other, clock) = accounts(0.0);
+            let mut account = account.with_recurring_interest(0.01, MONTH);
+            for op in &ops {
+                let _ = op.apply(&mut account, &mut other, &clock);
+                account.accrue_interest();
+                check_invariants(&account)?;
+                prop_assert!(account.balance() >= 0.0);
+            }
+        }
+
+        #[test]
+        fn test_valid_users_accepted(user in strategies::valid_user()) {
+            let mut service = UserService::new();
+            let id = service
+                .create_user(user.username.clone(), user.email.clone(), user.age)
+                .unwrap();
+            let created = service.get_user(id).unwrap();
+            prop_assert_eq!(&created.username, &user.username);
+            prop_assert_eq!(created.is_adult(), user.age >= 18);
+        }
+
+        #[test]
+        fn test_invalid_users_rejected(invalid in strategies::invalid_user()) {
+            let mut service = UserService::new();
+            let result = service.create_user(invalid.username, invalid.email, invalid.age);
+            prop_assert_eq!(result, Err(invalid.error.to_string()));
+            prop_assert_eq!(service.count(), 0);
+        }
+    }
 }
 
 // ============================================================================
@@ -1091,6 +4582,7 @@ mod property_tests {
 #[cfg(test)]
 mod mock_tests {
     use super::*;
+    use crate::builders::UserBuilder;
     use mockall::predicate::*;
     use mockall::*;
 
@@ -1109,7 +4601,7 @@ mod mock_tests {
     #[test]
     fn test_database_mock_save() {
         let mut mock_db = MockDatabase::new();
-        let user = User::new(1, "test".to_string(), "test@test.com".to_string(), 20).unwrap();
+        let user = UserBuilder::new().username("test").age(20).build();
 
         mock_db.expect_save_user().times(1).returning(|_| Ok(()));
 
@@ -1120,8 +4612,7 @@ mod mock_tests {
     #[test]
     fn test_database_mock_find() {
         let mut mock_db = MockDatabase::new();
-        let expected_user =
-            User::new(1, "alice".to_string(), "alice@test.com".to_string(), 25).unwrap();
+        let expected_user = UserBuilder::new().age(25).build();
 
         mock_db
             .expect_find_user()
@@ -1157,12 +4648,60 @@ mod mock_tests {
             .times(1)
             .returning(|_| Err("Database error".to_string()));
 
-        let user = User::new(1, "test".to_string(), "test@test.com".to_string(), 20).unwrap();
+        let user = UserBuilder::new().username("test").age(20).build();
         let result = mock_db.save_user(&user);
         assert!(result.is_err());
         assert_eq!(result.unwrap_err(), "Database error");
     }
 
+    // A mocked store checks what the service asks of it, without a real
+    // store that would hide the calls
+
+    fn service_with(store: MockUserStore) -> UserService {
+        UserService::with_store(Box::new(store), Box::new(SequentialIds::starting_at(7)))
+    }
+
+    #[test]
+    fn test_service_saves_validated_user() {
+        let mut store = MockUserStore::new();
+        store.expect_users().return_const(BTreeMap::new());
+        store
+            .expect_save()
+            .withf(|user| user.id == 7 && user.username == "alice")
+            .times(1)
+            .returning(|_| Ok(()));
+
+        let mut service = service_with(store);
+        let id = service.create_user("alice".to_string(), "alice@test.com".to_string(), 25);
+        assert_eq!(id, Ok(7));
+    }
+
+    #[test]
+    fn test_service_does_not_store_invalid_user() {
+        // No expectations: any call to the store fails the test
+        let mut service = service_with(MockUserStore::new());
+        let result = service.create_user("".to_string(), "alice@test.com".to_string(), 25);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_service_propagates_store_errors() {
+        let mut store = MockUserStore::new();
+        store.expect_users().return_const(BTreeMap::new());
+        store
+            .expect_save()
+            .returning(|_| Err("Disk full".to_string()));
+        store
+            .expect_delete()
+            .with(eq(7))
+            .returning(|_| Err("Disk full".to_string()));
+
+        let mut service = service_with(store);
+        let result = service.create_user("alice".to_string(), "alice@test.com".to_string(), 25);
+        assert_eq!(result, Err("Disk full".to_string()));
+        assert_eq!(service.delete_user(7), Err("Disk full".to_string()));
+    }
+
     #[test]
     fn test_mock_call_count() {
         let mut mock_email = MockEmailService::new();
@@ -1179,3 +4718,298 @@ mod mock_tests {
         }
     }
 }
+
+// ============================================================================
+// ASYNC TESTING WITH TOKIO
+// ============================================================================
+
+#[cfg(test)]
+mod async_tests {
+    use super::*;
+    use tokio::time::Instant;
+
+    fn service(transport: SimulatedTransport) -> NotificationService<SimulatedTransport> {
+        NotificationService::new(transport)
+            .with_timeout(Duration::from_secs(1))
+            .with_retries(3, Duration::from_millis(100))
+    }
+
+    // #[tokio::test] builds a runtime for each test and runs the async
+    // body on it
+    #[tokio::test]
+    async fn test_notify_delivers() {
+        let service = service(SimulatedTransport::new(Duration::ZERO));
+
+        assert_eq!(service.notify("alice@test.com", "Hello").await, Ok(1));
+        assert_eq!(
+            service.transport().sent(),
+            vec![("alice@test.com".to_string(), "Hello".to_string())]
+        );
+    }
+
+    // With the clock paused, tokio skips ahead whenever every task is
+    // waiting on a timer: a 10 minute sleep finishes at once, but the
+    // virtual time it took can still be measured
+    #[tokio::test]
+    async fn test_paused_clock_measures_virtual_time() {
+        tokio::time::pause();
+        let start = Instant::now();
+
+        tokio::time::sleep(Duration::from_secs(600)).await;
+
+        // Timers fire on whole milliseconds of the runtime's clock, and the
+        // clock was paused partway through one, so the sleep can end up to
+        // 1ms late. start_paused below avoids this.
+        let elapsed = start.elapsed();
+        assert!(elapsed >= Duration::from_secs(600), "{:?}", elapsed);
+        assert!(elapsed <= Duration::from_millis(600_001), "{:?}", elapsed);
+    }
+
+    // start_paused does the same as calling pause() first thing
+    #[tokio::test(start_paused = true)]
+    async fn test_slow_transport_takes_its_delay() {
+        let service = service(SimulatedTransport::new(Duration::from_millis(250)));
+        let start = Instant::now();
+
+        assert_eq!(service.notify("alice@test.com", "Hello").await, Ok(1));
+        assert_eq!(start.elapsed(), Duration::from_millis(250));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_retries_with_exponential_backoff() {
+        let transport = SimulatedTransport::new(Duration::from_millis(10)).failing_first(2);
+        let service = service(transport);
+        let start = Instant::now();
+
+        assert_eq!(service.notify("alice@test.com", "Hello").await, Ok(3));
+        // Three sends of 10ms, with waits of 100ms and then 200ms between
+        assert_eq!(start.elapsed(), Duration::from_millis(330));
+        assert_eq!(service.transport().attempts(), 3);
+        assert_eq!(service.transport().sent().len(), 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_gives_up_after_max_attempts() {
+        let transport = SimulatedTransport::new(Duration::from_millis(10)).failing_first(5);
+        let service = service(transport);
+        let start = Instant::now();
+
+        let result = service.notify("alice@test.com", "Hello").await;
+        assert_eq!(
+            result,
+            Err(NotifyError::Failed {
+                attempts: 3,
+                reason: "Service unavailable".to_string()
+            })
+        );
+        // No wait after the last attempt
+        assert_eq!(start.elapsed(), Duration::from_millis(330));
+        assert!(service.transport().sent().is_empty());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_each_attempt_times_out() {
+        let service = service(SimulatedTransport::new(Duration::from_secs(5)));
+        let start = Instant::now();
+
+        let result = service.notify("alice@test.com", "Hello").await;
+        assert_eq!(result, Err(NotifyError::TimedOut { attempts: 3 }));
+        assert_eq!(start.elapsed(), Duration::from_millis(3_300));
+        // The timed-out sends were dropped before they could deliver
+        assert_eq!(service.transport().attempts(), 3);
+        assert!(service.transport().sent().is_empty());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_send_finishing_at_timeout_is_on_time() {
+        // tokio's timeout polls the send before checking its deadline, so a
+        // send that takes exactly the timeout still gets through
+        let service = service(SimulatedTransport::new(Duration::from_secs(1)));
+        assert_eq!(service.notify("alice@test.com", "Hello").await, Ok(1));
+    }
+
+    // join! runs both futures on the same task, so the second one can
+    // check on the first partway through
+    #[tokio::test(start_paused = true)]
+    async fn test_observe_retry_in_progress() {
+        let transport = SimulatedTransport::new(Duration::from_millis(10)).failing_first(1);
+        let service = service(transport);
+
+        let (result, attempts_at_50ms) =
+            tokio::join!(service.notify("alice@test.com", "Hello"), async {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                service.transport().attempts()
+            });
+
+        // At 50ms the first attempt had failed and the retry was still
+        // waiting out its backoff
+        assert_eq!(attempts_at_50ms, 1);
+        assert_eq!(result, Ok(2));
+        assert_eq!(service.transport().attempts(), 2);
+    }
+
+    #[test]
+    fn test_notify_error_display() {
+        assert_eq!(
+            NotifyError::TimedOut { attempts: 3 }.to_string(),
+            "timed out after 3 attempts"
+        );
+        assert_eq!(
+            NotifyError::Failed {
+                attempts: 2,
+                reason: "Service unavailable".to_string()
+            }
+            .to_string(),
+            "failed after 2 attempts: Service unavailable"
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "At least one attempt is needed")]
+    fn test_zero_attempts_rejected() {
+        NotificationService::new(SimulatedTransport::default()).with_retries(0, Duration::ZERO);
+    }
+
+    mod currency_converter_tests {
+        use super::*;
+
+        fn converter() -> CurrencyConverter<FixedRates> {
+            CurrencyConverter::new(
+                FixedRates::new()
+                    .with_rate("USD", "EUR", 0.92)
+                    .with_rate("EUR", "JPY", 161.375),
+            )
+        }
+
+        #[tokio::test]
+        async fn test_convert_rounds_to_cents() {
+            let converter = converter();
+            assert_eq!(converter.convert(10.0, "USD", "EUR").await, Ok(9.2));
+            assert_eq!(converter.convert(0.01, "EUR", "JPY").await, Ok(1.61));
+        }
+
+        #[tokio::test]
+        async fn test_same_currency_needs_no_lookup() {
+            let converter = converter();
+            assert_eq!(converter.convert(42.5, "GBP", "gbp").await, Ok(42.5));
+            assert_eq!(converter.client().lookups(), 0);
+        }
+
+        #[tokio::test]
+        async fn test_rates_cached_per_pair() {
+            let converter = converter();
+            converter.convert(1.0, "USD", "EUR").await.unwrap();
+            converter.convert(2.0, "usd", "eur").await.unwrap();
+            assert_eq!(converter.client().lookups(), 1);
+
+            converter.convert(1.0, "EUR", "JPY").await.unwrap();
+            assert_eq!(converter.client().lookups(), 2);
+        }
+
+        #[tokio::test]
+        async fn test_errors_not_cached() {
+            let converter = converter();
+            for _ in 0..2 {
+                assert_eq!(
+                    converter.convert(1.0, "USD", "XYZ").await,
+                    Err(RateError::UnknownCurrency("XYZ".to_string()))
+                );
+            }
+            assert_eq!(converter.client().lookups(), 2);
+        }
+    }
+}
+
+// ============================================================================
+// SNAPSHOT TESTING WITH INSTA
+// ============================================================================
+
+#[cfg(test)]
+mod snapshot_tests {
+    use super::*;
+    use insta::{assert_json_snapshot, assert_snapshot};
+
+    // A snapshot test compares a value against a copy saved by an earlier
+    // run, in src/snapshots/. When the output changes, the test fails and
+    // `cargo insta review` shows the difference, to accept or reject.
+
+    #[test]
+    fn test_history_snapshot() {
+        // The real clock puts a different timestamp in every run, so the
+        // snapshot replaces each one. The dynamic redaction still checks
+        // that it looks like a time before hiding it.
+        let mut calc = Calculator::new();
+        calc.add(40, 2);
+        calc.divide(7, 2).unwrap();
+        calc.evaluate("-(2 + 3) * 4").unwrap();
+
+        assert_json_snapshot!(calc.history(), {
+            "[].timestamp" => insta::dynamic_redaction(|value, _| {
+                assert!(value.as_u64().is_some_and(|secs| secs > 1_600_000_000));
+                "[timestamp]"
+            }),
+        });
+    }
+
+    #[test]
+    fn test_saved_history_snapshot() {
+        // With a mock clock there's nothing to redact, so the snapshot is
+        // the exact text `save_history` writes
+        let mut calc = Calculator::with_clock(Box::new(MockClock::new(1_700_000_000)));
+        calc.multiply(6, 7);
+        calc.evaluate("2 * (3 + 4)").unwrap();
+
+        let mut json = Vec::new();
+        calc.save_history(&mut json).unwrap();
+        assert_snapshot!(String::from_utf8(json).unwrap());
+    }
+
+    #[test]
+    fn test_user_service_snapshot() {
+        let mut service = UserService::new();
+        for (username, age) in [("alice", 25), ("bob", 30), ("charlie", 15)] {
+            service
+                .create_user(username.to_string(), format!("{}@test.com", username), age)
+                .unwrap();
+        }
+        service
+            .update_user(2, "robert".to_string(), "robert@test.com".to_string(), 31)
+            .unwrap();
+        service.delete_user(3).unwrap();
+
+        assert_json_snapshot!(service.list_users(0, usize::MAX));
+    }
+
+    #[test]
+    fn test_search_inline_snapshot() {
+        // Small snapshots can live in the test itself
+        let mut service = UserService::new();
+        for (username, age) in [("alice", 25), ("adam", 17), ("bob", 30)] {
+            service
+                .create_user(username.to_string(), format!("{}@test.com", username), age)
+                .unwrap();
+        }
+        let filter = UserFilter {
+            username_prefix: Some("a".to_string()),
+            ..UserFilter::default()
+        };
+        let found: Vec<(u64, &str)> = service
+            .search(&filter)
+            .iter()
+            .map(|user| (user.id, user.username.as_str()))
+            .collect();
+
+        assert_json_snapshot!(found, @r#"
+        [
+          [
+            1,
+            "alice"
+          ],
+          [
+            2,
+            "adam"
+          ]
+        ]
+        "#);
+    }
+}
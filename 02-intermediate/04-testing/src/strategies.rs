@@ -0,0 +1,151 @@
+//! Proptest strategies for users and bank operations
+//!
+//! A strategy describes how to generate values of some type, and how to
+//! shrink a failing one towards something simpler. Building them here,
+//! once, means every property test draws from the same idea of what a
+//! valid user or a plausible run of transactions looks like:
+//!
+//! ```
+//! use proptest::prelude::*;
+//! use testing::strategies;
+//!
+//! proptest!(|(user in strategies::valid_user())| {
+//!     prop_assert!(user.email.contains('@'));
+//! });
+//! ```
+
+use proptest::prelude::*;
+
+use crate::{BankAccount, MockClock, User};
+
+/// Lowercase letters, digits and underscores, starting with a letter
+pub fn valid_username() -> impl Strategy<Value = String> {
+    "[a-z][a-z0-9_]{0,15}"
+}
+
+pub fn valid_email() -> impl Strategy<Value = String> {
+    "[a-z0-9.]{1,10}@[a-z]{1,10}\\.(com|org|net)"
+}
+
+/// Any age `User::new` accepts
+pub fn valid_age() -> impl Strategy<Value = u8> {
+    13u8..
+}
+
+pub fn valid_user() -> impl Strategy<Value = User> {
+    (any::<u64>(), valid_username(), valid_email(), valid_age()).prop_map(
+        |(id, username, email, age)| {
+            User::new(id, username, email, age).expect("the strategies only make valid details")
+        },
+    )
+}
+
+/// Details that break exactly one of `User::new`'s rules, with the error
+/// it should give
+#[derive(Debug, Clone)]
+pub struct InvalidUser {
+    pub username: String,
+    pub email: String,
+    pub age: u8,
+    pub error: &'static str,
+}
+
+pub fn invalid_user() -> impl Strategy<Value = InvalidUser> {
+    let empty_username = (valid_email(), valid_age()).prop_map(|(email, age)| InvalidUser {
+        username: String::new(),
+        email,
+        age,
+        error: "Username cannot be empty",
+    });
+    let bad_email =
+        (valid_username(), "[^@]{0,20}", valid_age()).prop_map(|(username, email, age)| {
+            InvalidUser {
+                username,
+                email,
+                age,
+                error: "Invalid email format",
+            }
+        });
+    let too_young =
+        (valid_username(), valid_email(), 0u8..13).prop_map(|(username, email, age)| InvalidUser {
+            username,
+            email,
+            age,
+            error: "User must be at least 13 years old",
+        });
+    prop_oneof![empty_username, bad_email, too_young]
+}
+
+/// One step in the life of an account. Amounts are whole cents, so they
+/// round-trip through `f64` exactly.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BankOp {
+    Deposit(f64),
+    Withdraw(f64),
+    /// Moves money to a second account
+    Transfer(f64),
+    ApplyInterest(f64),
+    /// Moves the clock on, which may make recurring interest due
+    Wait(u64),
+}
+
+impl BankOp {
+    /// Runs the operation on `account`, with `other` on the far side of
+    /// any transfer
+    pub fn apply(
+        &self,
+        account: &mut BankAccount,
+        other: &mut BankAccount,
+        clock: &MockClock,
+    ) -> Result<(), String> {
+        match *self {
+            BankOp::Deposit(amount) => account.deposit(amount),
+            BankOp::Withdraw(amount) => account.withdraw(amount),
+            BankOp::Transfer(amount) => account.transfer(other, amount),
+            BankOp::ApplyInterest(rate) => account.apply_interest(rate).map(|_| ()),
+            BankOp::Wait(seconds) => {
+                clock.advance(seconds);
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Between a cent and $5,000
+pub fn amount() -> impl Strategy<Value = f64> {
+    (1u32..=500_000).prop_map(|cents| f64::from(cents) / 100.0)
+}
+
+/// Mostly deposits and withdrawals, which are what can go wrong
+pub fn bank_op() -> impl Strategy<Value = BankOp> {
+    prop_oneof![
+        4 => amount().prop_map(BankOp::Deposit),
+        4 => amount().prop_map(BankOp::Withdraw),
+        2 => amount().prop_map(BankOp::Transfer),
+        1 => (0u32..=10).prop_map(|percent| BankOp::ApplyInterest(f64::from(percent) / 100.0)),
+        1 => (1u64..=90 * 24 * 60 * 60).prop_map(BankOp::Wait),
+    ]
+}
+
+pub fn bank_ops(max_len: usize) -> impl Strategy<Value = Vec<BankOp>> {
+    prop::collection::vec(bank_op(), 0..=max_len)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    proptest! {
+        #[test]
+        fn invalid_user_gives_its_error(invalid in invalid_user()) {
+            let result = User::new(1, invalid.username, invalid.email, invalid.age);
+            prop_assert_eq!(result.unwrap_err(), invalid.error);
+        }
+
+        #[test]
+        fn amounts_are_whole_cents(amount in amount()) {
+            prop_assert_eq!((amount * 100.0).round() / 100.0, amount);
+            prop_assert!(amount > 0.0);
+        }
+    }
+}
@@ -6,6 +6,8 @@
 //!
 //! Run with: cargo test --test integration_test
 
+use rstest::rstest;
+use testing::builders::{AccountBuilder, UserBuilder};
 use testing::*;
 
 // ============================================================================
@@ -14,7 +16,7 @@ use testing::*;
 
 #[test]
 fn integration_calculator_full_workflow() {
-    let calc = Calculator::new();
+    let mut calc = Calculator::new();
 
     // Test a series of operations
     let result1 = calc.add(10, 5);
@@ -35,7 +37,7 @@ fn integration_calculator_full_workflow() {
 
 #[test]
 fn integration_calculator_error_handling() {
-    let calc = Calculator::new();
+    let mut calc = Calculator::new();
 
     // Test error conditions
     let result = calc.divide(10, 0);
@@ -83,7 +85,7 @@ fn integration_user_service_complete_workflow() {
     assert_eq!(bob.age, 30);
 
     // Test deletion
-    assert!(service.delete_user(id2));
+    assert!(service.delete_user(id2).unwrap());
     assert_eq!(service.count(), 2);
     assert!(service.get_user(id2).is_none());
 
@@ -206,51 +208,41 @@ fn integration_data_processor_trait() {
 // INTEGRATION TEST 5: TEMPERATURE CONVERSIONS
 // ============================================================================
 
-#[test]
-fn integration_temperature_conversions() {
-    // Test common temperature points
-    let test_cases = vec![
-        (0.0, 32.0),    // Freezing point of water
-        (100.0, 212.0), // Boiling point of water
-        (-40.0, -40.0), // Same in both scales
-        (37.0, 98.6),   // Human body temperature (approximate)
-        (20.0, 68.0),   // Room temperature
-    ];
-
-    for (celsius, fahrenheit) in test_cases {
-        let converted_f = celsius_to_fahrenheit(celsius);
-        assert!(
-            (converted_f - fahrenheit).abs() < 0.1,
-            "{}°C should be {}°F, got {}°F",
-            celsius,
-            fahrenheit,
-            converted_f
-        );
-
-        let converted_c = fahrenheit_to_celsius(fahrenheit);
-        assert!(
-            (converted_c - celsius).abs() < 0.1,
-            "{}°F should be {}°C, got {}°C",
-            fahrenheit,
-            celsius,
-            converted_c
-        );
-    }
+#[rstest]
+#[case::freezing(0.0, 32.0)]
+#[case::boiling(100.0, 212.0)]
+#[case::same_in_both_scales(-40.0, -40.0)]
+#[case::body_temperature(37.0, 98.6)] // approximate
+#[case::room_temperature(20.0, 68.0)]
+fn integration_temperature_conversions(#[case] celsius: f64, #[case] fahrenheit: f64) {
+    let converted_f = celsius_to_fahrenheit(celsius);
+    assert!(
+        (converted_f - fahrenheit).abs() < 0.1,
+        "{}°C should be {}°F, got {}°F",
+        celsius,
+        fahrenheit,
+        converted_f
+    );
+
+    let converted_c = fahrenheit_to_celsius(fahrenheit);
+    assert!(
+        (converted_c - celsius).abs() < 0.1,
+        "{}°F should be {}°C, got {}°C",
+        fahrenheit,
+        celsius,
+        converted_c
+    );
 }
 
-#[test]
-fn integration_temperature_roundtrip() {
-    let temperatures: Vec<f64> = vec![-40.0, -20.0, 0.0, 25.0, 37.0, 100.0];
-
-    for temp in temperatures {
-        let fahrenheit = celsius_to_fahrenheit(temp);
-        let back_to_celsius = fahrenheit_to_celsius(fahrenheit);
-        assert!(
-            (temp - back_to_celsius).abs() < 0.0001,
-            "Roundtrip failed for {}°C",
-            temp
-        );
-    }
+#[rstest]
+fn integration_temperature_roundtrip(#[values(-40.0, -20.0, 0.0, 25.0, 37.0, 100.0)] temp: f64) {
+    let fahrenheit = celsius_to_fahrenheit(temp);
+    let back_to_celsius = fahrenheit_to_celsius(fahrenheit);
+    assert!(
+        (temp - back_to_celsius).abs() < 0.0001,
+        "Roundtrip failed for {}°C",
+        temp
+    );
 }
 
 // ============================================================================
@@ -341,19 +333,19 @@ fn integration_complex_scenario() {
 
     // 1. Create a user service and add users
     let mut user_service = UserService::new();
-    let user_id = user_service
-        .create_user("john".to_string(), "john@bank.com".to_string(), 30)
-        .unwrap();
+    let user_id = UserBuilder::new()
+        .username("john")
+        .email("john@bank.com")
+        .create_in(&mut user_service);
 
     let user = user_service.get_user(user_id).unwrap();
     assert!(user.is_adult());
 
     // 2. Create a bank account for the user
-    let mut account = BankAccount::new();
-    account.deposit(5000.0).unwrap();
+    let mut account = AccountBuilder::new().balance(5000.0).build();
 
     // 3. Process some transactions
-    let calc = Calculator::new();
+    let mut calc = Calculator::new();
     let salary = 3000;
     let bonus = calc.multiply(salary, 2) / 10; // 20% bonus
 
@@ -450,7 +442,7 @@ fn integration_stress_test_user_service() {
 
     // Delete half the users
     for i in (1..=user_count).step_by(2) {
-        assert!(service.delete_user(i as u64));
+        assert!(service.delete_user(i as u64).unwrap());
     }
 
     assert_eq!(service.count(), user_count / 2);
@@ -497,3 +489,578 @@ fn integration_calculator_large_factorial() {
         );
     }
 }
+
+// ============================================================================
+// INTEGRATION TEST 11: CALCULATOR HISTORY ON DISK
+// ============================================================================
+
+#[test]
+fn integration_calculator_history_file_round_trip() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("history.json");
+
+    let mut calc = Calculator::with_clock(Box::new(MockClock::new(1_700_000_000)));
+    calc.add(40, 2);
+    calc.evaluate("2 * (3 + 4)").unwrap();
+    calc.save_history(std::fs::File::create(&path).unwrap())
+        .unwrap();
+
+    // A new session picks up where the last one left off
+    let mut next_session = Calculator::new();
+    next_session
+        .load_history(std::fs::File::open(&path).unwrap())
+        .unwrap();
+    assert_eq!(next_session.history(), calc.history());
+    assert_eq!(next_session.last_result(), Some(14));
+
+    next_session.undo_last();
+    assert_eq!(next_session.last_result(), Some(42));
+}
+
+// ============================================================================
+// INTEGRATION TEST 12: USERS IN A FILE
+// ============================================================================
+
+#[test]
+fn integration_users_persist_across_sessions() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("users.json");
+
+    let open = || {
+        let store = FileUserStore::open(&path).unwrap();
+        UserService::with_store(Box::new(store), Box::new(SequentialIds::default()))
+    };
+
+    let mut service = open();
+    let alice = service
+        .create_user("alice".to_string(), "alice@test.com".to_string(), 25)
+        .unwrap();
+    let bob = service
+        .create_user("bob".to_string(), "bob@test.com".to_string(), 30)
+        .unwrap();
+    assert!(service.delete_user(alice).unwrap());
+
+    // The next session sees bob, and doesn't hand out his id again
+    let mut service = open();
+    assert_eq!(service.count(), 1);
+    assert_eq!(service.find_by_username("bob").unwrap().id, bob);
+    let carol = service
+        .create_user("carol".to_string(), "carol@test.com".to_string(), 41)
+        .unwrap();
+    assert_ne!(carol, bob);
+    assert_eq!(open().count(), 2);
+}
+
+// ============================================================================
+// INTEGRATION TEST 13: FUZZ SEEDS
+// ============================================================================
+
+/// The contents of every file in one fuzz target's seed directory
+fn fuzz_seeds(target: &str) -> Vec<String> {
+    let dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("fuzz/seeds")
+        .join(target);
+    std::fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| std::fs::read_to_string(entry.unwrap().path()).unwrap())
+        .collect()
+}
+
+/// Fuzzing needs nightly, so the seeds (and any crashing inputs added to
+/// them) are also replayed here on stable. This only checks that nothing
+/// panics; the fuzz targets check more.
+#[test]
+fn integration_fuzz_seeds_replay() {
+    let expressions = fuzz_seeds("expression");
+    assert!(!expressions.is_empty());
+    for expression in &expressions {
+        let _ = Calculator::new().evaluate(expression);
+    }
+
+    let users = fuzz_seeds("user_email");
+    assert!(!users.is_empty());
+    for text in &users {
+        // Username, email and age, one per line, as the fuzz target reads them
+        let mut lines = text.splitn(3, '\n');
+        let username = lines.next().unwrap_or_default().to_string();
+        let email = lines.next().unwrap_or_default().to_string();
+        let age = lines
+            .next()
+            .and_then(|age| age.trim().parse().ok())
+            .unwrap_or(0);
+        let _ = UserService::new().create_user(username, email, age);
+    }
+}
+
+// ============================================================================
+// INTEGRATION TEST 14: FILE PROCESSING ON DISK
+// ============================================================================
+
+mod file_processing {
+    use assert_fs::prelude::*;
+    use predicates::prelude::*;
+    use testing::{FileProcessor, ProcessError};
+
+    fn processor() -> FileProcessor {
+        FileProcessor::new("LOG".to_string())
+    }
+
+    #[test]
+    fn transforms_file() {
+        let dir = assert_fs::TempDir::new().unwrap();
+        let input = dir.child("in.txt");
+        input.write_str("started\n\nstopped\r\n").unwrap();
+        let output = dir.child("out.txt");
+
+        let summary = processor().process_file(&input, &output).unwrap();
+
+        output.assert("LOG: STARTED\nLOG: STOPPED\n");
+        assert_eq!(summary.lines_processed, 2);
+        assert_eq!(summary.lines_skipped, 1);
+    }
+
+    #[test]
+    fn missing_input() {
+        let dir = assert_fs::TempDir::new().unwrap();
+        let output = dir.child("out.txt");
+
+        let err = processor()
+            .process_file(&dir.child("missing.txt"), &output)
+            .unwrap_err();
+
+        assert!(matches!(err, ProcessError::NotFound(ref path) if path.ends_with("missing.txt")));
+        output.assert(predicate::path::missing());
+    }
+
+    #[test]
+    fn output_directory_missing() {
+        let dir = assert_fs::TempDir::new().unwrap();
+        let input = dir.child("in.txt");
+        input.write_str("data\n").unwrap();
+
+        let err = processor()
+            .process_file(&input, &dir.child("no/such/dir/out.txt"))
+            .unwrap_err();
+
+        assert!(matches!(err, ProcessError::NotFound(_)));
+    }
+
+    #[test]
+    fn invalid_utf8_leaves_output_untouched() {
+        let dir = assert_fs::TempDir::new().unwrap();
+        let input = dir.child("in.txt");
+        input.write_binary(b"fine\n\xc3\x28\n").unwrap();
+        let output = dir.child("out.txt");
+        output.write_str("previous run\n").unwrap();
+
+        let err = processor().process_file(&input, &output).unwrap_err();
+
+        assert!(matches!(err, ProcessError::InvalidUtf8 { line: 2 }));
+        output.assert("previous run\n");
+    }
+
+    #[test]
+    fn input_over_size_limit() {
+        let dir = assert_fs::TempDir::new().unwrap();
+        let input = dir.child("in.txt");
+        input.write_str(&"line\n".repeat(100)).unwrap();
+        let output = dir.child("out.txt");
+
+        let err = processor()
+            .with_max_size(499)
+            .process_file(&input, &output)
+            .unwrap_err();
+
+        assert!(matches!(
+            err,
+            ProcessError::TooLarge {
+                size: 500,
+                limit: 499
+            }
+        ));
+        output.assert(predicate::path::missing());
+        // Exactly at the limit is fine
+        processor()
+            .with_max_size(500)
+            .process_file(&input, &output)
+            .unwrap();
+    }
+
+    #[test]
+    fn large_input() {
+        let dir = assert_fs::TempDir::new().unwrap();
+        let input = dir.child("in.txt");
+        let lines = 200_000;
+        let text: String = (0..lines).map(|i| format!("entry {}\n", i)).collect();
+        input.write_str(&text).unwrap();
+        let output = dir.child("out.txt");
+
+        let summary = processor()
+            .with_max_size(text.len() as u64)
+            .process_file(&input, &output)
+            .unwrap();
+
+        assert_eq!(summary.lines_processed, lines);
+        output.assert(predicate::str::starts_with("LOG: ENTRY 0\n"));
+        output.assert(predicate::str::ends_with("LOG: ENTRY 199999\n"));
+        // The default limit is well under this
+        assert!(matches!(
+            processor().process_file(&input, &output),
+            Err(ProcessError::TooLarge { .. })
+        ));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn unreadable_input() {
+        use std::fs::Permissions;
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = assert_fs::TempDir::new().unwrap();
+        let input = dir.child("in.txt");
+        input.write_str("secret\n").unwrap();
+        std::fs::set_permissions(&input, Permissions::from_mode(0o000)).unwrap();
+        if std::fs::File::open(&input).is_ok() {
+            // Running as root, where permissions aren't enforced
+            return;
+        }
+
+        let err = processor()
+            .process_file(&input, &dir.child("out.txt"))
+            .unwrap_err();
+
+        assert!(matches!(err, ProcessError::PermissionDenied(_)));
+        assert!(err.to_string().starts_with("permission denied for"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn read_only_output_directory() {
+        use std::fs::Permissions;
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = assert_fs::TempDir::new().unwrap();
+        let input = dir.child("in.txt");
+        input.write_str("data\n").unwrap();
+        let locked = dir.child("locked");
+        locked.create_dir_all().unwrap();
+        std::fs::set_permissions(&locked, Permissions::from_mode(0o555)).unwrap();
+        if std::fs::File::create(locked.child("probe").path()).is_ok() {
+            // Running as root, where permissions aren't enforced
+            return;
+        }
+
+        let err = processor()
+            .process_file(&input, &locked.child("out.txt"))
+            .unwrap_err();
+
+        assert!(matches!(err, ProcessError::PermissionDenied(_)));
+        // Let the temporary directory clean itself up
+        std::fs::set_permissions(&locked, Permissions::from_mode(0o755)).unwrap();
+    }
+}
+
+// ============================================================================
+// INTEGRATION TEST 15: EXCHANGE RATES OVER HTTP
+// ============================================================================
+
+/// `HttpRateClient` against a real HTTP server: wiremock starts one on a
+/// random local port for each test and answers as it's told to. Unlike a
+/// mockall mock, this exercises the request the client actually sends and
+/// how it reads the response.
+mod exchange_rates {
+    use std::time::Duration;
+
+    use testing::{CurrencyConverter, ExchangeRateClient, HttpRateClient, RateError};
+    use wiremock::matchers::{method, path, query_param};
+    use wiremock::{Mock, MockBuilder, MockServer, ResponseTemplate};
+
+    fn usd_to_eur() -> MockBuilder {
+        Mock::given(method("GET"))
+            .and(path("/latest"))
+            .and(query_param("base", "USD"))
+            .and(query_param("symbols", "EUR"))
+    }
+
+    #[tokio::test]
+    async fn fetches_rate() {
+        let server = MockServer::start().await;
+        usd_to_eur()
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "base": "USD",
+                "rates": { "EUR": 0.92 }
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = HttpRateClient::new(server.uri());
+        assert_eq!(client.rate("USD", "EUR").await, Ok(0.92));
+        // The server checks `expect(1)` when it's dropped
+    }
+
+    #[tokio::test]
+    async fn converter_asks_once_per_pair() {
+        let server = MockServer::start().await;
+        usd_to_eur()
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "rates": { "EUR": 0.5 }
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let converter = CurrencyConverter::new(HttpRateClient::new(server.uri()));
+        assert_eq!(converter.convert(10.0, "USD", "EUR").await, Ok(5.0));
+        assert_eq!(converter.convert(3.0, "usd", "eur").await, Ok(1.5));
+    }
+
+    #[tokio::test]
+    async fn server_error() {
+        let server = MockServer::start().await;
+        usd_to_eur()
+            .respond_with(ResponseTemplate::new(500))
+            .mount(&server)
+            .await;
+
+        let client = HttpRateClient::new(server.uri());
+        let err = client.rate("USD", "EUR").await.unwrap_err();
+        assert_eq!(err, RateError::Status(500));
+        assert_eq!(err.to_string(), "rate server returned status 500");
+    }
+
+    #[tokio::test]
+    async fn slow_server_times_out() {
+        let server = MockServer::start().await;
+        usd_to_eur()
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(serde_json::json!({ "rates": { "EUR": 0.92 } }))
+                    .set_delay(Duration::from_secs(2)),
+            )
+            .mount(&server)
+            .await;
+
+        let client = HttpRateClient::new(server.uri()).with_timeout(Duration::from_millis(100));
+        assert_eq!(client.rate("USD", "EUR").await, Err(RateError::TimedOut));
+    }
+
+    #[tokio::test]
+    async fn malformed_json() {
+        let server = MockServer::start().await;
+        usd_to_eur()
+            .respond_with(ResponseTemplate::new(200).set_body_string("{\"rates\": {\"EUR\": "))
+            .mount(&server)
+            .await;
+
+        let client = HttpRateClient::new(server.uri());
+        assert!(matches!(
+            client.rate("USD", "EUR").await,
+            Err(RateError::Malformed(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn wrong_shape_or_bad_rate() {
+        let server = MockServer::start().await;
+        Mock::given(path("/latest"))
+            .and(query_param("symbols", "EUR"))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_json(serde_json::json!({ "EUR": 0.92 })),
+            )
+            .mount(&server)
+            .await;
+        Mock::given(path("/latest"))
+            .and(query_param("symbols", "GBP"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(serde_json::json!({ "rates": { "GBP": -1.0 } })),
+            )
+            .mount(&server)
+            .await;
+
+        let client = HttpRateClient::new(server.uri());
+        assert!(matches!(
+            client.rate("USD", "EUR").await,
+            Err(RateError::Malformed(_))
+        ));
+        assert_eq!(
+            client.rate("USD", "GBP").await,
+            Err(RateError::Malformed("rate -1 for GBP".to_string()))
+        );
+    }
+
+    #[tokio::test]
+    async fn unknown_currency() {
+        let server = MockServer::start().await;
+        Mock::given(path("/latest"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(serde_json::json!({ "rates": { "EUR": 0.92 } })),
+            )
+            .mount(&server)
+            .await;
+
+        let client = HttpRateClient::new(server.uri());
+        assert_eq!(
+            client.rate("USD", "XYZ").await,
+            Err(RateError::UnknownCurrency("XYZ".to_string()))
+        );
+    }
+
+    #[tokio::test]
+    async fn server_unreachable() {
+        // Take a free port, then stop listening on it. (wiremock keeps its
+        // servers in a pool, so a dropped MockServer would still answer.)
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+
+        let client = HttpRateClient::new(format!("http://127.0.0.1:{}", port));
+        assert!(matches!(
+            client.rate("USD", "EUR").await,
+            Err(RateError::Unavailable(_))
+        ));
+    }
+}
+
+// ============================================================================
+// INTEGRATION TEST 16: CASES LOADED FROM tests/data
+// ============================================================================
+
+/// Table-driven tests whose tables live in data files rather than in the
+/// code. Adding a case means adding a line of data, and the files are
+/// easy to review, generate or share with other implementations.
+mod data_driven {
+    use std::collections::HashMap;
+    use std::path::PathBuf;
+
+    use serde::de::DeserializeOwned;
+    use serde::Deserialize;
+    use testing::{celsius_to_fahrenheit, fahrenheit_to_celsius, is_palindrome};
+
+    fn data_path(name: &str) -> PathBuf {
+        std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/data")
+            .join(name)
+    }
+
+    fn read(name: &str) -> String {
+        std::fs::read_to_string(data_path(name))
+            .unwrap_or_else(|e| panic!("can't read tests/data/{}: {}", name, e))
+    }
+
+    /// One line of a CSV file, keyed by the header's column names
+    struct Row {
+        line: usize,
+        fields: HashMap<String, String>,
+    }
+
+    impl Row {
+        fn number(&self, column: &str) -> f64 {
+            let value = &self.fields[column];
+            value.parse().unwrap_or_else(|_| {
+                panic!("line {}: {} {:?} is not a number", self.line, column, value)
+            })
+        }
+    }
+
+    /// The rows of a simple CSV file: a header naming the columns, then
+    /// one line per case. Fields are split on commas, so they can't
+    /// contain any. Blank lines and lines starting with `#` are skipped.
+    fn load_csv(name: &str) -> Vec<Row> {
+        let text = read(name);
+        let mut lines = text
+            .lines()
+            .enumerate()
+            .map(|(i, text)| (i + 1, text))
+            .filter(|(_, text)| !text.trim().is_empty() && !text.starts_with('#'));
+        let (_, header) = lines
+            .next()
+            .unwrap_or_else(|| panic!("{} has no header", name));
+        let columns: Vec<&str> = header.split(',').map(str::trim).collect();
+
+        lines
+            .map(|(line, text)| {
+                let values: Vec<&str> = text.split(',').map(str::trim).collect();
+                assert_eq!(
+                    values.len(),
+                    columns.len(),
+                    "{}:{}: expected {} fields",
+                    name,
+                    line,
+                    columns.len()
+                );
+                let fields = columns
+                    .iter()
+                    .zip(values)
+                    .map(|(column, value)| (column.to_string(), value.to_string()))
+                    .collect();
+                Row { line, fields }
+            })
+            .collect()
+    }
+
+    fn load_json<T: DeserializeOwned>(name: &str) -> T {
+        serde_json::from_str(&read(name)).unwrap_or_else(|e| panic!("{}: {}", name, e))
+    }
+
+    /// Runs `check` on every case and then fails once, listing every case
+    /// that failed, rather than stopping at the first
+    fn check_all<T>(name: &str, cases: &[T], check: impl Fn(&T) -> Result<(), String>) {
+        assert!(!cases.is_empty(), "{} has no cases", name);
+        let failures: Vec<String> = cases.iter().filter_map(|case| check(case).err()).collect();
+        assert!(
+            failures.is_empty(),
+            "{} of {} cases in {} failed:\n  {}",
+            failures.len(),
+            cases.len(),
+            name,
+            failures.join("\n  ")
+        );
+    }
+
+    #[test]
+    fn temperatures_from_csv() {
+        let rows = load_csv("temperatures.csv");
+        check_all("temperatures.csv", &rows, |row| {
+            let (celsius, fahrenheit) = (row.number("celsius"), row.number("fahrenheit"));
+            let converted = celsius_to_fahrenheit(celsius);
+            let back = fahrenheit_to_celsius(fahrenheit);
+            if (converted - fahrenheit).abs() > 0.01 || (back - celsius).abs() > 0.01 {
+                let note = match row.fields["note"].as_str() {
+                    "" => String::new(),
+                    note => format!(" ({})", note),
+                };
+                return Err(format!(
+                    "line {}{}: {} °C gave {} °F, and {} °F gave {} °C",
+                    row.line, note, celsius, converted, fahrenheit, back
+                ));
+            }
+            Ok(())
+        });
+    }
+
+    #[derive(Deserialize)]
+    struct PalindromeCase {
+        input: String,
+        expected: bool,
+        why: String,
+    }
+
+    #[test]
+    fn palindromes_from_json() {
+        let cases: Vec<PalindromeCase> = load_json("palindromes.json");
+        check_all("palindromes.json", &cases, |case| {
+            if is_palindrome(&case.input) == case.expected {
+                return Ok(());
+            }
+            Err(format!(
+                "{:?} ({}): expected {}",
+                case.input, case.why, case.expected
+            ))
+        });
+    }
+}
@@ -69,6 +69,23 @@ fn main() -> Result<()> {
         println!("  Min:     {:.2}", result.min);
         println!("  Max:     {:.2}", result.max);
         println!();
+
+        println!("=== Anomaly Detection (feature: advanced) ===");
+        let series = advanced::fixtures::SyntheticSeries::new(100, 42)
+            .with_spike(40, 15.0)
+            .with_spike(75, -10.0);
+        let mut detector = advanced::AnomalyDetector::new(advanced::DetectorConfig::default())?;
+        for anomaly in detector.detect(&series.generate()) {
+            let reasons: Vec<String> = anomaly.reasons.iter().map(|r| r.to_string()).collect();
+            println!(
+                "  {} = {:.2} (score {:.2}): {}",
+                anomaly.data.name,
+                anomaly.data.value,
+                anomaly.score,
+                reasons.join(", ")
+            );
+        }
+        println!();
     }
 
     #[cfg(not(feature = "all-features"))]
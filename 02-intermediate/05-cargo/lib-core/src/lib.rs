@@ -7,6 +7,11 @@
 //! - `json` (default): Enables JSON serialization support
 //! - `extra`: Enables additional utility functions
 //! - `advanced`: Enables advanced features (includes `extra`)
+//!
+//! ## Storage
+//!
+//! [`store::DataStore`] holds `Data` items for concurrent readers: readers work
+//! on immutable snapshots and never wait for the (single) writer.
 
 use thiserror::Error;
 
@@ -63,6 +68,141 @@ impl Data {
     }
 }
 
+/// Concurrent data store with MVCC-style snapshots
+///
+/// The current state is an immutable [`Snapshot`](store::Snapshot) behind an
+/// `ArcSwap`. Readers load the pointer (lock-free) and keep reading their
+/// snapshot for as long as they like. The writer builds the next snapshot off
+/// to the side and publishes it with a single atomic swap, so readers never
+/// block on writers and never see a half-applied commit.
+pub mod store {
+    use super::{CoreError, Data, Result};
+    use arc_swap::ArcSwap;
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex, MutexGuard};
+
+    /// An immutable, consistent view of the store at one version
+    #[derive(Debug, Default)]
+    pub struct Snapshot {
+        version: u64,
+        items: Vec<Arc<Data>>,
+        index: HashMap<u64, usize>,
+    }
+
+    impl Snapshot {
+        /// Number of commits applied before this snapshot was taken
+        pub fn version(&self) -> u64 {
+            self.version
+        }
+
+        pub fn len(&self) -> usize {
+            self.items.len()
+        }
+
+        pub fn is_empty(&self) -> bool {
+            self.items.is_empty()
+        }
+
+        /// Look up an item by id
+        pub fn get(&self, id: u64) -> Option<&Data> {
+            self.index.get(&id).map(|&i| self.items[i].as_ref())
+        }
+
+        /// Iterate over items in insertion order
+        pub fn iter(&self) -> impl Iterator<Item = &Data> {
+            self.items.iter().map(|item| item.as_ref())
+        }
+    }
+
+    /// Append-only store of `Data` items
+    #[derive(Debug, Default)]
+    pub struct DataStore {
+        current: ArcSwap<Snapshot>,
+        // Serializes writers; readers never touch it
+        writer: Mutex<()>,
+    }
+
+    impl DataStore {
+        pub fn new() -> Self {
+            Self::default()
+        }
+
+        /// Get the latest committed snapshot (lock-free)
+        pub fn snapshot(&self) -> Arc<Snapshot> {
+            self.current.load_full()
+        }
+
+        /// Start a write transaction.
+        ///
+        /// Only one transaction can be open at a time; a second call waits
+        /// until the first one is committed or dropped. Readers are unaffected.
+        pub fn begin(&self) -> Transaction<'_> {
+            // A panicking writer never published anything, so the lock is still usable
+            let guard = self.writer.lock().unwrap_or_else(|e| e.into_inner());
+            Transaction {
+                store: self,
+                _guard: guard,
+                base: self.snapshot(),
+                pending: Vec::new(),
+            }
+        }
+
+        /// Append `items` in a single transaction and return the new version
+        pub fn commit(&self, items: impl IntoIterator<Item = Data>) -> Result<u64> {
+            let mut transaction = self.begin();
+            for item in items {
+                transaction.append(item)?;
+            }
+            transaction.commit()
+        }
+    }
+
+    /// Pending writes that become visible all at once on [`commit`](Transaction::commit).
+    ///
+    /// Dropping a transaction without committing discards its writes.
+    pub struct Transaction<'a> {
+        store: &'a DataStore,
+        _guard: MutexGuard<'a, ()>,
+        base: Arc<Snapshot>,
+        pending: Vec<Data>,
+    }
+
+    impl Transaction<'_> {
+        /// Stage an item; it must be valid and its id must not exist yet
+        pub fn append(&mut self, data: Data) -> Result<()> {
+            data.validate()?;
+            if self.base.get(data.id).is_some() || self.pending.iter().any(|d| d.id == data.id) {
+                return Err(CoreError::InvalidInput(format!(
+                    "id {} already exists",
+                    data.id
+                )));
+            }
+            self.pending.push(data);
+            Ok(())
+        }
+
+        /// Publish the staged items as a new snapshot and return its version
+        pub fn commit(self) -> Result<u64> {
+            // Existing items are shared with the previous snapshot, only the
+            // Arc pointers and the index are copied
+            let mut items = self.base.items.clone();
+            let mut index = self.base.index.clone();
+            for data in self.pending {
+                index.insert(data.id, items.len());
+                items.push(Arc::new(data));
+            }
+
+            let version = self.base.version + 1;
+            self.store.current.store(Arc::new(Snapshot {
+                version,
+                items,
+                index,
+            }));
+            Ok(version)
+        }
+    }
+}
+
 /// JSON serialization support (only available with "json" feature)
 #[cfg(feature = "json")]
 pub mod json {
@@ -115,7 +255,7 @@ pub mod extra {
 /// Advanced features (only available with "advanced" feature)
 #[cfg(feature = "advanced")]
 pub mod advanced {
-    use super::Data;
+    use super::{CoreError, Data, Result};
 
     /// Complex data analysis
     pub struct DataAnalyzer {
@@ -160,6 +300,256 @@ pub mod advanced {
         pub min: f64,
         pub max: f64,
     }
+
+    /// How eagerly the detector flags values
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum Sensitivity {
+        Low,
+        Medium,
+        High,
+    }
+
+    /// Anomaly detector settings
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct DetectorConfig {
+        /// Number of previous values the rolling z-score compares against (at least 2)
+        pub window: usize,
+        /// Flag when the rolling |z-score| exceeds this
+        pub z_threshold: f64,
+        /// EWMA smoothing factor (0-1); higher reacts faster to change
+        pub ewma_alpha: f64,
+        /// Flag when the value is this many EWMA standard deviations from the EWMA mean
+        pub ewma_threshold: f64,
+        /// Values observed before anything can be flagged
+        pub warmup: usize,
+    }
+
+    impl DetectorConfig {
+        /// Preset thresholds for a sensitivity level
+        pub fn with_sensitivity(sensitivity: Sensitivity) -> Self {
+            let (z_threshold, ewma_threshold) = match sensitivity {
+                Sensitivity::Low => (4.5, 5.0),
+                Sensitivity::Medium => (3.5, 4.0),
+                Sensitivity::High => (2.5, 3.0),
+            };
+            Self {
+                z_threshold,
+                ewma_threshold,
+                ..Self::default()
+            }
+        }
+    }
+
+    impl Default for DetectorConfig {
+        fn default() -> Self {
+            Self {
+                window: 20,
+                z_threshold: 3.5,
+                ewma_alpha: 0.1,
+                ewma_threshold: 4.0,
+                warmup: 10,
+            }
+        }
+    }
+
+    /// Why a value was flagged
+    #[derive(Debug, Clone, PartialEq)]
+    pub enum AnomalyReason {
+        /// Too far from the mean of the last `window` values
+        RollingZScore { z: f64 },
+        /// Too far from the exponentially weighted moving average
+        EwmaDeviation { deviation: f64 },
+    }
+
+    impl std::fmt::Display for AnomalyReason {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            match self {
+                AnomalyReason::RollingZScore { z } => write!(f, "rolling z-score {:.2}", z),
+                AnomalyReason::EwmaDeviation { deviation } => {
+                    write!(f, "{:.2} EWMA standard deviations", deviation)
+                }
+            }
+        }
+    }
+
+    /// A flagged item
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct Anomaly {
+        pub data: Data,
+        /// Largest absolute score among the reasons
+        pub score: f64,
+        pub reasons: Vec<AnomalyReason>,
+    }
+
+    /// Flags `Data` values that deviate from recent history.
+    ///
+    /// Two detectors run side by side: a rolling z-score over a fixed window
+    /// (good at isolated spikes) and an EWMA mean/variance (reacts smoothly and
+    /// needs no window). An item is flagged when either one fires.
+    #[derive(Debug, Clone)]
+    pub struct AnomalyDetector {
+        config: DetectorConfig,
+        window: std::collections::VecDeque<f64>,
+        ewma_mean: f64,
+        ewma_var: f64,
+        seen: usize,
+    }
+
+    impl AnomalyDetector {
+        /// Create a detector; fails if `config.window` is below 2, since a
+        /// z-score needs at least two values to have a spread
+        pub fn new(config: DetectorConfig) -> Result<Self> {
+            if config.window < 2 {
+                return Err(CoreError::InvalidInput(format!(
+                    "detector window must be at least 2, got {}",
+                    config.window
+                )));
+            }
+            Ok(Self {
+                window: std::collections::VecDeque::with_capacity(config.window),
+                config,
+                ewma_mean: 0.0,
+                ewma_var: 0.0,
+                seen: 0,
+            })
+        }
+
+        /// Score one incoming item, then add it to the history
+        pub fn observe(&mut self, data: &Data) -> Option<Anomaly> {
+            let x = data.value;
+            let mut reasons = Vec::new();
+
+            if self.seen >= self.config.warmup {
+                if let Some(z) = self.rolling_z(x) {
+                    if z.abs() > self.config.z_threshold {
+                        reasons.push(AnomalyReason::RollingZScore { z });
+                    }
+                }
+                if self.ewma_var > 0.0 {
+                    let deviation = (x - self.ewma_mean) / self.ewma_var.sqrt();
+                    if deviation.abs() > self.config.ewma_threshold {
+                        reasons.push(AnomalyReason::EwmaDeviation { deviation });
+                    }
+                }
+            }
+
+            self.update(x);
+
+            if reasons.is_empty() {
+                return None;
+            }
+            let score = reasons
+                .iter()
+                .map(|reason| match reason {
+                    AnomalyReason::RollingZScore { z } => z.abs(),
+                    AnomalyReason::EwmaDeviation { deviation } => deviation.abs(),
+                })
+                .fold(0.0, f64::max);
+            Some(Anomaly {
+                data: data.clone(),
+                score,
+                reasons,
+            })
+        }
+
+        /// Run the detector over a batch and return the flagged items in order
+        pub fn detect(&mut self, items: &[Data]) -> Vec<Anomaly> {
+            items.iter().filter_map(|item| self.observe(item)).collect()
+        }
+
+        fn rolling_z(&self, x: f64) -> Option<f64> {
+            let n = self.window.len() as f64;
+            if n < 2.0 {
+                return None;
+            }
+            let mean = self.window.iter().sum::<f64>() / n;
+            let var = self.window.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / n;
+            (var > 0.0).then(|| (x - mean) / var.sqrt())
+        }
+
+        fn update(&mut self, x: f64) {
+            if self.window.len() >= self.config.window {
+                self.window.pop_front();
+            }
+            self.window.push_back(x);
+
+            if self.seen == 0 {
+                self.ewma_mean = x;
+            } else {
+                let diff = x - self.ewma_mean;
+                let increment = self.config.ewma_alpha * diff;
+                self.ewma_mean += increment;
+                self.ewma_var = (1.0 - self.config.ewma_alpha) * (self.ewma_var + diff * increment);
+            }
+            self.seen += 1;
+        }
+    }
+
+    /// Deterministic synthetic series for replaying anomaly scenarios
+    pub mod fixtures {
+        use crate::Data;
+
+        /// A noisy baseline with anomalies injected at known positions.
+        ///
+        /// The same seed always produces the same series, so detector runs
+        /// can be replayed and compared.
+        #[derive(Debug, Clone)]
+        pub struct SyntheticSeries {
+            pub len: usize,
+            pub seed: u64,
+            pub baseline: f64,
+            /// Noise is uniform in `-noise..noise`
+            pub noise: f64,
+            /// `(index, offset)` pairs added on top of the baseline
+            pub spikes: Vec<(usize, f64)>,
+        }
+
+        impl SyntheticSeries {
+            pub fn new(len: usize, seed: u64) -> Self {
+                Self {
+                    len,
+                    seed,
+                    baseline: 100.0,
+                    noise: 1.0,
+                    spikes: Vec::new(),
+                }
+            }
+
+            pub fn with_spike(mut self, index: usize, offset: f64) -> Self {
+                self.spikes.push((index, offset));
+                self
+            }
+
+            /// Indices of the injected anomalies, in order
+            pub fn anomaly_indices(&self) -> Vec<usize> {
+                let mut indices: Vec<usize> = self.spikes.iter().map(|&(i, _)| i).collect();
+                indices.sort_unstable();
+                indices
+            }
+
+            /// Generate the series; item ids are the indices
+            pub fn generate(&self) -> Vec<Data> {
+                // 64-bit LCG (Knuth's MMIX constants) keeps the fixture dependency-free
+                let mut state = self.seed;
+                (0..self.len)
+                    .map(|i| {
+                        state = state
+                            .wrapping_mul(6364136223846793005)
+                            .wrapping_add(1442695040888963407);
+                        let unit = (state >> 11) as f64 / (1u64 << 53) as f64;
+                        let spike: f64 = self
+                            .spikes
+                            .iter()
+                            .filter(|&&(index, _)| index == i)
+                            .map(|&(_, offset)| offset)
+                            .sum();
+                        let value = self.baseline + (unit * 2.0 - 1.0) * self.noise + spike;
+                        Data::new(i as u64, format!("point-{}", i), value.max(0.0))
+                    })
+                    .collect()
+            }
+        }
+    }
 }
 
 #[cfg(test)]
@@ -192,6 +582,144 @@ mod tests {
         assert_eq!(data.calculate(), 25.0); // 10.0 * 2.0 + 5.0
     }
 
+    #[test]
+    fn test_store_commit_and_snapshot() {
+        let store = store::DataStore::new();
+        assert!(store.snapshot().is_empty());
+
+        let version = store
+            .commit(vec![Data::new(1, "a", 1.0), Data::new(2, "b", 2.0)])
+            .unwrap();
+        assert_eq!(version, 1);
+
+        let snapshot = store.snapshot();
+        assert_eq!(snapshot.version(), 1);
+        assert_eq!(snapshot.len(), 2);
+        assert_eq!(snapshot.get(2).unwrap().name, "b");
+        assert!(snapshot.get(3).is_none());
+    }
+
+    #[test]
+    fn test_store_rejects_invalid_and_duplicate_items() {
+        let store = store::DataStore::new();
+        store.commit(vec![Data::new(1, "a", 1.0)]).unwrap();
+
+        assert!(store.commit(vec![Data::new(1, "again", 1.0)]).is_err());
+        assert!(store
+            .commit(vec![Data::new(2, "b", 2.0), Data::new(2, "c", 3.0)])
+            .is_err());
+        assert!(store.commit(vec![Data::new(3, "", 1.0)]).is_err());
+
+        // Failed commits publish nothing
+        let snapshot = store.snapshot();
+        assert_eq!(snapshot.version(), 1);
+        assert_eq!(snapshot.len(), 1);
+    }
+
+    #[test]
+    fn test_store_snapshot_isolation() {
+        let store = store::DataStore::new();
+        store.commit(vec![Data::new(1, "a", 1.0)]).unwrap();
+        let before = store.snapshot();
+
+        let mut transaction = store.begin();
+        transaction.append(Data::new(2, "b", 2.0)).unwrap();
+        // Uncommitted writes are invisible
+        assert_eq!(store.snapshot().len(), 1);
+        transaction.commit().unwrap();
+
+        // Old snapshots keep their view, new ones see the commit
+        assert_eq!(before.len(), 1);
+        assert_eq!(store.snapshot().len(), 2);
+
+        // Dropping a transaction discards it
+        let mut transaction = store.begin();
+        transaction.append(Data::new(3, "c", 3.0)).unwrap();
+        drop(transaction);
+        assert_eq!(store.snapshot().len(), 2);
+    }
+
+    #[test]
+    fn test_store_readers_never_block_on_writer() {
+        use std::sync::mpsc;
+        use std::sync::Arc;
+        use std::thread;
+        use std::time::Duration;
+
+        let store = Arc::new(store::DataStore::new());
+        store.commit(vec![Data::new(1, "a", 1.0)]).unwrap();
+
+        // The writer opens a transaction and holds it until released
+        let (opened_tx, opened_rx) = mpsc::channel();
+        let (release_tx, release_rx) = mpsc::channel::<()>();
+        let writer = {
+            let store = Arc::clone(&store);
+            thread::spawn(move || {
+                let mut transaction = store.begin();
+                transaction.append(Data::new(2, "b", 2.0)).unwrap();
+                opened_tx.send(()).unwrap();
+                release_rx.recv().unwrap();
+                transaction.commit().unwrap()
+            })
+        };
+        opened_rx.recv().unwrap();
+
+        let (done_tx, done_rx) = mpsc::channel();
+        for _ in 0..4 {
+            let store = Arc::clone(&store);
+            let done_tx = done_tx.clone();
+            thread::spawn(move || {
+                for _ in 0..1000 {
+                    let snapshot = store.snapshot();
+                    assert_eq!(snapshot.version(), 1);
+                    assert_eq!(snapshot.len(), 1);
+                }
+                done_tx.send(()).unwrap();
+            });
+        }
+        drop(done_tx);
+
+        // Every reader must finish while the writer is still holding its lock
+        for _ in 0..4 {
+            done_rx
+                .recv_timeout(Duration::from_secs(5))
+                .expect("reader blocked on the open transaction");
+        }
+        assert!(!writer.is_finished());
+
+        release_tx.send(()).unwrap();
+        assert_eq!(writer.join().unwrap(), 2);
+        assert_eq!(store.snapshot().len(), 2);
+    }
+
+    #[test]
+    fn test_store_concurrent_readers_see_consistent_snapshots() {
+        use std::sync::Arc;
+        use std::thread;
+
+        let store = Arc::new(store::DataStore::new());
+
+        let reader = {
+            let store = Arc::clone(&store);
+            thread::spawn(move || {
+                for _ in 0..1000 {
+                    // Each commit adds 10 items, so every snapshot holds a multiple of 10
+                    let snapshot = store.snapshot();
+                    assert_eq!(snapshot.len() as u64, snapshot.version() * 10);
+                }
+            })
+        };
+
+        for batch in 0..50u64 {
+            store
+                .commit((0..10).map(|i| Data::new(batch * 10 + i, "item", i as f64)))
+                .unwrap();
+        }
+
+        reader.join().unwrap();
+        assert_eq!(store.snapshot().len(), 500);
+    }
+
     #[cfg(feature = "json")]
     #[test]
     fn test_json_serialization() {
@@ -238,4 +766,86 @@ mod tests {
         assert_eq!(result.min, 10.0);
         assert_eq!(result.max, 30.0);
     }
+
+    #[cfg(feature = "advanced")]
+    #[test]
+    fn test_anomaly_detector_flags_injected_spikes() {
+        use advanced::fixtures::SyntheticSeries;
+        use advanced::{AnomalyDetector, DetectorConfig};
+
+        let series = SyntheticSeries::new(200, 42)
+            .with_spike(50, 15.0)
+            .with_spike(120, -12.0)
+            .with_spike(170, 20.0);
+
+        let mut detector = AnomalyDetector::new(DetectorConfig::default()).unwrap();
+        let anomalies = detector.detect(&series.generate());
+
+        let flagged: Vec<usize> = anomalies.iter().map(|a| a.data.id as usize).collect();
+        assert_eq!(flagged, series.anomaly_indices());
+        for anomaly in &anomalies {
+            assert!(anomaly.score > 3.5);
+            assert!(!anomaly.reasons.is_empty());
+        }
+    }
+
+    #[cfg(feature = "advanced")]
+    #[test]
+    fn test_anomaly_detector_clean_series_and_replay() {
+        use advanced::fixtures::SyntheticSeries;
+        use advanced::{AnomalyDetector, DetectorConfig};
+
+        let series = SyntheticSeries::new(500, 7);
+        assert_eq!(series.generate(), series.generate());
+
+        let mut detector = AnomalyDetector::new(DetectorConfig::default()).unwrap();
+        assert!(detector.detect(&series.generate()).is_empty());
+    }
+
+    #[cfg(feature = "advanced")]
+    #[test]
+    fn test_anomaly_detector_sensitivity() {
+        use advanced::fixtures::SyntheticSeries;
+        use advanced::{AnomalyDetector, AnomalyReason, DetectorConfig, Sensitivity};
+
+        // A small spike: only the sensitive detector should notice it
+        let data = SyntheticSeries::new(100, 3).with_spike(60, 1.2).generate();
+        let detect = |sensitivity| {
+            AnomalyDetector::new(DetectorConfig::with_sensitivity(sensitivity))
+                .unwrap()
+                .detect(&data)
+        };
+
+        let high = detect(Sensitivity::High);
+        let spike = high.iter().find(|a| a.data.id == 60).unwrap();
+        assert!(spike
+            .reasons
+            .iter()
+            .any(|r| matches!(r, AnomalyReason::RollingZScore { .. })));
+
+        assert!(detect(Sensitivity::Medium).iter().all(|a| a.data.id != 60));
+        assert!(detect(Sensitivity::Low).is_empty());
+    }
+
+    #[cfg(feature = "advanced")]
+    #[test]
+    fn test_anomaly_detector_rejects_short_window() {
+        use advanced::{AnomalyDetector, DetectorConfig};
+
+        for window in [0, 1] {
+            let config = DetectorConfig {
+                window,
+                ..DetectorConfig::default()
+            };
+            assert!(matches!(
+                AnomalyDetector::new(config),
+                Err(CoreError::InvalidInput(_))
+            ));
+        }
+        let config = DetectorConfig {
+            window: 2,
+            ..DetectorConfig::default()
+        };
+        assert!(AnomalyDetector::new(config).is_ok());
+    }
 }
@@ -0,0 +1,50 @@
+//! `cargo xtask completions` / `cargo xtask man`: files generated from the
+//! taskflow CLI (06-libraries/05-clap)
+//!
+//! The clap `Command` lives inside the taskflow binary, so rather than
+//! duplicating its definition here the binary is built and asked to write
+//! the files itself (`taskflow completions <shell>`, `taskflow man`).
+
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::{build_binary, capture, repo_root, run_command};
+
+/// Shell name understood by `taskflow completions`, and the file name each
+/// shell looks for
+const SHELLS: [(&str, &str); 4] = [
+    ("bash", "taskflow.bash"),
+    ("zsh", "_taskflow"),
+    ("fish", "taskflow.fish"),
+    ("power-shell", "_taskflow.ps1"),
+];
+
+pub fn build_taskflow(release: bool) -> Result<PathBuf> {
+    let manifest = repo_root().join("06-libraries/05-clap/Cargo.toml");
+    build_binary(&manifest, "clap-example", release)
+}
+
+pub fn completions(taskflow: &Path, out_dir: &Path) -> Result<()> {
+    fs::create_dir_all(out_dir)
+        .with_context(|| format!("could not create {}", out_dir.display()))?;
+    for (shell, file) in SHELLS {
+        let script = capture(Command::new(taskflow).args(["completions", shell]))?;
+        let path = out_dir.join(file);
+        fs::write(&path, script).with_context(|| format!("could not write {}", path.display()))?;
+        eprintln!("wrote {}", path.display());
+    }
+    Ok(())
+}
+
+pub fn man_pages(taskflow: &Path, out_dir: &Path) -> Result<()> {
+    run_command(
+        Command::new(taskflow)
+            .arg("man")
+            .arg("--out-dir")
+            .arg(out_dir),
+    )?;
+    eprintln!("wrote man pages to {}", out_dir.display());
+    Ok(())
+}
@@ -0,0 +1,118 @@
+//! `cargo xtask dist`: release archives
+//!
+//! For each binary: a release build, a staging directory laid out the way
+//! it will be installed, and a `<name>-<version>-<host>.tar.gz` of it in
+//! `target/dist`. The archives are written with the `tar` and `flate2`
+//! crates, so packaging doesn't depend on a system `tar` (or on Windows
+//! having one).
+
+use anyhow::{Context, Result};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::env::consts::EXE_SUFFIX;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+
+use crate::{build_binary, capture, cli_assets, metadata, repo_root, target_dir, workspace_root};
+
+/// A binary to ship
+struct Artifact {
+    /// Installed name, which is also the archive name
+    name: &'static str,
+    manifest: PathBuf,
+    package: &'static str,
+    /// Ship shell completions and man pages generated by the binary
+    cli_assets: bool,
+}
+
+pub fn package_all() -> Result<()> {
+    let artifacts = [
+        Artifact {
+            name: "taskflow",
+            manifest: repo_root().join("06-libraries/05-clap/Cargo.toml"),
+            package: "clap-example",
+            cli_assets: true,
+        },
+        Artifact {
+            name: "app",
+            manifest: workspace_root().join("Cargo.toml"),
+            package: "bin-app",
+            cli_assets: false,
+        },
+    ];
+
+    let dist_dir = target_dir()?.join("dist");
+    let host = host_triple()?;
+    let mut archives = Vec::new();
+    for artifact in &artifacts {
+        archives.push(package(artifact, &dist_dir, &host)?);
+    }
+
+    eprintln!("\nRelease archives:");
+    for archive in archives {
+        let size = fs::metadata(&archive)?.len();
+        eprintln!("  {} ({} KiB)", archive.display(), size.div_ceil(1024));
+    }
+    Ok(())
+}
+
+fn package(artifact: &Artifact, dist_dir: &Path, host: &str) -> Result<PathBuf> {
+    let binary = build_binary(&artifact.manifest, artifact.package, true)?;
+    let version = package_version(&artifact.manifest, artifact.package)?;
+    let base_name = format!("{}-{}-{}", artifact.name, version, host);
+
+    // Start from a clean staging directory so removed files don't linger
+    let staging = dist_dir.join(&base_name);
+    if staging.exists() {
+        fs::remove_dir_all(&staging)?;
+    }
+    let bin_dir = staging.join("bin");
+    fs::create_dir_all(&bin_dir)?;
+
+    let installed = bin_dir.join(format!("{}{}", artifact.name, EXE_SUFFIX));
+    fs::copy(&binary, &installed)
+        .with_context(|| format!("could not copy {}", binary.display()))?;
+    fs::copy(repo_root().join("LICENSE"), staging.join("LICENSE"))?;
+    if artifact.cli_assets {
+        cli_assets::completions(&installed, &staging.join("completions"))?;
+        cli_assets::man_pages(&installed, &staging.join("man"))?;
+    }
+
+    let archive = dist_dir.join(format!("{}.tar.gz", base_name));
+    write_tar_gz(&staging, &base_name, &archive)?;
+    Ok(archive)
+}
+
+/// Archive `dir` with every path under a top-level `prefix/` directory, so
+/// extracting it doesn't scatter files into the current directory
+fn write_tar_gz(dir: &Path, prefix: &str, archive: &Path) -> Result<()> {
+    let file =
+        File::create(archive).with_context(|| format!("could not create {}", archive.display()))?;
+    let mut tar = tar::Builder::new(GzEncoder::new(file, Compression::best()));
+    tar.append_dir_all(prefix, dir)?;
+    // Both layers must be finished explicitly: dropping them would
+    // swallow any error from writing the last block
+    tar.into_inner()?.finish()?;
+    Ok(())
+}
+
+fn package_version(manifest: &Path, package: &str) -> Result<String> {
+    let metadata = metadata(manifest)?;
+    metadata["packages"]
+        .as_array()
+        .and_then(|packages| packages.iter().find(|p| p["name"] == package))
+        .and_then(|package| package["version"].as_str())
+        .map(str::to_string)
+        .with_context(|| format!("no version for {} in {}", package, manifest.display()))
+}
+
+/// The target triple rustc builds for by default, e.g. x86_64-unknown-linux-gnu
+fn host_triple() -> Result<String> {
+    let rustc = std::env::var_os("RUSTC").unwrap_or_else(|| "rustc".into());
+    let version = capture(std::process::Command::new(rustc).arg("-vV"))?;
+    version
+        .lines()
+        .find_map(|line| line.strip_prefix("host: "))
+        .map(str::to_string)
+        .context("`rustc -vV` printed no host triple")
+}
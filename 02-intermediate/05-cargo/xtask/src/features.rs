@@ -0,0 +1,155 @@
+//! `cargo xtask features`: the lib-core feature matrix
+//!
+//! CI usually only builds the defaults and `--all-features`, so code behind
+//! `#[cfg(feature = "extra")]` that secretly needs `json` goes unnoticed.
+//! This builds every combination instead. Combinations that enable the same
+//! features once implications are followed (`advanced` turns on `extra`)
+//! are only run once.
+
+use anyhow::{Context, Result};
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::{cargo, metadata, run_command, summarize, workspace_root};
+
+const PACKAGE: &str = "lib-core";
+
+pub fn run_matrix(clippy: bool) -> Result<()> {
+    let features = package_features(PACKAGE)?;
+    let combinations = distinct_combinations(&features);
+    eprintln!(
+        "{} features, {} distinct combinations",
+        features.len(),
+        combinations.len()
+    );
+
+    let results: Vec<_> = combinations
+        .iter()
+        .map(|combination| {
+            let list = combination.iter().cloned().collect::<Vec<_>>().join(",");
+            let mut command = cargo();
+            command
+                .arg(if clippy { "clippy" } else { "test" })
+                .args(["--package", PACKAGE, "--no-default-features"])
+                .arg(format!("--features={}", list))
+                .current_dir(workspace_root());
+            if clippy {
+                command.args(["--all-targets", "--", "-D", "warnings"]);
+            }
+            let name = if list.is_empty() {
+                "(none)".to_string()
+            } else {
+                list
+            };
+            (name, run_command(&mut command))
+        })
+        .collect();
+
+    summarize(&format!("{} feature matrix", PACKAGE), &results)
+}
+
+/// Feature name -> features it enables, read from `cargo metadata` so the
+/// matrix follows Cargo.toml without being edited alongside it
+type FeatureMap = BTreeMap<String, Vec<String>>;
+
+fn package_features(package: &str) -> Result<FeatureMap> {
+    let metadata = metadata(&workspace_root().join("Cargo.toml"))?;
+    let package = metadata["packages"]
+        .as_array()
+        .and_then(|packages| packages.iter().find(|p| p["name"] == package))
+        .with_context(|| format!("no package named {} in the workspace", package))?;
+
+    let features = package["features"]
+        .as_object()
+        .context("package has no feature table")?;
+    Ok(features
+        .iter()
+        .filter(|(name, _)| name.as_str() != "default")
+        .map(|(name, enables)| {
+            let enables = enables
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(|value| value.as_str())
+                .map(str::to_string)
+                .collect();
+            (name.clone(), enables)
+        })
+        .collect())
+}
+
+/// Every subset of the features, minus those that resolve to the same set
+/// as an earlier, smaller subset
+fn distinct_combinations(features: &FeatureMap) -> Vec<BTreeSet<String>> {
+    let names: Vec<&String> = features.keys().collect();
+    let mut seen = BTreeSet::new();
+    let mut combinations = Vec::new();
+
+    let mut subsets: Vec<BTreeSet<String>> = (0..1u32 << names.len())
+        .map(|mask| {
+            names
+                .iter()
+                .enumerate()
+                .filter(|(bit, _)| mask & (1 << bit) != 0)
+                .map(|(_, name)| (*name).clone())
+                .collect()
+        })
+        .collect();
+    subsets.sort_by_key(|subset| subset.len());
+
+    for subset in subsets {
+        if seen.insert(resolve(features, &subset)) {
+            combinations.push(subset);
+        }
+    }
+    combinations
+}
+
+/// Follow feature-to-feature edges; `dep:x` and `x/y` entries are
+/// dependencies, not features of this package
+fn resolve(features: &FeatureMap, enabled: &BTreeSet<String>) -> BTreeSet<String> {
+    let mut resolved = BTreeSet::new();
+    let mut pending: Vec<&str> = enabled.iter().map(String::as_str).collect();
+    while let Some(feature) = pending.pop() {
+        if resolved.insert(feature.to_string()) {
+            pending.extend(
+                features
+                    .get(feature)
+                    .into_iter()
+                    .flatten()
+                    .map(String::as_str)
+                    .filter(|enables| features.contains_key(*enables)),
+            );
+        }
+    }
+    resolved
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_implied_features_are_deduplicated() {
+        let features: FeatureMap = [
+            ("json", vec!["dep:serde", "dep:serde_json"]),
+            ("extra", vec![]),
+            ("advanced", vec!["extra"]),
+        ]
+        .into_iter()
+        .map(|(name, enables)| {
+            let enables = enables.into_iter().map(String::from).collect();
+            (name.to_string(), enables)
+        })
+        .collect();
+
+        let combinations = distinct_combinations(&features);
+
+        // 8 subsets, but {advanced} == {advanced, extra} with and without json
+        assert_eq!(combinations.len(), 6);
+        assert!(combinations[0].is_empty());
+        let advanced_only = BTreeSet::from(["advanced".to_string()]);
+        assert!(combinations.contains(&advanced_only));
+        let redundant = BTreeSet::from(["advanced".to_string(), "extra".to_string()]);
+        assert!(!combinations.contains(&redundant));
+    }
+}
@@ -0,0 +1,267 @@
+//! Project automation using the cargo-xtask pattern
+//!
+//! Instead of shell scripts or a Makefile, repository chores live in an
+//! ordinary Rust binary in the workspace. The alias in `.cargo/config.toml`
+//! turns `cargo xtask <task>` into `cargo run --package xtask -- <task>`,
+//! so the only tool a contributor needs is cargo itself, and the automation
+//! works the same on every platform.
+
+use anyhow::{bail, Context, Result};
+use std::env;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+mod cli_assets;
+mod dist;
+mod features;
+mod wasm;
+
+const USAGE: &str = "\
+Usage: cargo xtask <TASK> [OPTIONS]
+
+Tasks:
+  wasm [EXAMPLE...]     Build the 10-wasm examples with wasm-pack
+      --target <T>        wasm-pack target: web (default), nodejs, bundler
+      --dev               Unoptimized build
+  features              Test lib-core with every combination of its features
+      --clippy            Run clippy -D warnings instead of the tests
+  completions           Generate taskflow's shell completion scripts
+      --out-dir <DIR>     Default: target/xtask/completions
+  man                   Generate taskflow's man pages
+      --out-dir <DIR>     Default: target/xtask/man
+  dist                  Package release archives into target/dist
+  help                  Show this message
+";
+
+enum Task {
+    Wasm {
+        target: String,
+        dev: bool,
+        only: Vec<String>,
+    },
+    Features {
+        clippy: bool,
+    },
+    Completions {
+        out_dir: Option<PathBuf>,
+    },
+    Man {
+        out_dir: Option<PathBuf>,
+    },
+    Dist,
+    Help,
+}
+
+fn main() {
+    let result = parse_args(env::args_os().skip(1).collect()).and_then(run);
+    if let Err(e) = result {
+        eprintln!("error: {:#}", e);
+        std::process::exit(1);
+    }
+}
+
+fn run(task: Task) -> Result<()> {
+    match task {
+        Task::Wasm { target, dev, only } => wasm::build_all(&target, dev, &only),
+        Task::Features { clippy } => features::run_matrix(clippy),
+        Task::Completions { out_dir } => {
+            let out_dir = match out_dir {
+                Some(dir) => dir,
+                None => xtask_dir()?.join("completions"),
+            };
+            cli_assets::completions(&cli_assets::build_taskflow(false)?, &out_dir)
+        }
+        Task::Man { out_dir } => {
+            let out_dir = match out_dir {
+                Some(dir) => dir,
+                None => xtask_dir()?.join("man"),
+            };
+            cli_assets::man_pages(&cli_assets::build_taskflow(false)?, &out_dir)
+        }
+        Task::Dist => dist::package_all(),
+        Task::Help => {
+            print!("{}", USAGE);
+            Ok(())
+        }
+    }
+}
+
+fn parse_args(args: Vec<OsString>) -> Result<Task> {
+    let mut args = args.into_iter().map(|arg| {
+        arg.into_string()
+            .map_err(|arg| anyhow::anyhow!("argument is not valid UTF-8: {:?}", arg))
+    });
+    let Some(name) = args.next().transpose()? else {
+        print!("{}", USAGE);
+        bail!("no task given");
+    };
+
+    let mut task = match name.as_str() {
+        "wasm" => Task::Wasm {
+            target: "web".to_string(),
+            dev: false,
+            only: Vec::new(),
+        },
+        "features" => Task::Features { clippy: false },
+        "completions" => Task::Completions { out_dir: None },
+        "man" => Task::Man { out_dir: None },
+        "dist" => Task::Dist,
+        "help" | "--help" | "-h" => Task::Help,
+        other => bail!("unknown task `{}` (see `cargo xtask help`)", other),
+    };
+
+    while let Some(arg) = args.next().transpose()? {
+        let mut value = |flag: &str| {
+            args.next()
+                .transpose()?
+                .with_context(|| format!("{} needs a value", flag))
+        };
+        match (&mut task, arg.as_str()) {
+            (Task::Wasm { target, .. }, "--target") => *target = value("--target")?,
+            (Task::Wasm { dev, .. }, "--dev") => *dev = true,
+            (Task::Wasm { only, .. }, example) if !example.starts_with('-') => {
+                only.push(example.to_string())
+            }
+            (Task::Features { clippy }, "--clippy") => *clippy = true,
+            (Task::Completions { out_dir } | Task::Man { out_dir }, "--out-dir") => {
+                *out_dir = Some(PathBuf::from(value("--out-dir")?))
+            }
+            (_, other) => bail!("unexpected argument `{}` for `{}`", other, name),
+        }
+    }
+    Ok(task)
+}
+
+// ============================================================================
+// Shared helpers
+// ============================================================================
+
+/// The `02-intermediate/05-cargo` workspace this crate belongs to
+fn workspace_root() -> &'static Path {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .parent()
+        .expect("xtask lives inside the workspace")
+}
+
+/// The root of the whole examples repository
+fn repo_root() -> &'static Path {
+    workspace_root()
+        .ancestors()
+        .nth(2)
+        .expect("workspace lives two levels below the repository root")
+}
+
+/// The cargo that started us, so `cargo +nightly xtask` stays on nightly
+fn cargo() -> Command {
+    Command::new(env::var_os("CARGO").unwrap_or_else(|| "cargo".into()))
+}
+
+/// Echo a command, run it with inherited stdio, and fail on a non-zero exit
+fn run_command(command: &mut Command) -> Result<()> {
+    eprintln!("$ {}", display(command));
+    let status = command
+        .status()
+        .with_context(|| format!("could not start {:?}", command.get_program()))?;
+    if !status.success() {
+        bail!("`{}` failed with {}", display(command), status);
+    }
+    Ok(())
+}
+
+/// Run a command and return its stdout, letting stderr through
+fn capture(command: &mut Command) -> Result<String> {
+    let output = command
+        .stderr(Stdio::inherit())
+        .output()
+        .with_context(|| format!("could not start {:?}", command.get_program()))?;
+    if !output.status.success() {
+        bail!("`{}` failed with {}", display(command), output.status);
+    }
+    String::from_utf8(output.stdout).context("command printed invalid UTF-8")
+}
+
+fn display(command: &Command) -> String {
+    let mut line = command.get_program().to_string_lossy().into_owned();
+    for arg in command.get_args() {
+        line.push(' ');
+        line.push_str(&arg.to_string_lossy());
+    }
+    line
+}
+
+/// `cargo metadata` for a manifest: packages, features, target directory
+fn metadata(manifest: &Path) -> Result<serde_json::Value> {
+    let json = capture(
+        cargo()
+            .args([
+                "metadata",
+                "--format-version",
+                "1",
+                "--no-deps",
+                "--manifest-path",
+            ])
+            .arg(manifest),
+    )?;
+    serde_json::from_str(&json).context("could not parse cargo metadata")
+}
+
+/// Where build outputs go, honouring CARGO_TARGET_DIR and friends
+fn target_dir() -> Result<PathBuf> {
+    let metadata = metadata(&workspace_root().join("Cargo.toml"))?;
+    metadata["target_directory"]
+        .as_str()
+        .map(PathBuf::from)
+        .context("cargo metadata has no target_directory")
+}
+
+/// Default output directory for generated files
+fn xtask_dir() -> Result<PathBuf> {
+    Ok(target_dir()?.join("xtask"))
+}
+
+/// Build one binary and return the path cargo wrote it to
+///
+/// `--message-format=json` reports every artifact, which is more reliable
+/// than guessing `target/<profile>/<name>` (custom target dirs, `.exe`, ...).
+fn build_binary(manifest: &Path, package: &str, release: bool) -> Result<PathBuf> {
+    let mut command = cargo();
+    command
+        .args([
+            "build",
+            "--message-format=json-render-diagnostics",
+            "--package",
+        ])
+        .arg(package)
+        .arg("--manifest-path")
+        .arg(manifest);
+    if release {
+        command.arg("--release");
+    }
+    eprintln!("$ {}", display(&command));
+
+    let output = capture(&mut command)?;
+    output
+        .lines()
+        .filter_map(|line| serde_json::from_str::<serde_json::Value>(line).ok())
+        .filter(|message| message["reason"] == "compiler-artifact")
+        .filter_map(|message| message["executable"].as_str().map(PathBuf::from))
+        .next_back()
+        .with_context(|| format!("cargo built no executable for {}", package))
+}
+
+/// Print a pass/fail table and fail if anything failed
+fn summarize(title: &str, results: &[(String, Result<()>)]) -> Result<()> {
+    eprintln!("\n{}:", title);
+    for (name, result) in results {
+        match result {
+            Ok(()) => eprintln!("  ok    {}", name),
+            Err(e) => eprintln!("  FAIL  {} ({:#})", name, e),
+        }
+    }
+    let failed = results.iter().filter(|(_, result)| result.is_err()).count();
+    if failed > 0 {
+        bail!("{} of {} failed", failed, results.len());
+    }
+    Ok(())
+}
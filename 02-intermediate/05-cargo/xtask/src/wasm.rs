@@ -0,0 +1,79 @@
+//! `cargo xtask wasm`: build every 10-wasm example with wasm-pack
+//!
+//! Each example is its own crate, so this walks the directory instead of
+//! keeping a list. A failing example doesn't stop the others; the summary
+//! at the end shows which ones broke.
+
+use anyhow::{bail, Context, Result};
+use std::fs;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+use crate::{repo_root, run_command, summarize};
+
+const TARGETS: [&str; 3] = ["web", "nodejs", "bundler"];
+
+pub fn build_all(target: &str, dev: bool, only: &[String]) -> Result<()> {
+    if !TARGETS.contains(&target) {
+        bail!(
+            "unknown wasm-pack target `{}` (expected one of {:?})",
+            target,
+            TARGETS
+        );
+    }
+    check_wasm_pack()?;
+
+    let examples = examples(only)?;
+    if examples.is_empty() {
+        bail!("no wasm example matches {:?}", only);
+    }
+
+    let results: Vec<_> = examples
+        .into_iter()
+        .map(|dir| {
+            let name = dir.file_name().unwrap().to_string_lossy().into_owned();
+            let mut command = Command::new("wasm-pack");
+            command
+                .arg("build")
+                .arg(&dir)
+                .args(["--target", target])
+                .arg(if dev { "--dev" } else { "--release" });
+            let result = run_command(&mut command);
+            (format!("{} -> {}/pkg", name, dir.display()), result)
+        })
+        .collect();
+
+    summarize("wasm-pack builds", &results)
+}
+
+/// Example directories under 10-wasm, sorted, optionally filtered by name
+fn examples(only: &[String]) -> Result<Vec<PathBuf>> {
+    let wasm_dir = repo_root().join("10-wasm");
+    let mut examples = Vec::new();
+    for entry in
+        fs::read_dir(&wasm_dir).with_context(|| format!("could not read {}", wasm_dir.display()))?
+    {
+        let path = entry?.path();
+        if !path.join("Cargo.toml").is_file() {
+            continue;
+        }
+        let name = path.file_name().unwrap().to_string_lossy();
+        if only.is_empty() || only.iter().any(|filter| name.contains(filter.as_str())) {
+            examples.push(path);
+        }
+    }
+    examples.sort();
+    Ok(examples)
+}
+
+fn check_wasm_pack() -> Result<()> {
+    let found = Command::new("wasm-pack")
+        .arg("--version")
+        .stdout(Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success());
+    if !found {
+        bail!("wasm-pack not found on PATH; install it with `cargo install wasm-pack`");
+    }
+    Ok(())
+}
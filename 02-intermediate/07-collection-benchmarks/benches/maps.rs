@@ -0,0 +1,145 @@
+//! HashMap vs BTreeMap vs IndexMap
+//!
+//! Run with: cargo bench --bench maps
+//!
+//! Typical results, and why:
+//!
+//! - Lookups: HashMap and IndexMap are close, and both pull ahead of
+//!   BTreeMap as the map grows. A hash lookup is O(1): hash the key, probe
+//!   a slot or two. A BTreeMap lookup is O(log n) comparisons across a few
+//!   tree nodes, each of which may be a cache miss.
+//!
+//! - Building: similar ordering. IndexMap does a little more work per
+//!   insert (a hash table of indices plus a Vec of entries), so it trails
+//!   HashMap slightly.
+//!
+//! - Iterating: IndexMap is fastest, because its entries sit in one dense
+//!   Vec. HashMap has to skip empty slots, and BTreeMap walks tree nodes.
+//!
+//! - Word counting with String keys: hashing a short string with the
+//!   default SipHash is comparable to a few `memcmp`s, so the gap narrows.
+//!   A faster hasher (e.g. `ahash` or `rustc-hash`) would widen it again,
+//!   at the cost of HashDoS resistance.
+//!
+//! Takeaway: HashMap for plain lookups, BTreeMap when you need sorted
+//! iteration or range queries, IndexMap when you need insertion order or
+//! iterate far more than you look up.
+
+use collection_benchmarks::*;
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use indexmap::IndexMap;
+use std::collections::{BTreeMap, HashMap};
+
+const SIZES: [usize; 3] = [100, 10_000, 100_000];
+
+// ============================================================================
+// BENCHMARK 1: BUILD
+// ============================================================================
+
+fn benchmark_build(c: &mut Criterion) {
+    let mut group = c.benchmark_group("map_build");
+
+    for size in SIZES {
+        let keys = random_keys(size, 1);
+        group.bench_with_input(BenchmarkId::new("HashMap", size), &keys, |b, keys| {
+            b.iter(|| build_map::<HashMap<u64, u64>>(black_box(keys)))
+        });
+        group.bench_with_input(BenchmarkId::new("BTreeMap", size), &keys, |b, keys| {
+            b.iter(|| build_map::<BTreeMap<u64, u64>>(black_box(keys)))
+        });
+        group.bench_with_input(BenchmarkId::new("IndexMap", size), &keys, |b, keys| {
+            b.iter(|| build_map::<IndexMap<u64, u64>>(black_box(keys)))
+        });
+    }
+
+    group.finish();
+}
+
+// ============================================================================
+// BENCHMARK 2: LOOKUP
+// ============================================================================
+
+fn benchmark_lookup(c: &mut Criterion) {
+    let mut group = c.benchmark_group("map_lookup");
+
+    for size in SIZES {
+        let keys = random_keys(size, 2);
+        // Half hits, half misses: misses are where BTreeMap walks the
+        // whole height of the tree for nothing
+        let mut probes = keys[..size / 2].to_vec();
+        probes.extend(random_keys(size / 2, 99));
+
+        let hash: HashMap<u64, u64> = build_map(&keys);
+        let btree: BTreeMap<u64, u64> = build_map(&keys);
+        let index: IndexMap<u64, u64> = build_map(&keys);
+
+        group.bench_with_input(BenchmarkId::new("HashMap", size), &probes, |b, probes| {
+            b.iter(|| lookup_hits(&hash, black_box(probes)))
+        });
+        group.bench_with_input(BenchmarkId::new("BTreeMap", size), &probes, |b, probes| {
+            b.iter(|| lookup_hits(&btree, black_box(probes)))
+        });
+        group.bench_with_input(BenchmarkId::new("IndexMap", size), &probes, |b, probes| {
+            b.iter(|| lookup_hits(&index, black_box(probes)))
+        });
+    }
+
+    group.finish();
+}
+
+// ============================================================================
+// BENCHMARK 3: ITERATE
+// ============================================================================
+
+fn benchmark_iterate(c: &mut Criterion) {
+    let mut group = c.benchmark_group("map_iterate");
+
+    for size in SIZES {
+        let keys = random_keys(size, 3);
+        let hash: HashMap<u64, u64> = build_map(&keys);
+        let btree: BTreeMap<u64, u64> = build_map(&keys);
+        let index: IndexMap<u64, u64> = build_map(&keys);
+
+        group.bench_function(BenchmarkId::new("HashMap", size), |b| {
+            b.iter(|| black_box(&hash).values().sum::<u64>())
+        });
+        group.bench_function(BenchmarkId::new("BTreeMap", size), |b| {
+            b.iter(|| black_box(&btree).values().sum::<u64>())
+        });
+        group.bench_function(BenchmarkId::new("IndexMap", size), |b| {
+            b.iter(|| black_box(&index).values().sum::<u64>())
+        });
+    }
+
+    group.finish();
+}
+
+// ============================================================================
+// BENCHMARK 4: WORD FREQUENCIES
+// ============================================================================
+
+fn benchmark_word_frequencies(c: &mut Criterion) {
+    let mut group = c.benchmark_group("word_frequencies");
+    let words = skewed_words(50_000, 2_000, 4);
+
+    group.bench_function("HashMap", |b| {
+        b.iter(|| word_frequencies::<HashMap<String, u32>>(black_box(&words)))
+    });
+    group.bench_function("BTreeMap", |b| {
+        b.iter(|| word_frequencies::<BTreeMap<String, u32>>(black_box(&words)))
+    });
+    group.bench_function("IndexMap", |b| {
+        b.iter(|| word_frequencies::<IndexMap<String, u32>>(black_box(&words)))
+    });
+
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    benchmark_build,
+    benchmark_lookup,
+    benchmark_iterate,
+    benchmark_word_frequencies
+);
+criterion_main!(benches);
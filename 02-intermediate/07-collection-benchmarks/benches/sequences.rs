@@ -0,0 +1,117 @@
+//! Vec vs VecDeque vs LinkedList
+//!
+//! Run with: cargo bench --bench sequences
+//!
+//! Typical results, and why:
+//!
+//! - Appending and iterating: Vec and VecDeque are within a few percent of
+//!   each other. Both store elements contiguously, so iteration streams
+//!   through memory and the CPU prefetcher keeps up. LinkedList is several
+//!   times slower: every push allocates a node, and every step of the
+//!   iteration chases a pointer to wherever that node landed on the heap.
+//!
+//! - FIFO queue: VecDeque wins by orders of magnitude over Vec once the
+//!   queue holds more than a handful of elements. `Vec::remove(0)` shifts
+//!   every remaining element, making each pop O(n); VecDeque is a ring
+//!   buffer and just moves its head index. LinkedList pops in O(1) as well
+//!   but still pays an allocation per push.
+//!
+//! - Pushing at both ends: the same story as the FIFO queue, since
+//!   `Vec::insert(0, _)` is also O(n).
+//!
+//! Takeaway: use Vec by default, VecDeque whenever you remove from the
+//! front, and LinkedList almost never. Its one real strength, O(1) splicing
+//! of whole lists, isn't something most programs need.
+
+use collection_benchmarks::*;
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use std::collections::{LinkedList, VecDeque};
+
+const SIZES: [usize; 3] = [100, 1_000, 10_000];
+
+// ============================================================================
+// BENCHMARK 1: APPEND THEN ITERATE
+// ============================================================================
+
+fn benchmark_append_then_sum(c: &mut Criterion) {
+    let mut group = c.benchmark_group("append_then_sum");
+
+    for size in SIZES {
+        let values = random_keys(size, 1);
+        group.bench_with_input(BenchmarkId::new("Vec", size), &values, |b, values| {
+            b.iter(|| append_then_sum::<Vec<u64>>(black_box(values)))
+        });
+        group.bench_with_input(BenchmarkId::new("VecDeque", size), &values, |b, values| {
+            b.iter(|| append_then_sum::<VecDeque<u64>>(black_box(values)))
+        });
+        group.bench_with_input(
+            BenchmarkId::new("LinkedList", size),
+            &values,
+            |b, values| b.iter(|| append_then_sum::<LinkedList<u64>>(black_box(values))),
+        );
+    }
+
+    group.finish();
+}
+
+// ============================================================================
+// BENCHMARK 2: FIFO QUEUE
+// ============================================================================
+
+fn benchmark_sliding_window(c: &mut Criterion) {
+    let mut group = c.benchmark_group("sliding_window");
+    let values = random_keys(20_000, 2);
+
+    // The queue length is what matters here: Vec's cost per pop grows with
+    // it, the others stay flat
+    for capacity in SIZES {
+        group.bench_with_input(BenchmarkId::new("Vec", capacity), &capacity, |b, &cap| {
+            b.iter(|| sliding_window::<Vec<u64>>(black_box(&values), cap))
+        });
+        group.bench_with_input(
+            BenchmarkId::new("VecDeque", capacity),
+            &capacity,
+            |b, &cap| b.iter(|| sliding_window::<VecDeque<u64>>(black_box(&values), cap)),
+        );
+        group.bench_with_input(
+            BenchmarkId::new("LinkedList", capacity),
+            &capacity,
+            |b, &cap| b.iter(|| sliding_window::<LinkedList<u64>>(black_box(&values), cap)),
+        );
+    }
+
+    group.finish();
+}
+
+// ============================================================================
+// BENCHMARK 3: PUSH AT BOTH ENDS
+// ============================================================================
+
+fn benchmark_push_both_ends(c: &mut Criterion) {
+    let mut group = c.benchmark_group("push_both_ends");
+
+    for size in SIZES {
+        let values = random_keys(size, 3);
+        group.bench_with_input(BenchmarkId::new("Vec", size), &values, |b, values| {
+            b.iter(|| push_both_ends::<Vec<u64>>(black_box(values)))
+        });
+        group.bench_with_input(BenchmarkId::new("VecDeque", size), &values, |b, values| {
+            b.iter(|| push_both_ends::<VecDeque<u64>>(black_box(values)))
+        });
+        group.bench_with_input(
+            BenchmarkId::new("LinkedList", size),
+            &values,
+            |b, values| b.iter(|| push_both_ends::<LinkedList<u64>>(black_box(values))),
+        );
+    }
+
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    benchmark_append_then_sum,
+    benchmark_sliding_window,
+    benchmark_push_both_ends
+);
+criterion_main!(benches);
@@ -0,0 +1,66 @@
+//! Strategies for building a large String
+//!
+//! Run with: cargo bench --bench strings
+//!
+//! Every strategy builds the same CSV report, one line per record.
+//! Typical results, slowest first, and why:
+//!
+//! - `s = s + &format!(..)`: `+` reuses the left-hand String's buffer, so
+//!   this isn't quadratic, but each row still allocates a temporary String
+//!   for `format!` and then copies it.
+//!
+//! - `format!` per row + `join`: one allocation per row plus the final
+//!   join, which copies everything once more. Convenient and not terrible,
+//!   but it holds every line in memory twice at the peak.
+//!
+//! - `push_str` with `to_string()`: fewer format-machinery calls, but the
+//!   `to_string()` and `format!` temporaries still allocate per row.
+//!
+//! - `write!` into one String: no temporaries at all; the formatter writes
+//!   straight into the output buffer, which grows by doubling.
+//!
+//! - `write!` + `String::with_capacity`: the same, minus the handful of
+//!   reallocations and copies as the buffer doubles. The gain is modest,
+//!   since doubling already amortizes growth, but it's free when the size
+//!   is easy to estimate.
+//!
+//! Takeaway: write into a single String with `write!`/`writeln!` and avoid
+//! per-piece temporaries; reserve capacity when you know roughly how big
+//! the result will be.
+
+use collection_benchmarks::*;
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+
+// ============================================================================
+// BENCHMARK 1: CSV REPORT
+// ============================================================================
+
+fn benchmark_csv(c: &mut Criterion) {
+    let mut group = c.benchmark_group("csv");
+
+    for size in [100, 10_000] {
+        let rows = records(size, 1);
+        group.bench_with_input(BenchmarkId::new("add_format", size), &rows, |b, rows| {
+            b.iter(|| csv_add_format(black_box(rows)))
+        });
+        group.bench_with_input(BenchmarkId::new("join", size), &rows, |b, rows| {
+            b.iter(|| csv_join(black_box(rows)))
+        });
+        group.bench_with_input(BenchmarkId::new("push_str", size), &rows, |b, rows| {
+            b.iter(|| csv_push_str(black_box(rows)))
+        });
+        group.bench_with_input(BenchmarkId::new("write", size), &rows, |b, rows| {
+            b.iter(|| csv_write(black_box(rows)))
+        });
+        group.bench_with_input(
+            BenchmarkId::new("write_with_capacity", size),
+            &rows,
+            |b, rows| b.iter(|| csv_write_with_capacity(black_box(rows))),
+        );
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, benchmark_csv);
+criterion_main!(benches);
@@ -0,0 +1,455 @@
+//! Workloads for comparing collection types
+//!
+//! Each workload is written once, generically, and run against every
+//! collection that can do the job. That keeps the benchmarks honest: the
+//! only thing that changes between two measurements is the collection.
+//!
+//! The benchmarks live in `benches/` and are run with `cargo bench`. The
+//! tests below check that every implementation of a workload produces the
+//! same result, so a faster variant can't be faster by doing less.
+
+use indexmap::IndexMap;
+use std::collections::{BTreeMap, HashMap, LinkedList, VecDeque};
+use std::fmt::Write;
+use std::hash::Hash;
+
+// ============================================================================
+// SECTION 1: TEST DATA
+// ============================================================================
+
+/// Small, fast, deterministic pseudo-random numbers (xorshift64*), so every
+/// benchmark run sees the same data without pulling in `rand`
+pub struct XorShift(u64);
+
+impl XorShift {
+    pub fn new(seed: u64) -> Self {
+        // Zero is a fixed point of xorshift
+        XorShift(seed.max(1))
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    /// A number in `0..bound`
+    pub fn below(&mut self, bound: u64) -> u64 {
+        self.next_u64() % bound
+    }
+}
+
+/// `n` keys spread over a wide range, in random order
+pub fn random_keys(n: usize, seed: u64) -> Vec<u64> {
+    let mut rng = XorShift::new(seed);
+    (0..n).map(|_| rng.next_u64() >> 16).collect()
+}
+
+/// `n` words drawn from a vocabulary of `vocabulary` distinct words.
+/// Low-numbered words are much more common than high-numbered ones, like
+/// in real text, so a few keys get most of the updates.
+pub fn skewed_words(n: usize, vocabulary: u64, seed: u64) -> Vec<String> {
+    let mut rng = XorShift::new(seed);
+    (0..n)
+        .map(|_| {
+            // The product of two uniform draws favours small values
+            let rank = rng.below(vocabulary) * rng.below(vocabulary) / vocabulary;
+            format!("word{}", rank)
+        })
+        .collect()
+}
+
+/// One row of a report, for the string-building workloads
+#[derive(Debug, Clone, PartialEq)]
+pub struct Record {
+    pub id: u32,
+    pub name: String,
+    pub score: f64,
+}
+
+pub fn records(n: usize, seed: u64) -> Vec<Record> {
+    let mut rng = XorShift::new(seed);
+    (0..n)
+        .map(|i| Record {
+            id: i as u32,
+            name: format!("user-{}", rng.below(100_000)),
+            score: rng.below(10_000) as f64 / 100.0,
+        })
+        .collect()
+}
+
+// ============================================================================
+// SECTION 2: SEQUENCES (Vec, VecDeque, LinkedList)
+// ============================================================================
+
+/// The operations the sequence workloads need
+pub trait Sequence: Default {
+    fn push_back(&mut self, value: u64);
+    fn push_front(&mut self, value: u64);
+    fn pop_front(&mut self) -> Option<u64>;
+    fn len(&self) -> usize;
+    fn sum(&self) -> u64;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Sequence for Vec<u64> {
+    fn push_back(&mut self, value: u64) {
+        self.push(value);
+    }
+
+    /// O(n): every element shifts one slot to the right
+    fn push_front(&mut self, value: u64) {
+        self.insert(0, value);
+    }
+
+    /// O(n): every element shifts one slot to the left
+    fn pop_front(&mut self) -> Option<u64> {
+        if self.is_empty() {
+            None
+        } else {
+            Some(self.remove(0))
+        }
+    }
+
+    fn len(&self) -> usize {
+        Vec::len(self)
+    }
+
+    fn sum(&self) -> u64 {
+        self.iter().sum()
+    }
+}
+
+impl Sequence for VecDeque<u64> {
+    fn push_back(&mut self, value: u64) {
+        VecDeque::push_back(self, value);
+    }
+
+    fn push_front(&mut self, value: u64) {
+        VecDeque::push_front(self, value);
+    }
+
+    fn pop_front(&mut self) -> Option<u64> {
+        VecDeque::pop_front(self)
+    }
+
+    fn len(&self) -> usize {
+        VecDeque::len(self)
+    }
+
+    fn sum(&self) -> u64 {
+        self.iter().sum()
+    }
+}
+
+impl Sequence for LinkedList<u64> {
+    fn push_back(&mut self, value: u64) {
+        LinkedList::push_back(self, value);
+    }
+
+    fn push_front(&mut self, value: u64) {
+        LinkedList::push_front(self, value);
+    }
+
+    fn pop_front(&mut self) -> Option<u64> {
+        LinkedList::pop_front(self)
+    }
+
+    fn len(&self) -> usize {
+        LinkedList::len(self)
+    }
+
+    fn sum(&self) -> u64 {
+        self.iter().sum()
+    }
+}
+
+/// Append every value, then read them all back: the most common way a
+/// sequence is used
+pub fn append_then_sum<S: Sequence>(values: &[u64]) -> u64 {
+    let mut sequence = S::default();
+    for &value in values {
+        sequence.push_back(value);
+    }
+    sequence.sum()
+}
+
+/// A bounded FIFO queue, like a job queue or a sliding window: push at the
+/// back, and pop from the front whenever more than `capacity` are waiting.
+/// Returns the sum of everything popped.
+pub fn sliding_window<S: Sequence>(values: &[u64], capacity: usize) -> u64 {
+    let mut queue = S::default();
+    let mut popped = 0;
+    for &value in values {
+        queue.push_back(value);
+        if queue.len() > capacity {
+            popped += queue.pop_front().unwrap_or(0);
+        }
+    }
+    popped
+}
+
+/// Alternate pushes between the two ends, like a work-stealing deque
+pub fn push_both_ends<S: Sequence>(values: &[u64]) -> u64 {
+    let mut sequence = S::default();
+    for (i, &value) in values.iter().enumerate() {
+        if i % 2 == 0 {
+            sequence.push_back(value);
+        } else {
+            sequence.push_front(value);
+        }
+    }
+    sequence.sum()
+}
+
+// ============================================================================
+// SECTION 3: MAPS (HashMap, BTreeMap, IndexMap)
+// ============================================================================
+
+/// The operations the map workloads need
+pub trait Map<K, V>: Default {
+    fn insert(&mut self, key: K, value: V);
+    fn get(&self, key: &K) -> Option<&V>;
+    fn get_mut(&mut self, key: &K) -> Option<&mut V>;
+    fn len(&self) -> usize;
+    /// Entries in the map's own iteration order
+    fn entries(&self) -> Vec<(&K, &V)>;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<K: Hash + Eq, V> Map<K, V> for HashMap<K, V> {
+    fn insert(&mut self, key: K, value: V) {
+        HashMap::insert(self, key, value);
+    }
+
+    fn get(&self, key: &K) -> Option<&V> {
+        HashMap::get(self, key)
+    }
+
+    fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        HashMap::get_mut(self, key)
+    }
+
+    fn len(&self) -> usize {
+        HashMap::len(self)
+    }
+
+    fn entries(&self) -> Vec<(&K, &V)> {
+        self.iter().collect()
+    }
+}
+
+impl<K: Ord, V> Map<K, V> for BTreeMap<K, V> {
+    fn insert(&mut self, key: K, value: V) {
+        BTreeMap::insert(self, key, value);
+    }
+
+    fn get(&self, key: &K) -> Option<&V> {
+        BTreeMap::get(self, key)
+    }
+
+    fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        BTreeMap::get_mut(self, key)
+    }
+
+    fn len(&self) -> usize {
+        BTreeMap::len(self)
+    }
+
+    fn entries(&self) -> Vec<(&K, &V)> {
+        self.iter().collect()
+    }
+}
+
+impl<K: Hash + Eq, V> Map<K, V> for IndexMap<K, V> {
+    fn insert(&mut self, key: K, value: V) {
+        IndexMap::insert(self, key, value);
+    }
+
+    fn get(&self, key: &K) -> Option<&V> {
+        IndexMap::get(self, key)
+    }
+
+    fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        IndexMap::get_mut(self, key)
+    }
+
+    fn len(&self) -> usize {
+        IndexMap::len(self)
+    }
+
+    fn entries(&self) -> Vec<(&K, &V)> {
+        self.iter().collect()
+    }
+}
+
+/// Build a map with one entry per key
+pub fn build_map<M: Map<u64, u64>>(keys: &[u64]) -> M {
+    let mut map = M::default();
+    for (i, &key) in keys.iter().enumerate() {
+        map.insert(key, i as u64);
+    }
+    map
+}
+
+/// Look up every key in `probes`, counting the hits
+pub fn lookup_hits<M: Map<u64, u64>>(map: &M, probes: &[u64]) -> usize {
+    probes.iter().filter(|key| map.get(key).is_some()).count()
+}
+
+/// Count how often each word occurs: many updates to a few hot keys
+pub fn word_frequencies<M: Map<String, u32>>(words: &[String]) -> M {
+    let mut counts = M::default();
+    for word in words {
+        match counts.get_mut(word) {
+            Some(count) => *count += 1,
+            None => counts.insert(word.clone(), 1),
+        }
+    }
+    counts
+}
+
+/// The `limit` most frequent words, most frequent first, ties by word
+pub fn top_words<M: Map<String, u32>>(counts: &M, limit: usize) -> Vec<(String, u32)> {
+    let mut entries: Vec<(String, u32)> = counts
+        .entries()
+        .into_iter()
+        .map(|(word, &count)| (word.clone(), count))
+        .collect();
+    entries.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    entries.truncate(limit);
+    entries
+}
+
+// ============================================================================
+// SECTION 4: STRING BUILDING
+// ============================================================================
+
+/// `s = s + &format!(..)` per row: a fresh temporary `String` for every row
+pub fn csv_add_format(rows: &[Record]) -> String {
+    let mut out = String::new();
+    for row in rows {
+        out = out + &format!("{},{},{:.2}\n", row.id, row.name, row.score);
+    }
+    out
+}
+
+/// `format!` per row, each collected into a `Vec` and joined at the end
+pub fn csv_join(rows: &[Record]) -> String {
+    let mut lines: Vec<String> = rows
+        .iter()
+        .map(|row| format!("{},{},{:.2}", row.id, row.name, row.score))
+        .collect();
+    lines.push(String::new()); // trailing newline
+    lines.join("\n")
+}
+
+/// `write!` straight into one growing `String`, no temporaries
+pub fn csv_write(rows: &[Record]) -> String {
+    let mut out = String::new();
+    for row in rows {
+        // Writing to a String can't fail
+        writeln!(out, "{},{},{:.2}", row.id, row.name, row.score).unwrap();
+    }
+    out
+}
+
+/// Like `csv_write`, but with the final size reserved up front
+pub fn csv_write_with_capacity(rows: &[Record]) -> String {
+    // id (<= 10) + name + score (<= 6) + 2 commas + newline
+    let estimate: usize = rows.iter().map(|row| row.name.len() + 19).sum();
+    let mut out = String::with_capacity(estimate);
+    for row in rows {
+        writeln!(out, "{},{},{:.2}", row.id, row.name, row.score).unwrap();
+    }
+    out
+}
+
+/// `push_str` of each piece, formatting numbers through `to_string`
+pub fn csv_push_str(rows: &[Record]) -> String {
+    let mut out = String::new();
+    for row in rows {
+        out.push_str(&row.id.to_string());
+        out.push(',');
+        out.push_str(&row.name);
+        out.push(',');
+        out.push_str(&format!("{:.2}", row.score));
+        out.push('\n');
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sequences_agree() {
+        let values = random_keys(1_000, 7);
+        let expected = append_then_sum::<Vec<u64>>(&values);
+        assert_eq!(append_then_sum::<VecDeque<u64>>(&values), expected);
+        assert_eq!(append_then_sum::<LinkedList<u64>>(&values), expected);
+
+        let window = sliding_window::<Vec<u64>>(&values, 100);
+        assert_eq!(sliding_window::<VecDeque<u64>>(&values, 100), window);
+        assert_eq!(sliding_window::<LinkedList<u64>>(&values, 100), window);
+        // Everything except the last 100 values was popped
+        assert_eq!(window, values[..900].iter().sum::<u64>());
+
+        let both = push_both_ends::<Vec<u64>>(&values);
+        assert_eq!(push_both_ends::<VecDeque<u64>>(&values), both);
+        assert_eq!(push_both_ends::<LinkedList<u64>>(&values), both);
+    }
+
+    #[test]
+    fn test_maps_agree() {
+        let keys = random_keys(1_000, 11);
+        let probes = random_keys(500, 11); // same seed: the first 500 keys
+        let hash: HashMap<u64, u64> = build_map(&keys);
+        let btree: BTreeMap<u64, u64> = build_map(&keys);
+        let index: IndexMap<u64, u64> = build_map(&keys);
+        assert_eq!(lookup_hits(&hash, &probes), 500);
+        assert_eq!(lookup_hits(&btree, &probes), 500);
+        assert_eq!(lookup_hits(&index, &probes), 500);
+
+        // Only the BTreeMap iterates sorted, only the IndexMap in insertion order
+        let btree_keys: Vec<u64> = btree.entries().iter().map(|(k, _)| **k).collect();
+        assert!(btree_keys.windows(2).all(|pair| pair[0] < pair[1]));
+        let index_keys: Vec<u64> = index.entries().iter().map(|(k, _)| **k).collect();
+        assert_eq!(index_keys, keys);
+    }
+
+    #[test]
+    fn test_word_frequencies_agree() {
+        let words = skewed_words(5_000, 200, 3);
+        let hash: HashMap<String, u32> = word_frequencies(&words);
+        let btree: BTreeMap<String, u32> = word_frequencies(&words);
+        let index: IndexMap<String, u32> = word_frequencies(&words);
+
+        let top = top_words(&hash, 10);
+        assert_eq!(top_words(&btree, 10), top);
+        assert_eq!(top_words(&index, 10), top);
+        assert_eq!(hash.values().sum::<u32>(), 5_000);
+        // The skew makes the most common word far more common than average
+        assert!(top[0].1 > 5_000 / 200 * 3);
+    }
+
+    #[test]
+    fn test_string_strategies_agree() {
+        let rows = records(200, 5);
+        let expected = csv_write(&rows);
+        assert!(expected.starts_with("0,user-"));
+        assert_eq!(expected.lines().count(), 200);
+        assert_eq!(csv_add_format(&rows), expected);
+        assert_eq!(csv_join(&rows), expected);
+        assert_eq!(csv_write_with_capacity(&rows), expected);
+        assert_eq!(csv_push_str(&rows), expected);
+    }
+}
@@ -0,0 +1,84 @@
+// Collection Benchmarks
+//
+// A quick, unscientific tour of the workloads in src/lib.rs, timed once
+// each with Instant. Single runs are noisy and include warm-up effects;
+// for real numbers use `cargo bench`, which runs each workload many times
+// and reports confidence intervals.
+
+use collection_benchmarks::*;
+use indexmap::IndexMap;
+use std::collections::{BTreeMap, HashMap, LinkedList, VecDeque};
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+fn time<T>(label: &str, f: impl FnOnce() -> T) -> Duration {
+    let start = Instant::now();
+    black_box(f());
+    let elapsed = start.elapsed();
+    println!("   {:<28} {:>10.2?}", label, elapsed);
+    elapsed
+}
+
+fn main() {
+    println!("=== Collection Benchmarks (single runs, see `cargo bench`) ===\n");
+
+    let values = random_keys(100_000, 42);
+
+    println!("1. Append 100k values, then sum:");
+    time("Vec", || append_then_sum::<Vec<u64>>(&values));
+    time("VecDeque", || append_then_sum::<VecDeque<u64>>(&values));
+    time("LinkedList", || append_then_sum::<LinkedList<u64>>(&values));
+    println!();
+
+    println!("2. FIFO queue of 1,000 over 100k values:");
+    time("Vec (remove(0))", || {
+        sliding_window::<Vec<u64>>(&values, 1_000)
+    });
+    time("VecDeque", || {
+        sliding_window::<VecDeque<u64>>(&values, 1_000)
+    });
+    time("LinkedList", || {
+        sliding_window::<LinkedList<u64>>(&values, 1_000)
+    });
+    println!();
+
+    let keys = random_keys(100_000, 7);
+    let probes = random_keys(100_000, 8);
+
+    println!("3. Build a 100k-entry map, then 100k lookups:");
+    let hash: HashMap<u64, u64> = build_map(&keys);
+    let btree: BTreeMap<u64, u64> = build_map(&keys);
+    let index: IndexMap<u64, u64> = build_map(&keys);
+    time("HashMap", || lookup_hits(&hash, &probes));
+    time("BTreeMap", || lookup_hits(&btree, &probes));
+    time("IndexMap", || lookup_hits(&index, &probes));
+    println!();
+
+    let words = skewed_words(200_000, 5_000, 3);
+
+    println!("4. Word frequencies over 200k words:");
+    time("HashMap", || {
+        word_frequencies::<HashMap<String, u32>>(&words)
+    });
+    time("BTreeMap", || {
+        word_frequencies::<BTreeMap<String, u32>>(&words)
+    });
+    time("IndexMap", || {
+        word_frequencies::<IndexMap<String, u32>>(&words)
+    });
+    let counts: HashMap<String, u32> = word_frequencies(&words);
+    println!("   Top words: {:?}", top_words(&counts, 3));
+    println!();
+
+    let rows = records(50_000, 5);
+
+    println!("5. Build a 50k-row CSV:");
+    time("s = s + &format!()", || csv_add_format(&rows));
+    time("push_str", || csv_push_str(&rows));
+    time("format! + join", || csv_join(&rows));
+    time("write!", || csv_write(&rows));
+    time("write! + with_capacity", || csv_write_with_capacity(&rows));
+    println!();
+
+    println!("Run `cargo bench` for statistically sound measurements.");
+}
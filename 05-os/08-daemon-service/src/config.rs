@@ -0,0 +1,79 @@
+// Service configuration, loaded at startup and again on every SIGHUP.
+//
+// A reload either replaces the whole configuration or nothing: the new file
+// is parsed and validated first, and if anything is wrong the service logs
+// the error and keeps running with what it had.
+
+use serde::Deserialize;
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+use tracing_subscriber::EnvFilter;
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Seconds between jobs
+    pub interval_secs: u64,
+    /// Work items per job
+    pub batch_size: u32,
+    /// Simulated time per work item
+    pub item_millis: u64,
+    /// How long a shutdown waits for the running job
+    pub drain_timeout_secs: u64,
+    /// A `tracing` filter directive, like `RUST_LOG`
+    pub log_level: String,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            interval_secs: 5,
+            batch_size: 3,
+            item_millis: 300,
+            drain_timeout_secs: 10,
+            log_level: "info".to_string(),
+        }
+    }
+}
+
+impl Config {
+    /// Read and validate `path`, or use the defaults when there is no file
+    pub fn load(path: Option<&Path>) -> Result<Config, Box<dyn Error>> {
+        let config = match path {
+            Some(path) => {
+                let text = fs::read_to_string(path)
+                    .map_err(|e| format!("Cannot read {}: {}", path.display(), e))?;
+                toml::from_str(&text).map_err(|e| format!("Invalid {}: {}", path.display(), e))?
+            }
+            None => Config::default(),
+        };
+        config.validate()?;
+        Ok(config)
+    }
+
+    fn validate(&self) -> Result<(), Box<dyn Error>> {
+        if self.interval_secs == 0 {
+            return Err("interval_secs must be at least 1".into());
+        }
+        if self.batch_size == 0 {
+            return Err("batch_size must be at least 1".into());
+        }
+        EnvFilter::try_new(&self.log_level)
+            .map_err(|e| format!("Invalid log_level '{}': {}", self.log_level, e))?;
+        Ok(())
+    }
+
+    pub fn interval(&self) -> Duration {
+        Duration::from_secs(self.interval_secs)
+    }
+
+    pub fn item_duration(&self) -> Duration {
+        Duration::from_millis(self.item_millis)
+    }
+
+    pub fn drain_timeout(&self) -> Duration {
+        Duration::from_secs(self.drain_timeout_secs)
+    }
+}
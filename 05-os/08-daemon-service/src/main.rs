@@ -0,0 +1,267 @@
+// A Long-Running Service
+//
+// Wraps a small periodic worker in everything a real service needs:
+// - SIGHUP reloads the configuration file without restarting
+// - SIGTERM / Ctrl+C stop taking new work, drain the running job, and exit
+// - A PID file for init scripts, which also refuses a second instance
+// - Structured logs (text or JSON) with a level that follows the config
+// - Optional daemon mode on Unix, and Windows-service mode on Windows
+
+use std::error::Error;
+use std::future::Future;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use tokio::sync::watch;
+use tracing::{error, info, warn};
+
+mod config;
+mod logging;
+mod pidfile;
+#[cfg(windows)]
+mod service;
+mod signals;
+mod worker;
+
+use config::Config;
+use logging::{LogFormat, LogHandle};
+use pidfile::PidFile;
+use signals::{Event, Signals};
+
+const USAGE: &str = "\
+Usage: daemon-service [OPTIONS]
+
+Options:
+  --config <FILE>        Configuration file (TOML); defaults are used otherwise
+  --pid-file <FILE>      Write the process ID here while running
+  --log-file <FILE>      Append logs to FILE instead of stderr
+  --log-format <FORMAT>  text (default) or json
+  --daemon               Detach from the terminal (Unix; needs --log-file)
+  --service              Run under the Windows service control manager
+  -h, --help             Print this help";
+
+#[derive(Debug, Clone)]
+pub(crate) struct Options {
+    config: Option<PathBuf>,
+    pid_file: Option<PathBuf>,
+    log_file: Option<PathBuf>,
+    log_format: LogFormat,
+    daemon: bool,
+    service: bool,
+    help: bool,
+}
+
+impl Options {
+    fn parse(args: impl IntoIterator<Item = String>) -> Result<Options, String> {
+        let mut options = Options {
+            config: None,
+            pid_file: None,
+            log_file: None,
+            log_format: LogFormat::Text,
+            daemon: false,
+            service: false,
+            help: false,
+        };
+
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().ok_or(format!("{} needs a value", arg));
+            match arg.as_str() {
+                "--config" => options.config = Some(value()?.into()),
+                "--pid-file" => options.pid_file = Some(value()?.into()),
+                "--log-file" => options.log_file = Some(value()?.into()),
+                "--log-format" => {
+                    options.log_format = match value()?.as_str() {
+                        "text" => LogFormat::Text,
+                        "json" => LogFormat::Json,
+                        other => return Err(format!("Unknown log format '{}'", other)),
+                    }
+                }
+                "--daemon" => options.daemon = true,
+                "--service" => options.service = true,
+                "-h" | "--help" => options.help = true,
+                other => return Err(format!("Unknown argument '{}'", other)),
+            }
+        }
+        Ok(options)
+    }
+}
+
+fn main() -> ExitCode {
+    let options = match Options::parse(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+    if options.help {
+        println!("{}", USAGE);
+        return ExitCode::SUCCESS;
+    }
+
+    match start(options) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            // Also lands in the log file once logging is set up
+            error!(error = %e, "service failed");
+            eprintln!("Error: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn start(options: Options) -> Result<(), Box<dyn Error>> {
+    #[cfg(windows)]
+    if options.service {
+        return service::run(options);
+    }
+    #[cfg(not(windows))]
+    if options.service {
+        return Err("--service is only available on Windows".into());
+    }
+
+    // Check the config while errors still reach the terminal
+    let config = Config::load(options.config.as_deref())?;
+
+    let options = if options.daemon {
+        daemonize(options)?
+    } else {
+        options
+    };
+
+    let logs = logging::init(options.log_format, options.log_file.as_deref())?;
+
+    // The runtime is built only now: forking a process that already has
+    // worker threads would leave the child without them
+    let runtime = tokio::runtime::Runtime::new()?;
+    runtime.block_on(serve(&options, config, logs, std::future::pending()))
+}
+
+/// Detach from the terminal with the classic double fork, setsid, and
+/// `chdir("/")`, so the daemon doesn't keep a mount point busy
+#[cfg(unix)]
+fn daemonize(mut options: Options) -> Result<Options, Box<dyn Error>> {
+    if options.log_file.is_none() {
+        return Err("--daemon needs --log-file: a daemon has no terminal to log to".into());
+    }
+
+    // Relative paths would resolve against "/" after the fork
+    let cwd = std::env::current_dir()?;
+    for path in [
+        &mut options.config,
+        &mut options.pid_file,
+        &mut options.log_file,
+    ]
+    .into_iter()
+    .flatten()
+    {
+        if path.is_relative() {
+            *path = cwd.join(&*path);
+        }
+    }
+
+    if let Some(log_file) = &options.log_file {
+        println!("Starting daemon, logging to {}", log_file.display());
+    }
+    // stdin, stdout and stderr are pointed at /dev/null
+    daemonize::Daemonize::new()
+        .working_directory("/")
+        .umask(0o027)
+        .start()?;
+    Ok(options)
+}
+
+#[cfg(not(unix))]
+fn daemonize(_options: Options) -> Result<Options, Box<dyn Error>> {
+    Err("--daemon is only available on Unix; use --service on Windows".into())
+}
+
+/// Run until a stop signal (or `stop` completes), then drain the worker
+pub(crate) async fn serve(
+    options: &Options,
+    config: Config,
+    logs: LogHandle,
+    stop: impl Future<Output = ()>,
+) -> Result<(), Box<dyn Error>> {
+    logs.set_level(&config.log_level)?;
+    // Held until the end of this function, so it's removed on every return
+    let _pid_file = options
+        .pid_file
+        .as_deref()
+        .map(PidFile::create)
+        .transpose()?;
+    let mut signals = Signals::new()?;
+
+    info!(
+        pid = std::process::id(),
+        interval_secs = config.interval_secs,
+        batch_size = config.batch_size,
+        "service started"
+    );
+
+    let (config_tx, config_rx) = watch::channel(config);
+    let (shutdown_tx, shutdown_rx) = watch::channel(false);
+    let mut worker = tokio::spawn(worker::run(config_rx, shutdown_rx));
+
+    tokio::pin!(stop);
+    let reason = loop {
+        let event = tokio::select! {
+            event = signals.recv() => event,
+            _ = &mut stop => Event::Terminate,
+            result = &mut worker => {
+                return Err(format!("worker exited unexpectedly: {:?}", result).into());
+            }
+        };
+        match event {
+            Event::Reload => reload(options, &config_tx, &logs),
+            Event::Terminate | Event::Interrupt => break event,
+        }
+    };
+
+    let timeout = config_tx.borrow().drain_timeout();
+    info!(signal = ?reason, timeout_secs = timeout.as_secs(), "shutting down, draining worker");
+    // Ignore the error: it only means the worker is already gone
+    let _ = shutdown_tx.send(true);
+
+    tokio::select! {
+        _ = &mut worker => info!("worker drained"),
+        _ = tokio::time::sleep(timeout) => {
+            warn!("drain timed out, abandoning the running job");
+            worker.abort();
+        }
+        // A second Ctrl+C means "now"
+        event = signals.recv() => {
+            warn!(signal = ?event, "second signal, stopping immediately");
+            worker.abort();
+        }
+    }
+
+    info!("service stopped");
+    Ok(())
+}
+
+/// Apply a changed config file, or keep the current one if it's invalid
+fn reload(options: &Options, config: &watch::Sender<Config>, logs: &LogHandle) {
+    let Some(path) = &options.config else {
+        info!("SIGHUP received, but there is no config file to reload");
+        return;
+    };
+
+    match Config::load(Some(path)) {
+        Ok(new) if new == *config.borrow() => info!("configuration unchanged"),
+        Ok(new) => {
+            if let Err(e) = logs.set_level(&new.log_level) {
+                warn!(error = %e, "could not change the log level");
+            }
+            info!(
+                path = %path.display(),
+                interval_secs = new.interval_secs,
+                batch_size = new.batch_size,
+                log_level = %new.log_level,
+                "configuration reloaded"
+            );
+            config.send_replace(new);
+        }
+        Err(e) => error!(error = %e, "reload failed, keeping the current configuration"),
+    }
+}
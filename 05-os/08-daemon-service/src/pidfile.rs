@@ -0,0 +1,90 @@
+// PID file management.
+//
+// The PID file tells init scripts and operators which process to signal
+// (`kill -HUP $(cat service.pid)`), and stops a second copy from starting.
+// It is created with `create_new`, so two instances racing to start can't
+// both succeed. A file left behind by a crashed instance is detected by
+// checking whether its PID is still alive, and replaced.
+
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+
+/// Removes the file again when dropped, so it disappears on every normal
+/// exit, including a graceful SIGTERM shutdown
+#[derive(Debug)]
+pub struct PidFile {
+    path: PathBuf,
+}
+
+impl PidFile {
+    pub fn create(path: &Path) -> io::Result<PidFile> {
+        match write_new(path) {
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
+                match read_pid(path) {
+                    Some(pid) if process_alive(pid) => {
+                        return Err(io::Error::new(
+                            io::ErrorKind::AlreadyExists,
+                            format!("Already running as PID {} (see {})", pid, path.display()),
+                        ));
+                    }
+                    Some(pid) => {
+                        tracing::warn!(path = %path.display(), stale_pid = pid, "removing stale PID file")
+                    }
+                    None => tracing::warn!(path = %path.display(), "removing unreadable PID file"),
+                }
+                fs::remove_file(path)?;
+                write_new(path)?;
+            }
+            result => result?,
+        }
+
+        tracing::debug!(path = %path.display(), pid = process::id(), "wrote PID file");
+        Ok(PidFile {
+            path: path.to_path_buf(),
+        })
+    }
+}
+
+impl Drop for PidFile {
+    fn drop(&mut self) {
+        // Only remove the file if it is still ours; an operator may have
+        // deleted it and started another instance in the meantime
+        if read_pid(&self.path) == Some(process::id()) {
+            if let Err(e) = fs::remove_file(&self.path) {
+                tracing::warn!(path = %self.path.display(), error = %e, "could not remove PID file");
+            }
+        }
+    }
+}
+
+fn write_new(path: &Path) -> io::Result<()> {
+    let mut file = OpenOptions::new().write(true).create_new(true).open(path)?;
+    writeln!(file, "{}", process::id())
+}
+
+fn read_pid(path: &Path) -> Option<u32> {
+    fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+/// Signal 0 checks that a process exists without sending anything. EPERM
+/// means it exists but belongs to another user.
+#[cfg(unix)]
+fn process_alive(pid: u32) -> bool {
+    let Ok(pid) = libc::pid_t::try_from(pid) else {
+        return false;
+    };
+    // SAFETY: kill has no memory-safety requirements; signal 0 is a no-op
+    if unsafe { libc::kill(pid, 0) } == 0 {
+        return true;
+    }
+    io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
+/// Without a cheap portable check, assume the process is alive and let the
+/// operator delete the file by hand
+#[cfg(not(unix))]
+fn process_alive(_pid: u32) -> bool {
+    true
+}
@@ -0,0 +1,99 @@
+// Windows-service mode.
+//
+// A Windows service isn't started from a terminal but by the service
+// control manager (SCM), which calls back into the process and later asks
+// it to stop. The stop request plays the part SIGTERM plays on Unix: it
+// completes the `stop` future passed to `serve`, which drains the worker
+// the same way.
+//
+// Register the service (from an elevated prompt) with absolute paths:
+//   sc.exe create daemon-service binPath= "C:\svc\daemon-service.exe --service
+//       --config C:\svc\service.toml --log-file C:\svc\service.log"
+
+use std::error::Error;
+use std::ffi::OsString;
+use std::sync::OnceLock;
+use std::time::Duration;
+use windows_service::service::{
+    ServiceControl, ServiceControlAccept, ServiceExitCode, ServiceState, ServiceStatus, ServiceType,
+};
+use windows_service::service_control_handler::{self, ServiceControlHandlerResult};
+use windows_service::{define_windows_service, service_dispatcher};
+
+use crate::config::Config;
+use crate::{logging, Options};
+
+pub const SERVICE_NAME: &str = "daemon-service";
+
+/// The SCM calls `service_main` with its own arguments, so the command
+/// line options are handed over through a static
+static OPTIONS: OnceLock<Options> = OnceLock::new();
+
+define_windows_service!(ffi_service_main, service_main);
+
+/// Hand the process over to the SCM. Blocks until the service stops, and
+/// fails straight away when not started by the SCM.
+pub fn run(options: Options) -> Result<(), Box<dyn Error>> {
+    let _ = OPTIONS.set(options);
+    service_dispatcher::start(SERVICE_NAME, ffi_service_main)?;
+    Ok(())
+}
+
+fn service_main(_arguments: Vec<OsString>) {
+    if let Err(e) = run_service() {
+        tracing::error!(error = %e, "service failed");
+    }
+}
+
+fn run_service() -> Result<(), Box<dyn Error>> {
+    let options = OPTIONS.get().ok_or("service started without options")?;
+    let config = Config::load(options.config.as_deref())?;
+    let logs = logging::init(options.log_format, options.log_file.as_deref())?;
+    let runtime = tokio::runtime::Runtime::new()?;
+
+    let (stop_tx, mut stop_rx) = tokio::sync::watch::channel(false);
+    let status = service_control_handler::register(SERVICE_NAME, move |control| match control {
+        ServiceControl::Stop | ServiceControl::Shutdown => {
+            let _ = stop_tx.send(true);
+            ServiceControlHandlerResult::NoError
+        }
+        ServiceControl::Interrogate => ServiceControlHandlerResult::NoError,
+        _ => ServiceControlHandlerResult::NotImplemented,
+    })?;
+
+    let report = |state, controls_accepted, wait_hint| {
+        status.set_service_status(ServiceStatus {
+            service_type: ServiceType::OWN_PROCESS,
+            current_state: state,
+            controls_accepted,
+            exit_code: ServiceExitCode::Win32(0),
+            checkpoint: 0,
+            wait_hint,
+            process_id: None,
+        })
+    };
+    report(
+        ServiceState::Running,
+        ServiceControlAccept::STOP | ServiceControlAccept::SHUTDOWN,
+        Duration::default(),
+    )?;
+
+    // Tell the SCM how long draining may take, so it doesn't give up early
+    let drain_hint = config.drain_timeout() + Duration::from_secs(5);
+    let stop = async {
+        let _ = stop_rx.wait_for(|stop| *stop).await;
+        let _ = report(
+            ServiceState::StopPending,
+            ServiceControlAccept::empty(),
+            drain_hint,
+        );
+    };
+    let result = runtime.block_on(crate::serve(options, config, logs, stop));
+
+    report(
+        ServiceState::Stopped,
+        ServiceControlAccept::empty(),
+        Duration::default(),
+    )?;
+    result
+}
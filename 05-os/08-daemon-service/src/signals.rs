@@ -0,0 +1,69 @@
+// Turning OS signals into service events.
+//
+// tokio's signal streams are async-signal-safe: the real handler only sets
+// a flag and wakes the runtime, and the match on the event happens in
+// ordinary async code, where logging, file I/O and locks are all fine.
+
+use std::io;
+
+#[cfg(unix)]
+use tokio::signal::unix::{signal, Signal, SignalKind};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event {
+    /// SIGHUP: re-read the configuration (never sent on Windows)
+    #[cfg_attr(not(unix), allow(dead_code))]
+    Reload,
+    /// SIGTERM: the normal "please stop" from init systems and `kill`
+    Terminate,
+    /// SIGINT: Ctrl+C in a terminal
+    Interrupt,
+}
+
+#[cfg(unix)]
+pub struct Signals {
+    hangup: Signal,
+    terminate: Signal,
+    interrupt: Signal,
+}
+
+#[cfg(unix)]
+impl Signals {
+    /// Must be called inside the runtime. From here on the default actions
+    /// (which would kill the process) no longer apply to these signals.
+    pub fn new() -> io::Result<Signals> {
+        Ok(Signals {
+            hangup: signal(SignalKind::hangup())?,
+            terminate: signal(SignalKind::terminate())?,
+            interrupt: signal(SignalKind::interrupt())?,
+        })
+    }
+
+    pub async fn recv(&mut self) -> Event {
+        tokio::select! {
+            _ = self.hangup.recv() => Event::Reload,
+            _ = self.terminate.recv() => Event::Terminate,
+            _ = self.interrupt.recv() => Event::Interrupt,
+        }
+    }
+}
+
+/// Windows has no SIGHUP or SIGTERM: a console gets Ctrl+C, and a service
+/// gets stop requests from the service control manager instead
+#[cfg(not(unix))]
+pub struct Signals;
+
+#[cfg(not(unix))]
+impl Signals {
+    pub fn new() -> io::Result<Signals> {
+        Ok(Signals)
+    }
+
+    pub async fn recv(&mut self) -> Event {
+        match tokio::signal::ctrl_c().await {
+            Ok(()) => Event::Interrupt,
+            // No console to get Ctrl+C from: wait for some other stop request
+            Err(_) => std::future::pending().await,
+        }
+    }
+}
@@ -0,0 +1,54 @@
+// The periodic worker the service exists to run.
+//
+// Every `interval_secs` it runs a job of `batch_size` items. Between jobs
+// it watches two channels: a new `Config` restarts the wait with the new
+// interval, and the shutdown flag ends the loop. A job that is already
+// running is never interrupted by either; finishing it is what "draining"
+// means.
+
+use std::time::Instant;
+use tokio::sync::watch;
+use tracing::{debug, info, info_span, Instrument};
+
+use crate::config::Config;
+
+pub async fn run(mut config: watch::Receiver<Config>, mut shutdown: watch::Receiver<bool>) {
+    let mut job = 0u64;
+
+    loop {
+        let interval = config.borrow().interval();
+        tokio::select! {
+            _ = tokio::time::sleep(interval) => {}
+            changed = config.changed() => {
+                if changed.is_err() {
+                    break;
+                }
+                info!(interval_secs = config.borrow().interval_secs, "worker picked up new config");
+                continue;
+            }
+            _ = shutdown.changed() => break,
+        }
+
+        job += 1;
+        // Clone so a reload during the job can't change it halfway through
+        let settings = config.borrow().clone();
+        run_job(&settings)
+            .instrument(info_span!("job", id = job))
+            .await;
+    }
+
+    info!(jobs = job, "worker stopped");
+}
+
+async fn run_job(config: &Config) {
+    let started = Instant::now();
+    for item in 1..=config.batch_size {
+        tokio::time::sleep(config.item_duration()).await;
+        debug!(item, of = config.batch_size, "processed item");
+    }
+    info!(
+        items = config.batch_size,
+        elapsed_ms = started.elapsed().as_millis() as u64,
+        "job finished"
+    );
+}
@@ -13,13 +13,15 @@
 //! - Nested routers
 //! - Static file serving
 //! - Request validation
+//! - Traffic mirroring to a secondary backend (see `mirror`)
+//! - Maintenance mode and per-route toggles (see `maintenance`)
 
 use axum::{
     extract::{Path, Query, State},
-    http::{HeaderMap, Request, StatusCode},
+    http::{header, HeaderMap, Request, StatusCode},
     middleware::{self, Next},
     response::{IntoResponse, Response},
-    routing::get,
+    routing::{get, put},
     Json, Router,
 };
 use serde::{Deserialize, Serialize};
@@ -32,6 +34,12 @@ use tower_http::{
     trace::{DefaultMakeSpan, TraceLayer},
 };
 
+mod maintenance;
+mod mirror;
+
+use maintenance::{Maintenance, MaintenanceSettings};
+use mirror::{Mirror, MirrorConfig, MirrorMetricsSnapshot};
+
 // ============================================================================
 // Data Models
 // ============================================================================
@@ -140,6 +148,10 @@ struct AppState {
     users: Arc<RwLock<HashMap<u32, User>>>,
     next_product_id: Arc<RwLock<u32>>,
     next_user_id: Arc<RwLock<u32>>,
+    /// Traffic mirroring, when a secondary backend is configured
+    mirror: Option<Mirror>,
+    /// Maintenance switch and per-route flags
+    maintenance: Maintenance,
 }
 
 impl AppState {
@@ -149,9 +161,23 @@ impl AppState {
             users: Arc::new(RwLock::new(HashMap::new())),
             next_product_id: Arc::new(RwLock::new(1)),
             next_user_id: Arc::new(RwLock::new(1)),
+            mirror: None,
+            maintenance: Maintenance::in_memory(),
         }
     }
 
+    /// Use maintenance settings persisted to a file
+    fn with_maintenance(mut self, maintenance: Maintenance) -> Self {
+        self.maintenance = maintenance;
+        self
+    }
+
+    /// Mirror a share of API traffic to a secondary backend
+    fn with_mirror(mut self, config: MirrorConfig) -> Self {
+        self.mirror = Some(Mirror::new(config));
+        self
+    }
+
     /// Initialize state with sample data
     async fn init_sample_data(&self) {
         let mut products = self.products.write().await;
@@ -204,6 +230,11 @@ enum AppError {
     InternalServerError(String),
     Unauthorized(String),
     ValidationError(String),
+    ServiceUnavailable {
+        message: String,
+        /// Seconds the client should wait before retrying
+        retry_after: Option<u64>,
+    },
 }
 
 impl std::fmt::Display for AppError {
@@ -214,6 +245,9 @@ impl std::fmt::Display for AppError {
             AppError::InternalServerError(msg) => write!(f, "Internal Server Error: {}", msg),
             AppError::Unauthorized(msg) => write!(f, "Unauthorized: {}", msg),
             AppError::ValidationError(msg) => write!(f, "Validation Error: {}", msg),
+            AppError::ServiceUnavailable { message, .. } => {
+                write!(f, "Service Unavailable: {}", message)
+            }
         }
     }
 }
@@ -225,12 +259,28 @@ impl std::error::Error for AppError {}
 /// return our custom error from handler functions
 impl IntoResponse for AppError {
     fn into_response(self) -> Response {
+        if let AppError::ServiceUnavailable {
+            message,
+            retry_after,
+        } = self
+        {
+            let body = Json(ApiResponse::<()>::error(message));
+            let mut response = (StatusCode::SERVICE_UNAVAILABLE, body).into_response();
+            if let Some(seconds) = retry_after {
+                response
+                    .headers_mut()
+                    .insert(header::RETRY_AFTER, header::HeaderValue::from(seconds));
+            }
+            return response;
+        }
+
         let (status, message) = match self {
             AppError::NotFound(msg) => (StatusCode::NOT_FOUND, msg),
             AppError::BadRequest(msg) => (StatusCode::BAD_REQUEST, msg),
             AppError::InternalServerError(msg) => (StatusCode::INTERNAL_SERVER_ERROR, msg),
             AppError::Unauthorized(msg) => (StatusCode::UNAUTHORIZED, msg),
             AppError::ValidationError(msg) => (StatusCode::BAD_REQUEST, msg),
+            AppError::ServiceUnavailable { .. } => unreachable!("handled above"),
         };
 
         let body = Json(ApiResponse::<()>::error(message));
@@ -612,6 +662,95 @@ async fn get_user(
         .ok_or_else(|| AppError::NotFound(format!("User with id {} not found", id)))
 }
 
+// ============================================================================
+// Admin Handlers
+// ============================================================================
+
+/// Traffic mirroring counters
+/// Demonstrates: Optional features in shared state
+///
+/// Example: GET /admin/mirror
+async fn mirror_metrics(
+    State(state): State<AppState>,
+) -> Result<Json<ApiResponse<MirrorMetricsSnapshot>>, AppError> {
+    state
+        .mirror
+        .as_ref()
+        .map(|mirror| Json(ApiResponse::success(mirror.metrics())))
+        .ok_or_else(|| AppError::NotFound("Traffic mirroring is disabled".to_string()))
+}
+
+/// Request body for switching maintenance mode
+#[derive(Debug, Deserialize)]
+struct MaintenanceRequest {
+    enabled: bool,
+    retry_after_secs: Option<u64>,
+    message: Option<String>,
+}
+
+/// Request body for enabling or disabling a single route
+#[derive(Debug, Deserialize)]
+struct RouteToggle {
+    /// Route pattern, optionally with a method: "DELETE /api/products/:id"
+    route: String,
+    enabled: bool,
+}
+
+/// Current maintenance settings
+///
+/// Example: GET /admin/maintenance
+async fn get_maintenance(State(state): State<AppState>) -> Json<ApiResponse<MaintenanceSettings>> {
+    Json(ApiResponse::success(state.maintenance.settings().await))
+}
+
+/// Turn maintenance mode on or off
+/// Demonstrates: Runtime configuration persisted across restarts
+///
+/// Example: PUT /admin/maintenance
+/// Body: {"enabled": true, "retry_after_secs": 600}
+async fn set_maintenance(
+    State(state): State<AppState>,
+    Json(payload): Json<MaintenanceRequest>,
+) -> Result<Json<ApiResponse<MaintenanceSettings>>, AppError> {
+    let settings = state
+        .maintenance
+        .update(|settings| {
+            settings.enabled = payload.enabled;
+            if let Some(seconds) = payload.retry_after_secs {
+                settings.retry_after_secs = seconds;
+            }
+            settings.message = payload.message;
+        })
+        .await?;
+
+    tracing::warn!("Maintenance mode enabled: {}", settings.enabled);
+    Ok(Json(ApiResponse::success(settings)))
+}
+
+/// Enable or disable one route
+///
+/// Example: PUT /admin/routes
+/// Body: {"route": "POST /api/products", "enabled": false}
+async fn toggle_route(
+    State(state): State<AppState>,
+    Json(payload): Json<RouteToggle>,
+) -> Result<Json<ApiResponse<MaintenanceSettings>>, AppError> {
+    let key = maintenance::route_key(&payload.route)?;
+    let settings = state
+        .maintenance
+        .update(|settings| {
+            if payload.enabled {
+                settings.disabled_routes.remove(&key);
+            } else {
+                settings.disabled_routes.insert(key.clone());
+            }
+        })
+        .await?;
+
+    tracing::warn!("Route {} enabled: {}", key, payload.enabled);
+    Ok(Json(ApiResponse::success(settings)))
+}
+
 // ============================================================================
 // Router Configuration
 // ============================================================================
@@ -648,23 +787,55 @@ fn api_router() -> Router<AppState> {
         .route("/health", get(health_check))
 }
 
+/// Create the admin router
+/// Every admin route requires an Authorization header
+fn admin_router() -> Router<AppState> {
+    Router::new()
+        .route("/mirror", get(mirror_metrics))
+        .route("/maintenance", get(get_maintenance).put(set_maintenance))
+        .route("/routes", put(toggle_route))
+        // route_layer only runs for matched routes, so unknown paths stay 404
+        .route_layer(middleware::from_fn(auth_middleware))
+}
+
 /// Build the complete application with all routes and middleware
 /// Demonstrates: Router composition, state injection, middleware layers
 fn app(state: AppState) -> Router {
-    Router::new()
+    let mirror = state.mirror.clone();
+    let maintenance = state.maintenance.clone();
+
+    let router = Router::new()
         // Root endpoint
         .route("/", get(root_handler))
         // Health check at root level
         .route("/health", get(health_check))
+        // Operational endpoints, exempt from maintenance mode
+        .nest(maintenance::ADMIN_PREFIX, admin_router())
         // API routes (nested under /api prefix)
         .nest("/api", api_router())
         // Static file serving example
         // In a real app, create a "static" directory with files
         // This shows how to serve static files like images, CSS, JS
         .nest_service("/static", ServeDir::new("static"))
+        // Maintenance checks run after routing so they can see the matched route
+        .layer(middleware::from_fn_with_state(
+            maintenance,
+            maintenance::maintenance_middleware,
+        ))
         // Inject shared state into the router
         // All handlers with State<AppState> will receive this state
-        .with_state(state)
+        .with_state(state);
+
+    // Mirror before anything else runs, so the copy matches what the client sent
+    let router = match mirror {
+        Some(mirror) => router.layer(middleware::from_fn_with_state(
+            mirror,
+            mirror::mirror_middleware,
+        )),
+        None => router,
+    };
+
+    router
         // Add middleware layers
         // Middleware is executed in reverse order (bottom to top)
         // So requests flow: cors -> logging -> tracing -> handlers
@@ -714,9 +885,27 @@ async fn main() {
     println!("Initializing Axum REST API server...");
 
     // Create and initialize application state
-    let state = AppState::new();
+    // Maintenance settings survive restarts, e.g. MAINTENANCE_FILE=/var/lib/app/maintenance.json
+    let maintenance_file =
+        std::env::var("MAINTENANCE_FILE").unwrap_or_else(|_| "maintenance.json".to_string());
+    let maintenance =
+        Maintenance::load(&maintenance_file).expect("Failed to load maintenance settings");
+    if maintenance.settings().await.enabled {
+        println!("Starting in maintenance mode ({})", maintenance_file);
+    }
+
+    let mut state = AppState::new().with_maintenance(maintenance);
     state.init_sample_data().await;
 
+    // Optional traffic mirroring, e.g. MIRROR_TARGET=http://localhost:4000 MIRROR_PERCENT=25
+    if let Some(config) = MirrorConfig::from_env() {
+        println!(
+            "Mirroring {}% of {} traffic to {}",
+            config.percentage, config.path_prefix, config.target
+        );
+        state = state.with_mirror(config);
+    }
+
     println!("Sample data initialized");
 
     // Build the application with routes and middleware
@@ -741,6 +930,11 @@ async fn main() {
     println!("  GET    /api/users           - List users");
     println!("  GET    /api/users/:id       - Get user by ID");
     println!("  GET    /static/*            - Serve static files");
+    println!("  GET    /admin/mirror        - Traffic mirroring metrics");
+    println!("  GET    /admin/maintenance   - Maintenance settings");
+    println!("  PUT    /admin/maintenance   - Enable/disable maintenance mode");
+    println!("  PUT    /admin/routes        - Enable/disable a route");
+    println!("  (admin endpoints require an Authorization header)");
     println!("\nExample curl commands:");
     println!("  curl http://localhost:3000/");
     println!("  curl http://localhost:3000/api/products");
@@ -830,4 +1024,304 @@ mod tests {
         assert!(error_response.data.is_none());
         assert!(error_response.message.is_some());
     }
+
+    /// Test mirror sampling - percentages are spread evenly
+    #[test]
+    fn test_mirror_sampling() {
+        let mirrored = |percentage| {
+            (0..1000)
+                .filter(|&n| mirror::should_mirror(n, percentage))
+                .count()
+        };
+        assert_eq!(mirrored(0.0), 0);
+        assert_eq!(mirrored(10.0), 100);
+        assert_eq!(mirrored(25.0), 250);
+        assert_eq!(mirrored(100.0), 1000);
+
+        // 50% mirrors every other request
+        assert!(!mirror::should_mirror(0, 50.0));
+        assert!(mirror::should_mirror(1, 50.0));
+    }
+
+    /// Start a secondary backend that reports every request it receives
+    async fn spawn_secondary() -> (
+        String,
+        tokio::sync::mpsc::UnboundedReceiver<(String, String, String)>,
+    ) {
+        let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+        let secondary = Router::new().fallback(move |req: Request<axum::body::Body>| {
+            let tx = tx.clone();
+            async move {
+                let method = req.method().to_string();
+                let uri = req.uri().to_string();
+                let body = axum::body::to_bytes(req.into_body(), usize::MAX)
+                    .await
+                    .unwrap();
+                tx.send((method, uri, String::from_utf8(body.to_vec()).unwrap()))
+                    .unwrap();
+                StatusCode::OK
+            }
+        });
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, secondary).await.unwrap() });
+        (format!("http://{}", addr), rx)
+    }
+
+    fn json_request(method: &str, uri: &str, body: &str) -> Request<axum::body::Body> {
+        Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("content-type", "application/json")
+            .body(axum::body::Body::from(body.to_string()))
+            .unwrap()
+    }
+
+    /// Wait until background mirror tasks have finished
+    async fn wait_for_mirrors(state: &AppState, finished: u64) -> MirrorMetricsSnapshot {
+        let mirror = state.mirror.as_ref().unwrap();
+        for _ in 0..100 {
+            let metrics = mirror.metrics();
+            if metrics.succeeded + metrics.failed >= finished {
+                return metrics;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+        panic!("mirror requests did not finish");
+    }
+
+    /// Test mirroring - the secondary receives an identical copy
+    #[tokio::test]
+    async fn test_mirror_copies_requests() {
+        use tower::ServiceExt;
+
+        let (target, mut received) = spawn_secondary().await;
+        let state = AppState::new().with_mirror(MirrorConfig::new(target, 100.0));
+        state.init_sample_data().await;
+
+        let body = r#"{"name":"Keyboard","description":"Mechanical","price":79.99,"quantity":3,"category":"Electronics"}"#;
+        let response = app(state.clone())
+            .oneshot(json_request("POST", "/api/products?source=test", body))
+            .await
+            .unwrap();
+        // The client still gets the primary's response
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(state.products.read().await.len(), 4);
+
+        let (method, uri, mirrored_body) = received.recv().await.unwrap();
+        assert_eq!(method, "POST");
+        assert_eq!(uri, "/api/products?source=test");
+        assert_eq!(mirrored_body, body);
+
+        // Non-API routes are not mirrored
+        app(state.clone())
+            .oneshot(json_request("GET", "/health", ""))
+            .await
+            .unwrap();
+
+        let metrics = wait_for_mirrors(&state, 1).await;
+        assert_eq!(metrics.seen, 1);
+        assert_eq!(metrics.mirrored, 1);
+        assert_eq!(metrics.succeeded, 1);
+        assert_eq!(metrics.failed, 0);
+    }
+
+    /// Test mirroring - oversized bodies are passed through but not mirrored
+    #[tokio::test]
+    async fn test_mirror_skips_large_bodies() {
+        use tower::ServiceExt;
+
+        let (target, _received) = spawn_secondary().await;
+        let mut config = MirrorConfig::new(target, 100.0);
+        config.max_body_bytes = 16;
+        let state = AppState::new().with_mirror(config);
+
+        let body = r#"{"name":"Keyboard","description":"Mechanical","price":79.99,"quantity":3,"category":"Electronics"}"#;
+        let response = app(state.clone())
+            .oneshot(json_request("POST", "/api/products", body))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let metrics = state.mirror.as_ref().unwrap().metrics();
+        assert_eq!(metrics.mirrored, 0);
+        assert_eq!(metrics.skipped_body_too_large, 1);
+    }
+
+    /// Test mirroring - failures are counted and never reach the client
+    #[tokio::test]
+    async fn test_mirror_failures_are_counted() {
+        use tower::ServiceExt;
+
+        // Bind and drop a listener to get a port nobody is listening on
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let target = format!("http://{}", listener.local_addr().unwrap());
+        drop(listener);
+
+        let state = AppState::new().with_mirror(MirrorConfig::new(target, 100.0));
+        state.init_sample_data().await;
+
+        let response = app(state.clone())
+            .oneshot(json_request("GET", "/api/products/1", ""))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let metrics = wait_for_mirrors(&state, 1).await;
+        assert_eq!(metrics.failed, 1);
+        assert_eq!(metrics.succeeded, 0);
+
+        // Metrics are exposed over HTTP
+        let response = app(state)
+            .oneshot(admin_request("GET", "/admin/mirror", ""))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    fn admin_request(method: &str, uri: &str, body: &str) -> Request<axum::body::Body> {
+        let mut req = json_request(method, uri, body);
+        req.headers_mut()
+            .insert("authorization", "Bearer admin".parse().unwrap());
+        req
+    }
+
+    /// A unique settings file in the system temp directory
+    fn maintenance_file(name: &str) -> std::path::PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("axum-example-{}-{}.json", name, std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
+    async fn send(state: &AppState, req: Request<axum::body::Body>) -> Response {
+        use tower::ServiceExt;
+        app(state.clone()).oneshot(req).await.unwrap()
+    }
+
+    /// Test maintenance mode - non-admin routes get 503 with Retry-After
+    #[tokio::test]
+    async fn test_maintenance_mode() {
+        let state = AppState::new();
+        state.init_sample_data().await;
+
+        let response = send(
+            &state,
+            admin_request(
+                "PUT",
+                "/admin/maintenance",
+                r#"{"enabled":true,"retry_after_secs":120}"#,
+            ),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        for uri in ["/", "/health", "/api/products", "/api/products/1"] {
+            let response = send(&state, json_request("GET", uri, "")).await;
+            assert_eq!(
+                response.status(),
+                StatusCode::SERVICE_UNAVAILABLE,
+                "{}",
+                uri
+            );
+            assert_eq!(response.headers()[header::RETRY_AFTER], "120");
+        }
+
+        // Admin routes keep working so maintenance can be turned off
+        let response = send(&state, admin_request("GET", "/admin/maintenance", "")).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        send(
+            &state,
+            admin_request("PUT", "/admin/maintenance", r#"{"enabled":false}"#),
+        )
+        .await;
+
+        let response = send(&state, json_request("GET", "/api/products", "")).await;
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    /// Test admin routes - authorization is required
+    #[tokio::test]
+    async fn test_admin_requires_authorization() {
+        let state = AppState::new();
+        let response = send(
+            &state,
+            json_request("PUT", "/admin/maintenance", r#"{"enabled":true}"#),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        assert!(!state.maintenance.settings().await.enabled);
+    }
+
+    /// Test route toggles - flags match route patterns, optionally per method
+    #[tokio::test]
+    async fn test_route_toggles() {
+        let state = AppState::new();
+        state.init_sample_data().await;
+
+        let toggle = |route: &str, enabled: bool| {
+            admin_request(
+                "PUT",
+                "/admin/routes",
+                &format!(r#"{{"route":"{}","enabled":{}}}"#, route, enabled),
+            )
+        };
+
+        // Disable deletes only; reads of the same route still work
+        let response = send(&state, toggle("delete /api/products/:id", false)).await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response = send(&state, json_request("DELETE", "/api/products/2", "")).await;
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert!(response.headers().get(header::RETRY_AFTER).is_none());
+        let response = send(&state, json_request("GET", "/api/products/2", "")).await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        // Disable a whole route
+        send(&state, toggle("/api/users", false)).await;
+        let response = send(&state, json_request("GET", "/api/users", "")).await;
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+
+        // Re-enable
+        send(&state, toggle("DELETE /api/products/:id", true)).await;
+        let response = send(&state, json_request("DELETE", "/api/products/2", "")).await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        // Admin routes cannot be switched off
+        let response = send(&state, toggle("/admin/routes", false)).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    /// Test maintenance persistence - settings survive a restart
+    #[tokio::test]
+    async fn test_maintenance_persisted() {
+        let path = maintenance_file("persisted");
+
+        let state = AppState::new().with_maintenance(Maintenance::load(&path).unwrap());
+        send(
+            &state,
+            admin_request("PUT", "/admin/maintenance", r#"{"enabled":true}"#),
+        )
+        .await;
+        send(
+            &state,
+            admin_request(
+                "PUT",
+                "/admin/routes",
+                r#"{"route":"POST /api/products","enabled":false}"#,
+            ),
+        )
+        .await;
+
+        // "Restart": a fresh state loaded from the same file
+        let restarted = AppState::new().with_maintenance(Maintenance::load(&path).unwrap());
+        let settings = restarted.maintenance.settings().await;
+        assert!(settings.enabled);
+        assert!(settings.disabled_routes.contains("POST /api/products"));
+
+        let response = send(&restarted, json_request("GET", "/api/products", "")).await;
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+
+        std::fs::remove_file(&path).unwrap();
+    }
 }
@@ -0,0 +1,219 @@
+//! # Maintenance Mode and Route Toggles
+//!
+//! Operational switches that an admin can flip at runtime:
+//! - a global maintenance mode that answers every non-admin route with
+//!   `503 Service Unavailable` and a `Retry-After` header
+//! - per-route flags that take a single endpoint out of service
+//!
+//! Settings are written to a JSON file on every change and read back on
+//! startup, so a restart does not silently bring a service out of maintenance.
+//!
+//! Route flags are keyed by the route *pattern* (`/api/products/:id`), not
+//! the concrete path, and may be narrowed to one method
+//! (`DELETE /api/products/:id`). The middleware finds the pattern through
+//! `MatchedPath`, which is why it is added with `Router::layer`.
+
+use axum::{
+    extract::{MatchedPath, Request, State},
+    middleware::Next,
+    response::Response,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+use crate::AppError;
+
+/// Routes under this prefix are never blocked, so maintenance can be turned off
+pub const ADMIN_PREFIX: &str = "/admin";
+
+const DEFAULT_RETRY_AFTER_SECS: u64 = 300;
+
+/// Persisted operational settings
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct MaintenanceSettings {
+    pub enabled: bool,
+    /// Sent as `Retry-After` while in maintenance
+    pub retry_after_secs: u64,
+    /// Optional message shown to clients instead of the default
+    pub message: Option<String>,
+    /// Disabled route keys: `"/path/:param"` or `"METHOD /path/:param"`
+    pub disabled_routes: BTreeSet<String>,
+}
+
+impl Default for MaintenanceSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            retry_after_secs: DEFAULT_RETRY_AFTER_SECS,
+            message: None,
+            disabled_routes: BTreeSet::new(),
+        }
+    }
+}
+
+impl MaintenanceSettings {
+    /// Why a request may not proceed, if it may not
+    fn check(&self, method: &str, route: &str) -> Option<AppError> {
+        if route.starts_with(ADMIN_PREFIX) {
+            return None;
+        }
+
+        if self.enabled {
+            return Some(AppError::ServiceUnavailable {
+                message: self
+                    .message
+                    .clone()
+                    .unwrap_or_else(|| "Service is under maintenance".to_string()),
+                retry_after: Some(self.retry_after_secs),
+            });
+        }
+
+        let method_key = format!("{} {}", method, route);
+        if self.disabled_routes.contains(route) || self.disabled_routes.contains(&method_key) {
+            return Some(AppError::ServiceUnavailable {
+                message: format!("Route {} is disabled", route),
+                retry_after: None,
+            });
+        }
+
+        None
+    }
+}
+
+/// Normalize a route key: uppercase method (if any), single space, path as-is
+pub fn route_key(route: &str) -> Result<String, AppError> {
+    let route = route.trim();
+    let (method, path) = match route.split_once(char::is_whitespace) {
+        Some((method, path)) => (Some(method.to_ascii_uppercase()), path.trim()),
+        None => (None, route),
+    };
+
+    if !path.starts_with('/') {
+        return Err(AppError::ValidationError(format!(
+            "Route must start with '/': {}",
+            path
+        )));
+    }
+    if path.starts_with(ADMIN_PREFIX) {
+        return Err(AppError::ValidationError(
+            "Admin routes cannot be disabled".to_string(),
+        ));
+    }
+
+    Ok(match method {
+        Some(method) => format!("{} {}", method, path),
+        None => path.to_string(),
+    })
+}
+
+/// Shared handle to the settings and the file they are persisted to
+#[derive(Clone)]
+pub struct Maintenance {
+    settings: Arc<RwLock<MaintenanceSettings>>,
+    path: Option<Arc<PathBuf>>,
+}
+
+impl Maintenance {
+    /// Settings that live only as long as the process
+    pub fn in_memory() -> Self {
+        Self {
+            settings: Arc::new(RwLock::new(MaintenanceSettings::default())),
+            path: None,
+        }
+    }
+
+    /// Load settings from `path`, starting from defaults if it does not exist
+    pub fn load(path: impl Into<PathBuf>) -> Result<Self, AppError> {
+        let path = path.into();
+        let settings = match std::fs::read_to_string(&path) {
+            Ok(contents) => serde_json::from_str(&contents).map_err(|e| {
+                AppError::InternalServerError(format!(
+                    "Invalid maintenance file {}: {}",
+                    path.display(),
+                    e
+                ))
+            })?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => MaintenanceSettings::default(),
+            Err(e) => {
+                return Err(AppError::InternalServerError(format!(
+                    "Failed to read {}: {}",
+                    path.display(),
+                    e
+                )))
+            }
+        };
+
+        Ok(Self {
+            settings: Arc::new(RwLock::new(settings)),
+            path: Some(Arc::new(path)),
+        })
+    }
+
+    pub async fn settings(&self) -> MaintenanceSettings {
+        self.settings.read().await.clone()
+    }
+
+    /// Apply a change and persist it; the in-memory settings only change
+    /// if the file was written, so memory and disk never disagree
+    pub async fn update(
+        &self,
+        change: impl FnOnce(&mut MaintenanceSettings),
+    ) -> Result<MaintenanceSettings, AppError> {
+        let mut settings = self.settings.write().await;
+        let mut updated = settings.clone();
+        change(&mut updated);
+
+        if let Some(path) = &self.path {
+            persist(path, &updated).await?;
+        }
+
+        *settings = updated.clone();
+        Ok(updated)
+    }
+}
+
+/// Write to a temporary file and rename it over the old one, so a crash
+/// mid-write cannot leave a truncated settings file behind
+async fn persist(path: &Path, settings: &MaintenanceSettings) -> Result<(), AppError> {
+    let json = serde_json::to_vec_pretty(settings)
+        .map_err(|e| AppError::InternalServerError(e.to_string()))?;
+    let tmp = path.with_extension("json.tmp");
+    let io_error = |e: std::io::Error| {
+        AppError::InternalServerError(format!("Failed to save settings: {}", e))
+    };
+
+    tokio::fs::write(&tmp, json).await.map_err(io_error)?;
+    tokio::fs::rename(&tmp, path).await.map_err(io_error)
+}
+
+/// Reject requests while in maintenance or when their route is disabled.
+///
+/// Add with `Router::layer(middleware::from_fn_with_state(maintenance, maintenance_middleware))`
+/// so that `MatchedPath` is available.
+pub async fn maintenance_middleware(
+    State(maintenance): State<Maintenance>,
+    req: Request,
+    next: Next,
+) -> Result<Response, AppError> {
+    // Unmatched requests (404s, static files) fall back to the raw path
+    let route = req
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|matched| matched.as_str().to_string())
+        .unwrap_or_else(|| req.uri().path().to_string());
+
+    if let Some(error) = maintenance
+        .settings
+        .read()
+        .await
+        .check(req.method().as_str(), &route)
+    {
+        return Err(error);
+    }
+
+    Ok(next.run(req).await)
+}
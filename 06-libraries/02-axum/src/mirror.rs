@@ -0,0 +1,260 @@
+//! # Traffic Mirroring
+//!
+//! Middleware that copies a percentage of incoming requests to a secondary
+//! backend (for example a new version of the service) without affecting the
+//! client. The mirrored request is sent in a background task and its response
+//! is discarded; only the outcome is recorded in `MirrorMetrics`.
+//!
+//! Safety rails, since the mirror must never hurt the primary path:
+//! - bodies are only buffered when their size is known and below a limit
+//! - a cap on in-flight mirror requests drops mirrors instead of queueing them
+//! - every mirror request has a timeout
+
+use axum::{
+    body::{Body, HttpBody},
+    extract::{Request, State},
+    http::{header, HeaderName},
+    middleware::Next,
+    response::Response,
+};
+use serde::Serialize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Semaphore;
+
+use crate::AppError;
+
+/// Header added to mirrored requests so the secondary can tell them apart
+pub const MIRROR_HEADER: &str = "x-mirrored-request";
+
+/// Mirroring settings
+#[derive(Debug, Clone)]
+pub struct MirrorConfig {
+    /// Base URL of the secondary backend, e.g. `http://localhost:4000`
+    pub target: String,
+    /// Share of requests to mirror, 0-100
+    pub percentage: f64,
+    /// Only requests under this path are mirrored
+    pub path_prefix: String,
+    /// Requests with larger (or unknown-size) bodies are not mirrored
+    pub max_body_bytes: usize,
+    /// Mirrors in flight before new ones are dropped
+    pub max_in_flight: usize,
+    pub timeout: Duration,
+}
+
+impl MirrorConfig {
+    pub fn new(target: impl Into<String>, percentage: f64) -> Self {
+        Self {
+            target: target.into().trim_end_matches('/').to_string(),
+            percentage: percentage.clamp(0.0, 100.0),
+            path_prefix: "/api".to_string(),
+            max_body_bytes: 64 * 1024,
+            max_in_flight: 64,
+            timeout: Duration::from_secs(5),
+        }
+    }
+
+    /// Read `MIRROR_TARGET` and `MIRROR_PERCENT` (default 10) from the
+    /// environment; mirroring is off when no target is set
+    pub fn from_env() -> Option<Self> {
+        let target = std::env::var("MIRROR_TARGET").ok()?;
+        let percentage = std::env::var("MIRROR_PERCENT")
+            .ok()
+            .and_then(|p| p.parse().ok())
+            .unwrap_or(10.0);
+        let mut config = Self::new(target, percentage);
+        if let Some(limit) = std::env::var("MIRROR_MAX_BODY")
+            .ok()
+            .and_then(|l| l.parse().ok())
+        {
+            config.max_body_bytes = limit;
+        }
+        Some(config)
+    }
+}
+
+/// Counters describing what the mirror did
+#[derive(Debug, Default)]
+pub struct MirrorMetrics {
+    /// Requests that matched the path prefix
+    seen: AtomicU64,
+    /// Mirror requests started
+    mirrored: AtomicU64,
+    /// Mirror requests that got a non-5xx response
+    succeeded: AtomicU64,
+    /// Mirror requests that failed to send, timed out, or got a 5xx
+    failed: AtomicU64,
+    /// Sampled requests skipped because of body size
+    skipped_body_too_large: AtomicU64,
+    /// Sampled requests dropped because too many mirrors were in flight
+    dropped_overloaded: AtomicU64,
+}
+
+/// Point-in-time copy of `MirrorMetrics` for the metrics endpoint
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct MirrorMetricsSnapshot {
+    pub target: String,
+    pub percentage: f64,
+    pub seen: u64,
+    pub mirrored: u64,
+    pub succeeded: u64,
+    pub failed: u64,
+    pub skipped_body_too_large: u64,
+    pub dropped_overloaded: u64,
+}
+
+/// Shared mirror state: config, HTTP client and metrics
+#[derive(Clone)]
+pub struct Mirror {
+    config: Arc<MirrorConfig>,
+    client: reqwest::Client,
+    metrics: Arc<MirrorMetrics>,
+    in_flight: Arc<Semaphore>,
+}
+
+impl Mirror {
+    pub fn new(config: MirrorConfig) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(config.timeout)
+            .build()
+            .expect("Failed to build mirror HTTP client");
+
+        Self {
+            in_flight: Arc::new(Semaphore::new(config.max_in_flight)),
+            config: Arc::new(config),
+            client,
+            metrics: Arc::new(MirrorMetrics::default()),
+        }
+    }
+
+    pub fn metrics(&self) -> MirrorMetricsSnapshot {
+        let m = &self.metrics;
+        MirrorMetricsSnapshot {
+            target: self.config.target.clone(),
+            percentage: self.config.percentage,
+            seen: m.seen.load(Ordering::Relaxed),
+            mirrored: m.mirrored.load(Ordering::Relaxed),
+            succeeded: m.succeeded.load(Ordering::Relaxed),
+            failed: m.failed.load(Ordering::Relaxed),
+            skipped_body_too_large: m.skipped_body_too_large.load(Ordering::Relaxed),
+            dropped_overloaded: m.dropped_overloaded.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Send a copy of the request in the background
+    fn spawn_mirror(
+        &self,
+        method: reqwest::Method,
+        path_and_query: String,
+        headers: reqwest::header::HeaderMap,
+        body: axum::body::Bytes,
+    ) {
+        let Ok(permit) = Arc::clone(&self.in_flight).try_acquire_owned() else {
+            self.metrics
+                .dropped_overloaded
+                .fetch_add(1, Ordering::Relaxed);
+            return;
+        };
+        self.metrics.mirrored.fetch_add(1, Ordering::Relaxed);
+
+        let request = self
+            .client
+            .request(method, format!("{}{}", self.config.target, path_and_query))
+            .headers(headers)
+            .header(MIRROR_HEADER, "1")
+            .body(body);
+        let metrics = Arc::clone(&self.metrics);
+
+        tokio::spawn(async move {
+            let _permit = permit;
+            match request.send().await {
+                Ok(response) if !response.status().is_server_error() => {
+                    metrics.succeeded.fetch_add(1, Ordering::Relaxed);
+                }
+                Ok(response) => {
+                    metrics.failed.fetch_add(1, Ordering::Relaxed);
+                    tracing::warn!("Mirror got {}", response.status());
+                }
+                Err(e) => {
+                    metrics.failed.fetch_add(1, Ordering::Relaxed);
+                    tracing::warn!("Mirror request failed: {}", e);
+                }
+            }
+        });
+    }
+}
+
+/// Deterministic sampling: the `n`th matching request (0-based) is mirrored
+/// when it pushes the running total of mirrored requests up by one. This
+/// spreads mirrors evenly (10% = every 10th request) without randomness.
+pub fn should_mirror(n: u64, percentage: f64) -> bool {
+    let before = (n as f64 * percentage / 100.0).floor();
+    let after = ((n + 1) as f64 * percentage / 100.0).floor();
+    after > before
+}
+
+/// Headers that describe the hop, not the request, and must not be copied
+fn is_hop_header(name: &HeaderName) -> bool {
+    name == header::HOST
+        || name == header::CONNECTION
+        || name == header::CONTENT_LENGTH
+        || name == header::TRANSFER_ENCODING
+}
+
+/// Mirror a sample of requests to the secondary backend.
+///
+/// Add with `middleware::from_fn_with_state(mirror, mirror_middleware)`.
+pub async fn mirror_middleware(
+    State(mirror): State<Mirror>,
+    req: Request,
+    next: Next,
+) -> Result<Response, AppError> {
+    if !req.uri().path().starts_with(&mirror.config.path_prefix) {
+        return Ok(next.run(req).await);
+    }
+
+    let n = mirror.metrics.seen.fetch_add(1, Ordering::Relaxed);
+    if !should_mirror(n, mirror.config.percentage) {
+        return Ok(next.run(req).await);
+    }
+
+    // Only buffer bodies whose size is known up front, so a large or
+    // streaming upload is never held in memory on behalf of the mirror
+    let fits = req
+        .body()
+        .size_hint()
+        .upper()
+        .is_some_and(|size| size <= mirror.config.max_body_bytes as u64);
+    if !fits {
+        mirror
+            .metrics
+            .skipped_body_too_large
+            .fetch_add(1, Ordering::Relaxed);
+        return Ok(next.run(req).await);
+    }
+
+    let (parts, body) = req.into_parts();
+    let bytes = axum::body::to_bytes(body, mirror.config.max_body_bytes)
+        .await
+        .map_err(|e| AppError::BadRequest(format!("Failed to read request body: {}", e)))?;
+
+    let path_and_query = parts
+        .uri
+        .path_and_query()
+        .map(|pq| pq.to_string())
+        .unwrap_or_else(|| parts.uri.path().to_string());
+    let headers = parts
+        .headers
+        .iter()
+        .filter(|(name, _)| !is_hop_header(name))
+        .map(|(name, value)| (name.clone(), value.clone()))
+        .collect();
+    mirror.spawn_mirror(parts.method.clone(), path_and_query, headers, bytes.clone());
+
+    // Rebuild the original request from the buffered body
+    Ok(next
+        .run(Request::from_parts(parts, Body::from(bytes)))
+        .await)
+}
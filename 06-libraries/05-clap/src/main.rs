@@ -13,17 +13,31 @@
 //! - Argument groups
 //! - Environment variable fallback
 //! - Custom help text
+//! - Shell completions (clap_complete) and man pages (clap_mangen)
+//! - Opt-in usage telemetry with a local spool (see `telemetry`)
+//! - Tasks persisted to a JSON file (see `storage`)
 //!
 //! Run with: cargo run -- --help
 //! Example commands:
-//!   cargo run -- --config /path/to/config add task "Buy groceries" --priority high
+//!   cargo run -- --config /path/to/config add "Buy groceries" --priority high
 //!   cargo run -- list --filter active --format json
 //!   cargo run -- show 42 --verbose
 //!   cargo run -- remote add origin https://github.com/user/repo
 //!   cargo run -- config set user.name "John Doe"
+//!   cargo run -- telemetry enable
 
-use clap::{Parser, Subcommand, ValueEnum};
+use clap::{CommandFactory, Parser, Subcommand, ValueEnum};
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::io::{self, BufRead, Write};
 use std::path::PathBuf;
+use std::time::Instant;
+
+mod storage;
+mod telemetry;
+
+use storage::{NewTask, Task, TaskStore};
+use telemetry::Telemetry;
 
 // ============================================================================
 // Main CLI Structure
@@ -113,8 +127,9 @@ enum OutputFormat {
 // Priority Enum - Demonstrates possible values
 // ============================================================================
 
-/// Task priority levels
-#[derive(Debug, Clone, Copy, ValueEnum)]
+/// Task priority levels, lowest first so they sort by urgency
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 enum Priority {
     Low,
     Medium,
@@ -126,7 +141,8 @@ enum Priority {
 // Status Enum
 // ============================================================================
 
-#[derive(Debug, Clone, Copy, ValueEnum)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 enum Status {
     Active,
     Completed,
@@ -378,11 +394,30 @@ enum Commands {
     },
 
     /// Generate shell completions
+    ///
+    /// Writes the completion script to stdout.
     Completions {
         /// Shell type
         #[arg(value_enum)]
         shell: Shell,
     },
+
+    /// Generate man pages for taskflow and every subcommand
+    ///
+    /// Hidden: used by packaging (`cargo xtask dist`), not by end users.
+    #[command(hide = true)]
+    Man {
+        /// Directory to write the `.1` files into
+        #[arg(long, value_name = "DIR", default_value = "man")]
+        out_dir: PathBuf,
+    },
+
+    /// Anonymous usage telemetry (off unless enabled)
+    ///
+    /// Records only command names and durations to a local spool,
+    /// uploaded in batches during `remote sync`.
+    #[command(subcommand)]
+    Telemetry(TelemetryCommands),
 }
 
 // ============================================================================
@@ -565,6 +600,29 @@ enum ProjectCommands {
     },
 }
 
+// ============================================================================
+// Telemetry Subcommands
+// ============================================================================
+
+#[derive(Subcommand, Debug)]
+enum TelemetryCommands {
+    /// Show whether telemetry is enabled and what is waiting to be uploaded
+    Status {
+        /// List the spooled events
+        #[arg(long)]
+        show_events: bool,
+    },
+
+    /// Start recording command names and durations
+    Enable,
+
+    /// Stop recording and uploading (the spool is kept until purged)
+    Disable,
+
+    /// Delete all spooled events
+    Purge,
+}
+
 // ============================================================================
 // Additional Enums
 // ============================================================================
@@ -586,6 +644,17 @@ enum Shell {
     PowerShell,
 }
 
+impl From<Shell> for clap_complete::Shell {
+    fn from(shell: Shell) -> Self {
+        match shell {
+            Shell::Bash => clap_complete::Shell::Bash,
+            Shell::Zsh => clap_complete::Shell::Zsh,
+            Shell::Fish => clap_complete::Shell::Fish,
+            Shell::PowerShell => clap_complete::Shell::PowerShell,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, ValueEnum)]
 enum ProjectTemplate {
     Basic,
@@ -626,7 +695,8 @@ fn validate_date(s: &str) -> Result<String, String> {
         return Err(String::from("Day must be between 1 and 31"));
     }
 
-    Ok(s.to_string())
+    // Zero-padded, so due dates sort correctly as strings
+    Ok(format!("{:04}-{:02}-{:02}", year, month, day))
 }
 
 /// Validates positive u64 values (greater than 0)
@@ -668,6 +738,158 @@ fn validate_repeat_days(s: &str) -> Result<u32, String> {
     Ok(value)
 }
 
+// ============================================================================
+// Telemetry Helpers
+// ============================================================================
+
+/// Fixed name for each command. Telemetry only ever sees these strings,
+/// never the arguments, which is what keeps it anonymous.
+fn command_name(command: &Commands) -> &'static str {
+    match command {
+        Commands::Add { .. } => "add",
+        Commands::List { .. } => "list",
+        Commands::Show { .. } => "show",
+        Commands::Update { .. } => "update",
+        Commands::Delete { .. } => "delete",
+        Commands::Remote(RemoteCommands::Add { .. }) => "remote add",
+        Commands::Remote(RemoteCommands::Remove { .. }) => "remote remove",
+        Commands::Remote(RemoteCommands::List { .. }) => "remote list",
+        Commands::Remote(RemoteCommands::Sync { .. }) => "remote sync",
+        Commands::Config(ConfigCommands::Get { .. }) => "config get",
+        Commands::Config(ConfigCommands::Set { .. }) => "config set",
+        Commands::Config(ConfigCommands::Unset { .. }) => "config unset",
+        Commands::Config(ConfigCommands::List { .. }) => "config list",
+        Commands::Project(ProjectCommands::Create { .. }) => "project create",
+        Commands::Project(ProjectCommands::List { .. }) => "project list",
+        Commands::Project(ProjectCommands::Show { .. }) => "project show",
+        Commands::Project(ProjectCommands::Archive { .. }) => "project archive",
+        Commands::Project(ProjectCommands::Delete { .. }) => "project delete",
+        Commands::Search { .. } => "search",
+        Commands::Export { .. } => "export",
+        Commands::Import { .. } => "import",
+        Commands::Completions { .. } => "completions",
+        Commands::Man { .. } => "man",
+        Commands::Telemetry(_) => "telemetry",
+    }
+}
+
+/// Directory holding TaskFlow's local state
+fn data_dir(cli: &Cli) -> PathBuf {
+    cli.work_dir
+        .clone()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join(".taskflow")
+}
+
+// ============================================================================
+// Task Helpers
+// ============================================================================
+
+fn open_store(cli: &Cli) -> TaskStore {
+    TaskStore::load(data_dir(cli)).unwrap_or_else(|e| {
+        eprintln!("Could not load tasks: {}", e);
+        std::process::exit(1);
+    })
+}
+
+fn save_store(store: &mut TaskStore) {
+    if let Err(e) = store.save() {
+        eprintln!("Could not save tasks to {}: {}", store.path().display(), e);
+        std::process::exit(1);
+    }
+}
+
+fn task_not_found(id: u64) -> ! {
+    eprintln!("Task #{} not found", id);
+    std::process::exit(1);
+}
+
+fn summary_line(task: &Task) -> String {
+    let mut line = format!(
+        "#{} [{:?}/{:?}] {}",
+        task.id, task.priority, task.status, task.description
+    );
+    if !task.tags.is_empty() {
+        line.push_str(&format!(" ({})", task.tags.join(", ")));
+    }
+    if let Some(due) = &task.due {
+        line.push_str(&format!(" due {}", due));
+    }
+    if let Some(user) = &task.assignee {
+        line.push_str(&format!(" @{}", user));
+    }
+    line
+}
+
+fn print_details(task: &Task, history: bool) {
+    println!("Task #{}: {}", task.id, task.description);
+    println!("  Priority: {:?}", task.priority);
+    println!("  Status: {:?}", task.status);
+    if !task.tags.is_empty() {
+        println!("  Tags: {}", task.tags.join(", "));
+    }
+    if let Some(due) = &task.due {
+        println!("  Due date: {}", due);
+    }
+    if let Some(user) = &task.assignee {
+        println!("  Assignee: {}", user);
+    }
+    if let Some(days) = task.repeat_days {
+        println!("  Repeats every {} days", days);
+    }
+    if history {
+        println!("  Created: {} (unix time)", task.created);
+        println!("  Last modified: {} (unix time)", task.modified);
+    }
+}
+
+/// JSON for both formats that need structured output; there is no YAML
+/// serializer in this example
+fn print_json(value: &impl Serialize, format: OutputFormat) {
+    if matches!(format, OutputFormat::Yaml) {
+        eprintln!("YAML output is not available for tasks, using JSON");
+    }
+    match serde_json::to_string_pretty(value) {
+        Ok(json) => println!("{}", json),
+        Err(e) => eprintln!("Could not format tasks as JSON: {}", e),
+    }
+}
+
+fn print_task(task: &Task, format: OutputFormat, history: bool) {
+    match format {
+        OutputFormat::Text => print_details(task, history),
+        OutputFormat::Compact => println!("{}", summary_line(task)),
+        OutputFormat::Json | OutputFormat::Yaml => print_json(task, format),
+    }
+}
+
+fn print_tasks(tasks: &[&Task], format: OutputFormat) {
+    match format {
+        OutputFormat::Text | OutputFormat::Compact => {
+            if tasks.is_empty() {
+                println!("No tasks.");
+            }
+            for task in tasks {
+                println!("{}", summary_line(task));
+            }
+        }
+        OutputFormat::Json | OutputFormat::Yaml => print_json(&tasks, format),
+    }
+}
+
+/// Ask a yes/no question on stdin; anything but "y" or "yes" is a no
+fn confirm(question: &str) -> bool {
+    print!("{} [y/N] ", question);
+    if io::stdout().flush().is_err() {
+        return false;
+    }
+    let mut answer = String::new();
+    match io::stdin().lock().read_line(&mut answer) {
+        Ok(_) => matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"),
+        Err(_) => false,
+    }
+}
+
 // ============================================================================
 // Main Function - Command Handler
 // ============================================================================
@@ -675,6 +897,43 @@ fn validate_repeat_days(s: &str) -> Result<u32, String> {
 fn main() {
     // Parse command-line arguments using the derive API
     let cli = Cli::parse();
+    let started = Instant::now();
+    let mut telemetry = Telemetry::open(data_dir(&cli));
+
+    // Generated files must not contain the banner, so these run first
+    match &cli.command {
+        Commands::Completions { shell } => {
+            let mut command = Cli::command();
+            clap_complete::generate(
+                clap_complete::Shell::from(*shell),
+                &mut command,
+                "taskflow",
+                &mut io::stdout(),
+            );
+            eprintln!("To install, run the appropriate command for your shell:");
+            match shell {
+                Shell::Bash => {
+                    eprintln!("  taskflow completions bash > /etc/bash_completion.d/taskflow")
+                }
+                Shell::Zsh => eprintln!("  taskflow completions zsh > ~/.zsh/completion/_taskflow"),
+                Shell::Fish => eprintln!(
+                    "  taskflow completions fish > ~/.config/fish/completions/taskflow.fish"
+                ),
+                Shell::PowerShell => eprintln!("  taskflow completions powershell > taskflow.ps1"),
+            }
+            return;
+        }
+        Commands::Man { out_dir } => {
+            let result = std::fs::create_dir_all(out_dir)
+                .and_then(|_| clap_mangen::generate_to(Cli::command(), out_dir));
+            if let Err(e) = result {
+                eprintln!("Could not write man pages to {}: {}", out_dir.display(), e);
+                std::process::exit(1);
+            }
+            return;
+        }
+        _ => {}
+    }
 
     // Display global configuration
     println!("=== TaskFlow CLI ===");
@@ -703,21 +962,24 @@ fn main() {
             assignee,
             repeat,
         } => {
-            println!("Adding new task:");
-            println!("  Description: {}", description);
-            println!("  Priority: {:?}", priority);
-            if !tags.is_empty() {
-                println!("  Tags: {}", tags.join(", "));
-            }
-            if let Some(due_date) = due {
-                println!("  Due date: {}", due_date);
-            }
-            if let Some(user) = assignee {
-                println!("  Assignee: {}", user);
-            }
-            if let Some(days) = repeat {
-                println!("  Repeats every {} days", days);
+            let mut store = open_store(&cli);
+            let mut unique_tags: Vec<String> = Vec::new();
+            for tag in tags {
+                if !unique_tags.contains(tag) {
+                    unique_tags.push(tag.clone());
+                }
             }
+            let task = store.add(NewTask {
+                description: description.clone(),
+                priority: *priority,
+                tags: unique_tags,
+                due: due.clone(),
+                assignee: assignee.clone(),
+                repeat_days: *repeat,
+            });
+            println!("Added task #{}", task.id);
+            print_task(task, cli.format, false);
+            save_store(&mut store);
         }
 
         Commands::List {
@@ -730,25 +992,41 @@ fn main() {
             limit,
             show_archived,
         } => {
-            println!("Listing tasks:");
-            if let Some(status) = filter {
-                println!("  Filter by status: {:?}", status);
-            }
-            if let Some(pri) = priority {
-                println!("  Filter by priority: {:?}", pri);
-            }
-            if let Some(tag_name) = tag {
-                println!("  Filter by tag: {}", tag_name);
+            let store = open_store(&cli);
+            // Asking for archived tasks by status shows them too
+            let include_archived = *show_archived || matches!(filter, Some(Status::Archived));
+            let mut tasks: Vec<&Task> = store
+                .tasks()
+                .iter()
+                .filter(|task| include_archived || task.status != Status::Archived)
+                .filter(|task| filter.is_none_or(|status| task.status == status))
+                .filter(|task| priority.is_none_or(|pri| task.priority == pri))
+                .filter(|task| tag.as_ref().is_none_or(|name| task.tags.contains(name)))
+                .filter(|task| {
+                    assignee
+                        .as_ref()
+                        .is_none_or(|user| task.assignee.as_ref() == Some(user))
+                })
+                .collect();
+
+            // Ties keep id order, as the sorts are stable
+            match sort.as_str() {
+                "created" => tasks.sort_by_key(|task| task.created),
+                "modified" => tasks.sort_by_key(|task| task.modified),
+                "priority" => tasks.sort_by_key(|task| Reverse(task.priority)),
+                // Tasks without a due date go last
+                "due" => {
+                    tasks.sort_by(|a, b| (a.due.is_none(), &a.due).cmp(&(b.due.is_none(), &b.due)))
+                }
+                other => unreachable!("clap only accepts known sort fields, got {}", other),
             }
-            if let Some(user) = assignee {
-                println!("  Filter by assignee: {}", user);
+            if *reverse {
+                tasks.reverse();
             }
-            println!("  Sort by: {}", sort);
-            println!("  Reverse order: {}", reverse);
             if let Some(max) = limit {
-                println!("  Limit: {}", max);
+                tasks.truncate(*max);
             }
-            println!("  Show archived: {}", show_archived);
+            print_tasks(&tasks, cli.format);
         }
 
         Commands::Show {
@@ -756,9 +1034,21 @@ fn main() {
             history,
             related,
         } => {
-            println!("Showing task #{}:", task_id);
-            println!("  Show history: {}", history);
-            println!("  Show related: {}", related);
+            let store = open_store(&cli);
+            let task = store
+                .get(*task_id)
+                .unwrap_or_else(|| task_not_found(*task_id));
+            print_task(task, cli.format, *history);
+            if *related {
+                let related_tasks: Vec<&Task> = store
+                    .tasks()
+                    .iter()
+                    .filter(|other| other.id != task.id)
+                    .filter(|other| other.tags.iter().any(|t| task.tags.contains(t)))
+                    .collect();
+                println!("Related tasks (sharing a tag):");
+                print_tasks(&related_tasks, cli.format);
+            }
         }
 
         Commands::Update {
@@ -771,28 +1061,42 @@ fn main() {
             clear_tags,
             assignee,
         } => {
-            println!("Updating task #{}:", task_id);
-            if let Some(desc) = description {
-                println!("  New description: {}", desc);
-            }
-            if let Some(pri) = priority {
-                println!("  New priority: {:?}", pri);
-            }
-            if let Some(stat) = status {
-                println!("  New status: {:?}", stat);
-            }
-            if !add_tags.is_empty() {
-                println!("  Adding tags: {}", add_tags.join(", "));
-            }
-            if !remove_tags.is_empty() {
-                println!("  Removing tags: {}", remove_tags.join(", "));
-            }
-            if *clear_tags {
-                println!("  Clearing all tags");
-            }
-            if let Some(user) = assignee {
-                println!("  New assignee: {}", user);
+            let mut store = open_store(&cli);
+            let changed = store.update(*task_id, |task| {
+                if let Some(desc) = description {
+                    task.description = desc.clone();
+                }
+                if let Some(pri) = priority {
+                    task.priority = *pri;
+                }
+                if let Some(stat) = status {
+                    task.status = *stat;
+                }
+                // Clearing first lets `--clear-tags --add-tag x` replace the tags
+                if *clear_tags {
+                    task.tags.clear();
+                }
+                task.tags.retain(|t| !remove_tags.contains(t));
+                for tag in add_tags {
+                    if !task.tags.contains(tag) {
+                        task.tags.push(tag.clone());
+                    }
+                }
+                if let Some(user) = assignee {
+                    task.assignee = Some(user.clone());
+                }
+            });
+            match changed {
+                None => task_not_found(*task_id),
+                Some(false) => println!("Task #{} is unchanged", task_id),
+                Some(true) => {
+                    println!("Updated task #{}", task_id);
+                    if let Some(task) = store.get(*task_id) {
+                        print_task(task, cli.format, false);
+                    }
+                }
             }
+            save_store(&mut store);
         }
 
         Commands::Delete {
@@ -800,9 +1104,34 @@ fn main() {
             force,
             cascade,
         } => {
-            println!("Deleting tasks: {:?}", task_ids);
-            println!("  Force: {}", force);
-            println!("  Cascade: {}", cascade);
+            let mut store = open_store(&cli);
+            let mut ids = task_ids.clone();
+            ids.sort_unstable();
+            ids.dedup();
+            // All or nothing: a typo in one id shouldn't delete the others
+            let missing: Vec<String> = ids
+                .iter()
+                .filter(|id| store.get(**id).is_none())
+                .map(|id| format!("#{}", id))
+                .collect();
+            if !missing.is_empty() {
+                eprintln!("No such tasks: {}", missing.join(", "));
+                std::process::exit(1);
+            }
+
+            if *cascade {
+                println!("  Note: tasks have no relations yet, so --cascade deletes nothing extra");
+            }
+            if *force || confirm(&format!("Delete {} task(s)?", ids.len())) {
+                for id in &ids {
+                    if let Some(task) = store.remove(*id) {
+                        println!("Deleted task #{}: {}", task.id, task.description);
+                    }
+                }
+                save_store(&mut store);
+            } else {
+                println!("Nothing deleted.");
+            }
         }
 
         Commands::Remote(remote_cmd) => match remote_cmd {
@@ -843,6 +1172,16 @@ fn main() {
                 println!("  Push only: {}", push);
                 println!("  Pull only: {}", pull);
                 println!("  Force: {}", force);
+
+                // Spooled telemetry goes out with the sync, never on its own
+                match telemetry.sync(remote.as_deref().unwrap_or("default")) {
+                    Ok(report) if report.batches > 0 => println!(
+                        "  Telemetry: sent {} events in {} batches ({} left)",
+                        report.uploaded, report.batches, report.remaining
+                    ),
+                    Ok(_) => {}
+                    Err(e) => eprintln!("  Telemetry upload failed: {}", e),
+                }
             }
         },
 
@@ -960,18 +1299,57 @@ fn main() {
             println!("  Dry run: {}", dry_run);
         }
 
-        Commands::Completions { shell } => {
-            println!("Generating shell completions for: {:?}", shell);
-            println!("To install, run the appropriate command for your shell:");
-            match shell {
-                Shell::Bash => {
-                    println!("  taskflow completions bash > /etc/bash_completion.d/taskflow")
+        Commands::Completions { .. } | Commands::Man { .. } => {
+            unreachable!("handled before the banner")
+        }
+
+        Commands::Telemetry(telemetry_cmd) => {
+            let result = match telemetry_cmd {
+                TelemetryCommands::Status { show_events } => {
+                    telemetry.pending().map(|events| {
+                        println!("Telemetry status:");
+                        println!("  Enabled: {}", telemetry.is_enabled());
+                        if Telemetry::do_not_track() {
+                            println!("  (disabled by DO_NOT_TRACK)");
+                        }
+                        println!("  Spool: {}", telemetry.spool_path().display());
+                        println!("  Pending events: {}", events.len());
+                        if *show_events {
+                            for event in &events {
+                                println!(
+                                    "    {} ({} ms, v{}, hour {})",
+                                    event.command, event.duration_ms, event.version, event.hour
+                                );
+                            }
+                        }
+                    })
                 }
-                Shell::Zsh => println!("  taskflow completions zsh > ~/.zsh/completion/_taskflow"),
-                Shell::Fish => println!(
-                    "  taskflow completions fish > ~/.config/fish/completions/taskflow.fish"
-                ),
-                Shell::PowerShell => println!("  taskflow completions powershell > taskflow.ps1"),
+                TelemetryCommands::Enable => telemetry.set_enabled(true).map(|_| {
+                    println!("Telemetry enabled.");
+                    println!("  Recorded: command names (e.g. \"remote sync\"), durations, version, hour");
+                    println!("  Never recorded: arguments, task contents, paths, URLs, tokens, user names");
+                    println!("  Uploaded only during `remote sync`; run `telemetry purge` to delete");
+                }),
+                TelemetryCommands::Disable => telemetry
+                    .set_enabled(false)
+                    .map(|_| println!("Telemetry disabled. Spooled events were kept; use `telemetry purge` to delete them.")),
+                TelemetryCommands::Purge => telemetry
+                    .purge()
+                    .map(|count| println!("Deleted {} spooled events.", count)),
+            };
+            if let Err(e) = result {
+                eprintln!("Telemetry error: {}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    // Record after the command so the duration covers it. Telemetry
+    // commands are not recorded, and failures here never fail the command.
+    if !matches!(cli.command, Commands::Telemetry(_)) {
+        if let Err(e) = telemetry.record(command_name(&cli.command), started.elapsed()) {
+            if cli.verbose > 0 {
+                eprintln!("Telemetry: could not record event: {}", e);
             }
         }
     }
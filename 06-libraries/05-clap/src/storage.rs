@@ -0,0 +1,336 @@
+//! Tasks, kept in a JSON file in the TaskFlow directory
+//!
+//! The whole file is read when a task command starts and written back once
+//! at the end, and only if something changed:
+//!
+//! - **Ids** come from a counter stored with the tasks, so an id is never
+//!   handed out twice, even after the task that had it is deleted.
+//! - **Dirty tracking.** Every change goes through [`TaskStore`], which
+//!   notes that the file is out of date; [`TaskStore::save`] is a no-op
+//!   otherwise, so `list` and `show` never touch the file.
+//! - **Writes are atomic.** The new contents go to a temporary file that is
+//!   renamed over the old one, so an interrupted save leaves the previous
+//!   version intact.
+//! - **A damaged file is an error**, not an empty store, so the next save
+//!   cannot overwrite tasks that failed to load.
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::{Priority, Status};
+
+const TASKS_FILE: &str = "tasks.json";
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Task {
+    pub id: u64,
+    pub description: String,
+    pub priority: Priority,
+    pub status: Status,
+    pub tags: Vec<String>,
+    /// YYYY-MM-DD, so due dates sort as strings
+    pub due: Option<String>,
+    pub assignee: Option<String>,
+    pub repeat_days: Option<u32>,
+    /// Unix time in seconds
+    pub created: u64,
+    /// Unix time in seconds of the last change that altered the task
+    pub modified: u64,
+}
+
+/// What `add` needs to create a task; the store fills in the rest
+#[derive(Debug)]
+pub struct NewTask {
+    pub description: String,
+    pub priority: Priority,
+    pub tags: Vec<String>,
+    pub due: Option<String>,
+    pub assignee: Option<String>,
+    pub repeat_days: Option<u32>,
+}
+
+/// The file's layout
+#[derive(Debug, Serialize, Deserialize)]
+struct TaskFile {
+    next_id: u64,
+    /// In id order
+    tasks: Vec<Task>,
+}
+
+impl Default for TaskFile {
+    fn default() -> Self {
+        Self {
+            next_id: 1,
+            tasks: Vec::new(),
+        }
+    }
+}
+
+pub struct TaskStore {
+    path: PathBuf,
+    file: TaskFile,
+    dirty: bool,
+}
+
+impl TaskStore {
+    /// Load the tasks in `dir`. A missing file is an empty store; one that
+    /// can't be parsed is an error.
+    pub fn load(dir: impl AsRef<Path>) -> io::Result<Self> {
+        let path = dir.as_ref().join(TASKS_FILE);
+        let mut file: TaskFile = match fs::read_to_string(&path) {
+            Ok(contents) => serde_json::from_str(&contents).map_err(|e| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("{} is not a valid task file: {}", path.display(), e),
+                )
+            })?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => TaskFile::default(),
+            Err(e) => return Err(e),
+        };
+        // Keep the invariants even if the file was edited by hand
+        file.tasks.sort_by_key(|task| task.id);
+        if let Some(last) = file.tasks.last() {
+            file.next_id = file.next_id.max(last.id + 1);
+        }
+        Ok(Self {
+            path,
+            file,
+            dirty: false,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Write the tasks back if anything changed, returning whether it did
+    pub fn save(&mut self) -> io::Result<bool> {
+        if !self.dirty {
+            return Ok(false);
+        }
+
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let json = serde_json::to_string_pretty(&self.file).map_err(io::Error::other)?;
+        let tmp = self.path.with_extension("json.tmp");
+        fs::write(&tmp, json)?;
+        fs::rename(&tmp, &self.path)?;
+        self.dirty = false;
+        Ok(true)
+    }
+
+    pub fn tasks(&self) -> &[Task] {
+        &self.file.tasks
+    }
+
+    pub fn get(&self, id: u64) -> Option<&Task> {
+        self.position(id).map(|index| &self.file.tasks[index])
+    }
+
+    /// Add a task with the next free id. New tasks start out pending.
+    pub fn add(&mut self, new: NewTask) -> &Task {
+        let id = self.file.next_id;
+        self.file.next_id += 1;
+        let now = now();
+        self.file.tasks.push(Task {
+            id,
+            description: new.description,
+            priority: new.priority,
+            status: Status::Pending,
+            tags: new.tags,
+            due: new.due,
+            assignee: new.assignee,
+            repeat_days: new.repeat_days,
+            created: now,
+            modified: now,
+        });
+        self.dirty = true;
+        self.file.tasks.last().expect("a task was just pushed")
+    }
+
+    /// Apply `change` to task `id`. Returns `None` if there is no such
+    /// task, otherwise whether the change altered it; only a change that
+    /// did marks the store dirty and bumps `modified`.
+    pub fn update(&mut self, id: u64, change: impl FnOnce(&mut Task)) -> Option<bool> {
+        let index = self.position(id)?;
+        let task = &mut self.file.tasks[index];
+        let before = task.clone();
+        change(task);
+        // The id is the store's to allocate, not the caller's to edit
+        task.id = before.id;
+        if *task == before {
+            return Some(false);
+        }
+        task.modified = now();
+        self.dirty = true;
+        Some(true)
+    }
+
+    pub fn remove(&mut self, id: u64) -> Option<Task> {
+        let task = self.file.tasks.remove(self.position(id)?);
+        self.dirty = true;
+        Some(task)
+    }
+
+    fn position(&self, id: u64) -> Option<usize> {
+        // Tasks are only ever appended with increasing ids
+        self.file
+            .tasks
+            .binary_search_by_key(&id, |task| task.id)
+            .ok()
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn new_task(description: &str) -> NewTask {
+        NewTask {
+            description: description.to_string(),
+            priority: Priority::Medium,
+            tags: Vec::new(),
+            due: None,
+            assignee: None,
+            repeat_days: None,
+        }
+    }
+
+    fn ids(store: &TaskStore) -> Vec<u64> {
+        store.tasks().iter().map(|task| task.id).collect()
+    }
+
+    #[test]
+    fn missing_file_is_an_empty_store() {
+        let dir = TempDir::new().unwrap();
+        let mut store = TaskStore::load(dir.path()).unwrap();
+        assert!(store.tasks().is_empty());
+        assert_eq!(store.add(new_task("first")).id, 1);
+    }
+
+    #[test]
+    fn ids_are_never_reused() {
+        let dir = TempDir::new().unwrap();
+        let mut store = TaskStore::load(dir.path()).unwrap();
+        store.add(new_task("one"));
+        store.add(new_task("two"));
+        assert_eq!(store.remove(2).unwrap().description, "two");
+        assert_eq!(store.add(new_task("three")).id, 3);
+
+        // The counter is saved with the tasks, so the last id stays
+        // retired after reloading too
+        store.remove(3);
+        store.save().unwrap();
+        let mut store = TaskStore::load(dir.path()).unwrap();
+        assert_eq!(ids(&store), [1]);
+        assert_eq!(store.add(new_task("four")).id, 4);
+    }
+
+    #[test]
+    fn load_repairs_a_hand_edited_file() {
+        let dir = TempDir::new().unwrap();
+        let mut store = TaskStore::load(dir.path()).unwrap();
+        for description in ["one", "two", "three"] {
+            store.add(new_task(description));
+        }
+        store.save().unwrap();
+
+        // Out of order, and a counter that would hand out 1 again
+        let path = store.path().to_path_buf();
+        let mut file: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        file["tasks"].as_array_mut().unwrap().reverse();
+        file["next_id"] = 1.into();
+        fs::write(&path, file.to_string()).unwrap();
+
+        let mut store = TaskStore::load(dir.path()).unwrap();
+        assert_eq!(ids(&store), [1, 2, 3]);
+        assert_eq!(store.get(2).unwrap().description, "two");
+        assert_eq!(store.add(new_task("four")).id, 4);
+    }
+
+    #[test]
+    fn update_reports_whether_anything_changed() {
+        let dir = TempDir::new().unwrap();
+        let mut store = TaskStore::load(dir.path()).unwrap();
+        store.add(new_task("one"));
+        store.save().unwrap();
+
+        assert_eq!(
+            store.update(1, |task| task.priority = Priority::Medium),
+            Some(false)
+        );
+        assert!(
+            !store.save().unwrap(),
+            "an unchanged task left the store dirty"
+        );
+
+        assert_eq!(
+            store.update(1, |task| task.status = Status::Active),
+            Some(true)
+        );
+        assert!(store.save().unwrap());
+        assert_eq!(store.get(1).unwrap().status, Status::Active);
+
+        assert_eq!(store.update(7, |task| task.status = Status::Active), None);
+    }
+
+    #[test]
+    fn update_keeps_the_id() {
+        let dir = TempDir::new().unwrap();
+        let mut store = TaskStore::load(dir.path()).unwrap();
+        store.add(new_task("one"));
+        store.save().unwrap();
+
+        // Only the id changed, and putting it back leaves nothing changed
+        assert_eq!(store.update(1, |task| task.id = 99), Some(false));
+        assert_eq!(
+            store.update(1, |task| {
+                task.id = 99;
+                task.description = "renamed".to_string();
+            }),
+            Some(true)
+        );
+        assert_eq!(ids(&store), [1]);
+        assert_eq!(store.get(1).unwrap().description, "renamed");
+        assert!(store.get(99).is_none());
+    }
+
+    #[test]
+    fn clean_store_writes_nothing() {
+        let dir = TempDir::new().unwrap();
+        let mut store = TaskStore::load(dir.path()).unwrap();
+        assert!(!store.save().unwrap());
+        assert!(!store.path().exists());
+
+        store.add(new_task("one"));
+        assert!(store.save().unwrap());
+        assert!(store.path().exists());
+        // Saved, so clean again
+        assert!(!store.save().unwrap());
+    }
+
+    #[test]
+    fn corrupt_file_is_an_error() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join(TASKS_FILE);
+        fs::write(&path, "{ not json").unwrap();
+
+        let error = TaskStore::load(dir.path()).err().unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        assert!(error.to_string().contains("tasks.json"), "{}", error);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "{ not json");
+    }
+}
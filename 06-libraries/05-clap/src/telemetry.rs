@@ -0,0 +1,360 @@
+//! Opt-in, anonymous usage telemetry with a local spool
+//!
+//! Data-handling boundaries, enforced by the types in this module:
+//!
+//! - **Off by default.** Nothing is recorded until `taskflow telemetry enable`,
+//!   and `DO_NOT_TRACK=1` turns it off regardless of the setting.
+//! - **What is recorded.** Only an [`Event`]: a fixed command name such as
+//!   `"remote sync"`, how long it took, the TaskFlow version, and the hour it
+//!   ran in. Command names are `&'static str` chosen by the program, so
+//!   argument values (task descriptions, paths, URLs, tokens, user names) have
+//!   no way into the spool. There is no user, machine or install identifier.
+//! - **Where it is kept.** Events are appended to a local spool file in the
+//!   TaskFlow directory. They only leave the machine during `remote sync`.
+//! - **How to remove it.** `taskflow telemetry purge` deletes the spool;
+//!   disabling stops recording *and* uploading but keeps the spool for review.
+
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const SETTINGS_FILE: &str = "telemetry.json";
+const SPOOL_FILE: &str = "telemetry-spool.jsonl";
+
+/// Events sent per upload request
+pub const BATCH_SIZE: usize = 50;
+
+/// The spool keeps at most this many events, dropping the oldest first, so
+/// an offline machine does not fill the disk
+pub const MAX_SPOOLED_EVENTS: usize = 5_000;
+
+/// One command invocation. This is the complete list of what is collected.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Event {
+    pub command: String,
+    pub duration_ms: u64,
+    pub version: String,
+    /// Unix time rounded down to the hour, so runs cannot be correlated precisely
+    pub hour: u64,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Settings {
+    enabled: bool,
+}
+
+/// Result of uploading the spool
+#[derive(Debug, Default)]
+pub struct SyncReport {
+    pub uploaded: usize,
+    pub batches: usize,
+    /// Events kept in the spool because their batch failed
+    pub remaining: usize,
+}
+
+pub struct Telemetry {
+    dir: PathBuf,
+    settings: Settings,
+    do_not_track: bool,
+}
+
+impl Telemetry {
+    /// Open the telemetry state in `dir`. An unreadable settings file counts
+    /// as "disabled": telemetry must never stop a command from running.
+    pub fn open(dir: impl Into<PathBuf>) -> Self {
+        let dir = dir.into();
+        let settings = fs::read_to_string(dir.join(SETTINGS_FILE))
+            .ok()
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default();
+        Self {
+            dir,
+            settings,
+            do_not_track: Self::do_not_track(),
+        }
+    }
+
+    /// `DO_NOT_TRACK` (https://consoledonottrack.com) overrides the setting
+    pub fn do_not_track() -> bool {
+        std::env::var("DO_NOT_TRACK").is_ok_and(|v| !v.is_empty() && v != "0")
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.settings.enabled && !self.do_not_track
+    }
+
+    pub fn spool_path(&self) -> PathBuf {
+        self.dir.join(SPOOL_FILE)
+    }
+
+    pub fn set_enabled(&mut self, enabled: bool) -> io::Result<()> {
+        self.settings.enabled = enabled;
+        fs::create_dir_all(&self.dir)?;
+        let json = serde_json::to_string_pretty(&self.settings).map_err(io::Error::other)?;
+        fs::write(self.dir.join(SETTINGS_FILE), json)
+    }
+
+    /// Append an event to the spool, if telemetry is enabled
+    pub fn record(&self, command: &'static str, duration: Duration) -> io::Result<()> {
+        if !self.is_enabled() {
+            return Ok(());
+        }
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let event = Event {
+            command: command.to_string(),
+            duration_ms: duration.as_millis() as u64,
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            hour: now - now % 3600,
+        };
+
+        // A full spool makes room by dropping its oldest events
+        let mut events = self.pending()?;
+        if events.len() >= MAX_SPOOLED_EVENTS {
+            events.drain(..=events.len() - MAX_SPOOLED_EVENTS);
+            events.push(event);
+            return rewrite_spool(&self.spool_path(), &events);
+        }
+
+        fs::create_dir_all(&self.dir)?;
+        let mut spool = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.spool_path())?;
+        let line = serde_json::to_string(&event).map_err(io::Error::other)?;
+        writeln!(spool, "{}", line)
+    }
+
+    /// Events waiting to be uploaded. Unparseable lines are skipped.
+    pub fn pending(&self) -> io::Result<Vec<Event>> {
+        let file = match File::open(self.spool_path()) {
+            Ok(file) => file,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+
+        let mut events = Vec::new();
+        for line in BufReader::new(file).lines() {
+            if let Ok(event) = serde_json::from_str(&line?) {
+                events.push(event);
+            }
+        }
+        Ok(events)
+    }
+
+    /// Delete the spool, returning how many events were in it
+    pub fn purge(&self) -> io::Result<usize> {
+        let count = self.pending()?.len();
+        match fs::remove_file(self.spool_path()) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(count),
+        }
+    }
+
+    /// Upload the spool to `remote` in batches of `BATCH_SIZE`. Events are
+    /// removed only once their batch is accepted; the rest stay for next time.
+    pub fn sync(&self, remote: &str) -> io::Result<SyncReport> {
+        self.sync_with(remote, upload_batch)
+    }
+
+    /// `sync` with a custom uploader, called once per batch
+    pub fn sync_with(
+        &self,
+        remote: &str,
+        mut upload: impl FnMut(&str, &[Event]) -> Result<(), String>,
+    ) -> io::Result<SyncReport> {
+        let mut report = SyncReport::default();
+        if !self.is_enabled() {
+            return Ok(report);
+        }
+
+        let events = self.pending()?;
+        let mut remaining = Vec::new();
+        for batch in events.chunks(BATCH_SIZE) {
+            // Stop at the first failure so the remaining events keep their order
+            if remaining.is_empty() && upload(remote, batch).is_ok() {
+                report.uploaded += batch.len();
+                report.batches += 1;
+            } else {
+                remaining.extend_from_slice(batch);
+            }
+        }
+
+        report.remaining = remaining.len();
+        rewrite_spool(&self.spool_path(), &remaining)?;
+        Ok(report)
+    }
+}
+
+/// Send one batch to the remote's telemetry endpoint.
+///
+/// This example has no HTTP client, so it only prints what would be sent;
+/// a real implementation would POST `body` to `{remote}/telemetry`.
+fn upload_batch(remote: &str, batch: &[Event]) -> Result<(), String> {
+    let body = serde_json::to_string(batch).map_err(|e| e.to_string())?;
+    println!(
+        "  Telemetry: uploading {} events ({} bytes) to {}/telemetry",
+        batch.len(),
+        body.len(),
+        remote.trim_end_matches('/')
+    );
+    Ok(())
+}
+
+/// Replace the spool with `events`, writing a temporary file first so an
+/// interrupted sync cannot lose events that were not uploaded
+fn rewrite_spool(path: &Path, events: &[Event]) -> io::Result<()> {
+    if events.is_empty() {
+        return match fs::remove_file(path) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        };
+    }
+
+    let tmp = path.with_extension("jsonl.tmp");
+    let mut file = File::create(&tmp)?;
+    for event in events {
+        let line = serde_json::to_string(event).map_err(io::Error::other)?;
+        writeln!(file, "{}", line)?;
+    }
+    fs::rename(tmp, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    /// Enabled telemetry in `dir`, whatever `DO_NOT_TRACK` says in the test environment
+    fn enabled(dir: &Path) -> Telemetry {
+        let mut telemetry = Telemetry::open(dir);
+        telemetry.set_enabled(true).unwrap();
+        telemetry.do_not_track = false;
+        telemetry
+    }
+
+    fn event(duration_ms: u64) -> Event {
+        Event {
+            command: "list".to_string(),
+            duration_ms,
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            hour: 0,
+        }
+    }
+
+    fn durations(events: &[Event]) -> Vec<u64> {
+        events.iter().map(|event| event.duration_ms).collect()
+    }
+
+    #[test]
+    fn disabled_by_default_and_by_do_not_track() {
+        let dir = TempDir::new().unwrap();
+        let mut telemetry = Telemetry::open(dir.path());
+        telemetry.do_not_track = false;
+        assert!(!telemetry.is_enabled());
+        telemetry.record("list", Duration::ZERO).unwrap();
+        assert!(telemetry.pending().unwrap().is_empty());
+
+        // Opting in persists, but DO_NOT_TRACK still wins
+        let mut telemetry = enabled(dir.path());
+        telemetry.do_not_track = true;
+        assert!(!telemetry.is_enabled());
+        telemetry.record("list", Duration::ZERO).unwrap();
+        assert!(telemetry.pending().unwrap().is_empty());
+        let report = telemetry
+            .sync_with("remote", |_, _| panic!("uploaded"))
+            .unwrap();
+        assert_eq!(report.uploaded, 0);
+
+        let mut telemetry = Telemetry::open(dir.path());
+        telemetry.do_not_track = false;
+        assert!(telemetry.is_enabled());
+    }
+
+    #[test]
+    fn sync_stops_at_the_first_failed_batch() {
+        let dir = TempDir::new().unwrap();
+        let telemetry = enabled(dir.path());
+        let events: Vec<Event> = (0..BATCH_SIZE as u64 * 3).map(event).collect();
+        rewrite_spool(&telemetry.spool_path(), &events).unwrap();
+
+        // The second batch fails; the third must not be tried
+        let mut calls = 0;
+        let report = telemetry
+            .sync_with("remote", |_, _| {
+                calls += 1;
+                if calls == 2 {
+                    Err("offline".to_string())
+                } else {
+                    Ok(())
+                }
+            })
+            .unwrap();
+        assert_eq!(calls, 2);
+        assert_eq!(report.uploaded, BATCH_SIZE);
+        assert_eq!(report.batches, 1);
+        assert_eq!(report.remaining, BATCH_SIZE * 2);
+        assert_eq!(telemetry.pending().unwrap(), events[BATCH_SIZE..]);
+
+        // Everything left goes out next time, and the spool is removed
+        let report = telemetry.sync_with("remote", |_, _| Ok(())).unwrap();
+        assert_eq!(report.uploaded, BATCH_SIZE * 2);
+        assert_eq!(report.remaining, 0);
+        assert!(!telemetry.spool_path().exists());
+    }
+
+    #[test]
+    fn rewrite_replaces_the_spool_through_a_temporary_file() {
+        let dir = TempDir::new().unwrap();
+        let telemetry = enabled(dir.path());
+        let spool = telemetry.spool_path();
+        rewrite_spool(&spool, &[event(1), event(2), event(3)]).unwrap();
+
+        // Leftovers from an interrupted rewrite are overwritten, not appended to
+        let tmp = spool.with_extension("jsonl.tmp");
+        fs::write(&tmp, "garbage\n").unwrap();
+        rewrite_spool(&spool, &[event(2), event(3)]).unwrap();
+        assert!(!tmp.exists());
+        assert_eq!(durations(&telemetry.pending().unwrap()), [2, 3]);
+
+        rewrite_spool(&spool, &[]).unwrap();
+        assert!(!spool.exists());
+        rewrite_spool(&spool, &[]).unwrap();
+    }
+
+    #[test]
+    fn full_spool_drops_the_oldest_events() {
+        let dir = TempDir::new().unwrap();
+        let telemetry = enabled(dir.path());
+        let events: Vec<Event> = (0..MAX_SPOOLED_EVENTS as u64).map(event).collect();
+        rewrite_spool(&telemetry.spool_path(), &events).unwrap();
+
+        telemetry
+            .record("list", Duration::from_millis(99_999))
+            .unwrap();
+        let pending = durations(&telemetry.pending().unwrap());
+        assert_eq!(pending.len(), MAX_SPOOLED_EVENTS);
+        assert_eq!(pending[0], 1);
+        assert_eq!(pending[pending.len() - 1], 99_999);
+    }
+
+    #[test]
+    fn purge_deletes_the_spool() {
+        let dir = TempDir::new().unwrap();
+        let telemetry = enabled(dir.path());
+        assert_eq!(telemetry.purge().unwrap(), 0);
+
+        telemetry.record("list", Duration::ZERO).unwrap();
+        telemetry.record("add", Duration::ZERO).unwrap();
+        assert_eq!(telemetry.purge().unwrap(), 2);
+        assert!(!telemetry.spool_path().exists());
+        assert!(telemetry.pending().unwrap().is_empty());
+        // Purging keeps the opt-in
+        assert!(Telemetry::open(dir.path()).settings.enabled);
+    }
+}
@@ -0,0 +1,302 @@
+//! Asset hot-reload: watch texture and model files and re-upload them to
+//! the GPU when they change on disk.
+//!
+//! Draw code never holds GPU resources directly. It holds a `Handle` into an
+//! `AssetTable`, and the table slot is what gets updated on reload. Where
+//! possible the existing GPU resource is rewritten in place (same texture
+//! size, mesh fits in the old buffers), so bind groups stay valid; otherwise
+//! the slot is rebuilt and everything that looks it up picks up the new one.
+
+use anyhow::*;
+use image::GenericImageView;
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, Receiver};
+use wgpu::util::DeviceExt;
+
+use crate::{Texture, Vertex};
+
+/// Stable reference to an asset stored in an `AssetTable`
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Handle(usize);
+
+/// Indirection table: handles stay the same while the assets behind them
+/// are swapped out by reloads
+pub struct AssetTable<T> {
+    slots: Vec<(Option<PathBuf>, T)>,
+}
+
+impl<T> AssetTable<T> {
+    pub fn new() -> Self {
+        Self { slots: Vec::new() }
+    }
+
+    /// Add an asset, optionally backed by a file that should be watched
+    pub fn insert(&mut self, path: Option<PathBuf>, asset: T) -> Handle {
+        self.slots.push((path, asset));
+        Handle(self.slots.len() - 1)
+    }
+
+    pub fn get(&self, handle: Handle) -> &T {
+        &self.slots[handle.0].1
+    }
+
+    pub fn get_mut(&mut self, handle: Handle) -> &mut T {
+        &mut self.slots[handle.0].1
+    }
+
+    /// Find the asset loaded from `path`
+    pub fn find(&self, path: &Path) -> Option<Handle> {
+        self.slots
+            .iter()
+            .position(|(p, _)| p.as_deref() == Some(path))
+            .map(Handle)
+    }
+}
+
+/// A texture together with the bind group that exposes it to the shader
+pub struct TextureSlot {
+    pub texture: Texture,
+    pub bind_group: wgpu::BindGroup,
+}
+
+impl TextureSlot {
+    pub fn new(device: &wgpu::Device, layout: &wgpu::BindGroupLayout, texture: Texture) -> Self {
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&texture.view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&texture.sampler),
+                },
+            ],
+            label: Some("diffuse_bind_group"),
+        });
+
+        Self {
+            texture,
+            bind_group,
+        }
+    }
+
+    /// Reload from image bytes.
+    ///
+    /// Same-sized images are written into the existing texture, so the bind
+    /// group does not change. A size change needs a new texture and bind group.
+    pub fn reload(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        layout: &wgpu::BindGroupLayout,
+        bytes: &[u8],
+    ) -> Result<()> {
+        let img = image::load_from_memory(bytes)?;
+        let size = self.texture.texture.size();
+
+        if img.dimensions() == (size.width, size.height) {
+            queue.write_texture(
+                wgpu::ImageCopyTexture {
+                    aspect: wgpu::TextureAspect::All,
+                    texture: &self.texture.texture,
+                    mip_level: 0,
+                    origin: wgpu::Origin3d::ZERO,
+                },
+                &img.to_rgba8(),
+                wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(4 * size.width),
+                    rows_per_image: Some(size.height),
+                },
+                size,
+            );
+        } else {
+            let texture = Texture::from_image(device, queue, &img, Some("reloaded texture"))?;
+            *self = TextureSlot::new(device, layout, texture);
+        }
+
+        Ok(())
+    }
+}
+
+/// GPU vertex/index buffers for one model
+pub struct Mesh {
+    pub vertex_buffer: wgpu::Buffer,
+    pub index_buffer: wgpu::Buffer,
+    pub num_indices: u32,
+}
+
+impl Mesh {
+    pub fn new(device: &wgpu::Device, vertices: &[Vertex], indices: &[u16]) -> Self {
+        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Vertex Buffer"),
+            contents: bytemuck::cast_slice(vertices),
+            // COPY_DST so reloads can rewrite the buffer in place
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Index Buffer"),
+            contents: bytemuck::cast_slice(indices),
+            usage: wgpu::BufferUsages::INDEX | wgpu::BufferUsages::COPY_DST,
+        });
+
+        Self {
+            vertex_buffer,
+            index_buffer,
+            num_indices: indices.len() as u32,
+        }
+    }
+
+    /// Reload from OBJ source, reusing the buffers when the new data fits
+    pub fn reload(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        source: &str,
+    ) -> Result<()> {
+        let (vertices, indices) = parse_obj(source)?;
+
+        let vertex_bytes: &[u8] = bytemuck::cast_slice(&vertices);
+        // Buffer writes must be a multiple of 4 bytes, so an odd number of
+        // indices is uploaded with two bytes of padding. The padding is
+        // never drawn: `num_indices` counts only the real indices.
+        let mut index_bytes = bytemuck::cast_slice::<u16, u8>(&indices).to_vec();
+        index_bytes.resize(index_bytes.len().next_multiple_of(4), 0);
+
+        if vertex_bytes.len() as u64 <= self.vertex_buffer.size()
+            && index_bytes.len() as u64 <= self.index_buffer.size()
+        {
+            queue.write_buffer(&self.vertex_buffer, 0, vertex_bytes);
+            queue.write_buffer(&self.index_buffer, 0, &index_bytes);
+            self.num_indices = indices.len() as u32;
+        } else {
+            *self = Mesh::new(device, &vertices, &indices);
+        }
+
+        Ok(())
+    }
+}
+
+/// Parse a minimal Wavefront OBJ: `v`, `vt` and polygonal `f` lines.
+///
+/// Faces are fan-triangulated and every face corner becomes its own vertex,
+/// which keeps the parser short at the cost of some duplication.
+pub fn parse_obj(source: &str) -> Result<(Vec<Vertex>, Vec<u16>)> {
+    let mut positions: Vec<[f32; 3]> = Vec::new();
+    let mut tex_coords: Vec<[f32; 2]> = Vec::new();
+    let mut vertices = Vec::new();
+    let mut indices = Vec::new();
+
+    for (line_no, line) in source.lines().enumerate() {
+        let mut parts = line.split_whitespace();
+        match parts.next() {
+            Some("v") => {
+                let v: Vec<f32> = parts.map(str::parse).collect::<Result<_, _>>()?;
+                ensure!(
+                    v.len() >= 3,
+                    "line {}: vertex needs 3 components",
+                    line_no + 1
+                );
+                positions.push([v[0], v[1], v[2]]);
+            }
+            Some("vt") => {
+                let t: Vec<f32> = parts.map(str::parse).collect::<Result<_, _>>()?;
+                ensure!(t.len() >= 2, "line {}: uv needs 2 components", line_no + 1);
+                // OBJ puts v = 0 at the bottom, wgpu at the top
+                tex_coords.push([t[0], 1.0 - t[1]]);
+            }
+            Some("f") => {
+                let mut corners = Vec::new();
+                for corner in parts {
+                    let mut refs = corner.split('/');
+                    let position: usize = refs.next().unwrap_or("").parse()?;
+                    let uv: Option<usize> = match refs.next() {
+                        Some("") | None => None,
+                        Some(s) => Some(s.parse()?),
+                    };
+
+                    let position = *positions
+                        .get(position.wrapping_sub(1))
+                        .ok_or_else(|| anyhow!("line {}: bad vertex index", line_no + 1))?;
+                    let tex_coords = match uv {
+                        Some(i) => *tex_coords
+                            .get(i.wrapping_sub(1))
+                            .ok_or_else(|| anyhow!("line {}: bad uv index", line_no + 1))?,
+                        None => [0.0, 0.0],
+                    };
+
+                    vertices.push(Vertex {
+                        position,
+                        tex_coords,
+                    });
+                    ensure!(
+                        vertices.len() <= u16::MAX as usize,
+                        "model too large for u16 indices"
+                    );
+                    corners.push((vertices.len() - 1) as u16);
+                }
+
+                ensure!(
+                    corners.len() >= 3,
+                    "line {}: face needs 3 corners",
+                    line_no + 1
+                );
+                for i in 1..corners.len() - 1 {
+                    indices.extend_from_slice(&[corners[0], corners[i], corners[i + 1]]);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    ensure!(!indices.is_empty(), "model has no faces");
+    Ok((vertices, indices))
+}
+
+/// Watches a directory and reports which files changed since the last poll
+pub struct AssetWatcher {
+    // Kept alive for as long as we want events
+    _watcher: RecommendedWatcher,
+    events: Receiver<notify::Result<notify::Event>>,
+}
+
+impl AssetWatcher {
+    pub fn new(dir: &Path) -> Result<Self> {
+        let (tx, events) = channel();
+        let mut watcher = notify::recommended_watcher(move |event| {
+            // The receiver only goes away on shutdown
+            let _ = tx.send(event);
+        })?;
+        watcher.watch(dir, RecursiveMode::NonRecursive)?;
+
+        Ok(Self {
+            _watcher: watcher,
+            events,
+        })
+    }
+
+    /// Drain pending events and return each changed file once.
+    ///
+    /// Editors often emit several events per save, so deduplicating here
+    /// avoids reloading the same asset multiple times per frame.
+    pub fn poll_changes(&self) -> Vec<PathBuf> {
+        let mut changed = HashSet::new();
+        for event in self.events.try_iter() {
+            match event {
+                std::result::Result::Ok(event)
+                    if matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) =>
+                {
+                    changed.extend(event.paths);
+                }
+                std::result::Result::Ok(_) => {}
+                Err(e) => log::warn!("watch error: {}", e),
+            }
+        }
+        changed.into_iter().collect()
+    }
+}
@@ -1,5 +1,6 @@
 use anyhow::*;
 use image::GenericImageView;
+use std::path::{Path, PathBuf};
 use std::result::Result::Ok as StdOk;
 use std::sync::Arc;
 use winit::{
@@ -9,6 +10,15 @@ use winit::{
     window::{Window, WindowBuilder},
 };
 
+mod hot_reload;
+
+use hot_reload::{AssetTable, AssetWatcher, Handle, Mesh, TextureSlot};
+
+/// Directory watched for texture and model changes
+const ASSETS_DIR: &str = "assets";
+const TEXTURE_FILE: &str = "texture.png";
+const MODEL_FILE: &str = "quad.obj";
+
 /// Vertex with position and texture coordinates (UVs)
 #[repr(C)]
 #[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
@@ -252,13 +262,14 @@ struct State<'a> {
     config: wgpu::SurfaceConfiguration,
     size: winit::dpi::PhysicalSize<u32>,
     render_pipeline: wgpu::RenderPipeline,
-    vertex_buffer: wgpu::Buffer,
-    index_buffer: wgpu::Buffer,
-    num_indices: u32,
 
-    // Texture and bind group
-    diffuse_texture: Texture,
-    diffuse_bind_group: wgpu::BindGroup,
+    // Assets are referenced through handles so they can be hot-reloaded
+    texture_bind_group_layout: wgpu::BindGroupLayout,
+    textures: AssetTable<TextureSlot>,
+    meshes: AssetTable<Mesh>,
+    diffuse_texture: Handle,
+    quad_mesh: Handle,
+    watcher: Option<AssetWatcher>,
 }
 
 impl<'a> State<'a> {
@@ -315,8 +326,9 @@ impl<'a> State<'a> {
         surface.configure(&device, &config);
 
         // Try to load texture from file, fall back to test texture
-        let diffuse_texture = match std::fs::read("assets/texture.png") {
-            StdOk(bytes) => Texture::from_bytes(&device, &queue, &bytes, "texture.png")?,
+        let texture_path = asset_path(TEXTURE_FILE);
+        let diffuse_texture = match std::fs::read(&texture_path) {
+            StdOk(bytes) => Texture::from_bytes(&device, &queue, &bytes, TEXTURE_FILE)?,
             Err(_) => {
                 log::warn!("Could not load texture.png, using test pattern");
                 Texture::create_test_texture(&device, &queue)?
@@ -351,40 +363,37 @@ impl<'a> State<'a> {
             });
 
         // Create bind group
-        // This is the actual binding of resources to the layout
-        let diffuse_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            layout: &texture_bind_group_layout,
-            entries: &[
-                wgpu::BindGroupEntry {
-                    binding: 0,
-                    resource: wgpu::BindingResource::TextureView(&diffuse_texture.view),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 1,
-                    resource: wgpu::BindingResource::Sampler(&diffuse_texture.sampler),
-                },
-            ],
-            label: Some("diffuse_bind_group"),
-        });
+        // This is the actual binding of resources to the layout. The slot in
+        // the asset table owns it, so a reload can swap it out.
+        let mut textures = AssetTable::new();
+        let diffuse_texture = textures.insert(
+            Some(texture_path),
+            TextureSlot::new(&device, &texture_bind_group_layout, diffuse_texture),
+        );
 
         let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
             label: Some("Shader"),
             source: wgpu::ShaderSource::Wgsl(include_str!("shader.wgsl").into()),
         });
 
-        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("Vertex Buffer"),
-            contents: bytemuck::cast_slice(VERTICES),
-            usage: wgpu::BufferUsages::VERTEX,
-        });
-
-        let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("Index Buffer"),
-            contents: bytemuck::cast_slice(INDICES),
-            usage: wgpu::BufferUsages::INDEX,
-        });
-
-        let num_indices = INDICES.len() as u32;
+        // Load the quad from the model file if present, otherwise use the built-in one
+        let model_path = asset_path(MODEL_FILE);
+        let mut quad = Mesh::new(&device, VERTICES, INDICES);
+        if let StdOk(source) = std::fs::read_to_string(&model_path) {
+            if let Err(e) = quad.reload(&device, &queue, &source) {
+                log::warn!("Could not load {}: {}", MODEL_FILE, e);
+            }
+        }
+        let mut meshes = AssetTable::new();
+        let quad_mesh = meshes.insert(Some(model_path), quad);
+
+        let watcher = match AssetWatcher::new(&asset_path("")) {
+            StdOk(watcher) => Some(watcher),
+            Err(e) => {
+                log::warn!("Asset hot-reload disabled: {}", e);
+                None
+            }
+        };
 
         // Create pipeline layout with the texture bind group layout
         let render_pipeline_layout =
@@ -437,11 +446,12 @@ impl<'a> State<'a> {
             config,
             size,
             render_pipeline,
-            vertex_buffer,
-            index_buffer,
-            num_indices,
+            texture_bind_group_layout,
+            textures,
+            meshes,
             diffuse_texture,
-            diffuse_bind_group,
+            quad_mesh,
+            watcher,
         })
     }
 
@@ -463,7 +473,41 @@ impl<'a> State<'a> {
         false
     }
 
-    fn update(&mut self) {}
+    fn update(&mut self) {
+        self.reload_changed_assets();
+    }
+
+    /// Re-upload any watched texture or model that changed on disk.
+    ///
+    /// A failed reload (e.g. a half-written file) keeps the previous asset.
+    fn reload_changed_assets(&mut self) {
+        let Some(watcher) = &self.watcher else {
+            return;
+        };
+
+        for path in watcher.poll_changes() {
+            if let Some(handle) = self.textures.find(&path) {
+                let result = std::fs::read(&path).map_err(Error::from).and_then(|bytes| {
+                    self.textures.get_mut(handle).reload(
+                        &self.device,
+                        &self.queue,
+                        &self.texture_bind_group_layout,
+                        &bytes,
+                    )
+                });
+                report_reload(&path, result);
+            } else if let Some(handle) = self.meshes.find(&path) {
+                let result = std::fs::read_to_string(&path)
+                    .map_err(Error::from)
+                    .and_then(|source| {
+                        self.meshes
+                            .get_mut(handle)
+                            .reload(&self.device, &self.queue, &source)
+                    });
+                report_reload(&path, result);
+            }
+        }
+    }
 
     fn render(&mut self) -> Result<(), wgpu::SurfaceError> {
         let output = self.surface.get_current_texture()?;
@@ -502,11 +546,13 @@ impl<'a> State<'a> {
 
             // Bind the texture bind group before drawing
             // Group 0 corresponds to set = 0 in the shader
-            render_pass.set_bind_group(0, &self.diffuse_bind_group, &[]);
+            let texture = self.textures.get(self.diffuse_texture);
+            render_pass.set_bind_group(0, &texture.bind_group, &[]);
 
-            render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
-            render_pass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
-            render_pass.draw_indexed(0..self.num_indices, 0, 0..1);
+            let mesh = self.meshes.get(self.quad_mesh);
+            render_pass.set_vertex_buffer(0, mesh.vertex_buffer.slice(..));
+            render_pass.set_index_buffer(mesh.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
+            render_pass.draw_indexed(0..mesh.num_indices, 0, 0..1);
         }
 
         self.queue.submit(std::iter::once(encoder.finish()));
@@ -516,7 +562,22 @@ impl<'a> State<'a> {
     }
 }
 
-use wgpu::util::DeviceExt;
+/// Absolute path of a file in the assets directory (matches watcher event paths)
+fn asset_path(file: &str) -> PathBuf {
+    let dir = std::env::current_dir().unwrap_or_default().join(ASSETS_DIR);
+    if file.is_empty() {
+        dir
+    } else {
+        dir.join(file)
+    }
+}
+
+fn report_reload(path: &Path, result: Result<()>) {
+    match result {
+        StdOk(()) => log::info!("Reloaded {}", path.display()),
+        Err(e) => log::warn!("Failed to reload {}: {}", path.display(), e),
+    }
+}
 
 fn main() -> Result<()> {
     env_logger::init();
@@ -533,35 +594,31 @@ fn main() -> Result<()> {
         Event::WindowEvent {
             ref event,
             window_id,
-        } if window_id == state.window().id() => {
-            if !state.input(event) {
-                match event {
-                    WindowEvent::CloseRequested
-                    | WindowEvent::KeyboardInput {
-                        event:
-                            KeyEvent {
-                                state: ElementState::Pressed,
-                                logical_key: Key::Named(NamedKey::Escape),
-                                ..
-                            },
+        } if window_id == state.window().id() && !state.input(event) => match event {
+            WindowEvent::CloseRequested
+            | WindowEvent::KeyboardInput {
+                event:
+                    KeyEvent {
+                        state: ElementState::Pressed,
+                        logical_key: Key::Named(NamedKey::Escape),
                         ..
-                    } => elwt.exit(),
-                    WindowEvent::Resized(physical_size) => {
-                        state.resize(*physical_size);
-                    }
-                    WindowEvent::RedrawRequested => {
-                        state.update();
-                        match state.render() {
-                            StdOk(_) => {}
-                            Err(wgpu::SurfaceError::Lost) => state.resize(state.size),
-                            Err(wgpu::SurfaceError::OutOfMemory) => elwt.exit(),
-                            Err(e) => eprintln!("{:?}", e),
-                        }
-                    }
-                    _ => {}
+                    },
+                ..
+            } => elwt.exit(),
+            WindowEvent::Resized(physical_size) => {
+                state.resize(*physical_size);
+            }
+            WindowEvent::RedrawRequested => {
+                state.update();
+                match state.render() {
+                    StdOk(_) => {}
+                    Err(wgpu::SurfaceError::Lost) => state.resize(state.size),
+                    Err(wgpu::SurfaceError::OutOfMemory) => elwt.exit(),
+                    Err(e) => eprintln!("{:?}", e),
                 }
             }
-        }
+            _ => {}
+        },
         Event::AboutToWait => {
             state.window().request_redraw();
         }
@@ -0,0 +1,270 @@
+//! Keyframe animation.
+//!
+//! A [`Clip`] is a set of tracks, one each for position, rotation and
+//! scale, that give a value at a handful of times. In between, the track
+//! interpolates either in straight lines or along a smooth cubic curve.
+//! Channels a clip leaves out stay at the rest pose.
+//!
+//! An [`Animator`] plays one clip at a time. Switching clips doesn't snap:
+//! for a moment both are sampled and their poses blended, the old one
+//! fading out as the new one fades in.
+//!
+//! Everything is a function of time, so the animator only keeps a clock.
+//! It's stepped with the rest of the simulation and sampled at the
+//! frame's interpolated time, which gives smooth motion at any refresh
+//! rate without interpolating any of the poses themselves.
+
+use cgmath::Vector3;
+
+use common::timestep::{Interpolated, Lerp};
+
+/// How a track fills in the values between its keyframes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Interpolation {
+    /// A straight line from one keyframe to the next; corners at every key
+    Linear,
+    /// A cubic Hermite curve with Catmull-Rom tangents: it passes through
+    /// every keyframe, heading from the one before towards the one after
+    Cubic,
+}
+
+/// What a clip does after its last keyframe
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoopMode {
+    /// Hold the last pose
+    Once,
+    /// Start again from the beginning
+    Loop,
+    /// Play backwards to the beginning, then forwards again
+    PingPong,
+}
+
+/// Values of one channel over time
+#[derive(Debug, Clone)]
+pub struct Track {
+    /// `(time, value)` pairs in order of time
+    keyframes: Vec<(f32, Vector3<f32>)>,
+    interpolation: Interpolation,
+}
+
+impl Track {
+    /// # Panics
+    ///
+    /// If there are no keyframes or their times aren't increasing.
+    pub fn new(interpolation: Interpolation, keyframes: Vec<(f32, Vector3<f32>)>) -> Self {
+        assert!(!keyframes.is_empty(), "a track needs keyframes");
+        assert!(
+            keyframes.windows(2).all(|pair| pair[0].0 < pair[1].0),
+            "keyframe times must increase"
+        );
+        Self {
+            keyframes,
+            interpolation,
+        }
+    }
+
+    /// Time of the last keyframe
+    pub fn duration(&self) -> f32 {
+        self.keyframes[self.keyframes.len() - 1].0
+    }
+
+    /// The value at `time`, held at the first or last keyframe outside
+    /// their range
+    pub fn sample(&self, time: f32) -> Vector3<f32> {
+        let keys = &self.keyframes;
+        // Index of the first keyframe after `time`
+        let next = keys.partition_point(|&(key_time, _)| key_time <= time);
+        if next == 0 {
+            return keys[0].1;
+        }
+        if next == keys.len() {
+            return keys[keys.len() - 1].1;
+        }
+        let (t0, p0) = keys[next - 1];
+        let (t1, p1) = keys[next];
+        let span = t1 - t0;
+        let u = (time - t0) / span;
+        match self.interpolation {
+            Interpolation::Linear => p0.lerp(p1, u),
+            Interpolation::Cubic => {
+                let m0 = self.tangent(next - 1);
+                let m1 = self.tangent(next);
+                let (u2, u3) = (u * u, u * u * u);
+                // Hermite basis: each weight is 1 for what it controls at
+                // its own end of the segment and 0 for everything else
+                p0 * (2.0 * u3 - 3.0 * u2 + 1.0)
+                    + m0 * (span * (u3 - 2.0 * u2 + u))
+                    + p1 * (3.0 * u2 - 2.0 * u3)
+                    + m1 * (span * (u3 - u2))
+            }
+        }
+    }
+
+    /// Rate of change at keyframe `index`: the slope from its neighbours,
+    /// or from itself at either end
+    fn tangent(&self, index: usize) -> Vector3<f32> {
+        let keys = &self.keyframes;
+        let (t0, p0) = keys[index.saturating_sub(1)];
+        let (t1, p1) = keys[(index + 1).min(keys.len() - 1)];
+        if t1 > t0 {
+            (p1 - p0) / (t1 - t0)
+        } else {
+            Vector3::new(0.0, 0.0, 0.0)
+        }
+    }
+}
+
+/// A transform's position, rotation (Euler angles in radians, as in
+/// `Transform`) and scale
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Pose {
+    pub position: Vector3<f32>,
+    pub rotation: Vector3<f32>,
+    pub scale: Vector3<f32>,
+}
+
+impl Lerp for Pose {
+    fn lerp(self, other: Self, t: f32) -> Self {
+        Self {
+            position: self.position.lerp(other.position, t),
+            rotation: self.rotation.lerp(other.rotation, t),
+            scale: self.scale.lerp(other.scale, t),
+        }
+    }
+}
+
+/// A named animation made of up to one track per channel
+#[derive(Debug, Clone)]
+pub struct Clip {
+    pub name: &'static str,
+    pub position: Option<Track>,
+    pub rotation: Option<Track>,
+    pub scale: Option<Track>,
+    pub loop_mode: LoopMode,
+}
+
+impl Clip {
+    /// Length of one pass, up to the last keyframe of the longest track
+    pub fn duration(&self) -> f32 {
+        [&self.position, &self.rotation, &self.scale]
+            .into_iter()
+            .flatten()
+            .map(Track::duration)
+            .fold(0.0, f32::max)
+    }
+
+    /// The pose `time` seconds after the clip started, with channels the
+    /// clip has no track for taken from `rest`
+    pub fn sample(&self, time: f32, rest: &Pose) -> Pose {
+        let duration = self.duration();
+        let time = if duration <= 0.0 {
+            0.0
+        } else {
+            match self.loop_mode {
+                LoopMode::Once => time.min(duration),
+                LoopMode::Loop => time.rem_euclid(duration),
+                LoopMode::PingPong => {
+                    let time = time.rem_euclid(2.0 * duration);
+                    if time > duration {
+                        2.0 * duration - time
+                    } else {
+                        time
+                    }
+                }
+            }
+        };
+        let channel =
+            |track: &Option<Track>, rest| track.as_ref().map_or(rest, |track| track.sample(time));
+        Pose {
+            position: channel(&self.position, rest.position),
+            rotation: channel(&self.rotation, rest.rotation),
+            scale: channel(&self.scale, rest.scale),
+        }
+    }
+}
+
+/// A clip and the clock time it started at
+#[derive(Debug, Clone, Copy)]
+struct Playing {
+    clip: usize,
+    start: f32,
+}
+
+/// Plays one clip from a list, blending into the next when told to switch
+pub struct Animator {
+    clips: Vec<Clip>,
+    rest: Pose,
+    /// Seconds since the animator was created; every other time is a
+    /// reading of it
+    clock: Interpolated<f32>,
+    current: Playing,
+    /// The clip being faded out, when it started fading and how long the
+    /// fade takes
+    fading: Option<(Playing, f32, f32)>,
+}
+
+impl Animator {
+    /// An animator playing the first of `clips`
+    ///
+    /// # Panics
+    ///
+    /// If `clips` is empty.
+    pub fn new(rest: Pose, clips: Vec<Clip>) -> Self {
+        assert!(!clips.is_empty(), "an animator needs clips");
+        Self {
+            clips,
+            rest,
+            clock: Interpolated::new(0.0),
+            current: Playing {
+                clip: 0,
+                start: 0.0,
+            },
+            fading: None,
+        }
+    }
+
+    /// Switch to clip `index` from its start, blending out of the current
+    /// one over `blend` seconds (0 cuts straight to it)
+    pub fn play(&mut self, index: usize, blend: f32) {
+        let now = self.clock.at(1.0);
+        let previous = self.current;
+        self.current = Playing {
+            clip: index % self.clips.len(),
+            start: now,
+        };
+        self.fading = (blend > 0.0).then_some((previous, now, blend));
+    }
+
+    /// Index of the clip playing, or being blended into
+    pub fn current(&self) -> usize {
+        self.current.clip
+    }
+
+    pub fn clips(&self) -> &[Clip] {
+        &self.clips
+    }
+
+    /// Advance by one simulation step of `dt` seconds
+    pub fn update(&mut self, dt: f32) {
+        self.clock.step(|time| *time += dt);
+        let now = self.clock.at(1.0);
+        if matches!(self.fading, Some((_, start, blend)) if now - start >= blend) {
+            self.fading = None;
+        }
+    }
+
+    /// The pose to render at `alpha` between the last two simulation steps
+    pub fn pose(&self, alpha: f32) -> Pose {
+        let now = self.clock.at(alpha);
+        let sample =
+            |playing: Playing| self.clips[playing.clip].sample(now - playing.start, &self.rest);
+        let pose = sample(self.current);
+        match self.fading {
+            Some((previous, start, blend)) => {
+                let weight = ((now - start) / blend).clamp(0.0, 1.0);
+                sample(previous).lerp(pose, weight)
+            }
+            None => pose,
+        }
+    }
+}
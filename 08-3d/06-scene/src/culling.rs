@@ -0,0 +1,150 @@
+//! View frustum culling.
+//!
+//! The camera sees a truncated pyramid, the frustum, bounded by six
+//! planes. Anything entirely outside one of them can't show up on screen,
+//! so it can be skipped before it costs a draw call, an instance slot or a
+//! trip through the vertex shader.
+//!
+//! Testing the real mesh would cost as much as drawing it, so each object
+//! is stood in for by a simple shape around it: an axis-aligned bounding
+//! box (AABB) around the mesh, carried into the world by the object's
+//! matrix, or a bounding sphere where that's tight enough. The tests are
+//! conservative: a shape that only slightly overlaps the frustum, or sits
+//! just outside a corner of it, is kept. Drawing a few invisible objects is
+//! harmless; culling a visible one leaves a hole.
+
+use cgmath::prelude::*;
+use cgmath::{Matrix4, Vector3, Vector4};
+
+/// An axis-aligned box, `min` to `max` on every axis
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Aabb {
+    pub min: Vector3<f32>,
+    pub max: Vector3<f32>,
+}
+
+impl Aabb {
+    /// The smallest box around `positions`, or an empty one at the origin
+    /// if there are none
+    pub fn from_positions(positions: impl IntoIterator<Item = [f32; 3]>) -> Self {
+        let mut positions = positions.into_iter().map(Vector3::from);
+        let Some(first) = positions.next() else {
+            return Self {
+                min: Vector3::zero(),
+                max: Vector3::zero(),
+            };
+        };
+        positions.fold(
+            Self {
+                min: first,
+                max: first,
+            },
+            |bounds, position| Self {
+                min: bounds.min.zip(position, f32::min),
+                max: bounds.max.zip(position, f32::max),
+            },
+        )
+    }
+
+    /// The center and radius of the sphere around the box's center that
+    /// just contains it
+    pub fn bounding_sphere(&self) -> (Vector3<f32>, f32) {
+        (
+            (self.min + self.max) / 2.0,
+            (self.max - self.min).magnitude() / 2.0,
+        )
+    }
+
+    /// The box around this one after `matrix` transforms it
+    ///
+    /// Rather than transforming all eight corners, each axis of the new
+    /// box is built from the matrix's columns: a column scaled by the old
+    /// box's extent on that axis moves the new min by its negative parts
+    /// and the new max by its positive ones (Arvo, Graphics Gems, 1990).
+    pub fn transform(&self, matrix: &Matrix4<f32>) -> Self {
+        let translation = matrix.w.truncate();
+        let mut min = translation;
+        let mut max = translation;
+        for (axis, column) in [matrix.x, matrix.y, matrix.z].iter().enumerate() {
+            let a = column.truncate() * self.min[axis];
+            let b = column.truncate() * self.max[axis];
+            min += a.zip(b, f32::min);
+            max += a.zip(b, f32::max);
+        }
+        Self { min, max }
+    }
+}
+
+/// The six planes around what a view-projection matrix can see
+#[derive(Debug, Clone, Copy)]
+pub struct Frustum {
+    /// `(a, b, c, d)` with the normal `(a, b, c)` pointing inwards: a
+    /// point is on the inside when `a x + b y + c z + d >= 0`
+    planes: [Vector4<f32>; 6],
+}
+
+impl Frustum {
+    /// The planes of `view_proj`, read straight off its rows (Gribb and
+    /// Hartmann's method)
+    ///
+    /// A point is kept by the GPU when its clip coordinates satisfy
+    /// `-w <= x <= w`, `-w <= y <= w` and `0 <= z <= w`. Each clip
+    /// coordinate is a row of the matrix dotted with the point, so each
+    /// inequality, such as `w + x >= 0`, is a sum or difference of two
+    /// rows: the coefficients of a plane.
+    pub fn from_view_proj(view_proj: &Matrix4<f32>) -> Self {
+        let row = |index| view_proj.row(index);
+        let planes = [
+            row(3) + row(0), // left
+            row(3) - row(0), // right
+            row(3) + row(1), // bottom
+            row(3) - row(1), // top
+            row(2),          // near
+            row(3) - row(2), // far
+        ]
+        .map(|plane| plane / plane.truncate().magnitude());
+        Self { planes }
+    }
+
+    /// Whether any of the sphere might be inside
+    pub fn intersects_sphere(&self, center: Vector3<f32>, radius: f32) -> bool {
+        self.planes
+            .iter()
+            .all(|plane| plane.truncate().dot(center) + plane.w >= -radius)
+    }
+
+    /// Whether any of the box might be inside
+    ///
+    /// For each plane only the corner furthest along its normal matters: if
+    /// even that one is outside, the whole box is.
+    pub fn intersects_aabb(&self, aabb: &Aabb) -> bool {
+        self.planes.iter().all(|plane| {
+            let normal = plane.truncate();
+            let furthest = Vector3::new(
+                if normal.x >= 0.0 {
+                    aabb.max.x
+                } else {
+                    aabb.min.x
+                },
+                if normal.y >= 0.0 {
+                    aabb.max.y
+                } else {
+                    aabb.min.y
+                },
+                if normal.z >= 0.0 {
+                    aabb.max.z
+                } else {
+                    aabb.min.z
+                },
+            );
+            normal.dot(furthest) + plane.w >= 0.0
+        })
+    }
+}
+
+/// How many objects a frame drew and how many it culled
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CullStats {
+    pub drawn: usize,
+    pub culled: usize,
+}
@@ -0,0 +1,160 @@
+//! glTF 2.0 scene loading.
+//!
+//! glTF is the standard format for handing 3D scenes between tools: a JSON
+//! document describes a node hierarchy, meshes and materials, and binary
+//! buffers (embedded as base64, or in a `.bin`/`.glb`) hold the vertex data.
+//!
+//! This flattens the default scene into one entry per drawn primitive:
+//!
+//! - every primitive becomes a [`Mesh`] (positions, normals, first
+//!   texture coordinate set, indices)
+//! - every node that uses a mesh becomes a [`GltfObject`] with its world
+//!   transform (parent transforms applied) and its material's base color
+//!
+//! Textures, skins, animations, cameras and lights are ignored.
+
+use cgmath::prelude::*;
+use cgmath::{Euler, Matrix3, Matrix4, Quaternion, Vector3};
+use std::path::Path;
+
+use crate::meshes::Mesh;
+
+/// One drawable primitive placed in the world
+#[derive(Debug, Clone)]
+pub struct GltfObject {
+    pub name: String,
+    /// Index into [`GltfScene::meshes`]
+    pub mesh: usize,
+    pub translation: Vector3<f32>,
+    /// Euler angles in radians, applied X then Y then Z like
+    /// `scene_graph::Transform::matrix` does
+    pub rotation: Vector3<f32>,
+    pub scale: Vector3<f32>,
+    /// `baseColorFactor`, RGBA
+    pub base_color: [f32; 4],
+    pub metallic: f32,
+    pub roughness: f32,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct GltfScene {
+    pub meshes: Vec<Mesh>,
+    pub objects: Vec<GltfObject>,
+}
+
+#[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+pub fn load(path: impl AsRef<Path>) -> Result<GltfScene, gltf::Error> {
+    let (document, buffers, _images) = gltf::import(path)?;
+    Ok(flatten(&document, &buffers))
+}
+
+/// Read a scene from the contents of a `.gltf` or `.glb` file
+///
+/// There's no directory to look for other files in, so buffers that aren't
+/// embedded in the file (as base64 data URIs, or in the `.glb`) can't be
+/// loaded.
+#[cfg_attr(not(target_arch = "wasm32"), allow(dead_code))]
+pub fn parse(bytes: &[u8]) -> Result<GltfScene, gltf::Error> {
+    let (document, buffers, _images) = gltf::import_slice(bytes)?;
+    Ok(flatten(&document, &buffers))
+}
+
+fn flatten(document: &gltf::Document, buffers: &[gltf::buffer::Data]) -> GltfScene {
+    // Meshes are shared between nodes, so convert each one once;
+    // `mesh_primitives[mesh]` lists the GltfScene meshes for its primitives
+    // along with their materials
+    let mut scene = GltfScene::default();
+    let mut mesh_primitives = Vec::new();
+    for mesh in document.meshes() {
+        let mut primitives = Vec::new();
+        for primitive in mesh.primitives() {
+            if primitive.mode() != gltf::mesh::Mode::Triangles {
+                log::warn!(
+                    "skipping {:?} primitive in mesh {:?}",
+                    primitive.mode(),
+                    mesh.name()
+                );
+                continue;
+            }
+            let reader = primitive.reader(|buffer| Some(&buffers[buffer.index()]));
+            let Some(positions) = reader.read_positions() else {
+                continue;
+            };
+            let positions: Vec<[f32; 3]> = positions.collect();
+            let indices = match reader.read_indices() {
+                Some(indices) => indices.into_u32().collect(),
+                None => (0..positions.len() as u32).collect(),
+            };
+            let normals = match reader.read_normals() {
+                Some(normals) => normals.collect(),
+                None => vec![[0.0; 3]; positions.len()],
+            };
+            let uvs = match reader.read_tex_coords(0) {
+                Some(uvs) => uvs.into_f32().collect(),
+                None => vec![[0.0; 2]; positions.len()],
+            };
+            let mut data = Mesh {
+                positions,
+                normals,
+                uvs,
+                indices,
+            };
+            data.compute_normals();
+
+            primitives.push((scene.meshes.len(), primitive.material()));
+            scene.meshes.push(data);
+        }
+        mesh_primitives.push(primitives);
+    }
+
+    let Some(root) = document
+        .default_scene()
+        .or_else(|| document.scenes().next())
+    else {
+        return scene;
+    };
+    let mut pending: Vec<(gltf::Node, Matrix4<f32>)> = root
+        .nodes()
+        .map(|node| (node, Matrix4::identity()))
+        .collect();
+    while let Some((node, parent)) = pending.pop() {
+        let world = parent * Matrix4::from(node.transform().matrix());
+        if let Some(mesh) = node.mesh() {
+            let (translation, rotation, scale) = decompose(world);
+            for (index, material) in &mesh_primitives[mesh.index()] {
+                let pbr = material.pbr_metallic_roughness();
+                scene.objects.push(GltfObject {
+                    name: node.name().unwrap_or("unnamed").to_string(),
+                    mesh: *index,
+                    translation,
+                    rotation,
+                    scale,
+                    base_color: pbr.base_color_factor(),
+                    metallic: pbr.metallic_factor(),
+                    roughness: pbr.roughness_factor(),
+                });
+            }
+        }
+        pending.extend(node.children().map(|child| (child, world)));
+    }
+    scene
+}
+
+/// Split a world matrix back into translation, rotation and scale
+///
+/// A scene graph `Transform` is stored that way rather than as a matrix.
+/// This is exact for the usual TRS hierarchies; a rotated child of a
+/// non-uniformly scaled parent is sheared, which TRS can't represent and
+/// which comes out approximated.
+fn decompose(world: Matrix4<f32>) -> (Vector3<f32>, Vector3<f32>, Vector3<f32>) {
+    let translation = world.w.truncate();
+    let (x, y, z) = (world.x.truncate(), world.y.truncate(), world.z.truncate());
+    let scale = Vector3::new(x.magnitude(), y.magnitude(), z.magnitude());
+    let rotation = Matrix3::from_cols(x / scale.x, y / scale.y, z / scale.z);
+    let euler = Euler::from(Quaternion::from(rotation));
+    (
+        translation,
+        Vector3::new(euler.x.0, euler.y.0, euler.z.0),
+        scale,
+    )
+}
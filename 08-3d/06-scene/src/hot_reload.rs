@@ -0,0 +1,95 @@
+//! Reloading the WGSL shaders while the example runs.
+//!
+//! `include_str!` compiles the shaders into the binary, so trying a change
+//! to the lighting normally means rebuilding and restarting. Instead, a
+//! [`ShaderWatcher`] asks the operating system (through the notify crate)
+//! to report writes to the `.wgsl` files in `src/`. Once a frame, `State`
+//! reads every file that changed, compiles it and rebuilds the pipelines
+//! that use it.
+//!
+//! A typo in a shader shouldn't take the example down. Without an error
+//! scope, wgpu hands validation errors to the device's uncaptured error
+//! handler, which panics. [`validated`] opens a scope around the new
+//! module and its pipelines instead, so an error comes back as a value.
+//! The caller logs it and keeps the pipelines built from the previous
+//! version until the file is saved again.
+
+use std::collections::BTreeSet;
+use std::path::Path;
+use std::sync::mpsc;
+
+use notify::{RecursiveMode, Watcher};
+
+/// Reports `.wgsl` files in one directory that have been written
+pub struct ShaderWatcher {
+    /// Stops watching when dropped
+    _watcher: notify::RecommendedWatcher,
+    events: mpsc::Receiver<notify::Result<notify::Event>>,
+}
+
+impl ShaderWatcher {
+    pub fn new(dir: &Path) -> notify::Result<Self> {
+        let (sender, events) = mpsc::channel();
+        // The callback runs on notify's own thread
+        let mut watcher = notify::recommended_watcher(move |event| {
+            // The receiver only goes away along with the watcher
+            let _ = sender.send(event);
+        })?;
+        watcher.watch(dir, RecursiveMode::NonRecursive)?;
+        Ok(Self {
+            _watcher: watcher,
+            events,
+        })
+    }
+
+    /// File names (like `shader.wgsl`) of the shaders written since the
+    /// last call
+    ///
+    /// Editors often write a file in several steps, or save it by
+    /// replacing it with a new one, so each name is reported once however
+    /// many events it got.
+    pub fn changed(&self) -> BTreeSet<String> {
+        let mut names = BTreeSet::new();
+        for event in self.events.try_iter() {
+            let event = match event {
+                Ok(event) => event,
+                Err(e) => {
+                    log::warn!("shader watcher: {}", e);
+                    continue;
+                }
+            };
+            if !(event.kind.is_modify() || event.kind.is_create()) {
+                continue;
+            }
+            for path in &event.paths {
+                if path
+                    .extension()
+                    .is_some_and(|extension| extension == "wgsl")
+                {
+                    if let Some(name) = path.file_name() {
+                        names.insert(name.to_string_lossy().into_owned());
+                    }
+                }
+            }
+        }
+        names
+    }
+}
+
+/// Run `build`, returning what it created only if it caused no validation
+/// errors, or else the first error's description
+///
+/// Objects created with errors are still returned by wgpu, but using them
+/// is an error too, so they're dropped here. The description is what
+/// makes the error useful: for a shader it holds naga's report, with the
+/// offending line underlined.
+pub fn validated<T>(device: &wgpu::Device, build: impl FnOnce() -> T) -> Result<T, String> {
+    device.push_error_scope(wgpu::ErrorFilter::Validation);
+    let value = build();
+    // Native devices report errors as they happen, so this is ready at once
+    match pollster::block_on(device.pop_error_scope()) {
+        Some(wgpu::Error::Validation { description, .. }) => Err(description),
+        Some(error) => Err(error.to_string()),
+        None => Ok(value),
+    }
+}
@@ -0,0 +1,161 @@
+//! Scene lights: directional, point and spot.
+//!
+//! All lights share one GPU layout ([`GpuLight`]) with a `kind` tag, so the
+//! shader can keep them in a single fixed-size array and loop over the
+//! first `count` entries, adding each one's contribution:
+//!
+//! - **Directional**: parallel rays from far away (the sun); no position,
+//!   no falloff
+//! - **Point**: shines in every direction from a position and fades to
+//!   zero at `range`
+//! - **Spot**: a point light limited to a cone, full strength inside
+//!   `inner_angle` and fading out towards `outer_angle`
+//!
+//! The array lives in a uniform buffer rather than a storage buffer, which
+//! caps it at [`MAX_LIGHTS`] but also works on WebGL2.
+
+use cgmath::{Deg, InnerSpace, Rad, Vector3};
+
+/// Size of the light array in the shader (`MAX_LIGHTS` in shader.wgsl)
+pub const MAX_LIGHTS: usize = 8;
+
+const KIND_DIRECTIONAL: u32 = 0;
+const KIND_POINT: u32 = 1;
+const KIND_SPOT: u32 = 2;
+
+#[derive(Debug, Clone, Copy)]
+pub enum Light {
+    Directional {
+        /// The direction the light travels in
+        direction: Vector3<f32>,
+        color: [f32; 3],
+        intensity: f32,
+    },
+    Point {
+        position: Vector3<f32>,
+        color: [f32; 3],
+        intensity: f32,
+        /// Distance at which the light has faded out completely
+        range: f32,
+    },
+    Spot {
+        position: Vector3<f32>,
+        /// The direction the cone points in
+        direction: Vector3<f32>,
+        color: [f32; 3],
+        intensity: f32,
+        range: f32,
+        /// Half-angle of the fully lit part of the cone
+        inner_angle: Deg<f32>,
+        /// Half-angle where the light reaches zero
+        outer_angle: Deg<f32>,
+    },
+}
+
+/// One light as the shader sees it
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct GpuLight {
+    position: [f32; 3],
+    kind: u32,
+    direction: [f32; 3],
+    range: f32,
+    color: [f32; 3],
+    intensity: f32,
+    /// Cosines of the spot cone's half-angles; comparing cosines saves an
+    /// `acos` per fragment
+    inner_cos: f32,
+    outer_cos: f32,
+    _padding: [f32; 2],
+}
+
+impl Light {
+    fn to_gpu(self) -> GpuLight {
+        let mut gpu = GpuLight {
+            position: [0.0; 3],
+            kind: KIND_DIRECTIONAL,
+            direction: [0.0, -1.0, 0.0],
+            range: 0.0,
+            color: [1.0; 3],
+            intensity: 1.0,
+            inner_cos: 0.0,
+            outer_cos: 0.0,
+            _padding: [0.0; 2],
+        };
+        match self {
+            Light::Directional {
+                direction,
+                color,
+                intensity,
+            } => {
+                gpu.direction = direction.normalize().into();
+                gpu.color = color;
+                gpu.intensity = intensity;
+            }
+            Light::Point {
+                position,
+                color,
+                intensity,
+                range,
+            } => {
+                gpu.kind = KIND_POINT;
+                gpu.position = position.into();
+                gpu.color = color;
+                gpu.intensity = intensity;
+                gpu.range = range;
+            }
+            Light::Spot {
+                position,
+                direction,
+                color,
+                intensity,
+                range,
+                inner_angle,
+                outer_angle,
+            } => {
+                gpu.kind = KIND_SPOT;
+                gpu.position = position.into();
+                gpu.direction = direction.normalize().into();
+                gpu.color = color;
+                gpu.intensity = intensity;
+                gpu.range = range;
+                gpu.inner_cos = Rad::from(inner_angle).0.cos();
+                gpu.outer_cos = Rad::from(outer_angle).0.cos();
+            }
+        }
+        gpu
+    }
+}
+
+/// The `lights` uniform in shader.wgsl
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct LightsUniform {
+    /// Light that reaches every surface, scaled by each material's
+    /// `ambient`
+    ambient: [f32; 3],
+    count: u32,
+    lights: [GpuLight; MAX_LIGHTS],
+}
+
+impl LightsUniform {
+    /// Pack `lights` for upload; anything past `MAX_LIGHTS` is dropped
+    pub fn new(ambient: [f32; 3], lights: &[Light]) -> Self {
+        if lights.len() > MAX_LIGHTS {
+            log::warn!(
+                "{} lights, only the first {} are used",
+                lights.len(),
+                MAX_LIGHTS
+            );
+        }
+        let mut uniform = Self {
+            ambient,
+            count: lights.len().min(MAX_LIGHTS) as u32,
+            lights: [bytemuck::Zeroable::zeroed(); MAX_LIGHTS],
+        };
+        for (slot, light) in uniform.lights.iter_mut().zip(lights) {
+            *slot = light.to_gpu();
+        }
+        uniform
+    }
+}
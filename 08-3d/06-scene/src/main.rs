@@ -1,16 +1,59 @@
 use cgmath::prelude::*;
+use common::camera_controller::{Camera, CameraController};
+use common::msaa::{self, RenderTargets};
+use common::renderer::{PipelineBuilder, RenderPassBuilder, UniformBlock};
+use common::timestep::{FixedTimestep, Interpolated, STEPS_PER_SECOND};
+use common::uniform_slots::UniformSlots;
+use std::sync::Arc;
 use winit::{
-    dpi::PhysicalPosition,
     event::*,
-    event_loop::{ControlFlow, EventLoop},
+    event_loop::{EventLoop, EventLoopWindowTarget},
+    keyboard::{Key, KeyCode, NamedKey, PhysicalKey},
     window::{Window, WindowBuilder},
 };
 
+mod animation;
+mod culling;
+mod gltf_loader;
+#[cfg(not(target_arch = "wasm32"))]
+mod hot_reload;
+mod lights;
+mod meshes;
+mod obj;
+mod picking;
+mod postprocess;
+mod scene_file;
+mod scene_graph;
+mod screenshot;
+mod texture;
+#[cfg(target_arch = "wasm32")]
+mod web;
+
+use animation::{Animator, Clip, Interpolation, LoopMode, Pose, Track};
+use culling::{Aabb, CullStats, Frustum};
+use gltf_loader::GltfScene;
+#[cfg(not(target_arch = "wasm32"))]
+use hot_reload::ShaderWatcher;
+use lights::LightsUniform;
+use meshes::Mesh;
+use picking::Ray;
+use postprocess::{PostProcessor, HDR_FORMAT};
+use scene_file::{MeshDesc, SceneFile, SceneFileError, ShadingDesc, TextureDesc};
+use scene_graph::{NodeId, SceneGraph, Transform};
+use screenshot::Screenshot;
+use texture::Texture;
+
 #[repr(C)]
 #[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
 struct Vertex {
     position: [f32; 3],
     normal: [f32; 3],
+    /// Texture coordinates, (0, 0) at the top left of the image
+    uv: [f32; 2],
+    /// Direction of increasing u along the surface; `w` is +1 or -1 and
+    /// says which way the bitangent (up the texture) points relative to
+    /// `cross(normal, tangent)`. Filled in by `compute_tangents`.
+    tangent: [f32; 4],
 }
 
 impl Vertex {
@@ -29,214 +72,112 @@ impl Vertex {
                     shader_location: 1,
                     format: wgpu::VertexFormat::Float32x3,
                 },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 6]>() as wgpu::BufferAddress,
+                    shader_location: 2,
+                    format: wgpu::VertexFormat::Float32x2,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 8]>() as wgpu::BufferAddress,
+                    shader_location: 3,
+                    format: wgpu::VertexFormat::Float32x4,
+                },
             ],
         }
     }
 }
 
-// Cube geometry
-fn create_cube() -> (Vec<Vertex>, Vec<u16>) {
-    let vertices = vec![
-        // Front
-        Vertex {
-            position: [-0.5, -0.5, 0.5],
-            normal: [0.0, 0.0, 1.0],
-        },
-        Vertex {
-            position: [0.5, -0.5, 0.5],
-            normal: [0.0, 0.0, 1.0],
-        },
-        Vertex {
-            position: [0.5, 0.5, 0.5],
-            normal: [0.0, 0.0, 1.0],
-        },
-        Vertex {
-            position: [-0.5, 0.5, 0.5],
-            normal: [0.0, 0.0, 1.0],
-        },
-        // Back
-        Vertex {
-            position: [0.5, -0.5, -0.5],
-            normal: [0.0, 0.0, -1.0],
-        },
-        Vertex {
-            position: [-0.5, -0.5, -0.5],
-            normal: [0.0, 0.0, -1.0],
-        },
-        Vertex {
-            position: [-0.5, 0.5, -0.5],
-            normal: [0.0, 0.0, -1.0],
-        },
-        Vertex {
-            position: [0.5, 0.5, -0.5],
-            normal: [0.0, 0.0, -1.0],
-        },
-        // Right
-        Vertex {
-            position: [0.5, -0.5, 0.5],
-            normal: [1.0, 0.0, 0.0],
-        },
-        Vertex {
-            position: [0.5, -0.5, -0.5],
-            normal: [1.0, 0.0, 0.0],
-        },
-        Vertex {
-            position: [0.5, 0.5, -0.5],
-            normal: [1.0, 0.0, 0.0],
-        },
-        Vertex {
-            position: [0.5, 0.5, 0.5],
-            normal: [1.0, 0.0, 0.0],
-        },
-        // Left
-        Vertex {
-            position: [-0.5, -0.5, -0.5],
-            normal: [-1.0, 0.0, 0.0],
-        },
-        Vertex {
-            position: [-0.5, -0.5, 0.5],
-            normal: [-1.0, 0.0, 0.0],
-        },
-        Vertex {
-            position: [-0.5, 0.5, 0.5],
-            normal: [-1.0, 0.0, 0.0],
-        },
-        Vertex {
-            position: [-0.5, 0.5, -0.5],
-            normal: [-1.0, 0.0, 0.0],
-        },
-        // Top
-        Vertex {
-            position: [-0.5, 0.5, 0.5],
-            normal: [0.0, 1.0, 0.0],
-        },
-        Vertex {
-            position: [0.5, 0.5, 0.5],
-            normal: [0.0, 1.0, 0.0],
-        },
-        Vertex {
-            position: [0.5, 0.5, -0.5],
-            normal: [0.0, 1.0, 0.0],
-        },
-        Vertex {
-            position: [-0.5, 0.5, -0.5],
-            normal: [0.0, 1.0, 0.0],
-        },
-        // Bottom
-        Vertex {
-            position: [-0.5, -0.5, -0.5],
-            normal: [0.0, -1.0, 0.0],
-        },
-        Vertex {
-            position: [0.5, -0.5, -0.5],
-            normal: [0.0, -1.0, 0.0],
-        },
-        Vertex {
-            position: [0.5, -0.5, 0.5],
-            normal: [0.0, -1.0, 0.0],
-        },
-        Vertex {
-            position: [-0.5, -0.5, 0.5],
-            normal: [0.0, -1.0, 0.0],
-        },
-    ];
-
-    let indices = vec![
-        0, 1, 2, 2, 3, 0, 4, 5, 6, 6, 7, 4, 8, 9, 10, 10, 11, 8, 12, 13, 14, 14, 15, 12, 16, 17,
-        18, 18, 19, 16, 20, 21, 22, 22, 23, 20,
-    ];
-
-    (vertices, indices)
-}
-
-// Sphere geometry (icosphere approximation)
-fn create_sphere(subdivisions: u32) -> (Vec<Vertex>, Vec<u16>) {
-    let t = (1.0 + 5.0_f32.sqrt()) / 2.0;
-
-    let mut vertices = vec![
-        Vertex {
-            position: [-1.0, t, 0.0],
-            normal: [0.0, 0.0, 0.0],
-        },
-        Vertex {
-            position: [1.0, t, 0.0],
-            normal: [0.0, 0.0, 0.0],
-        },
-        Vertex {
-            position: [-1.0, -t, 0.0],
-            normal: [0.0, 0.0, 0.0],
-        },
-        Vertex {
-            position: [1.0, -t, 0.0],
-            normal: [0.0, 0.0, 0.0],
-        },
-        Vertex {
-            position: [0.0, -1.0, t],
-            normal: [0.0, 0.0, 0.0],
-        },
-        Vertex {
-            position: [0.0, 1.0, t],
-            normal: [0.0, 0.0, 0.0],
-        },
-        Vertex {
-            position: [0.0, -1.0, -t],
-            normal: [0.0, 0.0, 0.0],
-        },
-        Vertex {
-            position: [0.0, 1.0, -t],
-            normal: [0.0, 0.0, 0.0],
-        },
-        Vertex {
-            position: [t, 0.0, -1.0],
-            normal: [0.0, 0.0, 0.0],
-        },
-        Vertex {
-            position: [t, 0.0, 1.0],
-            normal: [0.0, 0.0, 0.0],
-        },
-        Vertex {
-            position: [-t, 0.0, -1.0],
-            normal: [0.0, 0.0, 0.0],
-        },
-        Vertex {
-            position: [-t, 0.0, 1.0],
-            normal: [0.0, 0.0, 0.0],
-        },
-    ];
-
-    // Normalize positions to create unit sphere and set normals
-    for vertex in &mut vertices {
-        let len =
-            (vertex.position[0].powi(2) + vertex.position[1].powi(2) + vertex.position[2].powi(2))
-                .sqrt();
-        vertex.position[0] /= len;
-        vertex.position[1] /= len;
-        vertex.position[2] /= len;
-        vertex.normal = vertex.position;
-    }
-
-    let indices = vec![
-        0, 11, 5, 0, 5, 1, 0, 1, 7, 0, 7, 10, 0, 10, 11, 1, 5, 9, 5, 11, 4, 11, 10, 2, 10, 7, 6, 7,
-        1, 8, 3, 9, 4, 3, 4, 2, 3, 2, 6, 3, 6, 8, 3, 8, 9, 4, 9, 5, 2, 4, 11, 6, 2, 10, 8, 6, 7, 9,
-        8, 1,
-    ];
+/// Fill in every vertex's tangent from its triangles' positions and UVs
+///
+/// Normal maps store directions relative to the surface: x along the
+/// texture's u axis, y up the texture, z straight out. To turn them into
+/// world space the shader needs those axes per vertex. For each triangle,
+/// the edges in 3D and the same edges in UV space give the 3D directions u
+/// and v grow in; those are summed per vertex, then made perpendicular to
+/// the normal.
+fn compute_tangents<I: Copy + Into<u32>>(vertices: &mut [Vertex], indices: &[I]) {
+    let mut tangents = vec![cgmath::Vector3::zero(); vertices.len()];
+    let mut bitangents = vec![cgmath::Vector3::zero(); vertices.len()];
+    for triangle in indices.chunks_exact(3) {
+        let [a, b, c] = [0, 1, 2].map(|i| triangle[i].into() as usize);
+        let p = [a, b, c].map(|i| cgmath::Vector3::from(vertices[i].position));
+        let uv = [a, b, c].map(|i| cgmath::Vector2::from(vertices[i].uv));
+        let (edge1, edge2) = (p[1] - p[0], p[2] - p[0]);
+        let (duv1, duv2) = (uv[1] - uv[0], uv[2] - uv[0]);
+        let det = duv1.x * duv2.y - duv2.x * duv1.y;
+        // No UVs, or UVs squashed to a line: no texture direction to follow
+        if det.abs() < f32::EPSILON {
+            continue;
+        }
+        let tangent = (edge1 * duv2.y - edge2 * duv1.y) / det;
+        // v grows down the image, so up the texture is -dP/dv
+        let bitangent = -(edge2 * duv1.x - edge1 * duv2.x) / det;
+        for i in [a, b, c] {
+            tangents[i] += tangent;
+            bitangents[i] += bitangent;
+        }
+    }
 
-    (vertices, indices)
+    for ((vertex, tangent), bitangent) in vertices.iter_mut().zip(tangents).zip(bitangents) {
+        let normal = cgmath::Vector3::from(vertex.normal);
+        // Gram-Schmidt: remove the part along the normal
+        let mut t = tangent - normal * normal.dot(tangent);
+        if t.magnitude2() < f32::EPSILON {
+            // Any direction in the surface plane will do
+            let axis = if normal.x.abs() < 0.9 {
+                cgmath::Vector3::unit_x()
+            } else {
+                cgmath::Vector3::unit_y()
+            };
+            t = axis - normal * normal.dot(axis);
+        }
+        let t = t.normalize();
+        let w = if normal.cross(t).dot(bitangent) < 0.0 {
+            -1.0
+        } else {
+            1.0
+        };
+        vertex.tangent = [t.x, t.y, t.z, w];
+    }
 }
 
 /// Material properties for an object
+///
+/// Both shading models read `color` (multiplied by the texture),
+/// `ambient` and `reflectivity`; Phong uses `diffuse`, `specular` and `shininess`, PBR uses
+/// `metallic`, `roughness` and `ao`.
 #[repr(C)]
 #[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
 struct Material {
     color: [f32; 3],
-    _padding1: f32,
+    /// Nonzero while the object is picked, which makes it glow; set when
+    /// the materials are uploaded
+    highlight: f32,
     ambient: f32,
     diffuse: f32,
     specular: f32,
     shininess: f32,
+    /// 0 for dielectrics (plastic, wood, stone), 1 for metals
+    metallic: f32,
+    /// 0 is a mirror-smooth surface, 1 a fully rough one
+    roughness: f32,
+    /// Ambient occlusion: how much of the ambient light reaches the surface
+    ao: f32,
+    /// How much of the environment map the surface mirrors; 0 turns
+    /// reflections off
+    reflectivity: f32,
 }
 
-/// Instance data for rendering multiple objects
+/// Which pipeline draws an object
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Shading {
+    /// Phong: ambient + diffuse + specular highlight
+    Phong,
+    /// Metallic-roughness PBR (Cook-Torrance)
+    Pbr,
+}
+
+/// Per-instance data, read from the instance buffer as vertex attributes
 #[repr(C)]
 #[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
 struct InstanceData {
@@ -244,43 +185,658 @@ struct InstanceData {
     normal_matrix: [[f32; 4]; 4],
 }
 
-/// Scene object with transform and material
+impl InstanceData {
+    fn new(model: cgmath::Matrix4<f32>) -> Self {
+        let normal_matrix = model
+            .invert()
+            .map_or(cgmath::Matrix4::identity(), |inv| inv.transpose());
+        Self {
+            model: model.into(),
+            normal_matrix: normal_matrix.into(),
+        }
+    }
+
+    /// A vertex attribute is at most a vec4, so each matrix takes four
+    /// consecutive locations, one per column
+    const ATTRIBUTES: [wgpu::VertexAttribute; 8] = wgpu::vertex_attr_array![
+        4 => Float32x4, 5 => Float32x4, 6 => Float32x4, 7 => Float32x4,
+        8 => Float32x4, 9 => Float32x4, 10 => Float32x4, 11 => Float32x4,
+    ];
+
+    fn desc() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<InstanceData>() as wgpu::BufferAddress,
+            // Advance once per instance instead of once per vertex
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &Self::ATTRIBUTES,
+        }
+    }
+}
+
+/// Cubes in the instancing stress test, drawn with a single draw call
+const STRESS_CUBES: usize = 10_000;
+
+const WINDOW_TITLE: &str = "wgpu Scene with Multiple Objects";
+
+/// What's drawn at a scene graph node; the node holds the transform
 struct SceneObject {
-    position: cgmath::Vector3<f32>,
-    rotation: cgmath::Vector3<f32>,
-    scale: cgmath::Vector3<f32>,
+    /// Reported when the object is picked
+    name: String,
     material: Material,
+    shading: Shading,
+    /// Index into `State::textures`; `None` draws the material color alone
+    texture: Option<usize>,
     mesh_type: MeshType,
 }
 
+/// Which mesh an object is drawn with: one of the generated ones, or a
+/// loaded model
 enum MeshType {
     Cube,
+    /// Radius 1, unlike the other generated meshes
     Sphere,
+    Plane,
+    Cylinder,
+    Cone,
+    Torus,
+    Capsule,
+    /// Index into `State::models`
+    Model(usize),
+}
+
+/// The OBJ model shown when none is given on the command line
+#[cfg(not(target_arch = "wasm32"))]
+const DEFAULT_MODEL: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/assets/torus.obj");
+/// The glTF scene shown when none is given on the command line
+#[cfg(not(target_arch = "wasm32"))]
+const DEFAULT_SCENE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/assets/table.gltf");
+/// The scene file read when none is given on the command line
+#[cfg(not(target_arch = "wasm32"))]
+const DEFAULT_SCENE_FILE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/assets/scene.json");
+
+/// Where the shaders are read from when they change on disk
+#[cfg(not(target_arch = "wasm32"))]
+const SHADER_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/src");
+
+/// The scene file, and the OBJ model and glTF scene added to the
+/// generated objects, each with the name it's logged under
+struct Assets {
+    scene_name: String,
+    scene: Result<SceneFile, SceneFileError>,
+    obj_name: String,
+    obj: Result<Mesh, obj::ObjError>,
+    gltf_name: String,
+    gltf: Result<GltfScene, gltf::Error>,
 }
 
-impl SceneObject {
-    fn model_matrix(&self) -> cgmath::Matrix4<f32> {
-        let translation = cgmath::Matrix4::from_translation(self.position);
-        let rotation = cgmath::Matrix4::from_angle_x(cgmath::Rad(self.rotation.x))
-            * cgmath::Matrix4::from_angle_y(cgmath::Rad(self.rotation.y))
-            * cgmath::Matrix4::from_angle_z(cgmath::Rad(self.rotation.z));
-        let scale =
-            cgmath::Matrix4::from_nonuniform_scale(self.scale.x, self.scale.y, self.scale.z);
-        translation * rotation * scale
+impl Assets {
+    /// The files named on the command line, or the defaults:
+    /// `cargo run -- scene.json model.obj scene.gltf` shows other assets,
+    /// and any of them can be left out
+    #[cfg(not(target_arch = "wasm32"))]
+    fn from_args() -> Self {
+        let mut scene_name = DEFAULT_SCENE_FILE.to_string();
+        let mut obj_name = DEFAULT_MODEL.to_string();
+        let mut gltf_name = DEFAULT_SCENE.to_string();
+        for arg in std::env::args().skip(1) {
+            if arg.ends_with(".json") {
+                scene_name = arg;
+            } else if arg.ends_with(".gltf") || arg.ends_with(".glb") {
+                gltf_name = arg;
+            } else {
+                obj_name = arg;
+            }
+        }
+        Self {
+            scene: scene_file::load(&scene_name),
+            scene_name,
+            obj: obj::load(&obj_name),
+            obj_name,
+            gltf: gltf_loader::load(&gltf_name),
+            gltf_name,
+        }
+    }
+
+    /// The default assets, compiled into the module since a web page has
+    /// no filesystem to read them from
+    #[cfg(target_arch = "wasm32")]
+    fn embedded() -> Self {
+        Self {
+            scene_name: "scene.json".to_string(),
+            scene: scene_file::parse(include_str!("../assets/scene.json")),
+            obj_name: "torus.obj".to_string(),
+            obj: obj::parse(include_str!("../assets/torus.obj")),
+            gltf_name: "table.gltf".to_string(),
+            gltf: gltf_loader::parse(include_bytes!("../assets/table.gltf")),
+        }
     }
 }
 
+/// Indices into `State::textures`
+const CHECKERBOARD_TEXTURE: usize = 0;
+const CRATE_TEXTURE: usize = 1;
+const BRICK_TEXTURE: usize = 2;
+
+/// GPU buffers for a mesh
+struct Model {
+    vertex_buffer: wgpu::Buffer,
+    index_buffer: wgpu::Buffer,
+    num_indices: u32,
+    /// Around the mesh, in model space; for frustum culling
+    bounds: Aabb,
+}
+
+impl Model {
+    fn new(device: &wgpu::Device, mesh: &Mesh) -> Self {
+        let mut vertices: Vec<Vertex> = mesh
+            .positions
+            .iter()
+            .zip(&mesh.normals)
+            .zip(&mesh.uvs)
+            .map(|((&position, &normal), &uv)| Vertex {
+                position,
+                normal,
+                uv,
+                tangent: [0.0; 4],
+            })
+            .collect();
+        compute_tangents(&mut vertices, &mesh.indices);
+
+        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Model Vertex Buffer"),
+            contents: bytemuck::cast_slice(&vertices),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+        // Loaded meshes can have more than 65536 vertices, so indices are
+        // 32-bit
+        let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Model Index Buffer"),
+            contents: bytemuck::cast_slice(&mesh.indices),
+            usage: wgpu::BufferUsages::INDEX,
+        });
+        Self {
+            vertex_buffer,
+            index_buffer,
+            num_indices: mesh.indices.len() as u32,
+            bounds: Aabb::from_positions(mesh.positions.iter().copied()),
+        }
+    }
+}
+
+/// GPU buffers for every generated mesh, one per `MeshType` other than
+/// `Model`
+struct Primitives {
+    cube: Model,
+    sphere: Model,
+    plane: Model,
+    cylinder: Model,
+    cone: Model,
+    torus: Model,
+    capsule: Model,
+}
+
+impl Primitives {
+    fn new(device: &wgpu::Device) -> Self {
+        Self {
+            cube: Model::new(device, &meshes::cube()),
+            sphere: Model::new(device, &meshes::icosphere(3)),
+            plane: Model::new(device, &meshes::plane(8)),
+            cylinder: Model::new(device, &meshes::cylinder(32)),
+            cone: Model::new(device, &meshes::cone(32)),
+            torus: Model::new(device, &meshes::torus(0.35, 0.15, 48, 24)),
+            capsule: Model::new(device, &meshes::capsule(0.25, 0.5, 32, 8)),
+        }
+    }
+}
+
+impl Material {
+    /// A glTF metallic-roughness material, with an approximation in this
+    /// example's Phong parameters for drawing it without PBR: rough
+    /// surfaces get a wide, dim highlight and smooth ones a tight, bright
+    /// one
+    fn from_gltf(object: &gltf_loader::GltfObject) -> Self {
+        let [r, g, b, _alpha] = object.base_color;
+        let roughness = object.roughness.clamp(0.05, 1.0);
+        Self {
+            color: [r, g, b],
+            highlight: 0.0,
+            ambient: 0.1,
+            // Metals have (almost) no diffuse reflection
+            diffuse: 1.0 - 0.7 * object.metallic,
+            specular: 1.0 - 0.8 * roughness,
+            // The usual Blinn-Phong exponent for a given roughness
+            shininess: (2.0 / roughness.powi(4) - 2.0).clamp(1.0, 256.0),
+            // The PBR pipeline takes the factors as they are
+            metallic: object.metallic,
+            roughness: object.roughness,
+            ao: 1.0,
+            reflectivity: 1.0,
+        }
+    }
+}
+
+/// Add the scene file's objects to `scene` as roots, returning their ids
+/// so a reload can take them out again
+fn add_file_objects(scene: &mut SceneGraph<SceneObject>, file: &SceneFile) -> Vec<NodeId> {
+    file.objects
+        .iter()
+        .map(|desc| {
+            let material = desc.material;
+            let object = SceneObject {
+                name: desc.name.clone(),
+                material: Material {
+                    color: material.color,
+                    highlight: 0.0,
+                    ambient: material.ambient,
+                    diffuse: material.diffuse,
+                    specular: material.specular,
+                    shininess: material.shininess,
+                    metallic: material.metallic,
+                    roughness: material.roughness,
+                    ao: material.ao,
+                    reflectivity: material.reflectivity,
+                },
+                shading: match desc.shading {
+                    ShadingDesc::Phong => Shading::Phong,
+                    ShadingDesc::Pbr => Shading::Pbr,
+                },
+                texture: desc.texture.map(|texture| match texture {
+                    TextureDesc::Checkerboard => CHECKERBOARD_TEXTURE,
+                    TextureDesc::Crate => CRATE_TEXTURE,
+                    TextureDesc::Brick => BRICK_TEXTURE,
+                }),
+                mesh_type: match desc.mesh {
+                    MeshDesc::Cube => MeshType::Cube,
+                    MeshDesc::Sphere => MeshType::Sphere,
+                    MeshDesc::Plane => MeshType::Plane,
+                    MeshDesc::Cylinder => MeshType::Cylinder,
+                    MeshDesc::Cone => MeshType::Cone,
+                    MeshDesc::Torus => MeshType::Torus,
+                    MeshDesc::Capsule => MeshType::Capsule,
+                },
+            };
+            let transform = Transform::new(desc.position())
+                .with_rotation(desc.rotation())
+                .with_spin(desc.spin())
+                .with_scale(desc.scale());
+            scene.add(None, transform, Some(object))
+        })
+        .collect()
+}
+
+/// Two columns of PBR spheres to the right of the scene, plastic and
+/// metal, each row rougher than the one in front of it
+fn create_pbr_spheres() -> Vec<(Transform, SceneObject)> {
+    const ROWS: usize = 5;
+    let mut spheres = Vec::new();
+    for row in 0..ROWS {
+        let roughness = 0.1 + 0.8 * row as f32 / (ROWS - 1) as f32;
+        for (metallic, x, color) in [(0.0, 4.5, [0.8, 0.1, 0.1]), (1.0, 5.5, [0.95, 0.64, 0.54])] {
+            spheres.push((
+                Transform::new(cgmath::Vector3::new(x, 0.0, 2.0 - row as f32))
+                    .with_scale(cgmath::Vector3::new(0.8, 0.8, 0.8)),
+                SceneObject {
+                    name: format!(
+                        "{} sphere, roughness {:.1}",
+                        if metallic > 0.0 { "metal" } else { "plastic" },
+                        roughness
+                    ),
+                    material: Material {
+                        color,
+                        highlight: 0.0,
+                        ambient: 0.1,
+                        diffuse: 1.0,
+                        specular: 0.5,
+                        shininess: 32.0,
+                        metallic,
+                        roughness,
+                        ao: 1.0,
+                        reflectivity: 1.0,
+                    },
+                    shading: Shading::Pbr,
+                    texture: None,
+                    mesh_type: MeshType::Sphere,
+                },
+            ));
+        }
+    }
+    spheres
+}
+
+/// A row of the other generated meshes along the front of the ground,
+/// checkered to show off their texture coordinates
+fn create_shapes() -> Vec<(Transform, SceneObject)> {
+    let quarter_turn = cgmath::Vector3::new(std::f32::consts::FRAC_PI_2, 0.0, 0.0);
+    let spin = cgmath::Vector3::new(0.0, 0.3, 0.0);
+    let shapes = [
+        // The plane and the torus are stood up to face the camera
+        (
+            "Plane",
+            MeshType::Plane,
+            quarter_turn,
+            cgmath::Vector3::zero(),
+            [0.9, 0.9, 0.9],
+        ),
+        (
+            "Cylinder",
+            MeshType::Cylinder,
+            cgmath::Vector3::zero(),
+            spin,
+            [0.9, 0.5, 0.3],
+        ),
+        (
+            "Cone",
+            MeshType::Cone,
+            cgmath::Vector3::zero(),
+            spin,
+            [0.4, 0.8, 0.4],
+        ),
+        (
+            "Torus",
+            MeshType::Torus,
+            quarter_turn,
+            spin,
+            [0.4, 0.6, 1.0],
+        ),
+        (
+            "Capsule",
+            MeshType::Capsule,
+            cgmath::Vector3::zero(),
+            spin,
+            [0.9, 0.8, 0.3],
+        ),
+    ];
+    shapes
+        .into_iter()
+        .enumerate()
+        .map(|(i, (name, mesh_type, rotation, spin, color))| {
+            // Each fits the unit cube, so at this height it rests on the
+            // ground
+            let x = (i as f32 - 2.0) * 1.2;
+            (
+                Transform::new(cgmath::Vector3::new(x, -0.45, 3.5))
+                    .with_rotation(rotation)
+                    .with_spin(spin),
+                SceneObject {
+                    name: name.to_string(),
+                    material: Material {
+                        color,
+                        highlight: 0.0,
+                        ambient: 0.15,
+                        diffuse: 0.9,
+                        specular: 0.3,
+                        shininess: 24.0,
+                        metallic: 0.0,
+                        roughness: 0.5,
+                        ao: 1.0,
+                        reflectivity: 0.0,
+                    },
+                    shading: Shading::Phong,
+                    texture: Some(CHECKERBOARD_TEXTURE),
+                    mesh_type,
+                },
+            )
+        })
+        .collect()
+}
+
+/// A `STRESS_CUBES`-sized grid of spinning crates floating above the scene
+fn create_stress_cubes() -> Vec<Transform> {
+    let side = (STRESS_CUBES as f32).sqrt().ceil() as usize;
+    let spacing = 1.0;
+    let offset = (side - 1) as f32 * spacing / 2.0;
+    (0..STRESS_CUBES)
+        .map(|i| {
+            let (row, column) = (i / side, i % side);
+            Transform::new(cgmath::Vector3::new(
+                column as f32 * spacing - offset,
+                8.0,
+                row as f32 * spacing - offset,
+            ))
+            .with_rotation(cgmath::Vector3::new(0.0, i as f32, 0.0))
+            .with_spin(cgmath::Vector3::new(0.3, 0.5 + (i % 7) as f32 * 0.2, 0.0))
+            .with_scale(cgmath::Vector3::new(0.4, 0.4, 0.4))
+        })
+        .collect()
+}
+
+/// What every stress cube is drawn with; white lets the crate texture's
+/// own colors through
+const STRESS_CUBE_MATERIAL: Material = Material {
+    color: [1.0, 1.0, 1.0],
+    highlight: 0.0,
+    ambient: 0.2,
+    diffuse: 1.0,
+    specular: 0.2,
+    shininess: 16.0,
+    metallic: 0.0,
+    roughness: 0.5,
+    ao: 1.0,
+    reflectivity: 0.0,
+};
+
+/// The most moons N can fill the orrery with
+const MAX_MOONS: usize = 6;
+
+/// An orrery floating above the scene: a glowing star with a planet
+/// circling it. Returns the planet's frame, for `add_moon`.
+///
+/// Orbits come from the hierarchy rather than from any orbit code. The
+/// planet hangs off a node at the star's center that spins, which swings
+/// the planet around in a circle. The planet's own body is a child of its
+/// frame rather than the frame itself, so its size and spin don't carry
+/// over to the moons.
+fn create_orrery(scene: &mut SceneGraph<SceneObject>) -> NodeId {
+    let center = cgmath::Vector3::new(0.0, 3.5, 0.0);
+    // Bright enough that bloom picks it up
+    scene.add(
+        None,
+        Transform::new(center).with_scale(cgmath::Vector3::new(0.6, 0.6, 0.6)),
+        Some(SceneObject {
+            name: "Star".to_string(),
+            material: Material {
+                color: [1.0, 0.75, 0.3],
+                highlight: 0.0,
+                ambient: 3.0,
+                diffuse: 0.5,
+                specular: 0.0,
+                shininess: 1.0,
+                metallic: 0.0,
+                roughness: 1.0,
+                ao: 1.0,
+                reflectivity: 0.0,
+            },
+            shading: Shading::Phong,
+            texture: None,
+            mesh_type: MeshType::Sphere,
+        }),
+    );
+    let orbit = scene.add(
+        None,
+        Transform::new(center).with_spin(cgmath::Vector3::new(0.0, 0.4, 0.0)),
+        None,
+    );
+    let planet = scene.add(
+        Some(orbit),
+        Transform::new(cgmath::Vector3::new(2.5, 0.0, 0.0)),
+        None,
+    );
+    scene.add(
+        Some(planet),
+        Transform::new(cgmath::Vector3::zero())
+            .with_spin(cgmath::Vector3::new(0.0, 1.5, 0.0))
+            .with_scale(cgmath::Vector3::new(0.35, 0.35, 0.35)),
+        Some(SceneObject {
+            name: "Planet".to_string(),
+            material: Material {
+                color: [0.2, 0.45, 0.9],
+                highlight: 0.0,
+                ambient: 0.1,
+                diffuse: 1.0,
+                specular: 0.4,
+                shininess: 32.0,
+                metallic: 0.0,
+                roughness: 0.5,
+                ao: 1.0,
+                reflectivity: 0.1,
+            },
+            shading: Shading::Phong,
+            texture: None,
+            mesh_type: MeshType::Sphere,
+        }),
+    );
+    planet
+}
+
+/// Put moon number `index` in orbit around `planet`, each one further out,
+/// slower and more tilted than the last; returns its orbit node, which
+/// takes the moon with it when removed
+fn add_moon(scene: &mut SceneGraph<SceneObject>, planet: NodeId, index: usize) -> NodeId {
+    let n = index as f32;
+    let orbit = scene.add(
+        Some(planet),
+        Transform::new(cgmath::Vector3::zero())
+            .with_rotation(cgmath::Vector3::new(0.25 * n, 1.3 * n, 0.0))
+            .with_spin(cgmath::Vector3::new(0.0, 2.0 / (1.0 + 0.4 * n), 0.0)),
+        None,
+    );
+    scene.add(
+        Some(orbit),
+        Transform::new(cgmath::Vector3::new(0.6 + 0.25 * n, 0.0, 0.0))
+            .with_scale(cgmath::Vector3::new(0.12, 0.12, 0.12)),
+        Some(SceneObject {
+            name: format!("Moon {}", index + 1),
+            material: Material {
+                color: [0.7, 0.7, 0.7],
+                highlight: 0.0,
+                ambient: 0.1,
+                diffuse: 1.0,
+                specular: 0.1,
+                shininess: 8.0,
+                metallic: 0.0,
+                roughness: 0.8,
+                ao: 1.0,
+                reflectivity: 0.0,
+            },
+            shading: Shading::Phong,
+            texture: None,
+            mesh_type: MeshType::Sphere,
+        }),
+    );
+    orbit
+}
+
+/// Where the dancing crate stands when a clip leaves a channel alone,
+/// resting on the ground
+const DANCER_REST: Pose = Pose {
+    position: cgmath::Vector3::new(-4.0, -0.65, 1.5),
+    rotation: cgmath::Vector3::new(0.0, 0.0, 0.0),
+    scale: cgmath::Vector3::new(0.6, 0.6, 0.6),
+};
+
+/// Seconds K takes to blend from one dance clip into the next
+const DANCE_BLEND: f32 = 0.5;
+
+/// The dancing crate's routine, in the order K cycles through it
+fn create_dance() -> Vec<Clip> {
+    let rest = DANCER_REST.position;
+    let up = |height| rest + cgmath::Vector3::new(0.0, height, 0.0);
+    let squash = cgmath::Vector3::new(0.72, 0.45, 0.72);
+    let stretch = cgmath::Vector3::new(0.55, 0.7, 0.55);
+    vec![
+        // Hops on the spot. The cubic height track passes the top with no
+        // vertical speed and leaves the ground at full speed, like a
+        // thrown ball; the linear scale track snaps into a squash on
+        // landing and stretches on the way up
+        Clip {
+            name: "bounce",
+            position: Some(Track::new(
+                Interpolation::Cubic,
+                // Squashing lowers the crate's center so it stays on the
+                // ground
+                vec![(0.0, up(-0.075)), (0.35, up(1.2)), (0.7, up(-0.075))],
+            )),
+            rotation: None,
+            scale: Some(Track::new(
+                Interpolation::Linear,
+                vec![
+                    (0.0, squash),
+                    (0.12, stretch),
+                    (0.35, DANCER_REST.scale),
+                    (0.58, stretch),
+                    (0.7, squash),
+                ],
+            )),
+            loop_mode: LoopMode::Loop,
+        },
+        // Rises while turning a full circle, then drops back down.
+        // A turn of 2π ends where it began, so the loop is seamless
+        Clip {
+            name: "spin",
+            position: Some(Track::new(
+                Interpolation::Cubic,
+                vec![(0.0, rest), (0.75, up(0.6)), (1.5, rest)],
+            )),
+            rotation: Some(Track::new(
+                Interpolation::Linear,
+                vec![
+                    (0.0, cgmath::Vector3::zero()),
+                    (1.5, cgmath::Vector3::new(0.0, std::f32::consts::TAU, 0.0)),
+                ],
+            )),
+            scale: None,
+            loop_mode: LoopMode::Loop,
+        },
+        // Slides from side to side, tipping over from one side to the
+        // other as it goes. Ping-pong plays the single pass back in reverse
+        // instead of jumping back to the start
+        Clip {
+            name: "sway",
+            position: Some(Track::new(
+                Interpolation::Linear,
+                vec![
+                    (0.0, rest - cgmath::Vector3::unit_x()),
+                    (1.2, rest + cgmath::Vector3::unit_x()),
+                ],
+            )),
+            rotation: Some(Track::new(
+                Interpolation::Linear,
+                vec![
+                    (0.0, cgmath::Vector3::new(0.0, 0.0, 0.3)),
+                    (1.2, cgmath::Vector3::new(0.0, 0.0, -0.3)),
+                ],
+            )),
+            scale: None,
+            loop_mode: LoopMode::PingPong,
+        },
+        // A single somersault, then stillness: played once, the clip holds
+        // its last pose, which is back at rest
+        Clip {
+            name: "flip",
+            position: Some(Track::new(
+                Interpolation::Cubic,
+                vec![(0.0, rest), (0.5, up(1.5)), (1.0, rest)],
+            )),
+            rotation: Some(Track::new(
+                Interpolation::Linear,
+                vec![
+                    (0.0, cgmath::Vector3::zero()),
+                    (1.0, cgmath::Vector3::new(std::f32::consts::TAU, 0.0, 0.0)),
+                ],
+            )),
+            scale: None,
+            loop_mode: LoopMode::Once,
+        },
+    ]
+}
+
 #[repr(C)]
 #[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
 struct Uniforms {
     view: [[f32; 4]; 4],
     projection: [[f32; 4]; 4],
-    light_position: [f32; 3],
-    _padding1: f32,
-    light_color: [f32; 3],
-    _padding2: f32,
     camera_position: [f32; 3],
-    _padding3: f32,
+    _padding: f32,
 }
 
 impl Uniforms {
@@ -288,53 +844,199 @@ impl Uniforms {
         Self {
             view: cgmath::Matrix4::identity().into(),
             projection: cgmath::Matrix4::identity().into(),
-            light_position: [5.0, 5.0, 5.0],
-            _padding1: 0.0,
-            light_color: [1.0, 1.0, 1.0],
-            _padding2: 0.0,
             camera_position: [0.0, 2.0, 10.0],
-            _padding3: 0.0,
+            _padding: 0.0,
+        }
+    }
+}
+
+/// Camera movement speed, in world units per second
+const CAMERA_SPEED: f32 = 5.0;
+/// Camera rotation per pixel of mouse drag, in radians
+const CAMERA_SENSITIVITY: f32 = 0.005;
+
+/// A pipeline drawing scene objects with the fragment shader `fs_entry`
+fn create_scene_pipeline(
+    device: &wgpu::Device,
+    layout: &wgpu::PipelineLayout,
+    shader: &wgpu::ShaderModule,
+    format: wgpu::TextureFormat,
+    sample_count: u32,
+    fs_entry: &str,
+    label: &str,
+) -> wgpu::RenderPipeline {
+    PipelineBuilder::new(label, layout, shader)
+        .fragment_entry(fs_entry)
+        .vertex_buffers(&[Vertex::desc(), InstanceData::desc()])
+        .color_target(format, Some(wgpu::BlendState::REPLACE))
+        .cull_back(wgpu::FrontFace::Ccw)
+        .depth(msaa::DEPTH_FORMAT, true, wgpu::CompareFunction::Less)
+        .multisample(msaa::multisample_state(sample_count))
+        .build(device)
+}
+
+/// The layouts and shaders the render pipelines are built from, kept so
+/// the pipelines can be rebuilt when the sample count or a shader changes
+struct PipelineSources {
+    scene_layout: wgpu::PipelineLayout,
+    scene_shader: wgpu::ShaderModule,
+    skybox_layout: wgpu::PipelineLayout,
+    skybox_shader: wgpu::ShaderModule,
+}
+
+impl PipelineSources {
+    /// The module compiled from the shader file `name`, with its label,
+    /// if these pipelines use it
+    #[cfg(not(target_arch = "wasm32"))]
+    fn shader_mut(&mut self, name: &str) -> Option<(&mut wgpu::ShaderModule, &'static str)> {
+        match name {
+            "shader.wgsl" => Some((&mut self.scene_shader, "Shader")),
+            "skybox.wgsl" => Some((&mut self.skybox_shader, "Skybox Shader")),
+            _ => None,
         }
     }
 }
 
-struct State {
-    surface: wgpu::Surface,
+/// Every pipeline in the render pass; all of them must match the render
+/// targets' sample count
+struct Pipelines {
+    phong: wgpu::RenderPipeline,
+    pbr: wgpu::RenderPipeline,
+    skybox: wgpu::RenderPipeline,
+}
+
+impl Pipelines {
+    fn new(
+        device: &wgpu::Device,
+        sources: &PipelineSources,
+        format: wgpu::TextureFormat,
+        sample_count: u32,
+    ) -> Self {
+        // Both scene pipelines share the vertex shader and bind groups and
+        // differ only in their fragment shader
+        let phong = create_scene_pipeline(
+            device,
+            &sources.scene_layout,
+            &sources.scene_shader,
+            format,
+            sample_count,
+            "fs_main",
+            "Phong Pipeline",
+        );
+        let pbr = create_scene_pipeline(
+            device,
+            &sources.scene_layout,
+            &sources.scene_shader,
+            format,
+            sample_count,
+            "fs_pbr",
+            "PBR Pipeline",
+        );
+
+        let skybox = PipelineBuilder::new(
+            "Skybox Pipeline",
+            &sources.skybox_layout,
+            &sources.skybox_shader,
+        )
+        .color_target(format, Some(wgpu::BlendState::REPLACE))
+        // At depth 1.0 it passes only where the depth buffer still holds
+        // its cleared value, i.e. where nothing was drawn
+        .depth(msaa::DEPTH_FORMAT, false, wgpu::CompareFunction::LessEqual)
+        .multisample(msaa::multisample_state(sample_count))
+        .build(device);
+
+        Self { phong, pbr, skybox }
+    }
+}
+
+struct State<'a> {
+    window: Arc<Window>,
+    surface: wgpu::Surface<'a>,
     device: wgpu::Device,
     queue: wgpu::Queue,
     config: wgpu::SurfaceConfiguration,
     size: winit::dpi::PhysicalSize<u32>,
-    window: Window,
-    render_pipeline: wgpu::RenderPipeline,
+    pipeline_sources: PipelineSources,
+    pipelines: Pipelines,
 
     // Geometry
-    cube_vertex_buffer: wgpu::Buffer,
-    cube_index_buffer: wgpu::Buffer,
-    cube_num_indices: u32,
-    sphere_vertex_buffer: wgpu::Buffer,
-    sphere_index_buffer: wgpu::Buffer,
-    sphere_num_indices: u32,
+    primitives: Primitives,
+    models: Vec<Model>,
 
     // Uniforms
-    uniform_buffer: wgpu::Buffer,
+    uniform_buffer: UniformBlock<Uniforms>,
     uniform_bind_group: wgpu::BindGroup,
     uniforms: Uniforms,
+    /// Rewritten when the scene file is reloaded
+    #[cfg(not(target_arch = "wasm32"))]
+    lights_buffer: UniformBlock<LightsUniform>,
 
     // Materials
-    material_buffer: wgpu::Buffer,
+    /// One slot per object in the scene graph, in the order `visit` finds
+    /// them, then one shared by the stress cubes
+    materials: UniformSlots<Material>,
     material_bind_group: wgpu::BindGroup,
-
-    // Depth
-    depth_texture: wgpu::Texture,
-    depth_view: wgpu::TextureView,
+    /// One bind group per texture and its normal map; a bind group keeps
+    /// its textures alive
+    textures: Vec<wgpu::BindGroup>,
+    /// Bound for objects without a texture (white, with a flat normal map)
+    white_texture: wgpu::BindGroup,
+
+    // Render targets
+    /// Depth, plus the multisampled color buffer when MSAA is on
+    render_targets: RenderTargets,
+    /// The sample count MSAA uses when it's on (M toggles it); 1 if the
+    /// adapter can't multisample
+    msaa_samples: u32,
+    post: PostProcessor,
 
     // Scene
-    objects: Vec<SceneObject>,
-    camera_angle: f32,
+    scene: SceneGraph<SceneObject>,
+    /// Where the objects and lights were read from; F5 reads it again
+    scene_file_name: String,
+    /// The objects placed by the scene file, replaced on reload
+    #[cfg(not(target_arch = "wasm32"))]
+    file_objects: Vec<NodeId>,
+    /// The frame the orrery's moons orbit in
+    planet: NodeId,
+    /// Each moon's orbit node, oldest first; N adds one, Backspace removes
+    /// the newest
+    moons: Vec<NodeId>,
+    /// The crate the animator moves; K switches to its next clip
+    dancer: NodeId,
+    animator: Animator,
+    /// Instancing stress test, toggled with I
+    stress_cubes: Vec<Transform>,
+    show_stress_cubes: bool,
+    /// Whether objects outside the view are skipped (C toggles it)
+    culling: bool,
+    /// The last frame's counts, shown in the title
+    cull_stats: CullStats,
+    /// Where the mouse is, if it's over the window
+    cursor: Option<winit::dpi::PhysicalPosition<f64>>,
+    /// A right-click waiting for the next frame to find what it hit
+    pick_at: Option<winit::dpi::PhysicalPosition<f64>>,
+    /// The selected object and its name
+    picked: Option<(NodeId, String)>,
+    /// False if the surface can't be copied from or its format can't be
+    /// saved as a PNG
+    can_screenshot: bool,
+    /// F12 was pressed: the next frame is saved to a file
+    screenshot_requested: bool,
+    /// Per-frame `InstanceData`: one for each object in `scene`, then the
+    /// stress cubes
+    instance_buffer: wgpu::Buffer,
+    camera: Interpolated<Camera>,
+    camera_controller: CameraController,
+    timestep: FixedTimestep,
+    /// Reports edited shaders, or `None` if watching `SHADER_DIR` failed
+    #[cfg(not(target_arch = "wasm32"))]
+    shader_watcher: Option<ShaderWatcher>,
 }
 
-impl State {
-    async fn new(window: Window) -> Self {
+impl<'a> State<'a> {
+    async fn new(window: Window, assets: Assets) -> Self {
+        let window = Arc::new(window);
         let size = window.inner_size();
 
         let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
@@ -342,7 +1044,7 @@ impl State {
             ..Default::default()
         });
 
-        let surface = unsafe { instance.create_surface(&window) }.unwrap();
+        let surface = instance.create_surface(Arc::clone(&window)).unwrap();
 
         let adapter = instance
             .request_adapter(&wgpu::RequestAdapterOptions {
@@ -373,175 +1075,325 @@ impl State {
             .find(|f| f.is_srgb())
             .unwrap_or(surface_caps.formats[0]);
 
+        // Screenshots copy out of the surface texture, which needs
+        // `COPY_SRC`; not every surface offers it
+        let copy_usage = surface_caps.usages & wgpu::TextureUsages::COPY_SRC;
+        let can_screenshot = !copy_usage.is_empty() && screenshot::supported(surface_format);
+        // A browser canvas is never sRGB, but it can be drawn through an
+        // sRGB view so the colors come out as they do natively
+        let view_format = surface_format.add_srgb_suffix();
         let config = wgpu::SurfaceConfiguration {
-            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | copy_usage,
             format: surface_format,
             width: size.width,
             height: size.height,
             present_mode: surface_caps.present_modes[0],
             alpha_mode: surface_caps.alpha_modes[0],
-            view_formats: vec![],
+            view_formats: if view_format == surface_format {
+                vec![]
+            } else {
+                vec![view_format]
+            },
+            desired_maximum_frame_latency: 2,
         };
         surface.configure(&device, &config);
 
-        // Create depth texture
-        let depth_texture = device.create_texture(&wgpu::TextureDescriptor {
-            label: Some("Depth Texture"),
-            size: wgpu::Extent3d {
-                width: config.width,
-                height: config.height,
-                depth_or_array_layers: 1,
-            },
-            mip_level_count: 1,
-            sample_count: 1,
-            dimension: wgpu::TextureDimension::D2,
-            format: wgpu::TextureFormat::Depth32Float,
-            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
-            view_formats: &[],
-        });
-        let depth_view = depth_texture.create_view(&wgpu::TextureViewDescriptor::default());
+        // The scene is drawn into an HDR texture and post-processed into
+        // the surface; with MSAA it's drawn into a multisampled texture
+        // first and resolved into the HDR one
+        let post = PostProcessor::new(&device, &config);
+        let sample_count = msaa::supported_sample_count(&adapter, HDR_FORMAT);
+        let render_targets = RenderTargets::new(
+            &device,
+            config.width,
+            config.height,
+            HDR_FORMAT,
+            sample_count,
+        );
 
-        // Create geometries
-        let (cube_vertices, cube_indices) = create_cube();
-        let cube_vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("Cube Vertex Buffer"),
-            contents: bytemuck::cast_slice(&cube_vertices),
-            usage: wgpu::BufferUsages::VERTEX,
-        });
-        let cube_index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("Cube Index Buffer"),
-            contents: bytemuck::cast_slice(&cube_indices),
-            usage: wgpu::BufferUsages::INDEX,
-        });
-        let cube_num_indices = cube_indices.len() as u32;
+        let primitives = Primitives::new(&device);
+
+        // Textures and their normal maps, in the order of the *_TEXTURE
+        // indices. Only the bricks have surface detail; the rest use a flat
+        // normal map.
+        let texture_bind_group_layout = Texture::bind_group_layout(&device);
+        let flat_normal_map = Texture::flat_normal_map(&device, &queue, "Flat Normal Map");
+        let brick_normal_map = Texture::normal_map_from_bytes(
+            &device,
+            &queue,
+            include_bytes!("../assets/brick_normal.png"),
+            "Brick Normal Map",
+        )
+        .expect("assets/brick_normal.png is a valid PNG");
+        let textures = [
+            (
+                Texture::checkerboard(&device, &queue, 16, "Checkerboard Texture"),
+                &flat_normal_map,
+            ),
+            (
+                Texture::from_bytes(
+                    &device,
+                    &queue,
+                    include_bytes!("../assets/crate.png"),
+                    "Crate Texture",
+                )
+                .expect("assets/crate.png is a valid PNG"),
+                &flat_normal_map,
+            ),
+            (
+                Texture::from_bytes(
+                    &device,
+                    &queue,
+                    include_bytes!("../assets/brick.png"),
+                    "Brick Texture",
+                )
+                .expect("assets/brick.png is a valid PNG"),
+                &brick_normal_map,
+            ),
+        ]
+        .iter()
+        .map(|(texture, normal_map)| {
+            texture.bind_group(&device, &texture_bind_group_layout, normal_map)
+        })
+        .collect();
+        let white_texture = Texture::solid(&device, &queue, [255; 4], "White Texture").bind_group(
+            &device,
+            &texture_bind_group_layout,
+            &flat_normal_map,
+        );
 
-        let (sphere_vertices, sphere_indices) = create_sphere(2);
-        let sphere_vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("Sphere Vertex Buffer"),
-            contents: bytemuck::cast_slice(&sphere_vertices),
-            usage: wgpu::BufferUsages::VERTEX,
-        });
-        let sphere_index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("Sphere Index Buffer"),
-            contents: bytemuck::cast_slice(&sphere_indices),
-            usage: wgpu::BufferUsages::INDEX,
-        });
-        let sphere_num_indices = sphere_indices.len() as u32;
-
-        // Create scene objects
-        let objects = vec![
-            // Ground plane (scaled cube)
-            SceneObject {
-                position: cgmath::Vector3::new(0.0, -1.0, 0.0),
-                rotation: cgmath::Vector3::zero(),
-                scale: cgmath::Vector3::new(10.0, 0.1, 10.0),
-                material: Material {
-                    color: [0.3, 0.3, 0.3],
-                    _padding1: 0.0,
-                    ambient: 0.2,
-                    diffuse: 0.8,
-                    specular: 0.1,
-                    shininess: 4.0,
-                },
-                mesh_type: MeshType::Cube,
-            },
-            // Red cube
-            SceneObject {
-                position: cgmath::Vector3::new(-2.0, 0.5, 0.0),
-                rotation: cgmath::Vector3::new(0.3, 0.5, 0.0),
-                scale: cgmath::Vector3::new(1.0, 1.0, 1.0),
-                material: Material {
-                    color: [0.8, 0.2, 0.2],
-                    _padding1: 0.0,
-                    ambient: 0.1,
-                    diffuse: 1.0,
-                    specular: 0.5,
-                    shininess: 32.0,
-                },
-                mesh_type: MeshType::Cube,
-            },
-            // Green sphere
-            SceneObject {
-                position: cgmath::Vector3::new(0.0, 0.5, 0.0),
-                rotation: cgmath::Vector3::zero(),
-                scale: cgmath::Vector3::new(1.0, 1.0, 1.0),
-                material: Material {
-                    color: [0.2, 0.8, 0.2],
-                    _padding1: 0.0,
-                    ambient: 0.1,
-                    diffuse: 1.0,
-                    specular: 0.8,
-                    shininess: 64.0,
-                },
-                mesh_type: MeshType::Sphere,
-            },
-            // Blue cube
-            SceneObject {
-                position: cgmath::Vector3::new(2.0, 0.5, 0.0),
-                rotation: cgmath::Vector3::new(0.0, 0.8, 0.3),
-                scale: cgmath::Vector3::new(1.0, 1.0, 1.0),
+        // The hand-placed objects and the lights; without them the rest of
+        // the scene still shows, lit by the ambient light alone
+        let scene_file = match assets.scene {
+            Ok(file) => {
+                log::info!(
+                    "loaded {}: {} objects, {} lights",
+                    assets.scene_name,
+                    file.objects.len(),
+                    file.lights.len()
+                );
+                file
+            }
+            Err(e) => {
+                log::warn!("could not load {}: {}", assets.scene_name, e);
+                SceneFile::default()
+            }
+        };
+
+        let mut objects = create_pbr_spheres();
+        objects.extend(create_shapes());
+        let mut models = Vec::new();
+
+        // Loaded assets; a missing or broken file shouldn't stop the rest
+        // of the scene
+        match assets.obj {
+            Ok(mut mesh) => {
+                mesh.fit_to_unit_cube();
+                log::info!(
+                    "loaded {}: {} vertices, {} triangles",
+                    assets.obj_name,
+                    mesh.positions.len(),
+                    mesh.indices.len() / 3
+                );
+                models.push(Model::new(&device, &mesh));
+                // Gold OBJ model, shaded as a PBR metal
+                objects.push((
+                    Transform::new(cgmath::Vector3::new(0.0, 0.5, -2.5))
+                        .with_rotation(cgmath::Vector3::new(0.4, 0.0, 0.0))
+                        .with_spin(cgmath::Vector3::new(0.0, 0.4, 0.0))
+                        .with_scale(cgmath::Vector3::new(1.5, 1.5, 1.5)),
+                    SceneObject {
+                        name: assets.obj_name.clone(),
+                        material: Material {
+                            color: [0.9, 0.7, 0.2],
+                            highlight: 0.0,
+                            ambient: 0.1,
+                            diffuse: 0.9,
+                            specular: 0.9,
+                            shininess: 64.0,
+                            metallic: 1.0,
+                            roughness: 0.35,
+                            ao: 1.0,
+                            reflectivity: 1.0,
+                        },
+                        shading: Shading::Pbr,
+                        texture: None,
+                        mesh_type: MeshType::Model(models.len() - 1),
+                    },
+                ));
+            }
+            Err(e) => log::warn!("could not load {}: {}", assets.obj_name, e),
+        }
+        match assets.gltf {
+            Ok(scene) => {
+                log::info!(
+                    "loaded {}: {} meshes, {} objects",
+                    assets.gltf_name,
+                    scene.meshes.len(),
+                    scene.objects.len()
+                );
+                let first_model = models.len();
+                models.extend(scene.meshes.iter().map(|mesh| Model::new(&device, mesh)));
+                for object in &scene.objects {
+                    log::debug!("glTF object {:?} uses mesh {}", object.name, object.mesh);
+                    objects.push((
+                        Transform::new(object.translation)
+                            .with_rotation(object.rotation)
+                            .with_scale(object.scale),
+                        SceneObject {
+                            name: object.name.clone(),
+                            material: Material::from_gltf(object),
+                            shading: Shading::Pbr,
+                            texture: None,
+                            mesh_type: MeshType::Model(first_model + object.mesh),
+                        },
+                    ));
+                }
+            }
+            Err(e) => log::warn!("could not load {}: {}", assets.gltf_name, e),
+        }
+
+        // Everything so far sits straight in the world; the orrery above
+        // it is where the hierarchy shows
+        let mut scene = SceneGraph::new();
+        let file_objects = add_file_objects(&mut scene, &scene_file);
+        for (transform, object) in objects {
+            scene.add(None, transform, Some(object));
+        }
+        let planet = create_orrery(&mut scene);
+        let moons = vec![add_moon(&mut scene, planet, 0)];
+        // Its transform is overwritten by the animator every frame
+        let dancer = scene.add(
+            None,
+            Transform::new(DANCER_REST.position).with_scale(DANCER_REST.scale),
+            Some(SceneObject {
+                name: "Dancing crate".to_string(),
                 material: Material {
-                    color: [0.2, 0.2, 0.8],
-                    _padding1: 0.0,
+                    color: [1.0, 1.0, 1.0],
+                    highlight: 0.0,
                     ambient: 0.1,
-                    diffuse: 1.0,
-                    specular: 0.5,
-                    shininess: 32.0,
+                    diffuse: 0.9,
+                    specular: 0.2,
+                    shininess: 16.0,
+                    metallic: 0.0,
+                    roughness: 0.6,
+                    ao: 1.0,
+                    reflectivity: 0.0,
                 },
+                shading: Shading::Phong,
+                texture: Some(CRATE_TEXTURE),
                 mesh_type: MeshType::Cube,
-            },
-        ];
+            }),
+        );
+        // Slots for the most objects the scene can hold: at most one per
+        // node, counting every moon N can add and the largest scene file
+        // F5 can load
+        let object_capacity = scene.len()
+            + 2 * (MAX_MOONS - moons.len())
+            + (scene_file::MAX_OBJECTS - file_objects.len());
+
+        let stress_cubes = create_stress_cubes();
+        let instance_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Instance Buffer"),
+            size: ((object_capacity + stress_cubes.len()) * std::mem::size_of::<InstanceData>())
+                as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
 
         // Create uniforms
         let uniforms = Uniforms::new();
-        let uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("Uniform Buffer"),
-            contents: bytemuck::cast_slice(&[uniforms]),
-            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
-        });
+        let uniform_buffer = UniformBlock::new(&device, &uniforms, "Uniform Buffer");
+
+        // The sky, drawn behind everything and mirrored by reflective
+        // materials
+        let environment = Texture::cubemap_from_bytes(
+            &device,
+            &queue,
+            [
+                include_bytes!("../assets/skybox/px.png"),
+                include_bytes!("../assets/skybox/nx.png"),
+                include_bytes!("../assets/skybox/py.png"),
+                include_bytes!("../assets/skybox/ny.png"),
+                include_bytes!("../assets/skybox/pz.png"),
+                include_bytes!("../assets/skybox/nz.png"),
+            ],
+            "Environment Cubemap",
+        )
+        .expect("assets/skybox holds six square PNGs of one size");
+
+        // The lights don't move, so they're only uploaded again when the
+        // scene file is reloaded
+        let lights = LightsUniform::new(scene_file.ambient, &scene_file.lights());
+        let lights_buffer = UniformBlock::new(&device, &lights, "Lights Buffer");
 
         let uniform_bind_group_layout =
             device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-                entries: &[wgpu::BindGroupLayoutEntry {
-                    binding: 0,
-                    visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Uniform,
-                        has_dynamic_offset: false,
-                        min_binding_size: None,
+                entries: &[
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
+                        ty: UniformBlock::<Uniforms>::binding_type(),
+                        count: None,
                     },
-                    count: None,
-                }],
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: UniformBlock::<LightsUniform>::binding_type(),
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 2,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Texture {
+                            multisampled: false,
+                            view_dimension: wgpu::TextureViewDimension::Cube,
+                            sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 3,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                        count: None,
+                    },
+                ],
                 label: Some("uniform_bind_group_layout"),
             });
 
         let uniform_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
             layout: &uniform_bind_group_layout,
-            entries: &[wgpu::BindGroupEntry {
-                binding: 0,
-                resource: uniform_buffer.as_entire_binding(),
-            }],
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: uniform_buffer.binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: lights_buffer.binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::TextureView(&environment.view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: wgpu::BindingResource::Sampler(&environment.sampler),
+                },
+            ],
             label: Some("uniform_bind_group"),
         });
 
-        // Create material buffer
-        let material = objects[0].material;
-        let material_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("Material Buffer"),
-            contents: bytemuck::cast_slice(&[material]),
-            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
-        });
+        // Each draw reads its material from its own slot of one buffer
+        let materials = UniformSlots::new(&device, object_capacity + 1, "Material Buffer");
 
         let material_bind_group_layout =
             device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
                 entries: &[wgpu::BindGroupLayoutEntry {
                     binding: 0,
                     visibility: wgpu::ShaderStages::FRAGMENT,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Uniform,
-                        has_dynamic_offset: false,
-                        min_binding_size: None,
-                    },
+                    ty: UniformSlots::<Material>::binding_type(),
                     count: None,
                 }],
                 label: Some("material_bind_group_layout"),
@@ -551,7 +1403,7 @@ impl State {
             layout: &material_bind_group_layout,
             entries: &[wgpu::BindGroupEntry {
                 binding: 0,
-                resource: material_buffer.as_entire_binding(),
+                resource: materials.binding(),
             }],
             label: Some("material_bind_group"),
         });
@@ -565,49 +1417,34 @@ impl State {
         let render_pipeline_layout =
             device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
                 label: Some("Render Pipeline Layout"),
-                bind_group_layouts: &[&uniform_bind_group_layout, &material_bind_group_layout],
-                push_constant_ranges: &[wgpu::PushConstantRange {
-                    stages: wgpu::ShaderStages::VERTEX,
-                    range: 0..128, // Two 4x4 matrices
-                }],
+                bind_group_layouts: &[
+                    &uniform_bind_group_layout,
+                    &material_bind_group_layout,
+                    &texture_bind_group_layout,
+                ],
+                push_constant_ranges: &[],
             });
 
-        let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some("Render Pipeline"),
-            layout: Some(&render_pipeline_layout),
-            vertex: wgpu::VertexState {
-                module: &shader,
-                entry_point: "vs_main",
-                buffers: &[Vertex::desc()],
-            },
-            fragment: Some(wgpu::FragmentState {
-                module: &shader,
-                entry_point: "fs_main",
-                targets: &[Some(wgpu::ColorTargetState {
-                    format: config.format,
-                    blend: Some(wgpu::BlendState::REPLACE),
-                    write_mask: wgpu::ColorWrites::ALL,
-                })],
-            }),
-            primitive: wgpu::PrimitiveState {
-                topology: wgpu::PrimitiveTopology::TriangleList,
-                strip_index_format: None,
-                front_face: wgpu::FrontFace::Ccw,
-                cull_mode: Some(wgpu::Face::Back),
-                polygon_mode: wgpu::PolygonMode::Fill,
-                unclipped_depth: false,
-                conservative: false,
-            },
-            depth_stencil: Some(wgpu::DepthStencilState {
-                format: wgpu::TextureFormat::Depth32Float,
-                depth_write_enabled: true,
-                depth_compare: wgpu::CompareFunction::Less,
-                stencil: wgpu::StencilState::default(),
-                bias: wgpu::DepthBiasState::default(),
-            }),
-            multisample: wgpu::MultisampleState::default(),
-            multiview: None,
+        // The skybox draws one full-screen triangle at the far plane and
+        // only needs the camera and the environment from group 0
+        let skybox_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Skybox Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("skybox.wgsl").into()),
         });
+        let skybox_pipeline_layout =
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("Skybox Pipeline Layout"),
+                bind_group_layouts: &[&uniform_bind_group_layout],
+                push_constant_ranges: &[],
+            });
+
+        let pipeline_sources = PipelineSources {
+            scene_layout: render_pipeline_layout,
+            scene_shader: shader,
+            skybox_layout: skybox_pipeline_layout,
+            skybox_shader,
+        };
+        let pipelines = Pipelines::new(&device, &pipeline_sources, HDR_FORMAT, sample_count);
 
         Self {
             window,
@@ -616,22 +1453,57 @@ impl State {
             queue,
             config,
             size,
-            render_pipeline,
-            cube_vertex_buffer,
-            cube_index_buffer,
-            cube_num_indices,
-            sphere_vertex_buffer,
-            sphere_index_buffer,
-            sphere_num_indices,
+            pipeline_sources,
+            pipelines,
+            primitives,
+            models,
             uniform_buffer,
             uniform_bind_group,
             uniforms,
-            material_buffer,
+            #[cfg(not(target_arch = "wasm32"))]
+            lights_buffer,
+            materials,
             material_bind_group,
-            depth_texture,
-            depth_view,
-            objects,
-            camera_angle: 0.0,
+            textures,
+            white_texture,
+            render_targets,
+            msaa_samples: sample_count,
+            post,
+            scene,
+            scene_file_name: assets.scene_name,
+            #[cfg(not(target_arch = "wasm32"))]
+            file_objects,
+            planet,
+            moons,
+            dancer,
+            animator: Animator::new(DANCER_REST, create_dance()),
+            stress_cubes,
+            show_stress_cubes: false,
+            culling: true,
+            cull_stats: CullStats::default(),
+            cursor: None,
+            pick_at: None,
+            picked: None,
+            can_screenshot,
+            screenshot_requested: false,
+            instance_buffer,
+            camera: Interpolated::new(Camera::looking_at(
+                cgmath::Point3::new(10.0, 3.0, 0.0),
+                cgmath::Point3::new(0.0, 0.0, 0.0),
+            )),
+            camera_controller: CameraController::new(CAMERA_SPEED, CAMERA_SENSITIVITY),
+            timestep: FixedTimestep::new(STEPS_PER_SECOND),
+            #[cfg(not(target_arch = "wasm32"))]
+            shader_watcher: ShaderWatcher::new(std::path::Path::new(SHADER_DIR))
+                .inspect_err(|e| log::warn!("not watching the shaders for changes: {}", e))
+                .ok(),
+        }
+    }
+
+    fn pipeline(&self, shading: Shading) -> &wgpu::RenderPipeline {
+        match shading {
+            Shading::Phong => &self.pipelines.phong,
+            Shading::Pbr => &self.pipelines.pbr,
         }
     }
 
@@ -646,46 +1518,311 @@ impl State {
             self.config.height = new_size.height;
             self.surface.configure(&self.device, &self.config);
 
-            self.depth_texture = self.device.create_texture(&wgpu::TextureDescriptor {
-                label: Some("Depth Texture"),
-                size: wgpu::Extent3d {
-                    width: new_size.width,
-                    height: new_size.height,
-                    depth_or_array_layers: 1,
+            self.render_targets = RenderTargets::new(
+                &self.device,
+                new_size.width,
+                new_size.height,
+                HDR_FORMAT,
+                self.render_targets.sample_count(),
+            );
+            self.post
+                .resize(&self.device, new_size.width, new_size.height);
+        }
+    }
+
+    fn input(&mut self, event: &WindowEvent) -> bool {
+        // The cursor is tracked for picking but still passed on, since the
+        // camera needs it for dragging
+        match event {
+            WindowEvent::CursorMoved { position, .. } => self.cursor = Some(*position),
+            WindowEvent::CursorLeft { .. } => self.cursor = None,
+            WindowEvent::MouseInput {
+                button: MouseButton::Right,
+                state: ElementState::Pressed,
+                ..
+            } => {
+                self.pick_at = self.cursor;
+                return true;
+            }
+            _ => {}
+        }
+        if let WindowEvent::KeyboardInput {
+            event:
+                KeyEvent {
+                    physical_key:
+                        PhysicalKey::Code(
+                            key @ (KeyCode::KeyI
+                            | KeyCode::KeyM
+                            | KeyCode::KeyB
+                            | KeyCode::KeyT
+                            | KeyCode::KeyV
+                            | KeyCode::KeyN
+                            | KeyCode::Backspace
+                            | KeyCode::KeyK
+                            | KeyCode::KeyC
+                            | KeyCode::F5
+                            | KeyCode::F12),
+                        ),
+                    state: ElementState::Pressed,
+                    repeat: false,
+                    ..
                 },
-                mip_level_count: 1,
-                sample_count: 1,
-                dimension: wgpu::TextureDimension::D2,
-                format: wgpu::TextureFormat::Depth32Float,
-                usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
-                view_formats: &[],
-            });
-            self.depth_view = self
-                .depth_texture
-                .create_view(&wgpu::TextureViewDescriptor::default());
+            ..
+        } = event
+        {
+            match key {
+                KeyCode::KeyI => self.show_stress_cubes = !self.show_stress_cubes,
+                KeyCode::KeyM => {
+                    let sample_count = if self.render_targets.sample_count() > 1 {
+                        1
+                    } else {
+                        self.msaa_samples
+                    };
+                    self.set_sample_count(sample_count);
+                }
+                KeyCode::KeyB => {
+                    self.post.toggle("bloom");
+                }
+                KeyCode::KeyT => {
+                    self.post.toggle("tone mapping");
+                }
+                KeyCode::KeyV => {
+                    self.post.toggle("vignette");
+                }
+                KeyCode::KeyC => self.culling = !self.culling,
+                KeyCode::F5 => self.reload_scene(),
+                KeyCode::F12 => {
+                    if self.can_screenshot {
+                        self.screenshot_requested = true;
+                    } else {
+                        log::warn!(
+                            "screenshots need a surface that can be copied from, in 8-bit RGBA or BGRA"
+                        );
+                    }
+                }
+                KeyCode::KeyK => {
+                    let next = self.animator.current() + 1;
+                    self.animator.play(next, DANCE_BLEND);
+                    log::info!(
+                        "dancing: {}",
+                        self.animator.clips()[self.animator.current()].name
+                    );
+                }
+                KeyCode::KeyN => {
+                    if self.moons.len() < MAX_MOONS {
+                        let moon = add_moon(&mut self.scene, self.planet, self.moons.len());
+                        self.moons.push(moon);
+                    }
+                }
+                _ => {
+                    // Taking the orbit node takes the moon under it along
+                    if let Some(moon) = self.moons.pop() {
+                        self.scene.remove(moon);
+                    }
+                    self.forget_removed_pick();
+                }
+            }
+            self.update_title();
+            return true;
         }
+        self.camera_controller.process_event(event)
     }
 
-    fn input(&mut self, _event: &WindowEvent) -> bool {
-        false
+    /// Drop the picked object if it has left the scene; its id is stale
+    /// rather than naming whatever reuses its slot
+    fn forget_removed_pick(&mut self) {
+        if let Some((id, _)) = &self.picked {
+            if !self.scene.contains(*id) {
+                self.picked = None;
+            }
+        }
     }
 
-    fn update(&mut self) {
-        self.camera_angle += 0.01;
+    /// Replace the scene file's objects and lights with what the file
+    /// holds now; if it can't be read the scene is left as it is
+    #[cfg(not(target_arch = "wasm32"))]
+    fn reload_scene(&mut self) {
+        let file = match scene_file::load(&self.scene_file_name) {
+            Ok(file) => file,
+            Err(e) => {
+                log::error!("could not reload {}: {}", self.scene_file_name, e);
+                return;
+            }
+        };
+        for id in self.file_objects.drain(..) {
+            self.scene.remove(id);
+        }
+        self.file_objects = add_file_objects(&mut self.scene, &file);
+        self.lights_buffer.write(
+            &self.queue,
+            &LightsUniform::new(file.ambient, &file.lights()),
+        );
+        self.forget_removed_pick();
+        log::info!(
+            "reloaded {}: {} objects, {} lights",
+            self.scene_file_name,
+            file.objects.len(),
+            file.lights.len()
+        );
+    }
 
-        // Rotate camera around scene
-        let camera_distance = 10.0;
-        let camera_pos = cgmath::Point3::new(
-            camera_angle.cos() * camera_distance,
-            3.0,
-            camera_angle.sin() * camera_distance,
+    #[cfg(target_arch = "wasm32")]
+    fn reload_scene(&mut self) {
+        log::warn!(
+            "{} is compiled into the page, so there's nothing to reload",
+            self.scene_file_name
         );
+    }
 
-        let view = cgmath::Matrix4::look_at_rh(
-            camera_pos,
-            cgmath::Point3::new(0.0, 0.0, 0.0),
-            cgmath::Vector3::unit_y(),
+    /// Rebuild the render targets and pipelines for `sample_count`
+    /// samples per pixel
+    fn set_sample_count(&mut self, sample_count: u32) {
+        self.render_targets = RenderTargets::new(
+            &self.device,
+            self.config.width,
+            self.config.height,
+            HDR_FORMAT,
+            sample_count,
+        );
+        self.pipelines = Pipelines::new(
+            &self.device,
+            &self.pipeline_sources,
+            HDR_FORMAT,
+            sample_count,
         );
+    }
+
+    fn update_title(&self) {
+        let mut details = Vec::new();
+        if self.render_targets.sample_count() == 1 {
+            details.push("MSAA off".to_string());
+        }
+        details.extend(self.post.disabled().map(|name| format!("{} off", name)));
+        if self.show_stress_cubes {
+            details.push(format!("{} instanced cubes", STRESS_CUBES));
+        }
+        if self.moons.len() != 1 {
+            details.push(format!("{} moons", self.moons.len()));
+        }
+        if self.animator.current() != 0 {
+            let clip = &self.animator.clips()[self.animator.current()];
+            details.push(format!("dance: {}", clip.name));
+        }
+        if let Some((_, name)) = &self.picked {
+            details.push(format!("picked: {}", name));
+        }
+        let CullStats { drawn, culled } = self.cull_stats;
+        if self.culling {
+            details.push(format!("{} drawn, {} culled", drawn, culled));
+        } else {
+            details.push(format!("culling off, {} drawn", drawn));
+        }
+        let title = if details.is_empty() {
+            WINDOW_TITLE.to_string()
+        } else {
+            format!("{} ({})", WINDOW_TITLE, details.join(", "))
+        };
+        self.window.set_title(&title);
+    }
+
+    /// Advance the simulation by one fixed step of `dt` seconds
+    fn update(&mut self, dt: f32) {
+        self.camera
+            .step(|camera| self.camera_controller.update_camera(camera, dt));
+        self.scene.update(dt);
+        self.animator.update(dt);
+        if self.show_stress_cubes {
+            for cube in &mut self.stress_cubes {
+                cube.update(dt);
+            }
+        }
+    }
+
+    /// Recompile the shaders edited since the last frame and rebuild the
+    /// pipelines using them
+    #[cfg(not(target_arch = "wasm32"))]
+    fn reload_changed_shaders(&mut self) {
+        let Some(watcher) = &self.shader_watcher else {
+            return;
+        };
+        for name in watcher.changed() {
+            let path = std::path::Path::new(SHADER_DIR).join(&name);
+            let source = match std::fs::read_to_string(&path) {
+                Ok(source) => source,
+                Err(e) => {
+                    log::warn!("could not read {}: {}", path.display(), e);
+                    continue;
+                }
+            };
+            match self.reload_shader(&name, &source) {
+                Ok(true) => log::info!("reloaded {}", name),
+                Ok(false) => log::debug!("{} changed, but no pipeline uses it", name),
+                Err(e) => log::error!(
+                    "{} not reloaded, keeping the last good version:\n{}",
+                    name,
+                    e
+                ),
+            }
+        }
+    }
+
+    /// Compile `source` as the shader file `name` and rebuild what uses
+    /// it; returns whether anything does
+    ///
+    /// Nothing is replaced unless the module and every pipeline built from
+    /// it are valid.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn reload_shader(&mut self, name: &str, source: &str) -> Result<bool, String> {
+        if name == "post.wgsl" {
+            self.post
+                .reload_shader(&self.device, &self.config, source)?;
+            return Ok(true);
+        }
+        let Some((slot, label)) = self.pipeline_sources.shader_mut(name) else {
+            return Ok(false);
+        };
+        let device = &self.device;
+        let shader = hot_reload::validated(device, || {
+            device.create_shader_module(wgpu::ShaderModuleDescriptor {
+                label: Some(label),
+                source: wgpu::ShaderSource::Wgsl(source.into()),
+            })
+        })?;
+
+        // Swap the new module in to build the pipelines from, and back out
+        // if any of them fails
+        let previous = std::mem::replace(slot, shader);
+        let sample_count = self.render_targets.sample_count();
+        match hot_reload::validated(device, || {
+            Pipelines::new(device, &self.pipeline_sources, HDR_FORMAT, sample_count)
+        }) {
+            Ok(pipelines) => {
+                self.pipelines = pipelines;
+                Ok(true)
+            }
+            Err(e) => {
+                if let Some((slot, _)) = self.pipeline_sources.shader_mut(name) {
+                    *slot = previous;
+                }
+                Err(e)
+            }
+        }
+    }
+
+    /// Run however many simulation steps the elapsed time calls for
+    fn tick(&mut self) {
+        let dt = self.timestep.dt();
+        for _ in 0..self.timestep.advance() {
+            self.update(dt);
+        }
+    }
+
+    /// Upload the camera for this frame, interpolated between steps;
+    /// returns its view-projection matrix
+    fn update_camera(&mut self, alpha: f32) -> cgmath::Matrix4<f32> {
+        let camera = self.camera.at(alpha);
+        let camera_pos = camera.position();
+        let view = camera.view_matrix();
 
         let aspect = self.config.width as f32 / self.config.height as f32;
         let projection = cgmath::perspective(cgmath::Deg(45.0), aspect, 0.1, 100.0);
@@ -694,22 +1831,145 @@ impl State {
         self.uniforms.projection = projection.into();
         self.uniforms.camera_position = camera_pos.into();
 
-        self.queue.write_buffer(
-            &self.uniform_buffer,
-            0,
-            bytemuck::cast_slice(&[self.uniforms]),
-        );
+        self.uniform_buffer.write(&self.queue, &self.uniforms);
+        projection * view
+    }
+
+    /// The nearest of `draws` under the pixel at `cursor`, if any
+    ///
+    /// Spheres are tested as spheres, everything else by its mesh's box.
+    fn pick(
+        &self,
+        draws: &[(NodeId, &SceneObject, cgmath::Matrix4<f32>)],
+        cursor: winit::dpi::PhysicalPosition<f64>,
+        eye: cgmath::Point3<f32>,
+        view_proj: &cgmath::Matrix4<f32>,
+    ) -> Option<(NodeId, String)> {
+        let ray = Ray::from_screen(
+            (cursor.x as f32, cursor.y as f32),
+            (self.config.width as f32, self.config.height as f32),
+            eye,
+            view_proj,
+        )?;
+        draws
+            .iter()
+            .filter_map(|&(id, object, world)| {
+                let ray = ray.to_local(&world)?;
+                let t = match object.mesh_type {
+                    MeshType::Sphere => ray.intersect_sphere(cgmath::Point3::origin(), 1.0),
+                    _ => ray.intersect_aabb(&self.model(&object.mesh_type).bounds),
+                }?;
+                Some((t, id, object))
+            })
+            .min_by(|a, b| a.0.total_cmp(&b.0))
+            .map(|(_, id, object)| (id, object.name.clone()))
+    }
 
-        // Animate objects
-        self.objects[1].rotation.y += 0.01;
-        self.objects[3].rotation.x += 0.015;
+    /// The buffers `mesh` is drawn from
+    fn model(&self, mesh: &MeshType) -> &Model {
+        let primitives = &self.primitives;
+        match mesh {
+            MeshType::Cube => &primitives.cube,
+            MeshType::Sphere => &primitives.sphere,
+            MeshType::Plane => &primitives.plane,
+            MeshType::Cylinder => &primitives.cylinder,
+            MeshType::Cone => &primitives.cone,
+            MeshType::Torus => &primitives.torus,
+            MeshType::Capsule => &primitives.capsule,
+            MeshType::Model(index) => &self.models[*index],
+        }
     }
 
     fn render(&mut self) -> Result<(), wgpu::SurfaceError> {
+        let alpha = self.timestep.alpha();
+        let view_proj = self.update_camera(alpha);
+        // Sampled at the frame's own time, so the dancer moves smoothly
+        // between simulation steps like everything else
+        let pose = self.animator.pose(alpha);
+        if let Some(transform) = self.scene.transform_mut(self.dancer) {
+            transform.set_pose(&pose);
+        }
+        let frustum = Frustum::from_view_proj(&view_proj);
+        let mut stats = CullStats::default();
+
+        // One walk of the scene graph gives every object's world matrix;
+        // those whose box is in view become draws, and draw `i` below is
+        // the `i`th of them
+        let mut draws = Vec::new();
+        self.scene.visit(alpha, |id, object, world| {
+            let bounds = self.model(&object.mesh_type).bounds.transform(&world);
+            if !self.culling || frustum.intersects_aabb(&bounds) {
+                draws.push((id, object, world));
+            } else {
+                stats.culled += 1;
+            }
+        });
+
+        // Every object's matrices go up in one write before the pass;
+        // draws then pick their slice of the buffer by instance index
+        let mut instances: Vec<InstanceData> = draws
+            .iter()
+            .map(|&(_, _, world)| InstanceData::new(world))
+            .collect();
+        // The stress cubes spin, so a sphere (which doesn't care) is a
+        // cheaper test than a box carried through their matrices. The ones
+        // in view are packed together and still drawn in one call.
+        let mut stress_drawn = 0;
+        if self.show_stress_cubes {
+            let (center, radius) = self.primitives.cube.bounds.bounding_sphere();
+            for cube in &self.stress_cubes {
+                let model = cube.matrix(alpha);
+                let scale = cube.scale.x.max(cube.scale.y).max(cube.scale.z);
+                let center = (model * center.extend(1.0)).truncate();
+                if !self.culling || frustum.intersects_sphere(center, radius * scale) {
+                    instances.push(InstanceData::new(model));
+                    stress_drawn += 1;
+                } else {
+                    stats.culled += 1;
+                }
+            }
+        }
+        stats.drawn = draws.len() + stress_drawn;
+        if stats != self.cull_stats {
+            self.cull_stats = stats;
+            self.update_title();
+        }
+
+        // Resolved against this frame's matrices, so the click hits what's
+        // on screen; only what survived culling can be under the cursor
+        if let Some(cursor) = self.pick_at.take() {
+            let eye = self.camera.at(alpha).position();
+            self.picked = self.pick(&draws, cursor, eye, &view_proj);
+            match &self.picked {
+                Some((_, name)) => log::info!("picked {}", name),
+                None => log::info!("picked nothing"),
+            }
+            self.update_title();
+        }
+        self.queue
+            .write_buffer(&self.instance_buffer, 0, bytemuck::cast_slice(&instances));
+
+        // Materials work the same way, selected by dynamic offset instead.
+        // Writing them one at a time between draws would not: every write
+        // lands before the pass runs, so all draws would see the last one.
+        // The picked object's copy gets its highlight switched on
+        let picked = self.picked.as_ref().map(|(id, _)| *id);
+        let mut materials: Vec<Material> = draws
+            .iter()
+            .map(|&(id, object, _)| Material {
+                highlight: if Some(id) == picked { 1.0 } else { 0.0 },
+                ..object.material
+            })
+            .collect();
+        materials.push(STRESS_CUBE_MATERIAL);
+        self.materials.write(&self.queue, &materials);
+
         let output = self.surface.get_current_texture()?;
-        let view = output
-            .texture
-            .create_view(&wgpu::TextureViewDescriptor::default());
+        // Through the sRGB view, if the surface was given one
+        let view = output.texture.create_view(&wgpu::TextureViewDescriptor {
+            format: self.config.view_formats.first().copied(),
+            ..Default::default()
+        });
 
         let mut encoder = self
             .device
@@ -718,146 +1978,173 @@ impl State {
             });
 
         {
-            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-                label: Some("Render Pass"),
-                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view: &view,
-                    resolve_target: None,
-                    ops: wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(wgpu::Color {
-                            r: 0.05,
-                            g: 0.05,
-                            b: 0.1,
-                            a: 1.0,
-                        }),
-                        store: wgpu::StoreOp::Store,
-                    },
-                })],
-                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
-                    view: &self.depth_view,
-                    depth_ops: Some(wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(1.0),
-                        store: wgpu::StoreOp::Store,
+            let mut render_pass = RenderPassBuilder::new("Render Pass")
+                // With MSAA this draws into the multisampled texture and
+                // resolves it into the HDR texture at the end of the pass
+                .color_attachment(self.render_targets.color_attachment(
+                    self.post.scene_view(),
+                    wgpu::LoadOp::Clear(wgpu::Color {
+                        r: 0.05,
+                        g: 0.05,
+                        b: 0.1,
+                        a: 1.0,
                     }),
-                    stencil_ops: None,
-                }),
-                occlusion_query_set: None,
-                timestamp_writes: None,
-            });
+                ))
+                .depth(self.render_targets.depth_view(), wgpu::LoadOp::Clear(1.0))
+                .begin(&mut encoder);
 
-            render_pass.set_pipeline(&self.render_pipeline);
             render_pass.set_bind_group(0, &self.uniform_bind_group, &[]);
-
-            // Render each object
-            for object in &self.objects {
-                // Update material
-                self.queue.write_buffer(
-                    &self.material_buffer,
-                    0,
-                    bytemuck::cast_slice(&[object.material]),
+            render_pass.set_vertex_buffer(1, self.instance_buffer.slice(..));
+
+            // Render each object, switching pipelines only when the shading
+            // changes; bind groups stay bound across the switch since both
+            // pipelines share a layout
+            let mut current_shading = None;
+            for (index, &(_, object, _)) in draws.iter().enumerate() {
+                let instance = index as u32;
+                if current_shading != Some(object.shading) {
+                    render_pass.set_pipeline(self.pipeline(object.shading));
+                    current_shading = Some(object.shading);
+                }
+                render_pass.set_bind_group(
+                    1,
+                    &self.material_bind_group,
+                    &[self.materials.offset(index)],
                 );
-                render_pass.set_bind_group(1, &self.material_bind_group, &[]);
-
-                // Calculate matrices
-                let model = object.model_matrix();
-                let normal_matrix = if let Some(inv) = model.invert() {
-                    inv.transpose()
-                } else {
-                    cgmath::Matrix4::identity()
+                let texture = match object.texture {
+                    Some(index) => &self.textures[index],
+                    None => &self.white_texture,
                 };
+                render_pass.set_bind_group(2, texture, &[]);
 
-                // Push constants for per-object matrices
-                let instance_data = InstanceData {
-                    model: model.into(),
-                    normal_matrix: normal_matrix.into(),
-                };
-                render_pass.set_push_constants(
-                    wgpu::ShaderStages::VERTEX,
+                let model = self.model(&object.mesh_type);
+                render_pass.set_vertex_buffer(0, model.vertex_buffer.slice(..));
+                render_pass
+                    .set_index_buffer(model.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+                render_pass.draw_indexed(0..model.num_indices, 0, instance..instance + 1);
+            }
+
+            // All the stress cubes share a mesh, material and texture, so
+            // one call draws every one of them
+            if stress_drawn > 0 {
+                render_pass.set_pipeline(&self.pipelines.phong);
+                render_pass.set_bind_group(
+                    1,
+                    &self.material_bind_group,
+                    &[self.materials.offset(draws.len())],
+                );
+                render_pass.set_bind_group(2, &self.textures[CRATE_TEXTURE], &[]);
+                let cube = &self.primitives.cube;
+                render_pass.set_vertex_buffer(0, cube.vertex_buffer.slice(..));
+                render_pass
+                    .set_index_buffer(cube.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+                let first = draws.len() as u32;
+                render_pass.draw_indexed(
+                    0..cube.num_indices,
                     0,
-                    bytemuck::cast_slice(&[instance_data]),
+                    first..first + stress_drawn as u32,
                 );
-
-                // Draw the appropriate mesh
-                match object.mesh_type {
-                    MeshType::Cube => {
-                        render_pass.set_vertex_buffer(0, self.cube_vertex_buffer.slice(..));
-                        render_pass.set_index_buffer(
-                            self.cube_index_buffer.slice(..),
-                            wgpu::IndexFormat::Uint16,
-                        );
-                        render_pass.draw_indexed(0..self.cube_num_indices, 0, 0..1);
-                    }
-                    MeshType::Sphere => {
-                        render_pass.set_vertex_buffer(0, self.sphere_vertex_buffer.slice(..));
-                        render_pass.set_index_buffer(
-                            self.sphere_index_buffer.slice(..),
-                            wgpu::IndexFormat::Uint16,
-                        );
-                        render_pass.draw_indexed(0..self.sphere_num_indices, 0, 0..1);
-                    }
-                }
             }
+
+            // Last, so the depth test skips every pixel an object covers
+            render_pass.set_pipeline(&self.pipelines.skybox);
+            render_pass.draw(0..3, 0..1);
         }
 
+        // Bloom, tone mapping and vignette, then into the surface texture
+        self.post.run(&self.device, &mut encoder, &view);
+
+        // The copy goes in the same submission, after post-processing has
+        // written the frame
+        let screenshot = std::mem::take(&mut self.screenshot_requested)
+            .then(|| Screenshot::capture(&self.device, &mut encoder, &output.texture));
+
         self.queue.submit(std::iter::once(encoder.finish()));
         output.present();
 
+        if let Some(screenshot) = screenshot {
+            screenshot.save(&self.device, screenshot::timestamped_path());
+        }
+
         Ok(())
     }
 }
 
+#[cfg(not(target_arch = "wasm32"))]
 fn main() {
     env_logger::init();
+    let assets = Assets::from_args();
 
-    let event_loop = EventLoop::new();
+    let event_loop = EventLoop::new().unwrap();
     let window = WindowBuilder::new()
-        .with_title("wgpu Scene with Multiple Objects")
+        .with_title(WINDOW_TITLE)
         .build(&event_loop)
         .unwrap();
 
-    let mut state = pollster::block_on(State::new(window));
+    let mut state = pollster::block_on(State::new(window, assets));
+
+    event_loop
+        .run(move |event, elwt| handle_event(&mut state, event, elwt))
+        .unwrap();
+}
+
+/// In the browser nothing may block, so the adapter and device requests
+/// are awaited in a task of their own, and the event loop is started from
+/// it with `spawn`, which returns to the page instead of running forever
+#[cfg(target_arch = "wasm32")]
+fn main() {
+    use winit::platform::web::EventLoopExtWebSys;
+
+    web::init_logging();
+
+    let event_loop = EventLoop::new().unwrap();
+    let window = web::canvas_window(WindowBuilder::new().with_title(WINDOW_TITLE))
+        .build(&event_loop)
+        .unwrap();
 
-    event_loop.run(move |event, _, control_flow| match event {
+    wasm_bindgen_futures::spawn_local(async move {
+        let mut state = State::new(window, Assets::embedded()).await;
+        event_loop.spawn(move |event, elwt| handle_event(&mut state, event, elwt));
+    });
+}
+
+fn handle_event(state: &mut State, event: Event<()>, elwt: &EventLoopWindowTarget<()>) {
+    match event {
         Event::WindowEvent {
             ref event,
             window_id,
-        } if window_id == state.window().id() => {
-            if !state.input(event) {
-                match event {
-                    WindowEvent::CloseRequested
-                    | WindowEvent::KeyboardInput {
-                        input:
-                            KeyboardInput {
-                                state: ElementState::Pressed,
-                                virtual_keycode: Some(VirtualKeyCode::Escape),
-                                ..
-                            },
+        } if window_id == state.window().id() && !state.input(event) => match event {
+            WindowEvent::CloseRequested
+            | WindowEvent::KeyboardInput {
+                event:
+                    KeyEvent {
+                        state: ElementState::Pressed,
+                        logical_key: Key::Named(NamedKey::Escape),
                         ..
-                    } => *control_flow = ControlFlow::Exit,
-                    WindowEvent::Resized(physical_size) => {
-                        state.resize(*physical_size);
-                    }
-                    WindowEvent::ScaleFactorChanged { new_inner_size, .. } => {
-                        state.resize(**new_inner_size);
-                    }
-                    _ => {}
-                }
+                    },
+                ..
+            } => elwt.exit(),
+            WindowEvent::Resized(physical_size) => {
+                state.resize(*physical_size);
             }
-        }
-        Event::RedrawRequested(window_id) if window_id == state.window().id() => {
-            state.update();
-            match state.render() {
-                Ok(_) => {}
-                Err(wgpu::SurfaceError::Lost) => state.resize(state.size),
-                Err(wgpu::SurfaceError::OutOfMemory) => *control_flow = ControlFlow::Exit,
-                Err(e) => eprintln!("{:?}", e),
+            WindowEvent::RedrawRequested => {
+                #[cfg(not(target_arch = "wasm32"))]
+                state.reload_changed_shaders();
+                state.tick();
+                match state.render() {
+                    Ok(_) => {}
+                    Err(wgpu::SurfaceError::Lost) => state.resize(state.size),
+                    Err(wgpu::SurfaceError::OutOfMemory) => elwt.exit(),
+                    Err(e) => log::error!("{:?}", e),
+                }
             }
-        }
-        Event::MainEventsCleared => {
+            _ => {}
+        },
+        Event::AboutToWait => {
             state.window().request_redraw();
         }
         _ => {}
-    });
+    }
 }
 
 use wgpu::util::DeviceExt;
@@ -0,0 +1,434 @@
+//! Triangle meshes, generated or loaded.
+//!
+//! [`Mesh`] is what every source of geometry in the scene produces: the
+//! generators here, the OBJ parser and the glTF loader. It holds plain
+//! arrays, one entry per vertex, and `Model::new` turns it into GPU
+//! buffers.
+//!
+//! Most of the generated shapes are surfaces swept out by two parameters:
+//! `u` around (or across) the shape and `v` from top to bottom. Each is
+//! built as a grid of quads over `(u, v)`, and those same parameters are
+//! the texture coordinates. The grid's last column is a copy of its first,
+//! with u = 1 instead of 0, so the texture can wrap all the way around a
+//! closed shape without one row of triangles smearing the whole image
+//! backwards across the seam.
+//!
+//! Every shape is centered on the origin, faces counter-clockwise from
+//! outside, and has texture coordinates laid out so an image reads the
+//! right way round from outside. All but the sphere fit inside the unit
+//! cube, like the cube itself; the sphere has radius 1.
+
+use cgmath::prelude::*;
+use cgmath::Vector3;
+use std::collections::HashMap;
+use std::f32::consts::{FRAC_PI_2, PI, TAU};
+
+/// A triangle mesh ready to be turned into vertex and index buffers
+#[derive(Debug, Clone, Default)]
+pub struct Mesh {
+    pub positions: Vec<[f32; 3]>,
+    /// One per position
+    pub normals: Vec<[f32; 3]>,
+    /// One per position, with (0, 0) at the top left of the image;
+    /// all zero if the source has none
+    pub uvs: Vec<[f32; 2]>,
+    /// Three per triangle, counter-clockwise
+    pub indices: Vec<u32>,
+}
+
+impl Mesh {
+    /// Smooth normals: every vertex gets the average of the normals of the
+    /// triangles around it, weighted by their area
+    pub fn compute_normals(&mut self) {
+        let mut sums = vec![[0.0f32; 3]; self.positions.len()];
+        for triangle in self.indices.chunks_exact(3) {
+            let [a, b, c] = [0, 1, 2].map(|i| self.positions[triangle[i] as usize]);
+            let ab = [b[0] - a[0], b[1] - a[1], b[2] - a[2]];
+            let ac = [c[0] - a[0], c[1] - a[1], c[2] - a[2]];
+            // Cross product: length is twice the triangle's area
+            let normal = [
+                ab[1] * ac[2] - ab[2] * ac[1],
+                ab[2] * ac[0] - ab[0] * ac[2],
+                ab[0] * ac[1] - ab[1] * ac[0],
+            ];
+            for &index in triangle {
+                let sum = &mut sums[index as usize];
+                for axis in 0..3 {
+                    sum[axis] += normal[axis];
+                }
+            }
+        }
+        for (normal, sum) in self.normals.iter_mut().zip(sums) {
+            // Vertices that came with a normal keep it
+            if *normal != [0.0; 3] {
+                continue;
+            }
+            let length = (sum[0] * sum[0] + sum[1] * sum[1] + sum[2] * sum[2]).sqrt();
+            if length > 0.0 {
+                *normal = [sum[0] / length, sum[1] / length, sum[2] / length];
+            }
+        }
+    }
+
+    /// Center the mesh on the origin and scale it so its largest side is 1,
+    /// so models authored at any size can share the scene with the unit cube
+    pub fn fit_to_unit_cube(&mut self) {
+        let Some(first) = self.positions.first() else {
+            return;
+        };
+        let (mut min, mut max) = (*first, *first);
+        for position in &self.positions {
+            for axis in 0..3 {
+                min[axis] = min[axis].min(position[axis]);
+                max[axis] = max[axis].max(position[axis]);
+            }
+        }
+        let size = (0..3).map(|axis| max[axis] - min[axis]).fold(0.0, f32::max);
+        let scale = if size > 0.0 { 1.0 / size } else { 1.0 };
+        for position in &mut self.positions {
+            for axis in 0..3 {
+                position[axis] = (position[axis] - (min[axis] + max[axis]) / 2.0) * scale;
+            }
+        }
+    }
+
+    /// Add a vertex and return its index
+    fn push(&mut self, position: Vector3<f32>, normal: Vector3<f32>, uv: [f32; 2]) -> u32 {
+        self.positions.push(position.into());
+        self.normals.push(normal.into());
+        self.uvs.push(uv);
+        self.positions.len() as u32 - 1
+    }
+
+    /// Add a `columns`-wide grid of quads, one row of vertices for each
+    /// entry of `rows` (the `v` of that row), placed by `point(u, v)`
+    /// returning a position and normal
+    ///
+    /// Triangles squashed to nothing, where a row shrinks to the point of
+    /// a cone or a pole, are left out.
+    fn grid(
+        &mut self,
+        columns: u32,
+        rows: &[f32],
+        point: impl Fn(f32, f32) -> (Vector3<f32>, Vector3<f32>),
+    ) {
+        let base = self.positions.len() as u32;
+        for &v in rows {
+            for column in 0..=columns {
+                let u = column as f32 / columns as f32;
+                let (position, normal) = point(u, v);
+                self.push(position, normal, [u, v]);
+            }
+        }
+        let stride = columns + 1;
+        for row in 0..rows.len() as u32 - 1 {
+            for column in 0..columns {
+                let a = base + row * stride + column;
+                let (b, c) = (a + 1, a + stride);
+                let d = c + 1;
+                // V grows down the surface and u to the right, seen from
+                // outside, so this order is counter-clockwise
+                for triangle in [[a, c, b], [b, c, d]] {
+                    if self.area(triangle) > 1e-8 {
+                        self.indices.extend(triangle);
+                    }
+                }
+            }
+        }
+    }
+
+    /// A flat disc of `radius` at height `y`, facing up or down, with the
+    /// texture stretched across it
+    fn disc(&mut self, y: f32, radius: f32, segments: u32, up: bool) {
+        let (normal, flip) = if up {
+            (Vector3::unit_y(), 1.0)
+        } else {
+            (-Vector3::unit_y(), -1.0)
+        };
+        let center = self.push(Vector3::new(0.0, y, 0.0), normal, [0.5, 0.5]);
+        for segment in 0..=segments {
+            let direction = around(segment as f32 / segments as f32, 1.0, 0.0);
+            // Seen from its own side, the top of the image is towards -z on
+            // the top cap and +z on the bottom one
+            self.push(
+                direction * radius + Vector3::new(0.0, y, 0.0),
+                normal,
+                [0.5 + 0.5 * direction.x, 0.5 + 0.5 * flip * direction.z],
+            );
+        }
+        for segment in 0..segments {
+            let (a, b) = (center + 1 + segment, center + 2 + segment);
+            if up {
+                self.indices.extend([center, a, b]);
+            } else {
+                self.indices.extend([center, b, a]);
+            }
+        }
+    }
+
+    /// Twice the area of a triangle
+    fn area(&self, triangle: [u32; 3]) -> f32 {
+        let [a, b, c] = triangle.map(|i| Vector3::from(self.positions[i as usize]));
+        (b - a).cross(c - a).magnitude()
+    }
+}
+
+/// The point `u` of the way around a circle of `radius` about the y axis,
+/// at height `y`
+///
+/// It starts at +x and turns from there towards -z, which is to the right
+/// for someone outside looking in, so u grows across their view the way
+/// it does across the image.
+fn around(u: f32, radius: f32, y: f32) -> Vector3<f32> {
+    let (sin, cos) = (TAU * u).sin_cos();
+    Vector3::new(radius * cos, y, -radius * sin)
+}
+
+/// `count + 1` evenly spaced values from `start` to `end`
+fn steps(start: f32, end: f32, count: u32) -> impl Iterator<Item = f32> {
+    (0..=count).map(move |i| start + (end - start) * i as f32 / count as f32)
+}
+
+/// A unit cube with the whole texture on each face, upright on the sides
+pub fn cube() -> Mesh {
+    let mut mesh = Mesh::default();
+    // Each face's normal and the direction u grows across it; up the
+    // texture is the normal crossed with that
+    let faces = [
+        (Vector3::unit_z(), Vector3::unit_x()),
+        (-Vector3::unit_z(), -Vector3::unit_x()),
+        (Vector3::unit_x(), -Vector3::unit_z()),
+        (-Vector3::unit_x(), Vector3::unit_z()),
+        (Vector3::unit_y(), Vector3::unit_x()),
+        (-Vector3::unit_y(), Vector3::unit_x()),
+    ];
+    for (normal, right) in faces {
+        let up = normal.cross(right);
+        let base = mesh.positions.len() as u32;
+        for uv in [[0.0, 1.0], [1.0, 1.0], [1.0, 0.0], [0.0, 0.0]] {
+            let position = (normal + right * (2.0 * uv[0] - 1.0) + up * (1.0 - 2.0 * uv[1])) * 0.5;
+            mesh.push(position, normal, uv);
+        }
+        mesh.indices
+            .extend([0, 1, 2, 2, 3, 0].map(|index| base + index));
+    }
+    mesh
+}
+
+/// A flat square in the XZ plane facing up, split into `subdivisions`
+/// quads along each side; the top of the texture is towards -z
+pub fn plane(subdivisions: u32) -> Mesh {
+    let subdivisions = subdivisions.max(1);
+    let mut mesh = Mesh::default();
+    let rows: Vec<f32> = steps(0.0, 1.0, subdivisions).collect();
+    mesh.grid(subdivisions, &rows, |u, v| {
+        (Vector3::new(u - 0.5, 0.0, v - 0.5), Vector3::unit_y())
+    });
+    mesh
+}
+
+/// A sphere of radius 1 made by splitting an icosahedron's faces
+/// `subdivisions` times
+///
+/// Every split turns a triangle into four by its edge midpoints, pushed
+/// out onto the sphere. Unlike a latitude-longitude sphere, the triangles
+/// end up nearly the same size everywhere, with no crowding at the poles.
+/// Each level has four times the triangles: 20, 80, 320, 1280...
+pub fn icosphere(subdivisions: u32) -> Mesh {
+    let t = (1.0 + 5.0_f32.sqrt()) / 2.0;
+    let mut positions: Vec<Vector3<f32>> = [
+        [-1.0, t, 0.0],
+        [1.0, t, 0.0],
+        [-1.0, -t, 0.0],
+        [1.0, -t, 0.0],
+        [0.0, -1.0, t],
+        [0.0, 1.0, t],
+        [0.0, -1.0, -t],
+        [0.0, 1.0, -t],
+        [t, 0.0, -1.0],
+        [t, 0.0, 1.0],
+        [-t, 0.0, -1.0],
+        [-t, 0.0, 1.0],
+    ]
+    .into_iter()
+    .map(|position| Vector3::from(position).normalize())
+    .collect();
+    let mut triangles: Vec<[u32; 3]> = vec![
+        [0, 11, 5],
+        [0, 5, 1],
+        [0, 1, 7],
+        [0, 7, 10],
+        [0, 10, 11],
+        [1, 5, 9],
+        [5, 11, 4],
+        [11, 10, 2],
+        [10, 7, 6],
+        [7, 1, 8],
+        [3, 9, 4],
+        [3, 4, 2],
+        [3, 2, 6],
+        [3, 6, 8],
+        [3, 8, 9],
+        [4, 9, 5],
+        [2, 4, 11],
+        [6, 2, 10],
+        [8, 6, 7],
+        [9, 8, 1],
+    ];
+
+    for _ in 0..subdivisions {
+        // Each edge is shared by two triangles, which must share its
+        // midpoint too or the surface would crack
+        let mut midpoints = HashMap::new();
+        let mut midpoint = |a: u32, b: u32| {
+            *midpoints.entry((a.min(b), a.max(b))).or_insert_with(|| {
+                let point = (positions[a as usize] + positions[b as usize]).normalize();
+                positions.push(point);
+                positions.len() as u32 - 1
+            })
+        };
+        triangles = triangles
+            .into_iter()
+            .flat_map(|[a, b, c]| {
+                let (ab, bc, ca) = (midpoint(a, b), midpoint(b, c), midpoint(c, a));
+                [[a, ab, ca], [b, bc, ab], [c, ca, bc], [ab, bc, ca]]
+            })
+            .collect();
+    }
+
+    let mut mesh = Mesh::default();
+    for &position in &positions {
+        // Longitude across the texture, latitude down it
+        let u = 0.5 + (-position.z).atan2(position.x) / TAU;
+        let v = 0.5 - position.y.asin() / PI;
+        mesh.push(position, position, [u, v]);
+    }
+
+    // Longitude jumps from 1 back to 0 on the far side (-x), so a triangle
+    // straddling that line would stretch across the whole texture the
+    // wrong way. Its corners near u = 0 get copies past u = 1 instead,
+    // which the repeating sampler wraps round. Longitude means nothing at
+    // the poles, so each triangle touching one gets its own copy of the
+    // pole, halfway between its other corners.
+    let is_pole = |index: u32| positions[index as usize].y.abs() > 1.0 - 1e-6;
+    let mut wrapped = HashMap::new();
+    for mut triangle in triangles {
+        let us = triangle
+            .iter()
+            .filter(|&&index| !is_pole(index))
+            .map(|&index| mesh.uvs[index as usize][0]);
+        let (low, high) = us.fold((1.0_f32, 0.0_f32), |(low, high), u| {
+            (low.min(u), high.max(u))
+        });
+        if high - low > 0.5 {
+            for index in triangle.iter_mut() {
+                if !is_pole(*index) && mesh.uvs[*index as usize][0] < 0.5 {
+                    *index = *wrapped.entry(*index).or_insert_with(|| {
+                        let [u, v] = mesh.uvs[*index as usize];
+                        let position = positions[*index as usize];
+                        mesh.push(position, position, [u + 1.0, v])
+                    });
+                }
+            }
+        }
+        for corner in 0..3 {
+            let index = triangle[corner];
+            if index < positions.len() as u32 && is_pole(index) {
+                let u = [(corner + 1) % 3, (corner + 2) % 3]
+                    .map(|other| mesh.uvs[triangle[other] as usize][0]);
+                let position = positions[index as usize];
+                let v = mesh.uvs[index as usize][1];
+                triangle[corner] = mesh.push(position, position, [(u[0] + u[1]) / 2.0, v]);
+            }
+        }
+        mesh.indices.extend(triangle);
+    }
+    mesh
+}
+
+/// An upright cylinder, diameter and height 1, with `segments` sides and
+/// flat caps
+pub fn cylinder(segments: u32) -> Mesh {
+    let segments = segments.max(3);
+    let mut mesh = Mesh::default();
+    mesh.grid(segments, &[0.0, 1.0], |u, v| {
+        (around(u, 0.5, 0.5 - v), around(u, 1.0, 0.0))
+    });
+    mesh.disc(0.5, 0.5, segments, true);
+    mesh.disc(-0.5, 0.5, segments, false);
+    mesh
+}
+
+/// A cone pointing up, base diameter and height 1, with `segments` sides
+///
+/// The side's normals lean up by the slope: for a cone half as wide as it
+/// is tall, out by 1 for every 0.5 up. The tip is one vertex per side,
+/// each with its side's normal, since a single shared one couldn't point
+/// every way at once.
+pub fn cone(segments: u32) -> Mesh {
+    let segments = segments.max(3);
+    let mut mesh = Mesh::default();
+    mesh.grid(segments, &[0.0, 1.0], |u, v| {
+        let normal = around(u, 1.0, 0.5).normalize();
+        (around(u, 0.5 * v, 0.5 - v), normal)
+    });
+    mesh.disc(-0.5, 0.5, segments, false);
+    mesh
+}
+
+/// A ring lying flat, its tube of radius `minor` circling the y axis at
+/// `major`, with `segments` steps around the ring and `sides` around the
+/// tube
+///
+/// u runs around the ring and v around the tube, starting from its top.
+pub fn torus(major: f32, minor: f32, segments: u32, sides: u32) -> Mesh {
+    let (segments, sides) = (segments.max(3), sides.max(3));
+    let mut mesh = Mesh::default();
+    let rows: Vec<f32> = steps(0.0, 1.0, sides).collect();
+    mesh.grid(segments, &rows, |u, v| {
+        // Around the tube: straight up at v = 0, then outwards, down,
+        // inwards and back up
+        let tube = FRAC_PI_2 - TAU * v;
+        let (sin, cos) = tube.sin_cos();
+        let normal = around(u, cos, sin);
+        (around(u, major, 0.0) + normal * minor, normal)
+    });
+    mesh
+}
+
+/// An upright capsule of `radius`: a cylinder `height` tall with a
+/// hemisphere on each end, `segments` sides around and `rings` rows of
+/// vertices per hemisphere
+///
+/// The texture runs down the profile by distance, so it isn't squashed on
+/// the caps or stretched along the middle.
+pub fn capsule(radius: f32, height: f32, segments: u32, rings: u32) -> Mesh {
+    let (segments, rings) = (segments.max(3), rings.max(1));
+    let quarter = FRAC_PI_2 * radius;
+    let length = 2.0 * quarter + height;
+    // Distance down the profile of each row: the top hemisphere from its
+    // pole to its equator, the bottom one from its equator to its pole,
+    // with the cylinder between the two equator rows
+    let rows: Vec<f32> = steps(0.0, quarter, rings)
+        .chain(steps(quarter + height, length, rings))
+        .map(|distance| distance / length)
+        .collect();
+    let mut mesh = Mesh::default();
+    mesh.grid(segments, &rows, |u, v| {
+        let distance = v * length;
+        // Angle of the profile from straight up, and which end's center
+        // it's measured from
+        let (polar, center) = if distance <= quarter + 1e-5 {
+            (distance / radius, height / 2.0)
+        } else {
+            (
+                FRAC_PI_2 + (distance - quarter - height) / radius,
+                -height / 2.0,
+            )
+        };
+        let (sin, cos) = polar.sin_cos();
+        let normal = around(u, sin, cos);
+        (Vector3::new(0.0, center, 0.0) + normal * radius, normal)
+    });
+    mesh
+}
@@ -0,0 +1,304 @@
+//! Wavefront OBJ loading.
+//!
+//! Only what the scene needs: positions, normals, texture coordinates and
+//! triangle indices. Materials and groups are skipped, polygons are
+//! split into triangle fans, and faces without `vn` normals get smooth
+//! ones averaged from the surrounding triangles.
+//!
+//! OBJ indexes positions, texture coordinates and normals separately
+//! (`f 1/1/4 2/2/4 3/3/4`) while the GPU wants one index per vertex, so
+//! every distinct combination becomes its own vertex.
+//!
+//! With the `tobj` feature the file is read by the tobj crate instead,
+//! which also handles the parts of the format this parser doesn't.
+
+use std::fmt;
+use std::path::Path;
+
+use crate::meshes::Mesh;
+
+#[derive(Debug)]
+pub enum ObjError {
+    Io(std::io::Error),
+    /// A line that couldn't be understood, with its 1-based line number
+    Parse {
+        line: usize,
+        message: String,
+    },
+    #[cfg(feature = "tobj")]
+    Tobj(tobj::LoadError),
+}
+
+impl fmt::Display for ObjError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ObjError::Io(e) => write!(f, "could not read OBJ file: {}", e),
+            ObjError::Parse { line, message } => write!(f, "line {}: {}", line, message),
+            #[cfg(feature = "tobj")]
+            ObjError::Tobj(e) => write!(f, "tobj: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for ObjError {}
+
+impl From<std::io::Error> for ObjError {
+    fn from(e: std::io::Error) -> Self {
+        ObjError::Io(e)
+    }
+}
+
+#[cfg(not(feature = "tobj"))]
+#[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+pub fn load(path: impl AsRef<Path>) -> Result<Mesh, ObjError> {
+    parse(&std::fs::read_to_string(path)?)
+}
+
+#[cfg(feature = "tobj")]
+#[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+pub fn load(path: impl AsRef<Path>) -> Result<Mesh, ObjError> {
+    let options = tobj::LoadOptions {
+        triangulate: true,
+        single_index: true,
+        ..Default::default()
+    };
+    let (models, _materials) = tobj::load_obj(path.as_ref(), &options).map_err(ObjError::Tobj)?;
+
+    // Merge every model (`o`/`g` section) in the file into one mesh
+    let mut mesh = Mesh::default();
+    let mut missing_normals = false;
+    for model in models {
+        let tobj_mesh = model.mesh;
+        let base = mesh.positions.len() as u32;
+        let count = tobj_mesh.positions.len() / 3;
+        for i in 0..count {
+            mesh.positions.push([
+                tobj_mesh.positions[3 * i],
+                tobj_mesh.positions[3 * i + 1],
+                tobj_mesh.positions[3 * i + 2],
+            ]);
+            mesh.normals
+                .push(match tobj_mesh.normals.get(3 * i..3 * i + 3) {
+                    Some(n) => [n[0], n[1], n[2]],
+                    None => [0.0; 3],
+                });
+            mesh.uvs
+                .push(match tobj_mesh.texcoords.get(2 * i..2 * i + 2) {
+                    Some(uv) => [uv[0], 1.0 - uv[1]],
+                    None => [0.0; 2],
+                });
+        }
+        missing_normals |= tobj_mesh.normals.is_empty();
+        mesh.indices
+            .extend(tobj_mesh.indices.iter().map(|&index| base + index));
+    }
+    if missing_normals {
+        mesh.compute_normals();
+    }
+    Ok(mesh)
+}
+
+/// Parse the text of an OBJ file
+#[cfg_attr(all(feature = "tobj", not(target_arch = "wasm32")), allow(dead_code))]
+pub fn parse(source: &str) -> Result<Mesh, ObjError> {
+    let mut positions: Vec<[f32; 3]> = Vec::new();
+    let mut normals: Vec<[f32; 3]> = Vec::new();
+    let mut uvs: Vec<[f32; 2]> = Vec::new();
+    let mut mesh = Mesh::default();
+    // (position, texture coordinate, normal) indices -> vertex index in `mesh`
+    let mut vertices = std::collections::HashMap::new();
+    let mut missing_normals = false;
+
+    for (number, line) in source.lines().enumerate() {
+        let number = number + 1;
+        let error = |message: String| ObjError::Parse {
+            line: number,
+            message,
+        };
+        let line = line.split('#').next().unwrap_or("");
+        let mut words = line.split_whitespace();
+        match words.next() {
+            Some("v") => positions.push(parse_vec3(words).map_err(error)?),
+            Some("vn") => normals.push(parse_vec3(words).map_err(error)?),
+            Some("vt") => uvs.push(parse_uv(words).map_err(error)?),
+            Some("f") => {
+                let mut face = Vec::new();
+                for word in words {
+                    let key = parse_face_vertex(word, [positions.len(), uvs.len(), normals.len()])
+                        .map_err(error)?;
+                    let (position, uv, normal) = key;
+                    missing_normals |= normal.is_none();
+                    let index = *vertices.entry(key).or_insert_with(|| {
+                        mesh.positions.push(positions[position]);
+                        mesh.normals.push(normal.map_or([0.0; 3], |n| normals[n]));
+                        mesh.uvs.push(uv.map_or([0.0; 2], |t| uvs[t]));
+                        mesh.positions.len() as u32 - 1
+                    });
+                    face.push(index);
+                }
+                if face.len() < 3 {
+                    return Err(error(format!("face has {} vertices", face.len())));
+                }
+                for i in 1..face.len() - 1 {
+                    mesh.indices.extend([face[0], face[i], face[i + 1]]);
+                }
+            }
+            // Groups, materials, smoothing groups, ...
+            _ => {}
+        }
+    }
+
+    if missing_normals {
+        mesh.compute_normals();
+    }
+    Ok(mesh)
+}
+
+fn parse_vec3<'a>(mut words: impl Iterator<Item = &'a str>) -> Result<[f32; 3], String> {
+    let mut component = || -> Result<f32, String> {
+        let word = words.next().ok_or("expected 3 numbers")?;
+        word.parse()
+            .map_err(|_| format!("`{}` is not a number", word))
+    };
+    Ok([component()?, component()?, component()?])
+}
+
+/// `u v [w]`, flipped to put (0, 0) at the top left like wgpu does;
+/// OBJ puts it at the bottom left
+fn parse_uv<'a>(mut words: impl Iterator<Item = &'a str>) -> Result<[f32; 2], String> {
+    let mut component = || -> Result<f32, String> {
+        let word = words.next().ok_or("expected 2 numbers")?;
+        word.parse()
+            .map_err(|_| format!("`{}` is not a number", word))
+    };
+    let (u, v) = (component()?, component()?);
+    Ok([u, 1.0 - v])
+}
+
+/// A face vertex's 0-based position, texture coordinate and normal
+/// indices
+type FaceVertex = (usize, Option<usize>, Option<usize>);
+
+/// `v`, `v/vt`, `v//vn` or `v/vt/vn`; `counts` are how many positions,
+/// texture coordinates and normals have been read so far. Negative
+/// indices count back from the most recent entry.
+fn parse_face_vertex(word: &str, counts: [usize; 3]) -> Result<FaceVertex, String> {
+    let mut parts = word.split('/');
+    let position = resolve_index(parts.next().unwrap_or(""), counts[0])?;
+    let mut optional = |count| match parts.next() {
+        Some(part) if !part.is_empty() => resolve_index(part, count).map(Some),
+        _ => Ok(None),
+    };
+    let uv = optional(counts[1])?;
+    let normal = optional(counts[2])?;
+    Ok((position, uv, normal))
+}
+
+fn resolve_index(word: &str, count: usize) -> Result<usize, String> {
+    let index: i64 = word
+        .parse()
+        .map_err(|_| format!("`{}` is not an index", word))?;
+    let resolved = if index < 0 {
+        count as i64 + index
+    } else {
+        index - 1
+    };
+    if (0..count as i64).contains(&resolved) {
+        Ok(resolved as usize)
+    } else {
+        Err(format!("index {} is out of range (1..={})", index, count))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The unit square in the XY plane, counter-clockwise seen from +Z
+    const SQUARE: &str = "v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\n";
+
+    fn parse_error(source: &str) -> (usize, String) {
+        match parse(source) {
+            Err(ObjError::Parse { line, message }) => (line, message),
+            other => panic!("expected a parse error, got {:?}", other.map(|m| m.indices)),
+        }
+    }
+
+    #[test]
+    fn polygons_become_triangle_fans() {
+        let mesh = parse(&format!("{SQUARE}f 1 2 3 4\n")).unwrap();
+        assert_eq!(mesh.positions.len(), 4);
+        assert_eq!(mesh.indices, [0, 1, 2, 0, 2, 3]);
+    }
+
+    #[test]
+    fn negative_indices_count_back_from_the_latest() {
+        let relative = parse(&format!("{SQUARE}f -4 -3 -2 -1\n")).unwrap();
+        let absolute = parse(&format!("{SQUARE}f 1 2 3 4\n")).unwrap();
+        assert_eq!(relative.positions, absolute.positions);
+        assert_eq!(relative.indices, absolute.indices);
+
+        // Relative to what has been read so far, not to the whole file
+        let mesh = parse("v 0 0 0\nv 1 0 0\nv 0 1 0\nf -3 -2 -1\nv 5 5 5\n").unwrap();
+        assert_eq!(
+            mesh.positions,
+            [[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]]
+        );
+    }
+
+    #[test]
+    fn out_of_range_indices_are_errors() {
+        let (line, message) = parse_error(&format!("{SQUARE}f 1 2 5\n"));
+        assert_eq!(line, 5);
+        assert!(message.contains("out of range"), "{}", message);
+
+        // OBJ indices start at 1, and -5 is before the first position
+        parse_error(&format!("{SQUARE}f 0 1 2\n"));
+        parse_error(&format!("{SQUARE}f -5 1 2\n"));
+        // A normal index that doesn't exist, even though the position does
+        parse_error(&format!("{SQUARE}vn 0 0 1\nf 1//1 2//2 3//1\n"));
+    }
+
+    #[test]
+    fn malformed_lines_are_errors() {
+        assert_eq!(parse_error("v 1 2\n").0, 1);
+        assert_eq!(parse_error("v 0 0 0\nvt 0 oops\n").0, 2);
+        assert_eq!(parse_error(&format!("{SQUARE}f 1 2\n")).0, 5);
+    }
+
+    #[test]
+    fn faces_without_normals_get_smooth_ones() {
+        let mesh = parse(&format!("{SQUARE}f 1 2 3 4\n")).unwrap();
+        for normal in &mesh.normals {
+            assert_eq!(*normal, [0.0, 0.0, 1.0]);
+        }
+    }
+
+    #[test]
+    fn given_normals_and_uvs_are_kept() {
+        let source = format!("{SQUARE}vn 0 0 -1\nvt 0 0\nvt 1 0\nvt 1 1\nf 1/1/1 2/2/1 3/3/1\n");
+        let mesh = parse(&source).unwrap();
+        assert_eq!(mesh.normals, [[0.0, 0.0, -1.0]; 3]);
+        // Flipped so (0, 0) is the top left
+        assert_eq!(mesh.uvs, [[0.0, 1.0], [1.0, 1.0], [1.0, 0.0]]);
+    }
+
+    #[test]
+    fn repeated_vertices_are_shared() {
+        let mesh = parse(&format!("{SQUARE}f 1 2 3\nf 1 3 4\n")).unwrap();
+        assert_eq!(mesh.positions.len(), 4);
+        assert_eq!(mesh.indices, [0, 1, 2, 0, 2, 3]);
+
+        // The same position with a different normal is a different vertex
+        let source = format!("{SQUARE}vn 0 0 1\nvn 1 0 0\nf 1//1 2//1 3//1\nf 1//2 3//2 4//2\n");
+        assert_eq!(parse(&source).unwrap().positions.len(), 6);
+    }
+
+    #[test]
+    fn comments_and_unknown_statements_are_skipped() {
+        let source = format!(
+            "# a square\nmtllib square.mtl\no square\n{SQUARE}usemtl red\ns 1\nf 1 2 3 # lower half\n"
+        );
+        assert_eq!(parse(&source).unwrap().indices, [0, 1, 2]);
+    }
+}
@@ -0,0 +1,102 @@
+//! Selecting objects with the mouse by ray casting.
+//!
+//! A pixel on screen is the image of a whole line of points in the world:
+//! everything on the ray from the eye through that pixel. Undoing the
+//! view-projection for the pixel at two depths gives two points on it, and
+//! the first object the ray hits is the one under the cursor.
+//!
+//! Objects are tested in their own model space: the ray is carried in by
+//! the inverse of the object's world matrix, which turns a rotated,
+//! stretched box back into its axis-aligned mesh bounds and an ellipsoid
+//! back into a unit sphere. The direction isn't normalized afterwards, so
+//! a hit's ray parameter `t` means the same in model space as in the world
+//! and hits on different objects can be compared directly.
+
+use cgmath::prelude::*;
+use cgmath::{Matrix4, Point3, Vector3, Vector4};
+
+use crate::culling::Aabb;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Ray {
+    pub origin: Point3<f32>,
+    pub direction: Vector3<f32>,
+}
+
+impl Ray {
+    /// The ray from `eye` through the pixel at `cursor` in a viewport of
+    /// `size` pixels, which `view_proj` projects the world into
+    ///
+    /// Returns `None` if `view_proj` can't be inverted.
+    pub fn from_screen(
+        cursor: (f32, f32),
+        size: (f32, f32),
+        eye: Point3<f32>,
+        view_proj: &Matrix4<f32>,
+    ) -> Option<Self> {
+        // Pixels count down from the top left; NDC runs -1 to 1, y up
+        let x = 2.0 * cursor.0 / size.0 - 1.0;
+        let y = 1.0 - 2.0 * cursor.1 / size.1;
+        // Any depth inside the clip volume names a point on the ray; the
+        // far plane is as good as any
+        let far = view_proj.invert()? * Vector4::new(x, y, 1.0, 1.0);
+        let far = Point3::from_homogeneous(far);
+        Some(Self {
+            origin: eye,
+            direction: (far - eye).normalize(),
+        })
+    }
+
+    /// This ray carried into the space that `matrix` maps into the world
+    pub fn to_local(self, matrix: &Matrix4<f32>) -> Option<Self> {
+        let inverse = matrix.invert()?;
+        Some(Self {
+            origin: inverse.transform_point(self.origin),
+            direction: inverse.transform_vector(self.direction),
+        })
+    }
+
+    /// How far along the ray it first meets the sphere, if it does
+    ///
+    /// Solves `|origin + t direction - center|^2 = radius^2`, a quadratic
+    /// in `t`. A ray starting inside the sphere hits its far side.
+    pub fn intersect_sphere(&self, center: Point3<f32>, radius: f32) -> Option<f32> {
+        let offset = self.origin - center;
+        let a = self.direction.magnitude2();
+        let half_b = offset.dot(self.direction);
+        let c = offset.magnitude2() - radius * radius;
+        let discriminant = half_b * half_b - a * c;
+        if discriminant < 0.0 {
+            return None;
+        }
+        let root = discriminant.sqrt();
+        [(-half_b - root) / a, (-half_b + root) / a]
+            .into_iter()
+            .find(|&t| t >= 0.0)
+    }
+
+    /// How far along the ray it first meets the box, if it does
+    ///
+    /// The slab method: the box is where three slabs overlap, one per axis
+    /// between the planes at `min` and `max`. The ray is inside each slab
+    /// for an interval of `t`, and hits the box if all three intervals
+    /// overlap somewhere in front of the origin.
+    pub fn intersect_aabb(&self, aabb: &Aabb) -> Option<f32> {
+        let mut near = f32::NEG_INFINITY;
+        let mut far = f32::INFINITY;
+        for axis in 0..3 {
+            // Dividing by a zero component gives infinities, which work
+            // out: a ray parallel to a slab is either always inside it or
+            // never
+            let inverse = 1.0 / self.direction[axis];
+            let t0 = (aabb.min[axis] - self.origin[axis]) * inverse;
+            let t1 = (aabb.max[axis] - self.origin[axis]) * inverse;
+            near = near.max(t0.min(t1));
+            far = far.min(t0.max(t1));
+        }
+        if near > far || far < 0.0 {
+            return None;
+        }
+        Some(near.max(0.0))
+    }
+}
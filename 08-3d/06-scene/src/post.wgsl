@@ -0,0 +1,105 @@
+// Post-processing passes. Every pass draws one full-screen triangle and
+// reads the previous stage's output through `input_texture`.
+
+@group(0) @binding(0)
+var input_sampler: sampler;
+
+// What the four numbers mean depends on the pass; see postprocess.rs
+@group(0) @binding(1)
+var<uniform> params: vec4<f32>;
+
+@group(0) @binding(2)
+var input_texture: texture_2d<f32>;
+
+// Only bound by passes that combine two images (the bloom composite)
+@group(0) @binding(3)
+var second_texture: texture_2d<f32>;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+};
+
+// One triangle that covers the screen: (-1, -1), (3, -1) and (-1, 3). The
+// parts outside the screen are clipped away.
+@vertex
+fn vs_fullscreen(@builtin(vertex_index) index: u32) -> VertexOutput {
+    let position = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u)) * 2.0 - 1.0;
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(position, 0.0, 1.0);
+    // Texture v grows downwards, clip y upwards
+    out.uv = vec2<f32>(position.x * 0.5 + 0.5, 0.5 - position.y * 0.5);
+    return out;
+}
+
+// Draw the input unchanged; the final pass into the surface, which
+// converts to sRGB on write
+@fragment
+fn fs_copy(in: VertexOutput) -> @location(0) vec4<f32> {
+    return textureSample(input_texture, input_sampler, in.uv);
+}
+
+// ACES filmic curve (Narkowicz's fit): maps [0, inf) into [0, 1), keeping
+// contrast in the midtones and rolling highlights off gently
+fn aces(color: vec3<f32>) -> vec3<f32> {
+    let a = 2.51;
+    let b = 0.03;
+    let c = 2.43;
+    let d = 0.59;
+    let e = 0.14;
+    return clamp((color * (a * color + b)) / (color * (c * color + d) + e), vec3<f32>(0.0), vec3<f32>(1.0));
+}
+
+// params.x = exposure
+@fragment
+fn fs_tonemap(in: VertexOutput) -> @location(0) vec4<f32> {
+    let hdr = textureSample(input_texture, input_sampler, in.uv).rgb;
+    return vec4<f32>(aces(hdr * params.x), 1.0);
+}
+
+// params.x = strength, params.y = radius where darkening starts,
+// params.z = how far it takes to reach full strength
+@fragment
+fn fs_vignette(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = textureSample(input_texture, input_sampler, in.uv).rgb;
+    let distance = length(in.uv - vec2<f32>(0.5)) * 1.4142135;
+    let darkening = smoothstep(params.y, params.y + params.z, distance) * params.x;
+    return vec4<f32>(color * (1.0 - darkening), 1.0);
+}
+
+// Keep only what's brighter than params.x, fading in over params.y
+// (a soft knee) so the threshold doesn't leave a hard edge
+@fragment
+fn fs_bloom_extract(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = textureSample(input_texture, input_sampler, in.uv).rgb;
+    let brightness = max(max(color.r, color.g), color.b);
+    let weight = smoothstep(params.x, params.x + params.y, brightness);
+    return vec4<f32>(color * weight, 1.0);
+}
+
+// One direction of a separable 9-tap Gaussian blur; params.xy is (1, 0)
+// for horizontal or (0, 1) for vertical. Bilinear filtering between pairs
+// of taps gives 9 taps' worth from 5 samples.
+@fragment
+fn fs_blur(in: VertexOutput) -> @location(0) vec4<f32> {
+    let texel = params.xy / vec2<f32>(textureDimensions(input_texture));
+    // Indexed with a loop counter, so these must be `var`
+    var offsets = array<f32, 3>(0.0, 1.3846153846, 3.2307692308);
+    var weights = array<f32, 3>(0.2270270270, 0.3162162162, 0.0702702703);
+
+    var color = textureSample(input_texture, input_sampler, in.uv).rgb * weights[0];
+    for (var i = 1; i < 3; i++) {
+        let offset = texel * offsets[i];
+        color += textureSample(input_texture, input_sampler, in.uv + offset).rgb * weights[i];
+        color += textureSample(input_texture, input_sampler, in.uv - offset).rgb * weights[i];
+    }
+    return vec4<f32>(color, 1.0);
+}
+
+// The scene plus the blurred highlights, scaled by params.x
+@fragment
+fn fs_bloom_composite(in: VertexOutput) -> @location(0) vec4<f32> {
+    let scene = textureSample(input_texture, input_sampler, in.uv).rgb;
+    let bloom = textureSample(second_texture, input_sampler, in.uv).rgb;
+    return vec4<f32>(scene + bloom * params.x, 1.0);
+}
@@ -0,0 +1,501 @@
+//! Post-processing: effects applied to the rendered image before it's shown.
+//!
+//! The scene is drawn into an offscreen HDR texture ([`HDR_FORMAT`]) instead
+//! of the surface, so lighting can go past 1.0 without clipping. The
+//! [`PostProcessor`] then runs a chain of [`Effect`]s over it, each one a
+//! few full-screen triangle passes reading the previous result. The final
+//! pass copies the result into the surface texture.
+//!
+//! The chain ping-pongs between two HDR textures: each effect reads one and
+//! writes the other. Adding an effect means implementing [`Effect`],
+//! usually with a [`FullscreenPass`] and a fragment shader in post.wgsl,
+//! and inserting it in `PostProcessor::new`. Disabled effects are skipped.
+//!
+//! The default chain:
+//!
+//! 1. [`Bloom`]: blurs the brightest parts of the image and adds them back,
+//!    so highlights glow
+//! 2. [`ToneMap`]: maps HDR values into the displayable 0 to 1 range with
+//!    the ACES filmic curve
+//! 3. [`Vignette`]: darkens towards the corners
+
+use common::renderer::{PipelineBuilder, RenderPassBuilder, UniformBlock};
+
+/// Format of the scene target and the textures between effects
+pub const HDR_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
+
+/// A texture the passes can both render into and sample
+pub struct RenderTexture {
+    #[allow(dead_code)] // only used through its view
+    texture: wgpu::Texture,
+    pub view: wgpu::TextureView,
+}
+
+impl RenderTexture {
+    pub fn new(device: &wgpu::Device, width: u32, height: u32, label: &str) -> Self {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some(label),
+            size: wgpu::Extent3d {
+                width: width.max(1),
+                height: height.max(1),
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: HDR_FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        Self { texture, view }
+    }
+}
+
+/// What an effect needs to record its passes
+pub struct Frame<'a> {
+    pub device: &'a wgpu::Device,
+    pub encoder: &'a mut wgpu::CommandEncoder,
+    /// Linear, clamp-to-edge
+    pub sampler: &'a wgpu::Sampler,
+}
+
+/// One stage of the post-processing chain
+pub trait Effect {
+    /// Shown in the window title when the effect is turned off
+    fn name(&self) -> &'static str;
+
+    /// Recreate anything sized to the window
+    fn resize(&mut self, _device: &wgpu::Device, _width: u32, _height: u32) {}
+
+    /// Record passes that read `input` and write the result to `output`
+    fn apply(&self, frame: &mut Frame, input: &wgpu::TextureView, output: &wgpu::TextureView);
+}
+
+/// A pipeline drawing one full-screen triangle with a fragment shader from
+/// post.wgsl, reading `inputs` textures and a `vec4<f32>` of parameters
+pub struct FullscreenPass {
+    pipeline: wgpu::RenderPipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+    label: &'static str,
+}
+
+impl FullscreenPass {
+    pub fn new(
+        device: &wgpu::Device,
+        shader: &wgpu::ShaderModule,
+        fs_entry: &str,
+        inputs: u32,
+        format: wgpu::TextureFormat,
+        label: &'static str,
+    ) -> Self {
+        let mut entries = vec![
+            wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 1,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: Params::binding_type(),
+                count: None,
+            },
+        ];
+        entries.extend((0..inputs).map(|input| wgpu::BindGroupLayoutEntry {
+            binding: 2 + input,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Texture {
+                sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                view_dimension: wgpu::TextureViewDimension::D2,
+                multisampled: false,
+            },
+            count: None,
+        }));
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some(label),
+            entries: &entries,
+        });
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some(label),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = PipelineBuilder::new(label, &layout, shader)
+            .vertex_entry("vs_fullscreen")
+            .fragment_entry(fs_entry)
+            .color_target(format, None)
+            .build(device);
+        Self {
+            pipeline,
+            bind_group_layout,
+            label,
+        }
+    }
+
+    /// Draw `inputs` through the shader into `output`
+    ///
+    /// The bind group is rebuilt on every call because the inputs change
+    /// from frame to frame as effects are turned on and off; creating one
+    /// is cheap next to a full-screen pass.
+    pub fn draw(
+        &self,
+        frame: &mut Frame,
+        params: &Params,
+        inputs: &[&wgpu::TextureView],
+        output: &wgpu::TextureView,
+    ) {
+        let mut entries = vec![
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::Sampler(frame.sampler),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: params.binding(),
+            },
+        ];
+        entries.extend(
+            inputs
+                .iter()
+                .enumerate()
+                .map(|(index, view)| wgpu::BindGroupEntry {
+                    binding: 2 + index as u32,
+                    resource: wgpu::BindingResource::TextureView(view),
+                }),
+        );
+        let bind_group = frame.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some(self.label),
+            layout: &self.bind_group_layout,
+            entries: &entries,
+        });
+
+        // Every pixel is overwritten
+        let mut pass = RenderPassBuilder::new(self.label)
+            .color(output, wgpu::LoadOp::Clear(wgpu::Color::BLACK))
+            .begin(frame.encoder);
+        pass.set_pipeline(&self.pipeline);
+        pass.set_bind_group(0, &bind_group, &[]);
+        pass.draw(0..3, 0..1);
+    }
+}
+
+/// A uniform holding one pass's `params`
+type Params = UniformBlock<[f32; 4]>;
+
+fn params_buffer(device: &wgpu::Device, params: [f32; 4], label: &str) -> Params {
+    UniformBlock::new(device, &params, label)
+}
+
+/// Glow around bright areas: the parts of the image above a threshold are
+/// blurred at half resolution and added back on top
+pub struct Bloom {
+    extract: FullscreenPass,
+    blur: FullscreenPass,
+    composite: FullscreenPass,
+    extract_params: Params,
+    horizontal_params: Params,
+    vertical_params: Params,
+    composite_params: Params,
+    /// Half-resolution scratch textures the blur ping-pongs between
+    targets: [RenderTexture; 2],
+}
+
+impl Bloom {
+    /// Brightness where the glow starts, and how far above it the glow
+    /// reaches full strength
+    const THRESHOLD: f32 = 1.0;
+    const KNEE: f32 = 0.5;
+    /// How strongly the blurred highlights are added back
+    const INTENSITY: f32 = 0.6;
+
+    pub fn new(
+        device: &wgpu::Device,
+        shader: &wgpu::ShaderModule,
+        width: u32,
+        height: u32,
+    ) -> Self {
+        Self {
+            extract: FullscreenPass::new(
+                device,
+                shader,
+                "fs_bloom_extract",
+                1,
+                HDR_FORMAT,
+                "Bloom Extract",
+            ),
+            blur: FullscreenPass::new(device, shader, "fs_blur", 1, HDR_FORMAT, "Bloom Blur"),
+            composite: FullscreenPass::new(
+                device,
+                shader,
+                "fs_bloom_composite",
+                2,
+                HDR_FORMAT,
+                "Bloom Composite",
+            ),
+            extract_params: params_buffer(
+                device,
+                [Self::THRESHOLD, Self::KNEE, 0.0, 0.0],
+                "Bloom Extract Params",
+            ),
+            horizontal_params: params_buffer(device, [1.0, 0.0, 0.0, 0.0], "Bloom Blur Params"),
+            vertical_params: params_buffer(device, [0.0, 1.0, 0.0, 0.0], "Bloom Blur Params"),
+            composite_params: params_buffer(
+                device,
+                [Self::INTENSITY, 0.0, 0.0, 0.0],
+                "Bloom Composite Params",
+            ),
+            targets: Self::targets(device, width, height),
+        }
+    }
+
+    fn targets(device: &wgpu::Device, width: u32, height: u32) -> [RenderTexture; 2] {
+        // Half resolution: the blur is wider for the same number of taps,
+        // and the bilinear upscale when compositing smooths it further
+        [0, 1].map(|_| RenderTexture::new(device, width / 2, height / 2, "Bloom Texture"))
+    }
+}
+
+impl Effect for Bloom {
+    fn name(&self) -> &'static str {
+        "bloom"
+    }
+
+    fn resize(&mut self, device: &wgpu::Device, width: u32, height: u32) {
+        self.targets = Self::targets(device, width, height);
+    }
+
+    fn apply(&self, frame: &mut Frame, input: &wgpu::TextureView, output: &wgpu::TextureView) {
+        let [a, b] = &self.targets;
+        self.extract
+            .draw(frame, &self.extract_params, &[input], &a.view);
+        self.blur
+            .draw(frame, &self.horizontal_params, &[&a.view], &b.view);
+        self.blur
+            .draw(frame, &self.vertical_params, &[&b.view], &a.view);
+        self.composite
+            .draw(frame, &self.composite_params, &[input, &a.view], output);
+    }
+}
+
+/// HDR to displayable range with the ACES filmic curve
+pub struct ToneMap {
+    pass: FullscreenPass,
+    params: Params,
+}
+
+impl ToneMap {
+    /// Scales the scene's brightness before the curve
+    const EXPOSURE: f32 = 1.0;
+
+    pub fn new(device: &wgpu::Device, shader: &wgpu::ShaderModule) -> Self {
+        Self {
+            pass: FullscreenPass::new(device, shader, "fs_tonemap", 1, HDR_FORMAT, "Tone Map"),
+            params: params_buffer(device, [Self::EXPOSURE, 0.0, 0.0, 0.0], "Tone Map Params"),
+        }
+    }
+}
+
+impl Effect for ToneMap {
+    fn name(&self) -> &'static str {
+        "tone mapping"
+    }
+
+    fn apply(&self, frame: &mut Frame, input: &wgpu::TextureView, output: &wgpu::TextureView) {
+        self.pass.draw(frame, &self.params, &[input], output);
+    }
+}
+
+/// Darkening towards the corners of the screen
+pub struct Vignette {
+    pass: FullscreenPass,
+    params: Params,
+}
+
+impl Vignette {
+    /// Darkening at the corners (0 to 1), and where it starts and how far
+    /// it takes to reach full strength, as fractions of the distance from
+    /// the center to a corner
+    const STRENGTH: f32 = 0.45;
+    const RADIUS: f32 = 0.5;
+    const SOFTNESS: f32 = 0.5;
+
+    pub fn new(device: &wgpu::Device, shader: &wgpu::ShaderModule) -> Self {
+        Self {
+            pass: FullscreenPass::new(device, shader, "fs_vignette", 1, HDR_FORMAT, "Vignette"),
+            params: params_buffer(
+                device,
+                [Self::STRENGTH, Self::RADIUS, Self::SOFTNESS, 0.0],
+                "Vignette Params",
+            ),
+        }
+    }
+}
+
+impl Effect for Vignette {
+    fn name(&self) -> &'static str {
+        "vignette"
+    }
+
+    fn apply(&self, frame: &mut Frame, input: &wgpu::TextureView, output: &wgpu::TextureView) {
+        self.pass.draw(frame, &self.params, &[input], output);
+    }
+}
+
+struct ChainEntry {
+    effect: Box<dyn Effect>,
+    enabled: bool,
+}
+
+/// The offscreen scene target and the effects run over it
+pub struct PostProcessor {
+    /// The scene is rendered (or resolved, with MSAA) into this
+    scene: RenderTexture,
+    /// Effects read one and write the other
+    ping_pong: [RenderTexture; 2],
+    chain: Vec<ChainEntry>,
+    /// Copies the result into the surface texture
+    present: FullscreenPass,
+    present_params: Params,
+    sampler: wgpu::Sampler,
+}
+
+impl PostProcessor {
+    pub fn new(device: &wgpu::Device, config: &wgpu::SurfaceConfiguration) -> Self {
+        Self::with_source(device, config, include_str!("post.wgsl"))
+    }
+
+    /// Rebuild every pass from a new version of post.wgsl, keeping which
+    /// effects are on; on an error nothing changes
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn reload_shader(
+        &mut self,
+        device: &wgpu::Device,
+        config: &wgpu::SurfaceConfiguration,
+        source: &str,
+    ) -> Result<(), String> {
+        let mut reloaded =
+            crate::hot_reload::validated(device, || Self::with_source(device, config, source))?;
+        for (entry, old) in reloaded.chain.iter_mut().zip(&self.chain) {
+            entry.enabled = old.enabled;
+        }
+        *self = reloaded;
+        Ok(())
+    }
+
+    fn with_source(
+        device: &wgpu::Device,
+        config: &wgpu::SurfaceConfiguration,
+        source: &str,
+    ) -> Self {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Post-Processing Shader"),
+            source: wgpu::ShaderSource::Wgsl(source.into()),
+        });
+        let (width, height) = (config.width, config.height);
+
+        // The order effects run in; insert new ones here
+        let effects: Vec<Box<dyn Effect>> = vec![
+            Box::new(Bloom::new(device, &shader, width, height)),
+            Box::new(ToneMap::new(device, &shader)),
+            Box::new(Vignette::new(device, &shader)),
+        ];
+
+        Self {
+            scene: RenderTexture::new(device, width, height, "HDR Scene Texture"),
+            ping_pong: Self::ping_pong(device, width, height),
+            chain: effects
+                .into_iter()
+                .map(|effect| ChainEntry {
+                    effect,
+                    enabled: true,
+                })
+                .collect(),
+            present: FullscreenPass::new(
+                device,
+                &shader,
+                "fs_copy",
+                1,
+                // The format the frame is viewed as, which may be an sRGB
+                // view of a non-sRGB surface
+                config
+                    .view_formats
+                    .first()
+                    .copied()
+                    .unwrap_or(config.format),
+                "Present",
+            ),
+            present_params: params_buffer(device, [0.0; 4], "Present Params"),
+            sampler: device.create_sampler(&wgpu::SamplerDescriptor {
+                label: Some("Post-Processing Sampler"),
+                address_mode_u: wgpu::AddressMode::ClampToEdge,
+                address_mode_v: wgpu::AddressMode::ClampToEdge,
+                mag_filter: wgpu::FilterMode::Linear,
+                min_filter: wgpu::FilterMode::Linear,
+                ..Default::default()
+            }),
+        }
+    }
+
+    fn ping_pong(device: &wgpu::Device, width: u32, height: u32) -> [RenderTexture; 2] {
+        [0, 1].map(|_| RenderTexture::new(device, width, height, "Post-Processing Texture"))
+    }
+
+    /// Where the scene pass draws
+    pub fn scene_view(&self) -> &wgpu::TextureView {
+        &self.scene.view
+    }
+
+    pub fn resize(&mut self, device: &wgpu::Device, width: u32, height: u32) {
+        self.scene = RenderTexture::new(device, width, height, "HDR Scene Texture");
+        self.ping_pong = Self::ping_pong(device, width, height);
+        for entry in &mut self.chain {
+            entry.effect.resize(device, width, height);
+        }
+    }
+
+    /// Turn the effect called `name` on or off; returns whether it's now
+    /// enabled, or `None` if there's no such effect
+    pub fn toggle(&mut self, name: &str) -> Option<bool> {
+        let entry = self
+            .chain
+            .iter_mut()
+            .find(|entry| entry.effect.name() == name)?;
+        entry.enabled = !entry.enabled;
+        Some(entry.enabled)
+    }
+
+    /// Names of the effects that are turned off
+    pub fn disabled(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.chain
+            .iter()
+            .filter(|entry| !entry.enabled)
+            .map(|entry| entry.effect.name())
+    }
+
+    /// Run the enabled effects over the scene texture and write the result
+    /// to `surface`
+    pub fn run(
+        &self,
+        device: &wgpu::Device,
+        encoder: &mut wgpu::CommandEncoder,
+        surface: &wgpu::TextureView,
+    ) {
+        let mut frame = Frame {
+            device,
+            encoder,
+            sampler: &self.sampler,
+        };
+        let mut input = &self.scene.view;
+        let mut next = 0;
+        for entry in self.chain.iter().filter(|entry| entry.enabled) {
+            let output = &self.ping_pong[next].view;
+            entry.effect.apply(&mut frame, input, output);
+            input = output;
+            next = 1 - next;
+        }
+        self.present
+            .draw(&mut frame, &self.present_params, &[input], surface);
+    }
+}
@@ -0,0 +1,309 @@
+//! The scene description file: hand-placed objects and the lights, as JSON.
+//!
+//! Placing an object in code means a recompile for every nudge, so the
+//! objects worth tweaking by hand and the lights live in
+//! `assets/scene.json` instead. The file is read at startup and again
+//! whenever F5 is pressed. Everything generated by code stays in code:
+//! the PBR sphere grid, the row of shapes, the orrery, the dancing crate
+//! and the stress test.
+//!
+//! The types here mirror the file's layout one to one and are turned into
+//! scene graph nodes and [`Light`]s by `State`. Fields that are usually
+//! left alone have defaults and can be omitted, and misspelled fields are
+//! errors rather than being silently ignored:
+//!
+//! ```json
+//! {
+//!   "lights": [
+//!     { "type": "point", "position": [0, 2, 0], "intensity": 5, "range": 6 }
+//!   ],
+//!   "objects": [
+//!     { "name": "Box", "mesh": "cube", "position": [0, 0.5, 0],
+//!       "material": { "color": [0.8, 0.2, 0.2] } }
+//!   ]
+//! }
+//! ```
+
+use std::fmt;
+use std::path::Path;
+
+use cgmath::{Deg, Vector3};
+use serde::Deserialize;
+
+use crate::lights::Light;
+
+/// The most objects a file can place; their uniform and instance slots
+/// are set aside at startup, so reloading never has to grow the buffers
+pub const MAX_OBJECTS: usize = 64;
+
+#[derive(Debug)]
+pub enum SceneFileError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    TooManyObjects(usize),
+}
+
+impl fmt::Display for SceneFileError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SceneFileError::Io(e) => write!(f, "could not read scene file: {}", e),
+            // serde_json's messages already end in "at line L column C"
+            SceneFileError::Json(e) => write!(f, "{}", e),
+            SceneFileError::TooManyObjects(count) => write!(
+                f,
+                "{} objects, but a scene file can hold at most {}",
+                count, MAX_OBJECTS
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SceneFileError {}
+
+impl From<std::io::Error> for SceneFileError {
+    fn from(e: std::io::Error) -> Self {
+        SceneFileError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for SceneFileError {
+    fn from(e: serde_json::Error) -> Self {
+        SceneFileError::Json(e)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SceneFile {
+    /// Light that reaches every surface, scaled by each material's
+    /// `ambient`
+    #[serde(default = "white")]
+    pub ambient: [f32; 3],
+    /// Only the first `MAX_LIGHTS` are used
+    #[serde(default)]
+    pub lights: Vec<LightDesc>,
+    #[serde(default)]
+    pub objects: Vec<ObjectDesc>,
+}
+
+impl SceneFile {
+    pub fn lights(&self) -> Vec<Light> {
+        self.lights.iter().map(LightDesc::to_light).collect()
+    }
+}
+
+/// What's shown when the file can't be read: white ambient light and
+/// nothing else
+impl Default for SceneFile {
+    fn default() -> Self {
+        Self {
+            ambient: white(),
+            lights: Vec::new(),
+            objects: Vec::new(),
+        }
+    }
+}
+
+/// A light, tagged with its kind: `"type": "directional"`, `"point"` or
+/// `"spot"`
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum LightDesc {
+    Directional {
+        /// The direction the light travels in
+        direction: [f32; 3],
+        #[serde(default = "white")]
+        color: [f32; 3],
+        intensity: f32,
+    },
+    Point {
+        position: [f32; 3],
+        #[serde(default = "white")]
+        color: [f32; 3],
+        intensity: f32,
+        range: f32,
+    },
+    Spot {
+        position: [f32; 3],
+        direction: [f32; 3],
+        #[serde(default = "white")]
+        color: [f32; 3],
+        intensity: f32,
+        range: f32,
+        /// Half-angles of the cone, in degrees
+        inner_angle: f32,
+        outer_angle: f32,
+    },
+}
+
+impl LightDesc {
+    pub fn to_light(&self) -> Light {
+        match *self {
+            LightDesc::Directional {
+                direction,
+                color,
+                intensity,
+            } => Light::Directional {
+                direction: direction.into(),
+                color,
+                intensity,
+            },
+            LightDesc::Point {
+                position,
+                color,
+                intensity,
+                range,
+            } => Light::Point {
+                position: position.into(),
+                color,
+                intensity,
+                range,
+            },
+            LightDesc::Spot {
+                position,
+                direction,
+                color,
+                intensity,
+                range,
+                inner_angle,
+                outer_angle,
+            } => Light::Spot {
+                position: position.into(),
+                direction: direction.into(),
+                color,
+                intensity,
+                range,
+                inner_angle: Deg(inner_angle),
+                outer_angle: Deg(outer_angle),
+            },
+        }
+    }
+}
+
+/// One object, placed at the root of the scene graph
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ObjectDesc {
+    /// Reported when the object is picked
+    pub name: String,
+    pub mesh: MeshDesc,
+    #[serde(default)]
+    pub position: [f32; 3],
+    /// Euler angles in radians, applied X then Y then Z
+    #[serde(default)]
+    pub rotation: [f32; 3],
+    #[serde(default = "one")]
+    pub scale: [f32; 3],
+    /// Radians per second around each axis
+    #[serde(default)]
+    pub spin: [f32; 3],
+    #[serde(default)]
+    pub shading: ShadingDesc,
+    /// Drawn with the material color alone if left out
+    #[serde(default)]
+    pub texture: Option<TextureDesc>,
+    #[serde(default)]
+    pub material: MaterialDesc,
+}
+
+impl ObjectDesc {
+    pub fn position(&self) -> Vector3<f32> {
+        self.position.into()
+    }
+
+    pub fn rotation(&self) -> Vector3<f32> {
+        self.rotation.into()
+    }
+
+    pub fn scale(&self) -> Vector3<f32> {
+        self.scale.into()
+    }
+
+    pub fn spin(&self) -> Vector3<f32> {
+        self.spin.into()
+    }
+}
+
+/// One of the generated meshes; loaded models can't be placed from a file
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MeshDesc {
+    Cube,
+    Sphere,
+    Plane,
+    Cylinder,
+    Cone,
+    Torus,
+    Capsule,
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ShadingDesc {
+    #[default]
+    Phong,
+    Pbr,
+}
+
+/// One of the textures in `assets/`
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TextureDesc {
+    Checkerboard,
+    Crate,
+    Brick,
+}
+
+/// A material's lighting parameters; see `Material` for what each does.
+/// Any left out take the defaults below.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MaterialDesc {
+    pub color: [f32; 3],
+    pub ambient: f32,
+    pub diffuse: f32,
+    pub specular: f32,
+    pub shininess: f32,
+    pub metallic: f32,
+    pub roughness: f32,
+    pub ao: f32,
+    pub reflectivity: f32,
+}
+
+impl Default for MaterialDesc {
+    fn default() -> Self {
+        Self {
+            color: white(),
+            ambient: 0.1,
+            diffuse: 1.0,
+            specular: 0.5,
+            shininess: 32.0,
+            metallic: 0.0,
+            roughness: 0.5,
+            ao: 1.0,
+            reflectivity: 0.0,
+        }
+    }
+}
+
+fn white() -> [f32; 3] {
+    [1.0; 3]
+}
+
+fn one() -> [f32; 3] {
+    [1.0; 3]
+}
+
+#[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+pub fn load(path: impl AsRef<Path>) -> Result<SceneFile, SceneFileError> {
+    parse(&std::fs::read_to_string(path)?)
+}
+
+/// Parse the text of a scene file
+pub fn parse(source: &str) -> Result<SceneFile, SceneFileError> {
+    let file: SceneFile = serde_json::from_str(source)?;
+    if file.objects.len() > MAX_OBJECTS {
+        return Err(SceneFileError::TooManyObjects(file.objects.len()));
+    }
+    Ok(file)
+}
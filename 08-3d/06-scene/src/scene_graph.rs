@@ -0,0 +1,247 @@
+//! A hierarchy of transforms.
+//!
+//! Every node has a transform relative to its parent, so moving, turning
+//! or scaling a node carries all of its descendants along. A node's world
+//! matrix is its parent's world matrix times its own local one:
+//!
+//! ```text
+//! world(node) = world(parent) * local(node)
+//! ```
+//!
+//! `visit` works that out for the whole tree in one depth-first walk from
+//! the roots, so each matrix is computed once per frame however deep the
+//! node sits.
+//!
+//! Nodes live in a `Vec` and are named by a [`NodeId`]: an index plus a
+//! generation counter. Removing a node frees its slot for reuse and bumps
+//! the generation, so an old id doesn't silently pick up whatever node
+//! moves in next.
+
+use cgmath::prelude::*;
+use cgmath::{Matrix4, Rad, Vector3};
+
+use crate::animation::Pose;
+use common::timestep::Interpolated;
+
+/// A node's placement relative to its parent
+#[derive(Debug, Clone, Copy)]
+pub struct Transform {
+    pub position: Vector3<f32>,
+    /// Euler angles in radians, applied X then Y then Z
+    pub rotation: Interpolated<Vector3<f32>>,
+    /// Radians per second around each axis
+    pub spin: Vector3<f32>,
+    pub scale: Vector3<f32>,
+}
+
+impl Transform {
+    pub fn new(position: Vector3<f32>) -> Self {
+        Self {
+            position,
+            rotation: Interpolated::new(Vector3::zero()),
+            spin: Vector3::zero(),
+            scale: Vector3::new(1.0, 1.0, 1.0),
+        }
+    }
+
+    pub fn with_rotation(mut self, rotation: Vector3<f32>) -> Self {
+        self.rotation = Interpolated::new(rotation);
+        self
+    }
+
+    pub fn with_spin(mut self, spin: Vector3<f32>) -> Self {
+        self.spin = spin;
+        self
+    }
+
+    pub fn with_scale(mut self, scale: Vector3<f32>) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    /// Advance the spin by one simulation step of `dt` seconds
+    pub fn update(&mut self, dt: f32) {
+        let spin = self.spin * dt;
+        self.rotation.step(|rotation| *rotation += spin);
+    }
+
+    /// Place the node at `pose` outright, leaving nothing to interpolate:
+    /// for transforms driven by something already sampled at the frame's
+    /// own time, such as an animator
+    pub fn set_pose(&mut self, pose: &Pose) {
+        self.position = pose.position;
+        self.rotation = Interpolated::new(pose.rotation);
+        self.scale = pose.scale;
+    }
+
+    /// Local matrix with the rotation interpolated `alpha` of the way from
+    /// the previous simulation step to the current one
+    pub fn matrix(&self, alpha: f32) -> Matrix4<f32> {
+        let rotation = self.rotation.at(alpha);
+        let translation = Matrix4::from_translation(self.position);
+        let rotation = Matrix4::from_angle_x(Rad(rotation.x))
+            * Matrix4::from_angle_y(Rad(rotation.y))
+            * Matrix4::from_angle_z(Rad(rotation.z));
+        let scale = Matrix4::from_nonuniform_scale(self.scale.x, self.scale.y, self.scale.z);
+        translation * rotation * scale
+    }
+}
+
+/// Names a node in a [`SceneGraph`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NodeId {
+    index: usize,
+    generation: u32,
+}
+
+struct Node<T> {
+    transform: Transform,
+    parent: Option<NodeId>,
+    children: Vec<NodeId>,
+    /// What's drawn at the node; `None` for nodes that only group or move
+    /// their children
+    item: Option<T>,
+}
+
+struct Slot<T> {
+    generation: u32,
+    node: Option<Node<T>>,
+}
+
+/// A tree of transforms, each optionally carrying an item to draw
+pub struct SceneGraph<T> {
+    slots: Vec<Slot<T>>,
+    /// Indices of empty slots, reused before the `Vec` grows
+    free: Vec<usize>,
+    roots: Vec<NodeId>,
+}
+
+impl<T> SceneGraph<T> {
+    pub fn new() -> Self {
+        Self {
+            slots: Vec::new(),
+            free: Vec::new(),
+            roots: Vec::new(),
+        }
+    }
+
+    /// Add a node under `parent`, or as a root with `None`
+    ///
+    /// # Panics
+    ///
+    /// If `parent` has been removed.
+    pub fn add(&mut self, parent: Option<NodeId>, transform: Transform, item: Option<T>) -> NodeId {
+        if let Some(parent) = parent {
+            assert!(self.contains(parent), "parent {:?} was removed", parent);
+        }
+        let node = Node {
+            transform,
+            parent,
+            children: Vec::new(),
+            item,
+        };
+        let index = match self.free.pop() {
+            Some(index) => {
+                self.slots[index].node = Some(node);
+                index
+            }
+            None => {
+                self.slots.push(Slot {
+                    generation: 0,
+                    node: Some(node),
+                });
+                self.slots.len() - 1
+            }
+        };
+        let id = NodeId {
+            index,
+            generation: self.slots[index].generation,
+        };
+        match parent {
+            Some(parent) => self.node_mut(parent).children.push(id),
+            None => self.roots.push(id),
+        }
+        id
+    }
+
+    /// Remove `id` and everything below it; returns how many nodes went,
+    /// 0 if `id` was already gone
+    pub fn remove(&mut self, id: NodeId) -> usize {
+        let Some(parent) = self.node(id).map(|node| node.parent) else {
+            return 0;
+        };
+        let siblings = match parent {
+            Some(parent) => &mut self.node_mut(parent).children,
+            None => &mut self.roots,
+        };
+        siblings.retain(|&sibling| sibling != id);
+
+        let mut removed = 0;
+        let mut pending = vec![id];
+        while let Some(id) = pending.pop() {
+            let slot = &mut self.slots[id.index];
+            let node = slot.node.take().expect("children of live nodes are live");
+            slot.generation += 1;
+            self.free.push(id.index);
+            pending.extend(node.children);
+            removed += 1;
+        }
+        removed
+    }
+
+    pub fn contains(&self, id: NodeId) -> bool {
+        self.node(id).is_some()
+    }
+
+    /// The transform of `id` relative to its parent, if it's still there
+    pub fn transform_mut(&mut self, id: NodeId) -> Option<&mut Transform> {
+        self.contains(id).then(|| &mut self.node_mut(id).transform)
+    }
+
+    /// Number of nodes, with or without items
+    pub fn len(&self) -> usize {
+        self.slots.len() - self.free.len()
+    }
+
+    /// Advance every node's transform by one simulation step
+    pub fn update(&mut self, dt: f32) {
+        for node in self.slots.iter_mut().filter_map(|slot| slot.node.as_mut()) {
+            node.transform.update(dt);
+        }
+    }
+
+    /// Call `f` with every item, its node and its world matrix, parents
+    /// before their children, interpolated `alpha` of the way between
+    /// simulation steps
+    pub fn visit<'a>(&'a self, alpha: f32, mut f: impl FnMut(NodeId, &'a T, Matrix4<f32>)) {
+        let mut pending: Vec<(NodeId, Matrix4<f32>)> = self
+            .roots
+            .iter()
+            .rev()
+            .map(|&root| (root, Matrix4::identity()))
+            .collect();
+        while let Some((id, parent_world)) = pending.pop() {
+            let node = self.node(id).expect("the tree only links live nodes");
+            let world = parent_world * node.transform.matrix(alpha);
+            if let Some(item) = &node.item {
+                f(id, item, world);
+            }
+            // Reversed so the first child comes off the stack first
+            pending.extend(node.children.iter().rev().map(|&child| (child, world)));
+        }
+    }
+
+    fn node(&self, id: NodeId) -> Option<&Node<T>> {
+        let slot = self.slots.get(id.index)?;
+        (slot.generation == id.generation)
+            .then_some(slot.node.as_ref())
+            .flatten()
+    }
+
+    /// For ids already known to be live
+    fn node_mut(&mut self, id: NodeId) -> &mut Node<T> {
+        let slot = &mut self.slots[id.index];
+        debug_assert_eq!(slot.generation, id.generation);
+        slot.node.as_mut().expect("node is live")
+    }
+}
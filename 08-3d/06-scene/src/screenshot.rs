@@ -0,0 +1,143 @@
+//! Saving the frame to a PNG file: reading a texture back from the GPU.
+//!
+//! The CPU can't read a texture directly. The frame is copied into a
+//! buffer created with `MAP_READ` (`copy_texture_to_buffer`, recorded with
+//! the rest of the frame), and once the GPU has run that copy the buffer
+//! is mapped into the CPU's address space and read like a slice.
+//!
+//! Two details of the copy:
+//!
+//! - Each row in the buffer must start at a multiple of
+//!   `COPY_BYTES_PER_ROW_ALIGNMENT` (256) bytes, so rows are padded and
+//!   the padding is dropped again when reading them back.
+//! - The surface is usually BGRA rather than RGBA, with the bytes of each
+//!   pixel in the opposite order from what PNG files store.
+//!
+//! Mapping waits for the GPU to finish the frame, which costs a stall of
+//! a frame or so. Encoding the PNG and writing the file take far longer,
+//! so they run on a thread of their own.
+
+use std::path::PathBuf;
+use std::sync::mpsc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const BYTES_PER_PIXEL: u32 = 4;
+
+/// Whether frames stored in `format` can be saved
+pub fn supported(format: wgpu::TextureFormat) -> bool {
+    use wgpu::TextureFormat::*;
+    matches!(
+        format,
+        Rgba8Unorm | Rgba8UnormSrgb | Bgra8Unorm | Bgra8UnormSrgb
+    )
+}
+
+/// A name for a screenshot taken now: `screenshot-<milliseconds since the
+/// Unix epoch>.png`, in the working directory
+pub fn timestamped_path() -> PathBuf {
+    let millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_millis());
+    PathBuf::from(format!("screenshot-{}.png", millis))
+}
+
+/// A copy of a frame on its way back from the GPU
+pub struct Screenshot {
+    buffer: wgpu::Buffer,
+    width: u32,
+    height: u32,
+    /// Row length in the buffer, padded to the copy alignment
+    padded_bytes_per_row: u32,
+    /// Whether the pixels are BGRA and need swapping to RGBA
+    bgra: bool,
+}
+
+impl Screenshot {
+    /// Record a copy of `texture` into a new buffer; the copy happens when
+    /// `encoder` is submitted
+    ///
+    /// # Panics
+    ///
+    /// If `texture` isn't in a [`supported`] format or wasn't created with
+    /// `COPY_SRC`.
+    pub fn capture(
+        device: &wgpu::Device,
+        encoder: &mut wgpu::CommandEncoder,
+        texture: &wgpu::Texture,
+    ) -> Self {
+        let format = texture.format();
+        assert!(supported(format), "can't save {:?} frames", format);
+        let (width, height) = (texture.width(), texture.height());
+        let padded_bytes_per_row =
+            (width * BYTES_PER_PIXEL).next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT);
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Screenshot Buffer"),
+            size: (padded_bytes_per_row * height) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+        encoder.copy_texture_to_buffer(
+            texture.as_image_copy(),
+            wgpu::ImageCopyBuffer {
+                buffer: &buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_bytes_per_row),
+                    rows_per_image: Some(height),
+                },
+            },
+            texture.size(),
+        );
+        Self {
+            buffer,
+            width,
+            height,
+            padded_bytes_per_row,
+            bgra: matches!(
+                format,
+                wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb
+            ),
+        }
+    }
+
+    /// Wait for the copy, which must have been submitted, then write the
+    /// pixels to `path` as a PNG in the background
+    pub fn save(self, device: &wgpu::Device, path: PathBuf) {
+        let slice = self.buffer.slice(..);
+        let (sender, receiver) = mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            // The receiver outlives the wait below
+            let _ = sender.send(result);
+        });
+        // Block until the GPU has run everything submitted so far, which
+        // calls the callback above
+        device.poll(wgpu::Maintain::Wait);
+        if let Err(e) = receiver.recv().expect("polling calls the callback") {
+            log::warn!("could not read the frame back: {}", e);
+            return;
+        }
+
+        let row_bytes = (self.width * BYTES_PER_PIXEL) as usize;
+        let mut pixels = Vec::with_capacity(row_bytes * self.height as usize);
+        {
+            let mapped = slice.get_mapped_range();
+            for row in mapped.chunks_exact(self.padded_bytes_per_row as usize) {
+                pixels.extend_from_slice(&row[..row_bytes]);
+            }
+        }
+        self.buffer.unmap();
+
+        let (width, height, bgra) = (self.width, self.height, self.bgra);
+        std::thread::spawn(move || {
+            if bgra {
+                for pixel in pixels.chunks_exact_mut(BYTES_PER_PIXEL as usize) {
+                    pixel.swap(0, 2);
+                }
+            }
+            match image::save_buffer(&path, &pixels, width, height, image::ColorType::Rgba8) {
+                Ok(()) => log::info!("saved {}", path.display()),
+                Err(e) => log::warn!("could not save {}: {}", path.display(), e),
+            }
+        });
+    }
+}
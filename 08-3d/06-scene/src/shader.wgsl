@@ -1,79 +1,302 @@
 struct Uniforms {
     view: mat4x4<f32>,
     projection: mat4x4<f32>,
-    light_position: vec3<f32>,
-    _padding1: f32,
-    light_color: vec3<f32>,
-    _padding2: f32,
     camera_position: vec3<f32>,
-    _padding3: f32,
+    _padding: f32,
+};
+
+// Must match lights::MAX_LIGHTS
+const MAX_LIGHTS: u32 = 8u;
+
+const LIGHT_DIRECTIONAL: u32 = 0u;
+const LIGHT_POINT: u32 = 1u;
+const LIGHT_SPOT: u32 = 2u;
+
+struct Light {
+    position: vec3<f32>,
+    kind: u32,
+    direction: vec3<f32>,
+    range: f32,
+    color: vec3<f32>,
+    intensity: f32,
+    inner_cos: f32,
+    outer_cos: f32,
+};
+
+struct Lights {
+    ambient: vec3<f32>,
+    count: u32,
+    lights: array<Light, MAX_LIGHTS>,
 };
 
 struct Material {
     color: vec3<f32>,
-    _padding1: f32,
+    highlight: f32,
     ambient: f32,
     diffuse: f32,
     specular: f32,
     shininess: f32,
-};
-
-struct InstanceData {
-    model: mat4x4<f32>,
-    normal_matrix: mat4x4<f32>,
+    metallic: f32,
+    roughness: f32,
+    ao: f32,
+    reflectivity: f32,
 };
 
 @group(0) @binding(0)
 var<uniform> uniforms: Uniforms;
 
+@group(0) @binding(1)
+var<uniform> lights: Lights;
+
+// The sky around the scene, for reflections (see skybox.wgsl)
+@group(0) @binding(2)
+var environment: texture_cube<f32>;
+@group(0) @binding(3)
+var environment_sampler: sampler;
+
 @group(1) @binding(0)
 var<uniform> material: Material;
 
-var<push_constant> instance: InstanceData;
+@group(2) @binding(0)
+var base_texture: texture_2d<f32>;
+@group(2) @binding(1)
+var base_sampler: sampler;
+@group(2) @binding(2)
+var normal_texture: texture_2d<f32>;
 
 struct VertexInput {
     @location(0) position: vec3<f32>,
     @location(1) normal: vec3<f32>,
+    @location(2) uv: vec2<f32>,
+    @location(3) tangent: vec4<f32>,
+}
+
+// Per-instance matrices, one column per location (see InstanceData::desc)
+struct InstanceInput {
+    @location(4) model_0: vec4<f32>,
+    @location(5) model_1: vec4<f32>,
+    @location(6) model_2: vec4<f32>,
+    @location(7) model_3: vec4<f32>,
+    @location(8) normal_0: vec4<f32>,
+    @location(9) normal_1: vec4<f32>,
+    @location(10) normal_2: vec4<f32>,
+    @location(11) normal_3: vec4<f32>,
 }
 
 struct VertexOutput {
     @builtin(position) clip_position: vec4<f32>,
     @location(0) world_position: vec3<f32>,
     @location(1) world_normal: vec3<f32>,
+    @location(2) uv: vec2<f32>,
+    @location(3) world_tangent: vec4<f32>,
 }
 
 @vertex
-fn vs_main(model: VertexInput) -> VertexOutput {
+fn vs_main(model: VertexInput, instance: InstanceInput) -> VertexOutput {
+    let model_matrix = mat4x4<f32>(
+        instance.model_0,
+        instance.model_1,
+        instance.model_2,
+        instance.model_3,
+    );
+    let normal_matrix = mat4x4<f32>(
+        instance.normal_0,
+        instance.normal_1,
+        instance.normal_2,
+        instance.normal_3,
+    );
+
     var out: VertexOutput;
 
-    let world_pos = instance.model * vec4<f32>(model.position, 1.0);
+    let world_pos = model_matrix * vec4<f32>(model.position, 1.0);
     out.world_position = world_pos.xyz;
     out.clip_position = uniforms.projection * uniforms.view * world_pos;
 
-    let world_normal = instance.normal_matrix * vec4<f32>(model.normal, 0.0);
+    let world_normal = normal_matrix * vec4<f32>(model.normal, 0.0);
     out.world_normal = normalize(world_normal.xyz);
+    // Tangents lie in the surface, so they transform like positions do
+    // (no translation), not with the normal matrix
+    let world_tangent = model_matrix * vec4<f32>(model.tangent.xyz, 0.0);
+    out.world_tangent = vec4<f32>(normalize(world_tangent.xyz), model.tangent.w);
+    out.uv = model.uv;
 
     return out;
 }
 
-@fragment
-fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
-    let normal = normalize(in.world_normal);
-    let light_dir = normalize(uniforms.light_position - in.world_position);
-    let view_dir = normalize(uniforms.camera_position - in.world_position);
+// Fades a point or spot light smoothly to zero at its range, with an
+// inverse-square falloff before that
+fn distance_attenuation(distance: f32, range: f32) -> f32 {
+    let ratio = distance / range;
+    let window = clamp(1.0 - ratio * ratio * ratio * ratio, 0.0, 1.0);
+    return window * window / (distance * distance + 1.0);
+}
+
+// What one light delivers to a point: the direction towards it and the
+// light arriving from there after falloff
+struct Incidence {
+    direction: vec3<f32>,
+    radiance: vec3<f32>,
+};
+
+fn incident_light(light: Light, position: vec3<f32>) -> Incidence {
+    var out: Incidence;
+    var attenuation = 1.0;
+    if light.kind == LIGHT_DIRECTIONAL {
+        out.direction = -light.direction;
+    } else {
+        let to_light = light.position - position;
+        let distance = length(to_light);
+        out.direction = to_light / distance;
+        attenuation = distance_attenuation(distance, light.range);
+        if light.kind == LIGHT_SPOT {
+            // 1 inside the inner cone, 0 outside the outer one
+            let cos_angle = dot(-out.direction, light.direction);
+            attenuation *= smoothstep(light.outer_cos, light.inner_cos, cos_angle);
+        }
+    }
+    out.radiance = light.color * light.intensity * attenuation;
+    return out;
+}
 
-    // Phong lighting with material properties
-    let ambient = material.ambient * uniforms.light_color;
+// Diffuse and specular Phong lighting from one light
+fn light_contribution(light: Light, position: vec3<f32>, normal: vec3<f32>, view_dir: vec3<f32>) -> vec3<f32> {
+    let incidence = incident_light(light, position);
+    let light_dir = incidence.direction;
 
     let diffuse_factor = max(dot(normal, light_dir), 0.0);
-    let diffuse = material.diffuse * diffuse_factor * uniforms.light_color;
+    let diffuse = material.diffuse * diffuse_factor;
 
     let reflect_dir = reflect(-light_dir, normal);
     let spec_factor = pow(max(dot(view_dir, reflect_dir), 0.0), material.shininess);
-    let specular = material.specular * spec_factor * uniforms.light_color;
+    let specular = material.specular * spec_factor;
+
+    return (diffuse + specular) * incidence.radiance;
+}
+
+// The mesh normal bent by the normal map
+fn surface_normal(in: VertexOutput) -> vec3<f32> {
+    let n = normalize(in.world_normal);
+    // Interpolation leaves the tangent slightly off perpendicular
+    let t = normalize(in.world_tangent.xyz - n * dot(n, in.world_tangent.xyz));
+    let b = cross(n, t) * in.world_tangent.w;
+    let tangent_to_world = mat3x3<f32>(t, b, n);
+
+    let tangent_normal = textureSample(normal_texture, base_sampler, in.uv).xyz * 2.0 - 1.0;
+    return normalize(tangent_to_world * tangent_normal);
+}
+
+// The glow on a picked object: a faint tint all over, brightest around
+// the silhouette where the surface turns away from the camera
+fn highlight(normal: vec3<f32>, view_dir: vec3<f32>) -> vec3<f32> {
+    let rim = pow(1.0 - max(dot(normal, view_dir), 0.0), 2.0);
+    return vec3<f32>(1.0, 0.75, 0.2) * (0.15 + 1.5 * rim) * material.highlight;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let normal = surface_normal(in);
+    let view_dir = normalize(uniforms.camera_position - in.world_position);
+
+    // Phong lighting with material properties, summed over every light
+    var lighting = material.ambient * lights.ambient;
+    for (var i = 0u; i < lights.count; i++) {
+        lighting += light_contribution(lights.lights[i], in.world_position, normal, view_dir);
+    }
+
+    // Untextured objects sample a white texture, leaving the color as is
+    let base_color = material.color * textureSample(base_texture, base_sampler, in.uv).rgb;
+    let lit_color = lighting * base_color;
+
+    // Mirror-like reflection of the sky, blended in by reflectivity
+    let reflected = textureSample(environment, environment_sampler, reflect(-view_dir, normal)).rgb;
+    let final_color = mix(lit_color, reflected, material.reflectivity);
+
+    return vec4<f32>(final_color + highlight(normal, view_dir), 1.0);
+}
+
+// ============================================================================
+// Metallic-roughness PBR
+// ============================================================================
+
+const PI: f32 = 3.14159265;
+
+// GGX/Trowbridge-Reitz: how many microfacets face along the half vector
+fn distribution_ggx(n_dot_h: f32, roughness: f32) -> f32 {
+    let a = roughness * roughness;
+    let a2 = a * a;
+    let d = n_dot_h * n_dot_h * (a2 - 1.0) + 1.0;
+    return a2 / (PI * d * d);
+}
+
+// Smith with Schlick-GGX: how many of those microfacets are neither
+// shadowed from the light nor hidden from the eye by their neighbours
+fn geometry_smith(n_dot_v: f32, n_dot_l: f32, roughness: f32) -> f32 {
+    let r = roughness + 1.0;
+    let k = r * r / 8.0;
+    let g_v = n_dot_v / (n_dot_v * (1.0 - k) + k);
+    let g_l = n_dot_l / (n_dot_l * (1.0 - k) + k);
+    return g_v * g_l;
+}
+
+// Schlick's Fresnel: reflectance rises towards 1 at grazing angles
+fn fresnel_schlick(cos_theta: f32, f0: vec3<f32>) -> vec3<f32> {
+    return f0 + (1.0 - f0) * pow(clamp(1.0 - cos_theta, 0.0, 1.0), 5.0);
+}
+
+// Fresnel for light arriving from the whole environment rather than one
+// direction: rough surfaces don't brighten as much at grazing angles
+fn fresnel_schlick_roughness(cos_theta: f32, f0: vec3<f32>, roughness: f32) -> vec3<f32> {
+    return f0 + (max(vec3<f32>(1.0 - roughness), f0) - f0) * pow(clamp(1.0 - cos_theta, 0.0, 1.0), 5.0);
+}
+
+@fragment
+fn fs_pbr(in: VertexOutput) -> @location(0) vec4<f32> {
+    let albedo = material.color * textureSample(base_texture, base_sampler, in.uv).rgb;
+    let normal = surface_normal(in);
+    let view_dir = normalize(uniforms.camera_position - in.world_position);
+    let n_dot_v = max(dot(normal, view_dir), 0.0001);
+    // Very low roughness turns point lights into invisible pinpricks
+    let roughness = clamp(material.roughness, 0.04, 1.0);
+
+    // Dielectrics reflect about 4% head-on; metals reflect their own color
+    // and have no diffuse part
+    let f0 = mix(vec3<f32>(0.04), albedo, material.metallic);
+
+    var radiance_out = vec3<f32>(0.0);
+    for (var i = 0u; i < lights.count; i++) {
+        let incidence = incident_light(lights.lights[i], in.world_position);
+        let light_dir = incidence.direction;
+        let n_dot_l = dot(normal, light_dir);
+        if n_dot_l <= 0.0 {
+            continue;
+        }
+        let half_dir = normalize(view_dir + light_dir);
+
+        // Cook-Torrance specular BRDF
+        let d = distribution_ggx(max(dot(normal, half_dir), 0.0), roughness);
+        let g = geometry_smith(n_dot_v, n_dot_l, roughness);
+        let f = fresnel_schlick(max(dot(half_dir, view_dir), 0.0), f0);
+        let specular = d * g * f / (4.0 * n_dot_v * n_dot_l);
+
+        // Light that isn't reflected is refracted and scattered (diffuse)
+        let diffuse = (1.0 - f) * (1.0 - material.metallic) * albedo / PI;
+
+        // The scene's light intensities are what a white Phong surface
+        // facing the light reaches; PI converts them so both pipelines
+        // light alike
+        radiance_out += (diffuse + specular) * incidence.radiance * n_dot_l * PI;
+    }
+
+    let ambient = material.ambient * lights.ambient * albedo * material.ao;
 
-    let lighting = ambient + diffuse + specular;
-    let final_color = lighting * material.color;
+    // Reflected sky: rougher surfaces read blurrier mip levels, and Fresnel
+    // decides how much is reflected (a lot for metals, little for plastic
+    // except at grazing angles)
+    let reflect_dir = reflect(-view_dir, normal);
+    let max_level = f32(textureNumLevels(environment) - 1u);
+    let environment_color = textureSampleLevel(environment, environment_sampler, reflect_dir, roughness * max_level).rgb;
+    let environment_fresnel = fresnel_schlick_roughness(n_dot_v, f0, roughness);
+    let reflection = environment_color * environment_fresnel * material.ao * material.reflectivity;
 
-    return vec4<f32>(final_color, 1.0);
+    return vec4<f32>(ambient + radiance_out + reflection + highlight(normal, view_dir), 1.0);
 }
@@ -0,0 +1,56 @@
+// Skybox: one triangle covering the screen, colored by looking up the view
+// direction through each pixel in the environment cubemap
+
+// Must match `Uniforms` in shader.wgsl
+struct Uniforms {
+    view: mat4x4<f32>,
+    projection: mat4x4<f32>,
+    camera_position: vec3<f32>,
+    _padding: f32,
+};
+
+@group(0) @binding(0)
+var<uniform> uniforms: Uniforms;
+
+@group(0) @binding(2)
+var environment: texture_cube<f32>;
+@group(0) @binding(3)
+var environment_sampler: sampler;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) ndc: vec2<f32>,
+}
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+    // (-1, -1), (3, -1), (-1, 3): a triangle whose inside covers the
+    // whole -1..1 screen square
+    let ndc = vec2<f32>(f32(index & 1u) * 4.0 - 1.0, f32(index >> 1u) * 4.0 - 1.0);
+
+    var out: VertexOutput;
+    // z = w puts every pixel exactly on the far plane (depth 1.0)
+    out.clip_position = vec4<f32>(ndc, 1.0, 1.0);
+    out.ndc = ndc;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    // Undo the projection to get a view-space direction, then undo the
+    // camera's rotation (but not its position: the sky is infinitely far
+    // away, so moving never gets you closer to it)
+    let view_dir = vec3<f32>(
+        in.ndc.x / uniforms.projection[0][0],
+        in.ndc.y / uniforms.projection[1][1],
+        -1.0,
+    );
+    let rotation = mat3x3<f32>(
+        uniforms.view[0].xyz,
+        uniforms.view[1].xyz,
+        uniforms.view[2].xyz,
+    );
+    let world_dir = transpose(rotation) * view_dir;
+
+    return textureSample(environment, environment_sampler, world_dir);
+}
@@ -0,0 +1,336 @@
+//! Textures for the scene's materials.
+//!
+//! Every object is drawn with a texture bound at group 2: textured
+//! materials bind their image, flat-colored ones a 1x1 white texture. The
+//! shader multiplies the sample by the material color either way, so one
+//! pipeline covers both instead of a second pipeline or a shader branch.
+//!
+//! Normal maps work the same way: next to every color texture sits a
+//! normal map, and surfaces without fine detail get a 1x1 "flat" one that
+//! leaves the mesh normal unchanged.
+
+use image::GenericImageView;
+
+/// Image files store sRGB colors; this format makes the GPU convert them
+/// to linear when sampling
+const SRGB: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8UnormSrgb;
+
+pub struct Texture {
+    #[allow(dead_code)] // only used through its view and the bind group
+    pub texture: wgpu::Texture,
+    pub view: wgpu::TextureView,
+    pub sampler: wgpu::Sampler,
+}
+
+impl Texture {
+    /// Decode a PNG or JPEG file's bytes into a texture
+    pub fn from_bytes(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        bytes: &[u8],
+        label: &str,
+    ) -> Result<Self, image::ImageError> {
+        let img = image::load_from_memory(bytes)?;
+        Ok(Self::from_image(device, queue, &img, label))
+    }
+
+    /// Decode a tangent-space normal map
+    ///
+    /// Unlike colors, its texels are vectors (`rgb * 2 - 1`), so it is
+    /// stored as linear `Rgba8Unorm`; the sRGB format would bend them.
+    pub fn normal_map_from_bytes(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        bytes: &[u8],
+        label: &str,
+    ) -> Result<Self, image::ImageError> {
+        let img = image::load_from_memory(bytes)?;
+        let (width, height) = img.dimensions();
+        Ok(Self::from_rgba(
+            device,
+            queue,
+            &img.to_rgba8(),
+            width,
+            height,
+            wgpu::TextureFormat::Rgba8Unorm,
+            label,
+        ))
+    }
+
+    /// A normal map pointing straight out of the surface everywhere
+    pub fn flat_normal_map(device: &wgpu::Device, queue: &wgpu::Queue, label: &str) -> Self {
+        Self::from_rgba(
+            device,
+            queue,
+            &[128, 128, 255, 255],
+            1,
+            1,
+            wgpu::TextureFormat::Rgba8Unorm,
+            label,
+        )
+    }
+
+    pub fn from_image(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        img: &image::DynamicImage,
+        label: &str,
+    ) -> Self {
+        let (width, height) = img.dimensions();
+        Self::from_rgba(device, queue, &img.to_rgba8(), width, height, SRGB, label)
+    }
+
+    /// A single-pixel texture; white turns texturing into a no-op
+    pub fn solid(device: &wgpu::Device, queue: &wgpu::Queue, rgba: [u8; 4], label: &str) -> Self {
+        Self::from_rgba(device, queue, &rgba, 1, 1, SRGB, label)
+    }
+
+    /// A black-and-white checkerboard with `squares` squares per side
+    pub fn checkerboard(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        squares: u32,
+        label: &str,
+    ) -> Self {
+        let size = 256;
+        let square = size / squares;
+        let mut data = Vec::with_capacity((size * size * 4) as usize);
+        for y in 0..size {
+            for x in 0..size {
+                let value = if (x / square + y / square).is_multiple_of(2) {
+                    230
+                } else {
+                    60
+                };
+                data.extend([value, value, value, 255]);
+            }
+        }
+        Self::from_rgba(device, queue, &data, size, size, SRGB, label)
+    }
+
+    /// A cube map from six square images, in wgpu's layer order: +X, -X,
+    /// +Y, -Y, +Z, -Z
+    ///
+    /// Every face gets a full mip chain, each level a box-filtered half of
+    /// the one above. Shaders pick a level with `textureSampleLevel` to
+    /// get a blurrier environment, which stands in for the rough
+    /// reflections a properly prefiltered environment map would give.
+    pub fn cubemap_from_bytes(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        faces: [&[u8]; 6],
+        label: &str,
+    ) -> Result<Self, image::ImageError> {
+        let mut images = Vec::with_capacity(faces.len());
+        for bytes in faces {
+            images.push(image::load_from_memory(bytes)?.to_rgba8());
+        }
+        let size = images[0].width();
+        if images
+            .iter()
+            .any(|image| image.width() != size || image.height() != size)
+        {
+            return Err(image::ImageError::Parameter(
+                image::error::ParameterError::from_kind(
+                    image::error::ParameterErrorKind::DimensionMismatch,
+                ),
+            ));
+        }
+
+        let mip_level_count = u32::BITS - size.leading_zeros();
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some(label),
+            size: wgpu::Extent3d {
+                width: size,
+                height: size,
+                depth_or_array_layers: 6,
+            },
+            mip_level_count,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: SRGB,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+        for (layer, image) in images.iter().enumerate() {
+            for level in 0..mip_level_count {
+                let level_size = (size >> level).max(1);
+                let resized;
+                let pixels = if level == 0 {
+                    image
+                } else {
+                    resized = image::imageops::resize(
+                        image,
+                        level_size,
+                        level_size,
+                        image::imageops::FilterType::Triangle,
+                    );
+                    &resized
+                };
+                queue.write_texture(
+                    wgpu::ImageCopyTexture {
+                        aspect: wgpu::TextureAspect::All,
+                        texture: &texture,
+                        mip_level: level,
+                        origin: wgpu::Origin3d {
+                            x: 0,
+                            y: 0,
+                            z: layer as u32,
+                        },
+                    },
+                    pixels,
+                    wgpu::ImageDataLayout {
+                        offset: 0,
+                        bytes_per_row: Some(4 * level_size),
+                        rows_per_image: Some(level_size),
+                    },
+                    wgpu::Extent3d {
+                        width: level_size,
+                        height: level_size,
+                        depth_or_array_layers: 1,
+                    },
+                );
+            }
+        }
+
+        let view = texture.create_view(&wgpu::TextureViewDescriptor {
+            dimension: Some(wgpu::TextureViewDimension::Cube),
+            ..Default::default()
+        });
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        Ok(Self {
+            texture,
+            view,
+            sampler,
+        })
+    }
+
+    fn from_rgba(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        rgba: &[u8],
+        width: u32,
+        height: u32,
+        format: wgpu::TextureFormat,
+        label: &str,
+    ) -> Self {
+        let size = wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        };
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some(label),
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+        queue.write_texture(
+            wgpu::ImageCopyTexture {
+                aspect: wgpu::TextureAspect::All,
+                texture: &texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+            },
+            rgba,
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(4 * width),
+                rows_per_image: Some(height),
+            },
+            size,
+        );
+
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        // Repeat, so models whose UVs go outside 0..1 tile the texture
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            address_mode_u: wgpu::AddressMode::Repeat,
+            address_mode_v: wgpu::AddressMode::Repeat,
+            address_mode_w: wgpu::AddressMode::Repeat,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Nearest,
+            ..Default::default()
+        });
+
+        Self {
+            texture,
+            view,
+            sampler,
+        }
+    }
+
+    pub fn bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+        device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("texture_bind_group_layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        multisampled: false,
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        multisampled: false,
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                    },
+                    count: None,
+                },
+            ],
+        })
+    }
+
+    /// Bind this texture with `normal_map`; both use this texture's sampler
+    pub fn bind_group(
+        &self,
+        device: &wgpu::Device,
+        layout: &wgpu::BindGroupLayout,
+        normal_map: &Texture,
+    ) -> wgpu::BindGroup {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("texture_bind_group"),
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&self.view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&self.sampler),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::TextureView(&normal_map.view),
+                },
+            ],
+        })
+    }
+}
@@ -0,0 +1,53 @@
+//! What the browser build needs that the native one gets from its
+//! platform: somewhere for log messages and panics to go, and a canvas to
+//! draw into.
+//!
+//! `env_logger` writes to stderr, which a web page doesn't have, so
+//! [`ConsoleLogger`] sends each record to the matching `console` method
+//! instead. Panics would otherwise only show up as an opaque
+//! `unreachable` trap; `console_error_panic_hook` logs their message and
+//! location first.
+
+use wasm_bindgen::JsValue;
+use winit::platform::web::WindowBuilderExtWebSys;
+use winit::window::WindowBuilder;
+
+/// Size of the canvas when the page doesn't style it, in CSS pixels
+const CANVAS_SIZE: winit::dpi::LogicalSize<f64> = winit::dpi::LogicalSize::new(1280.0, 720.0);
+
+struct ConsoleLogger;
+
+impl log::Log for ConsoleLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &log::Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let message = JsValue::from(format!("[{}] {}", record.target(), record.args()));
+        match record.level() {
+            log::Level::Error => web_sys::console::error_1(&message),
+            log::Level::Warn => web_sys::console::warn_1(&message),
+            log::Level::Info => web_sys::console::info_1(&message),
+            log::Level::Debug | log::Level::Trace => web_sys::console::debug_1(&message),
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+/// Send log messages at `info` and above, and panics, to the browser
+/// console
+pub fn init_logging() {
+    console_error_panic_hook::set_once();
+    if log::set_logger(&ConsoleLogger).is_ok() {
+        log::set_max_level(log::LevelFilter::Info);
+    }
+}
+
+/// A window that is a canvas appended to the page's body
+pub fn canvas_window(builder: WindowBuilder) -> WindowBuilder {
+    builder.with_append(true).with_inner_size(CANVAS_SIZE)
+}
@@ -0,0 +1,169 @@
+//! Cascaded shadow maps: fitting one orthographic light projection per
+//! slice of the camera frustum.
+//!
+//! A single shadow map stretched over the whole view wastes most of its
+//! texels on distant ground and leaves nearby shadows blocky. Cascades
+//! split the view distance into slices, short near the camera and longer
+//! further out, and give each slice its own shadow map covering just that
+//! slice. Every cascade has the same resolution, so texel density is
+//! highest where the camera looks closest.
+//!
+//! Each slice is enclosed in a sphere rather than a tight box. The sphere
+//! stays the same size as the camera turns, and its center is snapped to
+//! whole shadow-map texels, so shadow edges don't shimmer when the camera
+//! moves.
+
+use cgmath::prelude::*;
+use cgmath::{Matrix4, Point3, Rad, Vector3};
+use common::camera_controller::Camera;
+
+/// Number of cascades (2-4; the shader's `cascade_splits` is a `vec4`)
+pub const CASCADE_COUNT: usize = 3;
+
+/// How far from the camera shadows reach; beyond it everything is lit
+pub const SHADOW_DISTANCE: f32 = 30.0;
+
+/// Blend between logarithmic (1.0) and even (0.0) splits. Logarithmic
+/// matches perspective best but makes the first cascade tiny.
+const SPLIT_LAMBDA: f32 = 0.6;
+
+/// Extra depth behind each cascade towards the light, so objects outside
+/// the slice still cast shadows into it
+const CASTER_MARGIN: f32 = 20.0;
+
+/// cgmath produces OpenGL clip space (z from -1 to 1); wgpu expects 0 to 1
+#[rustfmt::skip]
+pub const OPENGL_TO_WGPU_MATRIX: Matrix4<f32> = Matrix4::new(
+    1.0, 0.0, 0.0, 0.0,
+    0.0, 1.0, 0.0, 0.0,
+    0.0, 0.0, 0.5, 0.0,
+    0.0, 0.0, 0.5, 1.0,
+);
+
+/// The camera projection the cascades are fitted to
+pub struct Projection {
+    pub fov_y: Rad<f32>,
+    pub aspect: f32,
+    pub near: f32,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Cascade {
+    /// World space to this cascade's shadow map (wgpu clip space)
+    pub view_proj: Matrix4<f32>,
+    /// Distance from the camera along its view direction where the
+    /// cascade ends
+    pub far: f32,
+}
+
+/// Where each cascade ends, blending logarithmic and even splits of
+/// `near..far`
+pub fn split_distances(near: f32, far: f32) -> [f32; CASCADE_COUNT] {
+    std::array::from_fn(|i| {
+        let t = (i + 1) as f32 / CASCADE_COUNT as f32;
+        let logarithmic = near * (far / near).powf(t);
+        let even = near + (far - near) * t;
+        SPLIT_LAMBDA * logarithmic + (1.0 - SPLIT_LAMBDA) * even
+    })
+}
+
+/// Fit every cascade to its slice of the camera's view
+///
+/// `light_direction` is the direction the light travels in.
+pub fn fit(
+    camera: &Camera,
+    projection: &Projection,
+    light_direction: Vector3<f32>,
+    map_size: u32,
+) -> [Cascade; CASCADE_COUNT] {
+    let splits = split_distances(projection.near, SHADOW_DISTANCE);
+    let light_direction = light_direction.normalize();
+    let up = if light_direction.y.abs() > 0.99 {
+        Vector3::unit_z()
+    } else {
+        Vector3::unit_y()
+    };
+    // The light's orientation alone, for snapping to its texel grid
+    let light_rotation = Matrix4::look_to_rh(Point3::origin(), light_direction, up);
+    let inverse_rotation = light_rotation
+        .invert()
+        .expect("a rotation is always invertible");
+
+    std::array::from_fn(|i| {
+        let near = if i == 0 {
+            projection.near
+        } else {
+            splits[i - 1]
+        };
+        let far = splits[i];
+
+        // Bounding sphere of the slice's eight corners
+        let corners = slice_corners(camera, projection, near, far);
+        let center = corners
+            .iter()
+            .fold(Vector3::zero(), |sum, corner| sum + corner.to_vec())
+            / corners.len() as f32;
+        let center = Point3::from_vec(center);
+        let radius = corners
+            .iter()
+            .map(|corner| corner.distance(center))
+            .fold(0.0, f32::max);
+        // Rounding the radius keeps float noise from resizing the map
+        let radius = (radius * 16.0).ceil() / 16.0;
+
+        // Move the center in whole texels, so a moving camera slides the
+        // shadow map by exact texels and the rasterized shadows stay put
+        let texel = 2.0 * radius / map_size as f32;
+        let light_space = light_rotation.transform_point(center);
+        let snapped = Point3::new(
+            (light_space.x / texel).floor() * texel,
+            (light_space.y / texel).floor() * texel,
+            light_space.z,
+        );
+        let center = inverse_rotation.transform_point(snapped);
+
+        let eye = center - light_direction * (radius + CASTER_MARGIN);
+        let view = Matrix4::look_to_rh(eye, light_direction, up);
+        let ortho = cgmath::ortho(
+            -radius,
+            radius,
+            -radius,
+            radius,
+            0.0,
+            2.0 * radius + CASTER_MARGIN,
+        );
+        Cascade {
+            view_proj: OPENGL_TO_WGPU_MATRIX * ortho * view,
+            far,
+        }
+    })
+}
+
+/// The corners of the part of the view frustum between `near` and `far`
+fn slice_corners(
+    camera: &Camera,
+    projection: &Projection,
+    near: f32,
+    far: f32,
+) -> [Point3<f32>; 8] {
+    let eye = camera.position();
+    let forward = camera.forward();
+    let right = camera.right();
+    let up = right.cross(forward);
+    let tan_half_fov = (projection.fov_y.0 / 2.0).tan();
+
+    let mut corners = [eye; 8];
+    for (i, corner) in corners.iter_mut().enumerate() {
+        let distance = if i < 4 { near } else { far };
+        let half_height = distance * tan_half_fov;
+        let half_width = half_height * projection.aspect;
+        let x = if i & 1 == 0 { -half_width } else { half_width };
+        let y = if i & 2 == 0 {
+            -half_height
+        } else {
+            half_height
+        };
+        *corner = eye + forward * distance + right * x + up * y;
+    }
+    corners
+}
@@ -0,0 +1,190 @@
+//! Outlines of the volumes the shadow passes render.
+//!
+//! Every shadow map is drawn through a view-projection matrix, and the
+//! part of the world it records is whatever that matrix maps into the clip
+//! volume: a box for a cascade's orthographic projection, a pyramid cut off
+//! at the near plane for a cube face. Running the eight corners of the clip
+//! volume back through the inverse matrix gives that shape's corners in the
+//! world, and its twelve edges are drawn as lines over the scene.
+//!
+//! The lines are depth tested against the scene but don't write depth, so
+//! they disappear behind the cubes without hiding each other.
+
+use cgmath::prelude::*;
+use cgmath::{Matrix4, Point3, Vector4};
+use common::msaa;
+use common::renderer::{PipelineBuilder, UniformBlock};
+
+/// The most volumes drawn at once: one per cube face
+pub const MAX_FRUSTA: usize = 6;
+
+/// Two vertices per edge, twelve edges per volume
+const MAX_VERTICES: usize = MAX_FRUSTA * 12 * 2;
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct LineVertex {
+    position: [f32; 3],
+    color: [f32; 3],
+}
+
+impl LineVertex {
+    const ATTRIBUTES: [wgpu::VertexAttribute; 2] =
+        wgpu::vertex_attr_array![0 => Float32x3, 1 => Float32x3];
+
+    fn desc() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<LineVertex>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &Self::ATTRIBUTES,
+        }
+    }
+}
+
+pub struct DebugLines {
+    pipeline: wgpu::RenderPipeline,
+    /// Kept to rebuild `pipeline` when the sample count changes
+    layout: wgpu::PipelineLayout,
+    shader: wgpu::ShaderModule,
+    format: wgpu::TextureFormat,
+    vertex_buffer: wgpu::Buffer,
+    vertex_count: u32,
+    /// The camera's view-projection
+    uniforms: UniformBlock<[[f32; 4]; 4]>,
+    bind_group: wgpu::BindGroup,
+}
+
+impl DebugLines {
+    /// Lines drawn in the main pass, into targets of `format` with
+    /// `sample_count` samples per pixel
+    pub fn new(device: &wgpu::Device, format: wgpu::TextureFormat, sample_count: u32) -> Self {
+        let vertex_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Debug Line Vertices"),
+            size: (MAX_VERTICES * std::mem::size_of::<LineVertex>()) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let uniforms =
+            UniformBlock::new(device, &Matrix4::identity().into(), "Debug Line Uniforms");
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("debug_lines_bind_group_layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX,
+                ty: UniformBlock::<[[f32; 4]; 4]>::binding_type(),
+                count: None,
+            }],
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("debug_lines_bind_group"),
+            layout: &bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: uniforms.binding(),
+            }],
+        });
+
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Debug Line Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("debug_lines.wgsl").into()),
+        });
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Debug Line Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = create_pipeline(device, &layout, &shader, format, sample_count);
+
+        Self {
+            pipeline,
+            layout,
+            shader,
+            format,
+            vertex_buffer,
+            vertex_count: 0,
+            uniforms,
+            bind_group,
+        }
+    }
+
+    /// Rebuild the pipeline for a main pass with `sample_count` samples
+    /// per pixel
+    pub fn set_sample_count(&mut self, device: &wgpu::Device, sample_count: u32) {
+        self.pipeline = create_pipeline(
+            device,
+            &self.layout,
+            &self.shader,
+            self.format,
+            sample_count,
+        );
+    }
+
+    /// Replace the lines with the outlines of what each of `frusta`'s
+    /// view-projections sees, in its color, as seen through `view_proj`
+    ///
+    /// # Panics
+    ///
+    /// If there are more than `MAX_FRUSTA` of them.
+    pub fn set_frusta(
+        &mut self,
+        queue: &wgpu::Queue,
+        view_proj: Matrix4<f32>,
+        frusta: &[(Matrix4<f32>, [f32; 3])],
+    ) {
+        assert!(frusta.len() <= MAX_FRUSTA, "too many frusta to outline");
+        let mut vertices = Vec::with_capacity(frusta.len() * 24);
+        for &(frustum, color) in frusta {
+            let Some(inverse) = frustum.invert() else {
+                continue;
+            };
+            // Corner `i` has x, y and z at the low or high end of the clip
+            // volume as bits 0, 1 and 2 of `i` are clear or set
+            let corners: [Point3<f32>; 8] = std::array::from_fn(|i| {
+                let bit = |b: usize, low: f32| if i & b == 0 { low } else { 1.0 };
+                Point3::from_homogeneous(
+                    inverse * Vector4::new(bit(1, -1.0), bit(2, -1.0), bit(4, 0.0), 1.0),
+                )
+            });
+            // An edge joins two corners that differ in one bit
+            for i in 0..8 {
+                for bit in [1, 2, 4] {
+                    if i & bit == 0 {
+                        for corner in [corners[i], corners[i | bit]] {
+                            vertices.push(LineVertex {
+                                position: corner.into(),
+                                color,
+                            });
+                        }
+                    }
+                }
+            }
+        }
+        self.uniforms.write(queue, &view_proj.into());
+        queue.write_buffer(&self.vertex_buffer, 0, bytemuck::cast_slice(&vertices));
+        self.vertex_count = vertices.len() as u32;
+    }
+
+    pub fn draw<'a>(&'a self, pass: &mut wgpu::RenderPass<'a>) {
+        pass.set_pipeline(&self.pipeline);
+        pass.set_bind_group(0, &self.bind_group, &[]);
+        pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+        pass.draw(0..self.vertex_count, 0..1);
+    }
+}
+
+fn create_pipeline(
+    device: &wgpu::Device,
+    layout: &wgpu::PipelineLayout,
+    shader: &wgpu::ShaderModule,
+    format: wgpu::TextureFormat,
+    sample_count: u32,
+) -> wgpu::RenderPipeline {
+    PipelineBuilder::new("Debug Line Pipeline", layout, shader)
+        .vertex_buffers(&[LineVertex::desc()])
+        .color_target(format, Some(wgpu::BlendState::REPLACE))
+        .topology(wgpu::PrimitiveTopology::LineList)
+        .depth(msaa::DEPTH_FORMAT, false, wgpu::CompareFunction::Less)
+        .multisample(msaa::multisample_state(sample_count))
+        .build(device)
+}
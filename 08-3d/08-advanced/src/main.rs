@@ -1,10 +1,29 @@
 use cgmath::prelude::*;
+use common::camera_controller::{Camera, CameraController};
+use common::msaa::{self, RenderTargets};
+use common::renderer::{PipelineBuilder, RenderPassBuilder};
+use common::timestep::{FixedTimestep, Interpolated, STEPS_PER_SECOND};
+use common::uniform_slots::UniformSlots;
+use std::sync::Arc;
 use winit::{
     event::*,
-    event_loop::{ControlFlow, EventLoop},
+    event_loop::EventLoop,
+    keyboard::{Key, KeyCode, NamedKey, PhysicalKey},
     window::{Window, WindowBuilder},
 };
 
+mod cascades;
+mod debug_lines;
+mod overlay;
+mod point_shadows;
+mod shadow_debug;
+
+use cascades::{CASCADE_COUNT, OPENGL_TO_WGPU_MATRIX};
+use debug_lines::DebugLines;
+use overlay::Overlay;
+use point_shadows::{POINT_SHADOW_FAR, POINT_SHADOW_NEAR, POINT_SHADOW_SIZE};
+use shadow_debug::ShadowDebug;
+
 #[repr(C)]
 #[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
 struct Vertex {
@@ -158,56 +177,356 @@ struct RenderUniforms {
     view_proj: [[f32; 4]; 4],
     model: [[f32; 4]; 4],
     normal_matrix: [[f32; 4]; 4],
-    light_view_proj: [[f32; 4]; 4],
-    light_position: [f32; 3],
-    _padding1: f32,
+    cascade_view_proj: [[[f32; 4]; 4]; CASCADE_COUNT],
+    /// Where each cascade ends, as a distance along the view direction;
+    /// entries past `CASCADE_COUNT` are unused
+    cascade_splits: [f32; 4],
+    /// The direction the light travels in
+    light_direction: [f32; 3],
+    /// PCF kernel radius in texels: 0 takes one sample, 1 a 3x3 grid, ...
+    pcf_radius: i32,
     light_color: [f32; 3],
-    _padding2: f32,
+    /// Nonzero tints each cascade a different color
+    show_cascades: u32,
     camera_position: [f32; 3],
-    _padding3: f32,
+    /// Nonzero shows each surface's normal as a color instead of lighting it
+    show_normals: u32,
+    camera_forward: [f32; 3],
+    _padding2: f32,
     object_color: [f32; 3],
-    _padding4: f32,
+    _padding3: f32,
+    light_position: [f32; 3],
+    /// 0 for the directional light, 1 for the point light
+    light_mode: u32,
+    point_shadow_near: f32,
+    point_shadow_far: f32,
+    _padding4: [f32; 2],
+}
+
+/// A cube in the scene
+struct Object {
+    /// Shown next to its color picker in the overlay
+    name: &'static str,
+    position: [f32; 3],
+    scale: [f32; 3],
+    color: [f32; 3],
 }
 
+impl Object {
+    fn model_matrix(&self) -> cgmath::Matrix4<f32> {
+        let [x, y, z] = self.scale;
+        cgmath::Matrix4::from_translation(self.position.into())
+            * cgmath::Matrix4::from_nonuniform_scale(x, y, z)
+    }
+}
+
+const OBJECTS: [Object; 4] = [
+    Object {
+        name: "Ground",
+        position: [0.0, -1.0, 0.0],
+        scale: [10.0, 0.1, 10.0],
+        color: [0.3, 0.3, 0.3],
+    },
+    Object {
+        name: "Red cube",
+        position: [-2.0, 0.5, 0.0],
+        scale: [1.0, 1.0, 1.0],
+        color: [0.8, 0.2, 0.2],
+    },
+    Object {
+        name: "Green cube",
+        position: [0.0, 0.5, 0.0],
+        scale: [1.0, 1.0, 1.0],
+        color: [0.2, 0.8, 0.2],
+    },
+    Object {
+        name: "Blue cube",
+        position: [2.0, 0.5, 0.0],
+        scale: [1.0, 1.0, 1.0],
+        color: [0.2, 0.2, 0.8],
+    },
+];
+
+/// Resolution of each cascade's shadow map
 const SHADOW_MAP_SIZE: u32 = 2048;
 
-struct State {
-    surface: wgpu::Surface,
+/// PCF kernel radius at startup (2 is a 5x5 kernel)
+const DEFAULT_PCF_RADIUS: i32 = 2;
+/// P cycles the kernel radius through 0..=MAX_PCF_RADIUS
+const MAX_PCF_RADIUS: i32 = 3;
+
+const FOV_Y: cgmath::Deg<f32> = cgmath::Deg(45.0);
+const Z_NEAR: f32 = 0.1;
+const Z_FAR: f32 = 100.0;
+
+/// Which light shines on the scene, and so which shadow map is rendered
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LightMode {
+    /// A sun far away, with cascaded shadow maps
+    Directional,
+    /// A lamp circling among the cubes, with a cube map of shadows
+    Point,
+}
+
+impl LightMode {
+    fn name(self) -> &'static str {
+        match self {
+            LightMode::Directional => "directional",
+            LightMode::Point => "point",
+        }
+    }
+}
+
+/// Everything the overlay and the keyboard shortcuts can change
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Settings {
+    light_mode: LightMode,
+    /// Whether the lights orbit the scene; when they don't, the overlay's
+    /// angle slider moves them
+    light_orbiting: bool,
+    /// The sun's height above the horizon, in degrees
+    sun_elevation: f32,
+    /// The point light's height above the origin
+    point_light_height: f32,
+    pcf_radius: i32,
+    show_cascades: bool,
+    /// Whether the main pass multisamples; only honoured if the adapter can
+    msaa: bool,
+    /// Each object's color, in the order of `OBJECTS`
+    object_colors: [[f32; 3]; OBJECTS.len()],
+    /// Whether the overlay shows a picture of the active shadow map
+    show_shadow_map: bool,
+    /// The cascade or cube face that picture shows
+    shadow_map_layer: u32,
+    /// Whether the main pass draws triangle edges only; only honoured if
+    /// the adapter can
+    wireframe: bool,
+    show_normals: bool,
+    /// Whether the volumes the shadow passes see are outlined
+    show_light_frustum: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            light_mode: LightMode::Directional,
+            light_orbiting: true,
+            sun_elevation: 32.0,
+            point_light_height: 1.5,
+            pcf_radius: DEFAULT_PCF_RADIUS,
+            show_cascades: false,
+            msaa: true,
+            object_colors: OBJECTS.map(|object| object.color),
+            show_shadow_map: false,
+            shadow_map_layer: 0,
+            wireframe: false,
+            show_normals: false,
+            show_light_frustum: false,
+        }
+    }
+}
+
+impl Settings {
+    /// The shadow map layer to show, from the active light's map: only
+    /// that one is drawn each frame
+    fn shadow_map_source(&self) -> shadow_debug::Source {
+        match self.light_mode {
+            LightMode::Directional => shadow_debug::Source::Cascade(self.shadow_map_layer),
+            LightMode::Point => shadow_debug::Source::CubeFace(self.shadow_map_layer),
+        }
+    }
+}
+
+/// The overlay's window, with the read-only values it shows
+struct OverlayPanel {
+    fps: f32,
+    frame_time_ms: f32,
+    /// False if the adapter can't multisample, which greys out the toggle
+    msaa_available: bool,
+    /// False if the adapter can't draw polygons as lines
+    wireframe_available: bool,
+    /// The picture of the shadow map
+    shadow_map: egui::TextureId,
+}
+
+impl OverlayPanel {
+    /// Describe the window for this frame, editing `settings` and the
+    /// lights' orbit angle (in radians) in place
+    fn show(&self, context: &egui::Context, settings: &mut Settings, light_angle: &mut f32) {
+        egui::Window::new("Debug")
+            .default_pos([12.0, 12.0])
+            .resizable(false)
+            .show(context, |ui| {
+                ui.label(format!(
+                    "{:.0} FPS ({:.2} ms)",
+                    self.fps, self.frame_time_ms
+                ));
+
+                ui.collapsing("Light", |ui| {
+                    ui.horizontal(|ui| {
+                        ui.radio_value(&mut settings.light_mode, LightMode::Directional, "Sun");
+                        ui.radio_value(&mut settings.light_mode, LightMode::Point, "Point");
+                    });
+                    ui.checkbox(&mut settings.light_orbiting, "Orbit");
+                    // Shown in degrees, wrapped to one turn
+                    let mut degrees = light_angle.rem_euclid(std::f32::consts::TAU).to_degrees();
+                    if ui
+                        .add(egui::Slider::new(&mut degrees, 0.0..=360.0).text("Angle"))
+                        .changed()
+                    {
+                        *light_angle = degrees.to_radians();
+                    }
+                    match settings.light_mode {
+                        LightMode::Directional => ui.add(
+                            egui::Slider::new(&mut settings.sun_elevation, 5.0..=90.0)
+                                .text("Elevation"),
+                        ),
+                        LightMode::Point => ui.add(
+                            egui::Slider::new(&mut settings.point_light_height, 0.2..=5.0)
+                                .text("Height"),
+                        ),
+                    };
+                });
+
+                ui.collapsing("Shadows", |ui| {
+                    ui.add(
+                        egui::Slider::new(&mut settings.pcf_radius, 0..=MAX_PCF_RADIUS)
+                            .text("PCF radius"),
+                    );
+                    ui.add_enabled(
+                        settings.light_mode == LightMode::Directional,
+                        egui::Checkbox::new(&mut settings.show_cascades, "Tint cascades"),
+                    );
+                    ui.add_enabled(
+                        self.msaa_available,
+                        egui::Checkbox::new(&mut settings.msaa, "4x MSAA"),
+                    );
+                });
+
+                ui.collapsing("Materials", |ui| {
+                    for (object, color) in OBJECTS.iter().zip(&mut settings.object_colors) {
+                        ui.horizontal(|ui| {
+                            ui.color_edit_button_rgb(color);
+                            ui.label(object.name);
+                        });
+                    }
+                });
+
+                ui.collapsing("Debug views", |ui| {
+                    ui.add_enabled(
+                        self.wireframe_available,
+                        egui::Checkbox::new(&mut settings.wireframe, "Wireframe"),
+                    );
+                    ui.checkbox(&mut settings.show_normals, "Normals");
+                    ui.checkbox(&mut settings.show_light_frustum, "Light frustum");
+                });
+
+                ui.collapsing("Shadow map", |ui| {
+                    ui.checkbox(&mut settings.show_shadow_map, "Show");
+                    if settings.show_shadow_map {
+                        let (label, layers) = match settings.light_mode {
+                            LightMode::Directional => ("Cascade", CASCADE_COUNT as u32),
+                            LightMode::Point => ("Cube face", 6),
+                        };
+                        settings.shadow_map_layer = settings.shadow_map_layer.min(layers - 1);
+                        ui.add(
+                            egui::Slider::new(&mut settings.shadow_map_layer, 0..=layers - 1)
+                                .text(label),
+                        );
+                        let size = shadow_debug::SIZE as f32;
+                        ui.image((self.shadow_map, egui::vec2(size, size)));
+                    }
+                });
+            });
+    }
+}
+
+const WINDOW_TITLE: &str = "wgpu Advanced - Shadow Mapping";
+
+/// Light orbit speed, in radians per second
+const LIGHT_ORBIT_SPEED: f32 = 0.6;
+
+/// Camera movement speed, in world units per second
+const CAMERA_SPEED: f32 = 5.0;
+/// Camera rotation per pixel of mouse drag, in radians
+const CAMERA_SENSITIVITY: f32 = 0.005;
+
+struct State<'a> {
+    window: Arc<Window>,
+    surface: wgpu::Surface<'a>,
     device: wgpu::Device,
     queue: wgpu::Queue,
     config: wgpu::SurfaceConfiguration,
     size: winit::dpi::PhysicalSize<u32>,
-    window: Window,
 
     // Shadow pass
     shadow_pipeline: wgpu::RenderPipeline,
+    #[allow(dead_code)] // only used through its views and the bind groups
     shadow_texture: wgpu::Texture,
-    shadow_view: wgpu::TextureView,
+    /// One layer of the shadow map array per cascade, to render into
+    shadow_cascade_views: Vec<wgpu::TextureView>,
+    #[allow(dead_code)]
     shadow_sampler: wgpu::Sampler,
     shadow_bind_group: wgpu::BindGroup,
-    shadow_uniform_buffer: wgpu::Buffer,
+    /// One slot per object per cascade: cascade `c`, object `i` is slot
+    /// `c * OBJECTS.len() + i`
+    shadow_uniforms: UniformSlots<ShadowUniforms>,
+    // Point light shadow pass
+    point_shadow_pipeline: wgpu::RenderPipeline,
+    #[allow(dead_code)] // only used through its views and the bind groups
+    point_shadow_texture: wgpu::Texture,
+    /// One view per cube face, to render into
+    point_shadow_face_views: Vec<wgpu::TextureView>,
+    point_shadow_bind_group: wgpu::BindGroup,
+    /// One slot per object per cube face, laid out like `shadow_uniforms`
+    point_shadow_uniforms: UniformSlots<ShadowUniforms>,
+
+    settings: Settings,
+
+    // Debug overlay
+    overlay: Overlay,
+    /// F1 shows and hides the overlay
+    show_overlay: bool,
+    shadow_debug: ShadowDebug,
+    /// `shadow_debug`'s picture, as egui knows it
+    shadow_debug_texture: egui::TextureId,
 
     // Render pass
     render_pipeline: wgpu::RenderPipeline,
+    /// `render_pipeline` drawing triangle edges only; `None` if the
+    /// adapter can't (it needs `Features::POLYGON_MODE_LINE`)
+    wireframe_pipeline: Option<wgpu::RenderPipeline>,
+    /// Kept to rebuild the render pipelines when the sample count changes
+    render_pipeline_layout: wgpu::PipelineLayout,
+    render_shader: wgpu::ShaderModule,
     render_bind_group: wgpu::BindGroup,
-    render_uniform_buffer: wgpu::Buffer,
+    /// One slot per object in `OBJECTS`
+    render_uniforms: UniformSlots<RenderUniforms>,
+    /// Outlines of the light's frusta, drawn over the scene
+    debug_lines: DebugLines,
 
     // Geometry
     vertex_buffer: wgpu::Buffer,
     index_buffer: wgpu::Buffer,
     num_indices: u32,
 
-    // Depth
-    depth_texture: wgpu::Texture,
-    depth_view: wgpu::TextureView,
+    // Render targets
+    /// Depth, plus the multisampled color buffer when MSAA is on
+    render_targets: RenderTargets,
+    /// The sample count MSAA uses when it's on (M toggles it); 1 if the
+    /// adapter can't multisample
+    msaa_samples: u32,
 
     // Animation
-    time: f32,
-    light_angle: f32,
+    light_angle: Interpolated<f32>,
+    camera: Interpolated<Camera>,
+    camera_controller: CameraController,
+    timestep: FixedTimestep,
 }
 
-impl State {
+impl<'a> State<'a> {
     async fn new(window: Window) -> Self {
+        let window = Arc::new(window);
         let size = window.inner_size();
 
         let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
@@ -215,7 +534,7 @@ impl State {
             ..Default::default()
         });
 
-        let surface = unsafe { instance.create_surface(&window) }.unwrap();
+        let surface = instance.create_surface(Arc::clone(&window)).unwrap();
 
         let adapter = instance
             .request_adapter(&wgpu::RequestAdapterOptions {
@@ -226,10 +545,13 @@ impl State {
             .await
             .unwrap();
 
+        // Wireframe rendering is optional in wgpu: ask for it only if the
+        // adapter has it, and grey out the toggle otherwise
+        let wireframe_features = adapter.features() & wgpu::Features::POLYGON_MODE_LINE;
         let (device, queue) = adapter
             .request_device(
                 &wgpu::DeviceDescriptor {
-                    required_features: wgpu::Features::empty(),
+                    required_features: wireframe_features,
                     required_limits: wgpu::Limits::default(),
                     label: None,
                 },
@@ -254,16 +576,17 @@ impl State {
             present_mode: surface_caps.present_modes[0],
             alpha_mode: surface_caps.alpha_modes[0],
             view_formats: vec![],
+            desired_maximum_frame_latency: 2,
         };
         surface.configure(&device, &config);
 
-        // Create shadow map texture
+        // Create the shadow map: a texture array with one layer per cascade
         let shadow_texture = device.create_texture(&wgpu::TextureDescriptor {
             label: Some("Shadow Map"),
             size: wgpu::Extent3d {
                 width: SHADOW_MAP_SIZE,
                 height: SHADOW_MAP_SIZE,
-                depth_or_array_layers: 1,
+                depth_or_array_layers: CASCADE_COUNT as u32,
             },
             mip_level_count: 1,
             sample_count: 1,
@@ -273,7 +596,23 @@ impl State {
             view_formats: &[],
         });
 
-        let shadow_view = shadow_texture.create_view(&wgpu::TextureViewDescriptor::default());
+        // The shadow passes render into one layer at a time; the main pass
+        // samples them all through an array view
+        let shadow_cascade_views = (0..CASCADE_COUNT as u32)
+            .map(|layer| {
+                shadow_texture.create_view(&wgpu::TextureViewDescriptor {
+                    label: Some("Shadow Cascade View"),
+                    dimension: Some(wgpu::TextureViewDimension::D2),
+                    base_array_layer: layer,
+                    array_layer_count: Some(1),
+                    ..Default::default()
+                })
+            })
+            .collect();
+        let shadow_view = shadow_texture.create_view(&wgpu::TextureViewDescriptor {
+            dimension: Some(wgpu::TextureViewDimension::D2Array),
+            ..Default::default()
+        });
 
         let shadow_sampler = device.create_sampler(&wgpu::SamplerDescriptor {
             label: Some("Shadow Sampler"),
@@ -287,22 +626,59 @@ impl State {
             ..Default::default()
         });
 
-        // Create depth texture
-        let depth_texture = device.create_texture(&wgpu::TextureDescriptor {
-            label: Some("Depth Texture"),
+        // The point light's shadow map: a cube map, written one face at a
+        // time and sampled as a cube
+        let point_shadow_texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Point Shadow Map"),
             size: wgpu::Extent3d {
-                width: config.width,
-                height: config.height,
-                depth_or_array_layers: 1,
+                width: POINT_SHADOW_SIZE,
+                height: POINT_SHADOW_SIZE,
+                depth_or_array_layers: 6,
             },
             mip_level_count: 1,
             sample_count: 1,
             dimension: wgpu::TextureDimension::D2,
             format: wgpu::TextureFormat::Depth32Float,
-            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
             view_formats: &[],
         });
-        let depth_view = depth_texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let point_shadow_face_views = (0..6)
+            .map(|face| {
+                point_shadow_texture.create_view(&wgpu::TextureViewDescriptor {
+                    label: Some("Point Shadow Face View"),
+                    dimension: Some(wgpu::TextureViewDimension::D2),
+                    base_array_layer: face,
+                    array_layer_count: Some(1),
+                    ..Default::default()
+                })
+            })
+            .collect();
+        let point_shadow_view = point_shadow_texture.create_view(&wgpu::TextureViewDescriptor {
+            dimension: Some(wgpu::TextureViewDimension::Cube),
+            ..Default::default()
+        });
+
+        // The overlay's shadow-map picture reads both maps one layer at a
+        // time, so it sees the cube map as an array of its six faces
+        let point_shadow_faces_view =
+            point_shadow_texture.create_view(&wgpu::TextureViewDescriptor {
+                dimension: Some(wgpu::TextureViewDimension::D2Array),
+                ..Default::default()
+            });
+        let shadow_debug = ShadowDebug::new(&device, &shadow_view, &point_shadow_faces_view);
+        let mut overlay = Overlay::new(&window, &device, config.format);
+        let shadow_debug_texture = overlay.register_texture(&device, shadow_debug.view());
+
+        // Depth and MSAA color targets for the main pass; the shadow
+        // passes only write depth and don't multisample
+        let sample_count = msaa::supported_sample_count(&adapter, config.format);
+        let render_targets = RenderTargets::new(
+            &device,
+            config.width,
+            config.height,
+            config.format,
+            sample_count,
+        );
 
         // Create geometry
         let (vertices, indices) = create_cube();
@@ -318,37 +694,16 @@ impl State {
         });
         let num_indices = indices.len() as u32;
 
-        // Shadow uniform buffer
-        let shadow_uniforms = ShadowUniforms {
-            light_view_proj: cgmath::Matrix4::identity().into(),
-            model: cgmath::Matrix4::identity().into(),
-        };
-        let shadow_uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("Shadow Uniform Buffer"),
-            contents: bytemuck::cast_slice(&[shadow_uniforms]),
-            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
-        });
-
-        // Render uniform buffer
-        let render_uniforms = RenderUniforms {
-            view_proj: cgmath::Matrix4::identity().into(),
-            model: cgmath::Matrix4::identity().into(),
-            normal_matrix: cgmath::Matrix4::identity().into(),
-            light_view_proj: cgmath::Matrix4::identity().into(),
-            light_position: [5.0, 5.0, 5.0],
-            _padding1: 0.0,
-            light_color: [1.0, 1.0, 0.9],
-            _padding2: 0.0,
-            camera_position: [0.0, 5.0, 10.0],
-            _padding3: 0.0,
-            object_color: [0.8, 0.2, 0.2],
-            _padding4: 0.0,
-        };
-        let render_uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("Render Uniform Buffer"),
-            contents: bytemuck::cast_slice(&[render_uniforms]),
-            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
-        });
+        // Per-object uniforms, one slot per object; each draw selects its
+        // slot with a dynamic offset
+        let shadow_uniforms = UniformSlots::new(
+            &device,
+            CASCADE_COUNT * OBJECTS.len(),
+            "Shadow Uniform Buffer",
+        );
+        let point_shadow_uniforms =
+            UniformSlots::new(&device, 6 * OBJECTS.len(), "Point Shadow Uniform Buffer");
+        let render_uniforms = UniformSlots::new(&device, OBJECTS.len(), "Render Uniform Buffer");
 
         // Shadow bind group layout
         let shadow_bind_group_layout =
@@ -356,11 +711,7 @@ impl State {
                 entries: &[wgpu::BindGroupLayoutEntry {
                     binding: 0,
                     visibility: wgpu::ShaderStages::VERTEX,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Uniform,
-                        has_dynamic_offset: false,
-                        min_binding_size: None,
-                    },
+                    ty: UniformSlots::<ShadowUniforms>::binding_type(),
                     count: None,
                 }],
                 label: Some("shadow_bind_group_layout"),
@@ -370,11 +721,20 @@ impl State {
             layout: &shadow_bind_group_layout,
             entries: &[wgpu::BindGroupEntry {
                 binding: 0,
-                resource: shadow_uniform_buffer.as_entire_binding(),
+                resource: shadow_uniforms.binding(),
             }],
             label: Some("shadow_bind_group"),
         });
 
+        let point_shadow_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &shadow_bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: point_shadow_uniforms.binding(),
+            }],
+            label: Some("point_shadow_bind_group"),
+        });
+
         // Render bind group layout
         let render_bind_group_layout =
             device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
@@ -382,11 +742,7 @@ impl State {
                     wgpu::BindGroupLayoutEntry {
                         binding: 0,
                         visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
-                        ty: wgpu::BindingType::Buffer {
-                            ty: wgpu::BufferBindingType::Uniform,
-                            has_dynamic_offset: false,
-                            min_binding_size: None,
-                        },
+                        ty: UniformSlots::<RenderUniforms>::binding_type(),
                         count: None,
                     },
                     wgpu::BindGroupLayoutEntry {
@@ -394,7 +750,7 @@ impl State {
                         visibility: wgpu::ShaderStages::FRAGMENT,
                         ty: wgpu::BindingType::Texture {
                             sample_type: wgpu::TextureSampleType::Depth,
-                            view_dimension: wgpu::TextureViewDimension::D2,
+                            view_dimension: wgpu::TextureViewDimension::D2Array,
                             multisampled: false,
                         },
                         count: None,
@@ -405,6 +761,16 @@ impl State {
                         ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Comparison),
                         count: None,
                     },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 3,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Texture {
+                            sample_type: wgpu::TextureSampleType::Depth,
+                            view_dimension: wgpu::TextureViewDimension::Cube,
+                            multisampled: false,
+                        },
+                        count: None,
+                    },
                 ],
                 label: Some("render_bind_group_layout"),
             });
@@ -414,7 +780,7 @@ impl State {
             entries: &[
                 wgpu::BindGroupEntry {
                     binding: 0,
-                    resource: render_uniform_buffer.as_entire_binding(),
+                    resource: render_uniforms.binding(),
                 },
                 wgpu::BindGroupEntry {
                     binding: 1,
@@ -424,6 +790,10 @@ impl State {
                     binding: 2,
                     resource: wgpu::BindingResource::Sampler(&shadow_sampler),
                 },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: wgpu::BindingResource::TextureView(&point_shadow_view),
+                },
             ],
             label: Some("render_bind_group"),
         });
@@ -447,38 +817,23 @@ impl State {
                 push_constant_ranges: &[],
             });
 
-        let shadow_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some("Shadow Pipeline"),
-            layout: Some(&shadow_pipeline_layout),
-            vertex: wgpu::VertexState {
-                module: &shadow_shader,
-                entry_point: "vs_main",
-                buffers: &[Vertex::desc()],
-            },
-            fragment: None, // Depth-only pass
-            primitive: wgpu::PrimitiveState {
-                topology: wgpu::PrimitiveTopology::TriangleList,
-                strip_index_format: None,
-                front_face: wgpu::FrontFace::Ccw,
-                cull_mode: Some(wgpu::Face::Back),
-                polygon_mode: wgpu::PolygonMode::Fill,
-                unclipped_depth: false,
-                conservative: false,
-            },
-            depth_stencil: Some(wgpu::DepthStencilState {
-                format: wgpu::TextureFormat::Depth32Float,
-                depth_write_enabled: true,
-                depth_compare: wgpu::CompareFunction::Less,
-                stencil: wgpu::StencilState::default(),
-                bias: wgpu::DepthBiasState {
-                    constant: 2,
-                    slope_scale: 2.0,
-                    clamp: 0.0,
-                },
-            }),
-            multisample: wgpu::MultisampleState::default(),
-            multiview: None,
-        });
+        let shadow_pipeline = create_shadow_pipeline(
+            &device,
+            &shadow_pipeline_layout,
+            &shadow_shader,
+            wgpu::FrontFace::Ccw,
+            "Shadow Pipeline",
+        );
+        // The cube faces' projections mirror the scene (see
+        // point_shadows.rs), which turns counter-clockwise triangles
+        // clockwise
+        let point_shadow_pipeline = create_shadow_pipeline(
+            &device,
+            &shadow_pipeline_layout,
+            &shadow_shader,
+            wgpu::FrontFace::Cw,
+            "Point Shadow Pipeline",
+        );
 
         // Create render pipeline
         let render_pipeline_layout =
@@ -488,42 +843,25 @@ impl State {
                 push_constant_ranges: &[],
             });
 
-        let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some("Render Pipeline"),
-            layout: Some(&render_pipeline_layout),
-            vertex: wgpu::VertexState {
-                module: &render_shader,
-                entry_point: "vs_main",
-                buffers: &[Vertex::desc()],
-            },
-            fragment: Some(wgpu::FragmentState {
-                module: &render_shader,
-                entry_point: "fs_main",
-                targets: &[Some(wgpu::ColorTargetState {
-                    format: config.format,
-                    blend: Some(wgpu::BlendState::REPLACE),
-                    write_mask: wgpu::ColorWrites::ALL,
-                })],
-            }),
-            primitive: wgpu::PrimitiveState {
-                topology: wgpu::PrimitiveTopology::TriangleList,
-                strip_index_format: None,
-                front_face: wgpu::FrontFace::Ccw,
-                cull_mode: Some(wgpu::Face::Back),
-                polygon_mode: wgpu::PolygonMode::Fill,
-                unclipped_depth: false,
-                conservative: false,
-            },
-            depth_stencil: Some(wgpu::DepthStencilState {
-                format: wgpu::TextureFormat::Depth32Float,
-                depth_write_enabled: true,
-                depth_compare: wgpu::CompareFunction::Less,
-                stencil: wgpu::StencilState::default(),
-                bias: wgpu::DepthBiasState::default(),
-            }),
-            multisample: wgpu::MultisampleState::default(),
-            multiview: None,
+        let render_pipeline = create_render_pipeline(
+            &device,
+            &render_pipeline_layout,
+            &render_shader,
+            config.format,
+            sample_count,
+            wgpu::PolygonMode::Fill,
+        );
+        let wireframe_pipeline = (!wireframe_features.is_empty()).then(|| {
+            create_render_pipeline(
+                &device,
+                &render_pipeline_layout,
+                &render_shader,
+                config.format,
+                sample_count,
+                wgpu::PolygonMode::Line,
+            )
         });
+        let debug_lines = DebugLines::new(&device, config.format, sample_count);
 
         Self {
             window,
@@ -534,20 +872,42 @@ impl State {
             size,
             shadow_pipeline,
             shadow_texture,
-            shadow_view,
+            shadow_cascade_views,
             shadow_sampler,
             shadow_bind_group,
-            shadow_uniform_buffer,
+            shadow_uniforms,
+            point_shadow_pipeline,
+            point_shadow_texture,
+            point_shadow_face_views,
+            point_shadow_bind_group,
+            point_shadow_uniforms,
+            settings: Settings {
+                msaa: sample_count > 1,
+                ..Default::default()
+            },
+            overlay,
+            show_overlay: true,
+            shadow_debug,
+            shadow_debug_texture,
             render_pipeline,
+            wireframe_pipeline,
+            render_pipeline_layout,
+            render_shader,
             render_bind_group,
-            render_uniform_buffer,
+            render_uniforms,
+            debug_lines,
             vertex_buffer,
             index_buffer,
             num_indices,
-            depth_texture,
-            depth_view,
-            time: 0.0,
-            light_angle: 0.0,
+            render_targets,
+            msaa_samples: sample_count,
+            light_angle: Interpolated::new(0.0),
+            camera: Interpolated::new(Camera::looking_at(
+                cgmath::Point3::new(0.0, 5.0, 10.0),
+                cgmath::Point3::new(0.0, 0.0, 0.0),
+            )),
+            camera_controller: CameraController::new(CAMERA_SPEED, CAMERA_SENSITIVITY),
+            timestep: FixedTimestep::new(STEPS_PER_SECOND),
         }
     }
 
@@ -562,104 +922,302 @@ impl State {
             self.config.height = new_size.height;
             self.surface.configure(&self.device, &self.config);
 
-            self.depth_texture = self.device.create_texture(&wgpu::TextureDescriptor {
-                label: Some("Depth Texture"),
-                size: wgpu::Extent3d {
-                    width: new_size.width,
-                    height: new_size.height,
-                    depth_or_array_layers: 1,
-                },
-                mip_level_count: 1,
-                sample_count: 1,
-                dimension: wgpu::TextureDimension::D2,
-                format: wgpu::TextureFormat::Depth32Float,
-                usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
-                view_formats: &[],
-            });
-            self.depth_view = self
-                .depth_texture
-                .create_view(&wgpu::TextureViewDescriptor::default());
+            self.render_targets = RenderTargets::new(
+                &self.device,
+                new_size.width,
+                new_size.height,
+                self.config.format,
+                self.render_targets.sample_count(),
+            );
         }
     }
 
-    fn input(&mut self, _event: &WindowEvent) -> bool {
-        false
+    fn input(&mut self, event: &WindowEvent) -> bool {
+        // Clicks and drags on the overlay shouldn't also turn the camera
+        if self.show_overlay && self.overlay.handle_event(&self.window, event) {
+            return true;
+        }
+        if let WindowEvent::KeyboardInput {
+            event:
+                KeyEvent {
+                    physical_key:
+                        PhysicalKey::Code(
+                            key @ (KeyCode::KeyL
+                            | KeyCode::KeyP
+                            | KeyCode::KeyC
+                            | KeyCode::KeyM
+                            | KeyCode::KeyZ
+                            | KeyCode::KeyN
+                            | KeyCode::KeyF
+                            | KeyCode::F1),
+                        ),
+                    state: ElementState::Pressed,
+                    repeat: false,
+                    ..
+                },
+            ..
+        } = event
+        {
+            let previous = self.settings;
+            let settings = &mut self.settings;
+            match key {
+                KeyCode::KeyL => {
+                    settings.light_mode = match settings.light_mode {
+                        LightMode::Directional => LightMode::Point,
+                        LightMode::Point => LightMode::Directional,
+                    }
+                }
+                KeyCode::KeyP => {
+                    settings.pcf_radius = (settings.pcf_radius + 1) % (MAX_PCF_RADIUS + 1)
+                }
+                KeyCode::KeyC => settings.show_cascades = !settings.show_cascades,
+                KeyCode::KeyM => settings.msaa = !settings.msaa,
+                KeyCode::KeyZ => {
+                    settings.wireframe = !settings.wireframe && self.wireframe_pipeline.is_some()
+                }
+                KeyCode::KeyN => settings.show_normals = !settings.show_normals,
+                KeyCode::KeyF => settings.show_light_frustum = !settings.show_light_frustum,
+                _ => self.show_overlay = !self.show_overlay,
+            }
+            self.apply_settings(previous);
+            return true;
+        }
+        self.camera_controller.process_event(event)
     }
 
-    fn update(&mut self) {
-        self.time += 0.016;
-        self.light_angle += 0.01;
+    /// Act on whatever changed in `settings` since `previous`
+    ///
+    /// Most settings are read every frame; only MSAA needs resources
+    /// rebuilt.
+    fn apply_settings(&mut self, previous: Settings) {
+        if self.settings.msaa != previous.msaa {
+            let sample_count = if self.settings.msaa {
+                self.msaa_samples
+            } else {
+                1
+            };
+            self.set_sample_count(sample_count);
+        }
+        let kernel = 2 * self.settings.pcf_radius + 1;
+        let mut views = String::new();
+        for (on, name) in [
+            (self.settings.wireframe, ", wireframe"),
+            (self.settings.show_normals, ", normals"),
+            (self.settings.show_light_frustum, ", light frustum"),
+        ] {
+            if on {
+                views.push_str(name);
+            }
+        }
+        self.window.set_title(&format!(
+            "{} ({} light, PCF {}x{}{}{}{})",
+            WINDOW_TITLE,
+            self.settings.light_mode.name(),
+            kernel,
+            kernel,
+            if self.settings.show_cascades {
+                ", cascades"
+            } else {
+                ""
+            },
+            if self.render_targets.sample_count() == 1 {
+                ", MSAA off"
+            } else {
+                ""
+            },
+            views
+        ));
+    }
 
-        // Light position orbits around scene
-        let light_pos = cgmath::Point3::new(
-            self.light_angle.cos() * 8.0,
-            5.0,
-            self.light_angle.sin() * 8.0,
+    /// Rebuild the render targets and the main pass's pipelines for
+    /// `sample_count` samples per pixel
+    fn set_sample_count(&mut self, sample_count: u32) {
+        self.render_targets = RenderTargets::new(
+            &self.device,
+            self.config.width,
+            self.config.height,
+            self.config.format,
+            sample_count,
+        );
+        self.render_pipeline = create_render_pipeline(
+            &self.device,
+            &self.render_pipeline_layout,
+            &self.render_shader,
+            self.config.format,
+            sample_count,
+            wgpu::PolygonMode::Fill,
         );
+        if self.wireframe_pipeline.is_some() {
+            self.wireframe_pipeline = Some(create_render_pipeline(
+                &self.device,
+                &self.render_pipeline_layout,
+                &self.render_shader,
+                self.config.format,
+                sample_count,
+                wgpu::PolygonMode::Line,
+            ));
+        }
+        self.debug_lines
+            .set_sample_count(&self.device, sample_count);
+    }
+
+    /// Advance the simulation by one fixed step of `dt` seconds
+    fn update(&mut self, dt: f32) {
+        if self.settings.light_orbiting {
+            self.light_angle
+                .step(|angle| *angle += LIGHT_ORBIT_SPEED * dt);
+        }
+        self.camera
+            .step(|camera| self.camera_controller.update_camera(camera, dt));
+    }
 
-        // Light view-projection for shadow map
-        let light_view = cgmath::Matrix4::look_at_rh(
-            light_pos,
-            cgmath::Point3::new(0.0, 0.0, 0.0),
-            cgmath::Vector3::unit_y(),
+    /// Run however many simulation steps the elapsed time calls for
+    fn tick(&mut self) {
+        let dt = self.timestep.dt();
+        for _ in 0..self.timestep.advance() {
+            self.update(dt);
+        }
+    }
+
+    /// Upload this frame's uniforms for every object and cascade, with the
+    /// light and camera interpolated between simulation steps
+    ///
+    /// Everything goes up before any pass is recorded: queued writes all
+    /// land before the submitted passes run, so rewriting one buffer per
+    /// draw would leave every draw with the last object's values.
+    fn update_uniforms(&mut self, light_angle: f32, camera: &Camera) {
+        let settings = &self.settings;
+        // The sun orbits the scene, shining towards its center from low in
+        // the sky
+        let elevation = cgmath::Rad::from(cgmath::Deg(settings.sun_elevation)).0;
+        let light_direction = -cgmath::Vector3::new(
+            light_angle.cos() * elevation.cos(),
+            elevation.sin(),
+            light_angle.sin() * elevation.cos(),
+        );
+        // The point light circles just above the cubes, close enough to
+        // throw their shadows outwards in every direction
+        let light_position = cgmath::Point3::new(
+            light_angle.cos() * 3.0,
+            settings.point_light_height,
+            light_angle.sin() * 3.0,
         );
-        let light_projection = cgmath::ortho(-10.0, 10.0, -10.0, 10.0, 1.0, 20.0);
-        let light_view_proj = light_projection * light_view;
 
         // Camera view-projection
-        let camera_pos = cgmath::Point3::new(0.0, 5.0, 10.0);
-        let view = cgmath::Matrix4::look_at_rh(
-            camera_pos,
-            cgmath::Point3::new(0.0, 0.0, 0.0),
-            cgmath::Vector3::unit_y(),
-        );
         let aspect = self.config.width as f32 / self.config.height as f32;
-        let projection = cgmath::perspective(cgmath::Deg(45.0), aspect, 0.1, 100.0);
-        let view_proj = projection * view;
-
-        // Update shadow uniforms for each object
-        let objects = vec![
-            (
-                cgmath::Vector3::new(0.0, -1.0, 0.0),
-                cgmath::Vector3::new(10.0, 0.1, 10.0),
-            ),
-            (
-                cgmath::Vector3::new(-2.0, 0.5, 0.0),
-                cgmath::Vector3::new(1.0, 1.0, 1.0),
-            ),
-            (
-                cgmath::Vector3::new(0.0, 0.5, 0.0),
-                cgmath::Vector3::new(1.0, 1.0, 1.0),
-            ),
-            (
-                cgmath::Vector3::new(2.0, 0.5, 0.0),
-                cgmath::Vector3::new(1.0, 1.0, 1.0),
-            ),
-        ];
-
-        // Store matrices for render pass
-        let render_uniforms = RenderUniforms {
-            view_proj: view_proj.into(),
-            model: cgmath::Matrix4::identity().into(),
-            normal_matrix: cgmath::Matrix4::identity().into(),
-            light_view_proj: light_view_proj.into(),
-            light_position: light_pos.into(),
-            _padding1: 0.0,
-            light_color: [1.0, 1.0, 0.9],
-            _padding2: 0.0,
-            camera_position: camera_pos.into(),
-            _padding3: 0.0,
-            object_color: [0.8, 0.2, 0.2],
-            _padding4: 0.0,
-        };
-        self.queue.write_buffer(
-            &self.render_uniform_buffer,
-            0,
-            bytemuck::cast_slice(&[render_uniforms]),
+        let projection = OPENGL_TO_WGPU_MATRIX * cgmath::perspective(FOV_Y, aspect, Z_NEAR, Z_FAR);
+        let view_proj = projection * camera.view_matrix();
+
+        // One light view-projection per slice of the camera frustum
+        let cascades = cascades::fit(
+            camera,
+            &cascades::Projection {
+                fov_y: FOV_Y.into(),
+                aspect,
+                near: Z_NEAR,
+            },
+            light_direction,
+            SHADOW_MAP_SIZE,
         );
+        let mut cascade_splits = [0.0; 4];
+        for (split, cascade) in cascade_splits.iter_mut().zip(&cascades) {
+            *split = cascade.far;
+        }
+
+        let shadow_uniforms = shadow_slots(cascades.map(|cascade| cascade.view_proj));
+        let point_shadow_uniforms = shadow_slots(point_shadows::face_view_projs(light_position));
+
+        let mut render_uniforms = Vec::with_capacity(OBJECTS.len());
+        for (object, color) in OBJECTS.iter().zip(settings.object_colors) {
+            let model = object.model_matrix();
+            let normal_matrix = if let Some(inv) = model.invert() {
+                inv.transpose()
+            } else {
+                cgmath::Matrix4::identity()
+            };
+
+            render_uniforms.push(RenderUniforms {
+                view_proj: view_proj.into(),
+                model: model.into(),
+                normal_matrix: normal_matrix.into(),
+                cascade_view_proj: cascades.map(|cascade| cascade.view_proj.into()),
+                cascade_splits,
+                light_direction: light_direction.into(),
+                pcf_radius: settings.pcf_radius,
+                light_color: [1.0, 1.0, 0.9],
+                show_cascades: settings.show_cascades as u32,
+                camera_position: camera.position().into(),
+                show_normals: settings.show_normals as u32,
+                camera_forward: camera.forward().into(),
+                _padding2: 0.0,
+                object_color: color,
+                _padding3: 0.0,
+                light_position: light_position.into(),
+                light_mode: match settings.light_mode {
+                    LightMode::Directional => 0,
+                    LightMode::Point => 1,
+                },
+                point_shadow_near: POINT_SHADOW_NEAR,
+                point_shadow_far: POINT_SHADOW_FAR,
+                _padding4: [0.0; 2],
+            });
+        }
+        if settings.show_light_frustum {
+            // Each cascade in the color the cascade tint gives it
+            let frusta: Vec<_> = match settings.light_mode {
+                LightMode::Directional => cascades
+                    .iter()
+                    .enumerate()
+                    .map(|(index, cascade)| {
+                        let mut tint = [0.0; 3];
+                        tint[index] = 1.0;
+                        (cascade.view_proj, tint)
+                    })
+                    .collect(),
+                LightMode::Point => point_shadows::face_view_projs(light_position)
+                    .map(|face| (face, [1.0, 0.9, 0.3]))
+                    .into(),
+            };
+            self.debug_lines.set_frusta(&self.queue, view_proj, &frusta);
+        }
+        self.shadow_uniforms.write(&self.queue, &shadow_uniforms);
+        self.point_shadow_uniforms
+            .write(&self.queue, &point_shadow_uniforms);
+        self.render_uniforms.write(&self.queue, &render_uniforms);
     }
 
     fn render(&mut self) -> Result<(), wgpu::SurfaceError> {
+        let alpha = self.timestep.alpha();
+        let mut light_angle = self.light_angle.at(alpha);
+        let camera = self.camera.at(alpha);
+
+        // The overlay runs first so its changes show up this frame. It runs
+        // even while hidden, to keep the frame time up to date.
+        let previous = self.settings;
+        let previous_angle = light_angle;
+        let panel = self.show_overlay.then(|| OverlayPanel {
+            fps: self.overlay.fps(),
+            frame_time_ms: self.overlay.frame_time_ms(),
+            msaa_available: self.msaa_samples > 1,
+            wireframe_available: self.wireframe_pipeline.is_some(),
+            shadow_map: self.shadow_debug_texture,
+        });
+        let settings = &mut self.settings;
+        self.overlay.run(&self.window, |context| {
+            if let Some(panel) = &panel {
+                panel.show(context, settings, &mut light_angle);
+            }
+        });
+        if light_angle != previous_angle {
+            // Jump straight there rather than blending from the old angle
+            self.light_angle = Interpolated::new(light_angle);
+        }
+        if self.settings != previous {
+            self.apply_settings(previous);
+        }
+
+        self.update_uniforms(light_angle, &camera);
+
         let output = self.surface.get_current_texture()?;
         let view = output
             .texture
@@ -671,194 +1229,98 @@ impl State {
                 label: Some("Render Encoder"),
             });
 
-        // Shadow pass
-        {
-            let mut shadow_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-                label: Some("Shadow Pass"),
-                color_attachments: &[],
-                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
-                    view: &self.shadow_view,
-                    depth_ops: Some(wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(1.0),
-                        store: wgpu::StoreOp::Store,
-                    }),
-                    stencil_ops: None,
-                }),
-                occlusion_query_set: None,
-                timestamp_writes: None,
-            });
+        // Shadow passes for whichever light is on: one per cascade for the
+        // sun, one per cube face for the point light
+        let (pipeline, bind_group, uniforms, views) = match self.settings.light_mode {
+            LightMode::Directional => (
+                &self.shadow_pipeline,
+                &self.shadow_bind_group,
+                &self.shadow_uniforms,
+                &self.shadow_cascade_views,
+            ),
+            LightMode::Point => (
+                &self.point_shadow_pipeline,
+                &self.point_shadow_bind_group,
+                &self.point_shadow_uniforms,
+                &self.point_shadow_face_views,
+            ),
+        };
+        for (layer, layer_view) in views.iter().enumerate() {
+            let mut shadow_pass = RenderPassBuilder::new("Shadow Pass")
+                .depth(layer_view, wgpu::LoadOp::Clear(1.0))
+                .begin(&mut encoder);
 
-            shadow_pass.set_pipeline(&self.shadow_pipeline);
-            shadow_pass.set_bind_group(0, &self.shadow_bind_group, &[]);
+            shadow_pass.set_pipeline(pipeline);
             shadow_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
             shadow_pass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
 
-            // Draw all objects to shadow map
-            let objects = vec![
-                (
-                    cgmath::Vector3::new(0.0, -1.0, 0.0),
-                    cgmath::Vector3::new(10.0, 0.1, 10.0),
-                ),
-                (
-                    cgmath::Vector3::new(-2.0, 0.5, 0.0),
-                    cgmath::Vector3::new(1.0, 1.0, 1.0),
-                ),
-                (
-                    cgmath::Vector3::new(0.0, 0.5, 0.0),
-                    cgmath::Vector3::new(1.0, 1.0, 1.0),
-                ),
-                (
-                    cgmath::Vector3::new(2.0, 0.5, 0.0),
-                    cgmath::Vector3::new(1.0, 1.0, 1.0),
-                ),
-            ];
-
-            for (pos, scale) in objects {
-                let model = cgmath::Matrix4::from_translation(pos)
-                    * cgmath::Matrix4::from_nonuniform_scale(scale.x, scale.y, scale.z);
-
-                // Update shadow uniforms
-                let light_pos = cgmath::Point3::new(
-                    self.light_angle.cos() * 8.0,
-                    5.0,
-                    self.light_angle.sin() * 8.0,
-                );
-                let light_view = cgmath::Matrix4::look_at_rh(
-                    light_pos,
-                    cgmath::Point3::new(0.0, 0.0, 0.0),
-                    cgmath::Vector3::unit_y(),
-                );
-                let light_projection = cgmath::ortho(-10.0, 10.0, -10.0, 10.0, 1.0, 20.0);
-                let light_view_proj = light_projection * light_view;
-
-                let shadow_uniforms = ShadowUniforms {
-                    light_view_proj: light_view_proj.into(),
-                    model: model.into(),
-                };
-                self.queue.write_buffer(
-                    &self.shadow_uniform_buffer,
-                    0,
-                    bytemuck::cast_slice(&[shadow_uniforms]),
-                );
-
+            // Draw all objects into this layer of the shadow map
+            for index in 0..OBJECTS.len() {
+                let slot = layer * OBJECTS.len() + index;
+                shadow_pass.set_bind_group(0, bind_group, &[uniforms.offset(slot)]);
                 shadow_pass.draw_indexed(0..self.num_indices, 0, 0..1);
             }
         }
 
+        if self.show_overlay && self.settings.show_shadow_map {
+            self.shadow_debug.draw(
+                &self.queue,
+                &mut encoder,
+                self.settings.shadow_map_source(),
+                POINT_SHADOW_NEAR,
+                POINT_SHADOW_FAR,
+            );
+        }
+
         // Render pass
         {
-            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-                label: Some("Render Pass"),
-                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view: &view,
-                    resolve_target: None,
-                    ops: wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(wgpu::Color {
-                            r: 0.1,
-                            g: 0.1,
-                            b: 0.15,
-                            a: 1.0,
-                        }),
-                        store: wgpu::StoreOp::Store,
-                    },
-                })],
-                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
-                    view: &self.depth_view,
-                    depth_ops: Some(wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(1.0),
-                        store: wgpu::StoreOp::Store,
+            let mut render_pass = RenderPassBuilder::new("Render Pass")
+                // With MSAA this draws into the multisampled texture and
+                // resolves it into the surface texture at the end of the pass
+                .color_attachment(self.render_targets.color_attachment(
+                    &view,
+                    wgpu::LoadOp::Clear(wgpu::Color {
+                        r: 0.1,
+                        g: 0.1,
+                        b: 0.15,
+                        a: 1.0,
                     }),
-                    stencil_ops: None,
-                }),
-                occlusion_query_set: None,
-                timestamp_writes: None,
-            });
-
-            render_pass.set_pipeline(&self.render_pipeline);
-            render_pass.set_bind_group(0, &self.render_bind_group, &[]);
+                ))
+                .depth(self.render_targets.depth_view(), wgpu::LoadOp::Clear(1.0))
+                .begin(&mut encoder);
+
+            let pipeline = match &self.wireframe_pipeline {
+                Some(wireframe) if self.settings.wireframe => wireframe,
+                _ => &self.render_pipeline,
+            };
+            render_pass.set_pipeline(pipeline);
             render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
             render_pass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
 
             // Draw all objects with shadows
-            let objects = vec![
-                (
-                    cgmath::Vector3::new(0.0, -1.0, 0.0),
-                    cgmath::Vector3::new(10.0, 0.1, 10.0),
-                    [0.3, 0.3, 0.3],
-                ),
-                (
-                    cgmath::Vector3::new(-2.0, 0.5, 0.0),
-                    cgmath::Vector3::new(1.0, 1.0, 1.0),
-                    [0.8, 0.2, 0.2],
-                ),
-                (
-                    cgmath::Vector3::new(0.0, 0.5, 0.0),
-                    cgmath::Vector3::new(1.0, 1.0, 1.0),
-                    [0.2, 0.8, 0.2],
-                ),
-                (
-                    cgmath::Vector3::new(2.0, 0.5, 0.0),
-                    cgmath::Vector3::new(1.0, 1.0, 1.0),
-                    [0.2, 0.2, 0.8],
-                ),
-            ];
-
-            for (pos, scale, color) in objects {
-                let model = cgmath::Matrix4::from_translation(pos)
-                    * cgmath::Matrix4::from_nonuniform_scale(scale.x, scale.y, scale.z);
-                let normal_matrix = if let Some(inv) = model.invert() {
-                    inv.transpose()
-                } else {
-                    cgmath::Matrix4::identity()
-                };
-
-                let light_pos = cgmath::Point3::new(
-                    self.light_angle.cos() * 8.0,
-                    5.0,
-                    self.light_angle.sin() * 8.0,
-                );
-                let light_view = cgmath::Matrix4::look_at_rh(
-                    light_pos,
-                    cgmath::Point3::new(0.0, 0.0, 0.0),
-                    cgmath::Vector3::unit_y(),
-                );
-                let light_projection = cgmath::ortho(-10.0, 10.0, -10.0, 10.0, 1.0, 20.0);
-                let light_view_proj = light_projection * light_view;
-
-                let camera_pos = cgmath::Point3::new(0.0, 5.0, 10.0);
-                let view = cgmath::Matrix4::look_at_rh(
-                    camera_pos,
-                    cgmath::Point3::new(0.0, 0.0, 0.0),
-                    cgmath::Vector3::unit_y(),
-                );
-                let aspect = self.config.width as f32 / self.config.height as f32;
-                let projection = cgmath::perspective(cgmath::Deg(45.0), aspect, 0.1, 100.0);
-                let view_proj = projection * view;
-
-                let render_uniforms = RenderUniforms {
-                    view_proj: view_proj.into(),
-                    model: model.into(),
-                    normal_matrix: normal_matrix.into(),
-                    light_view_proj: light_view_proj.into(),
-                    light_position: light_pos.into(),
-                    _padding1: 0.0,
-                    light_color: [1.0, 1.0, 0.9],
-                    _padding2: 0.0,
-                    camera_position: camera_pos.into(),
-                    _padding3: 0.0,
-                    object_color: color,
-                    _padding4: 0.0,
-                };
-                self.queue.write_buffer(
-                    &self.render_uniform_buffer,
+            for index in 0..OBJECTS.len() {
+                render_pass.set_bind_group(
                     0,
-                    bytemuck::cast_slice(&[render_uniforms]),
+                    &self.render_bind_group,
+                    &[self.render_uniforms.offset(index)],
                 );
-
                 render_pass.draw_indexed(0..self.num_indices, 0, 0..1);
             }
+
+            if self.settings.show_light_frustum {
+                self.debug_lines.draw(&mut render_pass);
+            }
         }
 
+        // The overlay goes on top of the resolved frame
+        self.overlay.paint(
+            &self.device,
+            &self.queue,
+            &mut encoder,
+            &view,
+            [self.config.width, self.config.height],
+        );
+
         self.queue.submit(std::iter::once(encoder.finish()));
         output.present();
 
@@ -866,58 +1328,119 @@ impl State {
     }
 }
 
+/// The main pass's pipeline, drawing into targets with `sample_count`
+/// samples per pixel; `PolygonMode::Line` draws only the triangles' edges
+fn create_render_pipeline(
+    device: &wgpu::Device,
+    layout: &wgpu::PipelineLayout,
+    shader: &wgpu::ShaderModule,
+    format: wgpu::TextureFormat,
+    sample_count: u32,
+    polygon_mode: wgpu::PolygonMode,
+) -> wgpu::RenderPipeline {
+    let label = match polygon_mode {
+        wgpu::PolygonMode::Fill => "Render Pipeline",
+        _ => "Wireframe Pipeline",
+    };
+    PipelineBuilder::new(label, layout, shader)
+        .vertex_buffers(&[Vertex::desc()])
+        .color_target(format, Some(wgpu::BlendState::REPLACE))
+        .cull_back(wgpu::FrontFace::Ccw)
+        .polygon_mode(polygon_mode)
+        .depth(msaa::DEPTH_FORMAT, true, wgpu::CompareFunction::Less)
+        .multisample(msaa::multisample_state(sample_count))
+        .build(device)
+}
+
+/// A depth-only pipeline for the shadow passes
+fn create_shadow_pipeline(
+    device: &wgpu::Device,
+    layout: &wgpu::PipelineLayout,
+    shader: &wgpu::ShaderModule,
+    front_face: wgpu::FrontFace,
+    label: &str,
+) -> wgpu::RenderPipeline {
+    PipelineBuilder::new(label, layout, shader)
+        .depth_only()
+        .vertex_buffers(&[Vertex::desc()])
+        .cull_back(front_face)
+        .depth(
+            wgpu::TextureFormat::Depth32Float,
+            true,
+            wgpu::CompareFunction::Less,
+        )
+        .depth_bias(wgpu::DepthBiasState {
+            constant: 2,
+            slope_scale: 2.0,
+            clamp: 0.0,
+        })
+        .build(device)
+}
+
+/// Shadow pass uniforms for every object in every layer of a shadow map:
+/// layer `l`, object `i` is slot `l * OBJECTS.len() + i`
+fn shadow_slots<const LAYERS: usize>(
+    view_projs: [cgmath::Matrix4<f32>; LAYERS],
+) -> Vec<ShadowUniforms> {
+    let mut slots = Vec::with_capacity(LAYERS * OBJECTS.len());
+    for view_proj in view_projs {
+        for object in &OBJECTS {
+            slots.push(ShadowUniforms {
+                light_view_proj: view_proj.into(),
+                model: object.model_matrix().into(),
+            });
+        }
+    }
+    slots
+}
+
 fn main() {
     env_logger::init();
 
-    let event_loop = EventLoop::new();
+    let event_loop = EventLoop::new().unwrap();
     let window = WindowBuilder::new()
-        .with_title("wgpu Advanced - Shadow Mapping")
+        .with_title(WINDOW_TITLE)
         .build(&event_loop)
         .unwrap();
 
     let mut state = pollster::block_on(State::new(window));
 
-    event_loop.run(move |event, _, control_flow| match event {
-        Event::WindowEvent {
-            ref event,
-            window_id,
-        } if window_id == state.window().id() => {
-            if !state.input(event) {
-                match event {
-                    WindowEvent::CloseRequested
-                    | WindowEvent::KeyboardInput {
-                        input:
-                            KeyboardInput {
-                                state: ElementState::Pressed,
-                                virtual_keycode: Some(VirtualKeyCode::Escape),
-                                ..
-                            },
-                        ..
-                    } => *control_flow = ControlFlow::Exit,
-                    WindowEvent::Resized(physical_size) => {
-                        state.resize(*physical_size);
-                    }
-                    WindowEvent::ScaleFactorChanged { new_inner_size, .. } => {
-                        state.resize(**new_inner_size);
+    event_loop
+        .run(move |event, elwt| match event {
+            Event::WindowEvent {
+                ref event,
+                window_id,
+            } if window_id == state.window().id() && !state.input(event) => match event {
+                WindowEvent::CloseRequested
+                | WindowEvent::KeyboardInput {
+                    event:
+                        KeyEvent {
+                            state: ElementState::Pressed,
+                            logical_key: Key::Named(NamedKey::Escape),
+                            ..
+                        },
+                    ..
+                } => elwt.exit(),
+                WindowEvent::Resized(physical_size) => {
+                    state.resize(*physical_size);
+                }
+                WindowEvent::RedrawRequested => {
+                    state.tick();
+                    match state.render() {
+                        Ok(_) => {}
+                        Err(wgpu::SurfaceError::Lost) => state.resize(state.size),
+                        Err(wgpu::SurfaceError::OutOfMemory) => elwt.exit(),
+                        Err(e) => eprintln!("{:?}", e),
                     }
-                    _ => {}
                 }
+                _ => {}
+            },
+            Event::AboutToWait => {
+                state.window().request_redraw();
             }
-        }
-        Event::RedrawRequested(window_id) if window_id == state.window().id() => {
-            state.update();
-            match state.render() {
-                Ok(_) => {}
-                Err(wgpu::SurfaceError::Lost) => state.resize(state.size),
-                Err(wgpu::SurfaceError::OutOfMemory) => *control_flow = ControlFlow::Exit,
-                Err(e) => eprintln!("{:?}", e),
-            }
-        }
-        Event::MainEventsCleared => {
-            state.window().request_redraw();
-        }
-        _ => {}
-    });
+            _ => {}
+        })
+        .unwrap();
 }
 
 use wgpu::util::DeviceExt;
@@ -0,0 +1,149 @@
+//! A debug overlay drawn with egui.
+//!
+//! egui is an immediate-mode GUI: every frame the UI is described from
+//! scratch by a closure that reads and writes the application's state
+//! directly, so a slider bound to `&mut settings.pcf_radius` needs no
+//! callbacks or change events. Three crates cooperate:
+//!
+//! - `egui` builds the UI and turns it into triangles
+//! - `egui-winit` converts window events into egui input (and reports
+//!   whether egui used them, so clicks on a panel don't also turn the
+//!   camera)
+//! - `egui-wgpu` uploads egui's textures and draws the triangles in a
+//!   render pass of our own
+//!
+//! The overlay draws in its own pass after the scene, loading the frame
+//! rather than clearing it.
+
+use common::renderer::RenderPassBuilder;
+use std::time::Instant;
+use winit::{event::WindowEvent, window::Window};
+
+/// Weight of the newest frame in the smoothed frame time
+const FRAME_TIME_SMOOTHING: f32 = 0.05;
+
+/// egui's output for one frame, waiting to be painted
+struct PendingFrame {
+    primitives: Vec<egui::ClippedPrimitive>,
+    textures_delta: egui::TexturesDelta,
+    pixels_per_point: f32,
+}
+
+pub struct Overlay {
+    context: egui::Context,
+    state: egui_winit::State,
+    renderer: egui_wgpu::Renderer,
+    pending: Option<PendingFrame>,
+    last_frame: Instant,
+    /// Seconds per frame, smoothed so the FPS readout is legible
+    frame_time: f32,
+}
+
+impl Overlay {
+    /// An overlay for `window`, drawing into surface textures of `format`
+    pub fn new(window: &Window, device: &wgpu::Device, format: wgpu::TextureFormat) -> Self {
+        let context = egui::Context::default();
+        let state = egui_winit::State::new(
+            context.clone(),
+            egui::ViewportId::ROOT,
+            window,
+            Some(window.scale_factor() as f32),
+            None,
+        );
+        // Drawn straight into the surface texture: no depth, no MSAA
+        let renderer = egui_wgpu::Renderer::new(device, format, None, 1);
+        Self {
+            context,
+            state,
+            renderer,
+            pending: None,
+            last_frame: Instant::now(),
+            frame_time: 1.0 / 60.0,
+        }
+    }
+
+    /// Pass a window event to egui; returns whether egui used it, in which
+    /// case the rest of the application should ignore it
+    pub fn handle_event(&mut self, window: &Window, event: &WindowEvent) -> bool {
+        self.state.on_window_event(window, event).consumed
+    }
+
+    /// Make a texture of ours available to `ui.image`; it must be
+    /// `Rgba8UnormSrgb` with `TEXTURE_BINDING` usage
+    pub fn register_texture(
+        &mut self,
+        device: &wgpu::Device,
+        view: &wgpu::TextureView,
+    ) -> egui::TextureId {
+        self.renderer
+            .register_native_texture(device, view, wgpu::FilterMode::Nearest)
+    }
+
+    /// Frames per second, from the smoothed frame time
+    pub fn fps(&self) -> f32 {
+        1.0 / self.frame_time
+    }
+
+    /// Smoothed time per frame, in milliseconds
+    pub fn frame_time_ms(&self) -> f32 {
+        self.frame_time * 1000.0
+    }
+
+    /// Build this frame's UI with `ui`; `paint` draws it
+    pub fn run(&mut self, window: &Window, ui: impl FnOnce(&egui::Context)) {
+        let now = Instant::now();
+        let dt = now.duration_since(self.last_frame).as_secs_f32();
+        self.last_frame = now;
+        self.frame_time += (dt - self.frame_time) * FRAME_TIME_SMOOTHING;
+
+        let input = self.state.take_egui_input(window);
+        let output = self.context.run(input, ui);
+        self.state
+            .handle_platform_output(window, output.platform_output);
+        self.pending = Some(PendingFrame {
+            primitives: self
+                .context
+                .tessellate(output.shapes, output.pixels_per_point),
+            textures_delta: output.textures_delta,
+            pixels_per_point: output.pixels_per_point,
+        });
+    }
+
+    /// Draw the UI built by the last `run` on top of `view`
+    pub fn paint(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        encoder: &mut wgpu::CommandEncoder,
+        view: &wgpu::TextureView,
+        size_in_pixels: [u32; 2],
+    ) {
+        let Some(frame) = self.pending.take() else {
+            return;
+        };
+        let screen = egui_wgpu::ScreenDescriptor {
+            size_in_pixels,
+            pixels_per_point: frame.pixels_per_point,
+        };
+
+        // New and changed textures (the font atlas, mostly) go up first
+        for (id, delta) in &frame.textures_delta.set {
+            self.renderer.update_texture(device, queue, *id, delta);
+        }
+        // Only paint callbacks return command buffers, and there are none
+        self.renderer
+            .update_buffers(device, queue, encoder, &frame.primitives, &screen);
+
+        {
+            // Keep the scene underneath
+            let mut pass = RenderPassBuilder::new("Overlay Pass")
+                .color(view, wgpu::LoadOp::Load)
+                .begin(encoder);
+            self.renderer.render(&mut pass, &frame.primitives, &screen);
+        }
+
+        for id in &frame.textures_delta.free {
+            self.renderer.free_texture(id);
+        }
+    }
+}
@@ -0,0 +1,64 @@
+//! Omnidirectional shadows for a point light, rendered into a cube map.
+//!
+//! A point light shines every way at once, so no single projection can see
+//! everything it lights. Instead the scene is rendered six times from the
+//! light's position, once per face of a cube map, each with a 90° field of
+//! view. The fragment shader then looks the depth up with the direction
+//! from the light to the fragment, and the hardware picks the face.
+//!
+//! Each face's projection is built straight from the cube map sampling
+//! rules (which face a direction selects and how it maps onto that face's
+//! texels), rather than from `look_at` matrices. That sidesteps the usual
+//! per-API face flips: cube maps are addressed as if seen from the inside,
+//! which is a mirror image of an ordinary camera, so triangles come out
+//! with the opposite winding (`FrontFace::Cw` in the point shadow
+//! pipeline).
+
+use cgmath::prelude::*;
+use cgmath::{Matrix4, Point3, Vector3};
+
+/// Width and height of each cube face
+pub const POINT_SHADOW_SIZE: u32 = 1024;
+
+/// Depth range of the face projections; nothing further than `FAR` from
+/// the light casts or receives a shadow
+pub const POINT_SHADOW_NEAR: f32 = 0.1;
+pub const POINT_SHADOW_FAR: f32 = 25.0;
+
+/// For each face in wgpu's layer order (+X, -X, +Y, -Y, +Z, -Z), the axes
+/// that give its horizontal texture coordinate, vertical texture
+/// coordinate and depth, as dot products with the light-to-point vector
+const FACE_AXES: [[[f32; 3]; 3]; 6] = [
+    [[0.0, 0.0, -1.0], [0.0, -1.0, 0.0], [1.0, 0.0, 0.0]],
+    [[0.0, 0.0, 1.0], [0.0, -1.0, 0.0], [-1.0, 0.0, 0.0]],
+    [[1.0, 0.0, 0.0], [0.0, 0.0, 1.0], [0.0, 1.0, 0.0]],
+    [[1.0, 0.0, 0.0], [0.0, 0.0, -1.0], [0.0, -1.0, 0.0]],
+    [[1.0, 0.0, 0.0], [0.0, -1.0, 0.0], [0.0, 0.0, 1.0]],
+    [[-1.0, 0.0, 0.0], [0.0, -1.0, 0.0], [0.0, 0.0, -1.0]],
+];
+
+/// World space to clip space for each cube face, in layer order
+///
+/// Depth is the usual perspective depth of the face's major axis, so the
+/// shader can recompute it from the largest component of the
+/// light-to-fragment vector (see `point_shadow_depth` in render.wgsl).
+pub fn face_view_projs(light_position: Point3<f32>) -> [Matrix4<f32>; 6] {
+    let (near, far) = (POINT_SHADOW_NEAR, POINT_SHADOW_FAR);
+    let depth_scale = far / (far - near);
+    let depth_offset = far * near / (far - near);
+    let light = light_position.to_vec();
+
+    FACE_AXES.map(|[s, t, major]| {
+        let (s, t, major) = (Vector3::from(s), Vector3::from(t), Vector3::from(major));
+        // Rows of the matrix, applied to the point relative to the light.
+        // Texture v grows downwards while clip y grows upwards, hence -t.
+        let row = |axis: Vector3<f32>, offset: f32| axis.extend(-axis.dot(light) + offset);
+        Matrix4::from_cols(
+            row(s, 0.0),
+            row(-t, 0.0),
+            row(major * depth_scale, -depth_offset),
+            row(major, 0.0),
+        )
+        .transpose()
+    })
+}
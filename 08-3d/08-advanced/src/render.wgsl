@@ -1,29 +1,49 @@
-// Main rendering shader with shadow mapping
+// Main rendering shader with cascaded and point-light shadow maps
+
+// Must match CASCADE_COUNT in cascades.rs
+const CASCADE_COUNT: u32 = 3u;
 
 struct Uniforms {
     view_proj: mat4x4<f32>,
     model: mat4x4<f32>,
     normal_matrix: mat4x4<f32>,
-    light_view_proj: mat4x4<f32>,
-    light_position: vec3<f32>,
-    _padding1: f32,
+    cascade_view_proj: array<mat4x4<f32>, CASCADE_COUNT>,
+    // Where each cascade ends, measured along the view direction
+    cascade_splits: vec4<f32>,
+    // The direction the light travels in
+    light_direction: vec3<f32>,
+    // PCF kernel radius in texels: the kernel is (2r + 1) x (2r + 1)
+    pcf_radius: i32,
     light_color: vec3<f32>,
-    _padding2: f32,
+    show_cascades: u32,
     camera_position: vec3<f32>,
-    _padding3: f32,
+    show_normals: u32,
+    camera_forward: vec3<f32>,
+    _padding2: f32,
     object_color: vec3<f32>,
-    _padding4: f32,
+    _padding3: f32,
+    light_position: vec3<f32>,
+    // 0 = directional light with cascades, 1 = point light with a cube map
+    light_mode: u32,
+    point_shadow_near: f32,
+    point_shadow_far: f32,
+    _padding4: vec2<f32>,
 };
 
 @group(0) @binding(0)
 var<uniform> uniforms: Uniforms;
 
+// One layer per cascade
 @group(0) @binding(1)
-var shadow_map: texture_depth_2d;
+var shadow_map: texture_depth_2d_array;
 
 @group(0) @binding(2)
 var shadow_sampler: sampler_comparison;
 
+// The point light's shadows, one face per direction
+@group(0) @binding(3)
+var point_shadow_map: texture_depth_cube;
+
 struct VertexInput {
     @location(0) position: vec3<f32>,
     @location(1) normal: vec3<f32>,
@@ -33,7 +53,6 @@ struct VertexOutput {
     @builtin(position) clip_position: vec4<f32>,
     @location(0) world_position: vec3<f32>,
     @location(1) world_normal: vec3<f32>,
-    @location(2) shadow_position: vec3<f32>,
 };
 
 @vertex
@@ -47,17 +66,130 @@ fn vs_main(in: VertexInput) -> VertexOutput {
     let world_normal = uniforms.normal_matrix * vec4<f32>(in.normal, 0.0);
     out.world_normal = normalize(world_normal.xyz);
 
-    // Calculate position in shadow map space
-    let shadow_pos = uniforms.light_view_proj * world_position;
-    out.shadow_position = shadow_pos.xyz / shadow_pos.w;
-
     return out;
 }
 
+// The first cascade whose slice contains `view_depth`, or CASCADE_COUNT
+// past the last one
+fn select_cascade(view_depth: f32) -> u32 {
+    for (var i = 0u; i < CASCADE_COUNT; i++) {
+        if view_depth < uniforms.cascade_splits[i] {
+            return i;
+        }
+    }
+    return CASCADE_COUNT;
+}
+
+// Fraction of the light reaching `world_position` (0 = fully shadowed),
+// averaged over a PCF kernel around its texel in `cascade`
+fn shadow_factor(cascade: u32, world_position: vec3<f32>, normal: vec3<f32>) -> f32 {
+    let dimensions = vec2<f32>(textureDimensions(shadow_map));
+    let texel_size = 1.0 / dimensions;
+    let light_view_proj = uniforms.cascade_view_proj[cascade];
+
+    // Push the lookup out along the normal by about a texel's width in
+    // world space. The cascades cover different areas, so a fixed depth
+    // bias would be too small for the far ones and too large for the near
+    // one. The length of the matrix's first row is 1 / half the
+    // cascade's width.
+    let x_scale = length(vec3<f32>(light_view_proj[0][0], light_view_proj[1][0], light_view_proj[2][0]));
+    let world_texel = 2.0 / (x_scale * dimensions.x);
+    let offset_position = world_position + normal * world_texel * 1.5;
+
+    let light_space = light_view_proj * vec4<f32>(offset_position, 1.0);
+    let ndc = light_space.xyz / light_space.w;
+    // Convert from NDC [-1,1] to texture coordinates [0,1], flipping Y
+    let uv = vec2<f32>(ndc.x * 0.5 + 0.5, ndc.y * -0.5 + 0.5);
+    let depth = ndc.z - 0.001;
+
+    // Outside the cascade's map nothing is known, so treat it as lit
+    if any(uv < vec2<f32>(0.0)) || any(uv > vec2<f32>(1.0)) || depth > 1.0 {
+        return 1.0;
+    }
+
+    // PCF (Percentage Closer Filtering): each comparison is 0 or 1, and
+    // averaging a grid of them softens the shadow edge over the kernel
+    let radius = uniforms.pcf_radius;
+    var shadow = 0.0;
+    for (var y = -radius; y <= radius; y++) {
+        for (var x = -radius; x <= radius; x++) {
+            let sample_uv = uv + vec2<f32>(f32(x), f32(y)) * texel_size;
+            shadow += textureSampleCompareLevel(
+                shadow_map,
+                shadow_sampler,
+                sample_uv,
+                cascade,
+                depth
+            );
+        }
+    }
+    let kernel = f32(2 * radius + 1);
+    return shadow / (kernel * kernel);
+}
+
+// The depth the point light's cube face wrote for a point at
+// `light_to_point`: perspective depth along the face's axis, which is the
+// vector's largest component (see point_shadows.rs)
+fn point_shadow_depth(light_to_point: vec3<f32>) -> f32 {
+    let near = uniforms.point_shadow_near;
+    let far = uniforms.point_shadow_far;
+    let axis_distance = max(max(abs(light_to_point.x), abs(light_to_point.y)), abs(light_to_point.z));
+    return far / (far - near) - far * near / ((far - near) * axis_distance);
+}
+
+// Fraction of the point light reaching `world_position`, with PCF over a
+// grid of directions around the one pointing at it
+fn point_shadow_factor(world_position: vec3<f32>, normal: vec3<f32>) -> f32 {
+    let face_size = f32(textureDimensions(point_shadow_map).x);
+
+    // A face spans 90 degrees, so one texel at the fragment's distance is
+    // about 2 * distance / face_size wide; offset along the normal by 1.5
+    // of them, as for the cascades
+    let distance = length(world_position - uniforms.light_position);
+    if distance > uniforms.point_shadow_far {
+        return 1.0;
+    }
+    let world_texel = 2.0 * distance / face_size;
+    let light_to_point = world_position + normal * world_texel * 1.5 - uniforms.light_position;
+    let depth = point_shadow_depth(light_to_point) - 0.0005;
+
+    // Two directions across the lookup vector to step the kernel along
+    var up = vec3<f32>(0.0, 1.0, 0.0);
+    if abs(normalize(light_to_point).y) > 0.99 {
+        up = vec3<f32>(1.0, 0.0, 0.0);
+    }
+    let across = normalize(cross(light_to_point, up));
+    let down = normalize(cross(light_to_point, across));
+
+    let radius = uniforms.pcf_radius;
+    var shadow = 0.0;
+    for (var y = -radius; y <= radius; y++) {
+        for (var x = -radius; x <= radius; x++) {
+            let offset = (across * f32(x) + down * f32(y)) * world_texel;
+            shadow += textureSampleCompareLevel(
+                point_shadow_map,
+                shadow_sampler,
+                light_to_point + offset,
+                depth
+            );
+        }
+    }
+    let kernel = f32(2 * radius + 1);
+    return shadow / (kernel * kernel);
+}
+
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
     let normal = normalize(in.world_normal);
-    let light_dir = normalize(uniforms.light_position - in.world_position);
+    // Debug view: map each normal's components from -1..1 to a color
+    if uniforms.show_normals != 0u {
+        return vec4<f32>(normal * 0.5 + 0.5, 1.0);
+    }
+    let point_light = uniforms.light_mode == 1u;
+    var light_dir = -normalize(uniforms.light_direction);
+    if point_light {
+        light_dir = normalize(uniforms.light_position - in.world_position);
+    }
     let view_dir = normalize(uniforms.camera_position - in.world_position);
 
     // Phong lighting
@@ -70,36 +202,27 @@ fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
     let spec_factor = pow(max(dot(view_dir, reflect_dir), 0.0), 32.0);
     let specular = 0.5 * spec_factor * uniforms.light_color;
 
-    // Shadow mapping
-    // Convert from NDC [-1,1] to texture coordinates [0,1]
-    let shadow_coord = vec3<f32>(
-        in.shadow_position.x * 0.5 + 0.5,
-        in.shadow_position.y * -0.5 + 0.5,  // Flip Y for texture coordinates
-        in.shadow_position.z
-    );
-
-    // Sample shadow map with PCF (Percentage Closer Filtering)
-    var shadow = 0.0;
-    let texel_size = 1.0 / 2048.0;
-
-    // 3x3 PCF kernel for soft shadows
-    for (var y = -1; y <= 1; y++) {
-        for (var x = -1; x <= 1; x++) {
-            let offset = vec2<f32>(f32(x), f32(y)) * texel_size;
-            let sample_coord = shadow_coord.xy + offset;
-            shadow += textureSampleCompare(
-                shadow_map,
-                shadow_sampler,
-                sample_coord,
-                shadow_coord.z - 0.005  // Bias to reduce shadow acne
-            );
-        }
+    // Pick the cascade by distance along the view direction, the same
+    // measure the splits were computed in
+    let view_depth = dot(in.world_position - uniforms.camera_position, uniforms.camera_forward);
+    let cascade = select_cascade(view_depth);
+    var shadow = 1.0;
+    if point_light {
+        shadow = point_shadow_factor(in.world_position, normal);
+    } else if cascade < CASCADE_COUNT {
+        shadow = shadow_factor(cascade, in.world_position, normal);
     }
-    shadow /= 9.0;  // Average the samples
 
     // Apply shadow to diffuse and specular only (not ambient)
     let lighting = ambient + shadow * (diffuse + specular);
-    let final_color = lighting * uniforms.object_color;
+    var final_color = lighting * uniforms.object_color;
+
+    // Debug view: tint each cascade red, green, blue
+    if uniforms.show_cascades != 0u && !point_light && cascade < CASCADE_COUNT {
+        var tint = vec3<f32>(0.0);
+        tint[cascade] = 1.0;
+        final_color = mix(final_color, tint, 0.25);
+    }
 
     return vec4<f32>(final_color, 1.0);
 }
@@ -0,0 +1,183 @@
+//! A picture of one shadow map layer, for the overlay.
+//!
+//! egui can only show color textures, so a small pass reads the chosen
+//! cascade or cube face with `textureLoad` and writes it out as grayscale:
+//! dark is close to the light, white is far away or empty.
+
+use common::renderer::{PipelineBuilder, RenderPassBuilder, UniformBlock};
+
+/// Width and height of the picture
+pub const SIZE: u32 = 256;
+
+/// The `Params` uniform in shadow_debug.wgsl
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct Params {
+    layer: u32,
+    linearize: u32,
+    near: f32,
+    far: f32,
+}
+
+/// Which shadow map to show
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Source {
+    /// A cascade of the sun's shadow map
+    Cascade(u32),
+    /// A face of the point light's cube map, in wgpu's layer order
+    CubeFace(u32),
+}
+
+pub struct ShadowDebug {
+    pipeline: wgpu::RenderPipeline,
+    cascades_bind_group: wgpu::BindGroup,
+    cube_bind_group: wgpu::BindGroup,
+    params: UniformBlock<Params>,
+    #[allow(dead_code)] // only used through its view
+    texture: wgpu::Texture,
+    view: wgpu::TextureView,
+}
+
+impl ShadowDebug {
+    /// `cascades` and `cube` are 2D array views of the two shadow maps
+    pub fn new(
+        device: &wgpu::Device,
+        cascades: &wgpu::TextureView,
+        cube: &wgpu::TextureView,
+    ) -> Self {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Shadow Debug Texture"),
+            size: wgpu::Extent3d {
+                width: SIZE,
+                height: SIZE,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            // What egui-wgpu expects of textures it didn't create
+            format: wgpu::TextureFormat::Rgba8UnormSrgb,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        let params = UniformBlock::new(
+            device,
+            &Params {
+                layer: 0,
+                linearize: 0,
+                near: 0.0,
+                far: 1.0,
+            },
+            "Shadow Debug Params",
+        );
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("shadow_debug_bind_group_layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: UniformBlock::<Params>::binding_type(),
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Depth,
+                        view_dimension: wgpu::TextureViewDimension::D2Array,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+            ],
+        });
+        let bind_group = |shadow_map: &wgpu::TextureView| {
+            device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("shadow_debug_bind_group"),
+                layout: &bind_group_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: params.binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::TextureView(shadow_map),
+                    },
+                ],
+            })
+        };
+        let cascades_bind_group = bind_group(cascades);
+        let cube_bind_group = bind_group(cube);
+
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Shadow Debug Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("shadow_debug.wgsl").into()),
+        });
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Shadow Debug Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = PipelineBuilder::new("Shadow Debug Pipeline", &layout, &shader)
+            .color_target(wgpu::TextureFormat::Rgba8UnormSrgb, None)
+            .build(device);
+
+        Self {
+            pipeline,
+            cascades_bind_group,
+            cube_bind_group,
+            params,
+            texture,
+            view,
+        }
+    }
+
+    /// The picture, to register with the overlay
+    pub fn view(&self) -> &wgpu::TextureView {
+        &self.view
+    }
+
+    /// Redraw the picture from `source`; cube faces are perspective
+    /// projections between `near` and `far`
+    pub fn draw(
+        &self,
+        queue: &wgpu::Queue,
+        encoder: &mut wgpu::CommandEncoder,
+        source: Source,
+        near: f32,
+        far: f32,
+    ) {
+        let (bind_group, params) = match source {
+            Source::Cascade(layer) => (
+                &self.cascades_bind_group,
+                Params {
+                    layer,
+                    linearize: 0,
+                    near,
+                    far,
+                },
+            ),
+            Source::CubeFace(layer) => (
+                &self.cube_bind_group,
+                Params {
+                    layer,
+                    linearize: 1,
+                    near,
+                    far,
+                },
+            ),
+        };
+        self.params.write(queue, &params);
+
+        let mut pass = RenderPassBuilder::new("Shadow Debug Pass")
+            .color(&self.view, wgpu::LoadOp::Clear(wgpu::Color::BLACK))
+            .begin(encoder);
+        pass.set_pipeline(&self.pipeline);
+        pass.set_bind_group(0, bind_group, &[]);
+        pass.draw(0..3, 0..1);
+    }
+}
@@ -0,0 +1,49 @@
+// Copies one layer of a shadow map into a color texture for the overlay.
+// Depth textures can't be shown by egui directly: it samples with a
+// filtering sampler, which depth formats don't support.
+
+struct Params {
+    // Which layer: a cascade, or a cube face
+    layer: u32,
+    // Nonzero for perspective depth, which is converted back to distance
+    // so it isn't all white
+    linearize: u32,
+    near: f32,
+    far: f32,
+};
+
+@group(0) @binding(0)
+var<uniform> params: Params;
+
+@group(0) @binding(1)
+var shadow_map: texture_depth_2d_array;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+};
+
+// One triangle that covers the target
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+    let position = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u)) * 2.0 - 1.0;
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(position, 0.0, 1.0);
+    out.uv = vec2<f32>(position.x * 0.5 + 0.5, 0.5 - position.y * 0.5);
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    // Nearest texel; the target is smaller than the shadow map
+    let size = vec2<f32>(textureDimensions(shadow_map));
+    let texel = vec2<i32>(min(in.uv * size, size - 1.0));
+    var depth = textureLoad(shadow_map, texel, params.layer, 0);
+    if params.linearize != 0u {
+        // Invert the perspective depth mapping, then scale to 0..1
+        let distance = params.near * params.far / (params.far - depth * (params.far - params.near));
+        depth = distance / params.far;
+    }
+    // Near is dark, far (and empty) is white
+    return vec4<f32>(vec3<f32>(depth), 1.0);
+}
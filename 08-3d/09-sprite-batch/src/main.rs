@@ -0,0 +1,361 @@
+use rand::Rng;
+use std::sync::Arc;
+use std::time::Instant;
+use winit::{
+    event::*,
+    event_loop::EventLoop,
+    keyboard::{Key, NamedKey},
+    window::{Window, WindowBuilder},
+};
+
+mod sprite_batch;
+
+use sprite_batch::{Sprite, SpriteBatch, TextureAtlas};
+
+const INITIAL_SPRITES: usize = 10_000;
+/// How many sprites the up/down arrow keys add or remove
+const SPRITE_STEP: usize = 5_000;
+
+/// Size of one cell in the generated atlas
+const CELL_SIZE: u32 = 64;
+const ATLAS_COLUMNS: u32 = 4;
+
+/// A sprite plus the velocities the demo animates it with
+struct Mover {
+    sprite: Sprite,
+    velocity: [f32; 2],
+    spin: f32,
+}
+
+impl Mover {
+    fn random(rng: &mut impl Rng, width: f32, height: f32, regions: usize) -> Self {
+        let size = rng.gen_range(8.0..32.0);
+        Self {
+            sprite: Sprite {
+                position: [rng.gen_range(0.0..width), rng.gen_range(0.0..height)],
+                size: [size, size],
+                rotation: rng.gen_range(0.0..std::f32::consts::TAU),
+                tint: [rng.gen(), rng.gen(), rng.gen(), 1.0],
+                region: rng.gen_range(0..regions),
+            },
+            velocity: [rng.gen_range(-150.0..150.0), rng.gen_range(-150.0..150.0)],
+            spin: rng.gen_range(-3.0..3.0),
+        }
+    }
+
+    /// Move and spin, bouncing off the window edges
+    fn update(&mut self, dt: f32, width: f32, height: f32) {
+        for (axis, bound) in [width, height].into_iter().enumerate() {
+            self.sprite.position[axis] += self.velocity[axis] * dt;
+            if self.sprite.position[axis] < 0.0 || self.sprite.position[axis] > bound {
+                self.velocity[axis] = -self.velocity[axis];
+                self.sprite.position[axis] = self.sprite.position[axis].clamp(0.0, bound);
+            }
+        }
+        self.sprite.rotation += self.spin * dt;
+    }
+}
+
+/// Build a white-on-transparent atlas with one shape per cell
+/// (circle, ring, diamond, square) so the per-sprite tint supplies the color
+fn generate_atlas_pixels() -> Vec<u8> {
+    let width = CELL_SIZE * ATLAS_COLUMNS;
+    let mut data = vec![0u8; (width * CELL_SIZE * 4) as usize];
+    let half = CELL_SIZE as f32 / 2.0;
+
+    for y in 0..CELL_SIZE {
+        for x in 0..width {
+            let cell = x / CELL_SIZE;
+            // Normalized coordinates inside the cell, -1..1
+            let u = ((x % CELL_SIZE) as f32 + 0.5 - half) / half;
+            let v = (y as f32 + 0.5 - half) / half;
+            let distance = (u * u + v * v).sqrt();
+
+            let inside = match cell {
+                0 => distance <= 0.95,
+                1 => (0.6..=0.95).contains(&distance),
+                2 => u.abs() + v.abs() <= 0.95,
+                _ => u.abs().max(v.abs()) <= 0.8,
+            };
+
+            if inside {
+                let idx = ((y * width + x) * 4) as usize;
+                data[idx..idx + 4].copy_from_slice(&[255, 255, 255, 255]);
+            }
+        }
+    }
+
+    data
+}
+
+struct State<'a> {
+    window: Arc<Window>,
+    surface: wgpu::Surface<'a>,
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    config: wgpu::SurfaceConfiguration,
+    size: winit::dpi::PhysicalSize<u32>,
+    batch: SpriteBatch,
+    atlas: TextureAtlas,
+    movers: Vec<Mover>,
+    last_frame: Instant,
+    // FPS counter shown in the window title
+    frames: u32,
+    fps_timer: Instant,
+}
+
+impl<'a> State<'a> {
+    async fn new(window: Window) -> Self {
+        let window = Arc::new(window);
+        let size = window.inner_size();
+
+        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
+            backends: wgpu::Backends::all(),
+            ..Default::default()
+        });
+
+        let surface = instance.create_surface(Arc::clone(&window)).unwrap();
+
+        let adapter = instance
+            .request_adapter(&wgpu::RequestAdapterOptions {
+                power_preference: wgpu::PowerPreference::default(),
+                compatible_surface: Some(&surface),
+                force_fallback_adapter: false,
+            })
+            .await
+            .unwrap();
+
+        let (device, queue) = adapter
+            .request_device(
+                &wgpu::DeviceDescriptor {
+                    required_features: wgpu::Features::empty(),
+                    required_limits: wgpu::Limits::default(),
+                    label: None,
+                },
+                None,
+            )
+            .await
+            .unwrap();
+
+        let surface_caps = surface.get_capabilities(&adapter);
+        let surface_format = surface_caps
+            .formats
+            .iter()
+            .copied()
+            .find(|f| f.is_srgb())
+            .unwrap_or(surface_caps.formats[0]);
+
+        let config = wgpu::SurfaceConfiguration {
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            format: surface_format,
+            width: size.width,
+            height: size.height,
+            present_mode: surface_caps.present_modes[0],
+            alpha_mode: surface_caps.alpha_modes[0],
+            view_formats: vec![],
+            desired_maximum_frame_latency: 2,
+        };
+        surface.configure(&device, &config);
+
+        let batch = SpriteBatch::new(&device, config.format, config.width, config.height);
+        let atlas = TextureAtlas::from_grid(
+            &device,
+            &queue,
+            &batch,
+            &generate_atlas_pixels(),
+            CELL_SIZE * ATLAS_COLUMNS,
+            CELL_SIZE,
+            ATLAS_COLUMNS,
+            1,
+        );
+
+        let mut state = Self {
+            window,
+            surface,
+            device,
+            queue,
+            config,
+            size,
+            batch,
+            atlas,
+            movers: Vec::new(),
+            last_frame: Instant::now(),
+            frames: 0,
+            fps_timer: Instant::now(),
+        };
+        state.add_sprites(INITIAL_SPRITES);
+        state
+    }
+
+    pub fn window(&self) -> &Window {
+        &self.window
+    }
+
+    fn add_sprites(&mut self, count: usize) {
+        let mut rng = rand::thread_rng();
+        // max(1) keeps gen_range from panicking on a not-yet-sized window
+        let width = self.config.width.max(1) as f32;
+        let height = self.config.height.max(1) as f32;
+        let regions = self.atlas.region_count();
+        self.movers
+            .extend((0..count).map(|_| Mover::random(&mut rng, width, height, regions)));
+    }
+
+    pub fn resize(&mut self, new_size: winit::dpi::PhysicalSize<u32>) {
+        if new_size.width > 0 && new_size.height > 0 {
+            self.size = new_size;
+            self.config.width = new_size.width;
+            self.config.height = new_size.height;
+            self.surface.configure(&self.device, &self.config);
+            self.batch
+                .resize(&self.queue, new_size.width, new_size.height);
+        }
+    }
+
+    fn input(&mut self, event: &WindowEvent) -> bool {
+        match event {
+            WindowEvent::KeyboardInput {
+                event:
+                    KeyEvent {
+                        state: ElementState::Pressed,
+                        logical_key: Key::Named(key),
+                        ..
+                    },
+                ..
+            } => match key {
+                NamedKey::ArrowUp => {
+                    self.add_sprites(SPRITE_STEP);
+                    true
+                }
+                NamedKey::ArrowDown => {
+                    let keep = self.movers.len().saturating_sub(SPRITE_STEP);
+                    self.movers.truncate(keep);
+                    true
+                }
+                _ => false,
+            },
+            _ => false,
+        }
+    }
+
+    fn update(&mut self) {
+        let now = Instant::now();
+        let dt = (now - self.last_frame).as_secs_f32();
+        self.last_frame = now;
+
+        let (width, height) = (self.config.width as f32, self.config.height as f32);
+        for mover in &mut self.movers {
+            mover.update(dt, width, height);
+        }
+
+        // Queue every sprite; the batch turns them into one draw call
+        self.batch.begin();
+        for mover in &self.movers {
+            self.batch.draw(&self.atlas, &mover.sprite);
+        }
+        self.batch.prepare(&self.device, &self.queue);
+
+        self.frames += 1;
+        let elapsed = self.fps_timer.elapsed().as_secs_f32();
+        if elapsed >= 1.0 {
+            self.window.set_title(&format!(
+                "wgpu Sprite Batch - {} sprites, {:.0} FPS",
+                self.batch.len(),
+                self.frames as f32 / elapsed
+            ));
+            self.frames = 0;
+            self.fps_timer = Instant::now();
+        }
+    }
+
+    fn render(&mut self) -> Result<(), wgpu::SurfaceError> {
+        let output = self.surface.get_current_texture()?;
+        let view = output
+            .texture
+            .create_view(&wgpu::TextureViewDescriptor::default());
+
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Render Encoder"),
+            });
+
+        {
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Sprite Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color {
+                            r: 0.05,
+                            g: 0.05,
+                            b: 0.1,
+                            a: 1.0,
+                        }),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                occlusion_query_set: None,
+                timestamp_writes: None,
+            });
+
+            self.batch.render(&mut render_pass, &self.atlas);
+        }
+
+        self.queue.submit(std::iter::once(encoder.finish()));
+        output.present();
+
+        Ok(())
+    }
+}
+
+fn main() {
+    env_logger::init();
+
+    let event_loop = EventLoop::new().unwrap();
+    let window = WindowBuilder::new()
+        .with_title("wgpu Sprite Batch")
+        .build(&event_loop)
+        .unwrap();
+
+    let mut state = pollster::block_on(State::new(window));
+
+    event_loop
+        .run(move |event, elwt| match event {
+            Event::WindowEvent {
+                ref event,
+                window_id,
+            } if window_id == state.window().id() && !state.input(event) => match event {
+                WindowEvent::CloseRequested
+                | WindowEvent::KeyboardInput {
+                    event:
+                        KeyEvent {
+                            state: ElementState::Pressed,
+                            logical_key: Key::Named(NamedKey::Escape),
+                            ..
+                        },
+                    ..
+                } => elwt.exit(),
+                WindowEvent::Resized(physical_size) => {
+                    state.resize(*physical_size);
+                }
+                WindowEvent::RedrawRequested => {
+                    state.update();
+                    match state.render() {
+                        Ok(_) => {}
+                        Err(wgpu::SurfaceError::Lost) => state.resize(state.size),
+                        Err(wgpu::SurfaceError::OutOfMemory) => elwt.exit(),
+                        Err(e) => eprintln!("{:?}", e),
+                    }
+                }
+                _ => {}
+            },
+            Event::AboutToWait => {
+                state.window().request_redraw();
+            }
+            _ => {}
+        })
+        .unwrap();
+}
@@ -0,0 +1,58 @@
+struct Camera {
+    // Orthographic projection: pixel coordinates -> clip space
+    projection: mat4x4<f32>,
+};
+
+@group(0) @binding(0)
+var<uniform> camera: Camera;
+
+@group(1) @binding(0)
+var t_atlas: texture_2d<f32>;
+@group(1) @binding(1)
+var s_atlas: sampler;
+
+// Unit quad corner (per-vertex), centered on the origin
+struct VertexInput {
+    @location(0) corner: vec2<f32>,
+};
+
+// One sprite (per-instance)
+struct SpriteInput {
+    @location(1) position: vec2<f32>,
+    @location(2) size: vec2<f32>,
+    @location(3) rotation: f32,
+    @location(4) uv_rect: vec4<f32>, // min u, min v, max u, max v
+    @location(5) tint: vec4<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) tex_coords: vec2<f32>,
+    @location(1) tint: vec4<f32>,
+};
+
+@vertex
+fn vs_main(vertex: VertexInput, sprite: SpriteInput) -> VertexOutput {
+    var out: VertexOutput;
+
+    // Scale, rotate around the sprite center, then move into place
+    let scaled = vertex.corner * sprite.size;
+    let c = cos(sprite.rotation);
+    let s = sin(sprite.rotation);
+    let rotated = vec2<f32>(scaled.x * c - scaled.y * s, scaled.x * s + scaled.y * c);
+    let world = rotated + sprite.position;
+
+    out.clip_position = camera.projection * vec4<f32>(world, 0.0, 1.0);
+
+    // Map the corner (-0.5..0.5) onto the sprite's region of the atlas
+    let t = vertex.corner + vec2<f32>(0.5, 0.5);
+    out.tex_coords = mix(sprite.uv_rect.xy, sprite.uv_rect.zw, t);
+    out.tint = sprite.tint;
+
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return textureSample(t_atlas, s_atlas, in.tex_coords) * in.tint;
+}
@@ -0,0 +1,168 @@
+//! GPU state shared by every window, and the per-window surface.
+//!
+//! Only one instance, adapter, device and queue exist, no matter how many
+//! windows are open. Buffers, textures and pipelines created on the device
+//! can be used to draw into any of the surfaces. What differs per window is
+//! the surface itself: its configuration (size, format, present mode) and
+//! the depth buffer that has to match its size.
+
+use std::sync::Arc;
+use winit::{dpi::PhysicalSize, window::Window};
+
+pub const DEPTH_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth32Float;
+
+pub struct GpuContext {
+    pub instance: wgpu::Instance,
+    pub adapter: wgpu::Adapter,
+    pub device: wgpu::Device,
+    pub queue: wgpu::Queue,
+}
+
+impl GpuContext {
+    /// Set up the device, picking an adapter that can present to `window`.
+    /// Returns the surface for that window, since it had to be created
+    /// first to choose the adapter.
+    pub async fn new(window: Arc<Window>) -> (Self, WindowSurface) {
+        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
+            backends: wgpu::Backends::all(),
+            ..Default::default()
+        });
+
+        let surface = instance.create_surface(Arc::clone(&window)).unwrap();
+
+        let adapter = instance
+            .request_adapter(&wgpu::RequestAdapterOptions {
+                power_preference: wgpu::PowerPreference::default(),
+                compatible_surface: Some(&surface),
+                force_fallback_adapter: false,
+            })
+            .await
+            .unwrap();
+
+        let (device, queue) = adapter
+            .request_device(
+                &wgpu::DeviceDescriptor {
+                    required_features: wgpu::Features::empty(),
+                    required_limits: wgpu::Limits::default(),
+                    label: None,
+                },
+                None,
+            )
+            .await
+            .unwrap();
+
+        let gpu = Self {
+            instance,
+            adapter,
+            device,
+            queue,
+        };
+        let target = WindowSurface::configure(&gpu, window, surface);
+        (gpu, target)
+    }
+}
+
+/// A window together with its surface and depth buffer
+pub struct WindowSurface {
+    pub window: Arc<Window>,
+    surface: wgpu::Surface<'static>,
+    pub config: wgpu::SurfaceConfiguration,
+    depth_view: wgpu::TextureView,
+}
+
+impl WindowSurface {
+    /// Create a surface for another window on the existing device
+    pub fn new(gpu: &GpuContext, window: Arc<Window>) -> Self {
+        let surface = gpu.instance.create_surface(Arc::clone(&window)).unwrap();
+        // The adapter was chosen for the first window. On a multi-GPU
+        // system a window on another output may not be presentable from it.
+        assert!(
+            gpu.adapter.is_surface_supported(&surface),
+            "adapter cannot present to window {:?}",
+            window.id()
+        );
+        Self::configure(gpu, window, surface)
+    }
+
+    fn configure(gpu: &GpuContext, window: Arc<Window>, surface: wgpu::Surface<'static>) -> Self {
+        let size = window.inner_size();
+
+        // Capabilities are per surface: each window picks its own format
+        let surface_caps = surface.get_capabilities(&gpu.adapter);
+        let surface_format = surface_caps
+            .formats
+            .iter()
+            .copied()
+            .find(|f| f.is_srgb())
+            .unwrap_or(surface_caps.formats[0]);
+
+        let config = wgpu::SurfaceConfiguration {
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            format: surface_format,
+            width: size.width.max(1),
+            height: size.height.max(1),
+            present_mode: surface_caps.present_modes[0],
+            alpha_mode: surface_caps.alpha_modes[0],
+            view_formats: vec![],
+            desired_maximum_frame_latency: 2,
+        };
+        surface.configure(&gpu.device, &config);
+        let depth_view = create_depth_view(&gpu.device, &config);
+
+        Self {
+            window,
+            surface,
+            config,
+            depth_view,
+        }
+    }
+
+    pub fn size(&self) -> PhysicalSize<u32> {
+        PhysicalSize::new(self.config.width, self.config.height)
+    }
+
+    pub fn format(&self) -> wgpu::TextureFormat {
+        self.config.format
+    }
+
+    pub fn depth_view(&self) -> &wgpu::TextureView {
+        &self.depth_view
+    }
+
+    /// Reconfigure after the window was resized. Minimized windows report
+    /// a zero size, which a surface can't have, so those are skipped.
+    pub fn resize(&mut self, device: &wgpu::Device, new_size: PhysicalSize<u32>) {
+        if new_size.width > 0 && new_size.height > 0 {
+            self.config.width = new_size.width;
+            self.config.height = new_size.height;
+            self.surface.configure(device, &self.config);
+            self.depth_view = create_depth_view(device, &self.config);
+        }
+    }
+
+    /// Get the next texture to draw into
+    pub fn acquire(&self) -> Result<wgpu::SurfaceTexture, wgpu::SurfaceError> {
+        self.surface.get_current_texture()
+    }
+}
+
+fn create_depth_view(
+    device: &wgpu::Device,
+    config: &wgpu::SurfaceConfiguration,
+) -> wgpu::TextureView {
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("Depth Texture"),
+        size: wgpu::Extent3d {
+            width: config.width,
+            height: config.height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: DEPTH_FORMAT,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+        view_formats: &[],
+    });
+    texture.create_view(&wgpu::TextureViewDescriptor::default())
+}
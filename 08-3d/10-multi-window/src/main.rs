@@ -0,0 +1,340 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Instant;
+use winit::{
+    dpi::PhysicalPosition,
+    event::*,
+    event_loop::{EventLoop, EventLoopWindowTarget},
+    keyboard::{Key, NamedKey},
+    window::{Window, WindowBuilder, WindowId},
+};
+
+mod gpu;
+mod scene;
+mod viewport;
+
+use gpu::{GpuContext, WindowSurface};
+use scene::Scene;
+use viewport::{OrbitCamera, Viewport, ViewportRect};
+
+const TITLE: &str = "wgpu Multi-Window";
+
+/// Camera for a new full-window view
+fn default_camera() -> OrbitCamera {
+    OrbitCamera::new(0.8, 0.5, 14.0)
+}
+
+/// Camera for the right half of a split view: almost straight down
+fn top_down_camera() -> OrbitCamera {
+    OrbitCamera::new(0.0, 1.5, 18.0)
+}
+
+/// Everything drawn into one window: its surface and the viewports that
+/// share it
+struct View {
+    surface: WindowSurface,
+    viewports: Vec<Viewport>,
+    cursor: PhysicalPosition<f64>,
+    /// The viewport a drag started in. It keeps receiving the drag even
+    /// when the cursor crosses into a neighbouring viewport.
+    dragging: Option<usize>,
+}
+
+impl View {
+    fn new(gpu: &GpuContext, scene: &mut Scene, surface: WindowSurface, split: bool) -> Self {
+        scene.prepare_format(&gpu.device, surface.format());
+        let mut view = Self {
+            surface,
+            viewports: vec![Viewport::new(
+                &gpu.device,
+                scene.camera_layout(),
+                ViewportRect::FULL,
+                default_camera(),
+            )],
+            cursor: PhysicalPosition::new(0.0, 0.0),
+            dragging: None,
+        };
+        view.set_split(gpu, scene, split);
+        view
+    }
+
+    fn window(&self) -> &Window {
+        &self.surface.window
+    }
+
+    fn is_split(&self) -> bool {
+        self.viewports.len() > 1
+    }
+
+    /// Show one camera, or two side by side. The first camera is kept
+    /// either way.
+    fn set_split(&mut self, gpu: &GpuContext, scene: &Scene, split: bool) {
+        self.dragging = None;
+        if split {
+            self.viewports[0].rect = ViewportRect::half(false);
+            if self.viewports.len() == 1 {
+                self.viewports.push(Viewport::new(
+                    &gpu.device,
+                    scene.camera_layout(),
+                    ViewportRect::half(true),
+                    top_down_camera(),
+                ));
+            }
+        } else {
+            self.viewports.truncate(1);
+            self.viewports[0].rect = ViewportRect::FULL;
+        }
+    }
+
+    fn viewport_at(&self, position: PhysicalPosition<f64>) -> Option<usize> {
+        let size = self.surface.size();
+        self.viewports
+            .iter()
+            .position(|viewport| viewport.rect.contains(position, size))
+    }
+
+    /// Mouse input goes to the camera of the viewport under the cursor
+    fn input(&mut self, gpu: &GpuContext, scene: &Scene, event: &WindowEvent) -> bool {
+        match event {
+            WindowEvent::MouseInput {
+                button: MouseButton::Left,
+                state,
+                ..
+            } => {
+                self.dragging = match state {
+                    ElementState::Pressed => self.viewport_at(self.cursor),
+                    ElementState::Released => None,
+                };
+                true
+            }
+            WindowEvent::CursorMoved { position, .. } => {
+                if let Some(index) = self.dragging {
+                    let dx = (position.x - self.cursor.x) as f32;
+                    let dy = (position.y - self.cursor.y) as f32;
+                    self.viewports[index].camera.orbit(dx, dy);
+                }
+                self.cursor = *position;
+                true
+            }
+            WindowEvent::MouseWheel { delta, .. } => {
+                let amount = match delta {
+                    MouseScrollDelta::LineDelta(_, y) => *y,
+                    MouseScrollDelta::PixelDelta(position) => position.y as f32 / 50.0,
+                };
+                if let Some(index) = self.viewport_at(self.cursor) {
+                    self.viewports[index].camera.zoom(amount);
+                }
+                true
+            }
+            WindowEvent::KeyboardInput {
+                event:
+                    KeyEvent {
+                        state: ElementState::Pressed,
+                        logical_key: Key::Character(c),
+                        ..
+                    },
+                ..
+            } if c.as_str() == "v" => {
+                let split = !self.is_split();
+                self.set_split(gpu, scene, split);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn render(&self, gpu: &GpuContext, scene: &Scene) -> Result<(), wgpu::SurfaceError> {
+        let size = self.surface.size();
+        for viewport in &self.viewports {
+            viewport.update(&gpu.queue, size);
+        }
+
+        let output = self.surface.acquire()?;
+        let view = output
+            .texture
+            .create_view(&wgpu::TextureViewDescriptor::default());
+
+        let mut encoder = gpu
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Render Encoder"),
+            });
+
+        {
+            // One pass per window: clear once, then draw the scene once per
+            // viewport into its own rectangle
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Render Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color {
+                            r: 0.05,
+                            g: 0.05,
+                            b: 0.1,
+                            a: 1.0,
+                        }),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                    view: self.surface.depth_view(),
+                    depth_ops: Some(wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(1.0),
+                        store: wgpu::StoreOp::Store,
+                    }),
+                    stencil_ops: None,
+                }),
+                occlusion_query_set: None,
+                timestamp_writes: None,
+            });
+
+            for viewport in &self.viewports {
+                let (x, y, width, height) = viewport.rect.to_pixels(size);
+                if width == 0 || height == 0 {
+                    continue;
+                }
+                // The viewport maps clip space onto the rectangle; the
+                // scissor makes sure nothing spills past its edges
+                render_pass.set_viewport(x as f32, y as f32, width as f32, height as f32, 0.0, 1.0);
+                render_pass.set_scissor_rect(x, y, width, height);
+                scene.draw(
+                    &mut render_pass,
+                    self.surface.format(),
+                    viewport.bind_group(),
+                );
+            }
+        }
+
+        gpu.queue.submit(std::iter::once(encoder.finish()));
+        output.present();
+
+        Ok(())
+    }
+}
+
+struct App {
+    gpu: GpuContext,
+    scene: Scene,
+    views: HashMap<WindowId, View>,
+    opened: usize,
+    last_frame: Instant,
+}
+
+impl App {
+    async fn new(first: Window) -> Self {
+        let (gpu, surface) = GpuContext::new(Arc::new(first)).await;
+        let mut scene = Scene::new(&gpu.device);
+        let view = View::new(&gpu, &mut scene, surface, false);
+
+        let mut views = HashMap::new();
+        views.insert(view.window().id(), view);
+        Self {
+            gpu,
+            scene,
+            views,
+            opened: 1,
+            last_frame: Instant::now(),
+        }
+    }
+
+    /// Add another window on the same device, drawing the same scene
+    fn open_window(&mut self, elwt: &EventLoopWindowTarget<()>, split: bool) {
+        self.opened += 1;
+        let window = WindowBuilder::new()
+            .with_title(format!("{} - Window {}", TITLE, self.opened))
+            .build(elwt)
+            .unwrap();
+        let surface = WindowSurface::new(&self.gpu, Arc::new(window));
+        let view = View::new(&self.gpu, &mut self.scene, surface, split);
+        self.views.insert(view.window().id(), view);
+    }
+
+    /// Route an event to the window it belongs to
+    fn window_event(
+        &mut self,
+        elwt: &EventLoopWindowTarget<()>,
+        window_id: WindowId,
+        event: WindowEvent,
+    ) {
+        let Some(view) = self.views.get_mut(&window_id) else {
+            // Events can still arrive for a window that was just closed
+            return;
+        };
+        if view.input(&self.gpu, &self.scene, &event) {
+            return;
+        }
+
+        match event {
+            WindowEvent::CloseRequested => {
+                // Dropping the view drops its surface and then its window
+                self.views.remove(&window_id);
+                if self.views.is_empty() {
+                    elwt.exit();
+                }
+            }
+            WindowEvent::KeyboardInput {
+                event:
+                    KeyEvent {
+                        state: ElementState::Pressed,
+                        logical_key,
+                        ..
+                    },
+                ..
+            } => match logical_key {
+                Key::Named(NamedKey::Escape) => elwt.exit(),
+                Key::Character(c) if c.as_str() == "n" => self.open_window(elwt, false),
+                _ => {}
+            },
+            WindowEvent::Resized(physical_size) => {
+                view.surface.resize(&self.gpu.device, physical_size);
+            }
+            WindowEvent::RedrawRequested => match view.render(&self.gpu, &self.scene) {
+                Ok(_) => {}
+                Err(wgpu::SurfaceError::Lost) => {
+                    let size = view.surface.size();
+                    view.surface.resize(&self.gpu.device, size);
+                }
+                Err(wgpu::SurfaceError::OutOfMemory) => elwt.exit(),
+                Err(e) => eprintln!("{:?}", e),
+            },
+            _ => {}
+        }
+    }
+
+    /// Advance the shared scene once per frame, then redraw every window
+    fn update(&mut self) {
+        let now = Instant::now();
+        let dt = (now - self.last_frame).as_secs_f32();
+        self.last_frame = now;
+
+        self.scene.update(&self.gpu.queue, dt);
+        for view in self.views.values() {
+            view.window().request_redraw();
+        }
+    }
+}
+
+fn main() {
+    env_logger::init();
+
+    let event_loop = EventLoop::new().unwrap();
+    let window = WindowBuilder::new()
+        .with_title(format!("{} - Window 1", TITLE))
+        .build(&event_loop)
+        .unwrap();
+
+    let mut app = pollster::block_on(App::new(window));
+    // The second window starts out split, to show both ways of having
+    // several cameras
+    app.open_window(&event_loop, true);
+
+    event_loop
+        .run(move |event, elwt| match event {
+            Event::WindowEvent { window_id, event } => app.window_event(elwt, window_id, event),
+            Event::AboutToWait => app.update(),
+            _ => {}
+        })
+        .unwrap();
+}
@@ -0,0 +1,290 @@
+//! The scene every view looks at: a floor and a grid of spinning cubes.
+//!
+//! All geometry lives in one vertex, index and instance buffer on the
+//! shared device and is updated once per frame, however many windows and
+//! viewports draw it. A view only brings its own camera bind group.
+
+use cgmath::{Matrix4, Rad, Vector3};
+use std::collections::HashMap;
+use wgpu::util::DeviceExt;
+
+use crate::gpu::DEPTH_FORMAT;
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct Vertex {
+    position: [f32; 3],
+    normal: [f32; 3],
+}
+
+impl Vertex {
+    fn desc() -> wgpu::VertexBufferLayout<'static> {
+        const ATTRIBUTES: [wgpu::VertexAttribute; 2] =
+            wgpu::vertex_attr_array![0 => Float32x3, 1 => Float32x3];
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<Vertex>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &ATTRIBUTES,
+        }
+    }
+}
+
+/// Per-instance model matrix and color
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct InstanceRaw {
+    model: [[f32; 4]; 4],
+    color: [f32; 3],
+}
+
+impl InstanceRaw {
+    fn desc() -> wgpu::VertexBufferLayout<'static> {
+        const ATTRIBUTES: [wgpu::VertexAttribute; 5] = wgpu::vertex_attr_array![
+            2 => Float32x4,
+            3 => Float32x4,
+            4 => Float32x4,
+            5 => Float32x4,
+            6 => Float32x3,
+        ];
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<InstanceRaw>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &ATTRIBUTES,
+        }
+    }
+}
+
+/// A unit cube centered on the origin, with flat normals per face
+fn cube_mesh() -> (Vec<Vertex>, Vec<u16>) {
+    // (normal, u, v) with u x v = normal, so every face winds CCW from outside
+    let faces: [([f32; 3], [f32; 3], [f32; 3]); 6] = [
+        ([1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]),
+        ([-1.0, 0.0, 0.0], [0.0, 0.0, 1.0], [0.0, 1.0, 0.0]),
+        ([0.0, 1.0, 0.0], [0.0, 0.0, 1.0], [1.0, 0.0, 0.0]),
+        ([0.0, -1.0, 0.0], [1.0, 0.0, 0.0], [0.0, 0.0, 1.0]),
+        ([0.0, 0.0, 1.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+        ([0.0, 0.0, -1.0], [0.0, 1.0, 0.0], [1.0, 0.0, 0.0]),
+    ];
+
+    let mut vertices = Vec::with_capacity(24);
+    let mut indices = Vec::with_capacity(36);
+    for (normal, u, v) in faces {
+        let base = vertices.len() as u16;
+        for (su, sv) in [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)] {
+            let position = [0, 1, 2].map(|i| 0.5 * (normal[i] + su * u[i] + sv * v[i]));
+            vertices.push(Vertex { position, normal });
+        }
+        indices.extend([0, 1, 2, 2, 3, 0].map(|i| base + i));
+    }
+    (vertices, indices)
+}
+
+struct Object {
+    position: Vector3<f32>,
+    scale: Vector3<f32>,
+    color: [f32; 3],
+    /// Rotation around the y axis, and how fast it changes (rad/s)
+    angle: f32,
+    spin: f32,
+}
+
+impl Object {
+    fn to_raw(&self) -> InstanceRaw {
+        let model = Matrix4::from_translation(self.position)
+            * Matrix4::from_angle_y(Rad(self.angle))
+            * Matrix4::from_nonuniform_scale(self.scale.x, self.scale.y, self.scale.z);
+        InstanceRaw {
+            model: model.into(),
+            color: self.color,
+        }
+    }
+}
+
+/// Half the number of cubes along each side of the grid
+const GRID_RADIUS: i32 = 2;
+const GRID_SPACING: f32 = 2.0;
+
+pub struct Scene {
+    objects: Vec<Object>,
+    vertex_buffer: wgpu::Buffer,
+    index_buffer: wgpu::Buffer,
+    num_indices: u32,
+    instance_buffer: wgpu::Buffer,
+    camera_layout: wgpu::BindGroupLayout,
+    pipeline_layout: wgpu::PipelineLayout,
+    shader: wgpu::ShaderModule,
+    /// Windows may end up with different surface formats; a render
+    /// pipeline is tied to one, so there is one pipeline per format in use
+    pipelines: HashMap<wgpu::TextureFormat, wgpu::RenderPipeline>,
+}
+
+impl Scene {
+    pub fn new(device: &wgpu::Device) -> Self {
+        let mut objects = vec![Object {
+            position: Vector3::new(0.0, -0.55, 0.0),
+            scale: Vector3::new(12.0, 0.1, 12.0),
+            color: [0.35, 0.35, 0.4],
+            angle: 0.0,
+            spin: 0.0,
+        }];
+        for x in -GRID_RADIUS..=GRID_RADIUS {
+            for z in -GRID_RADIUS..=GRID_RADIUS {
+                let (fx, fz) = (x as f32, z as f32);
+                objects.push(Object {
+                    position: Vector3::new(fx * GRID_SPACING, 0.0, fz * GRID_SPACING),
+                    scale: Vector3::new(1.0, 1.0, 1.0),
+                    color: [
+                        0.5 + 0.5 * fx / GRID_RADIUS as f32,
+                        0.6,
+                        0.5 + 0.5 * fz / GRID_RADIUS as f32,
+                    ],
+                    angle: 0.0,
+                    spin: 0.5 + 0.15 * (x + z) as f32,
+                });
+            }
+        }
+
+        let (vertices, indices) = cube_mesh();
+        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Vertex Buffer"),
+            contents: bytemuck::cast_slice(&vertices),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+        let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Index Buffer"),
+            contents: bytemuck::cast_slice(&indices),
+            usage: wgpu::BufferUsages::INDEX,
+        });
+        let instances: Vec<InstanceRaw> = objects.iter().map(Object::to_raw).collect();
+        let instance_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Instance Buffer"),
+            contents: bytemuck::cast_slice(&instances),
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let camera_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+            label: Some("camera_bind_group_layout"),
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Render Pipeline Layout"),
+            bind_group_layouts: &[&camera_layout],
+            push_constant_ranges: &[],
+        });
+
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("shader.wgsl").into()),
+        });
+
+        Self {
+            objects,
+            vertex_buffer,
+            index_buffer,
+            num_indices: indices.len() as u32,
+            instance_buffer,
+            camera_layout,
+            pipeline_layout,
+            shader,
+            pipelines: HashMap::new(),
+        }
+    }
+
+    /// Layout of the bind group each viewport binds its camera with
+    pub fn camera_layout(&self) -> &wgpu::BindGroupLayout {
+        &self.camera_layout
+    }
+
+    /// Make sure a pipeline exists for surfaces of `format`
+    pub fn prepare_format(&mut self, device: &wgpu::Device, format: wgpu::TextureFormat) {
+        if !self.pipelines.contains_key(&format) {
+            let pipeline = self.create_pipeline(device, format);
+            self.pipelines.insert(format, pipeline);
+        }
+    }
+
+    fn create_pipeline(
+        &self,
+        device: &wgpu::Device,
+        format: wgpu::TextureFormat,
+    ) -> wgpu::RenderPipeline {
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some(&format!("Render Pipeline ({:?})", format)),
+            layout: Some(&self.pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &self.shader,
+                entry_point: "vs_main",
+                buffers: &[Vertex::desc(), InstanceRaw::desc()],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &self.shader,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: Some(wgpu::BlendState::REPLACE),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode: Some(wgpu::Face::Back),
+                polygon_mode: wgpu::PolygonMode::Fill,
+                unclipped_depth: false,
+                conservative: false,
+            },
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: DEPTH_FORMAT,
+                depth_write_enabled: true,
+                depth_compare: wgpu::CompareFunction::Less,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState {
+                count: 1,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+            multiview: None,
+        })
+    }
+
+    /// Advance the animation. Called once per frame, not once per view.
+    pub fn update(&mut self, queue: &wgpu::Queue, dt: f32) {
+        for object in &mut self.objects {
+            object.angle += object.spin * dt;
+        }
+        let instances: Vec<InstanceRaw> = self.objects.iter().map(Object::to_raw).collect();
+        queue.write_buffer(&self.instance_buffer, 0, bytemuck::cast_slice(&instances));
+    }
+
+    /// Draw everything as seen through `camera`, into a target of `format`
+    pub fn draw<'a>(
+        &'a self,
+        render_pass: &mut wgpu::RenderPass<'a>,
+        format: wgpu::TextureFormat,
+        camera: &'a wgpu::BindGroup,
+    ) {
+        let pipeline = self
+            .pipelines
+            .get(&format)
+            .expect("prepare_format was not called for this surface format");
+        render_pass.set_pipeline(pipeline);
+        render_pass.set_bind_group(0, camera, &[]);
+        render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+        render_pass.set_vertex_buffer(1, self.instance_buffer.slice(..));
+        render_pass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
+        render_pass.draw_indexed(0..self.num_indices, 0, 0..self.objects.len() as u32);
+    }
+}
@@ -0,0 +1,65 @@
+// Shared by every window and viewport; only the camera bind group changes
+
+struct Camera {
+    view_proj: mat4x4<f32>,
+    position: vec4<f32>,
+};
+
+@group(0) @binding(0)
+var<uniform> camera: Camera;
+
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) normal: vec3<f32>,
+};
+
+struct InstanceInput {
+    @location(2) model_0: vec4<f32>,
+    @location(3) model_1: vec4<f32>,
+    @location(4) model_2: vec4<f32>,
+    @location(5) model_3: vec4<f32>,
+    @location(6) color: vec3<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) world_position: vec3<f32>,
+    @location(1) world_normal: vec3<f32>,
+    @location(2) color: vec3<f32>,
+};
+
+@vertex
+fn vs_main(vertex: VertexInput, instance: InstanceInput) -> VertexOutput {
+    let model = mat4x4<f32>(
+        instance.model_0,
+        instance.model_1,
+        instance.model_2,
+        instance.model_3,
+    );
+    let world_position = model * vec4<f32>(vertex.position, 1.0);
+
+    var out: VertexOutput;
+    out.clip_position = camera.view_proj * world_position;
+    out.world_position = world_position.xyz;
+    // Instances are only rotated and scaled along their own axes, so the
+    // model matrix is fine for normals once they are renormalized
+    out.world_normal = (model * vec4<f32>(vertex.normal, 0.0)).xyz;
+    out.color = instance.color;
+    return out;
+}
+
+const LIGHT_DIRECTION: vec3<f32> = vec3<f32>(0.4, 1.0, 0.3);
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let normal = normalize(in.world_normal);
+    let light_dir = normalize(LIGHT_DIRECTION);
+    let view_dir = normalize(camera.position.xyz - in.world_position);
+
+    let ambient = 0.15;
+    let diffuse = max(dot(normal, light_dir), 0.0);
+    let half_dir = normalize(light_dir + view_dir);
+    let specular = pow(max(dot(normal, half_dir), 0.0), 32.0) * 0.3;
+
+    return vec4<f32>(in.color * (ambient + diffuse) + vec3<f32>(specular), 1.0);
+}
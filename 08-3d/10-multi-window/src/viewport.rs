@@ -0,0 +1,192 @@
+//! Independent cameras, each drawn into a rectangle of a window.
+//!
+//! A viewport's rectangle is stored in fractions of the window (0.0 to 1.0),
+//! so it follows resizes without any bookkeeping. It's turned into pixels
+//! only when rendering (`set_viewport` / `set_scissor_rect`) and when
+//! deciding which viewport the mouse is over.
+
+use cgmath::{InnerSpace, Matrix4, Point3, Vector3};
+use std::f32::consts::FRAC_PI_2;
+use wgpu::util::DeviceExt;
+use winit::dpi::{PhysicalPosition, PhysicalSize};
+
+/// cgmath produces OpenGL clip space (z from -1 to 1); wgpu expects 0 to 1
+#[rustfmt::skip]
+const OPENGL_TO_WGPU_MATRIX: Matrix4<f32> = Matrix4::new(
+    1.0, 0.0, 0.0, 0.0,
+    0.0, 1.0, 0.0, 0.0,
+    0.0, 0.0, 0.5, 0.0,
+    0.0, 0.0, 0.5, 1.0,
+);
+
+/// Radians of orbit per pixel of mouse drag
+const ORBIT_SENSITIVITY: f32 = 0.01;
+/// Stay just short of straight up or down, where `look_at` breaks down
+const MAX_PITCH: f32 = FRAC_PI_2 - 0.01;
+
+/// A camera circling a target point
+#[derive(Debug, Clone, Copy)]
+pub struct OrbitCamera {
+    pub target: Point3<f32>,
+    /// Angle around the y axis, in radians
+    pub yaw: f32,
+    /// Angle above the horizon, in radians
+    pub pitch: f32,
+    pub distance: f32,
+    /// Vertical field of view, in degrees
+    pub fovy: f32,
+}
+
+impl OrbitCamera {
+    pub fn new(yaw: f32, pitch: f32, distance: f32) -> Self {
+        Self {
+            target: Point3::new(0.0, 0.0, 0.0),
+            yaw,
+            pitch: pitch.clamp(-MAX_PITCH, MAX_PITCH),
+            distance,
+            fovy: 45.0,
+        }
+    }
+
+    pub fn eye(&self) -> Point3<f32> {
+        let direction = Vector3::new(
+            self.pitch.cos() * self.yaw.cos(),
+            self.pitch.sin(),
+            self.pitch.cos() * self.yaw.sin(),
+        );
+        self.target + direction.normalize() * self.distance
+    }
+
+    pub fn view_projection(&self, aspect: f32) -> Matrix4<f32> {
+        let view = Matrix4::look_at_rh(self.eye(), self.target, Vector3::unit_y());
+        let projection = cgmath::perspective(cgmath::Deg(self.fovy), aspect, 0.1, 100.0);
+        OPENGL_TO_WGPU_MATRIX * projection * view
+    }
+
+    /// Rotate around the target by a mouse drag of `dx`, `dy` pixels
+    pub fn orbit(&mut self, dx: f32, dy: f32) {
+        self.yaw += dx * ORBIT_SENSITIVITY;
+        self.pitch = (self.pitch + dy * ORBIT_SENSITIVITY).clamp(-MAX_PITCH, MAX_PITCH);
+    }
+
+    /// Move closer (positive `amount`) or further away
+    pub fn zoom(&mut self, amount: f32) {
+        self.distance = (self.distance * (1.0 - amount * 0.1)).clamp(2.0, 50.0);
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct CameraUniform {
+    view_proj: [[f32; 4]; 4],
+    position: [f32; 4],
+}
+
+/// Fractions of the window covered by a viewport
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ViewportRect {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl ViewportRect {
+    pub const FULL: ViewportRect = ViewportRect {
+        x: 0.0,
+        y: 0.0,
+        width: 1.0,
+        height: 1.0,
+    };
+
+    /// The left or right half of the window
+    pub fn half(right: bool) -> Self {
+        ViewportRect {
+            x: if right { 0.5 } else { 0.0 },
+            y: 0.0,
+            width: 0.5,
+            height: 1.0,
+        }
+    }
+
+    /// Whole pixels `(x, y, width, height)` within a window of `size`.
+    /// Edges are rounded, so neighbouring viewports share an edge exactly.
+    pub fn to_pixels(self, size: PhysicalSize<u32>) -> (u32, u32, u32, u32) {
+        let (w, h) = (size.width as f32, size.height as f32);
+        let left = (self.x * w).round() as u32;
+        let top = (self.y * h).round() as u32;
+        let right = ((self.x + self.width) * w).round() as u32;
+        let bottom = ((self.y + self.height) * h).round() as u32;
+        (
+            left.min(size.width),
+            top.min(size.height),
+            right.min(size.width).saturating_sub(left),
+            bottom.min(size.height).saturating_sub(top),
+        )
+    }
+
+    pub fn contains(self, position: PhysicalPosition<f64>, size: PhysicalSize<u32>) -> bool {
+        let x = position.x as f32 / size.width as f32;
+        let y = position.y as f32 / size.height as f32;
+        (self.x..self.x + self.width).contains(&x) && (self.y..self.y + self.height).contains(&y)
+    }
+}
+
+/// A camera, where it's drawn, and the GPU buffer holding its matrices
+pub struct Viewport {
+    pub rect: ViewportRect,
+    pub camera: OrbitCamera,
+    buffer: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+}
+
+impl Viewport {
+    pub fn new(
+        device: &wgpu::Device,
+        layout: &wgpu::BindGroupLayout,
+        rect: ViewportRect,
+        camera: OrbitCamera,
+    ) -> Self {
+        // Every viewport needs its own buffer: queue writes all land before
+        // the frame's commands run, so uniforms shared between viewports
+        // would end up with the last camera written
+        let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Camera Buffer"),
+            contents: bytemuck::cast_slice(&[CameraUniform {
+                view_proj: camera.view_projection(1.0).into(),
+                position: camera.eye().to_homogeneous().into(),
+            }]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: buffer.as_entire_binding(),
+            }],
+            label: Some("camera_bind_group"),
+        });
+        Self {
+            rect,
+            camera,
+            buffer,
+            bind_group,
+        }
+    }
+
+    pub fn bind_group(&self) -> &wgpu::BindGroup {
+        &self.bind_group
+    }
+
+    /// Upload the camera, using the aspect ratio of the viewport itself
+    /// rather than of the whole window
+    pub fn update(&self, queue: &wgpu::Queue, window_size: PhysicalSize<u32>) {
+        let (_, _, width, height) = self.rect.to_pixels(window_size);
+        let aspect = width.max(1) as f32 / height.max(1) as f32;
+        let uniform = CameraUniform {
+            view_proj: self.camera.view_projection(aspect).into(),
+            position: self.camera.eye().to_homogeneous().into(),
+        };
+        queue.write_buffer(&self.buffer, 0, bytemuck::cast_slice(&[uniform]));
+    }
+}
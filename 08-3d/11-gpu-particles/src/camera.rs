@@ -0,0 +1,68 @@
+//! A camera circling the fountain, turned by dragging the mouse.
+
+use cgmath::{InnerSpace, Matrix4, Point3, Vector3};
+use std::f32::consts::FRAC_PI_2;
+
+/// cgmath produces OpenGL clip space (z from -1 to 1); wgpu expects 0 to 1
+#[rustfmt::skip]
+const OPENGL_TO_WGPU_MATRIX: Matrix4<f32> = Matrix4::new(
+    1.0, 0.0, 0.0, 0.0,
+    0.0, 1.0, 0.0, 0.0,
+    0.0, 0.0, 0.5, 0.0,
+    0.0, 0.0, 0.5, 1.0,
+);
+
+/// Radians of orbit per pixel of mouse drag
+const ORBIT_SENSITIVITY: f32 = 0.01;
+/// Stay just short of straight up or down, where `look_at` breaks down
+const MAX_PITCH: f32 = FRAC_PI_2 - 0.01;
+
+#[derive(Debug, Clone, Copy)]
+pub struct OrbitCamera {
+    pub target: Point3<f32>,
+    /// Angle around the y axis, in radians
+    pub yaw: f32,
+    /// Angle above the horizon, in radians
+    pub pitch: f32,
+    pub distance: f32,
+}
+
+impl OrbitCamera {
+    pub fn new(target: Point3<f32>, yaw: f32, pitch: f32, distance: f32) -> Self {
+        Self {
+            target,
+            yaw,
+            pitch: pitch.clamp(-MAX_PITCH, MAX_PITCH),
+            distance,
+        }
+    }
+
+    pub fn eye(&self) -> Point3<f32> {
+        let direction = Vector3::new(
+            self.pitch.cos() * self.yaw.cos(),
+            self.pitch.sin(),
+            self.pitch.cos() * self.yaw.sin(),
+        );
+        self.target + direction.normalize() * self.distance
+    }
+
+    pub fn view(&self) -> Matrix4<f32> {
+        Matrix4::look_at_rh(self.eye(), self.target, Vector3::unit_y())
+    }
+
+    pub fn view_projection(&self, aspect: f32) -> Matrix4<f32> {
+        let projection = cgmath::perspective(cgmath::Deg(45.0), aspect, 0.1, 200.0);
+        OPENGL_TO_WGPU_MATRIX * projection * self.view()
+    }
+
+    /// Rotate around the target by a mouse drag of `dx`, `dy` pixels
+    pub fn orbit(&mut self, dx: f32, dy: f32) {
+        self.yaw += dx * ORBIT_SENSITIVITY;
+        self.pitch = (self.pitch + dy * ORBIT_SENSITIVITY).clamp(-MAX_PITCH, MAX_PITCH);
+    }
+
+    /// Move closer (positive `amount`) or further away
+    pub fn zoom(&mut self, amount: f32) {
+        self.distance = (self.distance * (1.0 - amount * 0.1)).clamp(3.0, 80.0);
+    }
+}
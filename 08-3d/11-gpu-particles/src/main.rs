@@ -0,0 +1,326 @@
+use std::sync::Arc;
+use std::time::Instant;
+use winit::{
+    event::*,
+    event_loop::EventLoop,
+    keyboard::{Key, NamedKey},
+    window::{Window, WindowBuilder},
+};
+
+mod camera;
+mod particles;
+
+use camera::OrbitCamera;
+use particles::{ParticleSystem, MAX_PARTICLES};
+
+const WINDOW_TITLE: &str = "wgpu GPU Particles";
+
+const INITIAL_PARTICLES: u32 = 1 << 18;
+/// The fewest particles the down arrow goes to
+const MIN_PARTICLES: u32 = 1 << 12;
+
+/// The longest step the simulation takes: after a stall, particles slow
+/// down for a frame rather than jumping
+const MAX_STEP: f32 = 0.05;
+
+struct State<'a> {
+    window: Arc<Window>,
+    surface: wgpu::Surface<'a>,
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    config: wgpu::SurfaceConfiguration,
+    size: winit::dpi::PhysicalSize<u32>,
+    particles: ParticleSystem,
+    /// How many particles are simulated and drawn; the up and down arrows
+    /// double and halve it
+    count: u32,
+    camera: OrbitCamera,
+    /// Space freezes the simulation; the camera still moves
+    paused: bool,
+    /// Simulated seconds, which stop while paused
+    time: f32,
+    last_frame: Instant,
+    dragging: bool,
+    cursor: winit::dpi::PhysicalPosition<f64>,
+    // FPS counter shown in the window title
+    frames: u32,
+    fps_timer: Instant,
+}
+
+impl<'a> State<'a> {
+    async fn new(window: Window) -> Self {
+        let window = Arc::new(window);
+        let size = window.inner_size();
+
+        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
+            backends: wgpu::Backends::all(),
+            ..Default::default()
+        });
+
+        let surface = instance.create_surface(Arc::clone(&window)).unwrap();
+
+        let adapter = instance
+            .request_adapter(&wgpu::RequestAdapterOptions {
+                power_preference: wgpu::PowerPreference::HighPerformance,
+                compatible_surface: Some(&surface),
+                force_fallback_adapter: false,
+            })
+            .await
+            .unwrap();
+
+        let (device, queue) = adapter
+            .request_device(
+                &wgpu::DeviceDescriptor {
+                    required_features: wgpu::Features::empty(),
+                    required_limits: wgpu::Limits::default(),
+                    label: None,
+                },
+                None,
+            )
+            .await
+            .unwrap();
+
+        let surface_caps = surface.get_capabilities(&adapter);
+        let surface_format = surface_caps
+            .formats
+            .iter()
+            .copied()
+            .find(|f| f.is_srgb())
+            .unwrap_or(surface_caps.formats[0]);
+
+        let config = wgpu::SurfaceConfiguration {
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            format: surface_format,
+            width: size.width,
+            height: size.height,
+            present_mode: surface_caps.present_modes[0],
+            alpha_mode: surface_caps.alpha_modes[0],
+            view_formats: vec![],
+            desired_maximum_frame_latency: 2,
+        };
+        surface.configure(&device, &config);
+
+        let particles = ParticleSystem::new(&device, config.format);
+
+        Self {
+            window,
+            surface,
+            device,
+            queue,
+            config,
+            size,
+            particles,
+            count: INITIAL_PARTICLES,
+            camera: OrbitCamera::new(cgmath::Point3::new(0.0, 3.0, 0.0), 0.6, 0.3, 16.0),
+            paused: false,
+            time: 0.0,
+            last_frame: Instant::now(),
+            dragging: false,
+            cursor: winit::dpi::PhysicalPosition::new(0.0, 0.0),
+            frames: 0,
+            fps_timer: Instant::now(),
+        }
+    }
+
+    pub fn window(&self) -> &Window {
+        &self.window
+    }
+
+    pub fn resize(&mut self, new_size: winit::dpi::PhysicalSize<u32>) {
+        if new_size.width > 0 && new_size.height > 0 {
+            self.size = new_size;
+            self.config.width = new_size.width;
+            self.config.height = new_size.height;
+            self.surface.configure(&self.device, &self.config);
+        }
+    }
+
+    fn input(&mut self, event: &WindowEvent) -> bool {
+        match event {
+            WindowEvent::KeyboardInput {
+                event:
+                    KeyEvent {
+                        state: ElementState::Pressed,
+                        logical_key: Key::Named(key),
+                        repeat: false,
+                        ..
+                    },
+                ..
+            } => {
+                match key {
+                    NamedKey::Space => self.paused = !self.paused,
+                    NamedKey::ArrowUp => self.count = (self.count * 2).min(MAX_PARTICLES),
+                    NamedKey::ArrowDown => self.count = (self.count / 2).max(MIN_PARTICLES),
+                    _ => return false,
+                }
+                true
+            }
+            WindowEvent::MouseInput {
+                button: MouseButton::Left,
+                state,
+                ..
+            } => {
+                self.dragging = *state == ElementState::Pressed;
+                true
+            }
+            WindowEvent::CursorMoved { position, .. } => {
+                if self.dragging {
+                    let dx = (position.x - self.cursor.x) as f32;
+                    let dy = (position.y - self.cursor.y) as f32;
+                    self.camera.orbit(dx, dy);
+                }
+                self.cursor = *position;
+                true
+            }
+            WindowEvent::MouseWheel { delta, .. } => {
+                let amount = match delta {
+                    MouseScrollDelta::LineDelta(_, y) => *y,
+                    MouseScrollDelta::PixelDelta(position) => position.y as f32 / 50.0,
+                };
+                self.camera.zoom(amount);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Time since the last frame for the simulation, 0 while paused
+    fn frame_time(&mut self) -> f32 {
+        let now = Instant::now();
+        let dt = (now - self.last_frame).as_secs_f32().min(MAX_STEP);
+        self.last_frame = now;
+
+        self.frames += 1;
+        let elapsed = self.fps_timer.elapsed().as_secs_f32();
+        if elapsed >= 1.0 {
+            self.window.set_title(&format!(
+                "{} - {} particles, {:.0} FPS{}",
+                WINDOW_TITLE,
+                self.count,
+                self.frames as f32 / elapsed,
+                if self.paused { " (paused)" } else { "" }
+            ));
+            self.frames = 0;
+            self.fps_timer = Instant::now();
+        }
+
+        if self.paused {
+            0.0
+        } else {
+            dt
+        }
+    }
+
+    fn render(&mut self) -> Result<(), wgpu::SurfaceError> {
+        let dt = self.frame_time();
+        self.time += dt;
+        // The attractor loops around the fountain, tugging the spray
+        // sideways as it goes
+        let attractor = cgmath::Point3::new(
+            (self.time * 0.7).cos() * 3.0,
+            4.0 + (self.time * 1.3).sin(),
+            (self.time * 0.7).sin() * 3.0,
+        );
+        let aspect = self.config.width as f32 / self.config.height as f32;
+        self.particles.set_camera(&self.queue, &self.camera, aspect);
+
+        let output = self.surface.get_current_texture()?;
+        let view = output
+            .texture
+            .create_view(&wgpu::TextureViewDescriptor::default());
+
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Frame Encoder"),
+            });
+
+        // The compute pass writes the particles and the render pass reads
+        // them, in that order, in one submission
+        if dt > 0.0 {
+            self.particles.simulate(
+                &self.queue,
+                &mut encoder,
+                self.count,
+                dt,
+                self.time,
+                attractor,
+            );
+        }
+
+        {
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Particle Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color {
+                            r: 0.01,
+                            g: 0.01,
+                            b: 0.02,
+                            a: 1.0,
+                        }),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                occlusion_query_set: None,
+                timestamp_writes: None,
+            });
+
+            self.particles.draw(&mut render_pass, self.count);
+        }
+
+        self.queue.submit(std::iter::once(encoder.finish()));
+        output.present();
+
+        Ok(())
+    }
+}
+
+fn main() {
+    env_logger::init();
+
+    let event_loop = EventLoop::new().unwrap();
+    let window = WindowBuilder::new()
+        .with_title(WINDOW_TITLE)
+        .build(&event_loop)
+        .unwrap();
+
+    let mut state = pollster::block_on(State::new(window));
+
+    event_loop
+        .run(move |event, elwt| match event {
+            Event::WindowEvent {
+                ref event,
+                window_id,
+            } if window_id == state.window().id() && !state.input(event) => match event {
+                WindowEvent::CloseRequested
+                | WindowEvent::KeyboardInput {
+                    event:
+                        KeyEvent {
+                            state: ElementState::Pressed,
+                            logical_key: Key::Named(NamedKey::Escape),
+                            ..
+                        },
+                    ..
+                } => elwt.exit(),
+                WindowEvent::Resized(physical_size) => {
+                    state.resize(*physical_size);
+                }
+                WindowEvent::RedrawRequested => match state.render() {
+                    Ok(_) => {}
+                    Err(wgpu::SurfaceError::Lost) => state.resize(state.size),
+                    Err(wgpu::SurfaceError::OutOfMemory) => elwt.exit(),
+                    Err(e) => eprintln!("{:?}", e),
+                },
+                _ => {}
+            },
+            Event::AboutToWait => {
+                state.window().request_redraw();
+            }
+            _ => {}
+        })
+        .unwrap();
+}
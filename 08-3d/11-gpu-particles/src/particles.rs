@@ -0,0 +1,326 @@
+//! A particle fountain simulated by a compute shader and drawn straight out
+//! of the same buffer.
+//!
+//! Every particle lives in one storage buffer. Each frame a compute pass
+//! runs `simulate.wgsl` once per particle, in workgroups of
+//! `WORKGROUP_SIZE`, updating its position and velocity in place. A render
+//! pass then binds that buffer as an instance-rate vertex buffer and draws a
+//! camera-facing quad for every particle. The particle data never leaves the
+//! GPU: the CPU only writes a few uniforms per frame.
+//!
+//! Both passes go into one command encoder. wgpu sees the buffer written as
+//! `STORAGE` in the compute pass and read as `VERTEX` in the render pass,
+//! and inserts the barrier between them, so the draw always sees this
+//! frame's positions.
+//!
+//! No particle reads another, so each one can be updated in place. A
+//! simulation where particles interact (flocking, n-body) would need two
+//! buffers and swap them every frame, so no invocation reads a value
+//! another has already overwritten.
+
+use cgmath::{Matrix, Matrix4, Point3};
+use rand::Rng;
+use wgpu::util::DeviceExt;
+
+use crate::camera::OrbitCamera;
+
+/// How many particles the buffer holds; fewer may be simulated and drawn
+pub const MAX_PARTICLES: u32 = 1 << 20;
+
+/// Must match `@workgroup_size` in simulate.wgsl
+const WORKGROUP_SIZE: u32 = 256;
+
+/// One particle, as stored in the buffer and read by both shaders
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct Particle {
+    position: [f32; 3],
+    /// Seconds since the particle left the fountain; negative while it
+    /// waits to be launched for the first time
+    age: f32,
+    velocity: [f32; 3],
+    /// Seconds it lives before it's launched again
+    lifetime: f32,
+}
+
+impl Particle {
+    const ATTRIBUTES: [wgpu::VertexAttribute; 4] = wgpu::vertex_attr_array![
+        0 => Float32x3,
+        1 => Float32,
+        2 => Float32x3,
+        3 => Float32,
+    ];
+
+    /// The buffer read as vertex data: one particle per instance
+    fn desc() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<Particle>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &Self::ATTRIBUTES,
+        }
+    }
+}
+
+/// The `SimParams` uniform in simulate.wgsl
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct SimParams {
+    delta_time: f32,
+    time: f32,
+    count: u32,
+    attractor_strength: f32,
+    attractor: [f32; 3],
+    _padding: f32,
+}
+
+/// The `Camera` uniform in render.wgsl
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct CameraUniform {
+    view_proj: [[f32; 4]; 4],
+    /// The camera's right and up directions in the world, to build each
+    /// particle's quad from; `w` is unused
+    right: [f32; 4],
+    up: [f32; 4],
+}
+
+pub struct ParticleSystem {
+    particles: wgpu::Buffer,
+    params: wgpu::Buffer,
+    camera: wgpu::Buffer,
+    compute_pipeline: wgpu::ComputePipeline,
+    compute_bind_group: wgpu::BindGroup,
+    render_pipeline: wgpu::RenderPipeline,
+    render_bind_group: wgpu::BindGroup,
+}
+
+impl ParticleSystem {
+    /// A full buffer of particles waiting to launch, drawn into targets of
+    /// `format`
+    pub fn new(device: &wgpu::Device, format: wgpu::TextureFormat) -> Self {
+        // Launch times are spread over each particle's lifetime, so the
+        // fountain starts as a steady stream rather than one burst
+        let mut rng = rand::thread_rng();
+        let initial: Vec<Particle> = (0..MAX_PARTICLES)
+            .map(|_| {
+                let lifetime = rng.gen_range(2.5..4.5);
+                Particle {
+                    position: [0.0; 3],
+                    age: -rng.gen_range(0.0..lifetime),
+                    velocity: [0.0; 3],
+                    lifetime,
+                }
+            })
+            .collect();
+        let particles = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Particle Buffer"),
+            contents: bytemuck::cast_slice(&initial),
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::VERTEX,
+        });
+        let params = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Simulation Params"),
+            size: std::mem::size_of::<SimParams>() as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let camera = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Camera Buffer"),
+            size: std::mem::size_of::<CameraUniform>() as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        // Compute: the parameters and the particles, read and written
+        let compute_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("compute_bind_group_layout"),
+                entries: &[
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::COMPUTE,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: wgpu::ShaderStages::COMPUTE,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Storage { read_only: false },
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                ],
+            });
+        let compute_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("compute_bind_group"),
+            layout: &compute_bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: params.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: particles.as_entire_binding(),
+                },
+            ],
+        });
+        let compute_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Simulation Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("simulate.wgsl").into()),
+        });
+        let compute_pipeline_layout =
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("Compute Pipeline Layout"),
+                bind_group_layouts: &[&compute_bind_group_layout],
+                push_constant_ranges: &[],
+            });
+        let compute_pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("Simulation Pipeline"),
+            layout: Some(&compute_pipeline_layout),
+            module: &compute_shader,
+            entry_point: "main",
+        });
+
+        // Render: only the camera is bound; the particles come in as
+        // vertex data
+        let render_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("render_bind_group_layout"),
+                entries: &[wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::VERTEX,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                }],
+            });
+        let render_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("render_bind_group"),
+            layout: &render_bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: camera.as_entire_binding(),
+            }],
+        });
+        let render_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Particle Render Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("render.wgsl").into()),
+        });
+        let render_pipeline_layout =
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("Render Pipeline Layout"),
+                bind_group_layouts: &[&render_bind_group_layout],
+                push_constant_ranges: &[],
+            });
+        // Additive blending: overlapping particles add up to a glow, and
+        // the result doesn't depend on draw order, so neither sorting nor
+        // a depth buffer is needed
+        let additive = wgpu::BlendComponent {
+            src_factor: wgpu::BlendFactor::One,
+            dst_factor: wgpu::BlendFactor::One,
+            operation: wgpu::BlendOperation::Add,
+        };
+        let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Particle Render Pipeline"),
+            layout: Some(&render_pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &render_shader,
+                entry_point: "vs_main",
+                buffers: &[Particle::desc()],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &render_shader,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: Some(wgpu::BlendState {
+                        color: additive,
+                        alpha: additive,
+                    }),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState {
+                // Four vertices per instance make its quad
+                topology: wgpu::PrimitiveTopology::TriangleStrip,
+                ..Default::default()
+            },
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+
+        Self {
+            particles,
+            params,
+            camera,
+            compute_pipeline,
+            compute_bind_group,
+            render_pipeline,
+            render_bind_group,
+        }
+    }
+
+    /// Record a compute pass advancing the first `count` particles by `dt`
+    /// seconds, pulled towards `attractor` as well as down by gravity
+    pub fn simulate(
+        &self,
+        queue: &wgpu::Queue,
+        encoder: &mut wgpu::CommandEncoder,
+        count: u32,
+        dt: f32,
+        time: f32,
+        attractor: Point3<f32>,
+    ) {
+        let params = SimParams {
+            delta_time: dt,
+            time,
+            count,
+            attractor_strength: 60.0,
+            attractor: attractor.into(),
+            _padding: 0.0,
+        };
+        queue.write_buffer(&self.params, 0, bytemuck::bytes_of(&params));
+
+        let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("Simulation Pass"),
+            timestamp_writes: None,
+        });
+        pass.set_pipeline(&self.compute_pipeline);
+        pass.set_bind_group(0, &self.compute_bind_group, &[]);
+        // One invocation per particle, rounded up to whole workgroups; the
+        // shader skips the extra invocations in the last one
+        pass.dispatch_workgroups(count.div_ceil(WORKGROUP_SIZE), 1, 1);
+    }
+
+    /// Upload the camera the particles are drawn from
+    pub fn set_camera(&self, queue: &wgpu::Queue, camera: &OrbitCamera, aspect: f32) {
+        let view: Matrix4<f32> = camera.view();
+        // The view matrix's first two rows are the camera's right and up
+        // directions in world space
+        let uniform = CameraUniform {
+            view_proj: camera.view_projection(aspect).into(),
+            right: view.row(0).into(),
+            up: view.row(1).into(),
+        };
+        queue.write_buffer(&self.camera, 0, bytemuck::bytes_of(&uniform));
+    }
+
+    /// Draw the first `count` particles, one instance each
+    pub fn draw<'a>(&'a self, pass: &mut wgpu::RenderPass<'a>, count: u32) {
+        pass.set_pipeline(&self.render_pipeline);
+        pass.set_bind_group(0, &self.render_bind_group, &[]);
+        pass.set_vertex_buffer(0, self.particles.slice(..));
+        pass.draw(0..4, 0..count);
+    }
+}
@@ -0,0 +1,69 @@
+// Render shader: a camera-facing quad per particle, read from the buffer the
+// compute pass just wrote
+
+struct Camera {
+    view_proj: mat4x4<f32>,
+    right: vec4<f32>,
+    up: vec4<f32>,
+};
+
+@group(0) @binding(0)
+var<uniform> camera: Camera;
+
+// The particle buffer, read one particle per instance
+struct ParticleInput {
+    @location(0) position: vec3<f32>,
+    @location(1) age: f32,
+    @location(2) velocity: vec3<f32>,
+    @location(3) lifetime: f32,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    // -1..1 across the quad
+    @location(0) corner: vec2<f32>,
+    @location(1) color: vec3<f32>,
+};
+
+// Half the width of a particle, in world units
+const PARTICLE_RADIUS: f32 = 0.03;
+// Every particle adds this fraction of its color, so a dense crowd glows
+// without going white straight away
+const INTENSITY: f32 = 0.35;
+
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32, particle: ParticleInput) -> VertexOutput {
+    var out: VertexOutput;
+    // Triangle strip order: (-1, -1), (1, -1), (-1, 1), (1, 1)
+    let corner = vec2<f32>(f32(vertex_index & 1u), f32(vertex_index >> 1u)) * 2.0 - 1.0;
+    out.corner = corner;
+
+    // Not launched yet: put the quad outside the clip volume
+    if particle.age < 0.0 {
+        out.clip_position = vec4<f32>(0.0, 0.0, 2.0, 1.0);
+        out.color = vec3<f32>(0.0);
+        return out;
+    }
+
+    let offset = (camera.right.xyz * corner.x + camera.up.xyz * corner.y) * PARTICLE_RADIUS;
+    out.clip_position = camera.view_proj * vec4<f32>(particle.position + offset, 1.0);
+
+    // White-hot at launch, cooling through orange to a dim red as it ages,
+    // with a boost for fast particles
+    let t = clamp(particle.age / particle.lifetime, 0.0, 1.0);
+    let hot = vec3<f32>(1.0, 0.85, 0.5);
+    let cold = vec3<f32>(0.8, 0.15, 0.05);
+    let speed = min(length(particle.velocity) / 10.0, 1.0);
+    out.color = mix(hot, cold, t) * (1.0 - t) * (0.6 + 0.4 * speed) * INTENSITY;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    // A round, soft-edged dot inside the square quad
+    let distance_squared = dot(in.corner, in.corner);
+    if distance_squared > 1.0 {
+        discard;
+    }
+    return vec4<f32>(in.color * (1.0 - distance_squared), 1.0);
+}
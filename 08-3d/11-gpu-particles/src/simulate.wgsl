@@ -0,0 +1,107 @@
+// Compute shader: moves every particle one step, relaunching the ones that
+// have lived out their lifetime
+
+struct Particle {
+    position: vec3<f32>,
+    // Negative until the particle is first launched
+    age: f32,
+    velocity: vec3<f32>,
+    lifetime: f32,
+};
+
+struct SimParams {
+    delta_time: f32,
+    time: f32,
+    // How many particles are live; the rest of the buffer is left alone
+    count: u32,
+    attractor_strength: f32,
+    attractor: vec3<f32>,
+};
+
+@group(0) @binding(0)
+var<uniform> params: SimParams;
+
+@group(0) @binding(1)
+var<storage, read_write> particles: array<Particle>;
+
+const GRAVITY: vec3<f32> = vec3<f32>(0.0, -9.8, 0.0);
+// Fraction of velocity lost per second to air resistance
+const DRAG: f32 = 0.3;
+// Fraction of vertical speed kept when bouncing off the ground
+const BOUNCE: f32 = 0.4;
+const TAU: f32 = 6.2831853;
+
+// PCG hash (Jarzynski and Olano, 2020): a well-mixed u32 from any u32
+fn pcg(value: u32) -> u32 {
+    let state = value * 747796405u + 2891336453u;
+    let word = ((state >> ((state >> 28u) + 4u)) ^ state) * 277803737u;
+    return (word >> 22u) ^ word;
+}
+
+var<private> rng_state: u32;
+
+// A pseudo-random number in 0..1, different on every call
+fn random() -> f32 {
+    rng_state = pcg(rng_state);
+    return f32(rng_state) / 4294967295.0;
+}
+
+// Send `particle` up out of the fountain in a random direction within a
+// narrow cone
+fn launch(particle: ptr<function, Particle>, index: u32) {
+    // Seeded by the particle and the time, so every launch differs
+    rng_state = pcg(index ^ pcg(bitcast<u32>(params.time)));
+    let angle = random() * TAU;
+    let tilt = sqrt(random()) * 0.3;
+    let speed = 7.0 + random() * 2.0;
+    let direction = vec3<f32>(cos(angle) * sin(tilt), cos(tilt), sin(angle) * sin(tilt));
+    (*particle).position = vec3<f32>(0.0, 0.05, 0.0);
+    (*particle).velocity = direction * speed;
+}
+
+@compute @workgroup_size(256)
+fn main(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    let index = global_id.x;
+    // The dispatch is rounded up to whole workgroups
+    if index >= params.count {
+        return;
+    }
+
+    var particle = particles[index];
+    let dt = params.delta_time;
+    let was_waiting = particle.age < 0.0;
+    particle.age += dt;
+    if particle.age < 0.0 {
+        particles[index] = particle;
+        return;
+    }
+    if was_waiting || particle.age >= particle.lifetime {
+        // Keep the leftover time so launches stay spread out
+        if !was_waiting {
+            particle.age -= particle.lifetime;
+        }
+        launch(&particle, index);
+    }
+
+    // An inverse-square pull towards the attractor, softened so particles
+    // passing close by aren't flung away at huge speeds
+    let to_attractor = params.attractor - particle.position;
+    let distance_squared = dot(to_attractor, to_attractor) + 1.0;
+    let pull = to_attractor * inverseSqrt(distance_squared) * params.attractor_strength / distance_squared;
+
+    // Semi-implicit Euler: velocity first, then position with the new
+    // velocity, which stays stable at large steps where plain Euler gains
+    // energy
+    particle.velocity += (GRAVITY + pull) * dt;
+    particle.velocity *= 1.0 - DRAG * dt;
+    particle.position += particle.velocity * dt;
+
+    if particle.position.y < 0.0 {
+        particle.position.y = 0.0;
+        particle.velocity.y = -particle.velocity.y * BOUNCE;
+        particle.velocity.x *= 0.8;
+        particle.velocity.z *= 0.8;
+    }
+
+    particles[index] = particle;
+}
@@ -0,0 +1,267 @@
+//! Orbit/fly camera driven by keyboard and mouse input.
+//!
+//! The camera is stored as a point it looks at (`target`), a direction
+//! (`yaw`, `pitch`) and a `distance`; the eye sits `distance` behind the
+//! target. Both modes work on that one representation, so switching
+//! between them never makes the view jump:
+//!
+//! - **Orbit**: dragging circles the eye around the target, WASD/arrows
+//!   slide the target across the ground and the scroll wheel zooms.
+//! - **Fly**: dragging turns the view around the eye, WASD/arrows move
+//!   along the view direction and the scroll wheel pushes forward.
+//!
+//! Input only records what is held down or how far the mouse moved; the
+//! camera itself changes in `update_camera`, once per simulation step, so
+//! it can be interpolated like the rest of the scene.
+
+use cgmath::prelude::*;
+use cgmath::{Matrix4, Point3, Vector3};
+use std::f32::consts::FRAC_PI_2;
+use winit::dpi::PhysicalPosition;
+use winit::event::{ElementState, KeyEvent, MouseButton, MouseScrollDelta, WindowEvent};
+use winit::keyboard::{KeyCode, PhysicalKey};
+
+use crate::timestep::Lerp;
+
+/// Keeps the pitch just short of straight up/down, where `look_at`
+/// has no well-defined "up"
+const MAX_PITCH: f32 = FRAC_PI_2 - 0.01;
+
+const MIN_DISTANCE: f32 = 1.0;
+const MAX_DISTANCE: f32 = 50.0;
+
+/// Each scroll line moves the eye this fraction of the way to the target
+const ZOOM_PER_LINE: f32 = 0.1;
+
+/// Touchpads report scrolling in pixels rather than lines
+const PIXELS_PER_LINE: f32 = 40.0;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Camera {
+    /// The point the camera looks at (and orbits around)
+    pub target: Point3<f32>,
+    /// Rotation around the Y axis in radians; 0 looks down +X
+    pub yaw: f32,
+    /// Rotation above (positive) or below the horizon in radians
+    pub pitch: f32,
+    /// How far the eye is from `target`
+    pub distance: f32,
+}
+
+impl Camera {
+    /// A camera at `eye` looking at `target`
+    pub fn looking_at(eye: Point3<f32>, target: Point3<f32>) -> Self {
+        let offset = target - eye;
+        let distance = offset.magnitude();
+        Self {
+            target,
+            yaw: offset.z.atan2(offset.x),
+            pitch: (offset.y / distance).asin(),
+            distance,
+        }
+    }
+
+    /// Unit vector from the eye towards the target
+    pub fn forward(&self) -> Vector3<f32> {
+        Vector3::new(
+            self.yaw.cos() * self.pitch.cos(),
+            self.pitch.sin(),
+            self.yaw.sin() * self.pitch.cos(),
+        )
+    }
+
+    pub fn right(&self) -> Vector3<f32> {
+        self.forward().cross(Vector3::unit_y()).normalize()
+    }
+
+    /// The eye position in world space
+    pub fn position(&self) -> Point3<f32> {
+        self.target - self.forward() * self.distance
+    }
+
+    pub fn view_matrix(&self) -> Matrix4<f32> {
+        Matrix4::look_at_rh(self.position(), self.target, Vector3::unit_y())
+    }
+}
+
+impl Lerp for Camera {
+    fn lerp(self, other: Self, t: f32) -> Self {
+        Self {
+            target: self.target + (other.target - self.target) * t,
+            yaw: self.yaw.lerp(other.yaw, t),
+            pitch: self.pitch.lerp(other.pitch, t),
+            distance: self.distance.lerp(other.distance, t),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CameraMode {
+    Orbit,
+    Fly,
+}
+
+/// Turns window events into camera movement
+pub struct CameraController {
+    /// Movement speed in world units per second
+    pub speed: f32,
+    /// Rotation per pixel of mouse drag, in radians
+    pub sensitivity: f32,
+    mode: CameraMode,
+    forward: bool,
+    backward: bool,
+    left: bool,
+    right: bool,
+    up: bool,
+    down: bool,
+    dragging: bool,
+    cursor: Option<PhysicalPosition<f64>>,
+    /// Mouse movement and scrolling since the last update
+    drag: (f32, f32),
+    scroll: f32,
+}
+
+impl CameraController {
+    pub fn new(speed: f32, sensitivity: f32) -> Self {
+        Self {
+            speed,
+            sensitivity,
+            mode: CameraMode::Orbit,
+            forward: false,
+            backward: false,
+            left: false,
+            right: false,
+            up: false,
+            down: false,
+            dragging: false,
+            cursor: None,
+            drag: (0.0, 0.0),
+            scroll: 0.0,
+        }
+    }
+
+    /// Record an input event; returns whether the camera used it
+    pub fn process_event(&mut self, event: &WindowEvent) -> bool {
+        match event {
+            WindowEvent::KeyboardInput {
+                event:
+                    KeyEvent {
+                        physical_key: PhysicalKey::Code(key),
+                        state,
+                        repeat,
+                        ..
+                    },
+                ..
+            } => self.process_keyboard(*key, *state, *repeat),
+            WindowEvent::MouseInput {
+                button: MouseButton::Left,
+                state,
+                ..
+            } => {
+                self.dragging = *state == ElementState::Pressed;
+                true
+            }
+            WindowEvent::CursorMoved { position, .. } => {
+                if let (true, Some(last)) = (self.dragging, self.cursor) {
+                    self.drag.0 += (position.x - last.x) as f32;
+                    self.drag.1 += (position.y - last.y) as f32;
+                }
+                self.cursor = Some(*position);
+                self.dragging
+            }
+            WindowEvent::CursorLeft { .. } => {
+                self.cursor = None;
+                false
+            }
+            WindowEvent::MouseWheel { delta, .. } => {
+                self.scroll += match delta {
+                    MouseScrollDelta::LineDelta(_, lines) => *lines,
+                    MouseScrollDelta::PixelDelta(pixels) => pixels.y as f32 / PIXELS_PER_LINE,
+                };
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn process_keyboard(&mut self, key: KeyCode, state: ElementState, repeat: bool) -> bool {
+        let pressed = state == ElementState::Pressed;
+        match key {
+            KeyCode::KeyW | KeyCode::ArrowUp => self.forward = pressed,
+            KeyCode::KeyS | KeyCode::ArrowDown => self.backward = pressed,
+            KeyCode::KeyA | KeyCode::ArrowLeft => self.left = pressed,
+            KeyCode::KeyD | KeyCode::ArrowRight => self.right = pressed,
+            KeyCode::Space => self.up = pressed,
+            KeyCode::ShiftLeft => self.down = pressed,
+            KeyCode::Tab => {
+                if pressed && !repeat {
+                    self.mode = match self.mode {
+                        CameraMode::Orbit => CameraMode::Fly,
+                        CameraMode::Fly => CameraMode::Orbit,
+                    };
+                }
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    /// Apply the input gathered since the last step to `camera`
+    pub fn update_camera(&mut self, camera: &mut Camera, dt: f32) {
+        let (dx, dy) = std::mem::take(&mut self.drag);
+        let scroll = std::mem::take(&mut self.scroll);
+
+        // Dragging right/down turns the view the same way in both modes;
+        // in orbit mode the target stays put, in fly mode the eye does
+        let eye = camera.position();
+        camera.yaw += dx * self.sensitivity;
+        camera.pitch = (camera.pitch - dy * self.sensitivity).clamp(-MAX_PITCH, MAX_PITCH);
+        if self.mode == CameraMode::Fly {
+            camera.target = eye + camera.forward() * camera.distance;
+        }
+
+        // In orbit mode W/S slide along the ground instead of diving into it
+        let forward = match self.mode {
+            CameraMode::Orbit => {
+                let flat = camera.forward();
+                Vector3::new(flat.x, 0.0, flat.z).normalize()
+            }
+            CameraMode::Fly => camera.forward(),
+        };
+        let mut velocity = Vector3::zero();
+        if self.forward {
+            velocity += forward;
+        }
+        if self.backward {
+            velocity -= forward;
+        }
+        if self.right {
+            velocity += camera.right();
+        }
+        if self.left {
+            velocity -= camera.right();
+        }
+        if self.up {
+            velocity += Vector3::unit_y();
+        }
+        if self.down {
+            velocity -= Vector3::unit_y();
+        }
+        // Normalize so diagonals aren't faster
+        if velocity.magnitude2() > 0.0 {
+            camera.target += velocity.normalize() * self.speed * dt;
+        }
+
+        if scroll != 0.0 {
+            match self.mode {
+                CameraMode::Orbit => {
+                    camera.distance = (camera.distance * (1.0 - ZOOM_PER_LINE).powf(scroll))
+                        .clamp(MIN_DISTANCE, MAX_DISTANCE);
+                }
+                CameraMode::Fly => {
+                    camera.target += camera.forward() * scroll * self.speed * ZOOM_PER_LINE;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,10 @@
+//! Code shared by the 06-scene and 08-advanced examples.
+//!
+//! Both examples depend on this crate by path, so a fix made here reaches
+//! both of them. Each module's own docs describe what it does.
+
+pub mod camera_controller;
+pub mod msaa;
+pub mod renderer;
+pub mod timestep;
+pub mod uniform_slots;
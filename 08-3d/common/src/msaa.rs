@@ -0,0 +1,146 @@
+//! Multisample anti-aliasing (MSAA) render targets.
+//!
+//! With MSAA each pixel stores several samples instead of one. The
+//! rasterizer tests triangle coverage at every sample, but the fragment
+//! shader still runs once per pixel, so edges get smoothed at a fraction of
+//! the cost of rendering at a higher resolution.
+//!
+//! The surface texture can't hold multiple samples, so the scene is drawn
+//! into a separate multisampled color texture. At the end of the pass the
+//! GPU "resolves" it, averaging each pixel's samples into the texture named
+//! as the attachment's `resolve_target`: the surface texture, or one that
+//! post-processing reads. The depth buffer
+//! must have the same sample count as the color target and so does every
+//! pipeline drawn in the pass, which is why changing the sample count means
+//! rebuilding both the targets and the pipelines.
+
+/// Samples per pixel with MSAA on; 4 is supported everywhere wgpu runs
+pub const MSAA_SAMPLES: u32 = 4;
+
+pub const DEPTH_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth32Float;
+
+/// `MSAA_SAMPLES` if the adapter can multisample both `format` and the
+/// depth format that many times, otherwise 1
+pub fn supported_sample_count(adapter: &wgpu::Adapter, format: wgpu::TextureFormat) -> u32 {
+    let supported = [format, DEPTH_FORMAT].iter().all(|&format| {
+        adapter
+            .get_texture_format_features(format)
+            .flags
+            .sample_count_supported(MSAA_SAMPLES)
+    });
+    if supported {
+        MSAA_SAMPLES
+    } else {
+        log::warn!(
+            "{}x MSAA is not supported, rendering without it",
+            MSAA_SAMPLES
+        );
+        1
+    }
+}
+
+/// The window-sized textures a frame is drawn into, besides the surface
+pub struct RenderTargets {
+    sample_count: u32,
+    /// The multisampled color buffer, resolved into the frame's texture;
+    /// `None` without MSAA, when the pass draws to that texture directly
+    color: Option<(wgpu::Texture, wgpu::TextureView)>,
+    #[allow(dead_code)] // only used through its view
+    depth_texture: wgpu::Texture,
+    depth_view: wgpu::TextureView,
+}
+
+impl RenderTargets {
+    /// Targets of `width` x `height` pixels whose color is resolved into
+    /// a texture of `format`
+    pub fn new(
+        device: &wgpu::Device,
+        width: u32,
+        height: u32,
+        format: wgpu::TextureFormat,
+        sample_count: u32,
+    ) -> Self {
+        let size = wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        };
+        let color = (sample_count > 1).then(|| {
+            let texture = device.create_texture(&wgpu::TextureDescriptor {
+                label: Some("MSAA Color Texture"),
+                size,
+                mip_level_count: 1,
+                sample_count,
+                dimension: wgpu::TextureDimension::D2,
+                format,
+                usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+                view_formats: &[],
+            });
+            let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+            (texture, view)
+        });
+        let depth_texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Depth Texture"),
+            size,
+            mip_level_count: 1,
+            sample_count,
+            dimension: wgpu::TextureDimension::D2,
+            format: DEPTH_FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        });
+        let depth_view = depth_texture.create_view(&wgpu::TextureViewDescriptor::default());
+        Self {
+            sample_count,
+            color,
+            depth_texture,
+            depth_view,
+        }
+    }
+
+    pub fn sample_count(&self) -> u32 {
+        self.sample_count
+    }
+
+    pub fn depth_view(&self) -> &wgpu::TextureView {
+        &self.depth_view
+    }
+
+    /// The color attachment for drawing a frame into `frame`
+    ///
+    /// With MSAA the samples only need to live until they're resolved, so
+    /// they're discarded rather than stored.
+    pub fn color_attachment<'a>(
+        &'a self,
+        frame: &'a wgpu::TextureView,
+        load: wgpu::LoadOp<wgpu::Color>,
+    ) -> wgpu::RenderPassColorAttachment<'a> {
+        match &self.color {
+            Some((_, msaa_view)) => wgpu::RenderPassColorAttachment {
+                view: msaa_view,
+                resolve_target: Some(frame),
+                ops: wgpu::Operations {
+                    load,
+                    store: wgpu::StoreOp::Discard,
+                },
+            },
+            None => wgpu::RenderPassColorAttachment {
+                view: frame,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load,
+                    store: wgpu::StoreOp::Store,
+                },
+            },
+        }
+    }
+}
+
+/// The multisample state for pipelines drawing into targets with
+/// `sample_count` samples
+pub fn multisample_state(sample_count: u32) -> wgpu::MultisampleState {
+    wgpu::MultisampleState {
+        count: sample_count,
+        ..Default::default()
+    }
+}
@@ -0,0 +1,277 @@
+//! Builders for the setup every pass repeats: pipelines, render passes and
+//! single uniform buffers.
+//!
+//! wgpu's descriptors spell out every field, and most of them are the same
+//! from one pipeline to the next. [`PipelineBuilder`] starts from the common
+//! case (`vs_main` and `fs_main`, triangle lists, no depth) and only the
+//! differences are written down. [`RenderPassBuilder`] does the same for
+//! passes: attachments are added one call each, stored at the end of the
+//! pass, and the rest of the descriptor is left empty. [`UniformBlock`] is a
+//! uniform buffer holding one value, the kind a bind group entry reads
+//! without any offsets.
+//!
+//! Nothing here hides wgpu: every builder ends in the ordinary wgpu object,
+//! and anything they don't cover can still be built by hand.
+
+use std::marker::PhantomData;
+
+use wgpu::util::DeviceExt;
+
+/// A render pipeline, described one difference from the defaults at a time
+pub struct PipelineBuilder<'a> {
+    label: &'a str,
+    layout: &'a wgpu::PipelineLayout,
+    shader: &'a wgpu::ShaderModule,
+    vertex_entry: &'a str,
+    /// `None` for depth-only pipelines
+    fragment_entry: Option<&'a str>,
+    buffers: &'a [wgpu::VertexBufferLayout<'a>],
+    targets: Vec<Option<wgpu::ColorTargetState>>,
+    primitive: wgpu::PrimitiveState,
+    depth_stencil: Option<wgpu::DepthStencilState>,
+    multisample: wgpu::MultisampleState,
+}
+
+impl<'a> PipelineBuilder<'a> {
+    /// A pipeline running `vs_main` and `fs_main` from `shader`, with no
+    /// vertex buffers, color targets or depth yet, drawing triangle lists
+    /// without culling and without multisampling
+    pub fn new(
+        label: &'a str,
+        layout: &'a wgpu::PipelineLayout,
+        shader: &'a wgpu::ShaderModule,
+    ) -> Self {
+        Self {
+            label,
+            layout,
+            shader,
+            vertex_entry: "vs_main",
+            fragment_entry: Some("fs_main"),
+            buffers: &[],
+            targets: Vec::new(),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+        }
+    }
+
+    pub fn vertex_entry(mut self, entry: &'a str) -> Self {
+        self.vertex_entry = entry;
+        self
+    }
+
+    pub fn fragment_entry(mut self, entry: &'a str) -> Self {
+        self.fragment_entry = Some(entry);
+        self
+    }
+
+    /// Run no fragment shader, for passes that only write depth
+    pub fn depth_only(mut self) -> Self {
+        self.fragment_entry = None;
+        self
+    }
+
+    pub fn vertex_buffers(mut self, buffers: &'a [wgpu::VertexBufferLayout<'a>]) -> Self {
+        self.buffers = buffers;
+        self
+    }
+
+    /// Add a color target of `format`, written through `blend` (`None`
+    /// overwrites), after any added before
+    pub fn color_target(
+        mut self,
+        format: wgpu::TextureFormat,
+        blend: Option<wgpu::BlendState>,
+    ) -> Self {
+        self.targets.push(Some(wgpu::ColorTargetState {
+            format,
+            blend,
+            write_mask: wgpu::ColorWrites::ALL,
+        }));
+        self
+    }
+
+    pub fn topology(mut self, topology: wgpu::PrimitiveTopology) -> Self {
+        self.primitive.topology = topology;
+        self
+    }
+
+    /// Skip triangles facing away from the camera, with `front_face`
+    /// winding marking the ones that face it
+    pub fn cull_back(mut self, front_face: wgpu::FrontFace) -> Self {
+        self.primitive.front_face = front_face;
+        self.primitive.cull_mode = Some(wgpu::Face::Back);
+        self
+    }
+
+    /// `Line` draws only the edges of each triangle; it needs
+    /// `Features::POLYGON_MODE_LINE`
+    pub fn polygon_mode(mut self, mode: wgpu::PolygonMode) -> Self {
+        self.primitive.polygon_mode = mode;
+        self
+    }
+
+    /// Test each fragment against a depth buffer of `format` with `compare`,
+    /// writing its depth where it passes if `write` is set
+    pub fn depth(
+        mut self,
+        format: wgpu::TextureFormat,
+        write: bool,
+        compare: wgpu::CompareFunction,
+    ) -> Self {
+        self.depth_stencil = Some(wgpu::DepthStencilState {
+            format,
+            depth_write_enabled: write,
+            depth_compare: compare,
+            stencil: wgpu::StencilState::default(),
+            bias: wgpu::DepthBiasState::default(),
+        });
+        self
+    }
+
+    /// Offset the depth each fragment writes
+    ///
+    /// # Panics
+    ///
+    /// If `depth` hasn't been called first.
+    pub fn depth_bias(mut self, bias: wgpu::DepthBiasState) -> Self {
+        self.depth_stencil
+            .as_mut()
+            .expect("depth_bias needs a depth buffer; call depth first")
+            .bias = bias;
+        self
+    }
+
+    pub fn multisample(mut self, multisample: wgpu::MultisampleState) -> Self {
+        self.multisample = multisample;
+        self
+    }
+
+    pub fn build(self, device: &wgpu::Device) -> wgpu::RenderPipeline {
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some(self.label),
+            layout: Some(self.layout),
+            vertex: wgpu::VertexState {
+                module: self.shader,
+                entry_point: self.vertex_entry,
+                buffers: self.buffers,
+            },
+            fragment: self.fragment_entry.map(|entry_point| wgpu::FragmentState {
+                module: self.shader,
+                entry_point,
+                targets: &self.targets,
+            }),
+            primitive: self.primitive,
+            depth_stencil: self.depth_stencil,
+            multisample: self.multisample,
+            multiview: None,
+        })
+    }
+}
+
+/// A render pass, described one attachment at a time
+pub struct RenderPassBuilder<'a> {
+    label: &'a str,
+    color_attachments: Vec<Option<wgpu::RenderPassColorAttachment<'a>>>,
+    depth_stencil_attachment: Option<wgpu::RenderPassDepthStencilAttachment<'a>>,
+}
+
+impl<'a> RenderPassBuilder<'a> {
+    /// A pass with no attachments yet
+    pub fn new(label: &'a str) -> Self {
+        Self {
+            label,
+            color_attachments: Vec::new(),
+            depth_stencil_attachment: None,
+        }
+    }
+
+    /// Draw into `view`, starting from `load` (a clear color or what's
+    /// already there) and storing the result
+    pub fn color(self, view: &'a wgpu::TextureView, load: wgpu::LoadOp<wgpu::Color>) -> Self {
+        self.color_attachment(wgpu::RenderPassColorAttachment {
+            view,
+            resolve_target: None,
+            ops: wgpu::Operations {
+                load,
+                store: wgpu::StoreOp::Store,
+            },
+        })
+    }
+
+    /// A color attachment set up elsewhere, such as one that resolves
+    /// multisampled color into the frame
+    pub fn color_attachment(mut self, attachment: wgpu::RenderPassColorAttachment<'a>) -> Self {
+        self.color_attachments.push(Some(attachment));
+        self
+    }
+
+    /// Test and write depth in `view`, starting from `load` (usually a
+    /// clear to 1.0, the far plane) and storing the result
+    pub fn depth(mut self, view: &'a wgpu::TextureView, load: wgpu::LoadOp<f32>) -> Self {
+        self.depth_stencil_attachment = Some(wgpu::RenderPassDepthStencilAttachment {
+            view,
+            depth_ops: Some(wgpu::Operations {
+                load,
+                store: wgpu::StoreOp::Store,
+            }),
+            stencil_ops: None,
+        });
+        self
+    }
+
+    /// Start recording the pass into `encoder`; it ends when the returned
+    /// pass is dropped
+    pub fn begin(self, encoder: &'a mut wgpu::CommandEncoder) -> wgpu::RenderPass<'a> {
+        encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some(self.label),
+            color_attachments: &self.color_attachments,
+            depth_stencil_attachment: self.depth_stencil_attachment,
+            occlusion_query_set: None,
+            timestamp_writes: None,
+        })
+    }
+}
+
+/// A uniform buffer holding a single `T`
+///
+/// For values every draw in a pass shares, like a camera. Values that
+/// differ per draw need a slot each, in a `UniformSlots`.
+pub struct UniformBlock<T> {
+    buffer: wgpu::Buffer,
+    _marker: PhantomData<T>,
+}
+
+impl<T: bytemuck::Pod> UniformBlock<T> {
+    /// A buffer holding `value`, which `write` can replace
+    pub fn new(device: &wgpu::Device, value: &T, label: &str) -> Self {
+        let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some(label),
+            contents: bytemuck::bytes_of(value),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        Self {
+            buffer,
+            _marker: PhantomData,
+        }
+    }
+
+    /// The binding type for a layout entry that reads this block
+    pub fn binding_type() -> wgpu::BindingType {
+        wgpu::BindingType::Buffer {
+            ty: wgpu::BufferBindingType::Uniform,
+            has_dynamic_offset: false,
+            min_binding_size: wgpu::BufferSize::new(std::mem::size_of::<T>() as u64),
+        }
+    }
+
+    /// The resource for a bind group entry
+    pub fn binding(&self) -> wgpu::BindingResource<'_> {
+        self.buffer.as_entire_binding()
+    }
+
+    /// Replace the value, in time for the next submitted commands
+    pub fn write(&self, queue: &wgpu::Queue, value: &T) {
+        queue.write_buffer(&self.buffer, 0, bytemuck::bytes_of(value));
+    }
+}
@@ -0,0 +1,111 @@
+//! Fixed-timestep simulation, decoupled from the frame rate.
+//!
+//! Each frame, the real time since the last frame is added to an
+//! accumulator, and the simulation runs as many fixed `STEP`s as fit in
+//! it. Whatever is left over (less than one step) becomes `alpha`, which
+//! the renderer uses to blend between the previous and current simulation
+//! state. Animation speed is then the same at 30, 60 or 144 fps, and
+//! motion stays smooth when frames and steps don't line up.
+
+use std::time::Duration;
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Instant;
+// `std::time::Instant::now` panics in the browser, which has no clock
+// the standard library can read
+#[cfg(target_arch = "wasm32")]
+use web_time::Instant;
+
+/// Simulation rate
+pub const STEPS_PER_SECOND: u32 = 60;
+
+/// Frames longer than this (a debugger pause, a dragged window) are
+/// clamped, so the simulation doesn't try to catch up on all of it at once
+const MAX_FRAME_TIME: Duration = Duration::from_millis(250);
+
+pub struct FixedTimestep {
+    step: Duration,
+    accumulator: Duration,
+    last_frame: Instant,
+}
+
+impl FixedTimestep {
+    pub fn new(steps_per_second: u32) -> Self {
+        Self {
+            step: Duration::from_secs(1) / steps_per_second,
+            accumulator: Duration::ZERO,
+            last_frame: Instant::now(),
+        }
+    }
+
+    /// Length of one simulation step, in seconds
+    pub fn dt(&self) -> f32 {
+        self.step.as_secs_f32()
+    }
+
+    /// Account for the real time since the previous call and return how
+    /// many simulation steps to run this frame
+    pub fn advance(&mut self) -> u32 {
+        let now = Instant::now();
+        let frame_time = (now - self.last_frame).min(MAX_FRAME_TIME);
+        self.last_frame = now;
+
+        self.accumulator += frame_time;
+        let mut steps = 0;
+        while self.accumulator >= self.step {
+            self.accumulator -= self.step;
+            steps += 1;
+        }
+        steps
+    }
+
+    /// How far the current frame is between the last step and the next,
+    /// from 0.0 to 1.0
+    pub fn alpha(&self) -> f32 {
+        self.accumulator.as_secs_f32() / self.step.as_secs_f32()
+    }
+}
+
+/// Values that can be blended for interpolated rendering
+pub trait Lerp: Copy {
+    fn lerp(self, other: Self, t: f32) -> Self;
+}
+
+impl Lerp for f32 {
+    fn lerp(self, other: Self, t: f32) -> Self {
+        self + (other - self) * t
+    }
+}
+
+impl Lerp for cgmath::Vector3<f32> {
+    fn lerp(self, other: Self, t: f32) -> Self {
+        self + (other - self) * t
+    }
+}
+
+/// A simulated value together with its value one step earlier
+#[derive(Debug, Clone, Copy)]
+pub struct Interpolated<T> {
+    previous: T,
+    current: T,
+}
+
+impl<T: Lerp> Interpolated<T> {
+    pub fn new(value: T) -> Self {
+        Self {
+            previous: value,
+            current: value,
+        }
+    }
+
+    /// Mutate the current value for a new simulation step, remembering the
+    /// old one
+    pub fn step(&mut self, update: impl FnOnce(&mut T)) {
+        self.previous = self.current;
+        update(&mut self.current);
+    }
+
+    /// The value to render at `alpha` between the last two steps
+    pub fn at(&self, alpha: f32) -> T {
+        self.previous.lerp(self.current, alpha)
+    }
+}
@@ -0,0 +1,99 @@
+//! One uniform buffer holding a value per draw, picked with dynamic offsets.
+//!
+//! `queue.write_buffer` doesn't run when it's called: every write queued
+//! before a submit lands before any of that submit's commands execute. So
+//! rewriting one small uniform buffer between draws in a pass leaves every
+//! draw seeing the last value written.
+//!
+//! The fix is to give each draw its own slot in a larger buffer, upload all
+//! of them once per frame, and point each draw at its slot with the offset
+//! passed to `set_bind_group`. The bind group binds one slot's worth of the
+//! buffer; the layout entry's `has_dynamic_offset: true` lets that window
+//! move. Offsets must be multiples of the device's
+//! `min_uniform_buffer_offset_alignment` (usually 256 bytes), so slots are
+//! padded out to that stride.
+
+use std::marker::PhantomData;
+
+pub struct UniformSlots<T> {
+    buffer: wgpu::Buffer,
+    /// Bytes from one slot to the next
+    stride: wgpu::BufferAddress,
+    capacity: usize,
+    /// Packed slots, reused every frame
+    staging: Vec<u8>,
+    _marker: PhantomData<T>,
+}
+
+impl<T: bytemuck::Pod> UniformSlots<T> {
+    pub fn new(device: &wgpu::Device, capacity: usize, label: &str) -> Self {
+        let alignment = device.limits().min_uniform_buffer_offset_alignment as wgpu::BufferAddress;
+        let stride = Self::size().get().next_multiple_of(alignment);
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some(label),
+            size: stride * capacity.max(1) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        Self {
+            buffer,
+            stride,
+            capacity,
+            staging: Vec::new(),
+            _marker: PhantomData,
+        }
+    }
+
+    fn size() -> wgpu::BufferSize {
+        wgpu::BufferSize::new(std::mem::size_of::<T>() as u64).expect("uniform type is not empty")
+    }
+
+    /// The binding type for a layout entry that uses these slots
+    pub fn binding_type() -> wgpu::BindingType {
+        wgpu::BindingType::Buffer {
+            ty: wgpu::BufferBindingType::Uniform,
+            has_dynamic_offset: true,
+            min_binding_size: Some(Self::size()),
+        }
+    }
+
+    /// The resource for a bind group entry: a window one slot wide
+    pub fn binding(&self) -> wgpu::BindingResource<'_> {
+        wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+            buffer: &self.buffer,
+            offset: 0,
+            size: Some(Self::size()),
+        })
+    }
+
+    /// The dynamic offset that selects slot `index`
+    pub fn offset(&self, index: usize) -> wgpu::DynamicOffset {
+        (index as wgpu::BufferAddress * self.stride) as wgpu::DynamicOffset
+    }
+
+    /// Upload `values` into slots `0..values.len()` with a single write
+    pub fn write(&mut self, queue: &wgpu::Queue, values: &[T]) {
+        assert!(
+            values.len() <= self.capacity,
+            "{} values for {} uniform slots",
+            values.len(),
+            self.capacity
+        );
+        let Some(last) = values.len().checked_sub(1) else {
+            return;
+        };
+        self.staging.clear();
+        // Writes must be a multiple of 4 bytes long
+        let len = self.offset(last) as usize + std::mem::size_of::<T>();
+        self.staging.resize(
+            len.next_multiple_of(wgpu::COPY_BUFFER_ALIGNMENT as usize),
+            0,
+        );
+        for (index, value) in values.iter().enumerate() {
+            let start = self.offset(index) as usize;
+            self.staging[start..start + std::mem::size_of::<T>()]
+                .copy_from_slice(bytemuck::bytes_of(value));
+        }
+        queue.write_buffer(&self.buffer, 0, &self.staging);
+    }
+}
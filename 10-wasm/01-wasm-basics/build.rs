@@ -0,0 +1,30 @@
+//! Records build configuration so `size_report()` can tell JS which
+//! profile and settings produced the running binary.
+
+use std::env;
+use std::path::Path;
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+
+    // PROFILE is only "debug" or "release", so take the custom profile name
+    // from the output directory: target/<triple>/<profile>/build/<pkg>/out
+    let out_dir = env::var("OUT_DIR").unwrap();
+    let profile = Path::new(&out_dir)
+        .ancestors()
+        .nth(3)
+        .and_then(|dir| dir.file_name())
+        .and_then(|name| name.to_str())
+        .unwrap_or("unknown")
+        .to_string();
+
+    println!("cargo:rustc-env=BUILD_PROFILE={}", profile);
+    println!(
+        "cargo:rustc-env=BUILD_OPT_LEVEL={}",
+        env::var("OPT_LEVEL").unwrap()
+    );
+    println!(
+        "cargo:rustc-env=BUILD_TARGET={}",
+        env::var("TARGET").unwrap()
+    );
+}
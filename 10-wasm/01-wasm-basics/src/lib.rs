@@ -1,38 +1,214 @@
+use js_sys::{Array, Float64Array, Map, Object, Reflect};
 use wasm_bindgen::prelude::*;
 
+#[cfg(feature = "strings")]
+use unicode_segmentation::UnicodeSegmentation;
+
+// Use wee_alloc as the global allocator when the feature is enabled.
+// It is much smaller than the default allocator but slower and no longer
+// maintained, so it is a size/speed trade-off rather than a free win.
+#[cfg(feature = "wee_alloc")]
+#[global_allocator]
+static ALLOC: wee_alloc::WeeAlloc = wee_alloc::WeeAlloc::INIT;
+
+/// Call once after loading the module
+/// Without the panic hook a Rust panic reaches JavaScript as an opaque
+/// `RuntimeError: unreachable`; with it, the panic message and location are
+/// logged to the console first
+#[wasm_bindgen]
+pub fn init() {
+    #[cfg(feature = "panic_hook")]
+    console_error_panic_hook::set_once();
+}
+
+/// Build the value returned as `Err`; JavaScript throws it as an `Error`
+/// The messages are `&'static str` so numeric-only builds don't need
+/// `core::fmt` just to report a failure
+fn error(message: &str) -> JsValue {
+    JsError::new(message).into()
+}
+
 /// Add two numbers together
-/// This function is exported to JavaScript and can be called from JS
+/// This function is exported to JavaScript and can be called from JS.
+/// An `Err` result makes the JS call throw, instead of trapping on overflow.
 #[wasm_bindgen]
-pub fn add(a: i32, b: i32) -> i32 {
-    a + b
+pub fn add(a: i32, b: i32) -> Result<i32, JsValue> {
+    a.checked_add(b)
+        .ok_or_else(|| error("add: the sum does not fit in an i32"))
 }
 
 /// Greet a person by name
 /// Demonstrates string handling across the Rust/JS boundary
+#[cfg(feature = "strings")]
 #[wasm_bindgen]
 pub fn greet(name: &str) -> String {
     format!("Hello, {}! Welcome to WebAssembly with Rust.", name)
 }
 
 /// Calculate factorial recursively
-/// Shows more complex computation in WASM
+/// Shows more complex computation in WASM. This is the naive version: past
+/// `factorial(12)` the result no longer fits in a u32, which is reported as
+/// an error rather than a panic (debug) or a wrapped value (release).
 #[wasm_bindgen]
-pub fn factorial(n: u32) -> u32 {
+pub fn factorial(n: u32) -> Result<u32, JsValue> {
     if n <= 1 {
-        1
-    } else {
-        n * factorial(n - 1)
+        return Ok(1);
     }
+    n.checked_mul(factorial(n - 1)?).ok_or_else(|| {
+        error("factorial: n > 12 overflows a u32; use factorial_u128 or factorial_big")
+    })
 }
 
 /// Fibonacci sequence
-/// Demonstrates performance of WASM for computational tasks
+/// Demonstrates performance of WASM for computational tasks. Naive and
+/// exponential on purpose; rejects n > 47, whose result overflows a u32
+/// (and which would take minutes to compute this way).
 #[wasm_bindgen]
-pub fn fibonacci(n: u32) -> u32 {
+pub fn fibonacci(n: u32) -> Result<u32, JsValue> {
+    if n > 47 {
+        return Err(error(
+            "fibonacci: n > 47 overflows a u32; use fibonacci_u128 or fibonacci_big",
+        ));
+    }
+    Ok(naive_fibonacci(n))
+}
+
+fn naive_fibonacci(n: u32) -> u32 {
     match n {
         0 => 0,
         1 => 1,
-        _ => fibonacci(n - 1) + fibonacci(n - 2),
+        _ => naive_fibonacci(n - 1) + naive_fibonacci(n - 2),
+    }
+}
+
+/// Factorial that reports overflow instead of wrapping
+/// `None` becomes `undefined` in JavaScript
+#[wasm_bindgen]
+pub fn checked_factorial(n: u32) -> Option<u32> {
+    (2..=n).try_fold(1u32, |acc, i| acc.checked_mul(i))
+}
+
+/// Iterative Fibonacci that reports overflow instead of wrapping
+#[wasm_bindgen]
+pub fn checked_fibonacci(n: u32) -> Option<u32> {
+    if n == 0 {
+        return Some(0);
+    }
+    // Stop at F(n) itself so computing the unused F(n + 1) can't overflow
+    let (mut a, mut b) = (0u32, 1u32);
+    for _ in 1..n {
+        (a, b) = (b, a.checked_add(b)?);
+    }
+    Some(b)
+}
+
+/// Factorial as a u128, exact up to `factorial_u128(34)`
+/// u128 values cross the boundary as JavaScript `BigInt`s
+#[wasm_bindgen]
+pub fn factorial_u128(n: u32) -> Option<u128> {
+    (2..=n as u128).try_fold(1u128, |acc, i| acc.checked_mul(i))
+}
+
+/// Fibonacci as a u128, exact up to `fibonacci_u128(186)`
+#[wasm_bindgen]
+pub fn fibonacci_u128(n: u32) -> Option<u128> {
+    if n == 0 {
+        return Some(0);
+    }
+    // Stop at F(n) itself so computing the unused F(n + 1) can't overflow
+    let (mut a, mut b) = (0u128, 1u128);
+    for _ in 1..n {
+        (a, b) = (b, a.checked_add(b)?);
+    }
+    Some(b)
+}
+
+/// Factorial of any size, as a decimal string
+#[cfg(feature = "strings")]
+#[wasm_bindgen]
+pub fn factorial_big(n: u32) -> String {
+    let mut result = BigUint::from(1);
+    for i in 2..=n {
+        result.mul_small(i);
+    }
+    result.to_string()
+}
+
+/// Fibonacci number of any size, as a decimal string
+#[cfg(feature = "strings")]
+#[wasm_bindgen]
+pub fn fibonacci_big(n: u32) -> String {
+    let (mut a, mut b) = (BigUint::from(0), BigUint::from(1));
+    for _ in 0..n {
+        a.add_assign(&b);
+        std::mem::swap(&mut a, &mut b);
+    }
+    a.to_string()
+}
+
+/// Minimal unsigned big integer, just enough for the `*_big` functions
+/// Stores base-10^9 digits ("limbs"), least significant first, so printing
+/// in decimal needs no division
+#[cfg(feature = "strings")]
+struct BigUint {
+    limbs: Vec<u32>,
+}
+
+#[cfg(feature = "strings")]
+impl BigUint {
+    const BASE: u64 = 1_000_000_000;
+
+    fn from(value: u32) -> Self {
+        let value = u64::from(value);
+        let mut limbs = vec![(value % Self::BASE) as u32];
+        if value >= Self::BASE {
+            limbs.push((value / Self::BASE) as u32);
+        }
+        BigUint { limbs }
+    }
+
+    fn mul_small(&mut self, factor: u32) {
+        let mut carry = 0u64;
+        for limb in &mut self.limbs {
+            let product = u64::from(*limb) * u64::from(factor) + carry;
+            *limb = (product % Self::BASE) as u32;
+            carry = product / Self::BASE;
+        }
+        while carry > 0 {
+            self.limbs.push((carry % Self::BASE) as u32);
+            carry /= Self::BASE;
+        }
+    }
+
+    fn add_assign(&mut self, other: &BigUint) {
+        if self.limbs.len() < other.limbs.len() {
+            self.limbs.resize(other.limbs.len(), 0);
+        }
+        let mut carry = 0u64;
+        for (i, limb) in self.limbs.iter_mut().enumerate() {
+            let sum =
+                u64::from(*limb) + u64::from(other.limbs.get(i).copied().unwrap_or(0)) + carry;
+            *limb = (sum % Self::BASE) as u32;
+            carry = sum / Self::BASE;
+        }
+        if carry > 0 {
+            self.limbs.push(carry as u32);
+        }
+    }
+}
+
+#[cfg(feature = "strings")]
+impl std::fmt::Display for BigUint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut limbs = self.limbs.iter().rev();
+        if let Some(most_significant) = limbs.next() {
+            write!(f, "{}", most_significant)?;
+        }
+        // Every limb after the first is padded to its full nine digits
+        for limb in limbs {
+            write!(f, "{:09}", limb)?;
+        }
+        Ok(())
     }
 }
 
@@ -44,21 +220,209 @@ pub fn is_prime(n: u32) -> bool {
         return false;
     }
     for i in 2..=(n as f64).sqrt() as u32 {
-        if n % i == 0 {
+        if n.is_multiple_of(i) {
             return false;
         }
     }
     true
 }
 
+/// One prime power in a factorization: `prime ^ exponent`
+/// Exported as a JS class; `prime` is a `bigint` because it is a u64
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PrimeFactor {
+    pub prime: u64,
+    pub exponent: u32,
+}
+
+/// Prime factorization as (prime, exponent) pairs in increasing order
+/// `prime_factorization(360)` is `[(2, 3), (3, 2), (5, 1)]`, and 0 and 1
+/// have no prime factors. Tuples can't cross the boundary, so JS calls
+/// `prime_factors` instead.
+pub fn prime_factorization(mut n: u64) -> Vec<(u64, u32)> {
+    let mut factors = Vec::new();
+    if n == 0 {
+        return factors;
+    }
+    let mut divide_out = |n: &mut u64, p: u64| {
+        let mut exponent = 0;
+        while n.is_multiple_of(p) {
+            *n /= p;
+            exponent += 1;
+        }
+        if exponent > 0 {
+            factors.push((p, exponent));
+        }
+        exponent > 0
+    };
+
+    divide_out(&mut n, 2);
+    divide_out(&mut n, 3);
+    // Whatever is left may already be prime; then the rest of the trial
+    // division, which can take seconds for a large prime, is skipped.
+    // Only worth re-checking after n has changed.
+    let mut leftover_is_prime = is_prime_u64(n);
+    // Every prime above 3 is 6k - 1 or 6k + 1
+    let mut p = 5u64;
+    while !leftover_is_prime && p.saturating_mul(p) <= n {
+        let divided = divide_out(&mut n, p) | divide_out(&mut n, p + 2);
+        if divided {
+            leftover_is_prime = is_prime_u64(n);
+        }
+        p += 6;
+    }
+    if n > 1 {
+        factors.push((n, 1));
+    }
+    factors
+}
+
+/// Prime factorization as an array of `PrimeFactor` objects
+/// 0 is rejected, since it is divisible by every prime
+#[wasm_bindgen]
+pub fn prime_factors(n: u64) -> Result<Vec<PrimeFactor>, JsValue> {
+    if n == 0 {
+        return Err(error("prime_factors: 0 has no prime factorization"));
+    }
+    Ok(prime_factorization(n)
+        .into_iter()
+        .map(|(prime, exponent)| PrimeFactor { prime, exponent })
+        .collect())
+}
+
+/// Smallest prime greater than `n`, or `None` past the largest u64 prime
+#[wasm_bindgen]
+pub fn next_prime(n: u64) -> Option<u64> {
+    let mut candidate = n.checked_add(1)?;
+    while !is_prime_u64(candidate) {
+        candidate = candidate.checked_add(1)?;
+    }
+    Some(candidate)
+}
+
+/// Largest `n` accepted by `primes_up_to`; the sieve needs n bytes
+pub const SIEVE_LIMIT: u32 = 100_000_000;
+
+/// The k-th prime, counting from `nth_prime(1) == 2`
+/// Sieves up to an upper bound for p(k) instead of testing numbers one by
+/// one, so k is limited by `SIEVE_LIMIT` (to about 5.4 million)
+#[wasm_bindgen]
+pub fn nth_prime(k: u32) -> Result<u32, JsValue> {
+    if k == 0 {
+        return Err(error("nth_prime: primes are counted from k = 1"));
+    }
+    // p(k) < k (ln k + ln ln k) for k >= 6 (Rosser's theorem)
+    let bound = if k < 6 {
+        13.0
+    } else {
+        let k = k as f64;
+        k * (k.ln() + k.ln().ln())
+    };
+    if bound > SIEVE_LIMIT as f64 {
+        return Err(error(
+            "nth_prime: k is too large for the sieve (SIEVE_LIMIT)",
+        ));
+    }
+    sieve(bound as u32)
+        .get(k as usize - 1)
+        .copied()
+        .ok_or_else(|| error("nth_prime: upper bound too small"))
+}
+
+/// All primes `<= n`, using the sieve of Eratosthenes
+/// `Vec<u32>` arrives in JavaScript as a `Uint32Array`. Rejects n above
+/// `SIEVE_LIMIT` instead of aborting when memory runs out.
+#[wasm_bindgen]
+pub fn primes_up_to(n: u32) -> Result<Vec<u32>, JsValue> {
+    if n > SIEVE_LIMIT {
+        return Err(error(
+            "primes_up_to: n must be at most 100000000 (SIEVE_LIMIT)",
+        ));
+    }
+    Ok(sieve(n))
+}
+
+fn sieve(n: u32) -> Vec<u32> {
+    let n = n as usize;
+    if n < 2 {
+        return Vec::new();
+    }
+    let mut composite = vec![false; n + 1];
+    let mut i = 2;
+    while i * i <= n {
+        if !composite[i] {
+            // Smaller multiples were already crossed off by smaller primes
+            for multiple in (i * i..=n).step_by(i) {
+                composite[multiple] = true;
+            }
+        }
+        i += 1;
+    }
+    (2..=n)
+        .filter(|&i| !composite[i])
+        .map(|i| i as u32)
+        .collect()
+}
+
+/// Deterministic Miller-Rabin primality test for the whole u64 range
+/// Trial division would need up to 2^32 divisions; these 12 bases are
+/// known to have no false positives below 2^64
+fn is_prime_u64(n: u64) -> bool {
+    const BASES: [u64; 12] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37];
+    if n < 2 {
+        return false;
+    }
+    for p in BASES {
+        if n.is_multiple_of(p) {
+            return n == p;
+        }
+    }
+
+    // n - 1 = d * 2^s with d odd
+    let s = (n - 1).trailing_zeros();
+    let d = (n - 1) >> s;
+    let mul_mod = |a: u64, b: u64| (a as u128 * b as u128 % n as u128) as u64;
+    let pow_mod = |mut base: u64, mut exp: u64| {
+        let mut result = 1;
+        while exp > 0 {
+            if exp & 1 == 1 {
+                result = mul_mod(result, base);
+            }
+            base = mul_mod(base, base);
+            exp >>= 1;
+        }
+        result
+    };
+
+    'bases: for a in BASES {
+        let mut x = pow_mod(a, d);
+        if x == 1 || x == n - 1 {
+            continue;
+        }
+        for _ in 1..s {
+            x = mul_mod(x, x);
+            if x == n - 1 {
+                continue 'bases;
+            }
+        }
+        return false;
+    }
+    true
+}
+
 /// Reverse a string
-/// Demonstrates string manipulation in WASM
+/// Demonstrates string manipulation in WASM. Reverses `char`s (code points),
+/// which scrambles combining accents and emoji sequences; see
+/// `reverse_graphemes` for the version that doesn't.
+#[cfg(feature = "strings")]
 #[wasm_bindgen]
 pub fn reverse_string(s: &str) -> String {
     s.chars().rev().collect()
 }
 
 /// Count vowels in a string
+#[cfg(feature = "strings")]
 #[wasm_bindgen]
 pub fn count_vowels(s: &str) -> usize {
     s.chars()
@@ -66,15 +430,567 @@ pub fn count_vowels(s: &str) -> usize {
         .count()
 }
 
+/// Reverse a string by grapheme cluster
+/// A grapheme is what a reader sees as one character: "e" plus a combining
+/// accent, or a family emoji made of several code points joined by ZWJs
+#[cfg(feature = "strings")]
+#[wasm_bindgen]
+pub fn reverse_graphemes(s: &str) -> String {
+    s.graphemes(true).rev().collect()
+}
+
+/// Number of user-perceived characters
+/// JavaScript's `str.length` counts UTF-16 code units instead
+#[cfg(feature = "strings")]
+#[wasm_bindgen]
+pub fn grapheme_count(s: &str) -> usize {
+    s.graphemes(true).count()
+}
+
+/// Split text into words for the case transforms: on whitespace and
+/// punctuation, and also where camelCase switches from lower to upper case
+#[cfg(feature = "strings")]
+fn split_words(s: &str) -> Vec<&str> {
+    let mut words = Vec::new();
+    for word in s.unicode_words() {
+        let mut start = 0;
+        let mut previous_lower = false;
+        for (i, c) in word.char_indices() {
+            if c.is_uppercase() && previous_lower {
+                words.push(&word[start..i]);
+                start = i;
+            }
+            previous_lower = c.is_lowercase() || c.is_numeric();
+        }
+        words.push(&word[start..]);
+    }
+    words
+}
+
+/// Uppercase the first letter and lowercase the rest
+#[cfg(feature = "strings")]
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first
+            .to_uppercase()
+            .chain(chars.flat_map(char::to_lowercase))
+            .collect(),
+        None => String::new(),
+    }
+}
+
+/// "hello wasm world" -> "Hello Wasm World"
+#[cfg(feature = "strings")]
+#[wasm_bindgen]
+pub fn to_title_case(s: &str) -> String {
+    split_words(s)
+        .into_iter()
+        .map(capitalize)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// "Hello WASM world" -> "helloWasmWorld"
+#[cfg(feature = "strings")]
+#[wasm_bindgen]
+pub fn to_camel_case(s: &str) -> String {
+    split_words(s)
+        .into_iter()
+        .enumerate()
+        .map(|(i, word)| {
+            if i == 0 {
+                word.to_lowercase()
+            } else {
+                capitalize(word)
+            }
+        })
+        .collect()
+}
+
+/// "helloWasm World" -> "hello_wasm_world"
+#[cfg(feature = "strings")]
+#[wasm_bindgen]
+pub fn to_snake_case(s: &str) -> String {
+    split_words(s)
+        .into_iter()
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>()
+        .join("_")
+}
+
+/// Turn a title into a URL path segment: "Rust & WASM: Part 2!" -> "rust-wasm-part-2"
+/// Letters outside ASCII are kept (lowercased), so "Crème Brûlée" becomes
+/// "crème-brûlée"; browsers percent-encode them as needed
+#[cfg(feature = "strings")]
+#[wasm_bindgen]
+pub fn slugify(s: &str) -> String {
+    let mut slug = String::with_capacity(s.len());
+    for c in s.chars() {
+        if c.is_alphanumeric() {
+            slug.extend(c.to_lowercase());
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    // A trailing separator can only be a single '-'
+    if slug.ends_with('-') {
+        slug.pop();
+    }
+    slug
+}
+
+/// Edit distance: the fewest single-character insertions, deletions and
+/// substitutions that turn `a` into `b`
+/// Counts graphemes, so "é" written with a combining accent is one edit
+#[cfg(feature = "strings")]
+#[wasm_bindgen]
+pub fn levenshtein(a: &str, b: &str) -> usize {
+    let a: Vec<&str> = a.graphemes(true).collect();
+    let b: Vec<&str> = b.graphemes(true).collect();
+
+    // Only the previous row of the DP table is needed
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+    for (i, ga) in a.iter().enumerate() {
+        current[0] = i + 1;
+        for (j, gb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ga != gb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    previous[b.len()]
+}
+
+/// Lowercased word counts, most frequent first (ties alphabetically)
+/// The Rust-side half of `word_frequencies`, usable without a JS runtime
+#[cfg(feature = "strings")]
+pub fn count_words(s: &str) -> Vec<(String, u32)> {
+    let mut counts = std::collections::HashMap::new();
+    for word in s.unicode_words() {
+        *counts.entry(word.to_lowercase()).or_insert(0u32) += 1;
+    }
+    let mut counts: Vec<_> = counts.into_iter().collect();
+    counts.sort_by(|(word_a, count_a), (word_b, count_b)| {
+        count_b.cmp(count_a).then_with(|| word_a.cmp(word_b))
+    });
+    counts
+}
+
+/// Word frequencies as a JavaScript `Map<string, number>`
+/// A `Map` keeps insertion order, so iterating it yields the most
+/// frequent words first
+#[cfg(feature = "strings")]
+#[wasm_bindgen]
+pub fn word_frequencies(s: &str) -> js_sys::Map {
+    let map = js_sys::Map::new();
+    for (word, count) in count_words(s) {
+        map.set(&JsValue::from(word), &JsValue::from(count));
+    }
+    map
+}
+
+// ============================================================================
+// Collection interop
+// ============================================================================
+//
+// How arrays and collections cross the boundary:
+// - `Vec<T>` / `&[T]` parameters copy a JS typed array (or plain array of
+//   numbers) into WASM memory; `Vec<T>` results are copied back out as a
+//   new typed array.
+// - `&mut [T]` copies in, and copies the changes back into the caller's
+//   array when the function returns.
+// - `Float64Array::view` shares WASM memory with JS: no copy at all, but
+//   the view breaks as soon as WASM memory grows.
+// - `Map` and `Object` are JS objects; Rust works on them through js-sys
+//   calls instead of copying them.
+
+/// Sorted, with duplicates removed
+/// `Vec<i32>` in and out: pass an `Int32Array` or an array of numbers, get
+/// an `Int32Array` back
+#[wasm_bindgen]
+pub fn sort_unique(mut values: Vec<i32>) -> Vec<i32> {
+    values.sort_unstable();
+    values.dedup();
+    values
+}
+
+/// Arithmetic mean, `undefined` for an empty array
+/// `&[f64]` borrows a temporary copy of the caller's `Float64Array`
+#[wasm_bindgen]
+pub fn mean(values: &[f64]) -> Option<f64> {
+    if values.is_empty() {
+        return None;
+    }
+    Some(values.iter().sum::<f64>() / values.len() as f64)
+}
+
+/// Multiply every element by `factor`, modifying the caller's array
+#[wasm_bindgen]
+pub fn scale_in_place(values: &mut [f64], factor: f64) {
+    for value in values {
+        *value *= factor;
+    }
+}
+
+/// Sum a `Float64Array` without converting it at the boundary
+/// The typed array stays in JS; `copy_to` moves its contents into a Rust
+/// buffer in one call, which is much faster than calling `get_index` per
+/// element
+#[wasm_bindgen]
+pub fn sum_float64_array(array: &Float64Array) -> f64 {
+    let mut buffer = vec![0.0; array.length() as usize];
+    array.copy_to(&mut buffer);
+    buffer.iter().sum()
+}
+
+/// A buffer of f64 samples owned by WASM that JS can read and write in place
+#[wasm_bindgen]
+pub struct Samples {
+    data: Vec<f64>,
+}
+
+#[wasm_bindgen]
+impl Samples {
+    #[wasm_bindgen(constructor)]
+    pub fn new(len: usize) -> Samples {
+        Samples {
+            data: vec![0.0; len],
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// Zero-copy view of the samples
+    /// Writes through the view are seen by Rust and vice versa. Take a new
+    /// view after anything that may allocate in WASM (another call that
+    /// creates objects, strings or vectors): growing the memory detaches
+    /// the old one, and it then reads as length 0.
+    pub fn view(&self) -> Float64Array {
+        // SAFETY: the view aliases `self.data`. It is only valid until WASM
+        // memory grows or this buffer is reallocated, which the caller has
+        // been told above; Samples never reallocates `data` after `new`.
+        unsafe { Float64Array::view(&self.data) }
+    }
+
+    /// A copy of the samples, safe to keep around
+    pub fn to_array(&self) -> Float64Array {
+        Float64Array::from(self.data.as_slice())
+    }
+
+    pub fn sum(&self) -> f64 {
+        self.data.iter().sum()
+    }
+
+    /// Rescale so the samples range from 0 to 1
+    pub fn normalize(&mut self) {
+        let (min, max) = self
+            .data
+            .iter()
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), &v| {
+                (min.min(v), max.max(v))
+            });
+        let range = max - min;
+        if range > 0.0 {
+            for value in &mut self.data {
+                *value = (*value - min) / range;
+            }
+        }
+    }
+}
+
+/// How often each value occurs, as a `Map<number, number>`
+/// Keys appear in the order they are first seen
+#[wasm_bindgen]
+pub fn value_counts(values: &[i32]) -> Map {
+    let counts = Map::new();
+    for &value in values {
+        let key = JsValue::from(value);
+        let count = counts.get(&key).as_f64().unwrap_or(0.0);
+        counts.set(&key, &JsValue::from(count + 1.0));
+    }
+    counts
+}
+
+/// `{ count, min, max, mean }` for the values, as a plain JS object
+#[wasm_bindgen]
+pub fn describe(values: &[f64]) -> Result<Object, JsValue> {
+    let Some(mean) = mean(values) else {
+        return Err(error("describe: needs at least one value"));
+    };
+    let min = values.iter().copied().fold(f64::INFINITY, f64::min);
+    let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+
+    let object = Object::new();
+    for (key, value) in [
+        ("count", values.len() as f64),
+        ("min", min),
+        ("max", max),
+        ("mean", mean),
+    ] {
+        Reflect::set(&object, &JsValue::from(key), &JsValue::from(value))?;
+    }
+    Ok(object)
+}
+
+/// Sum the values of an object's own properties, e.g. `{ a: 1, b: 2.5 }`
+/// Throws if any value is not a number, instead of silently skipping it
+#[wasm_bindgen]
+pub fn sum_values(object: &Object) -> Result<f64, JsValue> {
+    let mut total = 0.0;
+    for entry in Object::entries(object).iter() {
+        let value = Array::from(&entry).get(1);
+        total += value
+            .as_f64()
+            .ok_or_else(|| error("sum_values: every property value must be a number"))?;
+    }
+    Ok(total)
+}
+
+// ============================================================================
+// Micro-benchmarks
+// ============================================================================
+
+#[wasm_bindgen]
+extern "C" {
+    /// `performance.now()`: milliseconds with sub-millisecond precision,
+    /// available in browsers, workers and Node.js
+    #[wasm_bindgen(js_namespace = performance, js_name = now)]
+    fn performance_now() -> f64;
+}
+
+/// Workloads `bench` can run, each calling one export with fixed input
+/// `black_box` stops the optimizer from deleting calls whose result is unused
+#[cfg(feature = "strings")]
+const BENCHMARKS: &[(&str, fn())] = &[
+    ("add", || {
+        let _ = black_box(add(black_box(12_345), black_box(678)));
+    }),
+    ("factorial", || {
+        let _ = black_box(factorial(black_box(12)));
+    }),
+    ("fibonacci", || {
+        let _ = black_box(fibonacci(black_box(25)));
+    }),
+    ("checked_fibonacci", || {
+        black_box(checked_fibonacci(black_box(47)));
+    }),
+    ("factorial_big", || {
+        black_box(factorial_big(black_box(200)));
+    }),
+    ("is_prime", || {
+        black_box(is_prime(black_box(1_000_003)));
+    }),
+    ("primes_up_to", || {
+        let _ = black_box(primes_up_to(black_box(100_000)));
+    }),
+    ("nth_prime", || {
+        let _ = black_box(nth_prime(black_box(1_000)));
+    }),
+    ("prime_factorization", || {
+        black_box(prime_factorization(black_box(600_851_475_143)));
+    }),
+    ("reverse_graphemes", || {
+        black_box(reverse_graphemes(black_box(BENCH_TEXT)));
+    }),
+    ("slugify", || {
+        black_box(slugify(black_box(BENCH_TEXT)));
+    }),
+    ("levenshtein", || {
+        black_box(levenshtein(
+            black_box("kitten sitting on the mat"),
+            black_box("sitting kitten on a hat"),
+        ));
+    }),
+    ("count_words", || {
+        black_box(count_words(black_box(BENCH_TEXT)));
+    }),
+];
+
+#[cfg(feature = "strings")]
+use std::hint::black_box;
+
+#[cfg(feature = "strings")]
+const BENCH_TEXT: &str = "The quick brown fox jumps over the lazy dog. Crème brûlée, \
+    naïve café! The dog barks; the fox runs. 👨‍👩‍👧 Ünïcödé text, the end.";
+
+/// Timings are taken in this many batches; `performance.now()` is too
+/// coarse (down to 0.1 ms in some browsers) to time a single fast call
+#[cfg(feature = "strings")]
+const BENCH_SAMPLES: u32 = 20;
+
+/// Timing statistics for one benchmark, per call, in milliseconds
+#[cfg(feature = "strings")]
+#[wasm_bindgen(getter_with_clone)]
+#[derive(Debug, Clone)]
+pub struct BenchStats {
+    /// Benchmarked function
+    pub name: String,
+    /// Calls made, rounded up to a multiple of `samples`
+    pub iterations: u32,
+    /// Number of timed batches the statistics are computed from
+    pub samples: u32,
+    pub total_ms: f64,
+    pub mean_ms: f64,
+    pub median_ms: f64,
+    pub min_ms: f64,
+    pub max_ms: f64,
+    /// Calls per second at the mean
+    pub ops_per_sec: f64,
+}
+
+/// Names accepted by `bench`
+#[cfg(feature = "strings")]
+#[wasm_bindgen]
+pub fn bench_functions() -> Vec<String> {
+    BENCHMARKS
+        .iter()
+        .map(|(name, _)| name.to_string())
+        .collect()
+}
+
+/// Time `iterations` calls of one of this crate's functions
+/// Returns per-call statistics; throws for an unknown name (see
+/// `bench_functions`) or zero iterations
+#[cfg(feature = "strings")]
+#[wasm_bindgen]
+pub fn bench(fn_name: &str, iterations: u32) -> Result<BenchStats, JsValue> {
+    run_bench(fn_name, iterations, performance_now).map_err(error)
+}
+
+/// `bench` with the clock passed in, so it can run outside a JS runtime
+#[cfg(feature = "strings")]
+fn run_bench(
+    fn_name: &str,
+    iterations: u32,
+    mut now: impl FnMut() -> f64,
+) -> Result<BenchStats, &'static str> {
+    let workload = BENCHMARKS
+        .iter()
+        .find(|(name, _)| *name == fn_name)
+        .map(|(_, workload)| *workload)
+        .ok_or("bench: unknown function name; see bench_functions()")?;
+    if iterations == 0 {
+        return Err("bench: iterations must be at least 1");
+    }
+
+    let samples = iterations.min(BENCH_SAMPLES);
+    let batch = iterations.div_ceil(samples);
+
+    // One untimed batch first, so one-off costs (allocating memory, warming
+    // caches) don't land in the first sample
+    for _ in 0..batch {
+        workload();
+    }
+
+    let mut per_call: Vec<f64> = (0..samples)
+        .map(|_| {
+            let start = now();
+            for _ in 0..batch {
+                workload();
+            }
+            (now() - start) / batch as f64
+        })
+        .collect();
+    per_call.sort_by(f64::total_cmp);
+
+    let calls = samples * batch;
+    let total_ms = per_call.iter().sum::<f64>() * batch as f64;
+    let mean_ms = total_ms / calls as f64;
+    let middle = per_call.len() / 2;
+    let median_ms = if per_call.len().is_multiple_of(2) {
+        (per_call[middle - 1] + per_call[middle]) / 2.0
+    } else {
+        per_call[middle]
+    };
+
+    Ok(BenchStats {
+        name: fn_name.to_string(),
+        iterations: calls,
+        samples,
+        total_ms,
+        mean_ms,
+        median_ms,
+        min_ms: per_call[0],
+        max_ms: per_call[per_call.len() - 1],
+        ops_per_sec: if mean_ms > 0.0 {
+            1000.0 / mean_ms
+        } else {
+            f64::INFINITY
+        },
+    })
+}
+
+/// Build configuration of the running binary
+/// Compare these between builds alongside the `.wasm` file size
+#[wasm_bindgen(getter_with_clone)]
+pub struct SizeReport {
+    /// Cargo profile directory the binary was built into (release, release-size, ...)
+    pub profile: String,
+    /// Optimization level ("0"-"3", "s" or "z")
+    pub opt_level: String,
+    /// Target triple
+    pub target: String,
+    /// Global allocator in use
+    pub allocator: String,
+    /// Panic strategy ("abort" or "unwind")
+    pub panic_strategy: String,
+    /// Enabled crate features, comma separated
+    pub features: String,
+    /// Whether debug assertions are compiled in
+    pub debug_assertions: bool,
+}
+
+/// Report how this binary was built
+#[wasm_bindgen]
+pub fn size_report() -> SizeReport {
+    let mut features = Vec::new();
+    if cfg!(feature = "strings") {
+        features.push("strings");
+    }
+    if cfg!(feature = "wee_alloc") {
+        features.push("wee_alloc");
+    }
+    if cfg!(feature = "panic_hook") {
+        features.push("panic_hook");
+    }
+
+    SizeReport {
+        profile: env!("BUILD_PROFILE").to_string(),
+        opt_level: env!("BUILD_OPT_LEVEL").to_string(),
+        target: env!("BUILD_TARGET").to_string(),
+        allocator: if cfg!(feature = "wee_alloc") {
+            "wee_alloc".to_string()
+        } else {
+            "default".to_string()
+        },
+        panic_strategy: if cfg!(panic = "abort") {
+            "abort".to_string()
+        } else {
+            "unwind".to_string()
+        },
+        features: features.join(","),
+        debug_assertions: cfg!(debug_assertions),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
     fn test_add() {
-        assert_eq!(add(2, 3), 5);
+        assert_eq!(add(2, 3).unwrap(), 5);
     }
 
+    #[cfg(feature = "strings")]
     #[test]
     fn test_greet() {
         assert_eq!(
@@ -85,7 +1001,111 @@ mod tests {
 
     #[test]
     fn test_factorial() {
-        assert_eq!(factorial(5), 120);
+        assert_eq!(factorial(5).unwrap(), 120);
+        assert_eq!(factorial(12).unwrap(), 479_001_600);
+    }
+
+    #[cfg(feature = "strings")]
+    #[test]
+    fn test_reverse_graphemes() {
+        // "e" + combining acute accent stays together
+        let decomposed = "cafe\u{301}";
+        assert_eq!(reverse_graphemes(decomposed), "e\u{301}fac");
+        assert_ne!(reverse_string(decomposed), reverse_graphemes(decomposed));
+        // A family emoji is several code points but a single grapheme
+        let family = "\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}";
+        assert_eq!(grapheme_count(family), 1);
+        assert_eq!(
+            reverse_graphemes(&format!("a{}b", family)),
+            format!("b{}a", family)
+        );
+    }
+
+    #[cfg(feature = "strings")]
+    #[test]
+    fn test_case_transforms() {
+        assert_eq!(to_title_case("hello wasm  world"), "Hello Wasm World");
+        assert_eq!(to_camel_case("Hello WASM world"), "helloWasmWorld");
+        assert_eq!(
+            to_snake_case("parseHttpResponse now"),
+            "parse_http_response_now"
+        );
+        assert_eq!(to_snake_case("émileZola"), "émile_zola");
+        assert_eq!(to_title_case(""), "");
+    }
+
+    #[cfg(feature = "strings")]
+    #[test]
+    fn test_slugify() {
+        assert_eq!(slugify("Rust & WASM: Part 2!"), "rust-wasm-part-2");
+        assert_eq!(slugify("  --Crème Brûlée--  "), "crème-brûlée");
+        assert_eq!(slugify("!!!"), "");
+    }
+
+    #[cfg(feature = "strings")]
+    #[test]
+    fn test_levenshtein() {
+        assert_eq!(levenshtein("kitten", "sitting"), 3);
+        assert_eq!(levenshtein("", "abc"), 3);
+        assert_eq!(levenshtein("same", "same"), 0);
+        // One grapheme replaced, although two code points differ
+        assert_eq!(levenshtein("cafe", "cafe\u{301}"), 1);
+    }
+
+    #[cfg(feature = "strings")]
+    #[test]
+    fn test_count_words() {
+        let counts = count_words("The cat and the hat. THE END, and that's it");
+        assert_eq!(counts[0], ("the".to_string(), 3));
+        assert_eq!(counts[1], ("and".to_string(), 2));
+        assert!(counts.contains(&("that's".to_string(), 1)));
+        assert!(count_words("").is_empty());
+    }
+
+    #[test]
+    fn test_checked_factorial() {
+        assert_eq!(checked_factorial(0), Some(1));
+        assert_eq!(checked_factorial(12), Some(479_001_600));
+        assert_eq!(checked_factorial(13), None);
+    }
+
+    #[test]
+    fn test_checked_fibonacci() {
+        assert_eq!(checked_fibonacci(0), Some(0));
+        assert_eq!(checked_fibonacci(20), Some(fibonacci(20).unwrap()));
+        assert_eq!(checked_fibonacci(47), Some(2_971_215_073));
+        assert_eq!(checked_fibonacci(48), None);
+    }
+
+    #[test]
+    fn test_u128_variants() {
+        assert_eq!(factorial_u128(20), Some(2_432_902_008_176_640_000));
+        assert!(factorial_u128(34).is_some());
+        assert_eq!(factorial_u128(35), None);
+        assert_eq!(fibonacci_u128(100), Some(354_224_848_179_261_915_075));
+        assert!(fibonacci_u128(186).is_some());
+        assert_eq!(fibonacci_u128(187), None);
+    }
+
+    #[cfg(feature = "strings")]
+    #[test]
+    fn test_big_variants() {
+        assert_eq!(factorial_big(0), "1");
+        assert_eq!(factorial_big(13), "6227020800");
+        assert_eq!(factorial_big(30), "265252859812191058636308480000000");
+        assert_eq!(fibonacci_big(0), "0");
+        assert_eq!(fibonacci_big(1), "1");
+        assert_eq!(
+            fibonacci_big(200),
+            "280571172992510140037611932413038677189525"
+        );
+        // The big versions agree with u128 wherever u128 is exact
+        for n in 0..=34 {
+            assert_eq!(factorial_big(n), factorial_u128(n).unwrap().to_string());
+        }
+        for n in 0..=186 {
+            assert_eq!(fibonacci_big(n), fibonacci_u128(n).unwrap().to_string());
+        }
     }
 
     #[test]
@@ -93,4 +1113,172 @@ mod tests {
         assert!(is_prime(7));
         assert!(!is_prime(8));
     }
+
+    #[test]
+    fn test_prime_factorization() {
+        assert_eq!(prime_factorization(360), vec![(2, 3), (3, 2), (5, 1)]);
+        assert_eq!(prime_factorization(97), vec![(97, 1)]);
+        assert!(prime_factorization(1).is_empty());
+        // Two large primes: the leftover-is-prime shortcut keeps this fast
+        assert_eq!(
+            prime_factorization(4_294_967_291 * 65_521),
+            vec![(65_521, 1), (4_294_967_291, 1)]
+        );
+        assert_eq!(
+            prime_factorization(u64::MAX),
+            vec![
+                (3, 1),
+                (5, 1),
+                (17, 1),
+                (257, 1),
+                (641, 1),
+                (65_537, 1),
+                (6_700_417, 1)
+            ]
+        );
+        assert!(prime_factorization(0).is_empty());
+        assert_eq!(
+            prime_factors(12).unwrap(),
+            vec![
+                PrimeFactor {
+                    prime: 2,
+                    exponent: 2
+                },
+                PrimeFactor {
+                    prime: 3,
+                    exponent: 1
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn test_next_and_nth_prime() {
+        assert_eq!(next_prime(0), Some(2));
+        assert_eq!(next_prime(13), Some(17));
+        assert_eq!(next_prime(u32::MAX as u64), Some(4_294_967_311));
+        // 2^64 - 59 is the largest u64 prime
+        assert_eq!(next_prime(u64::MAX - 59), Some(u64::MAX - 58));
+        assert_eq!(next_prime(u64::MAX - 58), None);
+
+        assert_eq!(nth_prime(1).unwrap(), 2);
+        assert_eq!(nth_prime(6).unwrap(), 13);
+        assert_eq!(nth_prime(10_000).unwrap(), 104_729);
+    }
+
+    #[test]
+    fn test_primes_up_to() {
+        assert_eq!(primes_up_to(1).unwrap(), Vec::<u32>::new());
+        assert_eq!(
+            primes_up_to(30).unwrap(),
+            vec![2, 3, 5, 7, 11, 13, 17, 19, 23, 29]
+        );
+        assert_eq!(primes_up_to(100_000).unwrap().len(), 9_592);
+        // The sieve and the trial-division demo agree
+        for n in 0..2_000 {
+            assert_eq!(primes_up_to(n).unwrap().last() == Some(&n), is_prime(n));
+        }
+    }
+
+    #[test]
+    fn test_collection_helpers() {
+        assert_eq!(sort_unique(vec![3, 1, 3, -2, 1]), vec![-2, 1, 3]);
+        assert_eq!(mean(&[1.0, 2.0, 6.0]), Some(3.0));
+        assert_eq!(mean(&[]), None);
+
+        let mut values = [1.0, -2.0, 0.5];
+        scale_in_place(&mut values, 2.0);
+        assert_eq!(values, [2.0, -4.0, 1.0]);
+
+        let mut samples = Samples::new(3);
+        samples.data.copy_from_slice(&[10.0, 20.0, 15.0]);
+        assert_eq!(samples.sum(), 45.0);
+        samples.normalize();
+        assert_eq!(samples.data, vec![0.0, 1.0, 0.5]);
+    }
+
+    #[cfg(feature = "strings")]
+    #[test]
+    fn test_run_bench() {
+        // A fake clock that advances 5 ms per reading: every batch of 5
+        // calls spans exactly one tick
+        let mut clock = 0.0;
+        let ticking = || {
+            clock += 5.0;
+            clock
+        };
+        let stats = run_bench("is_prime", 100, ticking).unwrap();
+        assert_eq!(stats.samples, 20);
+        assert_eq!(stats.iterations, 100);
+        assert_eq!(stats.mean_ms, 1.0);
+        assert_eq!(stats.median_ms, 1.0);
+        assert_eq!(stats.total_ms, 100.0);
+        assert_eq!(stats.ops_per_sec, 1_000.0);
+
+        // Fewer iterations than samples: one call per sample
+        let start = std::time::Instant::now();
+        let wall_clock = || start.elapsed().as_secs_f64() * 1000.0;
+        let stats = run_bench("fibonacci", 3, wall_clock).unwrap();
+        assert_eq!((stats.samples, stats.iterations), (3, 3));
+        assert!(stats.min_ms <= stats.median_ms && stats.median_ms <= stats.max_ms);
+
+        assert!(run_bench("nope", 10, || 0.0).is_err());
+        assert!(run_bench("add", 0, || 0.0).is_err());
+        // Every advertised name can be benchmarked
+        for name in bench_functions() {
+            assert!(run_bench(&name, 1, || 0.0).is_ok(), "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_size_report() {
+        let report = size_report();
+        assert!(!report.profile.is_empty());
+        assert_eq!(report.allocator, "default");
+        assert_eq!(report.debug_assertions, cfg!(debug_assertions));
+    }
+}
+
+#[cfg(test)]
+mod wasm_tests {
+    use super::*;
+    use wasm_bindgen_test::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    /// The message a JS `catch` would see
+    fn message(value: JsValue) -> String {
+        js_sys::Error::from(value).message().into()
+    }
+
+    #[wasm_bindgen_test]
+    fn test_errors_in_browser() {
+        init();
+        assert!(message(factorial(13).unwrap_err()).contains("factorial_u128"));
+        assert!(message(fibonacci(48).unwrap_err()).contains("n > 47"));
+        assert!(add(i32::MAX, 1).is_err());
+        assert!(nth_prime(0).is_err());
+        assert!(primes_up_to(SIEVE_LIMIT + 1).is_err());
+        assert!(prime_factors(0).is_err());
+    }
+
+    #[wasm_bindgen_test]
+    fn test_collections_in_browser() {
+        let samples = Samples::new(4);
+        // Writes through the view land in Rust's buffer
+        let view = samples.view();
+        view.copy_from(&[1.0, 2.0, 3.0, 4.0]);
+        assert_eq!(samples.sum(), 10.0);
+        assert_eq!(sum_float64_array(&samples.to_array()), 10.0);
+
+        let counts = value_counts(&[5, 7, 5]);
+        assert_eq!(counts.size(), 2);
+        assert_eq!(counts.get(&JsValue::from(5)).as_f64(), Some(2.0));
+
+        let summary = describe(&[2.0, 4.0]).unwrap();
+        let mean = Reflect::get(&summary, &JsValue::from("mean")).unwrap();
+        assert_eq!(mean.as_f64(), Some(3.0));
+        assert_eq!(sum_values(&summary).unwrap(), 2.0 + 2.0 + 4.0 + 3.0);
+        assert!(describe(&[]).is_err());
+    }
 }
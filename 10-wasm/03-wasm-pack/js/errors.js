@@ -0,0 +1,31 @@
+// Error classes thrown by the wasm module.
+//
+// wasm-bindgen can't define JS classes that extend `Error`, so they live
+// here and Rust imports them (see src/error.rs). `name` is set explicitly
+// rather than read from `new.target.name`, which minifiers rename.
+
+export class WasmError extends Error {
+    constructor(message, code) {
+        super(message);
+        this.name = "WasmError";
+        this.code = code;
+    }
+}
+
+export class StatisticsError extends WasmError {
+    constructor(message, code) {
+        super(message, code);
+        this.name = "StatisticsError";
+    }
+}
+
+export class ImageError extends WasmError {
+    constructor(message, code) {
+        super(message, code);
+        this.name = "ImageError";
+    }
+}
+
+export function errorClasses() {
+    return { WasmError, StatisticsError, ImageError };
+}
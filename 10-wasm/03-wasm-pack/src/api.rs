@@ -0,0 +1,228 @@
+//! An async HTTP client for a JSON users API, built on `fetch`.
+//!
+//! Exported `async fn`s become JS functions returning a `Promise`: `Ok`
+//! resolves it and `Err` rejects it, so JS can simply `await` them inside
+//! a `try`/`catch`:
+//!
+//! ```javascript
+//! const api = new ApiClient("https://example.com/api");
+//! api.set_header("Authorization", `Bearer ${token}`);
+//! try {
+//!     const user = await api.get_user(1);    // a `User`, validated
+//!     const everyone = await api.list_users();
+//! } catch (e) {
+//!     console.error(e);                      // "GET https://example.com/api/users/1 failed: 404 Not Found"
+//! }
+//! ```
+//!
+//! `JsFuture` turns the JS promises from `fetch()` and `response.json()`
+//! into Rust futures. While a request is pending the client stays borrowed,
+//! so calling `set_header` before it settles throws.
+
+use js_sys::{Promise, JSON};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{Request, RequestInit, Response, WorkerGlobalScope};
+
+use crate::{User, UserData};
+
+/// Client for an API exposing `GET /users`, `GET /users/{id}` and
+/// `POST /users`
+#[wasm_bindgen]
+#[derive(Debug, Clone)]
+pub struct ApiClient {
+    base_url: String,
+    headers: Vec<(String, String)>,
+}
+
+#[wasm_bindgen]
+impl ApiClient {
+    #[wasm_bindgen(constructor)]
+    pub fn new(base_url: String) -> ApiClient {
+        ApiClient {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            headers: Vec::new(),
+        }
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn base_url(&self) -> String {
+        self.base_url.clone()
+    }
+
+    /// Send `name: value` with every request, replacing an earlier value
+    pub fn set_header(&mut self, name: String, value: String) {
+        match self
+            .headers
+            .iter_mut()
+            .find(|(existing, _)| existing.eq_ignore_ascii_case(&name))
+        {
+            Some(header) => header.1 = value,
+            None => self.headers.push((name, value)),
+        }
+    }
+
+    /// Fetch one user. Rejects on network and HTTP errors, and when the
+    /// response isn't a valid user.
+    pub async fn get_user(&self, id: u32) -> Result<User, JsValue> {
+        let json = self.send("GET", &format!("/users/{}", id), None).await?;
+        decode_user(json)
+    }
+
+    /// Fetch every user
+    pub async fn list_users(&self) -> Result<Vec<User>, JsValue> {
+        let json = self.send("GET", "/users", None).await?;
+        let users: Vec<UserData> = serde_wasm_bindgen::from_value(json)?;
+        ApiClient::users_from_data(users).map_err(|e| JsValue::from_str(&e))
+    }
+
+    /// Create a user and return it as stored by the server
+    pub async fn create_user(&self, user: &User) -> Result<User, JsValue> {
+        let body = serde_wasm_bindgen::to_value(&UserData::from(user))?;
+        let body = JSON::stringify(&body)?;
+        let json = self.send("POST", "/users", Some(&body)).await?;
+        decode_user(json)
+    }
+}
+
+impl ApiClient {
+    /// Full URL for `path` (which starts with `/`)
+    pub fn url(&self, path: &str) -> String {
+        format!("{}{}", self.base_url, path)
+    }
+
+    pub fn headers(&self) -> &[(String, String)] {
+        &self.headers
+    }
+
+    /// Turn a non-2xx status into an error naming the request
+    pub fn check_status(
+        method: &str,
+        url: &str,
+        status: u16,
+        status_text: &str,
+    ) -> Result<(), String> {
+        if (200..300).contains(&status) {
+            return Ok(());
+        }
+        let reason = if status_text.is_empty() {
+            String::new()
+        } else {
+            format!(" {}", status_text)
+        };
+        Err(format!("{} {} failed: {}{}", method, url, status, reason))
+    }
+
+    /// Validate users received from the server, like `UserRegistry::import`
+    pub fn users_from_data(users: Vec<UserData>) -> Result<Vec<User>, String> {
+        users.into_iter().map(User::try_from).collect()
+    }
+
+    /// Send a request and parse the JSON response body
+    async fn send(
+        &self,
+        method: &str,
+        path: &str,
+        body: Option<&JsValue>,
+    ) -> Result<JsValue, JsValue> {
+        let url = self.url(path);
+
+        let init = RequestInit::new();
+        init.set_method(method);
+        if let Some(body) = body {
+            init.set_body(body);
+        }
+        let request = Request::new_with_str_and_init(&url, &init)?;
+        let headers = request.headers();
+        headers.set("Accept", "application/json")?;
+        if body.is_some() {
+            headers.set("Content-Type", "application/json")?;
+        }
+        for (name, value) in &self.headers {
+            headers.set(name, value)?;
+        }
+
+        let response: Response = JsFuture::from(fetch(&request)?).await?.dyn_into()?;
+        ApiClient::check_status(method, &url, response.status(), &response.status_text())
+            .map_err(|e| JsValue::from_str(&e))?;
+        JsFuture::from(response.json()?).await
+    }
+}
+
+fn decode_user(json: JsValue) -> Result<User, JsValue> {
+    let data: UserData = serde_wasm_bindgen::from_value(json)?;
+    User::try_from(data).map_err(|e| JsValue::from_str(&e))
+}
+
+/// `fetch` is a method of the global object, which is a `Window` on the
+/// main thread and a `WorkerGlobalScope` inside a worker
+fn fetch(request: &Request) -> Result<Promise, JsValue> {
+    if let Some(window) = web_sys::window() {
+        return Ok(window.fetch_with_request(request));
+    }
+    let global: WorkerGlobalScope = js_sys::global()
+        .dyn_into()
+        .map_err(|_| JsValue::from_str("fetch is not available in this context"))?;
+    Ok(global.fetch_with_request(request))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Role;
+
+    #[test]
+    fn test_api_client_urls() {
+        let client = ApiClient::new("https://example.com/api/".to_string());
+        assert_eq!(client.base_url(), "https://example.com/api");
+        assert_eq!(client.url("/users/7"), "https://example.com/api/users/7");
+    }
+
+    #[test]
+    fn test_api_client_headers_replace() {
+        let mut client = ApiClient::new("https://example.com".to_string());
+        client.set_header("Authorization".to_string(), "Bearer a".to_string());
+        client.set_header("authorization".to_string(), "Bearer b".to_string());
+        assert_eq!(
+            client.headers(),
+            [("Authorization".to_string(), "Bearer b".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_api_client_status() {
+        assert!(ApiClient::check_status("GET", "/users", 200, "OK").is_ok());
+        assert!(ApiClient::check_status("POST", "/users", 201, "Created").is_ok());
+        assert_eq!(
+            ApiClient::check_status("GET", "https://x.test/users/9", 404, "Not Found"),
+            Err("GET https://x.test/users/9 failed: 404 Not Found".to_string())
+        );
+        // HTTP/2 responses have no status text
+        assert_eq!(
+            ApiClient::check_status("GET", "/users", 500, ""),
+            Err("GET /users failed: 500".to_string())
+        );
+    }
+
+    #[test]
+    fn test_api_client_validates_users() {
+        let users: Vec<UserData> = serde_json::from_str(
+            r#"[
+                {"id": 1, "name": "Alice", "email": "alice@example.com", "active": true, "role": "Admin"},
+                {"id": 2, "name": "Bob", "email": "bob@example.com", "active": false}
+            ]"#,
+        )
+        .unwrap();
+        let users = ApiClient::users_from_data(users).unwrap();
+        assert_eq!(users[0].role(), Role::Admin);
+        assert_eq!(users[1].role(), Role::Member);
+        assert!(!users[1].active());
+
+        let invalid: Vec<UserData> = serde_json::from_str(
+            r#"[{"id": 3, "name": "", "email": "nobody@example.com", "active": true}]"#,
+        )
+        .unwrap();
+        assert!(ApiClient::users_from_data(invalid).is_err());
+    }
+}
@@ -0,0 +1,174 @@
+//! `ImageData` and canvas integration for `ImageProcessor`.
+//!
+//! The slice-based methods need JS to pull `data`, `width` and `height` out
+//! of an `ImageData`, and to wrap results back up in a new one. These
+//! methods take and return `ImageData` directly, and `read_canvas` /
+//! `write_canvas` go all the way to a `<canvas>` element by id:
+//!
+//! ```javascript
+//! const image = ImageProcessor.read_canvas("photo");
+//! const small = ImageProcessor.resize_image_data(image, 320, 240, ResizeFilter.Bilinear);
+//! ImageProcessor.write_canvas("preview", ImageProcessor.sobel_image_data(small));
+//! ```
+//!
+//! Every method returns a new `ImageData`; the input is left unchanged.
+
+use tsify::Ts;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::{Clamped, JsCast};
+use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement, ImageData};
+
+use crate::interop::from_ts_all;
+use crate::{ColorOp, ErrorCode, ImageProcessor, Kernel, ResizeFilter, WasmError};
+
+/// Wrap RGBA bytes in a new `ImageData`
+fn image_data(pixels: &[u8], width: u32, height: u32) -> Result<ImageData, JsValue> {
+    ImageData::new_with_u8_clamped_array_and_sh(Clamped(pixels), width, height)
+}
+
+/// Copy the pixels out, run an in-place operation, and wrap the result
+fn map_in_place(
+    image: &ImageData,
+    op: impl FnOnce(&mut [u8], u32, u32) -> Result<(), WasmError>,
+) -> Result<ImageData, JsValue> {
+    let (width, height) = (image.width(), image.height());
+    let mut pixels = image.data().0;
+    op(&mut pixels, width, height)?;
+    image_data(&pixels, width, height)
+}
+
+/// Look up a `<canvas>` by id and get its 2D context
+fn canvas_context(
+    canvas_id: &str,
+) -> Result<(HtmlCanvasElement, CanvasRenderingContext2d), JsValue> {
+    let unavailable = |message: String| WasmError::image(ErrorCode::CanvasUnavailable, message);
+    let document = web_sys::window()
+        .and_then(|window| window.document())
+        .ok_or_else(|| {
+            unavailable("No document available (canvas helpers need a window)".into())
+        })?;
+    let canvas: HtmlCanvasElement = document
+        .get_element_by_id(canvas_id)
+        .ok_or_else(|| unavailable(format!("No element with id '{}'", canvas_id)))?
+        .dyn_into()
+        .map_err(|_| unavailable(format!("Element '{}' is not a canvas", canvas_id)))?;
+    let context: CanvasRenderingContext2d = canvas
+        .get_context("2d")?
+        .ok_or_else(|| unavailable("Canvas has no 2D context".into()))?
+        .dyn_into()?;
+    Ok((canvas, context))
+}
+
+/// `ImageData` and canvas integration
+#[wasm_bindgen]
+impl ImageProcessor {
+    /// Read the full contents of the canvas with id `canvas_id`
+    pub fn read_canvas(canvas_id: &str) -> Result<ImageData, JsValue> {
+        let (canvas, context) = canvas_context(canvas_id)?;
+        context.get_image_data(0.0, 0.0, canvas.width() as f64, canvas.height() as f64)
+    }
+
+    /// Draw `image` onto the canvas with id `canvas_id`, resizing the
+    /// canvas to fit it first
+    pub fn write_canvas(canvas_id: &str, image: &ImageData) -> Result<(), JsValue> {
+        let (canvas, context) = canvas_context(canvas_id)?;
+        if canvas.width() != image.width() || canvas.height() != image.height() {
+            canvas.set_width(image.width());
+            canvas.set_height(image.height());
+        }
+        context.put_image_data(image, 0.0, 0.0)
+    }
+
+    /// Color adjustments, as in `pipeline`
+    pub fn adjust_image_data(
+        image: &ImageData,
+        ops: Vec<Ts<ColorOp>>,
+    ) -> Result<ImageData, JsValue> {
+        let ops = from_ts_all(&ops)?;
+        map_in_place(image, |pixels, _, _| Self::apply_color_ops(pixels, &ops))
+    }
+
+    /// Convolve with a square kernel, as in `convolve`
+    pub fn convolve_image_data(image: &ImageData, kernel: &[f32]) -> Result<ImageData, JsValue> {
+        let kernel = Kernel::new(kernel.to_vec())?;
+        map_in_place(image, |pixels, width, height| {
+            Self::apply_kernel(pixels, width, height, &kernel)
+        })
+    }
+
+    pub fn gaussian_blur_image_data(image: &ImageData, sigma: f32) -> Result<ImageData, JsValue> {
+        map_in_place(image, |pixels, width, height| {
+            Self::apply_gaussian_blur(pixels, width, height, sigma)
+        })
+    }
+
+    pub fn sharpen_image_data(image: &ImageData) -> Result<ImageData, JsValue> {
+        map_in_place(image, |pixels, width, height| {
+            Self::apply_kernel(pixels, width, height, &Kernel::sharpen())
+        })
+    }
+
+    pub fn emboss_image_data(image: &ImageData) -> Result<ImageData, JsValue> {
+        map_in_place(image, |pixels, width, height| {
+            Self::apply_kernel(pixels, width, height, &Kernel::emboss())
+        })
+    }
+
+    pub fn sobel_image_data(image: &ImageData) -> Result<ImageData, JsValue> {
+        map_in_place(image, Self::apply_sobel)
+    }
+
+    pub fn resize_image_data(
+        image: &ImageData,
+        new_width: u32,
+        new_height: u32,
+        filter: ResizeFilter,
+    ) -> Result<ImageData, JsValue> {
+        let pixels = Self::apply_resize(
+            &image.data(),
+            image.width(),
+            image.height(),
+            new_width,
+            new_height,
+            filter,
+        )?;
+        image_data(&pixels, new_width, new_height)
+    }
+
+    pub fn crop_image_data(
+        image: &ImageData,
+        x: u32,
+        y: u32,
+        crop_width: u32,
+        crop_height: u32,
+    ) -> Result<ImageData, JsValue> {
+        let pixels = Self::apply_crop(
+            &image.data(),
+            image.width(),
+            image.height(),
+            x,
+            y,
+            crop_width,
+            crop_height,
+        )?;
+        image_data(&pixels, crop_width, crop_height)
+    }
+
+    /// Rotate clockwise by `quarter_turns * 90` degrees
+    pub fn rotate_image_data(image: &ImageData, quarter_turns: u32) -> Result<ImageData, JsValue> {
+        let (width, height) = (image.width(), image.height());
+        let pixels = Self::apply_rotate(&image.data(), width, height, quarter_turns)?;
+        if quarter_turns % 2 == 1 {
+            image_data(&pixels, height, width)
+        } else {
+            image_data(&pixels, width, height)
+        }
+    }
+
+    /// Mirror left-to-right when `horizontal`, otherwise top-to-bottom
+    pub fn flip_image_data(image: &ImageData, horizontal: bool) -> Result<ImageData, JsValue> {
+        let (width, height) = (image.width(), image.height());
+        let pixels = Self::apply_flip(&image.data(), width, height, horizontal)?;
+        image_data(&pixels, width, height)
+    }
+}
@@ -0,0 +1,327 @@
+//! Color adjustments for `ImageProcessor`.
+//!
+//! Each adjustment is a `ColorOp`. `pipeline` applies a list of them in a
+//! single pass: every pixel is read once, run through all operations in
+//! floating point, and written back once, so chaining five adjustments costs
+//! one trip over the buffer instead of five (and rounds only once).
+//!
+//! Sepia, saturation and hue rotation use the color matrices from the CSS
+//! Filter Effects spec, so results match the equivalent CSS `filter`.
+
+use serde::{Deserialize, Serialize};
+use tsify::{Ts, Tsify};
+use wasm_bindgen::prelude::*;
+
+use crate::interop::from_ts_all;
+use crate::{ErrorCode, ImageProcessor, WasmError};
+
+/// One color adjustment. From JS, write as `{ op: "contrast", factor: 1.2 }`.
+#[derive(Serialize, Deserialize, Tsify, Debug, Clone, Copy, PartialEq)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum ColorOp {
+    Grayscale,
+    Invert,
+    /// Multiply every channel; 1.0 leaves the image unchanged
+    Brightness {
+        factor: f32,
+    },
+    /// Blend towards sepia tones; 0.0 = unchanged, 1.0 = full sepia
+    Sepia {
+        amount: f32,
+    },
+    /// Stretch values away from mid-gray; 0.0 = flat gray, 1.0 = unchanged
+    Contrast {
+        factor: f32,
+    },
+    /// 0.0 = grayscale, 1.0 = unchanged, above 1.0 = more vivid
+    Saturation {
+        factor: f32,
+    },
+    /// Rotate hues around the color wheel
+    HueRotate {
+        degrees: f32,
+    },
+    /// Gamma correction: above 1.0 brightens midtones, below 1.0 darkens
+    Gamma {
+        gamma: f32,
+    },
+}
+
+impl ColorOp {
+    fn validate(&self) -> Result<(), WasmError> {
+        let (name, value, valid) = match *self {
+            ColorOp::Grayscale | ColorOp::Invert => return Ok(()),
+            ColorOp::Brightness { factor } => ("brightness factor", factor, factor >= 0.0),
+            ColorOp::Sepia { amount } => ("sepia amount", amount, (0.0..=1.0).contains(&amount)),
+            ColorOp::Contrast { factor } => ("contrast factor", factor, factor >= 0.0),
+            ColorOp::Saturation { factor } => ("saturation factor", factor, factor >= 0.0),
+            ColorOp::HueRotate { degrees } => ("hue rotation", degrees, degrees.is_finite()),
+            ColorOp::Gamma { gamma } => ("gamma", gamma, gamma > 0.0 && gamma.is_finite()),
+        };
+        if valid {
+            Ok(())
+        } else {
+            Err(WasmError::image(
+                ErrorCode::InvalidArgument,
+                format!("Invalid {}: {}", name, value),
+            ))
+        }
+    }
+
+    /// Apply to one pixel's RGB values (0-255), clamping the result
+    fn apply(&self, rgb: [f32; 3]) -> [f32; 3] {
+        let [r, g, b] = rgb;
+        let out = match *self {
+            ColorOp::Grayscale => {
+                let avg = (r + g + b) / 3.0;
+                [avg; 3]
+            }
+            ColorOp::Invert => rgb.map(|c| 255.0 - c),
+            ColorOp::Brightness { factor } => rgb.map(|c| c * factor),
+            ColorOp::Contrast { factor } => rgb.map(|c| (c - 127.5) * factor + 127.5),
+            ColorOp::Gamma { gamma } => rgb.map(|c| 255.0 * (c / 255.0).powf(1.0 / gamma)),
+            ColorOp::Sepia { amount } => {
+                let k = 1.0 - amount;
+                multiply(
+                    [
+                        [0.393 + 0.607 * k, 0.769 - 0.769 * k, 0.189 - 0.189 * k],
+                        [0.349 - 0.349 * k, 0.686 + 0.314 * k, 0.168 - 0.168 * k],
+                        [0.272 - 0.272 * k, 0.534 - 0.534 * k, 0.131 + 0.869 * k],
+                    ],
+                    rgb,
+                )
+            }
+            ColorOp::Saturation { factor: s } => multiply(
+                [
+                    [0.213 + 0.787 * s, 0.715 - 0.715 * s, 0.072 - 0.072 * s],
+                    [0.213 - 0.213 * s, 0.715 + 0.285 * s, 0.072 - 0.072 * s],
+                    [0.213 - 0.213 * s, 0.715 - 0.715 * s, 0.072 + 0.928 * s],
+                ],
+                rgb,
+            ),
+            ColorOp::HueRotate { degrees } => {
+                let (sin, cos) = degrees.to_radians().sin_cos();
+                multiply(
+                    [
+                        [
+                            0.213 + cos * 0.787 - sin * 0.213,
+                            0.715 - cos * 0.715 - sin * 0.715,
+                            0.072 - cos * 0.072 + sin * 0.928,
+                        ],
+                        [
+                            0.213 - cos * 0.213 + sin * 0.143,
+                            0.715 + cos * 0.285 + sin * 0.140,
+                            0.072 - cos * 0.072 - sin * 0.283,
+                        ],
+                        [
+                            0.213 - cos * 0.213 - sin * 0.787,
+                            0.715 - cos * 0.715 + sin * 0.715,
+                            0.072 + cos * 0.928 + sin * 0.072,
+                        ],
+                    ],
+                    rgb,
+                )
+            }
+        };
+        // Clamp after every step, as applying the operations one by one would
+        out.map(|c| c.clamp(0.0, 255.0))
+    }
+}
+
+fn multiply(matrix: [[f32; 3]; 3], [r, g, b]: [f32; 3]) -> [f32; 3] {
+    matrix.map(|row| row[0] * r + row[1] * g + row[2] * b)
+}
+
+/// Color adjustments
+#[wasm_bindgen]
+impl ImageProcessor {
+    /// Tint towards sepia; `amount` from 0.0 (unchanged) to 1.0
+    pub fn sepia(pixels: &mut [u8], amount: f32) -> Result<(), JsValue> {
+        Self::apply_color_ops(pixels, &[ColorOp::Sepia { amount }]).map_err(JsValue::from)
+    }
+
+    /// Adjust contrast; 1.0 leaves the image unchanged
+    pub fn contrast(pixels: &mut [u8], factor: f32) -> Result<(), JsValue> {
+        Self::apply_color_ops(pixels, &[ColorOp::Contrast { factor }]).map_err(JsValue::from)
+    }
+
+    /// Adjust saturation; 0.0 is grayscale, 1.0 leaves the image unchanged
+    pub fn saturation(pixels: &mut [u8], factor: f32) -> Result<(), JsValue> {
+        Self::apply_color_ops(pixels, &[ColorOp::Saturation { factor }]).map_err(JsValue::from)
+    }
+
+    /// Rotate hues by `degrees`
+    pub fn hue_rotate(pixels: &mut [u8], degrees: f32) -> Result<(), JsValue> {
+        Self::apply_color_ops(pixels, &[ColorOp::HueRotate { degrees }]).map_err(JsValue::from)
+    }
+
+    /// Gamma correction; values above 1.0 brighten midtones
+    pub fn gamma(pixels: &mut [u8], gamma: f32) -> Result<(), JsValue> {
+        Self::apply_color_ops(pixels, &[ColorOp::Gamma { gamma }]).map_err(JsValue::from)
+    }
+
+    /// Apply a list of operations in one pass, e.g.
+    /// `[{ op: "contrast", factor: 1.2 }, { op: "hue_rotate", degrees: 90 }]`
+    pub fn pipeline(pixels: &mut [u8], ops: Vec<Ts<ColorOp>>) -> Result<(), JsValue> {
+        let ops = from_ts_all(&ops)?;
+        Self::apply_color_ops(pixels, &ops).map_err(JsValue::from)
+    }
+}
+
+impl ImageProcessor {
+    /// Run `ops` in order over every RGBA pixel, leaving alpha unchanged.
+    /// All parameters are checked before any pixel is touched.
+    pub fn apply_color_ops(pixels: &mut [u8], ops: &[ColorOp]) -> Result<(), WasmError> {
+        ops.iter().try_for_each(ColorOp::validate)?;
+
+        for pixel in pixels.chunks_exact_mut(4) {
+            let rgb = [pixel[0], pixel[1], pixel[2]].map(f32::from);
+            let rgb = ops.iter().fold(rgb, |rgb, op| op.apply(rgb));
+            for (channel, value) in pixel.iter_mut().zip(rgb) {
+                *channel = value.round() as u8;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> Vec<u8> {
+        vec![
+            255, 0, 0, 255, // red
+            30, 120, 220, 128, // blue-ish, half transparent
+            128, 128, 128, 0, // gray
+            250, 200, 10, 255, // yellow
+        ]
+    }
+
+    fn apply(ops: &[ColorOp]) -> Vec<u8> {
+        let mut pixels = sample();
+        ImageProcessor::apply_color_ops(&mut pixels, ops).unwrap();
+        pixels
+    }
+
+    /// Largest per-byte difference
+    fn distance(a: &[u8], b: &[u8]) -> u8 {
+        a.iter().zip(b).map(|(x, y)| x.abs_diff(*y)).max().unwrap()
+    }
+
+    #[test]
+    fn test_neutral_parameters() {
+        for op in [
+            ColorOp::Sepia { amount: 0.0 },
+            ColorOp::Contrast { factor: 1.0 },
+            ColorOp::Saturation { factor: 1.0 },
+            ColorOp::HueRotate { degrees: 0.0 },
+            ColorOp::Gamma { gamma: 1.0 },
+            ColorOp::Brightness { factor: 1.0 },
+        ] {
+            assert!(distance(&apply(&[op]), &sample()) <= 1, "{:?}", op);
+        }
+        // A full turn of the color wheel comes back to the start
+        let turned = apply(&[ColorOp::HueRotate { degrees: 360.0 }]);
+        assert!(distance(&turned, &sample()) <= 1);
+    }
+
+    #[test]
+    fn test_adjustments() {
+        // Zero saturation leaves only gray
+        let gray = apply(&[ColorOp::Saturation { factor: 0.0 }]);
+        assert!(gray
+            .chunks_exact(4)
+            .all(|p| p[0].abs_diff(p[1]) <= 1 && p[1].abs_diff(p[2]) <= 1));
+
+        // Zero contrast flattens to mid-gray
+        let flat = apply(&[ColorOp::Contrast { factor: 0.0 }]);
+        assert!(flat.chunks_exact(4).all(|p| p[..3] == [128, 128, 128]));
+
+        // Gamma above 1 brightens midtones but keeps black and white
+        let brighter = apply(&[ColorOp::Gamma { gamma: 2.2 }]);
+        assert!(brighter[8] > 128);
+        assert_eq!(&brighter[..3], &[255, 0, 0]);
+
+        // Sepia turns pure red into a warm brown
+        let sepia = apply(&[ColorOp::Sepia { amount: 1.0 }]);
+        assert_eq!(&sepia[..3], &[100, 89, 69]);
+
+        // Alpha is never touched
+        for pixels in [gray, flat, brighter, sepia] {
+            assert_eq!(
+                pixels.iter().skip(3).step_by(4).collect::<Vec<_>>(),
+                vec![&255, &128, &0, &255]
+            );
+        }
+    }
+
+    #[test]
+    fn test_clamping() {
+        let contrasty = apply(&[ColorOp::Contrast { factor: 4.0 }]);
+        assert_eq!(&contrasty[12..15], &[255, 255, 0]);
+
+        // Clamping happens after each step: once clipped by brightness,
+        // darkening again cannot recover the original values
+        let ops = [
+            ColorOp::Brightness { factor: 3.0 },
+            ColorOp::Brightness { factor: 0.5 },
+        ];
+        assert_eq!(&apply(&ops)[12..15], &[128, 128, 15]);
+    }
+
+    #[test]
+    fn test_pipeline_matches_sequential() {
+        let ops = [
+            ColorOp::Contrast { factor: 1.3 },
+            ColorOp::Saturation { factor: 1.5 },
+            ColorOp::HueRotate { degrees: 45.0 },
+            ColorOp::Gamma { gamma: 0.8 },
+            ColorOp::Sepia { amount: 0.3 },
+        ];
+
+        let mut sequential = sample();
+        for op in ops {
+            ImageProcessor::apply_color_ops(&mut sequential, &[op]).unwrap();
+        }
+
+        // One pass rounds once, so results differ by at most a few levels
+        assert!(distance(&apply(&ops), &sequential) <= 3);
+    }
+
+    #[test]
+    fn test_invalid_parameters() {
+        let mut pixels = sample();
+        for op in [
+            ColorOp::Sepia { amount: 1.5 },
+            ColorOp::Contrast { factor: -1.0 },
+            ColorOp::Saturation { factor: f32::NAN },
+            ColorOp::Gamma { gamma: 0.0 },
+            ColorOp::HueRotate {
+                degrees: f32::INFINITY,
+            },
+        ] {
+            let ops = [ColorOp::Invert, op];
+            assert!(ImageProcessor::apply_color_ops(&mut pixels, &ops).is_err());
+        }
+        // Nothing was applied, not even the valid first step
+        assert_eq!(pixels, sample());
+    }
+
+    #[test]
+    fn test_ops_from_json() {
+        let ops: Vec<ColorOp> = serde_json::from_str(
+            r#"[{"op":"contrast","factor":1.2},{"op":"hue_rotate","degrees":90},{"op":"grayscale"}]"#,
+        )
+        .unwrap();
+        assert_eq!(
+            ops,
+            vec![
+                ColorOp::Contrast { factor: 1.2 },
+                ColorOp::HueRotate { degrees: 90.0 },
+                ColorOp::Grayscale,
+            ]
+        );
+    }
+}
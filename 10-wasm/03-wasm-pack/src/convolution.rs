@@ -0,0 +1,351 @@
+//! Convolution filters for `ImageProcessor`.
+//!
+//! Every output pixel is a weighted sum of its neighbours, so a 5x5 kernel
+//! over a 1920x1080 image is ~50 million multiply-adds per channel. That is
+//! exactly the kind of tight numeric loop where WASM pulls ahead of JS.
+//!
+//! Pixels are RGBA (as in `ImageData.data`). Filters change RGB and leave
+//! alpha alone; edges are handled by clamping coordinates to the border.
+
+use wasm_bindgen::prelude::*;
+
+use crate::{ErrorCode, ImageProcessor, WasmError};
+
+/// Largest accepted Gaussian sigma; the kernel already spans 601 pixels
+const MAX_SIGMA: f32 = 100.0;
+
+/// A convolution kernel: `width * height` weights in row-major order,
+/// plus a constant added to every result
+#[derive(Debug, Clone, PartialEq)]
+pub struct Kernel {
+    width: usize,
+    height: usize,
+    weights: Vec<f32>,
+    bias: f32,
+}
+
+impl Kernel {
+    /// Square kernel; `weights.len()` must be an odd square (9, 25, 49, ...)
+    pub fn new(weights: Vec<f32>) -> Result<Kernel, WasmError> {
+        let size = (weights.len() as f64).sqrt() as usize;
+        if size * size != weights.len() || size.is_multiple_of(2) {
+            return Err(WasmError::image(
+                ErrorCode::InvalidKernel,
+                format!(
+                    "Kernel must have an odd square number of weights, got {}",
+                    weights.len()
+                ),
+            ));
+        }
+        Ok(Kernel {
+            width: size,
+            height: size,
+            weights,
+            bias: 0.0,
+        })
+    }
+
+    pub fn with_bias(mut self, bias: f32) -> Kernel {
+        self.bias = bias;
+        self
+    }
+
+    /// Horizontal and vertical 1-D Gaussian kernels (radius ~3 sigma).
+    /// Blurring with both in turn equals one 2-D Gaussian but costs
+    /// `2n` instead of `n²` operations per pixel. Sigma must be in `(0, 100]`.
+    pub fn gaussian(sigma: f32) -> Result<(Kernel, Kernel), WasmError> {
+        if sigma.is_nan() || sigma <= 0.0 || sigma > MAX_SIGMA {
+            return Err(WasmError::image(
+                ErrorCode::InvalidArgument,
+                format!("Sigma must be in (0, {}], got {}", MAX_SIGMA, sigma),
+            ));
+        }
+        let radius = (sigma * 3.0).ceil() as i32;
+        let mut weights: Vec<f32> = (-radius..=radius)
+            .map(|x| (-(x * x) as f32 / (2.0 * sigma * sigma)).exp())
+            .collect();
+        let sum: f32 = weights.iter().sum();
+        weights.iter_mut().for_each(|w| *w /= sum);
+
+        let len = weights.len();
+        let horizontal = Kernel {
+            width: len,
+            height: 1,
+            weights: weights.clone(),
+            bias: 0.0,
+        };
+        let vertical = Kernel {
+            width: 1,
+            height: len,
+            weights,
+            bias: 0.0,
+        };
+        Ok((horizontal, vertical))
+    }
+
+    pub fn sharpen() -> Kernel {
+        Kernel::new(vec![0.0, -1.0, 0.0, -1.0, 5.0, -1.0, 0.0, -1.0, 0.0]).unwrap()
+    }
+
+    pub fn emboss() -> Kernel {
+        Kernel::new(vec![-2.0, -1.0, 0.0, -1.0, 1.0, 1.0, 0.0, 1.0, 2.0]).unwrap()
+    }
+
+    pub fn sobel_x() -> Kernel {
+        Kernel::new(vec![-1.0, 0.0, 1.0, -2.0, 0.0, 2.0, -1.0, 0.0, 1.0]).unwrap()
+    }
+
+    pub fn sobel_y() -> Kernel {
+        Kernel::new(vec![-1.0, -2.0, -1.0, 0.0, 0.0, 0.0, 1.0, 2.0, 1.0]).unwrap()
+    }
+}
+
+/// Convolve one channel plane, clamping samples to the image border
+fn convolve_plane(plane: &[f32], width: usize, height: usize, kernel: &Kernel) -> Vec<f32> {
+    let (rx, ry) = (kernel.width / 2, kernel.height / 2);
+    let mut out = vec![0.0; plane.len()];
+
+    for y in 0..height {
+        for x in 0..width {
+            let mut sum = kernel.bias;
+            for ky in 0..kernel.height {
+                let sy = (y + ky).saturating_sub(ry).min(height - 1);
+                let row = &plane[sy * width..(sy + 1) * width];
+                let weights = &kernel.weights[ky * kernel.width..(ky + 1) * kernel.width];
+                for (kx, weight) in weights.iter().enumerate() {
+                    let sx = (x + kx).saturating_sub(rx).min(width - 1);
+                    sum += row[sx] * weight;
+                }
+            }
+            out[y * width + x] = sum;
+        }
+    }
+
+    out
+}
+
+pub(crate) fn check_dimensions(pixels: &[u8], width: u32, height: u32) -> Result<(), WasmError> {
+    // Can overflow on wasm32, where usize is 32 bits
+    let expected = (width as usize)
+        .checked_mul(height as usize)
+        .and_then(|n| n.checked_mul(4));
+    if width == 0 || height == 0 || expected != Some(pixels.len()) {
+        let expected = expected.map_or("more than usize::MAX".to_string(), |n| n.to_string());
+        return Err(WasmError::image(
+            ErrorCode::DimensionMismatch,
+            format!(
+                "Expected {}x{} RGBA pixels ({} bytes), got {} bytes",
+                width,
+                height,
+                expected,
+                pixels.len()
+            ),
+        ));
+    }
+    Ok(())
+}
+
+/// Run kernels one after another on each RGB channel
+fn apply_kernels(
+    pixels: &mut [u8],
+    width: u32,
+    height: u32,
+    kernels: &[Kernel],
+) -> Result<(), WasmError> {
+    check_dimensions(pixels, width, height)?;
+    let (width, height) = (width as usize, height as usize);
+
+    for channel in 0..3 {
+        let mut plane: Vec<f32> = pixels
+            .iter()
+            .skip(channel)
+            .step_by(4)
+            .map(|&v| v as f32)
+            .collect();
+        for kernel in kernels {
+            plane = convolve_plane(&plane, width, height, kernel);
+        }
+        for (pixel, value) in pixels.chunks_exact_mut(4).zip(plane) {
+            pixel[channel] = value.round().clamp(0.0, 255.0) as u8;
+        }
+    }
+
+    Ok(())
+}
+
+/// Convolution filters
+#[wasm_bindgen]
+impl ImageProcessor {
+    /// Apply an arbitrary square kernel (3x3, 5x5, ...) given in row-major
+    /// order. Weights are used as-is, so normalize blur kernels to sum to 1.
+    pub fn convolve(
+        pixels: &mut [u8],
+        width: u32,
+        height: u32,
+        kernel: &[f32],
+    ) -> Result<(), JsValue> {
+        let kernel = Kernel::new(kernel.to_vec())?;
+        Self::apply_kernel(pixels, width, height, &kernel).map_err(JsValue::from)
+    }
+
+    /// Gaussian blur; larger `sigma` (up to 100) blurs more
+    pub fn gaussian_blur(
+        pixels: &mut [u8],
+        width: u32,
+        height: u32,
+        sigma: f32,
+    ) -> Result<(), JsValue> {
+        Self::apply_gaussian_blur(pixels, width, height, sigma).map_err(JsValue::from)
+    }
+
+    /// Emphasize edges and fine detail
+    pub fn sharpen(pixels: &mut [u8], width: u32, height: u32) -> Result<(), JsValue> {
+        Self::apply_kernel(pixels, width, height, &Kernel::sharpen()).map_err(JsValue::from)
+    }
+
+    /// Raised-relief effect along the diagonal
+    pub fn emboss(pixels: &mut [u8], width: u32, height: u32) -> Result<(), JsValue> {
+        Self::apply_kernel(pixels, width, height, &Kernel::emboss()).map_err(JsValue::from)
+    }
+
+    /// Sobel edge detection: a grayscale image of gradient magnitude
+    pub fn sobel(pixels: &mut [u8], width: u32, height: u32) -> Result<(), JsValue> {
+        Self::apply_sobel(pixels, width, height).map_err(JsValue::from)
+    }
+}
+
+impl ImageProcessor {
+    pub fn apply_kernel(
+        pixels: &mut [u8],
+        width: u32,
+        height: u32,
+        kernel: &Kernel,
+    ) -> Result<(), WasmError> {
+        apply_kernels(pixels, width, height, std::slice::from_ref(kernel))
+    }
+
+    pub fn apply_gaussian_blur(
+        pixels: &mut [u8],
+        width: u32,
+        height: u32,
+        sigma: f32,
+    ) -> Result<(), WasmError> {
+        let (horizontal, vertical) = Kernel::gaussian(sigma)?;
+        apply_kernels(pixels, width, height, &[horizontal, vertical])
+    }
+
+    pub fn apply_sobel(pixels: &mut [u8], width: u32, height: u32) -> Result<(), WasmError> {
+        check_dimensions(pixels, width, height)?;
+        let (width, height) = (width as usize, height as usize);
+
+        // Gradients are taken on luminance, not per channel
+        let luma: Vec<f32> = pixels
+            .chunks_exact(4)
+            .map(|p| 0.299 * p[0] as f32 + 0.587 * p[1] as f32 + 0.114 * p[2] as f32)
+            .collect();
+        let gx = convolve_plane(&luma, width, height, &Kernel::sobel_x());
+        let gy = convolve_plane(&luma, width, height, &Kernel::sobel_y());
+
+        for ((pixel, x), y) in pixels.chunks_exact_mut(4).zip(gx).zip(gy) {
+            let magnitude = (x * x + y * y).sqrt().round().clamp(0.0, 255.0) as u8;
+            pixel[..3].fill(magnitude);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `width x height` image filled with one RGBA color
+    fn solid(width: u32, height: u32, rgba: [u8; 4]) -> Vec<u8> {
+        rgba.repeat((width * height) as usize)
+    }
+
+    #[test]
+    fn test_kernel_validation() {
+        assert!(Kernel::new(vec![1.0; 9]).is_ok());
+        assert!(Kernel::new(vec![1.0; 25]).is_ok());
+        assert!(Kernel::new(vec![1.0; 4]).is_err());
+        assert!(Kernel::new(vec![1.0; 8]).is_err());
+        assert!(Kernel::gaussian(0.0).is_err());
+        assert!(Kernel::gaussian(MAX_SIGMA).is_ok());
+        assert!(Kernel::gaussian(MAX_SIGMA * 2.0).is_err());
+        assert!(Kernel::gaussian(f32::INFINITY).is_err());
+
+        let mut pixels = solid(2, 2, [0, 0, 0, 255]);
+        let error =
+            ImageProcessor::apply_kernel(&mut pixels, 3, 2, &Kernel::sharpen()).unwrap_err();
+        assert_eq!(error.code(), ErrorCode::DimensionMismatch);
+
+        // The byte count doesn't fit in usize (on any target)
+        let error = check_dimensions(&pixels, u32::MAX, u32::MAX).unwrap_err();
+        assert_eq!(error.code(), ErrorCode::DimensionMismatch);
+    }
+
+    #[test]
+    fn test_identity_kernel() {
+        let mut pixels: Vec<u8> = (0..4 * 4 * 4).map(|i| (i * 7 % 256) as u8).collect();
+        let original = pixels.clone();
+        let identity = Kernel::new(vec![0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0]).unwrap();
+        ImageProcessor::apply_kernel(&mut pixels, 4, 4, &identity).unwrap();
+        assert_eq!(pixels, original);
+    }
+
+    #[test]
+    fn test_filters_keep_flat_regions() {
+        // Sharpen and emboss weights sum to 1, and blur is normalized,
+        // so a solid image comes back unchanged (alpha included)
+        for filter in ["blur", "sharpen", "emboss"] {
+            let mut pixels = solid(5, 5, [200, 100, 50, 128]);
+            match filter {
+                "blur" => ImageProcessor::apply_gaussian_blur(&mut pixels, 5, 5, 1.5),
+                "sharpen" => ImageProcessor::apply_kernel(&mut pixels, 5, 5, &Kernel::sharpen()),
+                _ => ImageProcessor::apply_kernel(&mut pixels, 5, 5, &Kernel::emboss()),
+            }
+            .unwrap();
+            assert_eq!(pixels, solid(5, 5, [200, 100, 50, 128]), "{}", filter);
+        }
+    }
+
+    #[test]
+    fn test_gaussian_blur_spreads_a_point() {
+        let mut pixels = solid(7, 7, [0, 0, 0, 255]);
+        let center = (3 * 7 + 3) * 4;
+        pixels[center..center + 3].fill(255);
+
+        ImageProcessor::apply_gaussian_blur(&mut pixels, 7, 7, 1.0).unwrap();
+
+        let red = |x: usize, y: usize| pixels[(y * 7 + x) * 4];
+        assert!(red(3, 3) < 255);
+        assert!(red(2, 3) > 0);
+        // Symmetric, and falls off with distance
+        assert_eq!(red(2, 3), red(4, 3));
+        assert_eq!(red(3, 2), red(3, 4));
+        assert!(red(3, 3) > red(2, 3));
+        assert!(red(2, 3) > red(2, 2));
+    }
+
+    #[test]
+    fn test_sobel_finds_vertical_edge() {
+        // Left half black, right half white
+        let mut pixels: Vec<u8> = (0..6 * 4)
+            .flat_map(|i| {
+                let v = if i % 6 < 3 { 0 } else { 255 };
+                [v, v, v, 255]
+            })
+            .collect();
+
+        ImageProcessor::apply_sobel(&mut pixels, 6, 4).unwrap();
+
+        let value = |x: usize, y: usize| pixels[(y * 6 + x) * 4];
+        assert_eq!(value(0, 1), 0);
+        assert_eq!(value(5, 1), 0);
+        assert_eq!(value(2, 1), 255);
+        assert_eq!(value(3, 1), 255);
+        // Output is gray with alpha untouched
+        assert_eq!(&pixels[8..12], &[255, 255, 255, 255]);
+    }
+}
@@ -0,0 +1,306 @@
+//! Line-based text diffing (Myers' algorithm, via the `similar` crate).
+//!
+//! `diff` returns unified-diff style hunks as plain JS objects; `diff_html`
+//! renders the same hunks as markup, with the changed words inside modified
+//! lines wrapped in `<mark>`.
+
+use serde::{Deserialize, Serialize};
+use similar::{ChangeTag, DiffOp, TextDiff};
+use std::ops::Range;
+use tsify::{Ts, Tsify};
+use wasm_bindgen::prelude::*;
+
+use crate::interop::to_ts;
+
+/// Unchanged lines kept around each change when no context is given
+pub const DEFAULT_CONTEXT: usize = 3;
+
+#[derive(Serialize, Deserialize, Tsify, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LineKind {
+    Equal,
+    Insert,
+    Delete,
+}
+
+impl From<ChangeTag> for LineKind {
+    fn from(tag: ChangeTag) -> Self {
+        match tag {
+            ChangeTag::Equal => LineKind::Equal,
+            ChangeTag::Insert => LineKind::Insert,
+            ChangeTag::Delete => LineKind::Delete,
+        }
+    }
+}
+
+/// One line of a hunk. Line numbers are 1-based; a deleted line has no
+/// `new_line` and an inserted line no `old_line`.
+#[derive(Serialize, Deserialize, Tsify, Debug, Clone, PartialEq)]
+pub struct DiffLine {
+    pub kind: LineKind,
+    pub old_line: Option<usize>,
+    pub new_line: Option<usize>,
+    /// The line without its line ending
+    pub text: String,
+}
+
+/// A run of changes plus surrounding context, like a `@@` block in a
+/// unified diff
+#[derive(Serialize, Deserialize, Tsify, Debug, Clone, PartialEq)]
+pub struct DiffHunk {
+    pub old_start: usize,
+    pub old_len: usize,
+    pub new_start: usize,
+    pub new_len: usize,
+    pub lines: Vec<DiffLine>,
+}
+
+impl DiffHunk {
+    /// The `@@ -old_start,old_len +new_start,new_len @@` header
+    pub fn header(&self) -> String {
+        format!(
+            "@@ -{},{} +{},{} @@",
+            self.old_start, self.old_len, self.new_start, self.new_len
+        )
+    }
+
+    fn new(old_range: Range<usize>, new_range: Range<usize>, lines: Vec<DiffLine>) -> Self {
+        DiffHunk {
+            old_start: hunk_start(&old_range),
+            old_len: old_range.len(),
+            new_start: hunk_start(&new_range),
+            new_len: new_range.len(),
+            lines,
+        }
+    }
+}
+
+/// The result of `diff()`
+#[derive(Serialize, Deserialize, Tsify, Debug, Clone, PartialEq)]
+pub struct Diff {
+    pub hunks: Vec<DiffHunk>,
+    pub insertions: usize,
+    pub deletions: usize,
+    /// 0.0 (nothing shared) to 1.0 (identical)
+    pub similarity: f32,
+}
+
+fn line_text(value: &str) -> String {
+    value.trim_end_matches(['\n', '\r']).to_string()
+}
+
+/// Unified-diff numbering: a hunk that covers no lines on one side starts
+/// at the line before the gap
+fn hunk_start(range: &Range<usize>) -> usize {
+    if range.is_empty() {
+        range.start
+    } else {
+        range.start + 1
+    }
+}
+
+fn hunk_header(old_range: Range<usize>, new_range: Range<usize>) -> String {
+    DiffHunk::new(old_range, new_range, Vec::new()).header()
+}
+
+fn hunk_ranges(group: &[DiffOp]) -> (Range<usize>, Range<usize>) {
+    let (first, last) = (&group[0], &group[group.len() - 1]);
+    (
+        first.old_range().start..last.old_range().end,
+        first.new_range().start..last.new_range().end,
+    )
+}
+
+/// Diff `old` against `new` line by line, keeping `context` unchanged
+/// lines around each change
+pub fn compute_diff(old: &str, new: &str, context: usize) -> Diff {
+    let text_diff = TextDiff::from_lines(old, new);
+    let mut insertions = 0;
+    let mut deletions = 0;
+
+    let hunks = text_diff
+        .grouped_ops(context)
+        .iter()
+        .map(|group| {
+            let (old_range, new_range) = hunk_ranges(group);
+            let mut lines = Vec::new();
+            for op in group {
+                for change in text_diff.iter_changes(op) {
+                    match change.tag() {
+                        ChangeTag::Insert => insertions += 1,
+                        ChangeTag::Delete => deletions += 1,
+                        ChangeTag::Equal => {}
+                    }
+                    lines.push(DiffLine {
+                        kind: change.tag().into(),
+                        old_line: change.old_index().map(|i| i + 1),
+                        new_line: change.new_index().map(|i| i + 1),
+                        text: line_text(change.value()),
+                    });
+                }
+            }
+            DiffHunk::new(old_range, new_range, lines)
+        })
+        .collect();
+
+    Diff {
+        hunks,
+        insertions,
+        deletions,
+        similarity: text_diff.ratio(),
+    }
+}
+
+fn escape_html(text: &str, out: &mut String) {
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(c),
+        }
+    }
+}
+
+fn line_number(number: Option<usize>, out: &mut String) {
+    out.push_str("<span class=\"diff-number\">");
+    if let Some(number) = number {
+        out.push_str(&number.to_string());
+    }
+    out.push_str("</span>");
+}
+
+/// Render the diff as HTML. Classes: `diff`, `diff-hunk`, `diff-line`
+/// plus `diff-equal`/`diff-insert`/`diff-delete`, and `diff-number`.
+/// All text is escaped, so the output is safe to assign to `innerHTML`.
+pub fn render_html(old: &str, new: &str, context: usize) -> String {
+    let text_diff = TextDiff::from_lines(old, new);
+    let mut out = String::from("<div class=\"diff\">");
+
+    for group in text_diff.grouped_ops(context) {
+        let (old_range, new_range) = hunk_ranges(&group);
+        out.push_str(&format!(
+            "<div class=\"diff-hunk\">{}</div>",
+            hunk_header(old_range, new_range)
+        ));
+
+        for op in &group {
+            for change in text_diff.iter_inline_changes(op) {
+                let kind = match change.tag() {
+                    ChangeTag::Equal => "equal",
+                    ChangeTag::Insert => "insert",
+                    ChangeTag::Delete => "delete",
+                };
+                out.push_str(&format!("<div class=\"diff-line diff-{}\">", kind));
+                line_number(change.old_index().map(|i| i + 1), &mut out);
+                line_number(change.new_index().map(|i| i + 1), &mut out);
+                out.push_str("<span class=\"diff-text\">");
+                for (emphasized, value) in change.iter_strings_lossy() {
+                    let value = value.trim_end_matches(['\n', '\r']);
+                    if emphasized {
+                        out.push_str("<mark>");
+                        escape_html(value, &mut out);
+                        out.push_str("</mark>");
+                    } else {
+                        escape_html(value, &mut out);
+                    }
+                }
+                out.push_str("</span></div>");
+            }
+        }
+    }
+
+    out.push_str("</div>");
+    out
+}
+
+/// Diff two texts line by line into hunks of `{ kind, old_line, new_line, text }` lines
+#[wasm_bindgen]
+pub fn diff(old: &str, new: &str, context: Option<usize>) -> Result<Ts<Diff>, JsValue> {
+    to_ts(&compute_diff(old, new, context.unwrap_or(DEFAULT_CONTEXT)))
+}
+
+/// Diff two texts and render the result as escaped HTML
+#[wasm_bindgen]
+pub fn diff_html(old: &str, new: &str, context: Option<usize>) -> String {
+    render_html(old, new, context.unwrap_or(DEFAULT_CONTEXT))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OLD: &str = "one\ntwo\nthree\nfour\nfive\nsix\nseven\neight\nnine\nten\n";
+
+    #[test]
+    fn test_diff_identical() {
+        let diff = compute_diff(OLD, OLD, DEFAULT_CONTEXT);
+        assert!(diff.hunks.is_empty());
+        assert_eq!((diff.insertions, diff.deletions), (0, 0));
+        assert_eq!(diff.similarity, 1.0);
+    }
+
+    #[test]
+    fn test_diff_replaced_line() {
+        let new = OLD.replace("five", "FIVE");
+        let diff = compute_diff(OLD, &new, 1);
+        assert_eq!(diff.hunks.len(), 1);
+        assert_eq!((diff.insertions, diff.deletions), (1, 1));
+
+        let hunk = &diff.hunks[0];
+        assert_eq!(hunk.header(), "@@ -4,3 +4,3 @@");
+        let kinds: Vec<LineKind> = hunk.lines.iter().map(|l| l.kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                LineKind::Equal,
+                LineKind::Delete,
+                LineKind::Insert,
+                LineKind::Equal
+            ]
+        );
+        assert_eq!(hunk.lines[1].text, "five");
+        assert_eq!(
+            (hunk.lines[1].old_line, hunk.lines[1].new_line),
+            (Some(5), None)
+        );
+        assert_eq!(
+            (hunk.lines[2].old_line, hunk.lines[2].new_line),
+            (None, Some(5))
+        );
+    }
+
+    #[test]
+    fn test_diff_separate_hunks() {
+        let new = OLD
+            .replace("two\n", "")
+            .replace("nine", "nine\nnine and a half");
+        let diff = compute_diff(OLD, &new, 1);
+        assert_eq!(diff.hunks.len(), 2);
+        assert_eq!(diff.hunks[0].header(), "@@ -1,3 +1,2 @@");
+        assert_eq!(diff.hunks[1].header(), "@@ -9,2 +8,3 @@");
+
+        // With enough context the two changes merge into one hunk
+        assert_eq!(compute_diff(OLD, &new, 4).hunks.len(), 1);
+    }
+
+    #[test]
+    fn test_diff_into_empty() {
+        let diff = compute_diff("", "a\nb\n", DEFAULT_CONTEXT);
+        assert_eq!(diff.hunks[0].header(), "@@ -0,0 +1,2 @@");
+        assert_eq!(diff.insertions, 2);
+    }
+
+    #[test]
+    fn test_diff_html() {
+        let html = render_html("let x = a < b;\n", "let x = a > b;\n", DEFAULT_CONTEXT);
+        assert!(html.starts_with("<div class=\"diff\">"));
+        assert!(html.contains("<div class=\"diff-hunk\">@@ -1,1 +1,1 @@</div>"));
+        assert!(html.contains("<div class=\"diff-line diff-delete\">"));
+        assert!(html.contains("<mark>&lt;</mark>"));
+        assert!(html.contains("<mark>&gt;</mark>"));
+        assert!(!html.contains("a < b"));
+    }
+}
@@ -0,0 +1,194 @@
+//! Structured errors for JS callers.
+//!
+//! A `WasmError` crosses the boundary as a real JS `Error` subclass with a
+//! `name`, a `message` and a machine-readable `code`, so callers can match
+//! on the class instead of parsing message strings:
+//!
+//! ```javascript
+//! const { StatisticsError, ImageError } = error_classes();
+//! try {
+//!     stats.histogram(0);
+//! } catch (e) {
+//!     if (e instanceof StatisticsError && e.code === "INVALID_ARGUMENT") { ... }
+//! }
+//! ```
+//!
+//! The classes are defined in `js/errors.js`. They are reached through
+//! `error_classes()` because wasm-pack places imported JS under a hashed
+//! `snippets/` path that isn't meant to be imported directly.
+
+use std::fmt;
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen(module = "/js/errors.js")]
+extern "C" {
+    #[wasm_bindgen(extends = js_sys::Error)]
+    type StatisticsError;
+
+    #[wasm_bindgen(constructor)]
+    fn new(message: &str, code: &str) -> StatisticsError;
+
+    #[wasm_bindgen(extends = js_sys::Error)]
+    type ImageError;
+
+    #[wasm_bindgen(constructor)]
+    fn new(message: &str, code: &str) -> ImageError;
+
+    #[wasm_bindgen(typescript_type = "ErrorClasses")]
+    pub type ErrorClasses;
+
+    #[wasm_bindgen(js_name = errorClasses)]
+    fn error_classes_js() -> ErrorClasses;
+}
+
+#[wasm_bindgen(typescript_custom_section)]
+const ERROR_TYPES: &str = r#"
+export type ErrorCode =
+    | "EMPTY_DATA"
+    | "INVALID_ARGUMENT"
+    | "INVALID_KERNEL"
+    | "DIMENSION_MISMATCH"
+    | "OUT_OF_BOUNDS"
+    | "CANVAS_UNAVAILABLE";
+
+export interface WasmError extends Error {
+    readonly code: ErrorCode;
+}
+
+export interface WasmErrorConstructor {
+    new (message: string, code: ErrorCode): WasmError;
+    readonly prototype: WasmError;
+}
+
+export interface ErrorClasses {
+    WasmError: WasmErrorConstructor;
+    StatisticsError: WasmErrorConstructor;
+    ImageError: WasmErrorConstructor;
+}
+"#;
+
+/// The error classes thrown by this module, for `instanceof` checks
+#[wasm_bindgen]
+pub fn error_classes() -> ErrorClasses {
+    error_classes_js()
+}
+
+/// Which JS class an error is thrown as
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    Statistics,
+    Image,
+}
+
+/// What went wrong, independent of the message wording
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorCode {
+    /// The operation needs data and there is none
+    EmptyData,
+    /// A parameter is out of range or malformed
+    InvalidArgument,
+    /// Convolution weights don't form a valid kernel
+    InvalidKernel,
+    /// A pixel buffer doesn't match its stated width and height
+    DimensionMismatch,
+    /// A region extends past the edge of the image
+    OutOfBounds,
+    /// The requested canvas or its 2D context doesn't exist
+    CanvasUnavailable,
+}
+
+impl ErrorCode {
+    /// The value of `code` on the JS error
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ErrorCode::EmptyData => "EMPTY_DATA",
+            ErrorCode::InvalidArgument => "INVALID_ARGUMENT",
+            ErrorCode::InvalidKernel => "INVALID_KERNEL",
+            ErrorCode::DimensionMismatch => "DIMENSION_MISMATCH",
+            ErrorCode::OutOfBounds => "OUT_OF_BOUNDS",
+            ErrorCode::CanvasUnavailable => "CANVAS_UNAVAILABLE",
+        }
+    }
+}
+
+/// An error from `Statistics` or `ImageProcessor`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WasmError {
+    kind: ErrorKind,
+    code: ErrorCode,
+    message: String,
+}
+
+impl WasmError {
+    pub fn statistics(code: ErrorCode, message: impl Into<String>) -> Self {
+        WasmError {
+            kind: ErrorKind::Statistics,
+            code,
+            message: message.into(),
+        }
+    }
+
+    pub fn image(code: ErrorCode, message: impl Into<String>) -> Self {
+        WasmError {
+            kind: ErrorKind::Image,
+            code,
+            message: message.into(),
+        }
+    }
+
+    pub fn kind(&self) -> ErrorKind {
+        self.kind
+    }
+
+    pub fn code(&self) -> ErrorCode {
+        self.code
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// The name of the JS class this error is thrown as
+    pub fn name(&self) -> &'static str {
+        match self.kind {
+            ErrorKind::Statistics => "StatisticsError",
+            ErrorKind::Image => "ImageError",
+        }
+    }
+}
+
+impl fmt::Display for WasmError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.name(), self.message)
+    }
+}
+
+impl std::error::Error for WasmError {}
+
+impl From<WasmError> for JsValue {
+    fn from(error: WasmError) -> Self {
+        let code = error.code.as_str();
+        match error.kind {
+            ErrorKind::Statistics => StatisticsError::new(&error.message, code).into(),
+            ErrorKind::Image => ImageError::new(&error.message, code).into(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wasm_error_fields() {
+        let error = WasmError::image(ErrorCode::OutOfBounds, "Crop does not fit");
+        assert_eq!(error.kind(), ErrorKind::Image);
+        assert_eq!(error.name(), "ImageError");
+        assert_eq!(error.code().as_str(), "OUT_OF_BOUNDS");
+        assert_eq!(error.message(), "Crop does not fit");
+        assert_eq!(error.to_string(), "ImageError: Crop does not fit");
+
+        let error = WasmError::statistics(ErrorCode::EmptyData, "No values");
+        assert_eq!(error.name(), "StatisticsError");
+    }
+}
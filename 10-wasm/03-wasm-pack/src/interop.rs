@@ -0,0 +1,58 @@
+//! Typed plain objects across the JS boundary.
+//!
+//! Types that derive `Tsify` get a TypeScript interface in the generated
+//! `.d.ts`. Passing them as `tsify::Ts<T>` makes wasm-bindgen use that
+//! interface instead of `any`. The conversion happens inside the exported
+//! function, so a malformed object from JS turns into a thrown error, not
+//! a panic.
+
+use serde::{de::DeserializeOwned, Serialize};
+use tsify::{Ts, Tsify};
+use wasm_bindgen::JsValue;
+
+fn to_js(error: tsify::Error) -> JsValue {
+    JsValue::from_str(&error.to_string())
+}
+
+/// Serialize `value` into its typed JS object
+pub(crate) fn to_ts<T: Tsify + Serialize>(value: &T) -> Result<Ts<T>, JsValue> {
+    value.into_ts().map_err(to_js)
+}
+
+/// Deserialize a typed JS object
+pub(crate) fn from_ts<T>(value: &Ts<T>) -> Result<T, JsValue>
+where
+    T: Tsify + DeserializeOwned,
+    T::JsType: Clone,
+{
+    value.to_rust().map_err(to_js)
+}
+
+/// Deserialize every object of a typed JS array
+pub(crate) fn from_ts_all<T>(values: &[Ts<T>]) -> Result<Vec<T>, JsValue>
+where
+    T: Tsify + DeserializeOwned,
+    T::JsType: Clone,
+{
+    values.iter().map(from_ts).collect()
+}
+
+/// Serialize every value into a typed JS array
+pub(crate) fn to_ts_all<'a, T: Tsify + Serialize + 'a>(
+    values: impl IntoIterator<Item = &'a T>,
+) -> Result<Vec<Ts<T>>, JsValue> {
+    values.into_iter().map(to_ts).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ColorOp, Diff, UserData};
+
+    #[test]
+    fn test_typescript_declarations() {
+        assert!(UserData::DECL.contains(r#"role?: "Admin" | "Member" | "Guest";"#));
+        assert!(ColorOp::DECL.contains(r#"{ op: "hue_rotate"; degrees: number }"#));
+        assert!(Diff::DECL.contains("hunks: DiffHunk[];"));
+    }
+}
@@ -1,6 +1,34 @@
 use serde::{Deserialize, Serialize};
+use tsify::{Ts, Tsify};
 use wasm_bindgen::prelude::*;
 
+use interop::{from_ts, to_ts};
+
+mod api;
+mod canvas;
+mod color;
+mod convolution;
+mod diff;
+mod error;
+mod interop;
+mod registry;
+mod roles;
+mod streaming;
+mod transform;
+
+pub use api::ApiClient;
+pub use color::ColorOp;
+pub use convolution::Kernel;
+pub use diff::{diff, diff_html, Diff, DiffHunk, DiffLine, LineKind};
+pub use error::{error_classes, ErrorClasses, ErrorCode, ErrorKind, WasmError};
+pub use registry::{UserPage, UserRegistry};
+pub use roles::{role_can, Permission, Role};
+pub use streaming::{OnlineStatistics, OnlineSummary, QuantileEstimate};
+pub use transform::ResizeFilter;
+
+/// Longest accepted user name, in characters
+pub const MAX_NAME_LENGTH: usize = 64;
+
 /// A user struct that can be serialized/deserialized across JS boundary
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[wasm_bindgen]
@@ -10,19 +38,55 @@ pub struct User {
     name: String,
     email: String,
     active: bool,
+    #[serde(default)]
+    role: Role,
+}
+
+/// Names must contain something other than whitespace and be at most
+/// `MAX_NAME_LENGTH` characters
+pub fn validate_name(name: &str) -> Result<(), String> {
+    if name.trim().is_empty() {
+        return Err("Name cannot be empty".to_string());
+    }
+    if name.chars().count() > MAX_NAME_LENGTH {
+        return Err(format!(
+            "Name cannot be longer than {} characters",
+            MAX_NAME_LENGTH
+        ));
+    }
+    Ok(())
+}
+
+/// A deliberately simple check: `local@domain.tld`, no whitespace
+pub fn validate_email(email: &str) -> Result<(), String> {
+    let invalid = || Err(format!("Invalid email address: '{}'", email));
+    if email.chars().any(char::is_whitespace) {
+        return invalid();
+    }
+    let Some((local, domain)) = email.split_once('@') else {
+        return invalid();
+    };
+    let domain_ok = domain.contains('.')
+        && !domain.starts_with('.')
+        && !domain.ends_with('.')
+        && !domain.contains('@');
+    if local.is_empty() || !domain_ok {
+        return invalid();
+    }
+    Ok(())
 }
 
 #[wasm_bindgen]
 impl User {
-    /// Create a new user
+    /// Create a new member. Throws if the name or email is invalid.
     #[wasm_bindgen(constructor)]
-    pub fn new(id: u32, name: String, email: String) -> User {
-        User {
-            id,
-            name,
-            email,
-            active: true,
-        }
+    pub fn new(id: u32, name: String, email: String) -> Result<User, JsValue> {
+        User::try_new(id, name, email, Role::Member).map_err(|e| JsValue::from_str(&e))
+    }
+
+    /// Create a new user with a specific role
+    pub fn with_role(id: u32, name: String, email: String, role: Role) -> Result<User, JsValue> {
+        User::try_new(id, name, email, role).map_err(|e| JsValue::from_str(&e))
     }
 
     /// Get user's name
@@ -31,10 +95,10 @@ impl User {
         self.name.clone()
     }
 
-    /// Set user's name
+    /// Set user's name. Throws (leaving the name unchanged) if invalid.
     #[wasm_bindgen(setter)]
-    pub fn set_name(&mut self, name: String) {
-        self.name = name;
+    pub fn set_name(&mut self, name: String) -> Result<(), JsValue> {
+        self.update_name(name).map_err(|e| JsValue::from_str(&e))
     }
 
     /// Get user's email
@@ -43,10 +107,10 @@ impl User {
         self.email.clone()
     }
 
-    /// Set user's email
+    /// Set user's email. Throws (leaving the email unchanged) if invalid.
     #[wasm_bindgen(setter)]
-    pub fn set_email(&mut self, email: String) {
-        self.email = email;
+    pub fn set_email(&mut self, email: String) -> Result<(), JsValue> {
+        self.update_email(email).map_err(|e| JsValue::from_str(&e))
     }
 
     /// Check if user is active
@@ -71,7 +135,101 @@ impl User {
     }
 }
 
+impl User {
+    /// Create a user after validating its name and email
+    pub fn try_new(id: u32, name: String, email: String, role: Role) -> Result<User, String> {
+        validate_name(&name)?;
+        validate_email(&email)?;
+        Ok(User {
+            id,
+            name,
+            email,
+            active: true,
+            role,
+        })
+    }
+
+    pub fn update_name(&mut self, name: String) -> Result<(), String> {
+        validate_name(&name)?;
+        self.name = name;
+        Ok(())
+    }
+
+    pub fn update_email(&mut self, email: String) -> Result<(), String> {
+        validate_email(&email)?;
+        self.email = email;
+        Ok(())
+    }
+
+    /// Check a user that did not come through the constructor, e.g. one
+    /// deserialized from JS
+    pub fn validate(&self) -> Result<(), String> {
+        validate_name(&self.name)?;
+        validate_email(&self.email)
+    }
+}
+
+/// A `User` as a plain JS object, e.g. for `JSON.stringify`, storage, or
+/// `postMessage`, which can't carry a wasm-bindgen class handle. Unlike
+/// `Role` on the class, `role` here is the variant name.
+#[derive(Serialize, Deserialize, Tsify, Debug, Clone, PartialEq)]
+pub struct UserData {
+    pub id: u32,
+    pub name: String,
+    pub email: String,
+    pub active: bool,
+    #[serde(default)]
+    #[tsify(optional, type = "\"Admin\" | \"Member\" | \"Guest\"")]
+    pub role: Role,
+}
+
+impl From<&User> for UserData {
+    fn from(user: &User) -> Self {
+        UserData {
+            id: user.id,
+            name: user.name.clone(),
+            email: user.email.clone(),
+            active: user.active,
+            role: user.role,
+        }
+    }
+}
+
+impl TryFrom<UserData> for User {
+    type Error = String;
+
+    fn try_from(data: UserData) -> Result<Self, Self::Error> {
+        let user = User {
+            id: data.id,
+            name: data.name,
+            email: data.email,
+            active: data.active,
+            role: data.role,
+        };
+        user.validate()?;
+        Ok(user)
+    }
+}
+
+/// Plain-object conversions
+#[wasm_bindgen]
+impl User {
+    /// Copy this user into a plain `UserData` object
+    pub fn to_data(&self) -> Result<Ts<UserData>, JsValue> {
+        to_ts(&UserData::from(self))
+    }
+
+    /// Build a user from a plain object. Throws if the name or email is invalid.
+    pub fn from_data(data: Ts<UserData>) -> Result<User, JsValue> {
+        User::try_from(from_ts(&data)?).map_err(|e| JsValue::from_str(&e))
+    }
+}
+
 /// Statistics calculator with various mathematical operations
+///
+/// NaN has no place in an ordering, so the order-based metrics (min, max,
+/// percentiles, median, mode) skip it. The mean and the moments are plain
+/// arithmetic and come out NaN.
 #[wasm_bindgen]
 pub struct Statistics {
     data: Vec<f64>,
@@ -115,49 +273,279 @@ impl Statistics {
 
     /// Calculate median
     pub fn median(&self) -> Option<f64> {
-        if self.data.is_empty() {
+        self.percentile(50.0)
+    }
+
+    /// Calculate the `p`th percentile (0-100), interpolating linearly
+    /// between the two closest values
+    pub fn percentile(&self, p: f64) -> Option<f64> {
+        if !(0.0..=100.0).contains(&p) {
             return None;
         }
 
-        let mut sorted = self.data.clone();
-        sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        let sorted = self.sorted();
+        if sorted.is_empty() {
+            return None;
+        }
+        let rank = p / 100.0 * (sorted.len() - 1) as f64;
+        let lower = rank.floor() as usize;
+        let upper = rank.ceil() as usize;
+        Some(sorted[lower] + (sorted[upper] - sorted[lower]) * (rank - lower as f64))
+    }
 
-        let mid = sorted.len() / 2;
-        if sorted.len() % 2 == 0 {
-            Some((sorted[mid - 1] + sorted[mid]) / 2.0)
-        } else {
-            Some(sorted[mid])
+    /// Get the most frequent value (the smallest one on ties)
+    pub fn mode(&self) -> Option<f64> {
+        let sorted = self.sorted();
+        let mut best: Option<(f64, usize)> = None;
+
+        for run in sorted.chunk_by(|a, b| a == b) {
+            if best.is_none_or(|(_, count)| run.len() > count) {
+                best = Some((run[0], run.len()));
+            }
         }
+
+        best.map(|(value, _)| value)
+    }
+
+    /// Calculate population variance
+    pub fn variance(&self) -> Option<f64> {
+        self.central_moment(2)
     }
 
     /// Calculate standard deviation
     pub fn std_dev(&self) -> Option<f64> {
-        if self.data.is_empty() {
-            return None;
-        }
+        self.variance().map(f64::sqrt)
+    }
 
-        let mean = self.mean()?;
-        let variance =
-            self.data.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / self.data.len() as f64;
+    /// Calculate skewness (0 for symmetric data, positive for a long right tail)
+    pub fn skewness(&self) -> Option<f64> {
+        let variance = self.variance().filter(|v| *v > 0.0)?;
+        Some(self.central_moment(3)? / variance.powf(1.5))
+    }
 
-        Some(variance.sqrt())
+    /// Calculate excess kurtosis (0 for a normal distribution, positive for heavy tails)
+    pub fn kurtosis(&self) -> Option<f64> {
+        let variance = self.variance().filter(|v| *v > 0.0)?;
+        Some(self.central_moment(4)? / variance.powi(2) - 3.0)
     }
 
     /// Get minimum value
     pub fn min(&self) -> Option<f64> {
         self.data
             .iter()
-            .min_by(|a, b| a.partial_cmp(b).unwrap())
             .copied()
+            .filter(|x| !x.is_nan())
+            .min_by(f64::total_cmp)
     }
 
     /// Get maximum value
     pub fn max(&self) -> Option<f64> {
         self.data
             .iter()
-            .max_by(|a, b| a.partial_cmp(b).unwrap())
             .copied()
+            .filter(|x| !x.is_nan())
+            .max_by(f64::total_cmp)
+    }
+
+    /// Get all metrics at once as a plain JS object
+    pub fn summary(&self) -> Result<Ts<StatisticsSummary>, JsValue> {
+        to_ts(&self.to_summary())
+    }
+
+    /// Split the range min..max into `bins` equal-width bins and count the
+    /// values in each. With `normalize`, also returns each bin's share of the total.
+    pub fn histogram(
+        &self,
+        bins: usize,
+        normalize: Option<bool>,
+    ) -> Result<Ts<Histogram>, JsValue> {
+        let histogram = self.to_histogram(bins, normalize.unwrap_or(false))?;
+        to_ts(&histogram)
+    }
+
+    /// Count values into bins with explicit, strictly increasing `edges`
+    /// (`n + 1` edges make `n` bins). Values outside the edges are counted in `outside`.
+    pub fn histogram_with_edges(
+        &self,
+        edges: Vec<f64>,
+        normalize: Option<bool>,
+    ) -> Result<Ts<Histogram>, JsValue> {
+        let histogram = self.to_histogram_with_edges(edges, normalize.unwrap_or(false))?;
+        to_ts(&histogram)
+    }
+}
+
+impl Default for Statistics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Statistics {
+    /// Collect every metric into a `StatisticsSummary`
+    pub fn to_summary(&self) -> StatisticsSummary {
+        StatisticsSummary {
+            count: self.count(),
+            mean: self.mean(),
+            median: self.median(),
+            mode: self.mode(),
+            variance: self.variance(),
+            std_dev: self.std_dev(),
+            skewness: self.skewness(),
+            kurtosis: self.kurtosis(),
+            min: self.min(),
+            max: self.max(),
+            p25: self.percentile(25.0),
+            p75: self.percentile(75.0),
+            p90: self.percentile(90.0),
+            p95: self.percentile(95.0),
+            p99: self.percentile(99.0),
+        }
+    }
+
+    /// The values in ascending order, without NaNs
+    fn sorted(&self) -> Vec<f64> {
+        let mut sorted: Vec<f64> = self.data.iter().copied().filter(|x| !x.is_nan()).collect();
+        sorted.sort_by(f64::total_cmp);
+        sorted
     }
+
+    /// Mean of `(x - mean)^k` over the dataset
+    fn central_moment(&self, k: i32) -> Option<f64> {
+        let mean = self.mean()?;
+        Some(self.data.iter().map(|x| (x - mean).powi(k)).sum::<f64>() / self.data.len() as f64)
+    }
+
+    /// Equal-width histogram over the min..max of the finite values
+    pub fn to_histogram(&self, bins: usize, normalize: bool) -> Result<Histogram, WasmError> {
+        if bins == 0 {
+            return Err(WasmError::statistics(
+                ErrorCode::InvalidArgument,
+                "Histogram needs at least one bin",
+            ));
+        }
+        let finite = self.data.iter().copied().filter(|x| x.is_finite());
+        let (Some(min), Some(max)) = (
+            finite.clone().min_by(f64::total_cmp),
+            finite.max_by(f64::total_cmp),
+        ) else {
+            return Err(WasmError::statistics(
+                ErrorCode::EmptyData,
+                "Histogram needs at least one finite value",
+            ));
+        };
+
+        // All values equal: center a unit-wide range on them
+        let (low, high) = if min == max {
+            (min - 0.5, max + 0.5)
+        } else {
+            (min, max)
+        };
+        let width = (high - low) / bins as f64;
+        let mut edges: Vec<f64> = (0..bins).map(|i| low + width * i as f64).collect();
+        // Set the last edge exactly so the max is never lost to rounding
+        edges.push(high);
+
+        self.to_histogram_with_edges(edges, normalize)
+    }
+
+    /// Histogram with caller-provided bin edges.
+    ///
+    /// Bins are half-open `[edge[i], edge[i + 1])` except the last, which also
+    /// includes its right edge. Non-finite values are always counted as outside.
+    pub fn to_histogram_with_edges(
+        &self,
+        edges: Vec<f64>,
+        normalize: bool,
+    ) -> Result<Histogram, WasmError> {
+        if edges.len() < 2 {
+            return Err(WasmError::statistics(
+                ErrorCode::InvalidArgument,
+                "Histogram needs at least two edges",
+            ));
+        }
+        // partial_cmp also rejects NaN edges
+        if edges
+            .windows(2)
+            .any(|pair| pair[0].partial_cmp(&pair[1]) != Some(std::cmp::Ordering::Less))
+        {
+            return Err(WasmError::statistics(
+                ErrorCode::InvalidArgument,
+                "Histogram edges must be strictly increasing",
+            ));
+        }
+
+        let last = edges.len() - 1;
+        let mut counts = vec![0u64; last];
+        let mut outside = 0;
+        for &value in &self.data {
+            if !value.is_finite() || value < edges[0] || value > edges[last] {
+                outside += 1;
+            } else {
+                // Number of edges <= value, minus one, is the bin index
+                let bin = edges.partition_point(|&edge| edge <= value) - 1;
+                counts[bin.min(last - 1)] += 1;
+            }
+        }
+
+        let total: u64 = counts.iter().sum();
+        let proportions = normalize.then(|| {
+            counts
+                .iter()
+                .map(|&count| {
+                    if total == 0 {
+                        0.0
+                    } else {
+                        count as f64 / total as f64
+                    }
+                })
+                .collect()
+        });
+
+        Ok(Histogram {
+            edges,
+            counts,
+            proportions,
+            total,
+            outside,
+        })
+    }
+}
+
+/// All `Statistics` metrics, serialized to JS by `Statistics::summary()`.
+/// Metrics that are undefined for the data (e.g. anything on an empty set)
+/// are `None` / `undefined`.
+#[derive(Serialize, Deserialize, Tsify, Debug, Clone, PartialEq)]
+pub struct StatisticsSummary {
+    pub count: usize,
+    pub mean: Option<f64>,
+    pub median: Option<f64>,
+    pub mode: Option<f64>,
+    pub variance: Option<f64>,
+    pub std_dev: Option<f64>,
+    pub skewness: Option<f64>,
+    pub kurtosis: Option<f64>,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    pub p25: Option<f64>,
+    pub p75: Option<f64>,
+    pub p90: Option<f64>,
+    pub p95: Option<f64>,
+    pub p99: Option<f64>,
+}
+
+/// Bin boundaries and counts, shaped for charting libraries
+#[derive(Serialize, Deserialize, Tsify, Debug, Clone, PartialEq)]
+pub struct Histogram {
+    /// `counts.len() + 1` bin boundaries
+    pub edges: Vec<f64>,
+    pub counts: Vec<u64>,
+    /// Each bin's share of `total` (only when normalization was requested)
+    pub proportions: Option<Vec<f64>>,
+    /// Values that fell into a bin
+    pub total: u64,
+    /// Values outside the edges, plus any NaN or infinite values
+    pub outside: u64,
 }
 
 /// Image processing utilities
@@ -188,9 +576,9 @@ impl ImageProcessor {
     /// Adjust brightness
     pub fn brightness(pixels: &mut [u8], factor: f32) {
         for chunk in pixels.chunks_mut(4) {
-            chunk[0] = ((chunk[0] as f32 * factor).min(255.0).max(0.0)) as u8;
-            chunk[1] = ((chunk[1] as f32 * factor).min(255.0).max(0.0)) as u8;
-            chunk[2] = ((chunk[2] as f32 * factor).min(255.0).max(0.0)) as u8;
+            chunk[0] = ((chunk[0] as f32 * factor).clamp(0.0, 255.0)) as u8;
+            chunk[1] = ((chunk[1] as f32 * factor).clamp(0.0, 255.0)) as u8;
+            chunk[2] = ((chunk[2] as f32 * factor).clamp(0.0, 255.0)) as u8;
         }
     }
 }
@@ -201,12 +589,89 @@ mod tests {
 
     #[test]
     fn test_user_creation() {
-        let user = User::new(1, "Alice".to_string(), "alice@example.com".to_string());
+        let user = User::try_new(
+            1,
+            "Alice".to_string(),
+            "alice@example.com".to_string(),
+            Role::Member,
+        )
+        .unwrap();
         assert_eq!(user.id, 1);
         assert_eq!(user.name(), "Alice");
         assert!(user.active());
     }
 
+    #[test]
+    fn test_user_validation() {
+        let user = |name: &str, email: &str| {
+            User::try_new(1, name.to_string(), email.to_string(), Role::Guest)
+        };
+        assert!(user("Alice", "alice@example.com").is_ok());
+        assert!(user("  ", "alice@example.com").is_err());
+        assert!(user(&"x".repeat(MAX_NAME_LENGTH + 1), "alice@example.com").is_err());
+
+        for email in [
+            "alice",
+            "@example.com",
+            "alice@",
+            "alice@example",
+            "alice@.com",
+            "alice@example.",
+            "a@b@example.com",
+            "alice @example.com",
+        ] {
+            assert!(
+                user("Alice", email).is_err(),
+                "{} should be rejected",
+                email
+            );
+        }
+    }
+
+    #[test]
+    fn test_user_data_round_trip() {
+        let mut user = User::try_new(
+            7,
+            "Alice".to_string(),
+            "alice@example.com".to_string(),
+            Role::Admin,
+        )
+        .unwrap();
+        user.deactivate();
+
+        let data = UserData::from(&user);
+        let json = serde_json::to_value(&data).unwrap();
+        assert_eq!(json["role"], "Admin");
+        assert_eq!(json["active"], false);
+
+        let back = User::try_from(data.clone()).unwrap();
+        assert_eq!(UserData::from(&back), data);
+
+        let invalid = UserData {
+            name: " ".to_string(),
+            ..data
+        };
+        assert!(User::try_from(invalid).is_err());
+    }
+
+    #[test]
+    fn test_user_update_keeps_valid_state() {
+        let mut user = User::try_new(
+            1,
+            "Alice".to_string(),
+            "alice@example.com".to_string(),
+            Role::Member,
+        )
+        .unwrap();
+        assert!(user.update_email("not-an-email".to_string()).is_err());
+        assert_eq!(user.email(), "alice@example.com");
+        assert!(user.update_name("".to_string()).is_err());
+        assert_eq!(user.name(), "Alice");
+
+        user.update_name("Alicia".to_string()).unwrap();
+        assert_eq!(user.name(), "Alicia");
+    }
+
     #[test]
     fn test_statistics_mean() {
         let mut stats = Statistics::new();
@@ -220,6 +685,172 @@ mod tests {
         stats.add_many(vec![1.0, 2.0, 3.0, 4.0, 5.0]);
         assert_eq!(stats.median(), Some(3.0));
     }
+
+    #[test]
+    fn test_statistics_median_even() {
+        let mut stats = Statistics::new();
+        stats.add_many(vec![4.0, 1.0, 3.0, 2.0]);
+        assert_eq!(stats.median(), Some(2.5));
+    }
+
+    #[test]
+    fn test_statistics_percentile() {
+        let mut stats = Statistics::new();
+        stats.add_many((1..=11).map(f64::from).collect());
+        assert_eq!(stats.percentile(0.0), Some(1.0));
+        assert_eq!(stats.percentile(25.0), Some(3.5));
+        assert_eq!(stats.percentile(100.0), Some(11.0));
+        assert_eq!(stats.percentile(101.0), None);
+    }
+
+    #[test]
+    fn test_statistics_mode() {
+        let mut stats = Statistics::new();
+        stats.add_many(vec![3.0, 1.0, 3.0, 2.0, 1.0]);
+        assert_eq!(stats.mode(), Some(1.0));
+        assert_eq!(Statistics::new().mode(), None);
+    }
+
+    #[test]
+    fn test_statistics_moments() {
+        let mut stats = Statistics::new();
+        stats.add_many(vec![2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0]);
+        assert_eq!(stats.variance(), Some(4.0));
+        assert_eq!(stats.std_dev(), Some(2.0));
+        // Third central moment is 5.25, fourth is 44.5
+        assert!((stats.skewness().unwrap() - 5.25 / 8.0).abs() < 1e-12);
+        assert!((stats.kurtosis().unwrap() - (44.5 / 16.0 - 3.0)).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_statistics_moments_constant_data() {
+        let mut stats = Statistics::new();
+        stats.add_many(vec![5.0, 5.0, 5.0]);
+        assert_eq!(stats.variance(), Some(0.0));
+        assert_eq!(stats.skewness(), None);
+        assert_eq!(stats.kurtosis(), None);
+    }
+
+    #[test]
+    fn test_statistics_summary() {
+        let mut stats = Statistics::new();
+        stats.add_many(vec![1.0, 2.0, 3.0, 4.0, 5.0]);
+        let summary = stats.to_summary();
+        assert_eq!(summary.count, 5);
+        assert_eq!(summary.mean, Some(3.0));
+        assert_eq!(summary.p25, Some(2.0));
+        assert_eq!(summary.skewness, Some(0.0));
+
+        let empty = Statistics::new().to_summary();
+        assert_eq!(empty.count, 0);
+        assert_eq!(empty.mean, None);
+        assert_eq!(empty.p99, None);
+    }
+
+    #[test]
+    fn test_statistics_nan() {
+        let mut stats = Statistics::new();
+        stats.add_many(vec![3.0, f64::NAN, 1.0, -f64::NAN, 2.0, 1.0]);
+        assert_eq!(stats.count(), 6);
+        assert_eq!(stats.min(), Some(1.0));
+        assert_eq!(stats.max(), Some(3.0));
+        assert_eq!(stats.median(), Some(1.5));
+        assert_eq!(stats.percentile(100.0), Some(3.0));
+        assert_eq!(stats.mode(), Some(1.0));
+        assert!(stats.mean().unwrap().is_nan());
+
+        let summary = stats.to_summary();
+        assert_eq!(summary.p25, Some(1.0));
+        assert!(summary.variance.unwrap().is_nan());
+
+        // Nothing to order at all
+        let mut stats = Statistics::new();
+        stats.add_many(vec![f64::NAN, f64::NAN]);
+        assert_eq!(stats.median(), None);
+        assert_eq!(stats.mode(), None);
+        assert_eq!(stats.min(), None);
+        assert_eq!(stats.to_summary().p99, None);
+    }
+
+    #[test]
+    fn test_statistics_histogram() {
+        let mut stats = Statistics::new();
+        stats.add_many(vec![0.0, 1.0, 2.0, 2.5, 3.0, 4.0]);
+
+        let histogram = stats.to_histogram(4, true).unwrap();
+        assert_eq!(histogram.edges, vec![0.0, 1.0, 2.0, 3.0, 4.0]);
+        // The max lands in the last, closed bin
+        assert_eq!(histogram.counts, vec![1, 1, 2, 2]);
+        assert_eq!(histogram.total, 6);
+        let proportions = histogram.proportions.unwrap();
+        assert!((proportions.iter().sum::<f64>() - 1.0).abs() < 1e-12);
+        assert_eq!(proportions[2], 2.0 / 6.0);
+
+        let error = stats.to_histogram(0, false).unwrap_err();
+        assert_eq!(error.code(), ErrorCode::InvalidArgument);
+        assert_eq!(error.kind(), ErrorKind::Statistics);
+        let error = Statistics::new().to_histogram(3, false).unwrap_err();
+        assert_eq!(error.code(), ErrorCode::EmptyData);
+    }
+
+    #[test]
+    fn test_statistics_histogram_constant_data() {
+        let mut stats = Statistics::new();
+        stats.add_many(vec![7.0, 7.0, 7.0]);
+        let histogram = stats.to_histogram(2, false).unwrap();
+        assert_eq!(histogram.edges, vec![6.5, 7.0, 7.5]);
+        assert_eq!(histogram.counts, vec![0, 3]);
+        assert_eq!(histogram.proportions, None);
+    }
+
+    #[test]
+    fn test_statistics_histogram_with_edges() {
+        let mut stats = Statistics::new();
+        stats.add_many(vec![-5.0, 0.0, 5.0, 10.0, 15.0, 100.0]);
+
+        let histogram = stats
+            .to_histogram_with_edges(vec![0.0, 10.0, 20.0], false)
+            .unwrap();
+        assert_eq!(histogram.counts, vec![2, 2]);
+        assert_eq!(histogram.outside, 2);
+
+        assert!(stats.to_histogram_with_edges(vec![1.0], false).is_err());
+        assert!(stats
+            .to_histogram_with_edges(vec![0.0, 5.0, 5.0], false)
+            .is_err());
+    }
+
+    #[test]
+    fn test_statistics_histogram_non_finite() {
+        let mut stats = Statistics::new();
+        stats.add_many(vec![
+            f64::NEG_INFINITY,
+            0.0,
+            f64::NAN,
+            2.0,
+            4.0,
+            f64::INFINITY,
+        ]);
+
+        // The range comes from the finite values; the rest are counted as outside
+        let histogram = stats.to_histogram(2, false).unwrap();
+        assert_eq!(histogram.edges, vec![0.0, 2.0, 4.0]);
+        assert_eq!(histogram.counts, vec![1, 2]);
+        assert_eq!(histogram.total, 3);
+        assert_eq!(histogram.outside, 3);
+
+        // Even edges that reach infinity don't take them in
+        let histogram = stats
+            .to_histogram_with_edges(vec![f64::NEG_INFINITY, 1.0, f64::INFINITY], false)
+            .unwrap();
+        assert_eq!(histogram.counts, vec![1, 2]);
+        assert_eq!(histogram.outside, 3);
+
+        let mut stats = Statistics::new();
+        stats.add_many(vec![f64::NAN, f64::INFINITY]);
+        let error = stats.to_histogram(2, false).unwrap_err();
+        assert_eq!(error.code(), ErrorCode::EmptyData);
+    }
 }
 
 #[cfg(test)]
@@ -231,10 +862,19 @@ mod wasm_tests {
 
     #[wasm_bindgen_test]
     fn test_user_in_browser() {
-        let mut user = User::new(1, "Bob".to_string(), "bob@test.com".to_string());
+        let mut user = User::new(1, "Bob".to_string(), "bob@test.com".to_string()).unwrap();
         assert_eq!(user.name(), "Bob");
-        user.set_name("Bobby".to_string());
+        user.set_name("Bobby".to_string()).unwrap();
         assert_eq!(user.name(), "Bobby");
+        assert!(user.set_email("bobby".to_string()).is_err());
+        assert!(User::new(2, "".to_string(), "x@test.com".to_string()).is_err());
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_api_client_rejects_on_network_error() {
+        // Nothing listens on port 1, so fetch itself fails
+        let client = ApiClient::new("http://127.0.0.1:1".to_string());
+        assert!(client.get_user(1).await.is_err());
     }
 
     #[wasm_bindgen_test]
@@ -243,4 +883,15 @@ mod wasm_tests {
         stats.add_many(vec![10.0, 20.0, 30.0]);
         assert_eq!(stats.mean(), Some(20.0));
     }
+
+    #[wasm_bindgen_test]
+    fn test_statistics_error_in_browser() {
+        use wasm_bindgen::JsCast;
+
+        let error = Statistics::new().histogram(3, None).unwrap_err();
+        let error: js_sys::Error = error.dyn_into().unwrap();
+        assert_eq!(error.name(), "StatisticsError");
+        let code = js_sys::Reflect::get(&error, &"code".into()).unwrap();
+        assert_eq!(code.as_string().as_deref(), Some("EMPTY_DATA"));
+    }
 }
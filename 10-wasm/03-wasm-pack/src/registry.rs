@@ -0,0 +1,305 @@
+//! A collection of `User`s owned by WASM.
+//!
+//! JS gets copies out (`get`, `search`, ...): a `User` handle returned from
+//! the registry is a separate object, so changes to it have to be written
+//! back with `update`.
+
+use serde::{Deserialize, Serialize};
+use tsify::{Ts, Tsify};
+use wasm_bindgen::prelude::*;
+
+use crate::interop::{from_ts_all, to_ts, to_ts_all};
+use crate::{User, UserData};
+
+/// One page of users, returned to JS by `UserRegistry::page()`
+#[derive(Serialize, Deserialize, Tsify, Debug, Clone)]
+pub struct UserPage {
+    pub users: Vec<UserData>,
+    /// Zero-based page index
+    pub page: usize,
+    pub page_size: usize,
+    /// Users in the whole registry
+    pub total: usize,
+    pub total_pages: usize,
+}
+
+/// Users kept in insertion order, unique by id and by email
+#[wasm_bindgen]
+#[derive(Default)]
+pub struct UserRegistry {
+    users: Vec<User>,
+}
+
+#[wasm_bindgen]
+impl UserRegistry {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> UserRegistry {
+        UserRegistry::default()
+    }
+
+    /// Rebuild a registry from the output of `export()`
+    pub fn import(users: Vec<Ts<UserData>>) -> Result<UserRegistry, JsValue> {
+        UserRegistry::from_data(from_ts_all(&users)?).map_err(|e| JsValue::from_str(&e))
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn len(&self) -> usize {
+        self.users.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.users.is_empty()
+    }
+
+    /// Add a copy of `user`. Fails if its id or email is already taken.
+    pub fn add(&mut self, user: &User) -> Result<(), JsValue> {
+        self.insert(user.clone()).map_err(|e| JsValue::from_str(&e))
+    }
+
+    /// Replace the stored user with the same id
+    pub fn update(&mut self, user: &User) -> Result<(), JsValue> {
+        self.replace(user.clone())
+            .map_err(|e| JsValue::from_str(&e))
+    }
+
+    /// Remove and return the user with `id`
+    pub fn remove(&mut self, id: u32) -> Option<User> {
+        let index = self.users.iter().position(|user| user.id == id)?;
+        Some(self.users.remove(index))
+    }
+
+    pub fn get(&self, id: u32) -> Option<User> {
+        self.users.iter().find(|user| user.id == id).cloned()
+    }
+
+    /// Look up a user by email, ignoring case
+    pub fn find_by_email(&self, email: &str) -> Option<User> {
+        self.position_by_email(email).map(|i| self.users[i].clone())
+    }
+
+    /// Users whose name or email contains `query`, ignoring case
+    pub fn search(&self, query: &str) -> Vec<User> {
+        self.matching(query).into_iter().cloned().collect()
+    }
+
+    /// One page of users (zero-based `page`) as a plain JS object
+    pub fn page(&self, page: usize, page_size: usize) -> Result<Ts<UserPage>, JsValue> {
+        let page = self
+            .to_page(page, page_size)
+            .map_err(|e| JsValue::from_str(&e))?;
+        to_ts(&page)
+    }
+
+    /// All users as an array of plain JS objects, for storage or `import()`
+    pub fn export(&self) -> Result<Vec<Ts<UserData>>, JsValue> {
+        to_ts_all(&self.to_data())
+    }
+}
+
+impl UserRegistry {
+    /// Build a registry from plain user objects, validating each one
+    pub fn from_data(users: Vec<UserData>) -> Result<UserRegistry, String> {
+        let mut registry = UserRegistry::new();
+        for data in users {
+            registry.insert(User::try_from(data)?)?;
+        }
+        Ok(registry)
+    }
+
+    /// Every user as plain data, in insertion order
+    pub fn to_data(&self) -> Vec<UserData> {
+        self.users.iter().map(UserData::from).collect()
+    }
+
+    /// Add a user, rejecting invalid users and duplicate ids and emails
+    pub fn insert(&mut self, user: User) -> Result<(), String> {
+        user.validate()?;
+        if self.users.iter().any(|existing| existing.id == user.id) {
+            return Err(format!("A user with id {} already exists", user.id));
+        }
+        if self.position_by_email(&user.email()).is_some() {
+            return Err(format!("Email '{}' is already registered", user.email()));
+        }
+        self.users.push(user);
+        Ok(())
+    }
+
+    /// Replace the user with `user.id`, keeping its position
+    pub fn replace(&mut self, user: User) -> Result<(), String> {
+        let index = self
+            .users
+            .iter()
+            .position(|existing| existing.id == user.id)
+            .ok_or_else(|| format!("No user with id {}", user.id))?;
+        if self
+            .position_by_email(&user.email())
+            .is_some_and(|other| other != index)
+        {
+            return Err(format!("Email '{}' is already registered", user.email()));
+        }
+        self.users[index] = user;
+        Ok(())
+    }
+
+    pub fn users(&self) -> &[User] {
+        &self.users
+    }
+
+    /// Users whose name or email contains `query`, ignoring case. An empty
+    /// query matches everyone.
+    pub fn matching(&self, query: &str) -> Vec<&User> {
+        let query = query.to_lowercase();
+        self.users
+            .iter()
+            .filter(|user| {
+                user.name().to_lowercase().contains(&query)
+                    || user.email().to_lowercase().contains(&query)
+            })
+            .collect()
+    }
+
+    pub fn to_page(&self, page: usize, page_size: usize) -> Result<UserPage, String> {
+        if page_size == 0 {
+            return Err("Page size must be at least 1".to_string());
+        }
+        let total = self.users.len();
+        let users = self
+            .users
+            .iter()
+            .skip(page.saturating_mul(page_size))
+            .take(page_size)
+            .map(UserData::from)
+            .collect();
+        Ok(UserPage {
+            users,
+            page,
+            page_size,
+            total,
+            total_pages: total.div_ceil(page_size),
+        })
+    }
+
+    fn position_by_email(&self, email: &str) -> Option<usize> {
+        self.users
+            .iter()
+            .position(|user| user.email().eq_ignore_ascii_case(email))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Role;
+
+    fn member(id: u32, name: &str, email: &str) -> User {
+        User::try_new(id, name.to_string(), email.to_string(), Role::Member).unwrap()
+    }
+
+    fn registry() -> UserRegistry {
+        let mut registry = UserRegistry::new();
+        for (id, name, email) in [
+            (1, "Alice", "alice@example.com"),
+            (2, "Bob", "bob@example.com"),
+            (3, "Carol", "carol@test.org"),
+            (4, "Alicia", "alicia@test.org"),
+            (5, "Dave", "dave@example.com"),
+        ] {
+            registry.insert(member(id, name, email)).unwrap();
+        }
+        registry
+    }
+
+    #[test]
+    fn test_registry_rejects_duplicates() {
+        let mut registry = registry();
+        let same_id = member(1, "Other", "other@example.com");
+        let same_email = member(9, "Other", "ALICE@example.com");
+        assert!(registry.insert(same_id).is_err());
+        assert!(registry.insert(same_email).is_err());
+
+        // Users deserialized from JS skip the constructor, so insert validates
+        let invalid: User = serde_json::from_value(serde_json::json!({
+            "id": 10, "name": "Eve", "email": "eve", "active": true
+        }))
+        .unwrap();
+        assert!(registry.insert(invalid).is_err());
+        assert_eq!(registry.len(), 5);
+    }
+
+    #[test]
+    fn test_registry_lookup_and_remove() {
+        let mut registry = registry();
+        assert_eq!(registry.find_by_email("Bob@Example.com").unwrap().id, 2);
+        assert_eq!(registry.get(3).unwrap().name(), "Carol");
+
+        assert_eq!(registry.remove(2).unwrap().name(), "Bob");
+        assert!(registry.remove(2).is_none());
+        assert!(registry.find_by_email("bob@example.com").is_none());
+        assert_eq!(registry.len(), 4);
+    }
+
+    #[test]
+    fn test_registry_replace() {
+        let mut registry = registry();
+        let mut carol = registry.get(3).unwrap();
+        carol.update_name("Caroline".to_string()).unwrap();
+        carol.deactivate();
+        registry.replace(carol).unwrap();
+        assert_eq!(registry.users()[2].name(), "Caroline");
+        assert!(!registry.users()[2].active());
+
+        let mut taken = registry.get(3).unwrap();
+        taken.update_email("alice@example.com".to_string()).unwrap();
+        assert!(registry.replace(taken).is_err());
+        let missing = member(42, "X", "x@example.com");
+        assert!(registry.replace(missing).is_err());
+    }
+
+    #[test]
+    fn test_registry_search() {
+        let registry = registry();
+        let ids = |query| -> Vec<u32> { registry.matching(query).iter().map(|u| u.id).collect() };
+        assert_eq!(ids("ali"), vec![1, 4]);
+        assert_eq!(ids("TEST.ORG"), vec![3, 4]);
+        assert_eq!(ids("zzz"), Vec::<u32>::new());
+        assert_eq!(ids("").len(), 5);
+    }
+
+    #[test]
+    fn test_registry_pagination() {
+        let registry = registry();
+        let first = registry.to_page(0, 2).unwrap();
+        assert_eq!(first.users.iter().map(|u| u.id).collect::<Vec<_>>(), [1, 2]);
+        assert_eq!((first.total, first.total_pages), (5, 3));
+
+        let last = registry.to_page(2, 2).unwrap();
+        assert_eq!(last.users.iter().map(|u| u.id).collect::<Vec<_>>(), [5]);
+        assert!(registry.to_page(3, 2).unwrap().users.is_empty());
+        assert!(registry.to_page(0, 0).is_err());
+    }
+
+    #[test]
+    fn test_registry_export_round_trip() {
+        let registry = registry();
+        let mut exported = registry.to_data();
+        assert_eq!(exported.len(), 5);
+        assert_eq!(exported[0].role, Role::Member);
+
+        let restored = UserRegistry::from_data(exported.clone()).unwrap();
+        assert_eq!(restored.to_data(), exported);
+
+        // Plain objects skip the constructor, so importing validates them
+        exported[4].email = "dave".to_string();
+        assert!(UserRegistry::from_data(exported).is_err());
+    }
+
+    #[test]
+    fn test_registry_export_shape() {
+        let page = registry().to_page(0, 1).unwrap();
+        let json = serde_json::to_value(&page).unwrap();
+        assert_eq!(json["users"][0]["email"], "alice@example.com");
+        assert_eq!(json["users"][0]["active"], true);
+        assert_eq!(json["users"][0]["role"], "Member");
+        assert_eq!(json["total_pages"], 5);
+    }
+}
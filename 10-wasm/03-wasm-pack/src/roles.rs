@@ -0,0 +1,110 @@
+//! Role-based permissions for `User`.
+//!
+//! wasm-bindgen exports C-style enums as plain JS objects of numbers
+//! (`Role.Admin === 0`), and such enums can't carry exported methods, so
+//! the checks are exposed on `User` and as the free function `role_can`.
+
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::User;
+
+#[wasm_bindgen]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Role {
+    /// Everything, including managing other users
+    Admin,
+    /// Read and write their own content
+    #[default]
+    Member,
+    /// Read only
+    Guest,
+}
+
+#[wasm_bindgen]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Permission {
+    Read,
+    Write,
+    Delete,
+    ManageUsers,
+}
+
+impl Role {
+    pub fn can(self, permission: Permission) -> bool {
+        match self {
+            Role::Admin => true,
+            Role::Member => matches!(permission, Permission::Read | Permission::Write),
+            Role::Guest => permission == Permission::Read,
+        }
+    }
+}
+
+/// Whether `role` grants `permission`
+#[wasm_bindgen]
+pub fn role_can(role: Role, permission: Permission) -> bool {
+    role.can(permission)
+}
+
+/// Roles and permissions
+#[wasm_bindgen]
+impl User {
+    #[wasm_bindgen(getter)]
+    pub fn role(&self) -> Role {
+        self.role
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_role(&mut self, role: Role) {
+        self.role = role;
+    }
+
+    /// Inactive users have no permissions, whatever their role
+    pub fn can(&self, permission: Permission) -> bool {
+        self.active() && self.role.can(permission)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_role_permissions() {
+        use Permission::*;
+        let all = [Read, Write, Delete, ManageUsers];
+        let granted =
+            |role: Role| -> Vec<Permission> { all.into_iter().filter(|&p| role.can(p)).collect() };
+        assert_eq!(granted(Role::Admin), all);
+        assert_eq!(granted(Role::Member), [Read, Write]);
+        assert_eq!(granted(Role::Guest), [Read]);
+    }
+
+    #[test]
+    fn test_user_permissions() {
+        let mut user = User::try_new(
+            1,
+            "Alice".to_string(),
+            "alice@example.com".to_string(),
+            Role::Guest,
+        )
+        .unwrap();
+        assert!(!user.can(Permission::Write));
+
+        user.set_role(Role::Admin);
+        assert!(user.can(Permission::ManageUsers));
+
+        user.deactivate();
+        assert!(!user.can(Permission::Read));
+    }
+
+    #[test]
+    fn test_role_defaults_when_missing() {
+        // Users exported before roles existed deserialize as members
+        let user: User = serde_json::from_str(
+            r#"{"id": 1, "name": "Alice", "email": "alice@example.com", "active": true}"#,
+        )
+        .unwrap();
+        assert_eq!(user.role(), Role::Member);
+    }
+}
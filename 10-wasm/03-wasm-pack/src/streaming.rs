@@ -0,0 +1,517 @@
+//! Online statistics for streamed data.
+//!
+//! `Statistics` keeps every value and re-sorts on each call, which is fine for
+//! a few thousand points but not for millions. `OnlineStatistics` updates its
+//! state in O(1) per `add()` and never stores the values:
+//!
+//! - mean, variance, skewness and kurtosis via Welford / Pébay moment updates
+//! - quantiles via the P² algorithm (five markers per tracked quantile)
+//! - min/max over everything, plus min/max over a sliding window of the last
+//!   `window` values (monotonic deques, bounded by the window size)
+//!
+//! Non-finite values (NaN, ±∞) are skipped by `add()`: a single one would
+//! poison the moments and break the ordering the quantile markers rely on.
+
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use tsify::{Ts, Tsify};
+use wasm_bindgen::prelude::*;
+
+use crate::interop::to_ts;
+
+/// Quantiles tracked when none are given explicitly
+const DEFAULT_QUANTILES: &[f64] = &[0.5, 0.9, 0.95, 0.99];
+
+/// Running mean and central moments (Pébay's update formulas, which reduce
+/// to Welford's algorithm for the mean and variance)
+#[derive(Debug, Clone, Default)]
+struct Moments {
+    count: u64,
+    mean: f64,
+    m2: f64,
+    m3: f64,
+    m4: f64,
+}
+
+impl Moments {
+    fn add(&mut self, x: f64) {
+        let n1 = self.count as f64;
+        self.count += 1;
+        let n = self.count as f64;
+
+        let delta = x - self.mean;
+        let delta_n = delta / n;
+        let delta_n2 = delta_n * delta_n;
+        let term1 = delta * delta_n * n1;
+
+        self.mean += delta_n;
+        // Order matters: each update uses the previous lower moments
+        self.m4 += term1 * delta_n2 * (n * n - 3.0 * n + 3.0) + 6.0 * delta_n2 * self.m2
+            - 4.0 * delta_n * self.m3;
+        self.m3 += term1 * delta_n * (n - 2.0) - 3.0 * delta_n * self.m2;
+        self.m2 += term1;
+    }
+
+    fn mean(&self) -> Option<f64> {
+        (self.count > 0).then_some(self.mean)
+    }
+
+    fn variance(&self) -> Option<f64> {
+        (self.count > 0).then(|| self.m2 / self.count as f64)
+    }
+
+    fn skewness(&self) -> Option<f64> {
+        (self.m2 > 0.0).then(|| (self.count as f64).sqrt() * self.m3 / self.m2.powf(1.5))
+    }
+
+    fn kurtosis(&self) -> Option<f64> {
+        (self.m2 > 0.0).then(|| self.count as f64 * self.m4 / (self.m2 * self.m2) - 3.0)
+    }
+}
+
+/// P² estimator for a single quantile (Jain & Chlamtac, 1985).
+///
+/// Keeps five markers whose heights approximate the min, p/2, p, (1+p)/2 and
+/// max quantiles, nudging them with a parabolic fit as values arrive.
+#[derive(Debug, Clone)]
+struct P2Quantile {
+    p: f64,
+    count: usize,
+    heights: [f64; 5],
+    positions: [f64; 5],
+    desired: [f64; 5],
+    increments: [f64; 5],
+}
+
+impl P2Quantile {
+    fn new(p: f64) -> Self {
+        Self {
+            p,
+            count: 0,
+            heights: [0.0; 5],
+            positions: [1.0, 2.0, 3.0, 4.0, 5.0],
+            desired: [1.0, 1.0 + 2.0 * p, 1.0 + 4.0 * p, 3.0 + 2.0 * p, 5.0],
+            increments: [0.0, p / 2.0, p, (1.0 + p) / 2.0, 1.0],
+        }
+    }
+
+    fn add(&mut self, x: f64) {
+        // The first five values initialize the markers
+        if self.count < 5 {
+            self.heights[self.count] = x;
+            self.count += 1;
+            if self.count == 5 {
+                self.heights.sort_by(f64::total_cmp);
+            }
+            return;
+        }
+        self.count += 1;
+
+        // Find the cell the value falls into, extending the extremes if needed
+        let k = if x < self.heights[0] {
+            self.heights[0] = x;
+            0
+        } else if x >= self.heights[4] {
+            self.heights[4] = x;
+            3
+        } else {
+            (0..4)
+                .find(|&i| x < self.heights[i + 1])
+                .expect("x is below the max marker")
+        };
+
+        for position in &mut self.positions[k + 1..] {
+            *position += 1.0;
+        }
+        for (desired, increment) in self.desired.iter_mut().zip(self.increments) {
+            *desired += increment;
+        }
+
+        // Move the three middle markers towards their desired positions
+        for i in 1..4 {
+            let d = self.desired[i] - self.positions[i];
+            if (d >= 1.0 && self.positions[i + 1] - self.positions[i] > 1.0)
+                || (d <= -1.0 && self.positions[i - 1] - self.positions[i] < -1.0)
+            {
+                let d = d.signum();
+                let parabolic = self.parabolic(i, d);
+                self.heights[i] =
+                    if self.heights[i - 1] < parabolic && parabolic < self.heights[i + 1] {
+                        parabolic
+                    } else {
+                        self.linear(i, d)
+                    };
+                self.positions[i] += d;
+            }
+        }
+    }
+
+    fn parabolic(&self, i: usize, d: f64) -> f64 {
+        let (q, n) = (&self.heights, &self.positions);
+        q[i] + d / (n[i + 1] - n[i - 1])
+            * ((n[i] - n[i - 1] + d) * (q[i + 1] - q[i]) / (n[i + 1] - n[i])
+                + (n[i + 1] - n[i] - d) * (q[i] - q[i - 1]) / (n[i] - n[i - 1]))
+    }
+
+    fn linear(&self, i: usize, d: f64) -> f64 {
+        let j = if d > 0.0 { i + 1 } else { i - 1 };
+        self.heights[i]
+            + d * (self.heights[j] - self.heights[i]) / (self.positions[j] - self.positions[i])
+    }
+
+    fn estimate(&self) -> Option<f64> {
+        match self.count {
+            0 => None,
+            // Too few values for the markers: interpolate over the values seen so far
+            1..=4 => {
+                let mut seen = self.heights[..self.count].to_vec();
+                seen.sort_by(f64::total_cmp);
+                let rank = self.p * (seen.len() - 1) as f64;
+                let lower = rank.floor() as usize;
+                let upper = rank.ceil() as usize;
+                Some(seen[lower] + (seen[upper] - seen[lower]) * (rank - lower as f64))
+            }
+            _ => Some(self.heights[2]),
+        }
+    }
+}
+
+/// Sliding-window min and max using monotonic deques.
+///
+/// Each deque holds `(index, value)` pairs that could still become the
+/// window's min (or max); every value is pushed and popped at most once, so
+/// updates are amortized O(1).
+#[derive(Debug, Clone)]
+struct RollingExtremes {
+    window: u64,
+    next_index: u64,
+    min: VecDeque<(u64, f64)>,
+    max: VecDeque<(u64, f64)>,
+}
+
+impl RollingExtremes {
+    fn new(window: usize) -> Self {
+        Self {
+            window: window.max(1) as u64,
+            next_index: 0,
+            min: VecDeque::new(),
+            max: VecDeque::new(),
+        }
+    }
+
+    fn add(&mut self, x: f64) {
+        let index = self.next_index;
+        self.next_index += 1;
+
+        while self.min.back().is_some_and(|&(_, v)| v >= x) {
+            self.min.pop_back();
+        }
+        self.min.push_back((index, x));
+        while self.max.back().is_some_and(|&(_, v)| v <= x) {
+            self.max.pop_back();
+        }
+        self.max.push_back((index, x));
+
+        // Drop values that slid out of the window
+        let oldest = self.next_index.saturating_sub(self.window);
+        while self.min.front().is_some_and(|&(i, _)| i < oldest) {
+            self.min.pop_front();
+        }
+        while self.max.front().is_some_and(|&(i, _)| i < oldest) {
+            self.max.pop_front();
+        }
+    }
+
+    fn min(&self) -> Option<f64> {
+        self.min.front().map(|&(_, v)| v)
+    }
+
+    fn max(&self) -> Option<f64> {
+        self.max.front().map(|&(_, v)| v)
+    }
+}
+
+/// Statistics over a stream of values in constant memory
+#[wasm_bindgen]
+#[derive(Debug, Clone)]
+pub struct OnlineStatistics {
+    moments: Moments,
+    min: Option<f64>,
+    max: Option<f64>,
+    quantiles: Vec<P2Quantile>,
+    rolling: RollingExtremes,
+}
+
+#[wasm_bindgen]
+impl OnlineStatistics {
+    /// Track the default quantiles (p50, p90, p95, p99) and min/max over the
+    /// last `window` values
+    #[wasm_bindgen(constructor)]
+    pub fn new(window: usize) -> OnlineStatistics {
+        Self::with_quantiles(window, DEFAULT_QUANTILES.to_vec())
+    }
+
+    /// Track the given quantiles (each between 0 and 1) instead of the defaults
+    pub fn with_quantiles(window: usize, quantiles: Vec<f64>) -> OnlineStatistics {
+        OnlineStatistics {
+            moments: Moments::default(),
+            min: None,
+            max: None,
+            quantiles: quantiles
+                .into_iter()
+                .filter(|p| (0.0..=1.0).contains(p))
+                .map(P2Quantile::new)
+                .collect(),
+            rolling: RollingExtremes::new(window),
+        }
+    }
+
+    /// Add a value to the stream; NaN and infinite values are ignored
+    pub fn add(&mut self, value: f64) {
+        if !value.is_finite() {
+            return;
+        }
+        self.moments.add(value);
+        self.min = Some(self.min.map_or(value, |m| m.min(value)));
+        self.max = Some(self.max.map_or(value, |m| m.max(value)));
+        for quantile in &mut self.quantiles {
+            quantile.add(value);
+        }
+        self.rolling.add(value);
+    }
+
+    /// Add multiple values from a JavaScript array
+    pub fn add_many(&mut self, values: Vec<f64>) {
+        for value in values {
+            self.add(value);
+        }
+    }
+
+    /// Get count of values seen
+    pub fn count(&self) -> u64 {
+        self.moments.count
+    }
+
+    pub fn mean(&self) -> Option<f64> {
+        self.moments.mean()
+    }
+
+    /// Population variance, matching `Statistics::variance`
+    pub fn variance(&self) -> Option<f64> {
+        self.moments.variance()
+    }
+
+    pub fn std_dev(&self) -> Option<f64> {
+        self.variance().map(f64::sqrt)
+    }
+
+    pub fn skewness(&self) -> Option<f64> {
+        self.moments.skewness()
+    }
+
+    /// Excess kurtosis, matching `Statistics::kurtosis`
+    pub fn kurtosis(&self) -> Option<f64> {
+        self.moments.kurtosis()
+    }
+
+    pub fn min(&self) -> Option<f64> {
+        self.min
+    }
+
+    pub fn max(&self) -> Option<f64> {
+        self.max
+    }
+
+    /// Minimum of the last `window` values
+    pub fn rolling_min(&self) -> Option<f64> {
+        self.rolling.min()
+    }
+
+    /// Maximum of the last `window` values
+    pub fn rolling_max(&self) -> Option<f64> {
+        self.rolling.max()
+    }
+
+    /// Estimated quantile `p` (0-1); only quantiles tracked since construction are available
+    pub fn quantile(&self, p: f64) -> Option<f64> {
+        self.quantiles
+            .iter()
+            .find(|q| q.p == p)
+            .and_then(P2Quantile::estimate)
+    }
+
+    /// Estimated median (requires p50 to be tracked, as it is by default)
+    pub fn median(&self) -> Option<f64> {
+        self.quantile(0.5)
+    }
+
+    /// Get all metrics at once as a plain JS object
+    pub fn summary(&self) -> Result<Ts<OnlineSummary>, JsValue> {
+        to_ts(&self.to_summary())
+    }
+}
+
+impl OnlineStatistics {
+    /// Collect every metric into an `OnlineSummary`
+    pub fn to_summary(&self) -> OnlineSummary {
+        OnlineSummary {
+            count: self.count(),
+            mean: self.mean(),
+            variance: self.variance(),
+            std_dev: self.std_dev(),
+            skewness: self.skewness(),
+            kurtosis: self.kurtosis(),
+            min: self.min(),
+            max: self.max(),
+            rolling_min: self.rolling_min(),
+            rolling_max: self.rolling_max(),
+            quantiles: self
+                .quantiles
+                .iter()
+                .filter_map(|q| q.estimate().map(|value| QuantileEstimate { p: q.p, value }))
+                .collect(),
+        }
+    }
+}
+
+/// One estimated quantile in an `OnlineSummary`
+#[derive(Serialize, Deserialize, Tsify, Debug, Clone, PartialEq)]
+pub struct QuantileEstimate {
+    pub p: f64,
+    pub value: f64,
+}
+
+/// All `OnlineStatistics` metrics, serialized to JS by `OnlineStatistics::summary()`
+#[derive(Serialize, Deserialize, Tsify, Debug, Clone, PartialEq)]
+pub struct OnlineSummary {
+    pub count: u64,
+    pub mean: Option<f64>,
+    pub variance: Option<f64>,
+    pub std_dev: Option<f64>,
+    pub skewness: Option<f64>,
+    pub kurtosis: Option<f64>,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    pub rolling_min: Option<f64>,
+    pub rolling_max: Option<f64>,
+    pub quantiles: Vec<QuantileEstimate>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Statistics;
+
+    /// Deterministic pseudo-random values in 0..1 (xorshift)
+    fn values(count: usize) -> Vec<f64> {
+        let mut state = 0x2545_f491_4f6c_dd1d_u64;
+        (0..count)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                (state >> 11) as f64 / (1u64 << 53) as f64
+            })
+            .collect()
+    }
+
+    fn assert_close(a: Option<f64>, b: Option<f64>, tolerance: f64) {
+        let (a, b) = (a.unwrap(), b.unwrap());
+        assert!((a - b).abs() <= tolerance, "{} vs {}", a, b);
+    }
+
+    #[test]
+    fn test_online_moments_match_batch() {
+        // Skewed data so skewness and kurtosis are non-trivial
+        let data: Vec<f64> = values(1000).into_iter().map(|x| x * x * 10.0).collect();
+        let mut online = OnlineStatistics::new(10);
+        let mut batch = Statistics::new();
+        online.add_many(data.clone());
+        batch.add_many(data);
+
+        assert_eq!(online.count(), 1000);
+        assert_close(online.mean(), batch.mean(), 1e-9);
+        assert_close(online.variance(), batch.variance(), 1e-9);
+        assert_close(online.skewness(), batch.skewness(), 1e-9);
+        assert_close(online.kurtosis(), batch.kurtosis(), 1e-9);
+        assert_eq!(online.min(), batch.min());
+        assert_eq!(online.max(), batch.max());
+    }
+
+    #[test]
+    fn test_online_empty() {
+        let online = OnlineStatistics::new(10);
+        let summary = online.to_summary();
+        assert_eq!(summary.count, 0);
+        assert_eq!(summary.mean, None);
+        assert_eq!(summary.rolling_min, None);
+        assert!(summary.quantiles.is_empty());
+    }
+
+    #[test]
+    fn test_p2_quantiles_approximate_exact() {
+        let data = values(100_000);
+        let mut online = OnlineStatistics::new(10);
+        let mut batch = Statistics::new();
+        online.add_many(data.clone());
+        batch.add_many(data);
+
+        // Uniform data on 0..1: estimates should be within 1% of the exact values
+        assert_close(online.median(), batch.percentile(50.0), 0.01);
+        assert_close(online.quantile(0.9), batch.percentile(90.0), 0.01);
+        assert_close(online.quantile(0.99), batch.percentile(99.0), 0.01);
+        assert_eq!(online.quantile(0.42), None);
+    }
+
+    #[test]
+    fn test_p2_quantile_with_few_values_is_exact() {
+        let mut online = OnlineStatistics::with_quantiles(10, vec![0.5]);
+        online.add_many(vec![4.0, 1.0, 3.0]);
+        assert_eq!(online.median(), Some(3.0));
+        online.add(2.0);
+        assert_eq!(online.median(), Some(2.5));
+    }
+
+    #[test]
+    fn test_rolling_min_max() {
+        let mut online = OnlineStatistics::new(3);
+        let mut seen = Vec::new();
+        for value in [5.0, 1.0, 4.0, 2.0, 8.0, 3.0, 3.0, 0.5] {
+            online.add(value);
+            seen.push(value);
+            let window = &seen[seen.len().saturating_sub(3)..];
+            let min = window.iter().copied().fold(f64::INFINITY, f64::min);
+            let max = window.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+            assert_eq!(online.rolling_min(), Some(min));
+            assert_eq!(online.rolling_max(), Some(max));
+        }
+        assert_eq!(online.min(), Some(0.5));
+        assert_eq!(online.max(), Some(8.0));
+    }
+
+    #[test]
+    fn test_rolling_state_stays_bounded() {
+        let mut online = OnlineStatistics::new(100);
+        online.add_many(values(10_000));
+        assert!(online.rolling.min.len() <= 100);
+        assert!(online.rolling.max.len() <= 100);
+    }
+
+    #[test]
+    fn test_non_finite_values_are_skipped() {
+        let mut online = OnlineStatistics::with_quantiles(3, vec![0.5]);
+        // Before the P² markers are initialized...
+        online.add_many(vec![f64::NAN, 4.0, 1.0, f64::INFINITY, 3.0]);
+        assert_eq!(online.count(), 3);
+        assert_eq!(online.median(), Some(3.0));
+        // ...and after
+        online.add_many(vec![2.0, 5.0, f64::NAN, f64::NEG_INFINITY, 6.0]);
+        assert_eq!(online.count(), 6);
+        assert_eq!(online.mean(), Some(3.5));
+        assert_eq!(online.min(), Some(1.0));
+        assert_eq!(online.max(), Some(6.0));
+        assert_eq!(online.rolling_min(), Some(2.0));
+        assert_eq!(online.rolling_max(), Some(6.0));
+        assert!(online.median().is_some_and(f64::is_finite));
+    }
+}
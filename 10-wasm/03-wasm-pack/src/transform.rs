@@ -0,0 +1,333 @@
+//! Geometric transforms for `ImageProcessor`.
+//!
+//! Unlike the filters, these change the image size, so they return a new
+//! RGBA buffer instead of editing in place. The caller is responsible for
+//! the new dimensions (e.g. `rotate90` swaps width and height).
+
+use wasm_bindgen::prelude::*;
+
+use crate::convolution::check_dimensions;
+use crate::{ErrorCode, ImageProcessor, WasmError};
+
+/// How `resize` samples the source image
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResizeFilter {
+    /// Copy the closest source pixel: fast, keeps hard edges (pixel art)
+    Nearest,
+    /// Blend the four closest source pixels: smoother for photos
+    Bilinear,
+}
+
+fn pixel(pixels: &[u8], width: usize, x: usize, y: usize) -> &[u8] {
+    let i = (y * width + x) * 4;
+    &pixels[i..i + 4]
+}
+
+/// Build a `width x height` image by asking `source` for each output
+/// pixel's source coordinates
+fn remap(
+    pixels: &[u8],
+    src_width: usize,
+    width: usize,
+    height: usize,
+    source: impl Fn(usize, usize) -> (usize, usize),
+) -> Vec<u8> {
+    let mut out = Vec::with_capacity(width * height * 4);
+    for y in 0..height {
+        for x in 0..width {
+            let (sx, sy) = source(x, y);
+            out.extend_from_slice(pixel(pixels, src_width, sx, sy));
+        }
+    }
+    out
+}
+
+/// Map an output coordinate to the continuous source coordinate of its
+/// center, so both images line up at their edges
+fn source_coordinate(dst: usize, dst_size: usize, src_size: usize) -> f32 {
+    ((dst as f32 + 0.5) * src_size as f32 / dst_size as f32 - 0.5).clamp(0.0, (src_size - 1) as f32)
+}
+
+/// Geometric transforms
+#[wasm_bindgen]
+impl ImageProcessor {
+    /// Scale to `new_width x new_height`
+    pub fn resize(
+        pixels: &[u8],
+        width: u32,
+        height: u32,
+        new_width: u32,
+        new_height: u32,
+        filter: ResizeFilter,
+    ) -> Result<Vec<u8>, JsValue> {
+        Self::apply_resize(pixels, width, height, new_width, new_height, filter)
+            .map_err(JsValue::from)
+    }
+
+    /// Cut out the `crop_width x crop_height` rectangle whose top-left corner is at `x, y`
+    pub fn crop(
+        pixels: &[u8],
+        width: u32,
+        height: u32,
+        x: u32,
+        y: u32,
+        crop_width: u32,
+        crop_height: u32,
+    ) -> Result<Vec<u8>, JsValue> {
+        Self::apply_crop(pixels, width, height, x, y, crop_width, crop_height)
+            .map_err(JsValue::from)
+    }
+
+    /// Rotate clockwise; the result is `height x width`
+    pub fn rotate90(pixels: &[u8], width: u32, height: u32) -> Result<Vec<u8>, JsValue> {
+        Self::apply_rotate(pixels, width, height, 1).map_err(JsValue::from)
+    }
+
+    pub fn rotate180(pixels: &[u8], width: u32, height: u32) -> Result<Vec<u8>, JsValue> {
+        Self::apply_rotate(pixels, width, height, 2).map_err(JsValue::from)
+    }
+
+    /// Rotate counter-clockwise; the result is `height x width`
+    pub fn rotate270(pixels: &[u8], width: u32, height: u32) -> Result<Vec<u8>, JsValue> {
+        Self::apply_rotate(pixels, width, height, 3).map_err(JsValue::from)
+    }
+
+    /// Mirror left-to-right
+    pub fn flip_horizontal(pixels: &[u8], width: u32, height: u32) -> Result<Vec<u8>, JsValue> {
+        Self::apply_flip(pixels, width, height, true).map_err(JsValue::from)
+    }
+
+    /// Mirror top-to-bottom
+    pub fn flip_vertical(pixels: &[u8], width: u32, height: u32) -> Result<Vec<u8>, JsValue> {
+        Self::apply_flip(pixels, width, height, false).map_err(JsValue::from)
+    }
+}
+
+impl ImageProcessor {
+    pub fn apply_resize(
+        pixels: &[u8],
+        width: u32,
+        height: u32,
+        new_width: u32,
+        new_height: u32,
+        filter: ResizeFilter,
+    ) -> Result<Vec<u8>, WasmError> {
+        check_dimensions(pixels, width, height)?;
+        if new_width == 0 || new_height == 0 {
+            return Err(WasmError::image(
+                ErrorCode::InvalidArgument,
+                format!(
+                    "Target size must be non-zero, got {}x{}",
+                    new_width, new_height
+                ),
+            ));
+        }
+        let (sw, sh) = (width as usize, height as usize);
+        let (dw, dh) = (new_width as usize, new_height as usize);
+
+        if filter == ResizeFilter::Nearest {
+            return Ok(remap(pixels, sw, dw, dh, |x, y| {
+                ((x * sw / dw).min(sw - 1), (y * sh / dh).min(sh - 1))
+            }));
+        }
+
+        let mut out = Vec::with_capacity(dw * dh * 4);
+        for y in 0..dh {
+            let fy = source_coordinate(y, dh, sh);
+            let (y0, ty) = (fy.floor() as usize, fy.fract());
+            let y1 = (y0 + 1).min(sh - 1);
+            for x in 0..dw {
+                let fx = source_coordinate(x, dw, sw);
+                let (x0, tx) = (fx.floor() as usize, fx.fract());
+                let x1 = (x0 + 1).min(sw - 1);
+
+                let (a, b) = (pixel(pixels, sw, x0, y0), pixel(pixels, sw, x1, y0));
+                let (c, d) = (pixel(pixels, sw, x0, y1), pixel(pixels, sw, x1, y1));
+                for channel in 0..4 {
+                    let top = a[channel] as f32 + (b[channel] as f32 - a[channel] as f32) * tx;
+                    let bottom = c[channel] as f32 + (d[channel] as f32 - c[channel] as f32) * tx;
+                    out.push((top + (bottom - top) * ty).round() as u8);
+                }
+            }
+        }
+        Ok(out)
+    }
+
+    pub fn apply_crop(
+        pixels: &[u8],
+        width: u32,
+        height: u32,
+        x: u32,
+        y: u32,
+        crop_width: u32,
+        crop_height: u32,
+    ) -> Result<Vec<u8>, WasmError> {
+        check_dimensions(pixels, width, height)?;
+        let fits = |start: u32, size: u32, limit: u32| {
+            size > 0 && start.checked_add(size).is_some_and(|end| end <= limit)
+        };
+        if !fits(x, crop_width, width) || !fits(y, crop_height, height) {
+            return Err(WasmError::image(
+                ErrorCode::OutOfBounds,
+                format!(
+                    "Crop {}x{} at ({}, {}) does not fit in a {}x{} image",
+                    crop_width, crop_height, x, y, width, height
+                ),
+            ));
+        }
+
+        let row_bytes = crop_width as usize * 4;
+        let mut out = Vec::with_capacity(row_bytes * crop_height as usize);
+        for row in y..y + crop_height {
+            let start = (row as usize * width as usize + x as usize) * 4;
+            out.extend_from_slice(&pixels[start..start + row_bytes]);
+        }
+        Ok(out)
+    }
+
+    /// Rotate clockwise by `quarter_turns * 90` degrees
+    pub fn apply_rotate(
+        pixels: &[u8],
+        width: u32,
+        height: u32,
+        quarter_turns: u32,
+    ) -> Result<Vec<u8>, WasmError> {
+        check_dimensions(pixels, width, height)?;
+        let (w, h) = (width as usize, height as usize);
+
+        Ok(match quarter_turns % 4 {
+            0 => pixels.to_vec(),
+            // Output is h x w: output column x comes from source row h-1-x
+            1 => remap(pixels, w, h, w, |x, y| (y, h - 1 - x)),
+            2 => remap(pixels, w, w, h, |x, y| (w - 1 - x, h - 1 - y)),
+            _ => remap(pixels, w, h, w, |x, y| (w - 1 - y, x)),
+        })
+    }
+
+    /// Mirror left-to-right when `horizontal`, otherwise top-to-bottom
+    pub fn apply_flip(
+        pixels: &[u8],
+        width: u32,
+        height: u32,
+        horizontal: bool,
+    ) -> Result<Vec<u8>, WasmError> {
+        check_dimensions(pixels, width, height)?;
+        let (w, h) = (width as usize, height as usize);
+
+        Ok(if horizontal {
+            remap(pixels, w, w, h, |x, y| (w - 1 - x, y))
+        } else {
+            remap(pixels, w, w, h, |x, y| (x, h - 1 - y))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Image where each pixel's red/green channels hold its own coordinates
+    fn coordinates(width: u32, height: u32) -> Vec<u8> {
+        (0..height)
+            .flat_map(|y| (0..width).map(move |x| [x as u8, y as u8, 0, 255]))
+            .flatten()
+            .collect()
+    }
+
+    /// (x, y) coordinates stored in every pixel, row by row
+    fn origins(pixels: &[u8]) -> Vec<(u8, u8)> {
+        pixels.chunks_exact(4).map(|p| (p[0], p[1])).collect()
+    }
+
+    #[test]
+    fn test_rotate() {
+        // 3x2 image:  (0,0) (1,0) (2,0)
+        //             (0,1) (1,1) (2,1)
+        let image = coordinates(3, 2);
+
+        let rotated = ImageProcessor::apply_rotate(&image, 3, 2, 1).unwrap();
+        assert_eq!(
+            origins(&rotated),
+            vec![(0, 1), (0, 0), (1, 1), (1, 0), (2, 1), (2, 0)]
+        );
+
+        let rotated = ImageProcessor::apply_rotate(&image, 3, 2, 2).unwrap();
+        assert_eq!(
+            origins(&rotated),
+            vec![(2, 1), (1, 1), (0, 1), (2, 0), (1, 0), (0, 0)]
+        );
+
+        let rotated = ImageProcessor::apply_rotate(&image, 3, 2, 3).unwrap();
+        assert_eq!(
+            origins(&rotated),
+            vec![(2, 0), (2, 1), (1, 0), (1, 1), (0, 0), (0, 1)]
+        );
+
+        // Four quarter turns (90 then 270) get back to the original
+        let there = ImageProcessor::apply_rotate(&image, 3, 2, 1).unwrap();
+        let back = ImageProcessor::apply_rotate(&there, 2, 3, 3).unwrap();
+        assert_eq!(back, image);
+    }
+
+    #[test]
+    fn test_flip() {
+        let image = coordinates(2, 2);
+        let flipped = ImageProcessor::apply_flip(&image, 2, 2, true).unwrap();
+        assert_eq!(origins(&flipped), vec![(1, 0), (0, 0), (1, 1), (0, 1)]);
+        let flipped = ImageProcessor::apply_flip(&image, 2, 2, false).unwrap();
+        assert_eq!(origins(&flipped), vec![(0, 1), (1, 1), (0, 0), (1, 0)]);
+    }
+
+    #[test]
+    fn test_crop() {
+        let image = coordinates(4, 3);
+        let cropped = ImageProcessor::apply_crop(&image, 4, 3, 1, 1, 2, 2).unwrap();
+        assert_eq!(origins(&cropped), vec![(1, 1), (2, 1), (1, 2), (2, 2)]);
+
+        let error = ImageProcessor::apply_crop(&image, 4, 3, 3, 0, 2, 1).unwrap_err();
+        assert_eq!(error.code(), ErrorCode::OutOfBounds);
+        assert_eq!(
+            error.message(),
+            "Crop 2x1 at (3, 0) does not fit in a 4x3 image"
+        );
+        assert!(ImageProcessor::apply_crop(&image, 4, 3, 0, 0, 0, 1).is_err());
+        assert!(ImageProcessor::apply_crop(&image, 4, 3, u32::MAX, 0, 2, 1).is_err());
+    }
+
+    #[test]
+    fn test_resize_nearest() {
+        let image = coordinates(2, 2);
+        let resized =
+            ImageProcessor::apply_resize(&image, 2, 2, 4, 4, ResizeFilter::Nearest).unwrap();
+        assert_eq!(resized.len(), 4 * 4 * 4);
+        assert_eq!(
+            origins(&resized[..16]),
+            vec![(0, 0), (0, 0), (1, 0), (1, 0)]
+        );
+
+        let small =
+            ImageProcessor::apply_resize(&resized, 4, 4, 2, 2, ResizeFilter::Nearest).unwrap();
+        assert_eq!(small, image);
+    }
+
+    #[test]
+    fn test_resize_bilinear() {
+        // Black to white horizontal gradient of 2 pixels, stretched to 4
+        let image = vec![0, 0, 0, 255, 255, 255, 255, 255];
+        let resized =
+            ImageProcessor::apply_resize(&image, 2, 1, 4, 1, ResizeFilter::Bilinear).unwrap();
+        let reds: Vec<u8> = resized.chunks_exact(4).map(|p| p[0]).collect();
+        // Edges keep the source colors, the middle is blended
+        assert_eq!(reds, vec![0, 64, 191, 255]);
+        assert!(resized.chunks_exact(4).all(|p| p[3] == 255));
+
+        // Same size is a no-op
+        let same =
+            ImageProcessor::apply_resize(&image, 2, 1, 2, 1, ResizeFilter::Bilinear).unwrap();
+        assert_eq!(same, image);
+
+        assert!(ImageProcessor::apply_resize(&image, 2, 1, 0, 1, ResizeFilter::Bilinear).is_err());
+        assert!(ImageProcessor::apply_resize(&image, 3, 1, 4, 1, ResizeFilter::Bilinear).is_err());
+    }
+}
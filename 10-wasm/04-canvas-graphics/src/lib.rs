@@ -45,12 +45,12 @@ impl Canvas {
 
     /// Set fill color
     pub fn set_fill_color(&self, color: &str) {
-        self.context.set_fill_style(&JsValue::from_str(color));
+        self.context.set_fill_style_str(color);
     }
 
     /// Set stroke color
     pub fn set_stroke_color(&self, color: &str) {
-        self.context.set_stroke_style(&JsValue::from_str(color));
+        self.context.set_stroke_style_str(color);
     }
 
     /// Set line width
@@ -158,6 +158,391 @@ impl Canvas {
     pub fn scale(&self, x: f64, y: f64) {
         self.context.scale(x, y).unwrap();
     }
+
+    /// Replace the current transform matrix
+    pub fn set_transform(&self, a: f64, b: f64, c: f64, d: f64, e: f64, f: f64) {
+        self.context.set_transform(a, b, c, d, e, f).unwrap();
+    }
+
+    /// Reset the transform to identity
+    pub fn reset_transform(&self) {
+        self.set_transform(1.0, 0.0, 0.0, 1.0, 0.0, 0.0);
+    }
+
+    /// Canvas width in pixels
+    #[wasm_bindgen(getter)]
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Canvas height in pixels
+    #[wasm_bindgen(getter)]
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+}
+
+/// A shape stored in the retained scene
+#[derive(Clone, Debug)]
+enum Shape {
+    Rect { width: f64, height: f64 },
+    Circle { radius: f64 },
+    Line { dx: f64, dy: f64 },
+}
+
+/// A node of the retained scene, positioned in world coordinates
+#[derive(Clone, Debug)]
+struct SceneNode {
+    shape: Shape,
+    x: f64,
+    y: f64,
+    color: String,
+}
+
+/// Retained scene: shapes live here independently of any canvas,
+/// so the same scene can be rendered to any number of views
+#[wasm_bindgen]
+pub struct Scene {
+    nodes: Vec<SceneNode>,
+    width: f64,
+    height: f64,
+}
+
+#[wasm_bindgen]
+impl Scene {
+    /// Create an empty scene with the given world size
+    #[wasm_bindgen(constructor)]
+    pub fn new(width: f64, height: f64) -> Scene {
+        Scene {
+            nodes: Vec::new(),
+            width,
+            height,
+        }
+    }
+
+    fn add(&mut self, shape: Shape, x: f64, y: f64, color: &str) -> usize {
+        self.nodes.push(SceneNode {
+            shape,
+            x,
+            y,
+            color: color.to_string(),
+        });
+        self.nodes.len() - 1
+    }
+
+    /// Add a filled rectangle, returning its node id
+    pub fn add_rect(&mut self, x: f64, y: f64, width: f64, height: f64, color: &str) -> usize {
+        self.add(Shape::Rect { width, height }, x, y, color)
+    }
+
+    /// Add a filled circle, returning its node id
+    pub fn add_circle(&mut self, x: f64, y: f64, radius: f64, color: &str) -> usize {
+        self.add(Shape::Circle { radius }, x, y, color)
+    }
+
+    /// Add a line segment, returning its node id
+    pub fn add_line(&mut self, x1: f64, y1: f64, x2: f64, y2: f64, color: &str) -> usize {
+        self.add(
+            Shape::Line {
+                dx: x2 - x1,
+                dy: y2 - y1,
+            },
+            x1,
+            y1,
+            color,
+        )
+    }
+
+    /// Move a node to a new world position
+    pub fn set_position(&mut self, id: usize, x: f64, y: f64) {
+        if let Some(node) = self.nodes.get_mut(id) {
+            node.x = x;
+            node.y = y;
+        }
+    }
+
+    /// Move a node by an offset
+    pub fn translate_node(&mut self, id: usize, dx: f64, dy: f64) {
+        if let Some(node) = self.nodes.get_mut(id) {
+            node.x += dx;
+            node.y += dy;
+        }
+    }
+
+    /// Change a node's color
+    pub fn set_color(&mut self, id: usize, color: &str) {
+        if let Some(node) = self.nodes.get_mut(id) {
+            node.color = color.to_string();
+        }
+    }
+
+    /// Number of nodes in the scene
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Whether the scene has no nodes
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn width(&self) -> f64 {
+        self.width
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn height(&self) -> f64 {
+        self.height
+    }
+}
+
+/// A render target: one canvas plus its own viewport transform.
+///
+/// Several views can render the same `Scene` with different pan/zoom,
+/// e.g. a main view and a minimap.
+#[wasm_bindgen]
+pub struct View {
+    canvas: Canvas,
+    offset_x: f64,
+    offset_y: f64,
+    zoom: f64,
+    background: String,
+}
+
+#[wasm_bindgen]
+impl View {
+    /// Create a view drawing into the canvas with the given ID
+    #[wasm_bindgen(constructor)]
+    pub fn new(canvas_id: &str) -> Result<View, JsValue> {
+        Ok(View {
+            canvas: Canvas::new(canvas_id)?,
+            offset_x: 0.0,
+            offset_y: 0.0,
+            zoom: 1.0,
+            background: "#1a1a1a".to_string(),
+        })
+    }
+
+    /// Set the background color used when rendering
+    pub fn set_background(&mut self, color: &str) {
+        self.background = color.to_string();
+    }
+
+    /// Pan the viewport by a screen-space offset
+    pub fn pan(&mut self, dx: f64, dy: f64) {
+        self.offset_x += dx / self.zoom;
+        self.offset_y += dy / self.zoom;
+    }
+
+    /// Zoom around a screen-space point, keeping that point fixed
+    pub fn zoom_at(&mut self, factor: f64, screen_x: f64, screen_y: f64) {
+        let (world_x, world_y) = self.to_world(screen_x, screen_y);
+        self.zoom = (self.zoom * factor).clamp(0.05, 50.0);
+        self.offset_x = world_x - screen_x / self.zoom;
+        self.offset_y = world_y - screen_y / self.zoom;
+    }
+
+    /// Center the viewport on a world-space point
+    pub fn center_on(&mut self, world_x: f64, world_y: f64) {
+        self.offset_x = world_x - self.visible_width() / 2.0;
+        self.offset_y = world_y - self.visible_height() / 2.0;
+    }
+
+    /// Fit the whole scene into this view (used for minimaps)
+    pub fn fit_to_scene(&mut self, scene: &Scene) {
+        let zoom_x = self.canvas.width as f64 / scene.width;
+        let zoom_y = self.canvas.height as f64 / scene.height;
+        self.zoom = zoom_x.min(zoom_y);
+        self.offset_x = 0.0;
+        self.offset_y = 0.0;
+    }
+
+    /// Convert screen coordinates to world coordinates as `[x, y]`
+    pub fn screen_to_world(&self, screen_x: f64, screen_y: f64) -> Vec<f64> {
+        let (x, y) = self.to_world(screen_x, screen_y);
+        vec![x, y]
+    }
+
+    /// Render the scene through this view's viewport
+    pub fn render(&self, scene: &Scene) {
+        self.canvas.reset_transform();
+        self.canvas.clear();
+        self.canvas.set_fill_color(&self.background);
+        self.canvas.fill_rect(
+            0.0,
+            0.0,
+            self.canvas.width as f64,
+            self.canvas.height as f64,
+        );
+
+        self.apply_viewport();
+        for node in &scene.nodes {
+            self.draw_node(node);
+        }
+        self.canvas.reset_transform();
+    }
+
+    /// Outline the area another view currently shows, in this view's space
+    pub fn draw_viewport_of(&self, other: &View, color: &str) {
+        self.apply_viewport();
+        self.canvas.set_stroke_color(color);
+        self.canvas.set_line_width(2.0 / self.zoom);
+        self.canvas.stroke_rect(
+            other.offset_x,
+            other.offset_y,
+            other.visible_width(),
+            other.visible_height(),
+        );
+        self.canvas.reset_transform();
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn zoom(&self) -> f64 {
+        self.zoom
+    }
+}
+
+impl View {
+    fn to_world(&self, screen_x: f64, screen_y: f64) -> (f64, f64) {
+        (
+            screen_x / self.zoom + self.offset_x,
+            screen_y / self.zoom + self.offset_y,
+        )
+    }
+
+    fn visible_width(&self) -> f64 {
+        self.canvas.width as f64 / self.zoom
+    }
+
+    fn visible_height(&self) -> f64 {
+        self.canvas.height as f64 / self.zoom
+    }
+
+    fn apply_viewport(&self) {
+        self.canvas.set_transform(
+            self.zoom,
+            0.0,
+            0.0,
+            self.zoom,
+            -self.offset_x * self.zoom,
+            -self.offset_y * self.zoom,
+        );
+    }
+
+    fn draw_node(&self, node: &SceneNode) {
+        match node.shape {
+            Shape::Rect { width, height } => {
+                self.canvas.set_fill_color(&node.color);
+                self.canvas.fill_rect(node.x, node.y, width, height);
+            }
+            Shape::Circle { radius } => {
+                self.canvas.set_fill_color(&node.color);
+                self.canvas.fill_circle(node.x, node.y, radius);
+            }
+            Shape::Line { dx, dy } => {
+                self.canvas.set_stroke_color(&node.color);
+                self.canvas.set_line_width(2.0 / self.zoom);
+                self.canvas
+                    .draw_line(node.x, node.y, node.x + dx, node.y + dy);
+            }
+        }
+    }
+}
+
+/// Main view + minimap over one shared scene.
+///
+/// Both views render the same retained `Scene`, so anything changed in the
+/// scene shows up in both on the next `render()`.
+#[wasm_bindgen]
+pub struct MinimapDemo {
+    scene: Scene,
+    main: View,
+    minimap: View,
+    ball: usize,
+    vx: f64,
+    vy: f64,
+}
+
+#[wasm_bindgen]
+impl MinimapDemo {
+    #[wasm_bindgen(constructor)]
+    pub fn new(main_canvas_id: &str, minimap_canvas_id: &str) -> Result<MinimapDemo, JsValue> {
+        let mut scene = Scene::new(2000.0, 1500.0);
+
+        // A grid of "buildings" so panning is noticeable
+        for row in 0..6 {
+            for col in 0..8 {
+                let color = if (row + col) % 2 == 0 {
+                    "#4682b4"
+                } else {
+                    "#2e8b57"
+                };
+                scene.add_rect(
+                    100.0 + col as f64 * 230.0,
+                    100.0 + row as f64 * 220.0,
+                    150.0,
+                    120.0,
+                    color,
+                );
+            }
+        }
+        scene.add_line(0.0, 750.0, 2000.0, 750.0, "#888888");
+        scene.add_line(1000.0, 0.0, 1000.0, 1500.0, "#888888");
+        let ball = scene.add_circle(300.0, 300.0, 30.0, "#ff6347");
+
+        let main = View::new(main_canvas_id)?;
+        let mut minimap = View::new(minimap_canvas_id)?;
+        minimap.set_background("#000000");
+        minimap.fit_to_scene(&scene);
+
+        Ok(MinimapDemo {
+            scene,
+            main,
+            minimap,
+            ball,
+            vx: 4.0,
+            vy: 3.0,
+        })
+    }
+
+    /// Advance the animation by one frame
+    pub fn update(&mut self) {
+        let node = &self.scene.nodes[self.ball];
+        let (mut x, mut y) = (node.x, node.y);
+        x += self.vx;
+        y += self.vy;
+        if x < 0.0 || x > self.scene.width {
+            self.vx = -self.vx;
+        }
+        if y < 0.0 || y > self.scene.height {
+            self.vy = -self.vy;
+        }
+        self.scene.set_position(self.ball, x, y);
+    }
+
+    /// Render the scene to both views and mark the main viewport on the minimap
+    pub fn render(&self) {
+        self.main.render(&self.scene);
+        self.minimap.render(&self.scene);
+        self.minimap.draw_viewport_of(&self.main, "#ffff00");
+    }
+
+    /// Pan the main view (e.g. from a mouse drag)
+    pub fn pan(&mut self, dx: f64, dy: f64) {
+        self.main.pan(dx, dy);
+    }
+
+    /// Zoom the main view around a screen point (e.g. from the mouse wheel)
+    pub fn zoom_at(&mut self, factor: f64, x: f64, y: f64) {
+        self.main.zoom_at(factor, x, y);
+    }
+
+    /// Jump the main view to the point clicked on the minimap
+    pub fn minimap_click(&mut self, x: f64, y: f64) {
+        let (world_x, world_y) = self.minimap.to_world(x, y);
+        self.main.center_on(world_x, world_y);
+    }
 }
 
 /// Draw a simple scene
@@ -0,0 +1,235 @@
+//! Structured benchmark harness for the compute functions.
+//!
+//! Runs a task N times, throws away warmup iterations and reports timing
+//! statistics as a serde struct, so the same benchmark can be run on the
+//! main thread and inside a worker and the two reports compared.
+
+use serde::{Deserialize, Serialize};
+use std::hint::black_box;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+/// High-resolution timestamp in milliseconds.
+///
+/// Uses `performance.now()` from whichever global is available (window or
+/// worker), falling back to `Date.now()`.
+pub(crate) fn now() -> f64 {
+    js_sys::Reflect::get(&js_sys::global(), &JsValue::from_str("performance"))
+        .ok()
+        .and_then(|perf| perf.dyn_into::<web_sys::Performance>().ok())
+        .map(|perf| perf.now())
+        .unwrap_or_else(js_sys::Date::now)
+}
+
+/// Time a single call of `f` in milliseconds
+pub(crate) fn time_ms<T>(f: impl FnOnce() -> T) -> f64 {
+    let start = now();
+    black_box(f());
+    now() - start
+}
+
+/// Where a benchmark was executed
+fn execution_context() -> &'static str {
+    if js_sys::global().has_type::<web_sys::DedicatedWorkerGlobalScope>() {
+        "worker"
+    } else {
+        "main"
+    }
+}
+
+/// Timing statistics over the measured (non-warmup) samples
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TimingStats {
+    pub mean_ms: f64,
+    pub median_ms: f64,
+    pub p95_ms: f64,
+    pub min_ms: f64,
+    pub max_ms: f64,
+}
+
+impl TimingStats {
+    /// Summarize samples; returns `None` when there are no samples
+    pub fn from_samples(samples: &[f64]) -> Option<TimingStats> {
+        if samples.is_empty() {
+            return None;
+        }
+
+        let mut sorted = samples.to_vec();
+        sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+        let n = sorted.len();
+        let mean_ms = sorted.iter().sum::<f64>() / n as f64;
+        let median_ms = if n.is_multiple_of(2) {
+            (sorted[n / 2 - 1] + sorted[n / 2]) / 2.0
+        } else {
+            sorted[n / 2]
+        };
+        // Nearest-rank percentile
+        let p95_index = ((0.95 * n as f64).ceil() as usize).clamp(1, n) - 1;
+
+        Some(TimingStats {
+            mean_ms,
+            median_ms,
+            p95_ms: sorted[p95_index],
+            min_ms: sorted[0],
+            max_ms: sorted[n - 1],
+        })
+    }
+}
+
+/// Result of one benchmark run, sent back to JS
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BenchmarkReport {
+    pub task: String,
+    pub param: f64,
+    pub context: String,
+    pub iterations: u32,
+    pub warmup: u32,
+    pub stats: TimingStats,
+}
+
+/// Main-thread vs worker comparison of the same task
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BenchmarkComparison {
+    pub task: String,
+    pub main_median_ms: f64,
+    pub worker_median_ms: f64,
+    /// `main / worker`; above 1.0 means the worker was faster
+    pub speedup: f64,
+}
+
+/// Run one of the crate's compute functions by name
+fn run_task(task: &str, param: f64) -> Result<(), JsValue> {
+    match task {
+        "primes" => {
+            black_box(crate::calculate_primes(param as u32));
+        }
+        "fibonacci" => {
+            black_box(crate::fibonacci(param as u32));
+        }
+        "fibonacci_iterative" => {
+            black_box(crate::fibonacci_iterative(param as u32));
+        }
+        "fibonacci_big" => {
+            black_box(crate::fibonacci_big(param as u32));
+        }
+        "pi" => {
+            black_box(crate::estimate_pi(param as u32));
+        }
+        "matrix" => {
+            black_box(crate::matrix_multiply(param as usize));
+        }
+        "matrix_blocked" => {
+            black_box(crate::matrix_multiply_blocked(param as usize, 32));
+        }
+        "matrix_simd" => {
+            black_box(crate::matrix_multiply_simd(param as usize));
+        }
+        "sort" => {
+            let data = (0..param as usize)
+                .map(|_| js_sys::Math::random())
+                .collect();
+            black_box(crate::sort_array(data));
+        }
+        "heavy" => {
+            black_box(crate::heavy_computation(param as u32));
+        }
+        _ => return Err(JsValue::from_str(&format!("Unknown task: {}", task))),
+    }
+    Ok(())
+}
+
+/// Benchmark runner: `iterations` measured runs after `warmup` discarded runs
+#[wasm_bindgen]
+pub struct Benchmark {
+    iterations: u32,
+    warmup: u32,
+}
+
+#[wasm_bindgen]
+impl Benchmark {
+    #[wasm_bindgen(constructor)]
+    pub fn new(iterations: u32, warmup: u32) -> Benchmark {
+        Benchmark {
+            iterations: iterations.max(1),
+            warmup,
+        }
+    }
+
+    /// Benchmark a task ("primes", "fibonacci", "fibonacci_iterative", "fibonacci_big",
+    /// "pi", "matrix", "matrix_blocked", "matrix_simd", "sort", "heavy") with the
+    /// given size parameter
+    pub fn run(&self, task: &str, param: f64) -> Result<JsValue, JsValue> {
+        for _ in 0..self.warmup {
+            run_task(task, param)?;
+        }
+
+        let mut samples = Vec::with_capacity(self.iterations as usize);
+        for _ in 0..self.iterations {
+            let start = now();
+            run_task(task, param)?;
+            samples.push(now() - start);
+        }
+
+        let report = BenchmarkReport {
+            task: task.to_string(),
+            param,
+            context: execution_context().to_string(),
+            iterations: self.iterations,
+            warmup: self.warmup,
+            stats: TimingStats::from_samples(&samples).unwrap(),
+        };
+
+        Ok(serde_wasm_bindgen::to_value(&report)?)
+    }
+
+    /// Compare a main-thread report with a worker report of the same task
+    pub fn compare(main: JsValue, worker: JsValue) -> Result<JsValue, JsValue> {
+        let main: BenchmarkReport = serde_wasm_bindgen::from_value(main)?;
+        let worker: BenchmarkReport = serde_wasm_bindgen::from_value(worker)?;
+
+        if main.task != worker.task {
+            return Err(JsValue::from_str(&format!(
+                "Cannot compare different tasks: {} vs {}",
+                main.task, worker.task
+            )));
+        }
+
+        let comparison = BenchmarkComparison {
+            speedup: main.stats.median_ms / worker.stats.median_ms,
+            task: main.task,
+            main_median_ms: main.stats.median_ms,
+            worker_median_ms: worker.stats.median_ms,
+        };
+
+        Ok(serde_wasm_bindgen::to_value(&comparison)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_timing_stats_empty() {
+        assert_eq!(TimingStats::from_samples(&[]), None);
+    }
+
+    #[test]
+    fn test_timing_stats_odd() {
+        let stats = TimingStats::from_samples(&[3.0, 1.0, 2.0]).unwrap();
+        assert_eq!(stats.mean_ms, 2.0);
+        assert_eq!(stats.median_ms, 2.0);
+        assert_eq!(stats.min_ms, 1.0);
+        assert_eq!(stats.max_ms, 3.0);
+        assert_eq!(stats.p95_ms, 3.0);
+    }
+
+    #[test]
+    fn test_timing_stats_p95() {
+        let samples: Vec<f64> = (1..=100).map(|i| i as f64).collect();
+        let stats = TimingStats::from_samples(&samples).unwrap();
+        assert_eq!(stats.median_ms, 50.5);
+        assert_eq!(stats.p95_ms, 95.0);
+    }
+}
@@ -0,0 +1,418 @@
+//! Resumable long-running jobs with checkpoints in IndexedDB.
+//!
+//! A Monte Carlo run with billions of iterations can take minutes. Instead of
+//! losing that work on a page reload, the worker runs the job in time slices
+//! and periodically saves a `Checkpoint` (the complete job state, including
+//! the RNG) to IndexedDB. IndexedDB is available in both windows and workers,
+//! so the main thread can open the same store as a job registry, list the
+//! unfinished jobs after a reload, and hand them back to a worker to resume.
+//!
+//! The job uses its own seeded RNG rather than `Math.random()`, so a resumed
+//! run continues the exact sequence it would have produced uninterrupted.
+
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::{future_to_promise, JsFuture};
+use web_sys::{IdbDatabase, IdbFactory, IdbObjectStoreParameters, IdbRequest, IdbTransactionMode};
+
+const DB_NAME: &str = "wasm-worker-jobs";
+const DB_VERSION: u32 = 1;
+const STORE_NAME: &str = "checkpoints";
+
+const MONTE_CARLO_PI: &str = "monte_carlo_pi";
+
+/// Iterations between clock checks in `run_for`
+const SLICE_BATCH: u64 = 100_000;
+
+/// Everything needed to resume a job, as stored in IndexedDB
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Checkpoint {
+    pub job_id: String,
+    pub kind: String,
+    pub total: u64,
+    pub done: u64,
+    pub inside: u64,
+    pub rng_state: u64,
+    /// `Date.now()` when the checkpoint was taken
+    pub updated_at: f64,
+}
+
+/// A saved job as listed by the registry
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct JobSummary {
+    pub job_id: String,
+    pub kind: String,
+    /// Fraction complete, 0-1
+    pub progress: f64,
+    pub done: f64,
+    pub total: f64,
+    pub estimate: f64,
+    pub updated_at: f64,
+}
+
+impl From<&Checkpoint> for JobSummary {
+    fn from(checkpoint: &Checkpoint) -> Self {
+        JobSummary {
+            job_id: checkpoint.job_id.clone(),
+            kind: checkpoint.kind.clone(),
+            progress: checkpoint.done as f64 / checkpoint.total.max(1) as f64,
+            done: checkpoint.done as f64,
+            total: checkpoint.total as f64,
+            estimate: pi_estimate(checkpoint.inside, checkpoint.done),
+            updated_at: checkpoint.updated_at,
+        }
+    }
+}
+
+fn pi_estimate(inside: u64, done: u64) -> f64 {
+    if done == 0 {
+        return 0.0;
+    }
+    4.0 * inside as f64 / done as f64
+}
+
+/// SplitMix64: tiny, fast, and its whole state is one `u64`, which makes
+/// it trivial to checkpoint
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in [0, 1)
+    fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+/// Monte Carlo Pi estimation that can be paused, checkpointed and resumed.
+///
+/// Counts are exposed to JS as numbers (exact up to 2^53 iterations).
+#[wasm_bindgen]
+pub struct MonteCarloPi {
+    job_id: String,
+    total: u64,
+    done: u64,
+    inside: u64,
+    rng: SplitMix64,
+}
+
+#[wasm_bindgen]
+impl MonteCarloPi {
+    /// Start a job of `total` iterations; pass `seed` for a reproducible run
+    #[wasm_bindgen(constructor)]
+    pub fn new(job_id: String, total: f64, seed: Option<u32>) -> MonteCarloPi {
+        let seed = seed.map_or_else(
+            || (js_sys::Math::random() * u32::MAX as f64) as u64,
+            u64::from,
+        );
+        MonteCarloPi {
+            job_id,
+            total: total.max(0.0) as u64,
+            done: 0,
+            inside: 0,
+            rng: SplitMix64(seed),
+        }
+    }
+
+    /// Continue a job from a checkpoint loaded from the store
+    pub fn resume(checkpoint: JsValue) -> Result<MonteCarloPi, JsValue> {
+        let checkpoint: Checkpoint = serde_wasm_bindgen::from_value(checkpoint)?;
+        Self::from_checkpoint(checkpoint).map_err(|e| JsValue::from_str(&e))
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn job_id(&self) -> String {
+        self.job_id.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn done(&self) -> f64 {
+        self.done as f64
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn total(&self) -> f64 {
+        self.total as f64
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn progress(&self) -> f64 {
+        self.done as f64 / self.total.max(1) as f64
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn estimate(&self) -> f64 {
+        pi_estimate(self.inside, self.done)
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.done >= self.total
+    }
+
+    /// Run up to `iterations` more samples; returns whether the job is complete
+    pub fn step(&mut self, iterations: f64) -> bool {
+        let iterations = (iterations.max(0.0) as u64).min(self.total - self.done);
+        for _ in 0..iterations {
+            let x = self.rng.next_f64();
+            let y = self.rng.next_f64();
+            if x * x + y * y <= 1.0 {
+                self.inside += 1;
+            }
+        }
+        self.done += iterations;
+        self.is_complete()
+    }
+
+    /// Run for about `budget_ms`, then return so the worker can post
+    /// progress, save a checkpoint, or handle a pause message
+    pub fn run_for(&mut self, budget_ms: f64) -> bool {
+        let deadline = crate::benchmark::now() + budget_ms;
+        while !self.step(SLICE_BATCH as f64) {
+            if crate::benchmark::now() >= deadline {
+                break;
+            }
+        }
+        self.is_complete()
+    }
+
+    /// Snapshot the job for `CheckpointStore.save`
+    pub fn checkpoint(&self) -> Result<JsValue, JsValue> {
+        to_js(&self.to_checkpoint(js_sys::Date::now()))
+    }
+}
+
+impl MonteCarloPi {
+    pub fn to_checkpoint(&self, updated_at: f64) -> Checkpoint {
+        Checkpoint {
+            job_id: self.job_id.clone(),
+            kind: MONTE_CARLO_PI.to_string(),
+            total: self.total,
+            done: self.done,
+            inside: self.inside,
+            rng_state: self.rng.0,
+            updated_at,
+        }
+    }
+
+    pub fn from_checkpoint(checkpoint: Checkpoint) -> Result<MonteCarloPi, String> {
+        if checkpoint.kind != MONTE_CARLO_PI {
+            return Err(format!("Not a Monte Carlo checkpoint: {}", checkpoint.kind));
+        }
+        if checkpoint.done > checkpoint.total || checkpoint.inside > checkpoint.done {
+            return Err(format!("Corrupt checkpoint for job {}", checkpoint.job_id));
+        }
+        Ok(MonteCarloPi {
+            job_id: checkpoint.job_id,
+            total: checkpoint.total,
+            done: checkpoint.done,
+            inside: checkpoint.inside,
+            rng: SplitMix64(checkpoint.rng_state),
+        })
+    }
+}
+
+/// Serialize for IndexedDB. `u64`s become BigInts so the RNG state
+/// survives exactly (IndexedDB stores BigInts natively).
+fn to_js(checkpoint: &Checkpoint) -> Result<JsValue, JsValue> {
+    let serializer =
+        serde_wasm_bindgen::Serializer::new().serialize_large_number_types_as_bigints(true);
+    Ok(checkpoint.serialize(&serializer)?)
+}
+
+/// Resolve once an IndexedDB request succeeds, with its result
+async fn request_result(request: &IdbRequest) -> Result<JsValue, JsValue> {
+    let promise = js_sys::Promise::new(&mut |resolve, reject| {
+        let success_request = request.clone();
+        let on_success = Closure::once_into_js(move || {
+            let result = success_request.result().unwrap_or(JsValue::UNDEFINED);
+            let _ = resolve.call1(&JsValue::UNDEFINED, &result);
+        });
+        let error_request = request.clone();
+        let on_error = Closure::once_into_js(move || {
+            let error = error_request
+                .error()
+                .ok()
+                .flatten()
+                .map(JsValue::from)
+                .unwrap_or_else(|| JsValue::from_str("IndexedDB request failed"));
+            let _ = reject.call1(&JsValue::UNDEFINED, &error);
+        });
+        request.set_onsuccess(Some(on_success.unchecked_ref()));
+        request.set_onerror(Some(on_error.unchecked_ref()));
+    });
+    JsFuture::from(promise).await
+}
+
+/// Checkpoint storage in IndexedDB, shared by workers (saving) and the
+/// main thread (the job registry)
+#[wasm_bindgen]
+pub struct CheckpointStore {
+    db: IdbDatabase,
+}
+
+#[wasm_bindgen]
+impl CheckpointStore {
+    /// Open (and on first use create) the checkpoint database
+    pub async fn open() -> Result<CheckpointStore, JsValue> {
+        // `indexedDB` exists on both Window and WorkerGlobalScope
+        let factory: IdbFactory =
+            js_sys::Reflect::get(&js_sys::global(), &JsValue::from_str("indexedDB"))?.dyn_into()?;
+        let request = factory.open_with_u32(DB_NAME, DB_VERSION)?;
+
+        let upgrade_request = request.clone();
+        let on_upgrade = Closure::once_into_js(move || {
+            let Ok(db) = upgrade_request
+                .result()
+                .and_then(|db| db.dyn_into::<IdbDatabase>())
+            else {
+                return;
+            };
+            if !db.object_store_names().contains(STORE_NAME) {
+                let params = IdbObjectStoreParameters::new();
+                params.set_key_path(&JsValue::from_str("job_id"));
+                let _ = db.create_object_store_with_optional_parameters(STORE_NAME, &params);
+            }
+        });
+        request.set_onupgradeneeded(Some(on_upgrade.unchecked_ref()));
+
+        let db = request_result(&request).await?.dyn_into::<IdbDatabase>()?;
+        Ok(CheckpointStore { db })
+    }
+
+    /// Save (or overwrite) a checkpoint from `MonteCarloPi.checkpoint()`
+    pub fn save(&self, checkpoint: JsValue) -> js_sys::Promise {
+        let db = self.db.clone();
+        future_to_promise(async move {
+            let store = db
+                .transaction_with_str_and_mode(STORE_NAME, IdbTransactionMode::Readwrite)?
+                .object_store(STORE_NAME)?;
+            request_result(&store.put(&checkpoint)?).await?;
+            Ok(JsValue::UNDEFINED)
+        })
+    }
+
+    /// The checkpoint for `job_id`, or `undefined`
+    pub fn load(&self, job_id: String) -> js_sys::Promise {
+        let db = self.db.clone();
+        future_to_promise(async move {
+            let store = db
+                .transaction_with_str(STORE_NAME)?
+                .object_store(STORE_NAME)?;
+            request_result(&store.get(&JsValue::from_str(&job_id))?).await
+        })
+    }
+
+    /// Forget a job, e.g. once its result has been used
+    pub fn remove(&self, job_id: String) -> js_sys::Promise {
+        let db = self.db.clone();
+        future_to_promise(async move {
+            let store = db
+                .transaction_with_str_and_mode(STORE_NAME, IdbTransactionMode::Readwrite)?
+                .object_store(STORE_NAME)?;
+            request_result(&store.delete(&JsValue::from_str(&job_id))?).await?;
+            Ok(JsValue::UNDEFINED)
+        })
+    }
+
+    /// Every saved job as a `JobSummary`, most recently updated first
+    pub fn jobs(&self) -> js_sys::Promise {
+        let db = self.db.clone();
+        future_to_promise(async move {
+            let summaries = all_summaries(&db).await?;
+            Ok(serde_wasm_bindgen::to_value(&summaries)?)
+        })
+    }
+
+    /// Saved jobs that have not finished yet - the work to offer for resuming
+    pub fn resumable(&self) -> js_sys::Promise {
+        let db = self.db.clone();
+        future_to_promise(async move {
+            let mut summaries = all_summaries(&db).await?;
+            summaries.retain(|job| job.done < job.total);
+            Ok(serde_wasm_bindgen::to_value(&summaries)?)
+        })
+    }
+}
+
+async fn all_summaries(db: &IdbDatabase) -> Result<Vec<JobSummary>, JsValue> {
+    let store = db
+        .transaction_with_str(STORE_NAME)?
+        .object_store(STORE_NAME)?;
+    let all: js_sys::Array = request_result(&store.get_all()?).await?.dyn_into()?;
+
+    let mut summaries = Vec::new();
+    for value in all.iter() {
+        let checkpoint: Checkpoint = serde_wasm_bindgen::from_value(value)?;
+        summaries.push(JobSummary::from(&checkpoint));
+    }
+    summaries.sort_by(|a, b| b.updated_at.total_cmp(&a.updated_at));
+    Ok(summaries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resume_continues_the_same_run() {
+        let mut uninterrupted = MonteCarloPi::new("a".to_string(), 10_000.0, Some(7));
+        uninterrupted.step(10_000.0);
+
+        let mut first_half = MonteCarloPi::new("a".to_string(), 10_000.0, Some(7));
+        first_half.step(4_000.0);
+
+        // Round-trip through JSON, as a stand-in for IndexedDB
+        let json = serde_json::to_string(&first_half.to_checkpoint(1.0)).unwrap();
+        let mut resumed =
+            MonteCarloPi::from_checkpoint(serde_json::from_str(&json).unwrap()).unwrap();
+        assert_eq!(resumed.done(), 4_000.0);
+        resumed.step(6_000.0);
+
+        assert_eq!(resumed.to_checkpoint(2.0), uninterrupted.to_checkpoint(2.0));
+    }
+
+    #[test]
+    fn test_step_stops_at_total() {
+        let mut job = MonteCarloPi::new("b".to_string(), 1_000.0, Some(1));
+        assert!(!job.step(600.0));
+        assert!(job.step(600.0));
+        assert_eq!(job.done(), 1_000.0);
+        assert_eq!(job.progress(), 1.0);
+        assert!(job.step(100.0));
+        assert_eq!(job.done(), 1_000.0);
+    }
+
+    #[test]
+    fn test_estimate() {
+        let mut job = MonteCarloPi::new("c".to_string(), 1_000_000.0, Some(42));
+        assert_eq!(job.estimate(), 0.0);
+        job.step(1_000_000.0);
+        assert!((job.estimate() - std::f64::consts::PI).abs() < 0.01);
+
+        let summary = JobSummary::from(&job.to_checkpoint(5.0));
+        assert_eq!(summary.progress, 1.0);
+        assert_eq!(summary.estimate, job.estimate());
+        assert_eq!(summary.updated_at, 5.0);
+    }
+
+    #[test]
+    fn test_rejects_bad_checkpoints() {
+        let job = MonteCarloPi::new("d".to_string(), 100.0, Some(3));
+
+        let mut other_kind = job.to_checkpoint(0.0);
+        other_kind.kind = "render".to_string();
+        assert!(MonteCarloPi::from_checkpoint(other_kind).is_err());
+
+        let mut corrupt = job.to_checkpoint(0.0);
+        corrupt.done = 200;
+        assert!(MonteCarloPi::from_checkpoint(corrupt).is_err());
+    }
+}
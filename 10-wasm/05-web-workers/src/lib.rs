@@ -1,6 +1,13 @@
 use serde::{Deserialize, Serialize};
 use wasm_bindgen::prelude::*;
-use web_sys::console;
+
+mod benchmark;
+mod checkpoint;
+mod tiles;
+
+pub use benchmark::{Benchmark, BenchmarkComparison, BenchmarkReport, TimingStats};
+pub use checkpoint::{Checkpoint, CheckpointStore, JobSummary, MonteCarloPi};
+pub use tiles::Tile;
 
 /// Message types for worker communication
 #[derive(Serialize, Deserialize, Debug)]
@@ -19,8 +26,6 @@ pub struct WorkerResult {
 /// Calculate prime numbers up to n (CPU-intensive task)
 #[wasm_bindgen]
 pub fn calculate_primes(n: u32) -> Vec<u32> {
-    let start = js_sys::Date::now();
-
     let mut primes = Vec::new();
     for num in 2..=n {
         if is_prime(num) {
@@ -28,9 +33,6 @@ pub fn calculate_primes(n: u32) -> Vec<u32> {
         }
     }
 
-    let elapsed = js_sys::Date::now() - start;
-    console::log_1(&format!("Found {} primes in {}ms", primes.len(), elapsed).into());
-
     primes
 }
 
@@ -39,7 +41,7 @@ fn is_prime(n: u32) -> bool {
         return false;
     }
     for i in 2..=(n as f64).sqrt() as u32 {
-        if n % i == 0 {
+        if n.is_multiple_of(i) {
             return false;
         }
     }
@@ -47,6 +49,9 @@ fn is_prime(n: u32) -> bool {
 }
 
 /// Calculate Fibonacci number (recursive, inefficient on purpose for demonstration)
+///
+/// SLOW DEMO: exponential time and overflows `u64` past n = 93. Use
+/// `fibonacci_iterative` or `fibonacci_big` for real work.
 #[wasm_bindgen]
 pub fn fibonacci(n: u32) -> u64 {
     match n {
@@ -56,11 +61,69 @@ pub fn fibonacci(n: u32) -> u64 {
     }
 }
 
+/// Iterative Fibonacci in linear time.
+///
+/// Returns `undefined` to JS once the result no longer fits in a `u64` (n > 93).
+#[wasm_bindgen]
+pub fn fibonacci_iterative(n: u32) -> Option<u64> {
+    if n == 0 {
+        return Some(0);
+    }
+    let (mut a, mut b) = (0u64, 1u64);
+    for _ in 1..n {
+        let next = a.checked_add(b)?;
+        a = b;
+        b = next;
+    }
+    Some(b)
+}
+
+/// Base of each limb in `fibonacci_big`: nine decimal digits per `u32`
+const BIG_LIMB_BASE: u32 = 1_000_000_000;
+
+/// Add two little-endian base-10^9 numbers
+fn big_add(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut result = Vec::with_capacity(a.len().max(b.len()) + 1);
+    let mut carry = 0;
+    for i in 0..a.len().max(b.len()) {
+        let sum = a.get(i).copied().unwrap_or(0) + b.get(i).copied().unwrap_or(0) + carry;
+        result.push(sum % BIG_LIMB_BASE);
+        carry = sum / BIG_LIMB_BASE;
+    }
+    if carry > 0 {
+        result.push(carry);
+    }
+    result
+}
+
+/// Format a little-endian base-10^9 number as a decimal string
+fn big_to_string(limbs: &[u32]) -> String {
+    let mut iter = limbs.iter().rev();
+    let mut out = iter
+        .next()
+        .map_or_else(|| "0".to_string(), |l| l.to_string());
+    for limb in iter {
+        out.push_str(&format!("{:09}", limb));
+    }
+    out
+}
+
+/// Exact Fibonacci number of any size, returned as a decimal string
+/// (e.g. `fibonacci_big(10000)` has 2090 digits)
+#[wasm_bindgen]
+pub fn fibonacci_big(n: u32) -> String {
+    let (mut a, mut b) = (vec![0u32], vec![1u32]);
+    for _ in 0..n {
+        let next = big_add(&a, &b);
+        a = b;
+        b = next;
+    }
+    big_to_string(&a)
+}
+
 /// Monte Carlo Pi estimation
 #[wasm_bindgen]
 pub fn estimate_pi(iterations: u32) -> f64 {
-    let start = js_sys::Date::now();
-
     let mut inside_circle = 0;
 
     for _ in 0..iterations {
@@ -72,19 +135,12 @@ pub fn estimate_pi(iterations: u32) -> f64 {
         }
     }
 
-    let pi_estimate = 4.0 * (inside_circle as f64) / (iterations as f64);
-
-    let elapsed = js_sys::Date::now() - start;
-    console::log_1(&format!("Pi estimated as {} in {}ms", pi_estimate, elapsed).into());
-
-    pi_estimate
+    4.0 * (inside_circle as f64) / (iterations as f64)
 }
 
 /// Matrix multiplication
 #[wasm_bindgen]
 pub fn matrix_multiply(size: usize) -> f64 {
-    let start = js_sys::Date::now();
-
     // Create two matrices filled with random values
     let mut a = vec![vec![0.0; size]; size];
     let mut b = vec![vec![0.0; size]; size];
@@ -106,29 +162,167 @@ pub fn matrix_multiply(size: usize) -> f64 {
         }
     }
 
-    let elapsed = js_sys::Date::now() - start;
-    console::log_1(&format!("Matrix multiply ({}x{}) in {}ms", size, size, elapsed).into());
-
     c[0][0] // Return something to prevent optimization
 }
 
-/// Sort a large array
+/// Fill a flat, row-major `size x size` matrix with random values
+fn random_matrix(size: usize) -> Vec<f64> {
+    (0..size * size).map(|_| js_sys::Math::random()).collect()
+}
+
+/// Naive triple loop over flat row-major matrices (i-j-k order)
+fn multiply_naive(a: &[f64], b: &[f64], n: usize) -> Vec<f64> {
+    let mut c = vec![0.0; n * n];
+    for i in 0..n {
+        for j in 0..n {
+            let mut sum = 0.0;
+            for k in 0..n {
+                sum += a[i * n + k] * b[k * n + j];
+            }
+            c[i * n + j] = sum;
+        }
+    }
+    c
+}
+
+/// Cache-blocked multiplication: works on `block x block` tiles so the
+/// rows of `b` being read stay hot in cache instead of striding through memory
+fn multiply_blocked(a: &[f64], b: &[f64], n: usize, block: usize) -> Vec<f64> {
+    let block = block.max(1);
+    let mut c = vec![0.0; n * n];
+    for ii in (0..n).step_by(block) {
+        for kk in (0..n).step_by(block) {
+            for jj in (0..n).step_by(block) {
+                for i in ii..(ii + block).min(n) {
+                    for k in kk..(kk + block).min(n) {
+                        let a_ik = a[i * n + k];
+                        for j in jj..(jj + block).min(n) {
+                            c[i * n + j] += a_ik * b[k * n + j];
+                        }
+                    }
+                }
+            }
+        }
+    }
+    c
+}
+
+/// SIMD multiplication using 128-bit wasm vectors (two f64 lanes per op).
+///
+/// Only compiled when building with `-C target-feature=+simd128`.
+#[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+fn multiply_simd(a: &[f64], b: &[f64], n: usize) -> Vec<f64> {
+    use core::arch::wasm32::{f64x2_add, f64x2_mul, f64x2_splat, v128, v128_load, v128_store};
+
+    let mut c = vec![0.0; n * n];
+    for i in 0..n {
+        let c_row = &mut c[i * n..(i + 1) * n];
+        for k in 0..n {
+            let a_ik = a[i * n + k];
+            let a_vec = f64x2_splat(a_ik);
+            let b_row = &b[k * n..(k + 1) * n];
+
+            let mut j = 0;
+            while j + 2 <= n {
+                // SAFETY: j + 2 <= n keeps both loads/stores inside the row slices,
+                // and wasm v128 loads/stores have no alignment requirement.
+                unsafe {
+                    let b_vec = v128_load(b_row.as_ptr().add(j) as *const v128);
+                    let c_vec = v128_load(c_row.as_ptr().add(j) as *const v128);
+                    let sum = f64x2_add(c_vec, f64x2_mul(a_vec, b_vec));
+                    v128_store(c_row.as_mut_ptr().add(j) as *mut v128, sum);
+                }
+                j += 2;
+            }
+            // Scalar tail for odd sizes
+            for j in j..n {
+                c_row[j] += a_ik * b_row[j];
+            }
+        }
+    }
+    c
+}
+
+/// Scalar fallback with the same i-k-j loop order as the SIMD version
+#[cfg(not(all(target_arch = "wasm32", target_feature = "simd128")))]
+fn multiply_simd(a: &[f64], b: &[f64], n: usize) -> Vec<f64> {
+    let mut c = vec![0.0; n * n];
+    for i in 0..n {
+        for k in 0..n {
+            let a_ik = a[i * n + k];
+            for j in 0..n {
+                c[i * n + j] += a_ik * b[k * n + j];
+            }
+        }
+    }
+    c
+}
+
+/// Whether this build was compiled with wasm SIMD enabled
 #[wasm_bindgen]
-pub fn sort_array(mut data: Vec<f64>) -> Vec<f64> {
-    let start = js_sys::Date::now();
+pub fn simd_enabled() -> bool {
+    cfg!(all(target_arch = "wasm32", target_feature = "simd128"))
+}
 
-    data.sort_by(|a, b| a.partial_cmp(b).unwrap());
+/// Cache-blocked matrix multiplication
+#[wasm_bindgen]
+pub fn matrix_multiply_blocked(size: usize, block: usize) -> f64 {
+    let a = random_matrix(size);
+    let b = random_matrix(size);
+    multiply_blocked(&a, &b, size, block)[0]
+}
 
-    let elapsed = js_sys::Date::now() - start;
-    console::log_1(&format!("Sorted {} elements in {}ms", data.len(), elapsed).into());
+/// SIMD matrix multiplication (scalar fallback when simd128 is not enabled)
+#[wasm_bindgen]
+pub fn matrix_multiply_simd(size: usize) -> f64 {
+    let a = random_matrix(size);
+    let b = random_matrix(size);
+    multiply_simd(&a, &b, size)[0]
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct MatrixBenchmark {
+    pub size: usize,
+    pub block: usize,
+    pub simd_enabled: bool,
+    pub naive_ms: f64,
+    pub blocked_ms: f64,
+    pub simd_ms: f64,
+}
 
+/// Compare naive vs blocked vs SIMD multiplication on the same inputs
+#[wasm_bindgen]
+pub fn benchmark_matrix_multiply(size: usize, block: usize) -> JsValue {
+    let a = random_matrix(size);
+    let b = random_matrix(size);
+
+    let naive_ms = benchmark::time_ms(|| multiply_naive(&a, &b, size));
+    let blocked_ms = benchmark::time_ms(|| multiply_blocked(&a, &b, size, block));
+    let simd_ms = benchmark::time_ms(|| multiply_simd(&a, &b, size));
+
+    let report = MatrixBenchmark {
+        size,
+        block,
+        simd_enabled: simd_enabled(),
+        naive_ms,
+        blocked_ms,
+        simd_ms,
+    };
+
+    serde_wasm_bindgen::to_value(&report).unwrap()
+}
+
+/// Sort a large array
+#[wasm_bindgen]
+pub fn sort_array(mut data: Vec<f64>) -> Vec<f64> {
+    data.sort_by(|a, b| a.partial_cmp(b).unwrap());
     data
 }
 
 /// Calculate statistics on a dataset
 #[wasm_bindgen]
 pub fn calculate_statistics(data: Vec<f64>) -> JsValue {
-    let start = js_sys::Date::now();
+    let start = benchmark::now();
 
     let n = data.len() as f64;
     let mean = data.iter().sum::<f64>() / n;
@@ -140,13 +334,13 @@ pub fn calculate_statistics(data: Vec<f64>) -> JsValue {
     let mut sorted = data.clone();
     sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
 
-    let median = if sorted.len() % 2 == 0 {
+    let median = if sorted.len().is_multiple_of(2) {
         (sorted[sorted.len() / 2 - 1] + sorted[sorted.len() / 2]) / 2.0
     } else {
         sorted[sorted.len() / 2]
     };
 
-    let elapsed = js_sys::Date::now() - start;
+    let elapsed = benchmark::now() - start;
 
     let result = serde_wasm_bindgen::to_value(&serde_json::json!({
         "mean": mean,
@@ -164,20 +358,15 @@ pub fn calculate_statistics(data: Vec<f64>) -> JsValue {
 /// Simulate heavy computation
 #[wasm_bindgen]
 pub fn heavy_computation(iterations: u32) -> u32 {
-    let start = js_sys::Date::now();
-
     let mut result = 0u32;
     for i in 0..iterations {
         result = result.wrapping_add(i);
         // Simulate work
-        for j in 0..1000 {
+        for j in 0..1000u32 {
             result = result.wrapping_mul(j.wrapping_add(1));
         }
     }
 
-    let elapsed = js_sys::Date::now() - start;
-    console::log_1(&format!("Heavy computation completed in {}ms", elapsed).into());
-
     result
 }
 
@@ -198,4 +387,49 @@ mod tests {
         assert_eq!(fibonacci(1), 1);
         assert_eq!(fibonacci(10), 55);
     }
+
+    #[test]
+    fn test_fibonacci_iterative() {
+        for n in 0..25 {
+            assert_eq!(fibonacci_iterative(n), Some(fibonacci(n)));
+        }
+        assert_eq!(fibonacci_iterative(93), Some(12_200_160_415_121_876_738));
+        assert_eq!(fibonacci_iterative(94), None);
+    }
+
+    #[test]
+    fn test_fibonacci_big() {
+        assert_eq!(fibonacci_big(0), "0");
+        assert_eq!(fibonacci_big(10), "55");
+        assert_eq!(fibonacci_big(93), "12200160415121876738");
+        assert_eq!(fibonacci_big(100), "354224848179261915075");
+        assert_eq!(fibonacci_big(10_000).len(), 2090);
+    }
+
+    fn test_matrices(n: usize) -> (Vec<f64>, Vec<f64>) {
+        let a = (0..n * n).map(|i| (i % 7) as f64 - 3.0).collect();
+        let b = (0..n * n).map(|i| (i % 5) as f64 * 0.5).collect();
+        (a, b)
+    }
+
+    #[test]
+    fn test_matrix_multiply_variants_agree() {
+        for n in [1, 3, 8, 17] {
+            let (a, b) = test_matrices(n);
+            let naive = multiply_naive(&a, &b, n);
+            assert_eq!(multiply_blocked(&a, &b, n, 4), naive);
+            assert_eq!(multiply_simd(&a, &b, n), naive);
+        }
+    }
+
+    #[test]
+    fn test_matrix_multiply_identity() {
+        let n = 4;
+        let (a, _) = test_matrices(n);
+        let mut identity = vec![0.0; n * n];
+        for i in 0..n {
+            identity[i * n + i] = 1.0;
+        }
+        assert_eq!(multiply_blocked(&a, &identity, n, 3), a);
+    }
 }
@@ -0,0 +1,278 @@
+//! Tiled image processing for spreading one image across several workers.
+//!
+//! The main thread plans tiles, copies each one out of the RGBA buffer
+//! (with a halo of extra pixels so neighborhood filters like blur see their
+//! neighbors), posts the tiles to workers as transferables, and writes the
+//! processed tiles back into the full image.
+
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+/// One rectangular piece of the image, in image pixel coordinates
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct Tile {
+    pub index: u32,
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+    /// Extra pixels copied on every side so filters have context at tile edges
+    pub halo: u32,
+}
+
+impl Tile {
+    fn padded_width(&self) -> u32 {
+        self.width + 2 * self.halo
+    }
+
+    fn padded_height(&self) -> u32 {
+        self.height + 2 * self.halo
+    }
+}
+
+/// Image operations that can run on a tile
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum TileOp {
+    Grayscale,
+    Blur { radius: u32 },
+}
+
+impl TileOp {
+    fn parse(op: &str, radius: u32) -> Result<TileOp, String> {
+        match op {
+            "grayscale" => Ok(TileOp::Grayscale),
+            "blur" => Ok(TileOp::Blur { radius }),
+            _ => Err(format!("Unknown operation: {}", op)),
+        }
+    }
+
+    fn apply(self, pixels: &mut [u8], width: u32, height: u32) {
+        match self {
+            TileOp::Grayscale => grayscale(pixels),
+            TileOp::Blur { radius } => box_blur(pixels, width, height, radius),
+        }
+    }
+}
+
+/// Split an image into tiles of at most `tile_size` x `tile_size` pixels
+pub fn plan(width: u32, height: u32, tile_size: u32, halo: u32) -> Vec<Tile> {
+    let tile_size = tile_size.max(1);
+    let mut tiles = Vec::new();
+    for y in (0..height).step_by(tile_size as usize) {
+        for x in (0..width).step_by(tile_size as usize) {
+            tiles.push(Tile {
+                index: tiles.len() as u32,
+                x,
+                y,
+                width: tile_size.min(width - x),
+                height: tile_size.min(height - y),
+                halo,
+            });
+        }
+    }
+    tiles
+}
+
+/// Copy a tile plus its halo out of the image; halo pixels beyond the image
+/// border repeat the edge pixel, matching how the full-image filters clamp
+pub fn extract(image: &[u8], image_width: u32, image_height: u32, tile: &Tile) -> Vec<u8> {
+    let mut out = Vec::with_capacity((tile.padded_width() * tile.padded_height() * 4) as usize);
+    for py in 0..tile.padded_height() {
+        let iy = (tile.y + py)
+            .saturating_sub(tile.halo)
+            .min(image_height - 1);
+        for px in 0..tile.padded_width() {
+            let ix = (tile.x + px).saturating_sub(tile.halo).min(image_width - 1);
+            let i = ((iy * image_width + ix) * 4) as usize;
+            out.extend_from_slice(&image[i..i + 4]);
+        }
+    }
+    out
+}
+
+/// Run an operation on a padded tile and return just the inner (unpadded) pixels
+fn process(mut padded: Vec<u8>, tile: &Tile, op: TileOp) -> Vec<u8> {
+    op.apply(&mut padded, tile.padded_width(), tile.padded_height());
+
+    let mut out = Vec::with_capacity((tile.width * tile.height * 4) as usize);
+    for y in 0..tile.height {
+        let start = (((y + tile.halo) * tile.padded_width() + tile.halo) * 4) as usize;
+        out.extend_from_slice(&padded[start..start + (tile.width * 4) as usize]);
+    }
+    out
+}
+
+/// Write a processed tile back into the full image
+pub fn write(image: &mut [u8], image_width: u32, tile: &Tile, pixels: &[u8]) {
+    let row_bytes = (tile.width * 4) as usize;
+    for y in 0..tile.height {
+        let dst = (((tile.y + y) * image_width + tile.x) * 4) as usize;
+        let src = (y as usize) * row_bytes;
+        image[dst..dst + row_bytes].copy_from_slice(&pixels[src..src + row_bytes]);
+    }
+}
+
+fn grayscale(pixels: &mut [u8]) {
+    for px in pixels.chunks_exact_mut(4) {
+        let gray =
+            (0.299 * px[0] as f32 + 0.587 * px[1] as f32 + 0.114 * px[2] as f32).round() as u8;
+        px[0] = gray;
+        px[1] = gray;
+        px[2] = gray;
+    }
+}
+
+/// Separable box blur with clamped edges (alpha is blurred too)
+fn box_blur(pixels: &mut [u8], width: u32, height: u32, radius: u32) {
+    if radius == 0 {
+        return;
+    }
+    let (w, h, r) = (width as i64, height as i64, radius as i64);
+    let count = (2 * r + 1) as u32;
+    let mut temp = vec![0u8; pixels.len()];
+
+    let blur_pass = |src: &[u8], dst: &mut [u8], horizontal: bool| {
+        for y in 0..h {
+            for x in 0..w {
+                let mut sums = [0u32; 4];
+                for d in -r..=r {
+                    let (sx, sy) = if horizontal {
+                        ((x + d).clamp(0, w - 1), y)
+                    } else {
+                        (x, (y + d).clamp(0, h - 1))
+                    };
+                    let i = ((sy * w + sx) * 4) as usize;
+                    for c in 0..4 {
+                        sums[c] += src[i + c] as u32;
+                    }
+                }
+                let i = ((y * w + x) * 4) as usize;
+                for c in 0..4 {
+                    dst[i + c] = ((sums[c] + count / 2) / count) as u8;
+                }
+            }
+        }
+    };
+
+    blur_pass(pixels, &mut temp, true);
+    blur_pass(&temp, pixels, false);
+}
+
+/// Plan tiles for an image; returns an array of `{ index, x, y, width, height, halo }`
+#[wasm_bindgen]
+pub fn plan_tiles(width: u32, height: u32, tile_size: u32, halo: u32) -> Result<JsValue, JsValue> {
+    Ok(serde_wasm_bindgen::to_value(&plan(
+        width, height, tile_size, halo,
+    ))?)
+}
+
+/// Copy one tile (with halo) out of an RGBA buffer, ready to post to a worker
+#[wasm_bindgen]
+pub fn extract_tile(
+    image: &[u8],
+    image_width: u32,
+    image_height: u32,
+    tile: JsValue,
+) -> Result<Vec<u8>, JsValue> {
+    let tile: Tile = serde_wasm_bindgen::from_value(tile)?;
+    Ok(extract(image, image_width, image_height, &tile))
+}
+
+/// Worker side: process a padded tile ("grayscale" or "blur") and return the inner pixels
+#[wasm_bindgen]
+pub fn process_tile(
+    padded: Vec<u8>,
+    tile: JsValue,
+    op: &str,
+    radius: u32,
+) -> Result<Vec<u8>, JsValue> {
+    let tile: Tile = serde_wasm_bindgen::from_value(tile)?;
+    let op = TileOp::parse(op, radius).map_err(|e| JsValue::from_str(&e))?;
+    if op == (TileOp::Blur { radius }) && tile.halo < radius {
+        return Err(JsValue::from_str(
+            "Tile halo must be at least the blur radius",
+        ));
+    }
+    Ok(process(padded, &tile, op))
+}
+
+/// Write a processed tile back into the full RGBA buffer
+#[wasm_bindgen]
+pub fn write_tile(
+    image: &mut [u8],
+    image_width: u32,
+    tile: JsValue,
+    pixels: &[u8],
+) -> Result<(), JsValue> {
+    let tile: Tile = serde_wasm_bindgen::from_value(tile)?;
+    write(image, image_width, &tile, pixels);
+    Ok(())
+}
+
+/// Single-threaded reference: process the whole image at once
+#[wasm_bindgen]
+pub fn process_image(
+    mut image: Vec<u8>,
+    width: u32,
+    height: u32,
+    op: &str,
+    radius: u32,
+) -> Result<Vec<u8>, JsValue> {
+    let op = TileOp::parse(op, radius).map_err(|e| JsValue::from_str(&e))?;
+    op.apply(&mut image, width, height);
+    Ok(image)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_image(width: u32, height: u32) -> Vec<u8> {
+        (0..width * height * 4)
+            .map(|i| ((i * 37 + i / 5) % 256) as u8)
+            .collect()
+    }
+
+    fn process_tiled(image: &[u8], width: u32, height: u32, tile_size: u32, op: TileOp) -> Vec<u8> {
+        let halo = match op {
+            TileOp::Blur { radius } => radius,
+            TileOp::Grayscale => 0,
+        };
+        let mut out = vec![0u8; image.len()];
+        for tile in plan(width, height, tile_size, halo) {
+            let padded = extract(image, width, height, &tile);
+            let pixels = process(padded, &tile, op);
+            write(&mut out, width, &tile, &pixels);
+        }
+        out
+    }
+
+    #[test]
+    fn test_plan_covers_image() {
+        let tiles = plan(10, 7, 4, 0);
+        assert_eq!(tiles.len(), 6);
+        let area: u32 = tiles.iter().map(|t| t.width * t.height).sum();
+        assert_eq!(area, 70);
+        assert_eq!(tiles[5].width, 2);
+        assert_eq!(tiles[5].height, 3);
+    }
+
+    #[test]
+    fn test_tiled_grayscale_matches_whole_image() {
+        let (w, h) = (13, 9);
+        let image = test_image(w, h);
+        let mut expected = image.clone();
+        TileOp::Grayscale.apply(&mut expected, w, h);
+        assert_eq!(process_tiled(&image, w, h, 4, TileOp::Grayscale), expected);
+    }
+
+    #[test]
+    fn test_tiled_blur_matches_whole_image() {
+        let (w, h) = (17, 11);
+        let image = test_image(w, h);
+        let op = TileOp::Blur { radius: 2 };
+        let mut expected = image.clone();
+        op.apply(&mut expected, w, h);
+        assert_eq!(process_tiled(&image, w, h, 5, op), expected);
+    }
+}